@@ -6,17 +6,36 @@
 extern crate criterion;
 extern crate ed25519_fun;
 
+#[cfg(not(feature = "verify-only"))]
 use ed25519_fun::Keypair;
+#[cfg(not(feature = "verify-only"))]
 use ed25519_fun::Signature;
 
+#[cfg(not(feature = "verify-only"))]
 use criterion::{criterion_group, criterion_main, Criterion};
 
+#[cfg(not(feature = "verify-only"))]
 fn keypair_generation(c: &mut Criterion) {
     c.bench_function("[Private key + public key] generation.", move |b| {
         b.iter(|| Keypair::generate())
     });
 }
 
+#[cfg(not(feature = "verify-only"))]
+fn batch_keypair_generation(c: &mut Criterion) {
+    c.bench_function("100 keypairs, one at a time.", move |b| {
+        b.iter(|| (0..100).map(|_| Keypair::generate()).collect::<Vec<_>>())
+    });
+    c.bench_function("100 keypairs, generate_batch.", move |b| {
+        b.iter(|| Keypair::generate_batch(100))
+    });
+    #[cfg(feature = "parallel")]
+    c.bench_function("100 keypairs, generate_batch_parallel.", move |b| {
+        b.iter(|| Keypair::generate_batch_parallel(100))
+    });
+}
+
+#[cfg(not(feature = "verify-only"))]
 fn signature_generation(c: &mut Criterion) {
     let keypair = Keypair::generate();
     let message: &[u8] = b"";
@@ -26,6 +45,28 @@ fn signature_generation(c: &mut Criterion) {
     });
 }
 
+#[cfg(not(feature = "verify-only"))]
+fn batch_signature_generation(c: &mut Criterion) {
+    let keypair = Keypair::generate();
+    let messages: Vec<Vec<u8>> = (0..2048u32).map(|i| i.to_le_bytes().to_vec()).collect();
+    let messages: Vec<&[u8]> = messages.iter().map(|m| &m[..]).collect();
+
+    let signing = messages.clone();
+    let one_at_a_time = keypair.clone();
+    c.bench_function("2048 signatures, one at a time.", move |b| {
+        b.iter(|| {
+            signing
+                .iter()
+                .map(|m| one_at_a_time.sign(m))
+                .collect::<Vec<_>>()
+        })
+    });
+    c.bench_function("2048 signatures, sign_batch.", move |b| {
+        b.iter(|| keypair.sign_batch(&messages))
+    });
+}
+
+#[cfg(not(feature = "verify-only"))]
 fn signature_verification(c: &mut Criterion) {
     let keypair = Keypair::generate();
     let message: &[u8] = b"";
@@ -36,12 +77,103 @@ fn signature_verification(c: &mut Criterion) {
     });
 }
 
+#[cfg(not(feature = "verify-only"))]
+fn batch_verification(c: &mut Criterion) {
+    let keypairs: Vec<Keypair> = (0..100).map(|_| Keypair::generate()).collect();
+    let messages: Vec<&[u8]> = (0..100).map(|_| &b"block"[..]).collect();
+    let signatures: Vec<Signature> = keypairs.iter().map(|k| k.sign(b"block")).collect();
+    let public_keys: Vec<_> = keypairs.iter().map(|k| *k.public()).collect();
+
+    c.bench_function("100 signatures, verified one at a time.", |b| {
+        b.iter(|| {
+            keypairs
+                .iter()
+                .zip(signatures.iter())
+                .all(|(keypair, signature)| keypair.verify(b"block", *signature).is_ok())
+        })
+    });
+    c.bench_function("100 signatures, verify_batch.", |b| {
+        b.iter(|| ed25519_fun::verify_batch(&messages, &signatures, &public_keys))
+    });
+}
+
+// With `parallel`, these run on the rayon thread pool.
+#[cfg(not(feature = "verify-only"))]
+fn large_batch_verification(c: &mut Criterion) {
+    let keypair = Keypair::generate();
+    let messages: Vec<Vec<u8>> = (0..4096u32).map(|i| i.to_le_bytes().to_vec()).collect();
+    let signatures: Vec<Signature> = messages.iter().map(|m| keypair.sign(m)).collect();
+    let public_keys = vec![*keypair.public(); 4096];
+
+    for &size in [256, 1024, 4096].iter() {
+        let messages: Vec<&[u8]> = messages[..size].iter().map(|m| &m[..]).collect();
+        let signatures = &signatures[..size];
+        let public_keys = &public_keys[..size];
+        c.bench_function(&format!("{} signatures, verify_batch.", size), |b| {
+            b.iter(|| ed25519_fun::verify_batch(&messages, signatures, public_keys))
+        });
+        c.bench_function(&format!("{} signatures, verify_each.", size), |b| {
+            b.iter(|| ed25519_fun::verify_each(&messages, signatures, public_keys))
+        });
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+fn same_message_batch_verification(c: &mut Criterion) {
+    let message = [0x42u8; 32];
+    let keypairs: Vec<Keypair> = (0..256).map(|_| Keypair::generate()).collect();
+
+    // Every key signs once, then 16 keys sign 16 times each.
+    for &signers in [256, 16].iter() {
+        let pairs: Vec<_> = (0..256)
+            .map(|i| {
+                let keypair = &keypairs[i % signers];
+                (*keypair.public(), keypair.sign(&message))
+            })
+            .collect();
+        let messages = vec![&message[..]; 256];
+        let signatures: Vec<_> = pairs.iter().map(|(_, s)| *s).collect();
+        let public_keys: Vec<_> = pairs.iter().map(|(p, _)| *p).collect();
+
+        c.bench_function(
+            &format!("256 votes by {} keys, verify_batch.", signers),
+            |b| b.iter(|| ed25519_fun::verify_batch(&messages, &signatures, &public_keys)),
+        );
+        c.bench_function(
+            &format!("256 votes by {} keys, verify_batch_same_message.", signers),
+            |b| b.iter(|| ed25519_fun::verify_batch_same_message(&message, &pairs)),
+        );
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+fn reader_signing(c: &mut Criterion) {
+    let keypair = Keypair::generate();
+    let message = vec![0x5au8; 1 << 20];
+
+    c.bench_function("1 MiB signed through sign_reader.", move |b| {
+        b.iter(|| keypair.sign_reader(&mut &message[..]).unwrap())
+    });
+}
+
+#[cfg(not(feature = "verify-only"))]
 criterion_group! {
     name = ed25519_benchmarks;
     config = Criterion::default();
     targets = keypair_generation,
+              batch_keypair_generation,
               signature_generation,
-              signature_verification
+              batch_signature_generation,
+              signature_verification,
+              batch_verification,
+              large_batch_verification,
+              same_message_batch_verification,
+              reader_signing
 }
 
+#[cfg(not(feature = "verify-only"))]
 criterion_main!(ed25519_benchmarks);
+
+// Every benchmark needs to sign.
+#[cfg(feature = "verify-only")]
+fn main() {}
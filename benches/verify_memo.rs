@@ -0,0 +1,70 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Retransmit workload: the same (message, signature) pairs verified again
+// and again under one precomputed key.
+
+extern crate criterion;
+extern crate ed25519_fun;
+
+#[cfg(not(feature = "verify-only"))]
+use ed25519_fun::{Keypair, PrecomputedPublicKey, Signature};
+
+#[cfg(not(feature = "verify-only"))]
+use criterion::{criterion_group, criterion_main, Criterion};
+
+#[cfg(not(feature = "verify-only"))]
+fn retransmits(keypair: &Keypair) -> Vec<(Vec<u8>, Signature)> {
+    (0..8u8)
+        .map(|i| {
+            let message = vec![i; 256];
+            let signature = keypair.sign(&message);
+            (message, signature)
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "verify-only"))]
+fn verification_without_memo(c: &mut Criterion) {
+    let keypair = Keypair::generate();
+    let precomputed = PrecomputedPublicKey::new(keypair.public()).unwrap();
+    let pairs = retransmits(&keypair);
+
+    c.bench_function("Retransmit verification without memo.", move |b| {
+        b.iter(|| {
+            for (message, signature) in &pairs {
+                precomputed.verify(message, signature).unwrap();
+            }
+        })
+    });
+}
+
+#[cfg(not(feature = "verify-only"))]
+fn verification_with_memo(c: &mut Criterion) {
+    let keypair = Keypair::generate();
+    let precomputed = PrecomputedPublicKey::with_memo(keypair.public(), 16).unwrap();
+    let pairs = retransmits(&keypair);
+
+    c.bench_function("Retransmit verification with memo.", move |b| {
+        b.iter(|| {
+            for (message, signature) in &pairs {
+                precomputed.verify(message, signature).unwrap();
+            }
+        })
+    });
+}
+
+#[cfg(not(feature = "verify-only"))]
+criterion_group! {
+    name = verify_memo;
+    config = Criterion::default();
+    targets = verification_without_memo,
+              verification_with_memo
+}
+
+#[cfg(not(feature = "verify-only"))]
+criterion_main!(verify_memo);
+
+// Every benchmark needs to sign.
+#[cfg(feature = "verify-only")]
+fn main() {}
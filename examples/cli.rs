@@ -0,0 +1,136 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// A small command line front end to the public API.
+//
+//   cli keygen --out key.hex [--pub pk.hex]
+//   cli sign --key key.hex --in file --out file.sig
+//   cli verify --pub pk.hex --in file --sig file.sig
+//
+// Keys are hex files, signatures raw 64-byte files. The exit status is 0
+// on success, 1 if the signature doesn't verify and 2 on bad input
+// (usage, unreadable files, malformed keys or signatures).
+//
+// Ed25519 hashes the message twice when signing, once for the nonce and
+// once for the challenge, and the library takes whole messages, so the
+// input file is read into memory.
+
+extern crate ed25519_fun;
+extern crate hex;
+
+#[cfg(not(feature = "verify-only"))]
+use ed25519_fun::{Keypair, SecretKey};
+use ed25519_fun::{PublicKey, Signature};
+
+use std::env;
+use std::fs;
+use std::process;
+
+const BAD_SIGNATURE: i32 = 1;
+const BAD_INPUT: i32 = 2;
+
+const USAGE: &str = "usage:
+  cli keygen --out key.hex [--pub pk.hex]
+  cli sign --key key.hex --in file --out file.sig
+  cli verify --pub pk.hex --in file --sig file.sig";
+
+/// Why a command failed, and so its exit status.
+enum Failure {
+    BadSignature,
+    BadInput(String),
+}
+
+/// The value of `--name` in `args`, which alternate names and values.
+fn option<'a>(args: &'a [String], name: &str) -> Result<&'a str, Failure> {
+    let flag = format!("--{}", name);
+    args.chunks(2)
+        .find(|pair| pair[0] == flag)
+        .and_then(|pair| pair.get(1))
+        .map(|value| value.as_str())
+        .ok_or_else(|| Failure::BadInput(format!("missing {}\n{}", flag, USAGE)))
+}
+
+fn read(path: &str) -> Result<Vec<u8>, Failure> {
+    fs::read(path).map_err(|e| Failure::BadInput(format!("{}: {}", path, e)))
+}
+
+fn read_hex(path: &str) -> Result<String, Failure> {
+    let text = read(path)?;
+    String::from_utf8(text)
+        .map(|text| text.trim().to_string())
+        .map_err(|_| Failure::BadInput(format!("{}: not hex", path)))
+}
+
+#[cfg(not(feature = "verify-only"))]
+fn write(path: &str, contents: &[u8]) -> Result<(), Failure> {
+    fs::write(path, contents).map_err(|e| Failure::BadInput(format!("{}: {}", path, e)))
+}
+
+#[cfg(not(feature = "verify-only"))]
+fn keygen(args: &[String]) -> Result<(), Failure> {
+    let out = option(args, "out")?;
+    let keypair = Keypair::generate();
+    let public = keypair.public().to_string();
+    write(out, format!("{}\n", keypair.secret().to_hex()).as_bytes())?;
+    if let Ok(path) = option(args, "pub") {
+        write(path, format!("{}\n", public).as_bytes())?;
+    }
+    println!("{}", public);
+    Ok(())
+}
+
+#[cfg(not(feature = "verify-only"))]
+fn sign(args: &[String]) -> Result<(), Failure> {
+    let key = option(args, "key")?;
+    let secret = SecretKey::from_hex(&read_hex(key)?)
+        .map_err(|e| Failure::BadInput(format!("{}: {}", key, e)))?;
+    let message = read(option(args, "in")?)?;
+    let signature = secret.sign_detached(&message);
+    write(option(args, "out")?, signature.as_bytes())
+}
+
+#[cfg(feature = "verify-only")]
+fn keygen(_args: &[String]) -> Result<(), Failure> {
+    Err(Failure::BadInput("built with verify-only".to_string()))
+}
+
+#[cfg(feature = "verify-only")]
+fn sign(_args: &[String]) -> Result<(), Failure> {
+    Err(Failure::BadInput("built with verify-only".to_string()))
+}
+
+fn verify(args: &[String]) -> Result<(), Failure> {
+    let path = option(args, "pub")?;
+    let public = hex::decode(read_hex(path)?)
+        .ok()
+        .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| Failure::BadInput(format!("{}: not a hex public key", path)))?;
+    let path = option(args, "sig")?;
+    let signature = Signature::from_bytes(&read(path)?)
+        .map_err(|e| Failure::BadInput(format!("{}: {}", path, e)))?;
+    let message = read(option(args, "in")?)?;
+    public
+        .verify(&message, &signature)
+        .map_err(|_| Failure::BadSignature)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(|command| command.as_str()) {
+        Some("keygen") => keygen(&args[1..]),
+        Some("sign") => sign(&args[1..]),
+        Some("verify") => verify(&args[1..]),
+        _ => Err(Failure::BadInput(USAGE.to_string())),
+    };
+    match result {
+        Ok(()) => {}
+        Err(Failure::BadSignature) => {
+            eprintln!("bad signature");
+            process::exit(BAD_SIGNATURE);
+        }
+        Err(Failure::BadInput(message)) => {
+            eprintln!("{}", message);
+            process::exit(BAD_INPUT);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+/*
+ * Author:
+ * - Yuzo <yuzonakai@gmail.com>
+ *
+ * C interface of ed25519-fun, built with the `ffi` feature. See src/ffi.rs.
+ * The shared library comes from
+ * `cargo rustc --release --features ffi --crate-type cdylib`.
+ *
+ * Secret keys and public keys are 32-byte buffers and signatures 64-byte
+ * buffers. Every function returns ED25519_FUN_OK or one of the negative
+ * ED25519_FUN_ERR_* codes, and writes no output unless it succeeds.
+ */
+
+#ifndef ED25519_FUN_H
+#define ED25519_FUN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define ED25519_FUN_SECRET_KEY_LENGTH 32
+#define ED25519_FUN_PUBLIC_KEY_LENGTH 32
+#define ED25519_FUN_SIGNATURE_LENGTH 64
+
+/* The call succeeded, and the signature is valid for ed25519_fun_verify. */
+#define ED25519_FUN_OK 0
+/* The signature doesn't verify. */
+#define ED25519_FUN_ERR_SIGNATURE_MISMATCH (-1)
+/* The public key isn't a curve point. */
+#define ED25519_FUN_ERR_INVALID_PUBLIC_KEY (-2)
+/* The scalar s of the signature isn't reduced modulo L. */
+#define ED25519_FUN_ERR_NON_CANONICAL_SCALAR (-3)
+/* The point R of the signature isn't a curve point. */
+#define ED25519_FUN_ERR_INVALID_SIGNATURE_POINT (-4)
+/* The public key isn't the secret key's. */
+#define ED25519_FUN_ERR_INVALID_KEYPAIR (-5)
+/* A required pointer is null. */
+#define ED25519_FUN_ERR_NULL_POINTER (-6)
+/* The call panicked; nothing was written. */
+#define ED25519_FUN_ERR_PANIC (-7)
+/* Any other error. */
+#define ED25519_FUN_ERR_OTHER (-8)
+
+/*
+ * Generates a keypair: writes the 32-byte secret key to out_sk and the
+ * 32-byte public key to out_pk. Not available in verify-only builds.
+ */
+int ed25519_fun_keypair_generate(uint8_t *out_sk, uint8_t *out_pk);
+
+/*
+ * Signs the msg_len bytes at msg (which may be NULL if msg_len is 0) with
+ * the secret key sk and its public key pk, and writes the 64-byte
+ * signature to sig_out. Returns ED25519_FUN_ERR_INVALID_KEYPAIR if pk
+ * isn't the public key of sk. Not available in verify-only builds.
+ */
+int ed25519_fun_sign(uint8_t *sig_out,
+                     const uint8_t *msg,
+                     size_t msg_len,
+                     const uint8_t *sk,
+                     const uint8_t *pk);
+
+/*
+ * Verifies the 64-byte signature sig of the msg_len bytes at msg (which
+ * may be NULL if msg_len is 0) with the 32-byte public key pk.
+ */
+int ed25519_fun_verify(const uint8_t *msg,
+                       size_t msg_len,
+                       const uint8_t *pk,
+                       const uint8_t *sig);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ED25519_FUN_H */
@@ -0,0 +1,112 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// `arbitrary::Arbitrary` for fuzz targets: `PublicKey` and `Signature`
+// consume exactly 32 and 64 bytes of the fuzzer input and go through
+// `from_bytes`, and `MultiPartMessage` borrows its parts from it.
+//
+// `from_bytes` only checks the length: it takes keys that aren't curve
+// points and signatures whose s isn't reduced, and leaves them to
+// `verify`. So these impls already reach every well-sized input, invalid
+// ones included, and no separate unchecked constructor is needed. On
+// short input they fail with `arbitrary::Error::NotEnoughData` rather
+// than padding, so that the size hints are exact.
+
+use crate::constants::*;
+use crate::multipart::*;
+use crate::public::*;
+use crate::signature::*;
+
+use ::arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+/// Consumes 32 bytes as `PublicKey::from_bytes` would take them.
+impl<'a> Arbitrary<'a> for PublicKey {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        PublicKey::from_bytes(u.bytes(PublicKeySize)?).map_err(|_| Error::IncorrectFormat)
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (PublicKeySize, Some(PublicKeySize))
+    }
+}
+
+/// Consumes 64 bytes as `Signature::from_bytes` would take them.
+impl<'a> Arbitrary<'a> for Signature {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Signature::from_bytes(u.bytes(SignatureSize)?).map_err(|_| Error::IncorrectFormat)
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (SignatureSize, Some(SignatureSize))
+    }
+}
+
+/// Reads a part count from 0 to `N`, then borrows each part from the
+/// input as `&[u8]` does.
+impl<'a, const N: usize> Arbitrary<'a> for MultiPartMessage<'a, N> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut message = MultiPartMessage::new();
+        for _ in 0..u.int_in_range(0..=N)? {
+            message
+                .push(<&'a [u8]>::arbitrary(u)?)
+                .map_err(|_| Error::IncorrectFormat)?;
+        }
+        Ok(message)
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        // Empty input is the empty message, and parts have no upper bound.
+        (0, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream() -> Vec<u8> {
+        (0..=255u8).collect()
+    }
+
+    #[test]
+    fn arbitrary_keys_and_signatures_consume_exact_sizes() {
+        let bytes = stream();
+        let mut u = Unstructured::new(&bytes);
+        let public = PublicKey::arbitrary(&mut u).unwrap();
+        let signature = Signature::arbitrary(&mut u).unwrap();
+        assert_eq!(public.as_bytes()[..], bytes[..32]);
+        assert_eq!(signature.as_bytes()[..], bytes[32..96]);
+        assert_eq!(u.len(), bytes.len() - 96);
+        assert_eq!(PublicKey::size_hint(0), (32, Some(32)));
+        assert_eq!(Signature::size_hint(0), (64, Some(64)));
+
+        // Short input fails, and isn't padded.
+        let mut u = Unstructured::new(&bytes[..31]);
+        assert!(matches!(
+            PublicKey::arbitrary(&mut u),
+            Err(Error::NotEnoughData)
+        ));
+        let mut u = Unstructured::new(&bytes[..63]);
+        assert!(matches!(
+            Signature::arbitrary(&mut u),
+            Err(Error::NotEnoughData)
+        ));
+    }
+
+    #[test]
+    fn arbitrary_multipart_message_borrows_the_input() {
+        let bytes = stream();
+        let message = MultiPartMessage::<4>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        assert!(message.len() <= 4);
+        let range = bytes.as_ptr_range();
+        for part in message.parts() {
+            assert!(part.is_empty() || range.contains(&part.as_ptr()));
+        }
+        // The same input gives the same message.
+        let again = MultiPartMessage::<4>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        assert_eq!(message.parts(), again.parts());
+
+        let empty = MultiPartMessage::<4>::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert!(empty.is_empty());
+    }
+}
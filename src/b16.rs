@@ -0,0 +1,198 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Hexadecimal (RFC 4648 base16): lowercase on output, either case on
+// input.
+//
+// As in `b64.rs`, the digits are mapped with masks instead of tables or
+// branches on the data, so secret keys go through in time that depends
+// only on their length.
+
+use core::fmt;
+
+/// All ones if `a < b` and zero otherwise, for `a, b < 2^31`.
+fn lt(a: u32, b: u32) -> u32 {
+    (a.wrapping_sub(b) >> 31).wrapping_neg()
+}
+
+/// All ones if `a` is in `[low, high]` and zero otherwise.
+fn within(a: u32, low: u8, high: u8) -> u32 {
+    !lt(a, low as u32) & lt(a, high as u32 + 1)
+}
+
+fn encode_nibble(nibble: u8) -> u8 {
+    let n = nibble as u32;
+    // '0' + n below 10 and 'a' + n - 10 from there.
+    (n + b'0' as u32 + (!lt(n, 10) & (b'a' as u32 - b'0' as u32 - 10))) as u8
+}
+
+/// Returns the value of `c` and all ones if it is a hex digit, or zero
+/// and zero otherwise.
+fn decode_nibble(c: u8) -> (u8, u32) {
+    let c = c as u32;
+    let digit = within(c, b'0', b'9');
+    let lower = within(c, b'a', b'f');
+    let upper = within(c, b'A', b'F');
+    let value = (digit & c.wrapping_sub(b'0' as u32))
+        | (lower & c.wrapping_sub(b'a' as u32 - 10))
+        | (upper & c.wrapping_sub(b'A' as u32 - 10));
+    (value as u8, digit | lower | upper)
+}
+
+/// Writes the lowercase hex of `bytes` to `f`.
+pub(crate) fn write(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for b in bytes {
+        write!(
+            f,
+            "{}{}",
+            encode_nibble(b >> 4) as char,
+            encode_nibble(b & 0x0f) as char
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the hex of `bytes`, at most 64 of them, to `f` as `{:x}` (or
+/// `{:X}` if `upper`) writes an integer: `#` prefixes `0x`, and the
+/// width, fill, alignment and `0` flag pad the whole. Only for public
+/// data: the uppercase pass branches on the digits.
+pub(crate) fn write_integral(f: &mut fmt::Formatter<'_>, bytes: &[u8], upper: bool) -> fmt::Result {
+    let mut digits = [0u8; 128];
+    let digits = &mut digits[..2 * bytes.len()];
+    for (pair, b) in digits.chunks_exact_mut(2).zip(bytes) {
+        pair[0] = encode_nibble(b >> 4);
+        pair[1] = encode_nibble(b & 0x0f);
+    }
+    if upper {
+        digits.make_ascii_uppercase();
+    }
+    f.pad_integral(
+        true,
+        "0x",
+        core::str::from_utf8(digits).map_err(|_| fmt::Error)?,
+    )
+}
+
+/// Returns the lowercase hex of `bytes`.
+#[cfg(feature = "std")]
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(2 * bytes.len());
+    for b in bytes {
+        s.push(encode_nibble(b >> 4) as char);
+        s.push(encode_nibble(b & 0x0f) as char);
+    }
+    s
+}
+
+/// Decodes `input` into `out`, which it must fill exactly.
+///
+/// Returns `Ok(())` if `input` is the hex of `out.len()` bytes and
+/// `Err(())` otherwise, with `out` zeroed.
+pub(crate) fn decode_to_slice(input: &str, out: &mut [u8]) -> Result<(), ()> {
+    let input = input.as_bytes();
+    if input.len() != 2 * out.len() {
+        return Err(());
+    }
+    let mut valid = !0u32;
+    for (o, pair) in out.iter_mut().zip(input.chunks_exact(2)) {
+        let (high, high_valid) = decode_nibble(pair[0]);
+        let (low, low_valid) = decode_nibble(pair[1]);
+        *o = (high << 4) | low;
+        valid &= high_valid & low_valid;
+    }
+    if valid == 0 {
+        for o in out.iter_mut() {
+            *o = 0;
+        }
+        return Err(());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn b16_matches_hex_crate() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let encoded = encode(&bytes);
+        assert_eq!(encoded, hex::encode(&bytes));
+
+        let mut out = [0u8; 256];
+        assert_eq!(decode_to_slice(&encoded, &mut out), Ok(()));
+        assert!(out[..] == bytes[..]);
+        assert_eq!(decode_to_slice(&encoded.to_uppercase(), &mut out), Ok(()));
+        assert!(out[..] == bytes[..]);
+    }
+
+    // Bytes formatted through `write_integral`.
+    struct Integral<'a>(&'a [u8]);
+
+    impl fmt::LowerHex for Integral<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write_integral(f, self.0, false)
+        }
+    }
+
+    impl fmt::UpperHex for Integral<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write_integral(f, self.0, true)
+        }
+    }
+
+    #[test]
+    fn b16_integral_flags_match_integers() {
+        // Leading zero bytes are kept, unlike the leading zeros of an
+        // integer.
+        let bytes = Integral(&[0x0a, 0xbc]);
+        assert_eq!(format!("{:x}", bytes), "0abc");
+        assert_eq!(format!("{:#X}", bytes), "0x0ABC");
+
+        // Otherwise the flags do what they do on the same integer.
+        let m = Integral(&[0xab, 0xcd]);
+        let k = 0xabcdu16;
+        for (formatted, expected) in [
+            (format!("{:x}", m), format!("{:x}", k)),
+            (format!("{:X}", m), format!("{:X}", k)),
+            (format!("{:#x}", m), format!("{:#x}", k)),
+            (format!("{:8x}", m), format!("{:8x}", k)),
+            (format!("{:<8x}", m), format!("{:<8x}", k)),
+            (format!("{:^8X}", m), format!("{:^8X}", k)),
+            (format!("{:*>9x}", m), format!("{:*>9x}", k)),
+            (format!("{:08x}", m), format!("{:08x}", k)),
+            (format!("{:#010x}", m), format!("{:#010x}", k)),
+            (format!("{:#010X}", m), format!("{:#010X}", k)),
+            (format!("{:#3x}", m), format!("{:#3x}", k)),
+        ]
+        .iter()
+        {
+            assert_eq!(formatted, expected);
+        }
+    }
+
+    #[test]
+    fn b16_rejects_malformed() {
+        let mut out = [0u8; 2];
+        // Odd, short and long inputs.
+        assert_eq!(decode_to_slice("abc", &mut out), Err(()));
+        assert_eq!(decode_to_slice("ab", &mut out), Err(()));
+        assert_eq!(decode_to_slice("abcdef", &mut out), Err(()));
+        // Every byte that isn't a hex digit, in either position.
+        for c in 0..=255u8 {
+            if c.is_ascii_hexdigit() {
+                continue;
+            }
+            let mut input = [b'a'; 4];
+            input[c as usize % 4] = c;
+            if let Ok(input) = std::str::from_utf8(&input) {
+                assert_eq!(decode_to_slice(input, &mut out), Err(()));
+                assert_eq!(out, [0, 0]);
+            }
+        }
+        // A multi-byte character of the right byte length.
+        assert_eq!(decode_to_slice("é12", &mut out), Err(()));
+    }
+}
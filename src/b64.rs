@@ -0,0 +1,378 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Base64 and base64url (RFC 4648), with or without padding.
+//
+// The alphabet lookups are computed with masks instead of tables or
+// branches on the data: every character is compared against each range
+// of the alphabet with `lt`, and the results select the value. Valid
+// input is thus decoded, and any input encoded, in time that depends only
+// on its length, so the codec can carry secret keys. On malformed input
+// the offending offset is located afterwards, by a variable-time scan.
+
+use crate::errors::*;
+
+/// An alphabet and padding mode.
+#[derive(Copy, Clone)]
+pub(crate) struct Variant {
+    pub(crate) url_safe: bool,
+    pub(crate) padding: bool,
+}
+
+pub(crate) const STANDARD: Variant = Variant {
+    url_safe: false,
+    padding: true,
+};
+
+pub(crate) const URL_SAFE_NO_PAD: Variant = Variant {
+    url_safe: true,
+    padding: false,
+};
+
+impl Variant {
+    // Characters of the values 62 and 63.
+    fn extra(self) -> (u32, u32) {
+        if self.url_safe {
+            (b'-' as u32, b'_' as u32)
+        } else {
+            (b'+' as u32, b'/' as u32)
+        }
+    }
+}
+
+/// All ones if `a < b` and zero otherwise, for `a, b < 2^31`.
+fn lt(a: u32, b: u32) -> u32 {
+    (a.wrapping_sub(b) >> 31).wrapping_neg()
+}
+
+/// All ones if `a == b` and zero otherwise.
+fn eq(a: u32, b: u32) -> u32 {
+    !lt(a, b) & lt(a, b + 1)
+}
+
+/// All ones if `a` is in `[low, high]` and zero otherwise.
+fn within(a: u32, low: u8, high: u8) -> u32 {
+    !lt(a, low as u32) & lt(a, high as u32 + 1)
+}
+
+fn encode_value(variant: Variant, value: u32) -> u8 {
+    let (c62, c63) = variant.extra();
+    let c = (lt(value, 26) & (value + b'A' as u32))
+        | (!lt(value, 26) & lt(value, 52) & (value + b'a' as u32 - 26))
+        | (!lt(value, 52) & lt(value, 62) & value.wrapping_sub(52 - b'0' as u32))
+        | (eq(value, 62) & c62)
+        | (eq(value, 63) & c63);
+    c as u8
+}
+
+/// Returns the value of `c` and a mask, all ones if `c` is in the alphabet.
+fn decode_value(variant: Variant, c: u8) -> (u32, u32) {
+    let (c62, c63) = variant.extra();
+    let c = c as u32;
+    let upper = within(c, b'A', b'Z');
+    let lower = within(c, b'a', b'z');
+    let digit = within(c, b'0', b'9');
+    let is62 = eq(c, c62);
+    let is63 = eq(c, c63);
+    let value = (upper & c.wrapping_sub(b'A' as u32))
+        | (lower & c.wrapping_sub(b'a' as u32 - 26))
+        | (digit & c.wrapping_add(52 - b'0' as u32))
+        | (is62 & 62)
+        | (is63 & 63);
+    (value, upper | lower | digit | is62 | is63)
+}
+
+/// Returns the length of the encoding of `len` bytes.
+pub(crate) fn encoded_len(variant: Variant, len: usize) -> usize {
+    if variant.padding {
+        len.div_ceil(3) * 4
+    } else {
+        len / 3 * 4 + (len % 3 * 4).div_ceil(3)
+    }
+}
+
+/// Encodes `input` into `out`.
+///
+/// Returns the length of the encoding, or `Err(Error::BufferTooSmall)` if
+/// it doesn't fit in `out`.
+pub(crate) fn encode_to_slice(
+    variant: Variant,
+    input: &[u8],
+    out: &mut [u8],
+) -> Result<usize, Error> {
+    let len = encoded_len(variant, input.len());
+    if out.len() < len {
+        return Err(Error::BufferTooSmall);
+    }
+
+    let mut written = 0;
+    for chunk in input.chunks(3) {
+        let mut block = [0u8; 3];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let bits = (block[0] as u32) << 16 | (block[1] as u32) << 8 | block[2] as u32;
+
+        let chars = chunk.len() + 1;
+        for i in 0..chars {
+            out[written + i] = encode_value(variant, (bits >> (18 - 6 * i)) & 0x3f);
+        }
+        written += chars;
+        if variant.padding {
+            for _ in chars..4 {
+                out[written] = b'=';
+                written += 1;
+            }
+        }
+    }
+    Ok(written)
+}
+
+/// Returns the number of characters of `input` carrying data and the
+/// length of the decoded bytes.
+fn decoded_len(variant: Variant, input: &[u8]) -> Result<(usize, usize), Error> {
+    let mut chars = input.len();
+    if variant.padding {
+        if !input.len().is_multiple_of(4) {
+            return Err(Error::InvalidBase64 {
+                offset: input.len(),
+            });
+        }
+        while chars > 0 && input.len() - chars < 2 && input[chars - 1] == b'=' {
+            chars -= 1;
+        }
+    }
+    match chars % 4 {
+        1 => Err(Error::InvalidBase64 { offset: chars - 1 }),
+        rem => Ok((chars, chars / 4 * 3 + rem.saturating_sub(1))),
+    }
+}
+
+/// Decodes `input` into `out`, rejecting whitespace, characters outside
+/// the alphabet, misplaced padding and non-zero trailing bits.
+///
+/// Returns the length of the decoded bytes, `Err(Error::InvalidBase64)`
+/// with the offset of the first offending character, or
+/// `Err(Error::BufferTooSmall)` if the bytes don't fit in `out`.
+pub(crate) fn decode_to_slice(
+    variant: Variant,
+    input: &[u8],
+    out: &mut [u8],
+) -> Result<usize, Error> {
+    let (chars, len) = decoded_len(variant, input)?;
+    if out.len() < len {
+        return Err(Error::BufferTooSmall);
+    }
+
+    let mut invalid = 0u32;
+    let mut written = 0;
+    for chunk in input[..chars].chunks(4) {
+        let mut bits = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let (value, valid) = decode_value(variant, *c);
+            invalid |= !valid;
+            bits |= value << (18 - 6 * i);
+        }
+
+        let bytes = chunk.len() - 1;
+        for i in 0..bytes {
+            out[written + i] = (bits >> (16 - 8 * i)) as u8;
+        }
+        written += bytes;
+
+        // The bits past the last byte must be zero.
+        let trailing = bits & (0xffffff >> (8 * bytes));
+        invalid |= (trailing | trailing.wrapping_neg()) >> 31;
+    }
+
+    if invalid != 0 {
+        for byte in out[..len].iter_mut() {
+            *byte = 0;
+        }
+        let offset = input[..chars]
+            .iter()
+            .position(|c| decode_value(variant, *c).1 == 0)
+            .unwrap_or(chars - 1);
+        return Err(Error::InvalidBase64 { offset });
+    }
+    Ok(len)
+}
+
+/// Encodes `input`.
+#[cfg(feature = "std")]
+pub(crate) fn encode(variant: Variant, input: &[u8]) -> String {
+    let mut out = vec![0u8; encoded_len(variant, input.len())];
+    let len = encode_to_slice(variant, input, &mut out).unwrap();
+    out.truncate(len);
+    String::from_utf8(out).unwrap()
+}
+
+/// Decodes `input`, as `decode_to_slice`.
+#[cfg_attr(
+    not(any(feature = "minisign", feature = "paseto", feature = "signify")),
+    allow(dead_code)
+)]
+#[cfg(feature = "std")]
+pub(crate) fn decode(variant: Variant, input: &str) -> Result<Vec<u8>, Error> {
+    let (_, len) = decoded_len(variant, input.as_bytes())?;
+    let mut out = vec![0u8; len];
+    decode_to_slice(variant, input.as_bytes(), &mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate base64;
+
+    #[cfg(feature = "std")]
+    use self::base64::engine::general_purpose::{
+        STANDARD as REF_STANDARD, STANDARD_NO_PAD as REF_STANDARD_NO_PAD, URL_SAFE as REF_URL_SAFE,
+        URL_SAFE_NO_PAD as REF_URL_SAFE_NO_PAD,
+    };
+    #[cfg(feature = "std")]
+    use self::base64::engine::GeneralPurpose;
+    #[cfg(feature = "std")]
+    use self::base64::Engine;
+    use super::*;
+    #[cfg(feature = "std")]
+    use rand::{thread_rng, Rng};
+
+    #[cfg(feature = "std")]
+    fn variants() -> [(Variant, GeneralPurpose); 4] {
+        [
+            (
+                Variant {
+                    url_safe: false,
+                    padding: true,
+                },
+                REF_STANDARD,
+            ),
+            (
+                Variant {
+                    url_safe: false,
+                    padding: false,
+                },
+                REF_STANDARD_NO_PAD,
+            ),
+            (
+                Variant {
+                    url_safe: true,
+                    padding: true,
+                },
+                REF_URL_SAFE,
+            ),
+            (
+                Variant {
+                    url_safe: true,
+                    padding: false,
+                },
+                REF_URL_SAFE_NO_PAD,
+            ),
+        ]
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn b64_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (decoded, encoded) in vectors.iter() {
+            assert_eq!(encode(STANDARD, decoded.as_bytes()), *encoded);
+            assert_eq!(decode(STANDARD, encoded).unwrap(), decoded.as_bytes());
+
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(encode(URL_SAFE_NO_PAD, decoded.as_bytes()), unpadded);
+            assert_eq!(
+                decode(URL_SAFE_NO_PAD, unpadded).unwrap(),
+                decoded.as_bytes()
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn b64_matches_reference() {
+        let mut rng = thread_rng();
+        for len in 0..200 {
+            let mut data = vec![0u8; len];
+            rng.fill(&mut data[..]);
+            for (variant, reference) in variants().iter() {
+                let encoded = encode(*variant, &data);
+                assert_eq!(encoded, reference.encode(&data));
+                assert_eq!(decode(*variant, &encoded).unwrap(), data);
+            }
+        }
+        // Every byte value through every position of a block.
+        let data: Vec<u8> = (0..=255).collect();
+        for (variant, reference) in variants().iter() {
+            for start in 0..3 {
+                assert_eq!(
+                    encode(*variant, &data[start..]),
+                    reference.encode(&data[start..])
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn b64_rejects_with_offsets() {
+        let cases: [(Variant, &str, usize); 10] = [
+            // Whitespace and foreign characters.
+            (STANDARD, "Zm9v YmF", 4),
+            (STANDARD, "Zm9vYmFy\n", 9),
+            (STANDARD, "Zm9vYm!y", 6),
+            // The other alphabet.
+            (STANDARD, "Zm9v-_Fy", 4),
+            (URL_SAFE_NO_PAD, "Zm9v+/Fy", 4),
+            // Padding where there is none or too much of it.
+            (URL_SAFE_NO_PAD, "Zm8=", 3),
+            (STANDARD, "Zg=v", 2),
+            (STANDARD, "Z===", 1),
+            // Non-zero trailing bits.
+            (STANDARD, "Zh==", 1),
+            (URL_SAFE_NO_PAD, "Zm9", 2),
+        ];
+        for (variant, input, offset) in cases.iter() {
+            assert_eq!(
+                decode(*variant, input),
+                Err(Error::InvalidBase64 { offset: *offset }),
+                "{}",
+                input
+            );
+        }
+        // A single character of a block.
+        assert_eq!(
+            decode(URL_SAFE_NO_PAD, "Zm9vY"),
+            Err(Error::InvalidBase64 { offset: 4 })
+        );
+    }
+
+    #[test]
+    fn b64_caller_buffers() {
+        let mut out = [0xffu8; 6];
+        assert_eq!(decode_to_slice(STANDARD, b"Zm9vYmFy", &mut out), Ok(6));
+        assert_eq!(&out, b"foobar");
+        assert_eq!(
+            decode_to_slice(STANDARD, b"Zm9vYmFy", &mut out[..5]),
+            Err(Error::BufferTooSmall)
+        );
+        // Nothing of malformed input is left in the buffer.
+        let mut out = [0xffu8; 6];
+        assert!(decode_to_slice(STANDARD, b"Zm9vYm!y", &mut out).is_err());
+        assert_eq!(out, [0u8; 6]);
+
+        let mut out = [0u8; 8];
+        assert_eq!(encode_to_slice(STANDARD, b"foob", &mut out), Ok(8));
+        assert_eq!(&out, b"Zm9vYg==");
+        assert_eq!(
+            encode_to_slice(URL_SAFE_NO_PAD, b"foob", &mut out[..5]),
+            Err(Error::BufferTooSmall)
+        );
+    }
+}
@@ -0,0 +1,352 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Base58 with the Bitcoin alphabet (base58btc). Leading zero bytes are
+// encoded as leading '1's. Inputs are short (keys and signatures), so
+// the quadratic big-number conversion is fine.
+//
+// With the `base58` feature, public keys and signatures also convert to
+// and from base58 and base58check (the payload followed by the first 4
+// bytes of its double SHA-256).
+
+#[cfg(feature = "base58")]
+use crate::constants::*;
+#[cfg(feature = "base58")]
+use crate::errors::*;
+#[cfg(feature = "base58")]
+use crate::public::*;
+#[cfg(feature = "base58")]
+use crate::signature::*;
+
+#[cfg(any(feature = "base58", feature = "tezos"))]
+use sha2::{Digest, Sha256};
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encodes `bytes` as base58btc.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+
+    // Base-58 digits, least significant first.
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut out = String::with_capacity(zeros + digits.len());
+    out.extend(core::iter::repeat_n('1', zeros));
+    out.extend(digits.iter().rev().map(|&d| ALPHABET[d as usize] as char));
+    out
+}
+
+/// Decodes a base58btc string.
+///
+/// Returns `None` if `text` contains a character outside the alphabet.
+#[cfg_attr(
+    not(any(
+        feature = "base58",
+        feature = "did-key",
+        feature = "near",
+        feature = "ss58",
+        feature = "tezos"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let zeros = text.bytes().take_while(|&c| c == b'1').count();
+
+    // Bytes, least significant first.
+    let mut bytes: Vec<u8> = Vec::with_capacity(text.len() * 733 / 1000 + 1);
+    for c in text.bytes().skip(zeros) {
+        let mut carry = ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes.iter().rev());
+    Some(out)
+}
+
+/// The base58check checksum of `payload`.
+#[cfg(any(feature = "base58", feature = "tezos"))]
+pub(crate) fn checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(&Sha256::digest(payload));
+    let mut check = [0u8; 4];
+    check.copy_from_slice(&hash[..4]);
+    check
+}
+
+/// Encodes `bytes` as base58check.
+#[cfg(any(feature = "base58", feature = "tezos"))]
+pub(crate) fn encode_check(bytes: &[u8]) -> String {
+    let mut payload = bytes.to_vec();
+    payload.extend_from_slice(&checksum(bytes));
+    encode(&payload)
+}
+
+/// Decodes the base58 of exactly `N` bytes, or of `N` bytes and their
+/// checksum if `check` is set. Longer texts are rejected before the
+/// quadratic decoding.
+///
+/// Returns `Err(Error::InvalidBase58)` on a character outside the
+/// alphabet, `Err(Error::ChecksumMismatch)` on a wrong checksum and
+/// `Err(length)` if the decoded length isn't the expected one.
+#[cfg(feature = "base58")]
+fn decode_exact<const N: usize>(text: &str, check: bool, length: Error) -> Result<[u8; N], Error> {
+    let len = if check { N + 4 } else { N };
+    // log(256) / log(58) < 1.38, and each leading zero byte is one '1'.
+    if text.len() > len * 138 / 100 + 1 {
+        return Err(length);
+    }
+    let decoded = decode(text).ok_or(Error::InvalidBase58)?;
+    if decoded.len() != len {
+        return Err(length);
+    }
+    if check && checksum(&decoded[..N]) != decoded[N..] {
+        return Err(Error::ChecksumMismatch);
+    }
+    let mut out = [0u8; N];
+    out.copy_from_slice(&decoded[..N]);
+    Ok(out)
+}
+
+#[cfg(feature = "base58")]
+impl PublicKey {
+    /// Encodes `PublicKey` as base58 (Bitcoin alphabet).
+    ///
+    /// Returns the base58 string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_base58("FVen3X669xLzsi6N2V91DoiyzHzg1uAgqiT8jZ9nS96Z").unwrap();
+    ///     assert_eq!(public_key.to_base58(), "FVen3X669xLzsi6N2V91DoiyzHzg1uAgqiT8jZ9nS96Z");
+    /// }
+    /// ```
+    pub fn to_base58(&self) -> String {
+        encode(&self.0)
+    }
+
+    /// Constructs `PublicKey` from base58 (Bitcoin alphabet).
+    ///
+    /// Returns `Err(Error::InvalidBase58)` on a character outside the
+    /// alphabet and `Err(Error::InvalidPublicKey)` if `text` isn't the
+    /// base58 of 32 bytes.
+    pub fn from_base58(text: &str) -> Result<Self, Error> {
+        decode_exact::<PublicKeySize>(text, false, Error::InvalidPublicKey).map(PublicKey)
+    }
+
+    /// Encodes `PublicKey` as base58check: the key followed by the first
+    /// 4 bytes of its double SHA-256.
+    ///
+    /// Returns the base58check string.
+    pub fn to_base58check(&self) -> String {
+        encode_check(&self.0)
+    }
+
+    /// Constructs `PublicKey` from base58check.
+    ///
+    /// Returns `Err(Error::InvalidBase58)` on a character outside the
+    /// alphabet, `Err(Error::ChecksumMismatch)` on a wrong checksum and
+    /// `Err(Error::InvalidPublicKey)` if the payload isn't 32 bytes long.
+    pub fn from_base58check(text: &str) -> Result<Self, Error> {
+        decode_exact::<PublicKeySize>(text, true, Error::InvalidPublicKey).map(PublicKey)
+    }
+}
+
+#[cfg(feature = "base58")]
+impl Signature {
+    /// Encodes `Signature` as base58 (Bitcoin alphabet).
+    ///
+    /// Returns the base58 string.
+    pub fn to_base58(&self) -> String {
+        encode(&self.0)
+    }
+
+    /// Constructs `Signature` from base58 (Bitcoin alphabet).
+    ///
+    /// Returns `Err(Error::InvalidBase58)` on a character outside the
+    /// alphabet and `Err(Error::InvalidSignatureEncoding)` if `text` isn't
+    /// the base58 of 64 bytes.
+    pub fn from_base58(text: &str) -> Result<Self, Error> {
+        decode_exact::<SignatureSize>(text, false, Error::InvalidSignatureEncoding).map(Signature)
+    }
+
+    /// Encodes `Signature` as base58check: the signature followed by the
+    /// first 4 bytes of its double SHA-256.
+    ///
+    /// Returns the base58check string.
+    pub fn to_base58check(&self) -> String {
+        encode_check(&self.0)
+    }
+
+    /// Constructs `Signature` from base58check.
+    ///
+    /// Returns `Err(Error::InvalidBase58)` on a character outside the
+    /// alphabet, `Err(Error::ChecksumMismatch)` on a wrong checksum and
+    /// `Err(Error::InvalidSignatureEncoding)` if the payload isn't 64 bytes
+    /// long.
+    pub fn from_base58check(text: &str) -> Result<Self, Error> {
+        decode_exact::<SignatureSize>(text, true, Error::InvalidSignatureEncoding).map(Signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    #[test]
+    fn base58_vectors() {
+        // draft-msporny-base58 test vectors.
+        let vectors: [(&[u8], &str); 4] = [
+            (b"Hello World!", "2NEpo7TZRRrLZSi2U"),
+            (
+                b"The quick brown fox jumps over the lazy dog.",
+                "USm3fpXnKG5EUBx2ndxBDMPVciP5hGey2Jh4NDv6gmeo1LkMeiKrLJUUBk6Z",
+            ),
+            (&[0x00, 0x00, 0x28, 0x7f, 0xb4, 0xcd], "11233QC4"),
+            (b"", ""),
+        ];
+        for (bytes, text) in vectors.iter() {
+            assert_eq!(encode(bytes), *text);
+            assert_eq!(decode(text).unwrap(), *bytes);
+        }
+    }
+
+    #[test]
+    fn base58_rejects_foreign_characters() {
+        for text in ["0", "O", "I", "l", "2NEpo7TZRRrLZSi2U+", "é"].iter() {
+            assert_eq!(decode(text), None);
+        }
+    }
+
+    // RFC 8032 TEST 1. The strings are the output of the bs58 crate (the
+    // library behind the bs58 CLI), which the tests below also compare
+    // against on random inputs.
+    #[cfg(feature = "base58")]
+    const PUBLIC_KEY: &str = "FVen3X669xLzsi6N2V91DoiyzHzg1uAgqiT8jZ9nS96Z";
+    #[cfg(feature = "base58")]
+    const PUBLIC_KEY_CHECK: &str = "2dqvheyJXzEYpywfm8g7TshzLbaXWTwHKQPkh4rYX3Db2B3TPZ";
+    #[cfg(feature = "base58")]
+    const SIGNATURE: &str =
+        "5awYiUvGiDFA33EJjj4TXJG44a5afJc8QjWRpGgQiu6b23jCr7yndW2fmp9ujwqJVe32J456wV3VF78Asb1obnTc";
+    #[cfg(feature = "base58")]
+    const SIGNATURE_CHECK: &str = "X1DoCZ2HnoNoWmRwQF4ph9Uya7Na33UFiysdsVEHjsVGcofJ2hPcMd65sLdW99QG9a5ovnTFBckoCjmH2LH3mi6oRQ1pM";
+
+    #[cfg(feature = "base58")]
+    #[test]
+    fn base58_rfc8032_vectors() {
+        let public = PublicKey::from_bytes(
+            &hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap(),
+        )
+        .unwrap();
+        let sig = Signature::from_bytes(&hex::decode("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap()).unwrap();
+
+        assert_eq!(public.to_base58(), PUBLIC_KEY);
+        assert_eq!(public.to_base58check(), PUBLIC_KEY_CHECK);
+        assert_eq!(sig.to_base58(), SIGNATURE);
+        assert_eq!(sig.to_base58check(), SIGNATURE_CHECK);
+
+        assert!(PublicKey::from_base58(PUBLIC_KEY).unwrap().as_bytes() == public.as_bytes());
+        assert!(
+            PublicKey::from_base58check(PUBLIC_KEY_CHECK)
+                .unwrap()
+                .as_bytes()
+                == public.as_bytes()
+        );
+        assert_eq!(Signature::from_base58(SIGNATURE), Ok(sig));
+        assert_eq!(Signature::from_base58check(SIGNATURE_CHECK), Ok(sig));
+    }
+
+    #[cfg(feature = "base58")]
+    #[test]
+    fn base58_matches_bs58_crate() {
+        use rand::RngCore;
+
+        let mut rng = rand::thread_rng();
+        for i in 0..200 {
+            let mut bytes = [0u8; 64];
+            rng.fill_bytes(&mut bytes);
+            // Leading zero bytes become leading '1's.
+            for b in bytes.iter_mut().take(i % 4) {
+                *b = 0;
+            }
+            let sig = Signature(bytes);
+            assert_eq!(sig.to_base58(), bs58::encode(&bytes[..]).into_string());
+            assert_eq!(
+                sig.to_base58check(),
+                bs58::encode(&bytes[..]).with_check().into_string()
+            );
+            let public = PublicKey::from_bytes(&bytes[..32]).unwrap();
+            assert_eq!(
+                public.to_base58check(),
+                bs58::encode(&bytes[..32]).with_check().into_string()
+            );
+            assert_eq!(Signature::from_base58check(&sig.to_base58check()), Ok(sig));
+        }
+    }
+
+    #[cfg(feature = "base58")]
+    #[test]
+    fn base58_errors_are_distinct() {
+        // A character outside the alphabet.
+        assert_eq!(
+            PublicKey::from_base58(&PUBLIC_KEY.replace('F', "0")).err(),
+            Some(Error::InvalidBase58)
+        );
+        assert_eq!(
+            Signature::from_base58check(&SIGNATURE_CHECK.replace('X', "l")).err(),
+            Some(Error::InvalidBase58)
+        );
+        // A valid encoding with a wrong checksum.
+        let mut bytes = bs58::decode(PUBLIC_KEY_CHECK).into_vec().unwrap();
+        bytes[35] ^= 1;
+        assert_eq!(
+            PublicKey::from_base58check(&encode(&bytes)).err(),
+            Some(Error::ChecksumMismatch)
+        );
+        // Wrong lengths, including a checked string where a plain one is
+        // expected and the reverse.
+        assert_eq!(
+            PublicKey::from_base58(PUBLIC_KEY_CHECK).err(),
+            Some(Error::InvalidPublicKey)
+        );
+        assert_eq!(
+            PublicKey::from_base58check(PUBLIC_KEY).err(),
+            Some(Error::InvalidPublicKey)
+        );
+        assert_eq!(
+            Signature::from_base58(PUBLIC_KEY).err(),
+            Some(Error::InvalidSignatureEncoding)
+        );
+        assert_eq!(
+            Signature::from_base58(&"1".repeat(1000)).err(),
+            Some(Error::InvalidSignatureEncoding)
+        );
+    }
+}
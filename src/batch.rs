@@ -0,0 +1,1006 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Batch verification: many signatures checked with one multiscalar
+// multiplication. Each signature's equation [s]B = R + [k]A is weighed
+// by a random 128-bit z, and the batch holds if
+//
+//     [8]([sum z_i s_i]B - sum [z_i]R_i - sum [z_i k_i]A_i) = 0.
+//
+// The doublings are shared by the whole batch, so a signature costs a
+// fraction of `verify`. Like `verify.rs`, this module only sees public
+// data.
+//
+// `verify_batch` draws the z_i from a CSPRNG. `verify_batch_deterministic`
+// derives them from a hash of the whole batch instead, so its verdict
+// depends on the inputs alone.
+//
+// With the `parallel` feature, batches of `PARALLEL_THRESHOLD` signatures
+// or more are decoded and hashed on the rayon thread pool, and their
+// equation is summed in chunks there. The verdicts don't change.
+
+#![allow(non_snake_case)]
+
+use crate::curve25519::group_element::*;
+use crate::curve25519::scalar_ops::*;
+
+use crate::errors::*;
+#[cfg(feature = "observer")]
+use crate::observer;
+use crate::public::*;
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+use crate::rng::*;
+use crate::signature::*;
+use crate::verify::*;
+
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+use rand_core::RngCore;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use sha2::{Digest, Sha512};
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+use std::collections::BTreeMap;
+
+// Smaller batches are verified on the calling thread.
+#[cfg(feature = "parallel")]
+const PARALLEL_THRESHOLD: usize = 64;
+// The fewest signatures a thread sums, so the shared doublings still pay.
+#[cfg(feature = "parallel")]
+const MIN_CHUNK: usize = 32;
+
+// Domain separation of the hash the deterministic coefficients come from.
+const DETERMINISTIC_BATCH_DOMAIN: &[u8] = b"ed25519-fun deterministic batch v1";
+
+/// A signature ready for the batch equation: R and A as returned by
+/// `P3::decode` (negated), s and the reduced challenge k.
+pub(crate) struct Prepared {
+    R: P3,
+    A: P3,
+    s: [u8; 32],
+    k: [u8; 32],
+}
+
+impl Prepared {
+    /// Decodes the points of a signature and computes its challenge, with
+    /// the checks of `verify` in the same order.
+    ///
+    /// Returns `Ok(Prepared)`, `Err(Error::InvalidPublicKey)`,
+    /// `Err(Error::NonCanonicalScalar)` or
+    /// `Err(Error::InvalidSignaturePoint)`.
+    fn decode(message: &[u8], sig: &Signature, public: &PublicKey) -> Result<Self, Error> {
+        let signature = sig.as_bytes();
+        let A = P3::decode(public.0).ok_or(Error::InvalidPublicKey)?;
+        if !check_lt_l(&signature[32..64]) {
+            return Err(Error::NonCanonicalScalar);
+        }
+        let mut R = [0u8; 32];
+        R.copy_from_slice(&signature[..32]);
+        let R = P3::decode(R).ok_or(Error::InvalidSignaturePoint)?;
+
+        let mut s = [0u8; 32];
+        s.copy_from_slice(&signature[32..64]);
+        let k = challenge(&signature[..32], &public.0, None, |hash: &mut Sha512| {
+            hash.input(message)
+        });
+        Ok(Prepared { R, A, s, k })
+    }
+
+    /// `decode` with the failure reported to the observer.
+    pub(crate) fn new(message: &[u8], sig: &Signature, public: &PublicKey) -> Result<Self, Error> {
+        let prepared = Prepared::decode(message, sig, public);
+        #[cfg(feature = "observer")]
+        observer::verified(public, prepared.as_ref().map(|_| ()).map_err(|e| *e));
+        prepared
+    }
+}
+
+/// Prepares every signature of a batch, after checking that the three
+/// slices have the same length.
+///
+/// Returns the prepared signatures, or the error of the first one that
+/// `verify` would reject before its group equation.
+pub(crate) fn prepare_batch(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+) -> Result<Vec<Prepared>, Error> {
+    if messages.len() != signatures.len() || messages.len() != public_keys.len() {
+        return Err(Error::BatchLengthMismatch {
+            messages: messages.len(),
+            signatures: signatures.len(),
+            public_keys: public_keys.len(),
+        });
+    }
+    prepare_each(messages, signatures, public_keys)
+        .into_iter()
+        .collect()
+}
+
+/// Prepares the signatures of slices of the same length, on the rayon
+/// thread pool for large batches with the `parallel` feature.
+///
+/// Returns the outcome of `Prepared::new` for every index, in order.
+fn prepare_each(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+) -> Vec<Result<Prepared, Error>> {
+    #[cfg(feature = "parallel")]
+    {
+        if messages.len() >= PARALLEL_THRESHOLD {
+            return (0..messages.len())
+                .into_par_iter()
+                .map(|i| Prepared::new(messages[i], &signatures[i], &public_keys[i]))
+                .collect();
+        }
+    }
+    messages
+        .iter()
+        .zip(signatures)
+        .zip(public_keys)
+        .map(|((message, sig), public)| Prepared::new(message, sig, public))
+        .collect()
+}
+
+/// Derives the coefficients of `verify_batch_deterministic`: with
+/// T = SHA-512(domain || len || R_i || A_i || s_i || SHA-512(M_i) || ...)
+/// over the whole batch, z_i is the first 16 bytes of
+/// SHA-512(T || i), with lengths and indices as 64-bit little-endian.
+///
+/// Returns one 128-bit z_i per signature, zero-extended to 32 bytes.
+pub(crate) fn deterministic_coefficients(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+) -> Vec<[u8; 32]> {
+    let mut transcript = Sha512::default();
+    transcript.input(DETERMINISTIC_BATCH_DOMAIN);
+    transcript.input((messages.len() as u64).to_le_bytes());
+    for ((message, sig), public) in messages.iter().zip(signatures).zip(public_keys) {
+        let signature = sig.as_bytes();
+        transcript.input(&signature[..32]);
+        transcript.input(public.0);
+        transcript.input(&signature[32..]);
+        transcript.input(Sha512::digest(message));
+    }
+    let transcript = transcript.result();
+
+    (0..messages.len() as u64)
+        .map(|i| {
+            let mut hash = Sha512::default();
+            hash.input(&transcript);
+            hash.input(i.to_le_bytes());
+            let mut z = [0u8; 32];
+            z[..16].copy_from_slice(&hash.result()[..16]);
+            z
+        })
+        .collect()
+}
+
+/// Checks the batch equation of `entries` weighed by the scalars `z`.
+///
+/// Returns `true` if [8]([sum z_i s_i]B - sum [z_i]R_i - sum [z_i k_i]A_i)
+/// is the identity.
+pub(crate) fn check_batch(entries: &[Prepared], z: &[[u8; 32]]) -> bool {
+    #[cfg(feature = "parallel")]
+    {
+        if entries.len() >= PARALLEL_THRESHOLD {
+            let chunk = MIN_CHUNK.max(entries.len().div_ceil(rayon::current_num_threads()));
+            return entries
+                .par_chunks(chunk)
+                .zip(z.par_chunks(chunk))
+                .map(|(entries, z)| batch_sum(entries, z))
+                .reduce(P3::zero, |a, b| (a + b.to_Cached()).to_P3())
+                .multiply_by_cofactor()
+                .is_identity();
+        }
+    }
+    batch_sum(entries, z).multiply_by_cofactor().is_identity()
+}
+
+/// Computes [sum z_i s_i]B - sum [z_i]R_i - sum [z_i k_i]A_i over
+/// `entries`, the batch equation before the cofactor.
+///
+/// Returns the point, which sums across disjoint sub-batches.
+fn batch_sum(entries: &[Prepared], z: &[[u8; 32]]) -> P3 {
+    let zero = [0u8; 32];
+    let mut b = [0u8; 32];
+    let mut terms = Vec::with_capacity(2 * entries.len());
+    for (entry, z) in entries.iter().zip(z) {
+        let mut sum = [0u8; 32];
+        multiply_add(&mut sum, z, &entry.s, &b);
+        b = sum;
+
+        let mut zk = [0u8; 32];
+        multiply_add(&mut zk, z, &entry.k, &zero);
+        terms.push((*z, entry.R));
+        terms.push((zk, entry.A));
+    }
+
+    P3::multiscalar_multiply_vartime(&b, &terms)
+}
+
+/// Verifies `signatures[i]` of `messages[i]` under `public_keys[i]`, for
+/// every i at once.
+///
+/// Each equation is weighed by a random 128-bit scalar from the CSPRNG of
+/// `Keypair::generate`, so a batch with an invalid signature is accepted
+/// with probability at most 2^-128. The batch equation is cofactored: it
+/// agrees with `verify` on every signature without a small-order
+/// component, and in particular on every signature made by signing, but
+/// may accept one that `verify` rejects because of such a component.
+///
+/// Returns `Ok(())` if every signature is valid,
+/// `Err(Error::BatchLengthMismatch)` if the slices don't have the same
+/// length, the error of `verify` for the first signature with an
+/// invalid key, s or R, and `Err(Error::SignatureMismatch)` if the batch
+/// equation fails. The empty batch is valid.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{verify_batch, Keypair};
+///
+/// fn main() {
+///     let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::generate()).collect();
+///     let messages: [&[u8]; 3] = [b"block 1", b"block 2", b"block 3"];
+///     let signatures: Vec<_> = keypairs
+///         .iter()
+///         .zip(messages.iter())
+///         .map(|(keypair, message)| keypair.sign(message))
+///         .collect();
+///     let public_keys: Vec<_> = keypairs.iter().map(|keypair| *keypair.public()).collect();
+///
+///     assert!(verify_batch(&messages, &signatures, &public_keys).is_ok());
+///     assert!(verify_batch(&messages[..2], &signatures, &public_keys).is_err());
+/// }
+/// ```
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+pub fn verify_batch(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+) -> Result<(), Error> {
+    let entries = prepare_batch(messages, signatures, public_keys)?;
+    let z = random_coefficients(entries.len());
+    batch_verdict(&entries, &z)
+}
+
+/// Verifies a batch as `verify_batch` and, if it fails, finds the
+/// invalid signatures by bisection: a failing half is split again, and
+/// a half whose sibling passed is known to fail without a check. The
+/// halves reuse the decoded points, challenges and coefficients of the
+/// whole batch, so with a few invalid signatures among n the cost is
+/// that of about log2(n) batches each rather than n verifications.
+///
+/// Returns `Ok(())` if every signature is valid, and otherwise the
+/// indices of the invalid ones in increasing order: those `verify`
+/// rejects for their key, s or R, those failing the batch equation and,
+/// if the slices don't have the same length, every index past the
+/// shortest one.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{verify_batch_identify, Keypair};
+///
+/// fn main() {
+///     let keypair = Keypair::generate();
+///     let messages: Vec<&[u8]> = vec![b"tx 1", b"tx 2", b"tx 3", b"tx 4"];
+///     let mut signatures: Vec<_> = messages.iter().map(|m| keypair.sign(m)).collect();
+///     let public_keys = vec![*keypair.public(); 4];
+///     assert_eq!(verify_batch_identify(&messages, &signatures, &public_keys), Ok(()));
+///
+///     signatures[2] = keypair.sign(b"forged");
+///     assert_eq!(
+///         verify_batch_identify(&messages, &signatures, &public_keys),
+///         Err(vec![2])
+///     );
+/// }
+/// ```
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+pub fn verify_batch_identify(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+) -> Result<(), Vec<usize>> {
+    let complete = messages.len().min(signatures.len()).min(public_keys.len());
+    let longest = messages.len().max(signatures.len()).max(public_keys.len());
+
+    let mut invalid: Vec<usize> = identify_each(
+        &messages[..complete],
+        &signatures[..complete],
+        &public_keys[..complete],
+    )
+    .iter()
+    .enumerate()
+    .filter(|(_, verdict)| verdict.is_err())
+    .map(|(i, _)| i)
+    .collect();
+    invalid.extend(complete..longest);
+
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(invalid)
+    }
+}
+
+/// Verifies slices of the same length as `verify_batch_identify`.
+///
+/// Returns the verdict for every index, in order: the error of `verify`
+/// for an invalid key, s or R, `Err(Error::SignatureMismatch)` for a
+/// signature failing the batch equation and `Ok(())` otherwise. Only the
+/// former are reported to the observer.
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+pub(crate) fn identify_each(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+) -> Vec<Result<(), Error>> {
+    let mut verdicts = Vec::with_capacity(messages.len());
+    let mut indices = Vec::with_capacity(messages.len());
+    let mut entries = Vec::with_capacity(messages.len());
+    for (i, prepared) in prepare_each(messages, signatures, public_keys)
+        .into_iter()
+        .enumerate()
+    {
+        match prepared {
+            Ok(entry) => {
+                indices.push(i);
+                entries.push(entry);
+                verdicts.push(Ok(()));
+            }
+            Err(error) => verdicts.push(Err(error)),
+        }
+    }
+
+    let z = random_coefficients(entries.len());
+    if !check_batch(&entries, &z) {
+        let mut invalid = Vec::new();
+        bisect(&entries, &z, &indices, &mut invalid);
+        for i in invalid {
+            verdicts[i] = Err(Error::SignatureMismatch);
+        }
+    }
+    verdicts
+}
+
+/// Finds the invalid signatures of `entries`, whose batch equation is
+/// known to fail, and appends their `indices` to `invalid`.
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+fn bisect(entries: &[Prepared], z: &[[u8; 32]], indices: &[usize], invalid: &mut Vec<usize>) {
+    if entries.len() == 1 {
+        invalid.push(indices[0]);
+        return;
+    }
+
+    let mid = entries.len() / 2;
+    let (left, right) = entries.split_at(mid);
+    let (z_left, z_right) = z.split_at(mid);
+    let (indices_left, indices_right) = indices.split_at(mid);
+    if check_batch(left, z_left) {
+        // The failure is all in the right half.
+        bisect(right, z_right, indices_right, invalid);
+    } else {
+        bisect(left, z_left, indices_left, invalid);
+        if !check_batch(right, z_right) {
+            bisect(right, z_right, indices_right, invalid);
+        }
+    }
+}
+
+/// Verifies signatures of one `message`, faster than `verify_batch` only
+/// when public keys repeat: with distinct signers it costs the same.
+///
+/// The verdicts are those of `verify_batch` with `message` for every
+/// pair. SHA-512 absorbs R and A before the message, so the challenges
+/// can't share a hash state. What the shared message allows is grouping
+/// by signer: every signature of the same public key adds to a single
+/// term [sum z_i k_i]A, and the key is decoded once. The more signatures
+/// per key, the less the batch costs next to `verify_batch`.
+///
+/// Returns `Ok(())` if every signature is valid, the error of `verify`
+/// for the first pair with an invalid key, s or R, and
+/// `Err(Error::SignatureMismatch)` if the batch equation fails. The
+/// empty batch is valid.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{verify_batch_same_message, Keypair};
+///
+/// fn main() {
+///     let proposal = [0x42u8; 32];
+///     let voters: Vec<Keypair> = (0..5).map(|_| Keypair::generate()).collect();
+///     let mut votes: Vec<_> = voters
+///         .iter()
+///         .map(|voter| (*voter.public(), voter.sign(&proposal)))
+///         .collect();
+///     assert!(verify_batch_same_message(&proposal, &votes).is_ok());
+///
+///     votes[3].1 = voters[3].sign(b"another proposal");
+///     assert!(verify_batch_same_message(&proposal, &votes).is_err());
+/// }
+/// ```
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+pub fn verify_batch_same_message(
+    message: &[u8],
+    pairs: &[(PublicKey, Signature)],
+) -> Result<(), Error> {
+    let z = random_coefficients(pairs.len());
+    let zero = [0u8; 32];
+    let mut b = [0u8; 32];
+    let mut terms = Vec::with_capacity(pairs.len() + 1);
+    // The position in `terms` of the [sum z_i k_i]A term of each key.
+    let mut signers: BTreeMap<[u8; 32], usize> = BTreeMap::new();
+
+    for ((public, sig), z) in pairs.iter().zip(&z) {
+        let signer = match signers.get(&public.0) {
+            Some(&signer) => Ok(signer),
+            None => match P3::decode(public.0) {
+                Some(A) => {
+                    terms.push((zero, A));
+                    signers.insert(public.0, terms.len() - 1);
+                    Ok(terms.len() - 1)
+                }
+                None => Err(Error::InvalidPublicKey),
+            },
+        };
+        let R = signer.and_then(|_| {
+            let signature = sig.as_bytes();
+            if !check_lt_l(&signature[32..64]) {
+                return Err(Error::NonCanonicalScalar);
+            }
+            let mut R = [0u8; 32];
+            R.copy_from_slice(&signature[..32]);
+            P3::decode(R).ok_or(Error::InvalidSignaturePoint)
+        });
+        #[cfg(feature = "observer")]
+        observer::verified(public, R.as_ref().map(|_| ()).map_err(|e| *e));
+        let (signer, R) = (signer?, R?);
+
+        let signature = sig.as_bytes();
+        let k = challenge(&signature[..32], &public.0, None, |hash: &mut Sha512| {
+            hash.input(message)
+        });
+        let mut sum = [0u8; 32];
+        multiply_add(&mut sum, z, &signature[32..64], &b);
+        b = sum;
+        let mut zk = [0u8; 32];
+        multiply_add(&mut zk, z, &k, &terms[signer].0);
+        terms[signer].0 = zk;
+        terms.push((*z, R));
+    }
+
+    if P3::multiscalar_multiply_vartime(&b, &terms)
+        .multiply_by_cofactor()
+        .is_identity()
+    {
+        Ok(())
+    } else {
+        Err(Error::SignatureMismatch)
+    }
+}
+
+/// Draws `n` random 128-bit coefficients from the CSPRNG of
+/// `Keypair::generate`.
+///
+/// Returns the z_i, zero-extended to 32 bytes.
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+fn random_coefficients(n: usize) -> Vec<[u8; 32]> {
+    let mut rng = default_rng();
+    (0..n)
+        .map(|_| {
+            let mut z = [0u8; 32];
+            rng.fill_bytes(&mut z[..16]);
+            z
+        })
+        .collect()
+}
+
+/// Verifies a batch as `verify_batch`, with the z_i derived from a
+/// SHA-512 of every R, A, s and message hash of the batch instead of a
+/// CSPRNG: the same inputs always get the same verdict, and no
+/// randomness is needed.
+///
+/// The z_i are only as unpredictable as SHA-512 is. With random z_i, a
+/// forger must get a batch past coefficients drawn after it was
+/// submitted; here it can compute them itself and search offline for a
+/// batch whose invalid signatures cancel out. Each try succeeds with
+/// probability about 2^-128, as with random z_i, but only as long as
+/// SHA-512 behaves as a random function on these inputs. Prefer
+/// `verify_batch` unless the verdict must be reproducible.
+///
+/// Returns the verdicts of `verify_batch`.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{verify_batch_deterministic, Keypair, SecretKey};
+///
+/// fn main() {
+///     let keypair = Keypair::from_secret(SecretKey::from_array([7; 32]));
+///     let messages: [&[u8]; 2] = [b"vote 1", b"vote 2"];
+///     let signatures = [keypair.sign(messages[0]), keypair.sign(messages[1])];
+///     let public_keys = [*keypair.public(); 2];
+///
+///     assert!(verify_batch_deterministic(&messages, &signatures, &public_keys).is_ok());
+///     let swapped = [signatures[1], signatures[0]];
+///     assert!(verify_batch_deterministic(&messages, &swapped, &public_keys).is_err());
+/// }
+/// ```
+pub fn verify_batch_deterministic(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+) -> Result<(), Error> {
+    let entries = prepare_batch(messages, signatures, public_keys)?;
+    let z = deterministic_coefficients(messages, signatures, public_keys);
+    batch_verdict(&entries, &z)
+}
+
+/// Verifies `signatures[i]` of `messages[i]` under `public_keys[i]` on
+/// its own for every i, on the rayon thread pool for large batches with
+/// the `parallel` feature.
+///
+/// Returns the verdict of `verify` for every index, in order, and
+/// `Err(Error::BatchLengthMismatch)` for every index past the shortest
+/// slice.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{verify_each, Error, Keypair, SecretKey};
+///
+/// fn main() {
+///     let keypair = Keypair::from_secret(SecretKey::from_array([7; 32]));
+///     let messages: [&[u8]; 2] = [b"ping", b"pong"];
+///     let signatures = [keypair.sign(b"ping"), keypair.sign(b"ping")];
+///     let public_keys = [*keypair.public(); 2];
+///
+///     assert_eq!(
+///         verify_each(&messages, &signatures, &public_keys),
+///         vec![Ok(()), Err(Error::SignatureMismatch)]
+///     );
+/// }
+/// ```
+pub fn verify_each(
+    messages: &[&[u8]],
+    signatures: &[Signature],
+    public_keys: &[PublicKey],
+) -> Vec<Result<(), Error>> {
+    let complete = messages.len().min(signatures.len()).min(public_keys.len());
+    let longest = messages.len().max(signatures.len()).max(public_keys.len());
+    let verify = |i: usize| public_keys[i].verify(messages[i], &signatures[i]);
+
+    #[cfg(feature = "parallel")]
+    let mut verdicts: Vec<Result<(), Error>> = if complete >= PARALLEL_THRESHOLD {
+        (0..complete).into_par_iter().map(verify).collect()
+    } else {
+        (0..complete).map(verify).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let mut verdicts: Vec<Result<(), Error>> = (0..complete).map(verify).collect();
+
+    verdicts.resize(
+        longest,
+        Err(Error::BatchLengthMismatch {
+            messages: messages.len(),
+            signatures: signatures.len(),
+            public_keys: public_keys.len(),
+        }),
+    );
+    verdicts
+}
+
+/// Returns `Ok(())` if `check_batch` holds and
+/// `Err(Error::SignatureMismatch)` otherwise.
+fn batch_verdict(entries: &[Prepared], z: &[[u8; 32]]) -> Result<(), Error> {
+    if check_batch(entries, z) {
+        Ok(())
+    } else {
+        Err(Error::SignatureMismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+    extern crate rand;
+
+    use super::*;
+    use rand::Rng;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+
+    // (public key, message, signature) of the sign.input corpus.
+    fn corpus() -> Vec<(PublicKey, Vec<u8>, Signature)> {
+        let file = File::open("./tests/sign.input").expect("Where are the test vectors? :(");
+        BufReader::new(file)
+            .lines()
+            .map(|line| {
+                let l = line.unwrap();
+                let slices: Vec<&str> = l.split(':').collect();
+                (
+                    PublicKey::from_bytes(&hex::decode(slices[1]).unwrap()).unwrap(),
+                    hex::decode(slices[2]).unwrap(),
+                    Signature::from_bytes(&hex::decode(slices[3]).unwrap()[..64]).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn batch(
+        entries: &[(PublicKey, Vec<u8>, Signature)],
+    ) -> (Vec<&[u8]>, Vec<Signature>, Vec<PublicKey>) {
+        (
+            entries.iter().map(|(_, m, _)| &m[..]).collect(),
+            entries.iter().map(|(_, _, s)| *s).collect(),
+            entries.iter().map(|(p, _, _)| *p).collect(),
+        )
+    }
+
+    type BatchVerifier = fn(&[&[u8]], &[Signature], &[PublicKey]) -> Result<(), Error>;
+
+    // Every batch verifier, which must all give the same verdicts.
+    fn verifiers() -> Vec<BatchVerifier> {
+        vec![
+            #[cfg(any(feature = "rand", feature = "getrandom"))]
+            verify_batch,
+            verify_batch_deterministic,
+        ]
+    }
+
+    #[test]
+    fn valid_batches_of_several_sizes() {
+        let corpus = corpus();
+        for verify in verifiers() {
+            for &size in [0, 1, 2, 3, 16, 100].iter() {
+                for chunk in corpus.chunks(size.max(1)).take(3) {
+                    let chunk = &chunk[..size.min(chunk.len())];
+                    let (messages, signatures, public_keys) = batch(chunk);
+                    assert_eq!(verify(&messages, &signatures, &public_keys), Ok(()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn one_corrupted_signature_fails_the_batch() {
+        let corpus = corpus();
+        let (messages, signatures, public_keys) = batch(&corpus[..32]);
+        for verify in verifiers() {
+            for &i in [0, 13, 31].iter() {
+                // A low bit of s keeps it reduced.
+                let mut corrupted = signatures.clone();
+                let mut bytes = corrupted[i].to_bytes();
+                bytes[32] ^= 1;
+                corrupted[i] = Signature::from_bytes(&bytes).unwrap();
+                assert_eq!(
+                    verify(&messages, &corrupted, &public_keys),
+                    Err(Error::SignatureMismatch)
+                );
+
+                let mut swapped = public_keys.clone();
+                swapped.swap(i, (i + 1) % 32);
+                assert_eq!(
+                    verify(&messages, &signatures, &swapped),
+                    Err(Error::SignatureMismatch)
+                );
+
+                let mut s_plus_l = signatures.clone();
+                let mut bytes = s_plus_l[i].to_bytes();
+                bytes[63] |= 0xf0;
+                s_plus_l[i] = Signature::from_bytes(&bytes).unwrap();
+                assert_eq!(
+                    verify(&messages, &s_plus_l, &public_keys),
+                    Err(Error::NonCanonicalScalar)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn length_mismatch_is_rejected_early() {
+        let corpus = corpus();
+        let (messages, signatures, public_keys) = batch(&corpus[..4]);
+        for verify in verifiers() {
+            assert_eq!(
+                verify(&messages[..3], &signatures, &public_keys),
+                Err(Error::BatchLengthMismatch {
+                    messages: 3,
+                    signatures: 4,
+                    public_keys: 4
+                })
+            );
+            assert_eq!(
+                verify(&messages, &signatures, &public_keys[..0]),
+                Err(Error::BatchLengthMismatch {
+                    messages: 4,
+                    signatures: 4,
+                    public_keys: 0
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn batch_agrees_with_verify_on_random_inputs() {
+        let corpus = corpus();
+        let mut rng = rand::thread_rng();
+        for _ in 0..24 {
+            let size = rng.gen_range(1, 12);
+            let mut entries: Vec<_> = (0..size)
+                .map(|_| corpus[rng.gen_range(0, corpus.len())].clone())
+                .collect();
+            // Corrupt a random byte of a random entry, or nothing.
+            if rng.gen_bool(0.75) {
+                let entry = &mut entries[rng.gen_range(0, size)];
+                match rng.gen_range(0, 3) {
+                    0 => entry.1.push(rng.gen()),
+                    1 => {
+                        let mut bytes = entry.2.to_bytes();
+                        bytes[rng.gen_range(0, 64)] ^= 1 << rng.gen_range(0, 8);
+                        entry.2 = Signature::from_bytes(&bytes).unwrap();
+                    }
+                    _ => {
+                        let mut bytes = entry.0.to_bytes();
+                        bytes[rng.gen_range(0, 32)] ^= 1 << rng.gen_range(0, 8);
+                        entry.0 = PublicKey::from_bytes(&bytes).unwrap();
+                    }
+                }
+            }
+
+            let (messages, signatures, public_keys) = batch(&entries);
+            let single: Result<(), Error> = entries
+                .iter()
+                .try_for_each(|(public, message, sig)| public.verify(message, sig));
+            for verify in verifiers() {
+                let batched = verify(&messages, &signatures, &public_keys);
+                assert_eq!(batched.is_ok(), single.is_ok());
+                if size == 1 {
+                    assert_eq!(batched, single);
+                }
+            }
+        }
+    }
+
+    // Replaces the signatures at `bad` with signatures of other
+    // messages, with R replaced too for every third.
+    #[cfg(any(feature = "rand", feature = "getrandom"))]
+    fn plant(
+        signatures: &mut [Signature],
+        bad: &[usize],
+        corpus: &[(PublicKey, Vec<u8>, Signature)],
+    ) {
+        for (n, &i) in bad.iter().enumerate() {
+            let mut bytes = corpus[(i + 1) % corpus.len()].2.to_bytes();
+            if n % 3 == 2 {
+                bytes[..32].copy_from_slice(&[2u8; 32]);
+            }
+            signatures[i] = Signature::from_bytes(&bytes).unwrap();
+        }
+    }
+
+    #[cfg(any(feature = "rand", feature = "getrandom"))]
+    #[test]
+    fn identify_finds_exactly_the_invalid_signatures() {
+        let corpus = corpus();
+        let (messages, signatures, public_keys) = batch(&corpus[..80]);
+        let mut rng = rand::thread_rng();
+        assert_eq!(
+            verify_batch_identify(&messages, &signatures, &public_keys),
+            Ok(())
+        );
+        assert_eq!(verify_batch_identify(&[], &[], &[]), Ok(()));
+
+        for &count in [1, 2, 8].iter() {
+            for _ in 0..4 {
+                let mut bad = rand::seq::index::sample(&mut rng, 80, count).into_vec();
+                bad.sort_unstable();
+                let mut planted = signatures.clone();
+                plant(&mut planted, &bad, &corpus);
+                assert_eq!(
+                    verify_batch_identify(&messages, &planted, &public_keys),
+                    Err(bad)
+                );
+            }
+        }
+
+        let mut planted = signatures.clone();
+        plant(&mut planted, &[0, 79], &corpus);
+        assert_eq!(
+            verify_batch_identify(&messages[..78], &planted, &public_keys),
+            Err(vec![0, 78, 79])
+        );
+    }
+
+    #[test]
+    fn verify_each_matches_verify() {
+        let corpus = corpus();
+        // Past `PARALLEL_THRESHOLD`, and below it.
+        for &size in [100, 5].iter() {
+            let (messages, mut signatures, public_keys) = batch(&corpus[..size]);
+            signatures[3] = signatures[4];
+            let mut bytes = signatures[size - 1].to_bytes();
+            bytes[63] |= 0xf0;
+            signatures[size - 1] = Signature::from_bytes(&bytes).unwrap();
+
+            let verdicts = verify_each(&messages, &signatures, &public_keys);
+            let expected: Vec<_> = (0..size)
+                .map(|i| public_keys[i].verify(messages[i], &signatures[i]))
+                .collect();
+            assert_eq!(verdicts, expected);
+            assert_eq!(verdicts[3], Err(Error::SignatureMismatch));
+            assert_eq!(verdicts[size - 1], Err(Error::NonCanonicalScalar));
+
+            let mismatch = Err(Error::BatchLengthMismatch {
+                messages: size - 2,
+                signatures: size,
+                public_keys: size,
+            });
+            let verdicts = verify_each(&messages[..size - 2], &signatures, &public_keys);
+            assert_eq!(&verdicts[..size - 2], &expected[..size - 2]);
+            assert_eq!(&verdicts[size - 2..], &[mismatch, mismatch]);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_batch_matches_serial() {
+        let corpus = corpus();
+        let (messages, signatures, public_keys) = batch(&corpus[..300]);
+        let mut forged = signatures.clone();
+        forged[201] = signatures[200];
+
+        for &(signatures, valid) in [(&signatures, true), (&forged, false)].iter() {
+            let entries = prepare_batch(&messages, signatures, &public_keys).unwrap();
+            let z = deterministic_coefficients(&messages, signatures, &public_keys);
+            let serial = batch_sum(&entries, &z).multiply_by_cofactor().is_identity();
+            assert_eq!(serial, valid);
+            assert_eq!(check_batch(&entries, &z), serial);
+        }
+
+        // The first error in index order, as on one thread.
+        let mut broken = signatures.clone();
+        let mut bytes = broken[150].to_bytes();
+        bytes[63] |= 0xf0;
+        broken[150] = Signature::from_bytes(&bytes).unwrap();
+        bytes = broken[250].to_bytes();
+        bytes[..32].copy_from_slice(&[2u8; 32]);
+        broken[250] = Signature::from_bytes(&bytes).unwrap();
+        for _ in 0..8 {
+            assert_eq!(
+                prepare_batch(&messages, &broken, &public_keys).err(),
+                Some(Error::NonCanonicalScalar)
+            );
+        }
+    }
+
+    // Signatures of `message` by `signers` distinct keys, in turn.
+    #[cfg(all(
+        any(feature = "rand", feature = "getrandom"),
+        not(feature = "verify-only")
+    ))]
+    fn votes(message: &[u8], signers: u8, n: usize) -> Vec<(PublicKey, Signature)> {
+        use crate::keypair::Keypair;
+        use crate::secret::SecretKey;
+
+        let keypairs: Vec<Keypair> = (0..signers)
+            .map(|i| Keypair::from_secret(SecretKey::from_array([i; 32])))
+            .collect();
+        (0..n)
+            .map(|i| {
+                let keypair = &keypairs[i % keypairs.len()];
+                (*keypair.public(), keypair.sign(message))
+            })
+            .collect()
+    }
+
+    #[cfg(all(
+        any(feature = "rand", feature = "getrandom"),
+        not(feature = "verify-only")
+    ))]
+    #[test]
+    fn same_message_batches() {
+        let message = [0x5au8; 32];
+        assert_eq!(verify_batch_same_message(&message, &[]), Ok(()));
+        for &(signers, n) in [(1, 1), (3, 3), (40, 40), (7, 50)].iter() {
+            let pairs = votes(&message, signers, n);
+            assert_eq!(verify_batch_same_message(&message, &pairs), Ok(()));
+            assert_eq!(
+                verify_batch_same_message(b"another message", &pairs),
+                Err(Error::SignatureMismatch)
+            );
+
+            // One bad signature hidden in the set.
+            let i = n / 2;
+            let mut hidden = pairs.clone();
+            hidden[i].1 = votes(b"another message", signers, n)[i].1;
+            assert_eq!(
+                verify_batch_same_message(&message, &hidden),
+                Err(Error::SignatureMismatch)
+            );
+            if n > 1 {
+                // A valid signature under another signer's key.
+                let mut swapped = pairs.clone();
+                swapped[i].0 = pairs[(i + 1) % n].0;
+                assert_eq!(
+                    verify_batch_same_message(&message, &swapped),
+                    Err(Error::SignatureMismatch)
+                );
+            }
+        }
+    }
+
+    #[cfg(all(
+        any(feature = "rand", feature = "getrandom"),
+        not(feature = "verify-only")
+    ))]
+    #[test]
+    fn same_message_agrees_with_verify_batch() {
+        let message = b"proposal";
+        let pairs = votes(message, 5, 12);
+        let mut rng = rand::thread_rng();
+        for _ in 0..24 {
+            let mut corrupted = pairs.clone();
+            let entry = &mut corrupted[rng.gen_range(0, 12)];
+            if rng.gen_bool(0.5) {
+                let mut bytes = entry.1.to_bytes();
+                bytes[rng.gen_range(0, 64)] ^= 1 << rng.gen_range(0, 8);
+                entry.1 = Signature::from_bytes(&bytes).unwrap();
+            } else {
+                let mut bytes = entry.0.to_bytes();
+                bytes[rng.gen_range(0, 32)] ^= 1 << rng.gen_range(0, 8);
+                entry.0 = PublicKey::from_bytes(&bytes).unwrap();
+            }
+
+            let messages = vec![&message[..]; 12];
+            let signatures: Vec<_> = corrupted.iter().map(|(_, s)| *s).collect();
+            let public_keys: Vec<_> = corrupted.iter().map(|(p, _)| *p).collect();
+            assert_eq!(
+                verify_batch_same_message(message, &corrupted),
+                verify_batch(&messages, &signatures, &public_keys)
+            );
+        }
+    }
+
+    #[test]
+    fn deterministic_coefficients_are_pinned() {
+        let corpus = corpus();
+        let (messages, signatures, public_keys) = batch(&corpus[..3]);
+        let z = deterministic_coefficients(&messages, &signatures, &public_keys);
+        assert_eq!(
+            z,
+            deterministic_coefficients(&messages, &signatures, &public_keys)
+        );
+        assert_eq!(hex::encode(&z[0][..16]), "ca6db9e3d8f412b8aed7b56d9dd1d67b");
+        assert!(z.iter().all(|z| z[16..] == [0u8; 16]));
+
+        // Any change to the batch changes every coefficient.
+        let mut other = signatures.clone();
+        other.swap(1, 2);
+        let z_other = deterministic_coefficients(&messages, &other, &public_keys);
+        assert!(z.iter().zip(&z_other).all(|(a, b)| a != b));
+        assert_eq!(
+            z_other,
+            deterministic_coefficients(&messages, &other, &public_keys)
+        );
+    }
+}
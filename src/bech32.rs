@@ -0,0 +1,354 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Bech32m (BIP 350) public keys: a human-readable part, the separator
+// '1', the key regrouped into 5-bit characters and a 6-character
+// checksum. There is no witness version: the data part is the key alone.
+// See https://github.com/bitcoin/bips/blob/master/bip-0350.mediawiki.
+
+use crate::constants::*;
+use crate::errors::*;
+use crate::public::*;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+// Longest string, human-readable part included.
+const MAX_LEN: usize = 90;
+
+const CHECKSUM_LEN: usize = 6;
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GEN: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk: u32 = 1;
+    for value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ value as u32;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+/// The checksum input of `hrp` and the 5-bit `data`.
+fn checksum_input<'a>(hrp: &'a [u8], data: &'a [u8]) -> impl Iterator<Item = u8> + 'a {
+    hrp.iter()
+        .map(|c| c >> 5)
+        .chain(core::iter::once(0))
+        .chain(hrp.iter().map(|c| c & 31))
+        .chain(data.iter().copied())
+}
+
+/// Whether `hrp` is 1 to 83 characters in the printable ASCII range.
+fn is_valid_hrp(hrp: &[u8]) -> bool {
+    !hrp.is_empty() && hrp.len() <= 83 && hrp.iter().all(|&c| (33..=126).contains(&c))
+}
+
+/// Encodes the 5-bit `data` under the lowercase `hrp`.
+fn encode(hrp: &[u8], data: &[u8]) -> String {
+    let pm = polymod(checksum_input(hrp, data).chain([0u8; CHECKSUM_LEN].iter().copied()))
+        ^ BECH32M_CONST;
+
+    let mut out = String::with_capacity(hrp.len() + 1 + data.len() + CHECKSUM_LEN);
+    out.extend(hrp.iter().map(|&c| c as char));
+    out.push('1');
+    out.extend(data.iter().map(|&d| CHARSET[d as usize] as char));
+    out.extend((0..CHECKSUM_LEN).map(|i| CHARSET[((pm >> (5 * (5 - i))) & 31) as usize] as char));
+    out
+}
+
+/// Decodes a bech32m string.
+///
+/// Returns the lowercase human-readable part and the 5-bit data without
+/// the checksum, `Err(Error::ChecksumMismatch)` on a wrong checksum and
+/// `Err(Error::InvalidBech32)` on any other defect.
+fn decode(s: &str) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    let bytes = s.as_bytes();
+    if bytes.len() > MAX_LEN {
+        return Err(Error::InvalidBech32);
+    }
+    let has_lower = bytes.iter().any(|c| c.is_ascii_lowercase());
+    let has_upper = bytes.iter().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return Err(Error::InvalidBech32);
+    }
+    let lower = s.to_ascii_lowercase().into_bytes();
+
+    let separator = lower
+        .iter()
+        .rposition(|&c| c == b'1')
+        .ok_or(Error::InvalidBech32)?;
+    let (hrp, data) = (&lower[..separator], &lower[separator + 1..]);
+    if !is_valid_hrp(hrp) || data.len() < CHECKSUM_LEN {
+        return Err(Error::InvalidBech32);
+    }
+    let data = data
+        .iter()
+        .map(|&c| CHARSET.iter().position(|&a| a == c).map(|v| v as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or(Error::InvalidBech32)?;
+
+    if polymod(checksum_input(hrp, &data)) != BECH32M_CONST {
+        return Err(Error::ChecksumMismatch);
+    }
+    Ok((hrp.to_vec(), data[..data.len() - CHECKSUM_LEN].to_vec()))
+}
+
+/// Regroups bytes into 5-bit values, zero-padding the last one.
+fn to_base32(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for &b in bytes {
+        acc = (acc << 8) | b as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(((acc >> bits) & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        out.push(((acc << (5 - bits)) & 31) as u8);
+    }
+    out
+}
+
+/// Regroups 5-bit values into bytes.
+///
+/// Returns `None` if the padding is longer than 4 bits or not zero.
+fn from_base32(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() * 5 / 8);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for &d in data {
+        acc = (acc << 5) | d as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits >= 5 || (acc << (8 - bits)) & 0xff != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+impl PublicKey {
+    /// Encodes `PublicKey` as bech32m under the human-readable part `hrp`.
+    ///
+    /// Returns the lowercase bech32m string, or `Err(Error::InvalidBech32)`
+    /// if `hrp` is empty, has uppercase letters or characters outside
+    /// printable ASCII, or makes the string longer than 90 characters.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Error, PublicKey};
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let public_key: PublicKey =
+    ///         "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a".parse()?;
+    ///     let encoded = public_key.to_bech32("ed")?;
+    ///     assert!(encoded.starts_with("ed1"));
+    ///     assert_eq!(
+    ///         PublicKey::from_bech32("ed", &encoded)?.as_bytes(),
+    ///         public_key.as_bytes()
+    ///     );
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn to_bech32(&self, hrp: &str) -> Result<String, Error> {
+        let hrp = hrp.as_bytes();
+        let data = to_base32(&self.0);
+        if !is_valid_hrp(hrp)
+            || hrp.iter().any(|c| c.is_ascii_uppercase())
+            || hrp.len() + 1 + data.len() + CHECKSUM_LEN > MAX_LEN
+        {
+            return Err(Error::InvalidBech32);
+        }
+        Ok(encode(hrp, &data))
+    }
+
+    /// Constructs `PublicKey` from the bech32m string `s`, whose
+    /// human-readable part must be `expected_hrp` (in any case, if `s` is
+    /// in that case too).
+    ///
+    /// Returns `Err(Error::HrpMismatch)` for another human-readable part,
+    /// `Err(Error::ChecksumMismatch)` on a wrong checksum,
+    /// `Err(Error::InvalidPublicKey)` if the data isn't 32 bytes long and
+    /// `Err(Error::InvalidBech32)` if `s` is otherwise malformed, mixed
+    /// case included.
+    pub fn from_bech32(expected_hrp: &str, s: &str) -> Result<Self, Error> {
+        let (hrp, data) = decode(s)?;
+        if hrp != expected_hrp.to_ascii_lowercase().as_bytes() {
+            return Err(Error::HrpMismatch);
+        }
+        let bytes = from_base32(&data).ok_or(Error::InvalidBech32)?;
+        if bytes.len() != PublicKeySize {
+            return Err(Error::InvalidPublicKey);
+        }
+        PublicKey::from_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+    use super::*;
+    use ::bech32::{Bech32m, Hrp};
+    use proptest::prelude::*;
+
+    // BIP 350 "Valid Bech32m" strings.
+    const VALID: [&str; 7] = [
+        "A1LQFN3A",
+        "a1lqfn3a",
+        "an83characterlonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11sg7hg6",
+        "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+        "11llllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllludsr8",
+        "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+        "?1v759aa",
+    ];
+
+    #[test]
+    fn bech32m_bip350_valid_strings() {
+        for s in VALID.iter() {
+            let (hrp, data) = decode(s).unwrap();
+            // Re-encoding gives the lowercase form back.
+            assert_eq!(encode(&hrp, &data), s.to_ascii_lowercase());
+        }
+        // BIP 173 (bech32) strings fail the bech32m checksum.
+        assert_eq!(decode("A12UEL5L"), Err(Error::ChecksumMismatch));
+        assert_eq!(decode("a12uel5l"), Err(Error::ChecksumMismatch));
+    }
+
+    #[test]
+    fn bech32m_bip350_invalid_strings() {
+        let invalid = [
+            // HRP character out of range.
+            "\u{20}1xj0phk",
+            "\u{7F}1g6xzxy",
+            "\u{80}1vctc34",
+            // Overall max length exceeded.
+            "an84characterslonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11d6pts4",
+            // No separator character.
+            "qyrz8wqd2c9m",
+            // Empty HRP.
+            "1qyrz8wqd2c9m",
+            "16plkw9",
+            "1p2gdwpf",
+            // Invalid data character.
+            "y1b0jsk6g98",
+            "lt1igcx5c0",
+            // Too short checksum.
+            "in1muywd",
+            // Invalid character in checksum.
+            "mm1crxm3i",
+            "au1s5cgom",
+        ];
+        for s in invalid.iter() {
+            assert_eq!(decode(s), Err(Error::InvalidBech32), "{}", s);
+        }
+        // Checksum calculated with the uppercase form of the HRP.
+        assert_eq!(decode("M1VUXWEZ"), Err(Error::ChecksumMismatch));
+    }
+
+    // BIP 350 addresses with a 32-byte witness program: after the witness
+    // version character, the data part is a 32-byte payload as here.
+    #[test]
+    fn bech32m_bip350_32_byte_programs() {
+        for address in [
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+            "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+        ]
+        .iter()
+        {
+            let (hrp, data) = decode(address).unwrap();
+            assert_eq!(data[0], 1);
+            let program = from_base32(&data[1..]).unwrap();
+            let (_, _, expected) = ::bech32::segwit::decode(address).unwrap();
+            assert_eq!(program, expected);
+
+            // The same payload as a key, without the version character.
+            let public = PublicKey::from_bytes(&program).unwrap();
+            let hrp = core::str::from_utf8(&hrp).unwrap();
+            let encoded = public.to_bech32(hrp).unwrap();
+            assert_eq!(
+                PublicKey::from_bech32(hrp, &encoded).unwrap().as_bytes(),
+                public.as_bytes()
+            );
+            // With the version character, the payload is 33 bytes long.
+            assert_eq!(
+                PublicKey::from_bech32(hrp, address).err(),
+                Some(Error::InvalidPublicKey)
+            );
+        }
+    }
+
+    #[test]
+    fn bech32m_public_key_errors() {
+        let public = PublicKey::from_bytes(
+            &hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap(),
+        )
+        .unwrap();
+        let encoded = public.to_bech32("ed").unwrap();
+
+        // Either case, but not both.
+        assert!(PublicKey::from_bech32("ed", &encoded.to_uppercase()).is_ok());
+        assert!(PublicKey::from_bech32("ED", &encoded.to_uppercase()).is_ok());
+        let mixed = format!("ED{}", &encoded[2..]);
+        assert_eq!(
+            PublicKey::from_bech32("ed", &mixed).err(),
+            Some(Error::InvalidBech32)
+        );
+        assert_eq!(
+            PublicKey::from_bech32("ex", &encoded).err(),
+            Some(Error::HrpMismatch)
+        );
+
+        let mut flipped = encoded.clone().into_bytes();
+        flipped[10] = if flipped[10] == b'q' { b'p' } else { b'q' };
+        assert_eq!(
+            PublicKey::from_bech32("ed", core::str::from_utf8(&flipped).unwrap()).err(),
+            Some(Error::ChecksumMismatch)
+        );
+
+        // A 31-byte payload.
+        let short = encode(b"ed", &to_base32(&[7u8; 31]));
+        assert_eq!(
+            PublicKey::from_bech32("ed", &short).err(),
+            Some(Error::InvalidPublicKey)
+        );
+
+        for hrp in ["", "Ed", "e d", "\u{7f}", &"e".repeat(32)].iter() {
+            assert_eq!(public.to_bech32(hrp).err(), Some(Error::InvalidBech32));
+        }
+        assert!(public.to_bech32(&"e".repeat(31)).is_ok());
+    }
+
+    proptest! {
+        #[test]
+        fn bech32m_round_trip_matches_bech32_crate(
+            key in any::<[u8; 32]>(),
+            hrp in "[a-z0-9!-/:-@\\[-`{-~]{1,31}",
+        ) {
+            let public = PublicKey::from_bytes(&key).unwrap();
+            let encoded = public.to_bech32(&hrp).unwrap();
+            let reference = ::bech32::encode::<Bech32m>(Hrp::parse(&hrp).unwrap(), &key).unwrap();
+            prop_assert_eq!(&encoded, &reference);
+            let decoded = PublicKey::from_bech32(&hrp, &encoded).unwrap();
+            prop_assert_eq!(*decoded.as_bytes(), key);
+        }
+    }
+}
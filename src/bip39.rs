@@ -0,0 +1,137 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Keypairs from BIP39 mnemonics (seed phrases), with the English
+// wordlist.
+//
+// BIP39 only goes as far as a 64-byte seed: PBKDF2-HMAC-SHA512 of the
+// NFKD-normalized phrase, salted with "mnemonic" and the passphrase, over
+// 2048 rounds. The Ed25519 secret key is then the first 32 bytes of that
+// seed, as `solana-keygen` derives its keypairs. Other wallets start from
+// the same seed but derive keys along a path instead (SLIP-0010, as
+// Ledger and Phantom do, or Cardano's BIP32-Ed25519), and Polkadot
+// derives from the entropy rather than the seed, so the same phrase gives
+// different keys there.
+
+use crate::errors::*;
+use crate::keypair::*;
+use crate::secret::*;
+
+use ::bip39::{Language, Mnemonic};
+
+/// Maps the errors of the `bip39` crate.
+fn mnemonic_error(error: ::bip39::Error, phrase: &str) -> Error {
+    match error {
+        ::bip39::Error::UnknownWord(index) => Error::InvalidMnemonicWord { index },
+        ::bip39::Error::InvalidChecksum => Error::InvalidMnemonicChecksum,
+        // With only the English wordlist, every other failure is the
+        // word count.
+        _ => Error::InvalidMnemonicLength {
+            words: phrase.split_whitespace().count(),
+        },
+    }
+}
+
+impl Keypair {
+    /// Constructs `Keypair` from a BIP39 mnemonic and its passphrase.
+    ///
+    /// The secret key is the first 32 bytes of the BIP39 seed, as in
+    /// `solana-keygen`; wallets deriving keys along a path (SLIP-0010)
+    /// won't agree. See the module source for the convention.
+    ///
+    /// Returns `Err(Error::InvalidMnemonicWord)` if a word isn't in the
+    /// English wordlist, `Err(Error::InvalidMnemonicChecksum)` if the
+    /// checksum doesn't match, and `Err(Error::InvalidMnemonicLength)` if
+    /// the phrase doesn't have 12, 15, 18, 21 or 24 words.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let phrase = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+    ///     let keypair = Keypair::from_mnemonic(phrase, "TREZOR").unwrap();
+    ///     assert_eq!(
+    ///         keypair.public().to_string(),
+    ///         "451bde832454ba73e6e0de313fcf5d1565ec51080edc73bb19287b8e0ab2122b"
+    ///     );
+    /// }
+    /// ```
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Keypair, Error> {
+        let mnemonic = Mnemonic::parse_in(Language::English, phrase)
+            .map_err(|error| mnemonic_error(error, phrase))?;
+        let seed = zeroize::Zeroizing::new(mnemonic.to_seed(passphrase));
+        Ok(Keypair::from_secret(SecretKey::from_bytes(&seed[..32])?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+
+    // tests/bip39.input holds the English vectors of the Trezor reference
+    // implementation (python-mnemonic), all with the passphrase "TREZOR":
+    // entropy, mnemonic, seed and the public key of the first 32 bytes of
+    // the seed. The public keys were computed with pyca/cryptography, and
+    // pin the derivation: they must not change across releases.
+    #[test]
+    fn from_mnemonic_trezor_vectors() {
+        let file = File::open("./tests/bip39.input").expect("Where are the test vectors? :(");
+        let mut count = 0;
+        for line in BufReader::new(file).lines() {
+            let l = line.unwrap();
+            let slices: Vec<&str> = l.split(':').collect();
+            let seed = hex::decode(slices[2]).unwrap();
+
+            let keypair = Keypair::from_mnemonic(slices[1], "TREZOR").unwrap();
+            assert_eq!(keypair.secret().as_bytes()[..], seed[..32]);
+            assert_eq!(keypair.public().to_string(), slices[3]);
+            // The passphrase is part of the seed.
+            let other = Keypair::from_mnemonic(slices[1], "").unwrap();
+            assert!(other.public() != keypair.public());
+            count += 1;
+        }
+        assert_eq!(count, 24);
+    }
+
+    #[test]
+    fn from_mnemonic_normalizes_the_phrase() {
+        let phrase = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let keypair = Keypair::from_mnemonic(phrase, "TREZOR").unwrap();
+        let spaced = format!("  {}\n", phrase.replace(' ', "  "));
+        assert!(Keypair::from_mnemonic(&spaced, "TREZOR").unwrap().public() == keypair.public());
+    }
+
+    #[test]
+    fn from_mnemonic_rejects_bad_phrases() {
+        let phrase = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let error = |phrase: &str| Keypair::from_mnemonic(phrase, "").err().unwrap();
+
+        // "yellow" is the only valid last word of the eleven before it.
+        assert_eq!(
+            error(&phrase.replace("yellow", "zoo")),
+            Error::InvalidMnemonicChecksum
+        );
+        assert_eq!(
+            error(&phrase.replace("sausage", "sausages")),
+            Error::InvalidMnemonicWord { index: 5 }
+        );
+        assert_eq!(
+            error(&phrase.replace(" yellow", "")),
+            Error::InvalidMnemonicLength { words: 11 }
+        );
+        assert_eq!(error(""), Error::InvalidMnemonicLength { words: 0 });
+        // Upper case words aren't in the wordlist.
+        assert_eq!(
+            error(&phrase.to_uppercase()),
+            Error::InvalidMnemonicWord { index: 0 }
+        );
+    }
+}
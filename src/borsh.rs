@@ -0,0 +1,155 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Borsh serialization: as in `codec`, every type is its raw fixed-size
+// bytes, with no length prefix, which is also how borsh lays out a
+// `[u8; 32]` or `[u8; 64]` field. Deserialization reads exactly that many
+// bytes and goes through `from_bytes`.
+//
+// `SecretKey` is only serializable with the `borsh-secret` feature, so
+// that deriving borsh on a struct holding one is a deliberate choice.
+
+use crate::constants::*;
+use crate::errors::*;
+use crate::public::*;
+#[cfg(all(feature = "borsh-secret", not(feature = "verify-only")))]
+use crate::secret::*;
+use crate::signature::*;
+
+use ::borsh::io::{self, Read, Write};
+use ::borsh::{BorshDeserialize, BorshSerialize};
+
+/// Wraps a validation failure as `io::ErrorKind::InvalidData`, the kind
+/// borsh reports malformed input with.
+fn invalid_data(error: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Writes the 32 bytes of `PublicKey`.
+impl BorshSerialize for PublicKey {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+}
+
+/// Reads 32 bytes into `PublicKey` as `from_bytes` does.
+impl BorshDeserialize for PublicKey {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0u8; PublicKeySize];
+        reader.read_exact(&mut bytes)?;
+        PublicKey::from_bytes(&bytes).map_err(invalid_data)
+    }
+}
+
+/// Writes the 64 bytes of `Signature`.
+impl BorshSerialize for Signature {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.0)
+    }
+}
+
+/// Reads 64 bytes into `Signature` as `from_bytes` does.
+impl BorshDeserialize for Signature {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0u8; SignatureSize];
+        reader.read_exact(&mut bytes)?;
+        Signature::from_bytes(&bytes).map_err(invalid_data)
+    }
+}
+
+/// Writes the 32-byte seed of `SecretKey`.
+#[cfg(all(feature = "borsh-secret", not(feature = "verify-only")))]
+impl BorshSerialize for SecretKey {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.as_bytes())
+    }
+}
+
+/// Reads 32 bytes into `SecretKey` as `from_bytes` does, through a buffer
+/// wiped on drop.
+#[cfg(all(feature = "borsh-secret", not(feature = "verify-only")))]
+impl BorshDeserialize for SecretKey {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = zeroize::Zeroizing::new([0u8; SecretKeySize]);
+        reader.read_exact(&mut bytes[..])?;
+        SecretKey::from_bytes(&bytes[..]).map_err(invalid_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // RFC 8032, TEST 1.
+    const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    #[test]
+    fn borsh_round_trip() {
+        let public: PublicKey = PUBLIC_KEY.parse().unwrap();
+        let signature = Signature::from_hex(SIGNATURE).unwrap();
+
+        let bytes = ::borsh::to_vec(&public).unwrap();
+        assert_eq!(hex::encode(&bytes), PUBLIC_KEY);
+        assert!(::borsh::from_slice::<PublicKey>(&bytes).unwrap() == public);
+        let bytes = ::borsh::to_vec(&signature).unwrap();
+        assert_eq!(hex::encode(&bytes), SIGNATURE);
+        assert_eq!(::borsh::from_slice::<Signature>(&bytes).unwrap(), signature);
+    }
+
+    // Laid out as borsh lays out the fixed-size arrays, in any language:
+    // no length prefix, so a field of either type can replace a
+    // `[u8; 32]` or `[u8; 64]` field in an existing schema.
+    #[test]
+    fn borsh_matches_fixed_size_arrays() {
+        let public: PublicKey = PUBLIC_KEY.parse().unwrap();
+        let signature = Signature::from_hex(SIGNATURE).unwrap();
+
+        let ours = ::borsh::to_vec(&(7u32, public, signature, 9u8)).unwrap();
+        let arrays =
+            ::borsh::to_vec(&(7u32, public.to_bytes(), signature.to_bytes(), 9u8)).unwrap();
+        assert_eq!(ours, arrays);
+        assert_eq!(ours.len(), 4 + 32 + 64 + 1);
+        let (n, p, s, m) = ::borsh::from_slice::<(u32, PublicKey, Signature, u8)>(&ours).unwrap();
+        assert!((n, m) == (7, 9) && p == public && s == signature);
+    }
+
+    #[test]
+    fn borsh_consumes_exactly_the_length() {
+        let bytes = hex::decode(SIGNATURE).unwrap();
+        for len in [0, 31, 63].iter() {
+            let error = ::borsh::from_slice::<Signature>(&bytes[..*len]).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        }
+        assert!(::borsh::from_slice::<PublicKey>(&bytes[..32]).is_ok());
+        // `from_slice` rejects what is left over.
+        assert_eq!(
+            ::borsh::from_slice::<PublicKey>(&bytes[..33])
+                .err()
+                .map(|e| e.kind()),
+            Some(io::ErrorKind::InvalidData)
+        );
+
+        // Back to back, each value takes its own bytes.
+        let mut input = &bytes[..];
+        let first = PublicKey::deserialize(&mut input).unwrap();
+        let second = PublicKey::deserialize(&mut input).unwrap();
+        assert!(input.is_empty());
+        assert!(first.as_bytes()[..] == bytes[..32] && second.as_bytes()[..] == bytes[32..]);
+    }
+
+    #[cfg(all(feature = "borsh-secret", not(feature = "verify-only")))]
+    #[test]
+    fn borsh_secret_key() {
+        let seed = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+        let secret = SecretKey::from_hex(seed).unwrap();
+        let bytes = ::borsh::to_vec(&secret).unwrap();
+        assert_eq!(hex::encode(&bytes), seed);
+        let decoded = ::borsh::from_slice::<SecretKey>(&bytes).unwrap();
+        assert!(decoded == secret);
+        assert!(decoded.public_key() == PUBLIC_KEY.parse::<PublicKey>().unwrap());
+        assert!(::borsh::from_slice::<SecretKey>(&bytes[..31]).is_err());
+    }
+}
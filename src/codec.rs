@@ -0,0 +1,285 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Binary codec without serde: every type is its raw fixed-size bytes, with
+// no length or type prefix, exactly as `as_bytes`/`from_bytes` lay it out.
+// There is one wire format per type whichever API writes it. Nothing here
+// allocates.
+//
+// `decode_from` reads a prefix of its input and reports how many bytes it
+// consumed, so values can be read back to back from one buffer; a caller
+// that expects nothing after the value checks that all of the input was
+// consumed.
+
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+/// Copies `bytes` to the start of `out`.
+///
+/// Returns `Ok(bytes.len())` and `Err(Error::BufferTooSmall)` if `out` is
+/// shorter than `bytes`.
+fn encode_into(bytes: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+    if out.len() < bytes.len() {
+        return Err(Error::BufferTooSmall);
+    }
+    out[..bytes.len()].copy_from_slice(bytes);
+    Ok(bytes.len())
+}
+
+/// Splits the first `len` bytes off `input`.
+///
+/// Returns `Err(Error::Truncated)` at the end of `input` if it is shorter
+/// than `len`.
+fn decode_from(input: &[u8], len: usize) -> Result<&[u8], Error> {
+    if input.len() < len {
+        return Err(Error::Truncated {
+            offset: input.len(),
+        });
+    }
+    Ok(&input[..len])
+}
+
+impl PublicKey {
+    /// Returns the length of the encoding: 32 bytes.
+    pub fn encoded_len(&self) -> usize {
+        PublicKeySize
+    }
+
+    /// Writes the 32 bytes of `PublicKey` to the start of `out`.
+    ///
+    /// Returns the number of bytes written, or
+    /// `Err(Error::BufferTooSmall)` if `out` is shorter than 32 bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{PublicKey, Signature};
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([7u8; 32]);
+    ///     let signature = Signature::from_array([9u8; 64]);
+    ///
+    ///     let mut buffer = [0u8; 96];
+    ///     let written = public_key.encode_into(&mut buffer).unwrap();
+    ///     signature.encode_into(&mut buffer[written..]).unwrap();
+    ///
+    ///     let (decoded_key, read) = PublicKey::decode_from(&buffer).unwrap();
+    ///     let (decoded_signature, _) = Signature::decode_from(&buffer[read..]).unwrap();
+    ///     assert_eq!(decoded_key.as_bytes(), public_key.as_bytes());
+    ///     assert_eq!(decoded_signature, signature);
+    /// }
+    /// ```
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize, Error> {
+        encode_into(&self.0, out)
+    }
+
+    /// Reads `PublicKey` from the first 32 bytes of `input`.
+    ///
+    /// Returns the key and the number of bytes consumed, or
+    /// `Err(Error::Truncated)` if `input` is shorter than 32 bytes.
+    pub fn decode_from(input: &[u8]) -> Result<(Self, usize), Error> {
+        let bytes = decode_from(input, PublicKeySize)?;
+        Ok((PublicKey::from_bytes(bytes)?, PublicKeySize))
+    }
+}
+
+impl Signature {
+    /// Returns the length of the encoding: 64 bytes.
+    pub fn encoded_len(&self) -> usize {
+        SignatureSize
+    }
+
+    /// Writes the 64 bytes of `Signature` to the start of `out`.
+    ///
+    /// Returns the number of bytes written, or
+    /// `Err(Error::BufferTooSmall)` if `out` is shorter than 64 bytes.
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize, Error> {
+        encode_into(&self.0, out)
+    }
+
+    /// Reads `Signature` from the first 64 bytes of `input`.
+    ///
+    /// Returns the signature and the number of bytes consumed, or
+    /// `Err(Error::Truncated)` if `input` is shorter than 64 bytes.
+    pub fn decode_from(input: &[u8]) -> Result<(Self, usize), Error> {
+        let bytes = decode_from(input, SignatureSize)?;
+        Ok((Signature::from_bytes(bytes)?, SignatureSize))
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl SecretKey {
+    /// Returns the length of the encoding: 32 bytes.
+    pub fn encoded_len(&self) -> usize {
+        SecretKeySize
+    }
+
+    /// Writes the 32 secret bytes of `SecretKey` to the start of `out`.
+    ///
+    /// This exports secret material: `out` holds the key afterwards and
+    /// is the caller's to protect and wipe.
+    ///
+    /// Returns the number of bytes written, or
+    /// `Err(Error::BufferTooSmall)` if `out` is shorter than 32 bytes.
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize, Error> {
+        let written = encode_into(&self.0, out)?;
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::SecretKey);
+        Ok(written)
+    }
+
+    /// Reads `SecretKey` from the first 32 bytes of `input`.
+    ///
+    /// Returns the key and the number of bytes consumed, or
+    /// `Err(Error::Truncated)` if `input` is shorter than 32 bytes.
+    pub fn decode_from(input: &[u8]) -> Result<(Self, usize), Error> {
+        let bytes = decode_from(input, SecretKeySize)?;
+        Ok((SecretKey::from_bytes(bytes)?, SecretKeySize))
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl Keypair {
+    /// Returns the length of the encoding: 64 bytes.
+    pub fn encoded_len(&self) -> usize {
+        KeypairSize
+    }
+
+    /// Writes the 64 bytes of `Keypair`, the secret key followed by the
+    /// public key, to the start of `out`.
+    ///
+    /// This exports secret material: `out` holds the secret key
+    /// afterwards and is the caller's to protect and wipe.
+    ///
+    /// Returns the number of bytes written, or
+    /// `Err(Error::BufferTooSmall)` if `out` is shorter than 64 bytes.
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize, Error> {
+        if out.len() < KeypairSize {
+            return Err(Error::BufferTooSmall);
+        }
+        encode_into(&self.secret.0, out)?;
+        encode_into(&self.public.0, &mut out[SecretKeySize..])?;
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::Keypair);
+        Ok(KeypairSize)
+    }
+
+    /// Reads `Keypair` from the first 64 bytes of `input`, as
+    /// `Keypair::from_bytes` does.
+    ///
+    /// Returns the keypair and the number of bytes consumed, or
+    /// `Err(Error::Truncated)` if `input` is shorter than 64 bytes.
+    pub fn decode_from(input: &[u8]) -> Result<(Self, usize), Error> {
+        let bytes = decode_from(input, KeypairSize)?;
+        Ok((Keypair::from_bytes(bytes)?, KeypairSize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The codec and the byte methods agree on every input length around
+    // the encoded one, including the errors.
+    #[test]
+    fn codec_matches_byte_methods() {
+        let data: Vec<u8> = (0..100u8).collect();
+
+        for len in 0..data.len() {
+            let input = &data[..len];
+
+            let decoded = PublicKey::decode_from(input);
+            if len < PublicKeySize {
+                assert!(matches!(decoded, Err(Error::Truncated { offset }) if offset == len));
+            } else {
+                let (public, read) = decoded.unwrap();
+                assert_eq!(read, public.encoded_len());
+                let from_bytes = PublicKey::from_bytes(&input[..read]).unwrap();
+                assert_eq!(public.as_bytes(), from_bytes.as_bytes());
+                let mut out = [0u8; 100];
+                assert_eq!(public.encode_into(&mut out), Ok(read));
+                assert_eq!(out[..read], public.as_bytes()[..]);
+            }
+
+            match Signature::decode_from(input) {
+                Err(error) => {
+                    assert!(len < SignatureSize);
+                    assert_eq!(error, Error::Truncated { offset: len });
+                }
+                Ok((sig, read)) => {
+                    assert_eq!(read, sig.encoded_len());
+                    assert_eq!(Signature::from_bytes(&input[..read]), Ok(sig));
+                    let mut out = [0u8; 100];
+                    assert_eq!(sig.encode_into(&mut out), Ok(read));
+                    assert!(out[..read] == sig.as_bytes()[..]);
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn codec_matches_byte_methods_for_secrets() {
+        let keypair = Keypair::from_secret(SecretKey::from_array([7; 32]));
+        let mut stream = [0u8; 97];
+        let written = keypair.encode_into(&mut stream).unwrap();
+        assert_eq!(written, keypair.encoded_len());
+        assert!(stream[..written] == keypair.to_bytes()[..]);
+        keypair.secret.encode_into(&mut stream[written..]).unwrap();
+        assert!(stream[written..written + SecretKeySize] == keypair.secret.as_bytes()[..]);
+
+        let (decoded, read) = Keypair::decode_from(&stream).unwrap();
+        assert_eq!(read, KeypairSize);
+        assert!(
+            decoded.to_bytes()[..] == Keypair::from_bytes(&stream[..read]).unwrap().to_bytes()[..]
+        );
+        let (secret, read) = SecretKey::decode_from(&stream[read..]).unwrap();
+        assert_eq!(read, secret.encoded_len());
+        assert!(secret.as_bytes() == keypair.secret.as_bytes());
+
+        assert!(matches!(
+            Keypair::decode_from(&stream[..63]),
+            Err(Error::Truncated { offset: 63 })
+        ));
+        assert!(matches!(
+            SecretKey::decode_from(&stream[..31]),
+            Err(Error::Truncated { offset: 31 })
+        ));
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn codec_reports_small_buffers() {
+        let keypair = Keypair::from_secret(SecretKey::from_array([7; 32]));
+        let sig = keypair.sign(b"codec");
+        let mut out = [0u8; 64];
+        assert_eq!(
+            keypair.encode_into(&mut out[..63]),
+            Err(Error::BufferTooSmall)
+        );
+        assert_eq!(
+            keypair.secret.encode_into(&mut out[..31]),
+            Err(Error::BufferTooSmall)
+        );
+        assert_eq!(
+            keypair.public.encode_into(&mut out[..31]),
+            Err(Error::BufferTooSmall)
+        );
+        assert_eq!(sig.encode_into(&mut out[..63]), Err(Error::BufferTooSmall));
+        assert_eq!(sig.encode_into(&mut out), Ok(64));
+        // Nothing was written on error.
+        let mut untouched = [0u8; 63];
+        assert!(sig.encode_into(&mut untouched).is_err());
+        assert!(untouched.iter().all(|&b| b == 0));
+    }
+}
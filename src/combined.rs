@@ -0,0 +1,208 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// libsodium's combined mode (`crypto_sign` / `crypto_sign_open`): the
+// 64-byte signature followed by the message. Opening returns the message
+// only once the signature verifies.
+
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::public::*;
+use crate::signature::*;
+
+/// Splits a combined-mode blob into its signature and message.
+///
+/// Returns `Err(Error::InvalidSignatureLength)` if `signed` is shorter
+/// than a signature.
+fn split(signed: &[u8]) -> Result<(Signature, &[u8]), Error> {
+    if signed.len() < SignatureSize {
+        return Err(Error::InvalidSignatureLength {
+            expected: SignatureSize,
+            got: signed.len(),
+        });
+    }
+    let (signature, message) = signed.split_at(SignatureSize);
+    Ok((Signature::from_bytes(signature)?, message))
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl Keypair {
+    /// Signs `message` in libsodium's combined mode, as `crypto_sign`
+    /// does.
+    ///
+    /// Returns the signature followed by the message.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let signed = keypair.sign_combined(b"message");
+    ///     assert_eq!(keypair.public().open_combined(&signed).unwrap(), b"message");
+    /// }
+    /// ```
+    pub fn sign_combined(&self, message: &[u8]) -> Vec<u8> {
+        let mut signed = Vec::with_capacity(SignatureSize + message.len());
+        signed.extend_from_slice(&self.sign(message).0);
+        signed.extend_from_slice(message);
+        signed
+    }
+
+    /// Signs `message` in libsodium's combined mode into `out`, without
+    /// allocating.
+    ///
+    /// Returns the length written, `SignatureSize + message.len()`, or
+    /// `Err(Error::BufferTooSmall)`, with nothing written, if it doesn't
+    /// fit.
+    pub fn sign_combined_into(&self, message: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+        let len = SignatureSize + message.len();
+        if out.len() < len {
+            return Err(Error::BufferTooSmall);
+        }
+        out[..SignatureSize].copy_from_slice(&self.sign(message).0);
+        out[SignatureSize..len].copy_from_slice(message);
+        Ok(len)
+    }
+}
+
+impl PublicKey {
+    /// Verifies a libsodium combined-mode blob, as `crypto_sign_open`
+    /// does.
+    ///
+    /// Returns the message if the signature verifies,
+    /// `Err(Error::InvalidSignatureLength)` if `signed` is shorter than a
+    /// signature and `Err` otherwise.
+    pub fn open_combined(&self, signed: &[u8]) -> Result<Vec<u8>, Error> {
+        self.open_combined_slice(signed)
+            .map(|message| message.to_vec())
+    }
+
+    /// Verifies a libsodium combined-mode blob without copying.
+    ///
+    /// Returns the message as a slice of `signed` if the signature
+    /// verifies, `Err(Error::InvalidSignatureLength)` if `signed` is
+    /// shorter than a signature and `Err` otherwise.
+    pub fn open_combined_slice<'a>(&self, signed: &'a [u8]) -> Result<&'a [u8], Error> {
+        let (signature, message) = split(signed)?;
+        self.verify(message, &signature)?;
+        Ok(message)
+    }
+
+    /// Verifies a libsodium combined-mode blob and copies the message
+    /// into `out`, without allocating.
+    ///
+    /// Returns the message length if the signature verifies,
+    /// `Err(Error::BufferTooSmall)` if the message doesn't fit in `out`
+    /// and `Err` otherwise. Nothing is written unless the signature
+    /// verifies.
+    pub fn open_combined_into(&self, signed: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+        let message = self.open_combined_slice(signed)?;
+        let out = out.get_mut(..message.len()).ok_or(Error::BufferTooSmall)?;
+        out.copy_from_slice(message);
+        Ok(message.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // crypto_sign of "libsodium combined mode" by libsodium 1.0.18, with
+    // the RFC 8032 test 2 seed.
+    #[cfg(not(feature = "verify-only"))]
+    const SEED: &str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+    const PUBLIC_KEY: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const SIGNED: &str = "880caa5400c39a9398e75749dba824874d9d3236bbd2f458ad6351767931b1eb\
+                          ece028ea289efbef6ab0d55d98bb853b978acd12e3387658bdb19af406b44c02\
+                          6c6962736f6469756d20636f6d62696e6564206d6f6465";
+    const MESSAGE: &[u8] = b"libsodium combined mode";
+
+    fn public() -> PublicKey {
+        PublicKey::from_bytes(&hex::decode(PUBLIC_KEY).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn libsodium_combined_blob_opens() {
+        let signed = hex::decode(SIGNED).unwrap();
+        assert_eq!(public().open_combined(&signed).unwrap(), MESSAGE);
+        assert_eq!(public().open_combined_slice(&signed).unwrap(), MESSAGE);
+        let mut out = [0u8; 64];
+        assert_eq!(
+            public().open_combined_into(&signed, &mut out),
+            Ok(MESSAGE.len())
+        );
+        assert_eq!(&out[..MESSAGE.len()], MESSAGE);
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn libsodium_combined_blob_signs() {
+        use crate::secret::*;
+
+        let secret = SecretKey::from_bytes(&hex::decode(SEED).unwrap()).unwrap();
+        let keypair = Keypair::from_secret(secret);
+        assert_eq!(hex::encode(keypair.sign_combined(MESSAGE)), SIGNED);
+
+        let mut out = [0xaau8; 100];
+        assert_eq!(
+            keypair.sign_combined_into(MESSAGE, &mut out),
+            Ok(SignatureSize + MESSAGE.len())
+        );
+        assert_eq!(hex::encode(&out[..SignatureSize + MESSAGE.len()]), SIGNED);
+        assert!(out[SignatureSize + MESSAGE.len()..]
+            .iter()
+            .all(|&b| b == 0xaa));
+
+        let mut short = [0xaau8; 86];
+        assert_eq!(
+            keypair.sign_combined_into(MESSAGE, &mut short),
+            Err(Error::BufferTooSmall)
+        );
+        assert!(short.iter().all(|&b| b == 0xaa));
+
+        // The empty message is the signature alone.
+        let signed = keypair.sign_combined(b"");
+        assert_eq!(signed.len(), SignatureSize);
+        assert_eq!(keypair.public().open_combined(&signed).unwrap(), b"");
+    }
+
+    #[test]
+    fn open_combined_rejects() {
+        let signed = hex::decode(SIGNED).unwrap();
+        for len in [0, 1, SignatureSize - 1].iter() {
+            assert_eq!(
+                public().open_combined(&signed[..*len]).err(),
+                Some(Error::InvalidSignatureLength {
+                    expected: SignatureSize,
+                    got: *len
+                })
+            );
+        }
+
+        // A flipped bit in the signature or the message.
+        for i in [0, SignatureSize, signed.len() - 1].iter() {
+            let mut altered = signed.clone();
+            altered[*i] ^= 1;
+            assert!(public().open_combined(&altered).is_err());
+            let mut out = [0xaau8; 64];
+            assert!(public().open_combined_into(&altered, &mut out).is_err());
+            assert!(out.iter().all(|&b| b == 0xaa));
+        }
+        // The message alone, without its signature.
+        assert!(public().open_combined(&signed[SignatureSize..]).is_err());
+
+        let mut out = [0u8; 22];
+        assert_eq!(
+            public().open_combined_into(&signed, &mut out),
+            Err(Error::BufferTooSmall)
+        );
+    }
+}
@@ -9,10 +9,40 @@
 pub(crate) const PublicKeySize: usize = 32;
 
 // Length of the Ed25519 private key: 32 bytes.
+#[cfg_attr(feature = "verify-only", allow(dead_code))]
 pub(crate) const SecretKeySize: usize = 32;
 
 // Length of the Ed25519 signature: 64 bytes.
 pub(crate) const SignatureSize: usize = 64;
 
-// Length of the Ed25519 signature: 64 bytes.
+// Length of the unpadded base64 of a signature: 86 characters.
+pub(crate) const Base64SignatureSize: usize = 86;
+
+// Length of the Ed25519 keypair: 64 bytes.
+#[cfg_attr(feature = "verify-only", allow(dead_code))]
 pub(crate) const KeypairSize: usize = 64;
+
+// Length of a libsodium secret key: the seed followed by the public key.
+#[cfg_attr(feature = "verify-only", allow(dead_code))]
+pub(crate) const LibsodiumSecretKeySize: usize = SecretKeySize + PublicKeySize;
+
+/// Length of an Ed25519 public key, in bytes.
+pub const PUBLIC_KEY_LENGTH: usize = PublicKeySize;
+
+/// Length of an Ed25519 secret key (the seed), in bytes.
+pub const SECRET_KEY_LENGTH: usize = SecretKeySize;
+
+/// Length of an Ed25519 signature, in bytes.
+pub const SIGNATURE_LENGTH: usize = SignatureSize;
+
+/// Length of a `Keypair` as `Keypair::to_bytes` encodes it: the secret
+/// key followed by the public key.
+pub const KEYPAIR_LENGTH: usize = KeypairSize;
+
+/// Largest length a framing format accepts for a single message or
+/// field: 2^31 - 1 bytes.
+///
+/// This is the largest allocation Rust allows on 32-bit targets
+/// (`isize::MAX`), so every length accepted on one target is accepted on
+/// all of them, and a decoded length always fits in `usize`.
+pub const MAX_MESSAGE_LEN: u64 = (1 << 31) - 1;
@@ -0,0 +1,226 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Ed25519ctx (RFC 8032, section 5.1): Ed25519 over the message in the
+// domain dom2(0, context), so that signatures made by one key for two
+// protocols can't be replayed from one to the other. Plain `sign` and
+// `verify` stay pure Ed25519: no dom2 prefix.
+
+use crate::dom2::*;
+use crate::errors::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+use sha2::digest::Input;
+
+/// The dom2 of Ed25519ctx with `context`.
+///
+/// Returns `Err(Error::EmptyContext)` if `context` is empty, as RFC 8032
+/// recommends, and `Err(Error::ContextTooLong)` if it is longer than 255
+/// bytes.
+fn ctx_domain(context: &[u8]) -> Result<Option<(u8, &[u8])>, Error> {
+    if context.is_empty() {
+        return Err(Error::EmptyContext);
+    }
+    if context.len() > MAX_CONTEXT_LEN {
+        return Err(Error::ContextTooLong { len: context.len() });
+    }
+    Ok(Some((0, context)))
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl SecretKey {
+    /// Signs `message` with Ed25519ctx, in the domain of `context`.
+    ///
+    /// The signature only verifies with `verify_with_context` and the
+    /// same context, never with `verify`.
+    ///
+    /// Returns `Ok(Signature)`, `Err(Error::PublicKeyMismatch)` if
+    /// `public` isn't the public key of `SecretKey`,
+    /// `Err(Error::EmptyContext)` if `context` is empty and
+    /// `Err(Error::ContextTooLong)` if it is longer than 255 bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let (secret, public) = (keypair.secret(), keypair.public());
+    ///     let signature = secret.sign_with_context(public, b"transfer 10", b"payments v1").unwrap();
+    ///     assert!(public.verify_with_context(b"transfer 10", b"payments v1", &signature).is_ok());
+    ///     assert!(public.verify_with_context(b"transfer 10", b"audit v1", &signature).is_err());
+    /// }
+    /// ```
+    pub fn sign_with_context(
+        &self,
+        public: &PublicKey,
+        message: &[u8],
+        context: &[u8],
+    ) -> Result<Signature, Error> {
+        let domain = ctx_domain(context)?;
+        if PublicKey::from_secret(self) != *public {
+            return Err(Error::PublicKeyMismatch);
+        }
+        let signature = self.sign_absorbing(public, domain, |hash| hash.input(message));
+        #[cfg(feature = "observer")]
+        observer::signed(public, |digest| digest.update(message));
+        Ok(signature)
+    }
+}
+
+impl PublicKey {
+    /// Verifies an Ed25519ctx signature of `message` in the domain of
+    /// `context`.
+    ///
+    /// Returns `Ok(())` if the signature is valid,
+    /// `Err(Error::EmptyContext)` if `context` is empty,
+    /// `Err(Error::ContextTooLong)` if it is longer than 255 bytes and
+    /// `Err` otherwise.
+    pub fn verify_with_context(
+        &self,
+        message: &[u8],
+        context: &[u8],
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        let domain = ctx_domain(context)?;
+        self.verify_absorbing(signature, domain, |hash| hash.input(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // RFC 8032, section 7.2: the foo, bar, foo2 and foo3 tests, as
+    // (secret key, public key, message, context, signature).
+    const VECTORS: [(&str, &str, &str, &str, &str); 4] = [
+        (
+            "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+            "f726936d19c800494e3fdaff20b276a8",
+            "666f6f",
+            "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a\
+             8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d",
+        ),
+        (
+            "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+            "f726936d19c800494e3fdaff20b276a8",
+            "626172",
+            "fc60d5872fc46b3aa69f8b5b4351d5808f92bcc044606db097abab6dbcb1aee3\
+             216c48e8b3b66431b5b186d1d28f8ee15a5ca2df6668346291c2043d4eb3e90d",
+        ),
+        (
+            "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6",
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292",
+            "508e9e6882b979fea900f62adceaca35",
+            "666f6f",
+            "8b70c1cc8310e1de20ac53ce28ae6e7207f33c3295e03bb5c0732a1d20dc6490\
+             8922a8b052cf99b7c4fe107a5abb5b2c4085ae75890d02df26269d8945f84b0b",
+        ),
+        (
+            "ab9c2853ce297ddab85c993b3ae14bcad39b2c682beabc27d6d4eb20711d6560",
+            "0f1d1274943b91415889152e893d80e93275a1fc0b65fd71b4b0dda10ad7d772",
+            "f726936d19c800494e3fdaff20b276a8",
+            "666f6f",
+            "21655b5f1aa965996b3f97b3c849eafba922a0a62992f73b3d1b73106a84ad85\
+             e9b86a7b6005ea868337ff2d20a7f5fbd4cd10b0be49a68da2b2e0dc0ad8960f",
+        ),
+    ];
+
+    fn public(hex: &str) -> PublicKey {
+        PublicKey::from_bytes(&hex::decode(hex).unwrap()).unwrap()
+    }
+
+    fn signature(hex: &str) -> Signature {
+        Signature::from_bytes(&hex::decode(hex).unwrap()).unwrap()
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn sign_with_context_rfc8032_vectors() {
+        for (secret, public_key, message, context, expected) in VECTORS.iter() {
+            let secret = SecretKey::from_bytes(&hex::decode(secret).unwrap()).unwrap();
+            let signature = secret
+                .sign_with_context(
+                    &public(public_key),
+                    &hex::decode(message).unwrap(),
+                    &hex::decode(context).unwrap(),
+                )
+                .unwrap();
+            assert_eq!(hex::encode(signature.0), *expected);
+        }
+    }
+
+    #[test]
+    fn verify_with_context_rfc8032_vectors() {
+        for (_, public_key, message, context, expected) in VECTORS.iter() {
+            let message = hex::decode(message).unwrap();
+            let signature = signature(expected);
+            let public = public(public_key);
+            assert!(public
+                .verify_with_context(&message, &hex::decode(context).unwrap(), &signature)
+                .is_ok());
+
+            // Under another context, or none.
+            for other in [&b"fo"[..], b"foo\x00", b"baz"].iter() {
+                assert_eq!(
+                    public.verify_with_context(&message, other, &signature),
+                    Err(Error::SignatureMismatch)
+                );
+            }
+            assert!(public.verify(&message, &signature).is_err());
+        }
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn context_bounds() {
+        let secret = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = secret.public_key();
+        assert_eq!(
+            secret.sign_with_context(&public, b"m", b""),
+            Err(Error::EmptyContext)
+        );
+        assert_eq!(
+            public.verify_with_context(b"m", b"", &secret.sign_detached(b"m")),
+            Err(Error::EmptyContext)
+        );
+
+        let longest = [0x5au8; 255];
+        let signature = secret.sign_with_context(&public, b"m", &longest).unwrap();
+        assert!(public
+            .verify_with_context(b"m", &longest, &signature)
+            .is_ok());
+        let too_long = [0x5au8; 256];
+        assert_eq!(
+            secret.sign_with_context(&public, b"m", &too_long),
+            Err(Error::ContextTooLong { len: 256 })
+        );
+        assert_eq!(
+            public.verify_with_context(b"m", &too_long, &signature),
+            Err(Error::ContextTooLong { len: 256 })
+        );
+
+        // Plain signatures don't verify with a context.
+        let plain = secret.sign_detached(b"m");
+        assert!(public.verify(b"m", &plain).is_ok());
+        assert!(public.verify_with_context(b"m", b"x", &plain).is_err());
+
+        let other = SecretKey::from_bytes(&[8u8; 32]).unwrap();
+        assert_eq!(
+            other.sign_with_context(&public, b"m", b"x"),
+            Err(Error::PublicKeyMismatch)
+        );
+    }
+}
@@ -0,0 +1,605 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// COSE (RFC 9052/9053) encodings: COSE_Key and COSE_Sign1.
+// Only the small subset of CBOR needed by these structures is
+// implemented here.
+
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::public::*;
+use crate::reader::*;
+use crate::signature::*;
+
+// CBOR major types.
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+// COSE_Sign1 CBOR tag.
+const TAG_COSE_SIGN1: u64 = 18;
+
+// COSE labels and values (RFC 9052, RFC 9053).
+const LABEL_KTY: i64 = 1;
+const LABEL_ALG: i64 = 3;
+const LABEL_CRV: i64 = -1;
+const LABEL_X: i64 = -2;
+const LABEL_HEADER_ALG: i64 = 1;
+const KTY_OKP: i64 = 1;
+const ALG_EDDSA: i64 = -8;
+const CRV_ED25519: i64 = 6;
+
+// Maximum nesting accepted when skipping unknown header values.
+const MAX_DEPTH: usize = 8;
+
+/// Writes a CBOR item head: major type and argument.
+fn write_head(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    if value < 24 {
+        out.push(major | value as u8);
+    } else if value <= 0xff {
+        out.push(major | 24);
+        out.push(value as u8);
+    } else if value <= 0xffff {
+        out.push(major | 25);
+        out.extend_from_slice(&(value as u16).to_be_bytes());
+    } else if value <= 0xffff_ffff {
+        out.push(major | 26);
+        out.extend_from_slice(&(value as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+/// Writes a CBOR integer.
+fn write_int(out: &mut Vec<u8>, value: i64) {
+    if value >= 0 {
+        write_head(out, MAJOR_UNSIGNED, value as u64);
+    } else {
+        write_head(out, MAJOR_NEGATIVE, !(value as u64));
+    }
+}
+
+/// Writes a CBOR byte string.
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_head(out, MAJOR_BYTES, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Minimal CBOR decoder over an `ExactReader`. Structural problems are
+/// reported as `malformed`, truncation as `Error::Truncated` and declared
+/// lengths above `MAX_MESSAGE_LEN` as `Error::LengthOverflow`.
+struct Cbor<'r, 'a> {
+    reader: &'r mut ExactReader<'a>,
+    malformed: Error,
+}
+
+impl<'r, 'a> Cbor<'r, 'a> {
+    fn new(reader: &'r mut ExactReader<'a>, malformed: Error) -> Cbor<'r, 'a> {
+        Cbor { reader, malformed }
+    }
+
+    fn peek_major(&self) -> Option<u8> {
+        self.reader.peek().map(|b| b >> 5)
+    }
+
+    /// Reads an item head. Indefinite lengths are not supported, and the
+    /// argument must use its shortest encoding so that every item has a
+    /// single valid encoding.
+    fn head(&mut self) -> Result<(u8, u64), Error> {
+        let initial = self.reader.read_u8()?;
+        let major = initial >> 5;
+        let info = initial & 0x1f;
+        let (value, minimum) = match info {
+            0..=23 => (info as u64, 0),
+            24 => (self.reader.read_u8()? as u64, 24),
+            25 => (u16::from_be_bytes(self.reader.take_array()?) as u64, 1 << 8),
+            26 => (
+                u32::from_be_bytes(self.reader.take_array()?) as u64,
+                1 << 16,
+            ),
+            27 => (u64::from_be_bytes(self.reader.take_array()?), 1 << 32),
+            _ => return Err(self.malformed),
+        };
+        if value < minimum {
+            return Err(self.malformed);
+        }
+        Ok((major, value))
+    }
+
+    fn int(&mut self) -> Result<i64, Error> {
+        match self.head()? {
+            (MAJOR_UNSIGNED, v) if v <= i64::MAX as u64 => Ok(v as i64),
+            (MAJOR_NEGATIVE, v) if v <= i64::MAX as u64 => Ok(!(v as i64)),
+            _ => Err(self.malformed),
+        }
+    }
+
+    /// Consumes the content of a byte or text string of declared length `len`.
+    fn content(&mut self, len: u64) -> Result<&'a [u8], Error> {
+        self.reader.take_declared(len)
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        match self.head()? {
+            (MAJOR_BYTES, len) => self.content(len),
+            _ => Err(self.malformed),
+        }
+    }
+
+    /// Reads an array or map head and returns its item count.
+    fn container(&mut self, major: u8) -> Result<usize, Error> {
+        match self.head()? {
+            (m, len) if m == major => self.reader.declared_len(len),
+            _ => Err(self.malformed),
+        }
+    }
+
+    /// Skips one complete data item.
+    fn skip(&mut self, depth: usize) -> Result<(), Error> {
+        if depth > MAX_DEPTH {
+            return Err(self.malformed);
+        }
+        match self.head()? {
+            (MAJOR_UNSIGNED, _) | (MAJOR_NEGATIVE, _) | (MAJOR_SIMPLE, _) => Ok(()),
+            (MAJOR_BYTES, len) | (MAJOR_TEXT, len) => self.content(len).map(|_| ()),
+            (MAJOR_ARRAY, len) => {
+                for _ in 0..self.reader.declared_len(len)? {
+                    self.skip(depth + 1)?;
+                }
+                Ok(())
+            }
+            (MAJOR_MAP, len) => {
+                for _ in 0..self.reader.declared_len(len)? {
+                    self.skip(depth + 1)?;
+                    self.skip(depth + 1)?;
+                }
+                Ok(())
+            }
+            (MAJOR_TAG, _) => self.skip(depth + 1),
+            _ => Err(self.malformed),
+        }
+    }
+}
+
+/// Orders map labels as required by the CBOR deterministic encoding:
+/// unsigned labels first (ascending), then negative labels (descending).
+fn label_rank(label: i64) -> (u8, u64) {
+    if label >= 0 {
+        (0, label as u64)
+    } else {
+        (1, !(label as u64))
+    }
+}
+
+/// Checks that an encoded protected header map declares `alg: EdDSA`.
+fn check_protected_headers(protected: &[u8]) -> Result<(), Error> {
+    let alg = parse_exact(protected, |reader| {
+        let mut cbor = Cbor::new(reader, Error::InvalidCoseSign1);
+        let entries = cbor.container(MAJOR_MAP)?;
+
+        let mut alg = None;
+        for _ in 0..entries {
+            match cbor.peek_major() {
+                Some(MAJOR_UNSIGNED) | Some(MAJOR_NEGATIVE) => {
+                    if cbor.int()? == LABEL_HEADER_ALG {
+                        if alg.is_some() {
+                            return Err(Error::InvalidCoseSign1);
+                        }
+                        alg = Some(cbor.int()?);
+                        continue;
+                    }
+                }
+                _ => cbor.skip(0)?,
+            }
+            cbor.skip(0)?;
+        }
+        Ok(alg)
+    })?;
+
+    if alg != Some(ALG_EDDSA) {
+        return Err(Error::InvalidCoseSign1);
+    }
+
+    Ok(())
+}
+
+/// Builds the COSE Sig_structure for a COSE_Sign1 with an empty
+/// external AAD: ["Signature1", protected, h'', payload].
+fn sig_structure(protected: &[u8], payload: &[u8]) -> Vec<u8> {
+    let context = b"Signature1";
+    let mut out = Vec::with_capacity(protected.len() + payload.len() + 32);
+    write_head(&mut out, MAJOR_ARRAY, 4);
+    write_head(&mut out, MAJOR_TEXT, context.len() as u64);
+    out.extend_from_slice(context);
+    write_bytes(&mut out, protected);
+    write_bytes(&mut out, &[]);
+    write_bytes(&mut out, payload);
+    out
+}
+
+impl PublicKey {
+    /// Encodes this `PublicKey` as a COSE_Key map
+    /// (kty: OKP, alg: EdDSA, crv: Ed25519, x).
+    ///
+    /// Returns the deterministic CBOR encoding of the map.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     let cose_key: Vec<u8> = public_key.to_cose_key();
+    ///     let decoded = PublicKey::from_cose_key(&cose_key).unwrap();
+    ///     assert_eq!(decoded.as_bytes(), public_key.as_bytes());
+    /// }
+    /// ```
+    pub fn to_cose_key(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(PublicKeySize + 12);
+        write_head(&mut out, MAJOR_MAP, 4);
+        write_int(&mut out, LABEL_KTY);
+        write_int(&mut out, KTY_OKP);
+        write_int(&mut out, LABEL_ALG);
+        write_int(&mut out, ALG_EDDSA);
+        write_int(&mut out, LABEL_CRV);
+        write_int(&mut out, CRV_ED25519);
+        write_int(&mut out, LABEL_X);
+        write_bytes(&mut out, &self.0);
+        out
+    }
+
+    /// Constructs `PublicKey` from a COSE_Key map.
+    ///
+    /// The map labels must be integers in deterministic CBOR order,
+    /// `kty` (OKP), `crv` (Ed25519) and `x` are required, and `alg`, if
+    /// present, must be EdDSA. Other labels are skipped.
+    ///
+    /// Returns `Ok(PublicKey)` if the map is a valid Ed25519 COSE_Key
+    /// and `Err` otherwise.
+    pub fn from_cose_key(bytes: &[u8]) -> Result<Self, Error> {
+        let (kty, crv, x) = parse_exact(bytes, |reader| {
+            let mut cbor = Cbor::new(reader, Error::InvalidCoseKey);
+            let entries = cbor.container(MAJOR_MAP)?;
+
+            let mut previous: Option<(u8, u64)> = None;
+            let mut kty = None;
+            let mut crv = None;
+            let mut x = None;
+            for _ in 0..entries {
+                let label = cbor.int()?;
+                let rank = label_rank(label);
+                // Rejects both mis-ordered and duplicated labels.
+                if previous.is_some_and(|p| p >= rank) {
+                    return Err(Error::InvalidCoseKey);
+                }
+                previous = Some(rank);
+
+                match label {
+                    LABEL_KTY => kty = Some(cbor.int()?),
+                    LABEL_ALG => {
+                        if cbor.int()? != ALG_EDDSA {
+                            return Err(Error::InvalidCoseKey);
+                        }
+                    }
+                    LABEL_CRV => crv = Some(cbor.int()?),
+                    LABEL_X => x = Some(cbor.bytes()?),
+                    _ => cbor.skip(0)?,
+                }
+            }
+            Ok((kty, crv, x))
+        })?;
+
+        if kty != Some(KTY_OKP) || crv != Some(CRV_ED25519) {
+            return Err(Error::InvalidCoseKey);
+        }
+
+        match x {
+            Some(x) if x.len() == PublicKeySize => PublicKey::from_bytes(x),
+            _ => Err(Error::InvalidCoseKey),
+        }
+    }
+}
+
+/// Signs `payload` as a tagged COSE_Sign1 structure with an empty
+/// unprotected header map.
+///
+/// `protected_headers` is the encoded protected header map; it must
+/// declare `alg: EdDSA` (for instance `a10127`).
+///
+/// Returns `Ok` with the encoded COSE_Sign1 and `Err` if the protected
+/// headers are malformed or `payload` is longer than `MAX_MESSAGE_LEN`.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::cose::{sign_cose_sign1, verify_cose_sign1};
+/// use ed25519_fun::Keypair;
+///
+/// fn main() {
+///     let keypair = Keypair::generate();
+///     let protected: &[u8] = &[0xa1, 0x01, 0x27];
+///     let cose = sign_cose_sign1(&keypair, b"payload", protected).unwrap();
+///     let payload = verify_cose_sign1(keypair.public(), &cose).unwrap();
+///     assert_eq!(payload, b"payload");
+/// }
+/// ```
+#[cfg(not(feature = "verify-only"))]
+pub fn sign_cose_sign1(
+    keypair: &Keypair,
+    payload: &[u8],
+    protected_headers: &[u8],
+) -> Result<Vec<u8>, Error> {
+    check_protected_headers(protected_headers)?;
+    // Our own parser would reject a longer payload.
+    if payload.len() as u64 > MAX_MESSAGE_LEN {
+        return Err(Error::LengthOverflow);
+    }
+
+    let signature = keypair.sign(&sig_structure(protected_headers, payload));
+
+    let mut out = Vec::with_capacity(protected_headers.len() + payload.len() + 80);
+    write_head(&mut out, MAJOR_TAG, TAG_COSE_SIGN1);
+    write_head(&mut out, MAJOR_ARRAY, 4);
+    write_bytes(&mut out, protected_headers);
+    write_head(&mut out, MAJOR_MAP, 0);
+    write_bytes(&mut out, payload);
+    write_bytes(&mut out, signature.as_bytes());
+    Ok(out)
+}
+
+/// Verifies a COSE_Sign1 structure (tagged or untagged) with `public`.
+///
+/// Returns `Ok` with the attached payload if the signature is valid and
+/// `Err` otherwise.
+pub fn verify_cose_sign1(public: &PublicKey, cose: &[u8]) -> Result<Vec<u8>, Error> {
+    let (protected, payload, signature) = parse_exact(cose, |reader| {
+        let mut cbor = Cbor::new(reader, Error::InvalidCoseSign1);
+
+        if cbor.peek_major() == Some(MAJOR_TAG) && cbor.head()? != (MAJOR_TAG, TAG_COSE_SIGN1) {
+            return Err(Error::InvalidCoseSign1);
+        }
+        if cbor.container(MAJOR_ARRAY)? != 4 {
+            return Err(Error::InvalidCoseSign1);
+        }
+
+        let protected = cbor.bytes()?;
+        // The unprotected header map is not covered by the signature.
+        if cbor.peek_major() != Some(MAJOR_MAP) {
+            return Err(Error::InvalidCoseSign1);
+        }
+        cbor.skip(0)?;
+        // Detached payloads (nil) are not supported.
+        let payload = cbor.bytes()?;
+        let signature = cbor.bytes()?;
+        Ok((protected, payload, signature))
+    })?;
+
+    if signature.len() != SignatureSize {
+        return Err(Error::InvalidCoseSign1);
+    }
+    check_protected_headers(protected)?;
+
+    let signature = Signature::from_bytes(signature)?;
+    public.verify(&sig_structure(protected, payload), &signature)?;
+
+    Ok(payload.to_vec())
+}
+
+#[cfg(all(test, not(feature = "verify-only")))]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // RFC 8032 TEST 1 key, used as kid "11" in the cose-wg examples.
+    const SECRET: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    // COSE_Sign1 over "This is the content." with protected {1: -8} and
+    // unprotected {4: '11'}, laid out as the cose-wg eddsa-sig-01 example.
+    // The signature was recomputed independently (Python `cryptography`).
+    const COSE_SIGN1: &str = "d28443a10127a10442313154546869732069732074686520636f6e74656e742e58406354488f9f290e36cd80e23762e664a5cb03e4267c66a8cffaef7c66d89a40bf2cbb8222432a08e5ee410d8b540c6931d26fb6af673f7e2100655d8bae765c04";
+
+    fn keypair() -> Keypair {
+        let secret = crate::secret::SecretKey::from_bytes(&hex::decode(SECRET).unwrap()).unwrap();
+        Keypair::from_secret(secret)
+    }
+
+    #[test]
+    fn cose_key_round_trip() {
+        let public = PublicKey::from_bytes(&hex::decode(PUBLIC).unwrap()).unwrap();
+        let cose_key = public.to_cose_key();
+        assert_eq!(
+            hex::encode(&cose_key),
+            format!("a4010103272006215820{}", PUBLIC)
+        );
+        let decoded = PublicKey::from_cose_key(&cose_key).unwrap();
+        assert!(decoded.as_bytes() == public.as_bytes());
+    }
+
+    #[test]
+    fn cose_key_rejects_malformed_maps() {
+        let x = format!("5820{}", PUBLIC);
+        // Mis-ordered labels (crv before alg).
+        let misordered = hex::decode(format!("a401012006032721{}", x)).unwrap();
+        assert_eq!(
+            PublicKey::from_cose_key(&misordered).err(),
+            Some(Error::InvalidCoseKey)
+        );
+        // Missing crv.
+        let missing = hex::decode(format!("a30101032721{}", x)).unwrap();
+        assert_eq!(
+            PublicKey::from_cose_key(&missing).err(),
+            Some(Error::InvalidCoseKey)
+        );
+        // X25519 curve.
+        let wrong_crv = hex::decode(format!("a30101200421{}", x)).unwrap();
+        assert!(PublicKey::from_cose_key(&wrong_crv).is_err());
+        // Truncated input.
+        let key = PublicKey::from_bytes(&hex::decode(PUBLIC).unwrap())
+            .unwrap()
+            .to_cose_key();
+        for len in 0..key.len() {
+            assert!(PublicKey::from_cose_key(&key[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn cose_sign1_example() {
+        let keypair = keypair();
+        let cose = hex::decode(COSE_SIGN1).unwrap();
+        let payload = verify_cose_sign1(&keypair.public, &cose).unwrap();
+        assert_eq!(payload, b"This is the content.");
+
+        // Same structure, empty unprotected headers.
+        let signed =
+            sign_cose_sign1(&keypair, b"This is the content.", &[0xa1, 0x01, 0x27]).unwrap();
+        assert_eq!(&signed[..6], &cose[..6]);
+        assert_eq!(signed[6], 0xa0);
+        assert_eq!(&signed[7..], &cose[11..]);
+    }
+
+    #[test]
+    fn cose_sign1_rejects_tampering() {
+        let keypair = keypair();
+        let mut cose = hex::decode(COSE_SIGN1).unwrap();
+        let last = cose.len() - 1;
+        cose[last] ^= 1;
+        assert_eq!(
+            verify_cose_sign1(&keypair.public, &cose).err(),
+            Some(Error::SignatureMismatch)
+        );
+        assert!(sign_cose_sign1(&keypair, b"", &[0xa0]).is_err());
+        for len in 0..cose.len() {
+            assert!(verify_cose_sign1(&keypair.public, &cose[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn cose_rejects_trailing_bytes() {
+        let keypair = keypair();
+        let mut cose = hex::decode(COSE_SIGN1).unwrap();
+        let total = cose.len() + 1;
+        cose.push(0);
+        assert_eq!(
+            verify_cose_sign1(&keypair.public, &cose).err(),
+            Some(Error::TrailingBytes {
+                consumed: total - 1,
+                total
+            })
+        );
+
+        let mut key = keypair.public.to_cose_key();
+        key.push(0);
+        assert_eq!(
+            PublicKey::from_cose_key(&key).err(),
+            Some(Error::TrailingBytes {
+                consumed: 42,
+                total: 43
+            })
+        );
+
+        // Trailing bytes inside the protected header bstr.
+        assert_eq!(
+            sign_cose_sign1(&keypair, b"", &[0xa1, 0x01, 0x27, 0x00]).err(),
+            Some(Error::TrailingBytes {
+                consumed: 3,
+                total: 4
+            })
+        );
+    }
+
+    #[test]
+    fn cose_rejects_random_suffixes() {
+        let keypair = keypair();
+        let corpus = [
+            hex::decode(COSE_SIGN1).unwrap(),
+            keypair.public.to_cose_key(),
+        ];
+        for _ in 0..64 {
+            let len = 1 + (rand::random::<u8>() % 16) as usize;
+            let suffix: Vec<u8> = (0..len).map(|_| rand::random::<u8>()).collect();
+
+            let mut cose = corpus[0].clone();
+            cose.extend_from_slice(&suffix);
+            assert!(verify_cose_sign1(&keypair.public, &cose).is_err());
+
+            let mut key = corpus[1].clone();
+            key.extend_from_slice(&suffix);
+            assert!(PublicKey::from_cose_key(&key).is_err());
+        }
+    }
+
+    #[test]
+    fn cose_rejects_non_minimal_heads() {
+        let keypair = keypair();
+
+        // x with 2-, 4- and 8-byte length arguments, kty with a 1-byte one.
+        for key in [
+            format!("a401010327200621590020{}", PUBLIC),
+            format!("a4010103272006215a00000020{}", PUBLIC),
+            format!("a4010103272006215b0000000000000020{}", PUBLIC),
+            format!("a418010103272006215820{}", PUBLIC),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                PublicKey::from_cose_key(&hex::decode(key).unwrap()).err(),
+                Some(Error::InvalidCoseKey)
+            );
+        }
+
+        // Payload re-encoded with a 1-byte length argument.
+        let sign1 = COSE_SIGN1.replacen("3131545468", "313158145468", 1);
+        assert_eq!(
+            verify_cose_sign1(&keypair.public, &hex::decode(sign1).unwrap()).err(),
+            Some(Error::InvalidCoseSign1)
+        );
+    }
+
+    #[test]
+    fn cose_truncation_boundary() {
+        let key = PublicKey::from_bytes(&hex::decode(PUBLIC).unwrap())
+            .unwrap()
+            .to_cose_key();
+        // The x bstr header is at offset 8, its content at offset 10.
+        assert_eq!(
+            PublicKey::from_cose_key(&key[..41]).err(),
+            Some(Error::Truncated { offset: 10 })
+        );
+        assert_eq!(
+            PublicKey::from_cose_key(&key[..8]).err(),
+            Some(Error::Truncated { offset: 8 })
+        );
+    }
+
+    // Zeroed allocations are lazy, so the oversized payload costs no
+    // memory as long as it isn't read.
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn cose_sign1_rejects_oversized_payload() {
+        let big = vec![0u8; MAX_MESSAGE_LEN as usize + 1];
+        assert_eq!(
+            sign_cose_sign1(&keypair(), &big, &[0xa1, 0x01, 0x27]).err(),
+            Some(Error::LengthOverflow)
+        );
+    }
+}
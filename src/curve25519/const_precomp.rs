@@ -0,0 +1,396 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Compile-time re-derivation of part of the precomputed tables.
+//
+// Everything here is `const fn` and runs only during compilation: the
+// curve constants and the base point are derived from their definitions
+// (d = -121665/121666, B = (x, 4/5) with x even), then the first column
+// of PRECOMP_BASE (256^i * B) and all of BI ((2i + 1) * B) are computed
+// and compared with the committed tables by the `const` assertion below.
+// A tampered entry fails the build. The arithmetic is plain schoolbook on
+// 51-bit limbs, without the optimizations (or the constant-time care) of
+// the runtime code, which must not call it.
+
+#![allow(non_snake_case)]
+
+use super::constants::{D, D2, I};
+use super::group_element::Precomp;
+use super::precomp::{BI, PRECOMP_BASE};
+
+type Fe = [u64; 5];
+
+const MASK: u64 = (1 << 51) - 1;
+
+const fn fe(n: u64) -> Fe {
+    [n, 0, 0, 0, 0]
+}
+
+const fn carry(mut a: Fe) -> Fe {
+    let mut i = 0;
+    while i < 4 {
+        a[i + 1] += a[i] >> 51;
+        a[i] &= MASK;
+        i += 1;
+    }
+    a[0] += 19 * (a[4] >> 51);
+    a[4] &= MASK;
+    a
+}
+
+/// The unique representative in [0, p).
+const fn canonical(a: Fe) -> Fe {
+    let mut a = carry(carry(a));
+    // a < 2^255 + 19 * 2: subtract p if a >= p.
+    let mut q = (a[0] + 19) >> 51;
+    let mut i = 1;
+    while i < 5 {
+        q = (a[i] + q) >> 51;
+        i += 1;
+    }
+    a[0] += 19 * q;
+    let mut i = 0;
+    while i < 4 {
+        a[i + 1] += a[i] >> 51;
+        a[i] &= MASK;
+        i += 1;
+    }
+    a[4] &= MASK;
+    a
+}
+
+const fn add(a: Fe, b: Fe) -> Fe {
+    carry([
+        a[0] + b[0],
+        a[1] + b[1],
+        a[2] + b[2],
+        a[3] + b[3],
+        a[4] + b[4],
+    ])
+}
+
+const fn sub(a: Fe, b: Fe) -> Fe {
+    // a + 4p - b, for carried b.
+    let b = carry(b);
+    carry([
+        a[0] + 4 * (MASK - 18) - b[0],
+        a[1] + 4 * MASK - b[1],
+        a[2] + 4 * MASK - b[2],
+        a[3] + 4 * MASK - b[3],
+        a[4] + 4 * MASK - b[4],
+    ])
+}
+
+const fn mul(a: Fe, b: Fe) -> Fe {
+    let a = carry(a);
+    let b = carry(b);
+    let mut h = [0u128; 5];
+    let mut i = 0;
+    while i < 5 {
+        let mut j = 0;
+        while j < 5 {
+            let product = a[i] as u128 * b[j] as u128;
+            if i + j < 5 {
+                h[i + j] += product;
+            } else {
+                h[i + j - 5] += 19 * product;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    let mut i = 0;
+    while i < 4 {
+        h[i + 1] += h[i] >> 51;
+        h[i] &= MASK as u128;
+        i += 1;
+    }
+    let top = h[4] >> 51;
+    carry([
+        h[0] as u64 + 19 * top as u64,
+        h[1] as u64,
+        h[2] as u64,
+        h[3] as u64,
+        h[4] as u64 & MASK,
+    ])
+}
+
+/// a^e, `e` little-endian.
+const fn pow(a: Fe, e: [u8; 32]) -> Fe {
+    let mut r = fe(1);
+    let mut i = 256;
+    while i > 0 {
+        i -= 1;
+        r = mul(r, r);
+        if (e[i / 8] >> (i % 8)) & 1 == 1 {
+            r = mul(r, a);
+        }
+    }
+    r
+}
+
+/// The little-endian exponent of 0xff bytes between `low` and `top`.
+const fn exponent(low: u8, top: u8) -> [u8; 32] {
+    let mut e = [0xff; 32];
+    e[0] = low;
+    e[31] = top;
+    e
+}
+
+const fn invert(a: Fe) -> Fe {
+    // p - 2.
+    pow(a, exponent(0xeb, 0x7f))
+}
+
+const fn eq(a: Fe, b: Fe) -> bool {
+    let a = canonical(a);
+    let b = canonical(b);
+    let mut i = 0;
+    while i < 5 {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn is_odd(a: Fe) -> bool {
+    canonical(a)[0] & 1 == 1
+}
+
+/// sqrt(-1) = 2^((p - 1) / 4).
+const fn sqrt_m1() -> Fe {
+    pow(fe(2), exponent(0xfb, 0x1f))
+}
+
+/// d = -121665 / 121666.
+const fn d() -> Fe {
+    sub(fe(0), mul(fe(121665), invert(fe(121666))))
+}
+
+/// A square root of `a`, which must be a square.
+const fn sqrt(a: Fe) -> Fe {
+    // a^((p + 3) / 8), times sqrt(-1) if its square is -a.
+    let x = pow(a, exponent(0xfe, 0x0f));
+    if eq(mul(x, x), a) {
+        x
+    } else {
+        mul(x, sqrt_m1())
+    }
+}
+
+/// Extended coordinates (X : Y : Z : T).
+#[derive(Copy, Clone)]
+struct Point {
+    X: Fe,
+    Y: Fe,
+    Z: Fe,
+    T: Fe,
+}
+
+/// B = (x, 4/5), where x is even and -x^2 + y^2 = 1 + d x^2 y^2.
+const fn base_point() -> Point {
+    let y = mul(fe(4), invert(fe(5)));
+    let yy = mul(y, y);
+    let xx = mul(sub(yy, fe(1)), invert(add(mul(d(), yy), fe(1))));
+    let mut x = sqrt(xx);
+    if is_odd(x) {
+        x = sub(fe(0), x);
+    }
+    Point {
+        X: x,
+        Y: y,
+        Z: fe(1),
+        T: mul(x, y),
+    }
+}
+
+/// Unified addition (add-2008-hwcd-3), also used for doubling.
+const fn point_add(p: Point, q: Point, d2: Fe) -> Point {
+    let a = mul(sub(p.Y, p.X), sub(q.Y, q.X));
+    let b = mul(add(p.Y, p.X), add(q.Y, q.X));
+    let c = mul(mul(p.T, d2), q.T);
+    let d = mul(add(p.Z, p.Z), q.Z);
+    let e = sub(b, a);
+    let f = sub(d, c);
+    let g = add(d, c);
+    let h = add(b, a);
+    Point {
+        X: mul(e, f),
+        Y: mul(g, h),
+        Z: mul(f, g),
+        T: mul(e, h),
+    }
+}
+
+/// The Precomp entries (y + x, y - x, 2d x y) of `points`, canonical,
+/// with a single inversion for all of them.
+const fn to_precomp<const N: usize>(points: [Point; N], d2: Fe) -> [[Fe; 3]; N] {
+    // prefix[i] = Z_0 * ... * Z_(i-1).
+    let mut prefix = [fe(1); N];
+    let mut i = 1;
+    while i < N {
+        prefix[i] = mul(prefix[i - 1], points[i - 1].Z);
+        i += 1;
+    }
+    // inverse = 1 / (Z_0 * ... * Z_(i-1)) while walking back.
+    let mut inverse = invert(mul(prefix[N - 1], points[N - 1].Z));
+
+    let mut entries = [[fe(0); 3]; N];
+    let mut i = N;
+    while i > 0 {
+        i -= 1;
+        let zinv = mul(inverse, prefix[i]);
+        inverse = mul(inverse, points[i].Z);
+
+        let x = mul(points[i].X, zinv);
+        let y = mul(points[i].Y, zinv);
+        entries[i] = [
+            canonical(add(y, x)),
+            canonical(sub(y, x)),
+            canonical(mul(mul(x, y), d2)),
+        ];
+    }
+    entries
+}
+
+/// 256^i * B for i in 0..32: PRECOMP_BASE[i][0].
+pub(crate) const fn base_column() -> [[Fe; 3]; 32] {
+    let d2 = add(d(), d());
+    let mut points = [base_point(); 32];
+    let mut i = 1;
+    while i < 32 {
+        let mut p = points[i - 1];
+        let mut k = 0;
+        while k < 8 {
+            p = point_add(p, p, d2);
+            k += 1;
+        }
+        points[i] = p;
+        i += 1;
+    }
+    to_precomp(points, d2)
+}
+
+/// (2i + 1) * B for i in 0..8: BI.
+pub(crate) const fn odd_multiples() -> [[Fe; 3]; 8] {
+    let d2 = add(d(), d());
+    let b = base_point();
+    let b2 = point_add(b, b, d2);
+    let mut points = [b; 8];
+    let mut i = 1;
+    while i < 8 {
+        points[i] = point_add(points[i - 1], b2, d2);
+        i += 1;
+    }
+    to_precomp(points, d2)
+}
+
+const fn matches(committed: &Precomp, derived: &[Fe; 3]) -> bool {
+    eq(committed.YpX.0, derived[0])
+        && eq(committed.YmX.0, derived[1])
+        && eq(committed.XY2d.0, derived[2])
+}
+
+const fn committed_tables_match() -> bool {
+    if !eq(D.0, d()) || !eq(D2.0, add(d(), d())) || !eq(I.0, sqrt_m1()) {
+        return false;
+    }
+
+    let column = base_column();
+    let mut i = 0;
+    while i < 32 {
+        if !matches(&PRECOMP_BASE[i][0], &column[i]) {
+            return false;
+        }
+        i += 1;
+    }
+
+    let odd = odd_multiples();
+    let mut i = 0;
+    while i < 8 {
+        if !matches(&BI[i], &odd[i]) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const _: () = assert!(
+    committed_tables_match(),
+    "committed curve constants or precomputed tables differ from their definition"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve25519::field_element::FieldElement;
+    use crate::curve25519::group_element::P3;
+
+    // Runtime arithmetic: n * B without the tables, as a Precomp entry.
+    fn runtime_entry(scalar: &[u8; 32]) -> [[u8; 32]; 3] {
+        let p: P3 = Precomp::scalar_multiply_without_precomputation(scalar);
+        let zinv = p.Z.invert();
+        let x = p.X * zinv;
+        let y = p.Y * zinv;
+        [(y + x).encode(), (y - x).encode(), (x * y * D2).encode()]
+    }
+
+    fn encoded(entry: &[Fe; 3]) -> [[u8; 32]; 3] {
+        [
+            FieldElement(entry[0]).encode(),
+            FieldElement(entry[1]).encode(),
+            FieldElement(entry[2]).encode(),
+        ]
+    }
+
+    fn committed(entry: &Precomp) -> [[u8; 32]; 3] {
+        [entry.YpX.encode(), entry.YmX.encode(), entry.XY2d.encode()]
+    }
+
+    // (j + 1) * 256^i as a little-endian scalar.
+    fn scalar(i: usize, j: u8) -> [u8; 32] {
+        let mut s = [0u8; 32];
+        s[i] = j + 1;
+        s
+    }
+
+    #[test]
+    fn const_math_matches_runtime_math() {
+        for (i, entry) in base_column().iter().enumerate() {
+            assert_eq!(encoded(entry), runtime_entry(&scalar(i, 0)));
+        }
+        for (i, entry) in odd_multiples().iter().enumerate() {
+            assert_eq!(encoded(entry), runtime_entry(&scalar(0, 2 * i as u8)));
+        }
+
+        // The derived constants, by their defining equations.
+        let d = FieldElement(d());
+        assert!(
+            d * FieldElement([121666, 0, 0, 0, 0]) + FieldElement([121665, 0, 0, 0, 0])
+                == FieldElement([0, 0, 0, 0, 0])
+        );
+        let i = FieldElement(sqrt_m1());
+        assert!((i * i).negate() == FieldElement([1, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn committed_tables_match_runtime_math() {
+        for (i, row) in PRECOMP_BASE.iter().enumerate() {
+            for (j, entry) in row.iter().enumerate() {
+                assert_eq!(
+                    committed(entry),
+                    runtime_entry(&scalar(i, j as u8)),
+                    "PRECOMP_BASE[{}][{}]",
+                    i,
+                    j
+                );
+            }
+        }
+        for (i, entry) in BI.iter().enumerate() {
+            assert_eq!(committed(entry), runtime_entry(&scalar(0, 2 * i as u8)));
+        }
+    }
+}
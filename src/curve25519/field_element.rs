@@ -3,10 +3,10 @@
 
 // This code provides field arithmetic arithmetic modulo p.
 
+use core::cmp::{Eq, PartialEq};
 use core::ops::Add;
 use core::ops::Mul;
 use core::ops::Sub;
-use std::cmp::{Eq, PartialEq};
 
 use super::constants::{Reduce51Mask, TwoP0, TwoP1234};
 use super::utils::{load_8, m6464};
@@ -181,7 +181,7 @@ impl ConditionallySelectable for FieldElement {
     }
 
     /// Conditionally assign b to self according to choice.
-    fn conditional_assign(self: &mut Self, b: &FieldElement, choice: Choice) {
+    fn conditional_assign(&mut self, b: &FieldElement, choice: Choice) {
         for i in 0..5 {
             self.0[i].conditional_assign(&b.0[i], choice);
         }
@@ -192,7 +192,7 @@ impl FieldElement {
     /// Determines if the FieldElement is zero.
     pub fn is_zero(&self) -> Choice {
         let zero = [0u8; 32];
-        self.encode().ct_eq(&zero).into()
+        self.encode().ct_eq(&zero)
     }
 
     /// Determines if the FieldElement is negative.
@@ -5,9 +5,9 @@
 
 #![allow(non_snake_case)]
 
+use core::cmp::min;
 use core::ops::Add;
 use core::ops::Sub;
-use std::cmp::min;
 
 use super::constants::{FieldOne, FieldZero, D, D2, I};
 use super::field_element::FieldElement;
@@ -16,6 +16,17 @@ use super::utils::equal;
 
 use subtle::{Choice, ConditionallySelectable};
 
+/// Uncompressed encoding of the affine point (X/Z, Y/Z): the canonical
+/// encodings of x and y.
+#[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+fn encode_affine(X: &FieldElement, Y: &FieldElement, Z: &FieldElement) -> [u8; 64] {
+    let recip = Z.invert();
+    let mut s = [0u8; 64];
+    s[..32].copy_from_slice(&(*X * recip).encode());
+    s[32..].copy_from_slice(&(*Y * recip).encode());
+    s
+}
+
 /// Projective representation (P^2): (X : Y : Z), satisfying
 /// x = X/Z, y = Y/Z.
 #[derive(Clone, Copy)]
@@ -64,27 +75,24 @@ pub struct Cached {
 
 impl P1P1 {
     /// Converts P1P1 representation to P2.
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_P2(&self) -> P2 {
         let X = self.X * self.T;
         let Y = self.Y * self.Z;
         let Z = self.Z * self.T;
 
-        P2 { X: X, Y: Y, Z: Z }
+        P2 { X, Y, Z }
     }
 
     /// Converts P1P1 representation to P3.
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_P3(&self) -> P3 {
         let X = self.X * self.T;
         let Y = self.Y * self.Z;
         let Z = self.Z * self.T;
         let T = self.X * self.Y;
 
-        P3 {
-            X: X,
-            Y: Y,
-            Z: Z,
-            T: T,
-        }
+        P3 { X, Y, Z, T }
     }
 }
 
@@ -116,6 +124,23 @@ impl P2 {
         s
     }
 
+    /// Encodes the group element as its affine coordinates x || y.
+    #[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+    pub fn encode_uncompressed(&self) -> [u8; 64] {
+        encode_affine(&self.X, &self.Y, &self.Z)
+    }
+
+    /// Converts P2 representation to P3: (XZ : YZ : Z^2 : XY).
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_P3(&self) -> P3 {
+        P3 {
+            X: self.X * self.Z,
+            Y: self.Y * self.Z,
+            Z: self.Z.square(),
+            T: self.X * self.Y,
+        }
+    }
+
     /// Point doubling: 2 * self.
     pub fn double(&self) -> P1P1 {
         let A = self.X.square();
@@ -137,6 +162,7 @@ impl P2 {
         }
     }
 
+    #[allow(clippy::needless_range_loop)]
     pub fn slide(a: &[u8]) -> [i8; 256] {
         let mut r = [0i8; 256];
 
@@ -176,20 +202,7 @@ impl P2 {
         let aslide = P2::slide(a);
         let bslide = P2::slide(b);
 
-        // A * I precomputation.
-        // {A, 3A, 5A, 7A, 9A, 11A, 13A, 15A}.
-        let mut AI = [Cached {
-            YpX: FieldZero,
-            YmX: FieldZero,
-            Z: FieldZero,
-            T2d: FieldZero,
-        }; 8];
-        AI[0] = A.to_Cached(); // A
-        let A2 = A.double().to_P3(); // 2A
-        for i in 1..8 {
-            // 3A, 5A, 7A, ..., 15A
-            AI[i] = (A2.add(AI[i - 1])).to_P3().to_Cached();
-        }
+        let AI = A.odd_multiples();
 
         let mut r = P2::zero();
         let mut i: usize = 255;
@@ -242,7 +255,114 @@ impl P3 {
         }
     }
 
+    /// A * I precomputation.
+    /// Returns {A, 3A, 5A, 7A, 9A, 11A, 13A, 15A}.
+    pub fn odd_multiples(&self) -> [Cached; 8] {
+        let mut AI = [Cached {
+            YpX: FieldZero,
+            YmX: FieldZero,
+            Z: FieldZero,
+            T2d: FieldZero,
+        }; 8];
+        AI[0] = self.to_Cached(); // A
+        let A2 = self.double().to_P3(); // 2A
+        for i in 1..8 {
+            // 3A, 5A, 7A, ..., 15A
+            AI[i] = (A2.add(AI[i - 1])).to_P3().to_Cached();
+        }
+        AI
+    }
+
+    /// Vartime multiscalar multiplication: [b]B + [a_1]A_1 + ... +
+    /// [a_n]A_n for the `terms` (a_i, A_i), with every scalar recoded by
+    /// `P2::slide` and a single chain of doublings (Straus).
+    #[cfg(feature = "std")]
+    pub fn multiscalar_multiply_vartime(b: &[u8], terms: &[([u8; 32], P3)]) -> P3 {
+        let bslide = P2::slide(b);
+        let slides: Vec<[i8; 256]> = terms.iter().map(|(a, _)| P2::slide(a)).collect();
+        let tables: Vec<[Cached; 8]> = terms.iter().map(|(_, A)| A.odd_multiples()).collect();
+
+        // Skip the leading zero digits of every scalar.
+        let top = (0..256)
+            .rev()
+            .find(|&i| bslide[i] != 0 || slides.iter().any(|slide| slide[i] != 0));
+        let mut r = P3::zero();
+        let top = match top {
+            Some(top) => top,
+            None => return r,
+        };
+
+        for i in (0..=top).rev() {
+            let mut t = r.double();
+
+            for (slide, AI) in slides.iter().zip(tables.iter()) {
+                if slide[i] > 0 {
+                    t = t.to_P3() + AI[(slide[i] / 2) as usize];
+                } else if slide[i] < 0 {
+                    t = t.to_P3() - AI[(-slide[i] / 2) as usize];
+                }
+            }
+
+            if bslide[i] > 0 {
+                t = t.to_P3() + BI[(bslide[i] / 2) as usize];
+            } else if bslide[i] < 0 {
+                t = t.to_P3() - BI[(-bslide[i] / 2) as usize];
+            }
+
+            r = t.to_P3();
+        }
+
+        r
+    }
+
+    /// Performs scalar multiplication h = a * self in constant time.
+    /// a: a[0] + 256 * a[1] + 256^{2} * a[2] + ...
+    /// + 256^{31} * a[31], with a[31] <= 127.
+    #[allow(clippy::doc_lazy_continuation)]
+    #[cfg_attr(not(feature = "vrf"), allow(dead_code))]
+    pub fn scalar_multiply(&self, a: &[u8]) -> P3 {
+        // {A, 2A, 3A, ..., 8A}
+        let mut AI = [self.to_Cached(); 8];
+        for i in 1..8 {
+            AI[i] = (*self + AI[i - 1]).to_P3().to_Cached();
+        }
+        let e: [i8; 64] = Precomp::radix16(a);
+
+        let mut h = P3::zero();
+        // 64 table lookups, 64 point additions and 4 doublings each
+        for i in (0..64).rev() {
+            h = h
+                .double()
+                .to_P2()
+                .double()
+                .to_P2()
+                .double()
+                .to_P2()
+                .double()
+                .to_P3();
+            h = (h + Cached::select(&AI, e[i])).to_P3();
+        }
+
+        h
+    }
+
+    /// Multiplication by the cofactor: [8]self.
+    pub fn multiply_by_cofactor(&self) -> P3 {
+        self.double().to_P2().double().to_P2().double().to_P3()
+    }
+
+    /// Whether self is one of the eight points of order dividing 8.
+    pub fn is_small_order(&self) -> bool {
+        self.multiply_by_cofactor().is_identity()
+    }
+
+    /// Whether self is the neutral element (0, 1).
+    pub fn is_identity(&self) -> bool {
+        self.X.is_zero().unwrap_u8() == 1 && (self.Y - self.Z).is_zero().unwrap_u8() == 1
+    }
+
     /// Converts P3 representation to P2.
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_P2(&self) -> P2 {
         P2 {
             X: self.X,
@@ -252,6 +372,7 @@ impl P3 {
     }
 
     /// Converts P3 representation to Cached.
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_Cached(&self) -> Cached {
         Cached {
             YpX: self.Y + self.X,
@@ -276,6 +397,50 @@ impl P3 {
         s
     }
 
+    /// Encodes the group element as its affine coordinates x || y.
+    #[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+    pub fn encode_uncompressed(&self) -> [u8; 64] {
+        encode_affine(&self.X, &self.Y, &self.Z)
+    }
+
+    /// Returns a GroupElement given its affine coordinates x || y, which
+    /// must be canonical and on the curve.
+    #[cfg_attr(not(feature = "hazmat"), allow(dead_code))]
+    pub fn decode_uncompressed(enc: &[u8; 64]) -> Option<P3> {
+        let mut xs = [0u8; 32];
+        let mut ys = [0u8; 32];
+        xs.copy_from_slice(&enc[..32]);
+        ys.copy_from_slice(&enc[32..]);
+        let x = FieldElement::decode(xs);
+        let y = FieldElement::decode(ys);
+        if x.encode() != xs || y.encode() != ys {
+            return None;
+        }
+        // -x^2 + y^2 = 1 + d * x^2 * y^2.
+        let xx = x.square();
+        let yy = y.square();
+        if yy - xx != FieldOne + D * xx * yy {
+            return None;
+        }
+
+        Some(P3 {
+            X: x,
+            Y: y,
+            Z: FieldOne,
+            T: x * y,
+        })
+    }
+
+    /// Point negation: -self.
+    pub fn negate(&self) -> P3 {
+        P3 {
+            X: self.X.negate(),
+            Y: self.Y,
+            Z: self.Z,
+            T: self.T.negate(),
+        }
+    }
+
     pub fn double(&self) -> P1P1 {
         self.to_P2().double()
     }
@@ -368,6 +533,7 @@ impl Precomp {
     /// Converts a to radix 16 representation.
     /// a: a[0] + 256 * a[1] + 256^{2} * a[2] + ...
     /// + 256^{31} * a[31].
+    #[allow(clippy::identity_op, clippy::needless_range_loop)]
     fn radix16(a: &[u8]) -> [i8; 64] {
         let mut e = [0i8; 64];
 
@@ -398,6 +564,7 @@ impl Precomp {
     /// + 256^{31} * a[31].
     /// B: Ed25519 base point (x, 4/5) with positive x.
     /// Uses precomputed values.
+    #[allow(clippy::doc_lazy_continuation)]
     pub fn scalar_multiply(a: &[u8]) -> P3 {
         let e: [i8; 64] = Precomp::radix16(a);
         let mut t: Precomp;
@@ -463,7 +630,7 @@ impl Precomp {
             // add p + q
             let ps = (p + q_cached).to_P3();
             q = (q + q_cached).to_P3();
-            let b = ((scalar[(i >> 3)] >> (i as u8 & 7)) & 1) as u8;
+            let b = (scalar[i >> 3] >> (i as u8 & 7)) & 1;
             if b == 1u8 {
                 p = ps;
             }
@@ -473,6 +640,52 @@ impl Precomp {
     }
 }
 
+#[cfg_attr(not(feature = "vrf"), allow(dead_code))]
+impl Cached {
+    pub fn zero() -> Cached {
+        Cached {
+            YpX: FieldOne,
+            YmX: FieldOne,
+            Z: FieldOne,
+            T2d: FieldZero,
+        }
+    }
+
+    /// Assign b to self, according to choice.
+    pub fn conditional_assign(&mut self, b: &Cached, choice: Choice) {
+        self.YpX.conditional_assign(&b.YpX, choice);
+        self.YmX.conditional_assign(&b.YmX, choice);
+        self.Z.conditional_assign(&b.Z, choice);
+        self.T2d.conditional_assign(&b.T2d, choice);
+    }
+
+    /// Selects [b]A from AI = {A, 2A, ..., 8A} in constant time, for b
+    /// in [-8..8].
+    pub fn select(AI: &[Cached; 8], b: i8) -> Cached {
+        // As in Precomp::select.
+        let negative = (b as u8) >> 7;
+        let absolute: u8 = (b - (((-(negative as i8)) & b) << 1)) as u8;
+        let mut t = Cached::zero();
+
+        for (i, A) in AI.iter().enumerate() {
+            t.conditional_assign(A, equal(absolute, i as u8 + 1).into());
+        }
+
+        // Negative of t.
+        let negative_t = Cached {
+            YpX: t.YmX,
+            YmX: t.YpX,
+            Z: t.Z,
+            T2d: t.T2d.negate(),
+        };
+
+        // Assign negative of t if b is negative.
+        t.conditional_assign(&negative_t, negative.into());
+
+        t
+    }
+}
+
 impl Add<Cached> for P3 {
     type Output = P1P1;
 
@@ -613,7 +826,7 @@ mod tests {
         // let a = hex::decode("d072f8dd9c07fa7bc8d22a4b325d26301ee9202f6db89aa7c3731529e37e437c").unwrap();
         // let mut a_bytes = [0u8; 32];
         // a_bytes.copy_from_slice(&a);
-        let mut BY = BASEPOINT_Y.clone();
+        let mut BY = BASEPOINT_Y;
         // BY[31] |= 1 << 7;
         let B = P3::decode(BY).unwrap();
 
@@ -645,6 +858,24 @@ mod tests {
         // assert!(aB.T == AB.T);
     }
 
+    #[test]
+    fn variable_base_scalar_multiply_test() {
+        let a = hex::decode("d072f8dd9c07fa7bc8d22a4b325d26301ee9202f6db89aa7c3731529e37e437c")
+            .unwrap();
+        assert!(B_P3.scalar_multiply(&a).encode() == Precomp::scalar_multiply(&a).encode());
+        assert!(B_P3.scalar_multiply(&[0u8; 32]).is_identity());
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        assert!(B_P3.scalar_multiply(&one).encode() == B_P3.encode());
+
+        // Against the vartime multiplication, on another point.
+        let two_B = B_P3.double().to_P3();
+        assert!(
+            two_B.scalar_multiply(&a).encode()
+                == P2::double_scalar_multiply_vartime(&a, &[0u8; 32], two_B).encode()
+        );
+    }
+
     #[test]
     fn scalar_multiply_no_precomp_test() {
         let a = hex::decode("d072f8dd9c07fa7bc8d22a4b325d26301ee9202f6db89aa7c3731529e37e437c")
@@ -669,10 +900,73 @@ mod tests {
     fn double_scalar_multiply_vartime_and_point_doubling_test() {
         let two = hex::decode("0200000000000000000000000000000000000000000000000000000000000000")
             .unwrap();
-        let B = B_P3.clone();
+        let B = B_P3;
         let four_B = P2::double_scalar_multiply_vartime(&two, &two, B).encode();
         let B_four = B.double().to_P3().double().to_P2().encode();
 
         assert!(four_B == B_four);
+
+        // Through P3 and back.
+        let lifted = P2::double_scalar_multiply_vartime(&two, &two, B).to_P3();
+        assert!(lifted.encode() == B_four);
+        assert!(
+            (lifted + B.negate().to_Cached()).to_P3().encode() == {
+                let three =
+                    hex::decode("0300000000000000000000000000000000000000000000000000000000000000")
+                        .unwrap();
+                Precomp::scalar_multiply(&three).encode()
+            }
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn multiscalar_multiply_vartime_test() {
+        let a = hex::decode("d072f8dd9c07fa7bc8d22a4b325d26301ee9202f6db89aa7c3731529e37e437c")
+            .unwrap();
+        let mut a_bytes = [0u8; 32];
+        a_bytes.copy_from_slice(&a);
+        let mut b = [0u8; 32];
+        b[0] = 9;
+        let two_B = B_P3.double().to_P3();
+
+        let expected = P2::double_scalar_multiply_vartime(&a, &b, two_B).encode();
+        let terms = [(a_bytes, two_B)];
+        assert!(P3::multiscalar_multiply_vartime(&b, &terms).encode() == expected);
+
+        // [a]B + [a](-B) + [0]B is the identity.
+        let terms = [(a_bytes, B_P3), (a_bytes, B_P3.negate())];
+        assert!(P3::multiscalar_multiply_vartime(&[0u8; 32], &terms).is_identity());
+        assert!(P3::multiscalar_multiply_vartime(&[0u8; 32], &[]).is_identity());
+        assert!(!B_P3.is_identity());
+
+        let mut eight = [0u8; 32];
+        eight[0] = 8;
+        assert!(B_P3.multiply_by_cofactor().encode() == Precomp::scalar_multiply(&eight).encode());
+    }
+
+    #[test]
+    fn uncompressed_encoding_test() {
+        let B = B_P3;
+        let enc = B.encode_uncompressed();
+        assert!(enc[..32] == B.X.encode());
+        assert!(enc[32..] == BASEPOINT_Y);
+        assert!(B.to_P2().encode_uncompressed() == enc);
+
+        let decoded = P3::decode_uncompressed(&enc).unwrap();
+        assert!(decoded.encode() == B.encode());
+        assert!(decoded.negate().encode_uncompressed()[32..] == BASEPOINT_Y);
+        assert!(decoded.negate().encode() == P3::decode(BASEPOINT_Y).unwrap().encode());
+
+        // Off the curve, and a non-canonical coordinate.
+        let mut off = enc;
+        off[0] ^= 1;
+        assert!(P3::decode_uncompressed(&off).is_none());
+        let mut non_canonical = [0u8; 64];
+        non_canonical[..32].copy_from_slice(&[0xff; 32]);
+        non_canonical[0] = 0xed;
+        non_canonical[31] = 0x7f;
+        non_canonical[32] = 1;
+        assert!(P3::decode_uncompressed(&non_canonical).is_none());
     }
 }
@@ -1,6 +1,7 @@
 // Author:
 // - Yuzo <yuzonakai@gmail.com>
 
+pub mod const_precomp;
 pub mod constants;
 pub mod field_element;
 pub mod group_element;
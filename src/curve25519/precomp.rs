@@ -10,6 +10,7 @@ use super::field_element::FieldElement;
 use super::group_element::{Precomp, P3};
 
 #[allow(dead_code)]
+#[allow(clippy::needless_range_loop)]
 pub fn precompute_single(B: P3) -> [[Precomp; 8]; 32] {
     let mut precomp = [[Precomp {
         YpX: FieldZero,
@@ -40,6 +41,7 @@ pub fn precompute_single(B: P3) -> [[Precomp; 8]; 32] {
 }
 
 #[allow(dead_code)]
+#[allow(clippy::needless_range_loop)]
 pub fn precompute_double(B: P3) -> [Precomp; 8] {
     let mut precomp = [Precomp {
         YpX: FieldZero,
@@ -255,6 +257,7 @@ pub const BI: [Precomp; 8] = [
 ];
 
 // [i][j] = (j + 1) * 256^i * B
+#[allow(clippy::large_const_arrays)]
 pub const PRECOMP_BASE: [[Precomp; 8]; 32] = [
     //Auto generated file.
     [
@@ -10,6 +10,11 @@ use super::utils::{load_3i, load_4i};
 /// Returns s = a mod L = s[0] + 256*s[1] + ...
 /// + 256^{2}*s[2] + 256^{31}*s[31].
 /// L = 2^{252} + 27742317777372353535851937790883648493.
+#[allow(
+    clippy::doc_lazy_continuation,
+    clippy::identity_op,
+    clippy::needless_late_init
+)]
 pub fn reduce(s: &mut [u8]) {
     let mut s0: i64 = 2097151 & load_3i(s);
     let mut s1: i64 = 2097151 & (load_4i(&s[2..6]) >> 5);
@@ -343,6 +348,11 @@ pub fn reduce(s: &mut [u8]) {
 /// Returns s = (a * b + c) mod L.
 /// s = s[0] + 256*s[1] + 256^{2}*s[2] + ...
 /// + 256^{31}*s[31].
+#[allow(
+    clippy::doc_lazy_continuation,
+    clippy::identity_op,
+    clippy::needless_late_init
+)]
 pub fn multiply_add(s: &mut [u8], a: &[u8], b: &[u8], c: &[u8]) {
     let a0 = 2097151 & load_3i(&a[0..3]);
     let a1 = 2097151 & (load_4i(&a[2..6]) >> 5);
@@ -0,0 +1,192 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// did:key identifiers for Ed25519 public keys: the multicodec prefix
+// 0xed01 followed by the key, base58btc encoded with the 'z' multibase
+// prefix. See https://w3c-ccg.github.io/did-method-key/.
+
+use crate::base58;
+use crate::constants::*;
+use crate::errors::*;
+use crate::public::*;
+use crate::reader::*;
+
+const DID_KEY_PREFIX: &str = "did:key:";
+
+// Multibase prefix of base58btc.
+const MULTIBASE_BASE58BTC: char = 'z';
+
+// Multicodec ed25519-pub, as an unsigned varint.
+const MULTICODEC_ED25519_PUB: [u8; 2] = [0xed, 0x01];
+
+// Longest base58btc encoding of the 34-byte multicodec key: 256^34 < 58^47.
+// Longer inputs are rejected before the quadratic base58 decoding.
+const MAX_ENCODED_LEN: usize = 47;
+
+impl PublicKey {
+    /// Encodes `PublicKey` as a `did:key` identifier.
+    ///
+    /// Returns a `did:key:z6Mk...` string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     let did = public_key.to_did_key();
+    ///     assert!(did.starts_with("did:key:z6Mk"));
+    ///     let decoded = PublicKey::from_did_key(&did).unwrap();
+    ///     assert_eq!(decoded.as_bytes(), public_key.as_bytes());
+    /// }
+    /// ```
+    pub fn to_did_key(&self) -> String {
+        let mut multicodec = [0u8; 2 + PublicKeySize];
+        multicodec[..2].copy_from_slice(&MULTICODEC_ED25519_PUB);
+        multicodec[2..].copy_from_slice(&self.0);
+
+        let mut did = String::from(DID_KEY_PREFIX);
+        did.push(MULTIBASE_BASE58BTC);
+        did.push_str(&base58::encode(&multicodec));
+        did
+    }
+
+    /// Constructs `PublicKey` from a `did:key` identifier.
+    ///
+    /// Returns `Ok(PublicKey)` if `did` is a base58btc `did:key` of an
+    /// Ed25519 public key and `Err` otherwise.
+    pub fn from_did_key(did: &str) -> Result<Self, Error> {
+        let multibase = did
+            .strip_prefix(DID_KEY_PREFIX)
+            .ok_or(Error::InvalidDidKey)?;
+        let encoded = multibase
+            .strip_prefix(MULTIBASE_BASE58BTC)
+            .ok_or(Error::InvalidDidKey)?;
+        if encoded.len() > MAX_ENCODED_LEN {
+            return Err(Error::InvalidDidKey);
+        }
+        let multicodec = base58::decode(encoded).ok_or(Error::InvalidDidKey)?;
+
+        parse_exact(&multicodec, |reader| {
+            if reader.take_array::<2>()? != MULTICODEC_ED25519_PUB {
+                return Err(Error::InvalidDidKey);
+            }
+            Ok(PublicKey(reader.take_array::<PublicKeySize>()?))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // W3C did:key test vectors (ed25519-x25519.json), seeds 0 and 1.
+    const VECTORS: [(&str, &str); 2] = [
+        (
+            "3b6a27bcceb6a42d62a3a8d02a6f0d73653215771de243a63ac048a18b59da29",
+            "did:key:z6MkiTBz1ymuepAQ4HEHYSF1H8quG5GLVVQR3djdX3mDooWp",
+        ),
+        (
+            "4cb5abf6ad79fbf5abbccafcc269d85cd2651ed4b885b5869f241aedf0a5ba29",
+            "did:key:z6MkjchhfUsD6mmvni8mCdXHw216Xrm9bQe2mBH1P5RDjVJG",
+        ),
+    ];
+
+    #[test]
+    fn did_key_vectors() {
+        for (public, did) in VECTORS.iter() {
+            let public = PublicKey::from_bytes(&hex::decode(public).unwrap()).unwrap();
+            assert_eq!(public.to_did_key(), *did);
+            assert_eq!(
+                PublicKey::from_did_key(did).unwrap().as_bytes(),
+                public.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn did_key_spec_examples_roundtrip() {
+        for did in [
+            "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK",
+            "did:key:z6MknGc3ocHs3zdPiJbnaaqDi58NGb4pk1Sp9WxWufuXSdxf",
+        ]
+        .iter()
+        {
+            assert_eq!(PublicKey::from_did_key(did).unwrap().to_did_key(), *did);
+        }
+    }
+
+    #[test]
+    fn did_key_rejects_long_input() {
+        let did = VECTORS[0].1;
+        assert_eq!(did.len(), "did:key:z".len() + MAX_ENCODED_LEN);
+
+        // One character more.
+        assert_eq!(
+            PublicKey::from_did_key(&format!("{}1", did)).err(),
+            Some(Error::InvalidDidKey)
+        );
+        // Megabytes of input are rejected without decoding them.
+        let huge = format!("did:key:z{}", "2".repeat(4 << 20));
+        assert_eq!(
+            PublicKey::from_did_key(&huge).err(),
+            Some(Error::InvalidDidKey)
+        );
+    }
+
+    #[test]
+    fn did_key_rejects_malformed() {
+        let did = VECTORS[0].1;
+
+        // Not a did:key.
+        assert_eq!(
+            PublicKey::from_did_key(&did.replace("did:key:", "did:web:")).err(),
+            Some(Error::InvalidDidKey)
+        );
+        // Other multibase (base32 'b', base64url 'u').
+        assert_eq!(
+            PublicKey::from_did_key(&did.replace(":z", ":b")).err(),
+            Some(Error::InvalidDidKey)
+        );
+        assert_eq!(
+            PublicKey::from_did_key(&did.replace(":z", ":u")).err(),
+            Some(Error::InvalidDidKey)
+        );
+        // Characters outside the base58btc alphabet.
+        assert_eq!(
+            PublicKey::from_did_key(&did.replace("z6Mk", "z6M0")).err(),
+            Some(Error::InvalidDidKey)
+        );
+        assert_eq!(
+            PublicKey::from_did_key(&did.replace("z6Mk", "z6Ml")).err(),
+            Some(Error::InvalidDidKey)
+        );
+        // X25519 multicodec (0xec01).
+        let mut x25519 = vec![0xec, 0x01];
+        x25519.extend_from_slice(&[9u8; 32]);
+        assert_eq!(
+            PublicKey::from_did_key(&format!("did:key:z{}", base58::encode(&x25519))).err(),
+            Some(Error::InvalidDidKey)
+        );
+        // Wrong key lengths. A longer key can't fit in MAX_ENCODED_LEN.
+        let mut long = MULTICODEC_ED25519_PUB.to_vec();
+        long.extend_from_slice(&[9u8; 33]);
+        assert_eq!(
+            PublicKey::from_did_key(&format!("did:key:z{}", base58::encode(&long))).err(),
+            Some(Error::InvalidDidKey)
+        );
+        assert_eq!(
+            PublicKey::from_did_key(&format!("did:key:z{}", base58::encode(&long[..33]))).err(),
+            Some(Error::Truncated { offset: 2 })
+        );
+    }
+}
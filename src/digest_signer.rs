@@ -0,0 +1,138 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// `signature::DigestSigner` and `DigestVerifier` over SHA-512: the caller
+// hashes the message into a `Sha512` of the RustCrypto `sha2` crate, and
+// the signature is Ed25519ph (RFC 8032, section 5.1) over its output,
+// with the empty context, as `SecretKey::sign_prehashed` makes.
+//
+// Ed25519ph signatures are not Ed25519 signatures: the dom2 prefix
+// separates them, so neither verifies as the other, even over the same
+// bytes. The traits are only implemented for `Sha512`, so a digest of
+// another output size doesn't type-check.
+
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::prehash::*;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+use sha2_10::{Digest, Sha512};
+
+/// Signs the finished `Sha512` with Ed25519ph, not Ed25519.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+/// extern crate sha2_10 as sha2;
+/// extern crate signature;
+///
+/// use ed25519_fun::Keypair;
+/// use sha2::{Digest, Sha512};
+/// use signature::{DigestSigner, DigestVerifier};
+///
+/// fn main() {
+///     let keypair = Keypair::generate();
+///     let signature = keypair.sign_digest(Sha512::new().chain_update(b"abc"));
+///     let digest = Sha512::new().chain_update(b"abc");
+///     assert!(DigestVerifier::verify_digest(keypair.public(), digest, &signature).is_ok());
+///     // Not an Ed25519 signature of the message.
+///     assert!(keypair.public().verify(b"abc", &signature).is_err());
+/// }
+/// ```
+#[cfg(not(feature = "verify-only"))]
+impl ::signature::DigestSigner<Sha512, Signature> for Keypair {
+    fn try_sign_digest(&self, digest: Sha512) -> Result<Signature, ::signature::Error> {
+        let domain = ph_domain(None)?;
+        Ok(self
+            .secret
+            .sign_prehash_unchecked(&self.signing_public(), &digest.finalize(), domain))
+    }
+}
+
+/// Signs the finished `Sha512` with Ed25519ph, not Ed25519. The public
+/// key is computed on every call: sign with `Keypair` to avoid that.
+#[cfg(not(feature = "verify-only"))]
+impl ::signature::DigestSigner<Sha512, Signature> for SecretKey {
+    fn try_sign_digest(&self, digest: Sha512) -> Result<Signature, ::signature::Error> {
+        let domain = ph_domain(None)?;
+        Ok(self.sign_prehash_unchecked(&self.public_key(), &digest.finalize(), domain))
+    }
+}
+
+/// Verifies an Ed25519ph signature, not an Ed25519 one, of the finished
+/// `Sha512`.
+impl ::signature::DigestVerifier<Sha512, Signature> for PublicKey {
+    fn verify_digest(
+        &self,
+        digest: Sha512,
+        signature: &Signature,
+    ) -> Result<(), ::signature::Error> {
+        let domain = ph_domain(None)?;
+        self.verify_prehash(&digest.finalize(), domain, signature)
+            .map_err(::signature::Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use crate::errors::*;
+    use ::signature::DigestVerifier;
+
+    // RFC 8032, section 7.3: Ed25519ph, message "abc".
+    #[cfg(not(feature = "verify-only"))]
+    const SECRET_KEY: &str = "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42";
+    const PUBLIC_KEY: &str = "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf";
+    const SIGNATURE: &str = "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+                             31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406";
+
+    fn abc() -> Sha512 {
+        Sha512::new().chain_update(b"abc")
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn digest_signer_rfc8032_ed25519ph() {
+        use ::signature::DigestSigner;
+
+        let secret = SecretKey::from_bytes(&hex::decode(SECRET_KEY).unwrap()).unwrap();
+        // Called through the trait: with the `digest` feature, the
+        // inherent `SecretKey::sign_digest` takes a context too.
+        let signature: Signature = DigestSigner::sign_digest(&secret, abc());
+        assert_eq!(hex::encode(signature.0), SIGNATURE);
+        let keypair = Keypair::from_secret(secret);
+        let signature: Signature = keypair.sign_digest(abc());
+        assert_eq!(hex::encode(signature.0), SIGNATURE);
+
+        // Hashing in pieces is the same digest.
+        let mut digest = Sha512::new();
+        digest.update(b"a");
+        digest.update(b"bc");
+        assert_eq!(keypair.sign_digest(digest), signature);
+    }
+
+    #[test]
+    fn digest_verifier_rfc8032_ed25519ph() {
+        let public = PublicKey::from_bytes(&hex::decode(PUBLIC_KEY).unwrap()).unwrap();
+        let signature = Signature::from_bytes(&hex::decode(SIGNATURE).unwrap()).unwrap();
+        assert!(DigestVerifier::verify_digest(&public, abc(), &signature).is_ok());
+
+        let abd = Sha512::new().chain_update(b"abd");
+        let error = DigestVerifier::verify_digest(&public, abd, &signature).unwrap_err();
+        assert_eq!(
+            Error::from_signature_error(&error),
+            Error::SignatureMismatch
+        );
+
+        // Ed25519ph isn't Ed25519: neither over the message nor over its
+        // prehash.
+        assert!(public.verify(b"abc", &signature).is_err());
+        assert!(public.verify(&abc().finalize(), &signature).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// DKIM ed25519-sha256 (RFC 8463): the signature step only.
+//
+// The caller canonicalizes the signed header fields (relaxed or simple),
+// with the DKIM-Signature field last, its b= value empty and no trailing
+// CRLF, and computes bh= over the body. Ed25519 then signs the SHA-256
+// of that header block: RFC 8463 keeps the RFC 6376 hash step, so the
+// 32-byte digest, not the header block, is the Ed25519 message.
+//
+// Public keys are published as the base64 of the raw 32-byte key in the
+// p= tag of the selector's TXT record.
+
+use crate::b64;
+use crate::constants::*;
+use crate::errors::*;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+use sha2::{Digest, Sha256};
+
+/// The SHA-256 of the canonicalized header block.
+fn header_hash(canonical_headers: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Sha256::digest(canonical_headers));
+    hash
+}
+
+/// Decodes a base64 tag value, which may be folded: whitespace is
+/// ignored (RFC 6376, section 3.2).
+fn decode_tag(value: &str, length: Error) -> Result<Vec<u8>, Error> {
+    let unfolded: String = value
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '\t' | '\r' | '\n'))
+        .collect();
+    b64::decode(b64::STANDARD, &unfolded).map_err(|_| length)
+}
+
+impl PublicKey {
+    /// Encodes `PublicKey` as the `p=` value of a DKIM key record.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let p = "11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=";
+    ///     let public_key = PublicKey::from_dkim_p(p).unwrap();
+    ///     assert_eq!(public_key.to_dkim_p(), p);
+    /// }
+    /// ```
+    pub fn to_dkim_p(&self) -> String {
+        b64::encode(b64::STANDARD, &self.0)
+    }
+
+    /// Decodes the `p=` value of a DKIM `k=ed25519` key record.
+    ///
+    /// Returns `Ok(PublicKey)` if `p` is the base64 of 32 bytes and
+    /// `Err(Error::InvalidPublicKey)` otherwise.
+    pub fn from_dkim_p(p: &str) -> Result<Self, Error> {
+        PublicKey::from_bytes(&decode_tag(p, Error::InvalidPublicKey)?)
+            .map_err(|_| Error::InvalidPublicKey)
+    }
+}
+
+/// Signs a canonicalized header block with ed25519-sha256.
+///
+/// Returns the base64 `b=` value, or `Err(Error::PublicKeyMismatch)` if
+/// `public` isn't the public key of `secret`.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::dkim::{sign_headers, verify_headers};
+/// use ed25519_fun::Keypair;
+///
+/// fn main() {
+///     let keypair = Keypair::generate();
+///     let headers = b"from:Joe <joe@example.com>\r\ndkim-signature:v=1; a=ed25519-sha256; b=";
+///     let b = sign_headers(keypair.secret(), keypair.public(), headers).unwrap();
+///     assert!(verify_headers(keypair.public(), headers, &b).is_ok());
+/// }
+/// ```
+#[cfg(not(feature = "verify-only"))]
+pub fn sign_headers(
+    secret: &SecretKey,
+    public: &PublicKey,
+    canonical_headers: &[u8],
+) -> Result<String, Error> {
+    let signature = secret.sign(public, &header_hash(canonical_headers))?;
+    Ok(b64::encode(b64::STANDARD, &signature.0))
+}
+
+/// Verifies the `b=` value of an ed25519-sha256 DKIM-Signature over a
+/// canonicalized header block.
+///
+/// Returns `Ok(())` if the signature is valid,
+/// `Err(Error::InvalidSignatureEncoding)` if `b` isn't the base64 of 64
+/// bytes and `Err` otherwise.
+pub fn verify_headers(public: &PublicKey, canonical_headers: &[u8], b: &str) -> Result<(), Error> {
+    let bytes = decode_tag(b, Error::InvalidSignatureEncoding)?;
+    if bytes.len() != SignatureSize {
+        return Err(Error::InvalidSignatureEncoding);
+    }
+    let signature = Signature::from_bytes(&bytes)?;
+    public.verify(&header_hash(canonical_headers), &signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The ed25519-sha256 signature of RFC 8463, appendix A. The secret
+    // key is the brisbane selector's, and the header block is the
+    // relaxed canonicalization of the example message's From, To,
+    // Subject, Date and Message-ID fields and of its DKIM-Signature field
+    // with b= emptied.
+    #[cfg(not(feature = "verify-only"))]
+    const SECRET_KEY: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    const P: &str = "11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=";
+    const HEADERS: &str = "from:Joe SixPack <joe@football.example.com>\r\n\
+                           to:Suzie Q <suzie@shopping.example.net>\r\n\
+                           subject:Is dinner ready?\r\n\
+                           date:Fri, 11 Jul 2003 21:00:37 -0700 (PDT)\r\n\
+                           message-id:<20030712040037.46341.5F8J@football.example.com>\r\n\
+                           dkim-signature:v=1; a=ed25519-sha256; c=relaxed/relaxed; \
+                           d=football.example.com; i=@football.example.com; q=dns/txt; \
+                           s=brisbane; t=1528637909; h=from : to : subject : date : \
+                           message-id : from : subject : date; \
+                           bh=2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=; b=";
+    // As folded in the message.
+    const B: &str = "/gCrinpcQOoIfuHNQIbq4pgh9kyIK3AQUdt9OdqQehSwhEIug4D11Bus\r\n \
+                     Fa3bT3FY5OsU7ZbnKELq+eXdp1Q1Dw==";
+
+    #[test]
+    fn rfc8463_signature_verifies() {
+        let public = PublicKey::from_dkim_p(P).unwrap();
+        assert!(verify_headers(&public, HEADERS.as_bytes(), B).is_ok());
+
+        // A changed header or signature fails.
+        let altered = HEADERS.replace("dinner", "Dinner");
+        assert_eq!(
+            verify_headers(&public, altered.as_bytes(), B),
+            Err(Error::SignatureMismatch)
+        );
+        let altered = B.replace("Dw==", "Dg==");
+        assert!(verify_headers(&public, HEADERS.as_bytes(), &altered).is_err());
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn rfc8463_signature_signs() {
+        extern crate hex;
+
+        let secret = SecretKey::from_bytes(&hex::decode(SECRET_KEY).unwrap()).unwrap();
+        let public = secret.public_key();
+        assert_eq!(public.to_dkim_p(), P);
+        let b = sign_headers(&secret, &public, HEADERS.as_bytes()).unwrap();
+        assert_eq!(b, B.replace("\r\n ", ""));
+
+        let other = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        assert_eq!(
+            sign_headers(&other, &public, HEADERS.as_bytes()).err(),
+            Some(Error::PublicKeyMismatch)
+        );
+    }
+
+    #[test]
+    fn dkim_rejects_malformed_values() {
+        let public = PublicKey::from_dkim_p(P).unwrap();
+        for p in [
+            "",
+            "11qYAYKx",
+            "11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo",
+            "!",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                PublicKey::from_dkim_p(p).err(),
+                Some(Error::InvalidPublicKey),
+                "{}",
+                p
+            );
+        }
+        // Folded p= values are unfolded.
+        assert!(PublicKey::from_dkim_p(&P.replace("S/7", "S/\r\n 7")).is_ok());
+
+        let b = B.replace("\r\n ", "");
+        for b in [
+            "".to_string(),
+            b[..b.len() - 4].to_string(),
+            format!("{}AAAA", b),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                verify_headers(&public, HEADERS.as_bytes(), b).err(),
+                Some(Error::InvalidSignatureEncoding),
+                "{}",
+                b
+            );
+        }
+    }
+}
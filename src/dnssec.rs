@@ -0,0 +1,359 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// DNSSEC with Ed25519, algorithm 15 (RFC 8080).
+//
+// The DNSKEY RDATA of a key is flags (2 bytes), protocol (1 byte),
+// algorithm (1 byte) and the 32-byte key. An RRSIG signs its own RDATA
+// up to the signer's name, followed by the canonical form of the RRset
+// (RFC 4034, section 3.1.8.1):
+//
+//   type covered (2) | algorithm (1) | labels (1) | original TTL (4)
+//   | expiration (4) | inception (4) | key tag (2) | signer's name
+//   | RR(1) | RR(2) | ...
+//
+// Every field is big-endian. Putting the records in canonical form
+// (lowercased names, sorted RDATA) is left to the caller, who has the
+// zone.
+
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::public::*;
+use crate::reader::*;
+use crate::signature::*;
+
+use sha2::digest::Input;
+
+/// The DNSSEC algorithm number of Ed25519.
+pub const ALGORITHM: u8 = 15;
+
+/// The length of the DNSKEY RDATA of an Ed25519 key.
+pub const DNSKEY_RDATA_SIZE: usize = 4 + PublicKeySize;
+
+/// The length of the RRSIG RDATA fields before the signer's name.
+const RRSIG_FIXED_SIZE: usize = 18;
+
+/// The longest domain name on the wire.
+const MAX_NAME_SIZE: usize = 255;
+
+/// Computes the key tag of a DNSKEY RDATA (RFC 4034, Appendix B).
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::dnssec::key_tag;
+/// use ed25519_fun::PublicKey;
+///
+/// fn main() {
+///     // The key of the first example of RFC 8080.
+///     let public_key = PublicKey::from_array([
+///         0x97, 0x4d, 0x96, 0xa2, 0x2d, 0x22, 0x4b, 0xc0, 0x1a, 0xdb, 0x91, 0x50, 0x91, 0x47,
+///         0x7d, 0x44, 0xcc, 0xd9, 0x1c, 0x9a, 0x41, 0xa1, 0x14, 0x30, 0x01, 0x01, 0x17, 0xd5,
+///         0x2c, 0x59, 0x24, 0x0e,
+///     ]);
+///     assert_eq!(key_tag(&public_key.to_dnskey_rdata(257, 3)), 3613);
+/// }
+/// ```
+pub fn key_tag(dnskey_rdata: &[u8]) -> u16 {
+    let mut sum = 0u32;
+    for (i, &byte) in dnskey_rdata.iter().enumerate() {
+        sum += if i & 1 == 0 {
+            (byte as u32) << 8
+        } else {
+            byte as u32
+        };
+    }
+    sum += (sum >> 16) & 0xffff;
+    sum as u16
+}
+
+/// Checks the RRSIG RDATA up to the signer's name: an Ed25519 signature,
+/// and a wire-format name that ends the prefix.
+fn check_rrsig_prefix(prefix: &[u8]) -> Result<(), Error> {
+    parse_exact(prefix, |reader| {
+        let fixed = reader.take(RRSIG_FIXED_SIZE)?;
+        if fixed[2] != ALGORITHM {
+            return Err(Error::InvalidRrsig);
+        }
+        let mut name_size = 0;
+        loop {
+            let len = reader.read_u8()? as usize;
+            // Compression pointers and extended labels have the top bits
+            // set; neither may appear in the signed data.
+            if len > 63 {
+                return Err(Error::InvalidRrsig);
+            }
+            name_size += 1 + len;
+            if name_size > MAX_NAME_SIZE {
+                return Err(Error::InvalidRrsig);
+            }
+            if len == 0 {
+                return Ok(());
+            }
+            reader.take(len)?;
+        }
+    })
+    .map_err(|_| Error::InvalidRrsig)
+}
+
+/// Feeds the signed data of an RRSIG to `hash`.
+fn absorb<H: Input>(hash: &mut H, rrsig_rdata_prefix: &[u8], canonical_rrset: &[u8]) {
+    hash.input(rrsig_rdata_prefix);
+    hash.input(canonical_rrset);
+}
+
+impl PublicKey {
+    /// Encodes `PublicKey` as DNSKEY RDATA with `flags` (256 for a zone
+    /// key, 257 for a secure entry point) and `protocol` (always 3).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([0x5a; 32]);
+    ///     let rdata = public_key.to_dnskey_rdata(257, 3);
+    ///     let (flags, protocol, decoded) = PublicKey::from_dnskey_rdata(&rdata).unwrap();
+    ///     assert_eq!((flags, protocol), (257, 3));
+    ///     assert_eq!(decoded.as_bytes(), public_key.as_bytes());
+    /// }
+    /// ```
+    pub fn to_dnskey_rdata(&self, flags: u16, protocol: u8) -> [u8; DNSKEY_RDATA_SIZE] {
+        let mut rdata = [0u8; DNSKEY_RDATA_SIZE];
+        rdata[..2].copy_from_slice(&flags.to_be_bytes());
+        rdata[2] = protocol;
+        rdata[3] = ALGORITHM;
+        rdata[4..].copy_from_slice(&self.0);
+        rdata
+    }
+
+    /// Decodes DNSKEY RDATA.
+    ///
+    /// Returns `Ok((flags, protocol, PublicKey))` if `rdata` is the
+    /// DNSKEY RDATA of an Ed25519 key and `Err(Error::InvalidDnskey)`
+    /// otherwise.
+    pub fn from_dnskey_rdata(rdata: &[u8]) -> Result<(u16, u8, PublicKey), Error> {
+        parse_exact(rdata, |reader| {
+            let header = reader.take_array::<4>()?;
+            if header[3] != ALGORITHM {
+                return Err(Error::InvalidDnskey);
+            }
+            let public = PublicKey(reader.take_array::<PublicKeySize>()?);
+            Ok((
+                u16::from_be_bytes([header[0], header[1]]),
+                header[2],
+                public,
+            ))
+        })
+        .map_err(|_| Error::InvalidDnskey)
+    }
+}
+
+/// Signs an RRset, as the signature field of its RRSIG.
+///
+/// `rrsig_rdata_prefix` is the RRSIG RDATA up to and including the
+/// signer's name, and `canonical_rrset` the records in canonical form
+/// and order.
+///
+/// Returns `Signature`, or `Err(Error::InvalidRrsig)` if the prefix isn't
+/// of an Ed25519 RRSIG.
+#[cfg(not(feature = "verify-only"))]
+pub fn sign_rrsig(
+    keypair: &Keypair,
+    rrsig_rdata_prefix: &[u8],
+    canonical_rrset: &[u8],
+) -> Result<Signature, Error> {
+    check_rrsig_prefix(rrsig_rdata_prefix)?;
+    let signature = keypair
+        .secret
+        .sign_absorbing(&keypair.signing_public(), None, |hash| {
+            absorb(hash, rrsig_rdata_prefix, canonical_rrset)
+        });
+    #[cfg(feature = "observer")]
+    observer::signed(&keypair.public, |digest| {
+        absorb(digest, rrsig_rdata_prefix, canonical_rrset)
+    });
+    Ok(signature)
+}
+
+/// Verifies the signature of an RRSIG over an RRset.
+///
+/// `rrsig_rdata_prefix` is the RRSIG RDATA up to and including the
+/// signer's name, and `canonical_rrset` the records in canonical form
+/// and order. The validity period and the key tag are the caller's to
+/// check.
+///
+/// Returns `Ok(())` if the signature is valid,
+/// `Err(Error::InvalidRrsig)` if the prefix isn't of an Ed25519 RRSIG and
+/// `Err` otherwise.
+pub fn verify_rrsig(
+    public: &PublicKey,
+    rrsig_rdata_prefix: &[u8],
+    canonical_rrset: &[u8],
+    signature: &Signature,
+) -> Result<(), Error> {
+    check_rrsig_prefix(rrsig_rdata_prefix)?;
+    public.verify_absorbing(signature, None, |hash| {
+        absorb(hash, rrsig_rdata_prefix, canonical_rrset)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // The two examples of RFC 8080, section 6: the DNSKEY, key tag and
+    // RRSIG of the example.com MX RRset under each key. The signed data
+    // is laid out from the presentation format of the RFC.
+    struct Example {
+        #[cfg_attr(feature = "verify-only", allow(dead_code))]
+        seed: &'static str,
+        public: &'static str,
+        tag: u16,
+        rrsig_prefix: &'static str,
+        signature: &'static str,
+    }
+
+    const EXAMPLES: [Example; 2] = [
+        Example {
+            // ODIyNjAzODQ2MjgwODAxMjI2NDUxOTAyMDQxNDIyNjI=
+            seed: "3832323630333834363238303830313232363435313930323034313432323632",
+            // l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=
+            public: "974d96a22d224bc01adb915091477d44ccd91c9a41a11430010117d52c59240e",
+            tag: 3613,
+            rrsig_prefix: "000f0f0200000e1055d4fc6055b94ce00e1d076578616d706c6503636f6d00",
+            signature: "a0bf64ac9ba7ef17c138859c1878bb99a839fe1759aca5b0d798cf1ab1e98d07\
+                        9102f4ddb3368f0fe40bb377f1f00e0cddedb799167d56b6e932783072ba8d02",
+        },
+        Example {
+            // DSSF3o0s0f+ElWzj9E/Osxw8hLpk55chkmx0LYN5WiY=
+            seed: "0d2485de8d2cd1ff84956ce3f44fceb31c3c84ba64e79721926c742d83795a26",
+            // zPnZ/QwEe7S8C5SPz2OfS5RR40ATk2/rYnE9xHIEijs=
+            public: "ccf9d9fd0c047bb4bc0b948fcf639f4b9451e34013936feb62713dc472048a3b",
+            tag: 35217,
+            rrsig_prefix: "000f0f0200000e1055d4fc6055b94ce08991076578616d706c6503636f6d00",
+            signature: "cd74346e4620413105c9f2f28bd428898e83f19758a38c32521562a1865715d4\
+                        f8d7440f4484d04aa2529f34284a6e69a09ee00fb0104743bb2ae239936a5c06",
+        },
+    ];
+
+    // example.com. 3600 IN MX 10 mail.example.com.
+    const MX_RRSET: &str =
+        "076578616d706c6503636f6d00000f000100000e100014000a046d61696c076578616d706c6503636f6d00";
+
+    fn public(example: &Example) -> PublicKey {
+        PublicKey::from_bytes(&hex::decode(example.public).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn rfc8080_dnskey_and_key_tag() {
+        for example in EXAMPLES.iter() {
+            let rdata = public(example).to_dnskey_rdata(257, 3);
+            assert_eq!(key_tag(&rdata), example.tag);
+            let (flags, protocol, decoded) = PublicKey::from_dnskey_rdata(&rdata).unwrap();
+            assert_eq!((flags, protocol), (257, 3));
+            assert_eq!(decoded, public(example));
+        }
+
+        let rdata = public(&EXAMPLES[0]).to_dnskey_rdata(257, 3);
+        let mut other = rdata;
+        other[3] = 13;
+        for bad in [&other[..], &rdata[..35], &[&rdata[..], &[0][..]].concat()].iter() {
+            assert_eq!(
+                PublicKey::from_dnskey_rdata(bad).err(),
+                Some(Error::InvalidDnskey)
+            );
+        }
+    }
+
+    #[test]
+    fn rfc8080_rrsig_verifies() {
+        let rrset = hex::decode(MX_RRSET).unwrap();
+        for example in EXAMPLES.iter() {
+            let prefix = hex::decode(example.rrsig_prefix).unwrap();
+            let signature =
+                Signature::from_bytes(&hex::decode(example.signature).unwrap()).unwrap();
+            assert!(verify_rrsig(&public(example), &prefix, &rrset, &signature).is_ok());
+
+            // A flipped bit anywhere in the signed data fails.
+            for i in [0, prefix.len() - 1].iter() {
+                let mut altered = prefix.clone();
+                altered[*i] ^= 1;
+                assert!(verify_rrsig(&public(example), &altered, &rrset, &signature).is_err());
+            }
+            let mut altered = rrset.clone();
+            altered[rrset.len() - 3] ^= 0x20;
+            assert!(verify_rrsig(&public(example), &prefix, &altered, &signature).is_err());
+            let mut altered = signature.to_bytes();
+            altered[0] ^= 1;
+            assert!(verify_rrsig(
+                &public(example),
+                &prefix,
+                &rrset,
+                &Signature::from_bytes(&altered).unwrap()
+            )
+            .is_err());
+        }
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn rfc8080_rrsig_signs() {
+        use crate::secret::*;
+
+        let rrset = hex::decode(MX_RRSET).unwrap();
+        for example in EXAMPLES.iter() {
+            let secret = SecretKey::from_bytes(&hex::decode(example.seed).unwrap()).unwrap();
+            let keypair = Keypair::from_secret(secret);
+            assert_eq!(*keypair.public(), public(example));
+            let prefix = hex::decode(example.rrsig_prefix).unwrap();
+            let signature = sign_rrsig(&keypair, &prefix, &rrset).unwrap();
+            assert_eq!(hex::encode(signature.to_bytes()), example.signature);
+        }
+    }
+
+    #[test]
+    fn rrsig_prefix_is_checked() {
+        let prefix = hex::decode(EXAMPLES[0].rrsig_prefix).unwrap();
+        let mut other_algorithm = prefix.clone();
+        other_algorithm[2] = 13;
+        let mut pointer = prefix[..RRSIG_FIXED_SIZE].to_vec();
+        pointer.extend_from_slice(&[0xc0, 0x0c]);
+        let mut long_name = prefix[..RRSIG_FIXED_SIZE].to_vec();
+        for _ in 0..5 {
+            long_name.push(63);
+            long_name.extend_from_slice(&[b'a'; 63]);
+        }
+        long_name.push(0);
+        let cases = [
+            other_algorithm,
+            pointer,
+            long_name,
+            // No terminating root label, and bytes after it.
+            prefix[..prefix.len() - 1].to_vec(),
+            [&prefix[..], &[0][..]].concat(),
+            prefix[..RRSIG_FIXED_SIZE].to_vec(),
+            Vec::new(),
+        ];
+        let signature = Signature::from_bytes(&[0u8; 64]).unwrap();
+        for case in cases.iter() {
+            assert_eq!(
+                verify_rrsig(&public(&EXAMPLES[0]), case, &[], &signature).err(),
+                Some(Error::InvalidRrsig),
+                "{}",
+                hex::encode(case)
+            );
+        }
+    }
+}
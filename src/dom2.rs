@@ -0,0 +1,168 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// dom2(x, y), the prefix separating Ed25519ctx and Ed25519ph from Ed25519
+// and from each other. See RFC 8032, section 5.1.
+
+use crate::hasher::*;
+
+const DOM2_PREFIX: &[u8; 32] = b"SigEd25519 no Ed25519 collisions";
+
+/// The longest context dom2 can encode.
+pub(crate) const MAX_CONTEXT_LEN: usize = 255;
+
+/// Absorbs dom2(`phflag`, `context`): the prefix, the flag (1 for
+/// Ed25519ph, 0 for Ed25519ctx), the context length and the context.
+///
+/// Callers reject contexts longer than `MAX_CONTEXT_LEN` before signing
+/// or verifying.
+pub(crate) fn write_dom2<H: Sha512Provider>(hasher: &mut H, phflag: u8, context: &[u8]) {
+    debug_assert!(phflag <= 1);
+    debug_assert!(context.len() <= MAX_CONTEXT_LEN);
+
+    hasher.update(DOM2_PREFIX);
+    hasher.update(&[phflag, context.len() as u8]);
+    hasher.update(context);
+}
+
+/// Absorbs the domain of a signature: dom2 for `Some((phflag, context))`
+/// and nothing for pure Ed25519 (`None`).
+pub(crate) fn write_domain<H: Sha512Provider>(hasher: &mut H, dom2: Option<(u8, &[u8])>) {
+    if let Some((phflag, context)) = dom2 {
+        write_dom2(hasher, phflag, context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    #[cfg(not(feature = "verify-only"))]
+    use crate::keypair::*;
+    #[cfg(not(feature = "verify-only"))]
+    use crate::secret::*;
+    #[cfg(not(feature = "verify-only"))]
+    use crate::signature::*;
+    #[cfg(not(feature = "verify-only"))]
+    use sha2::{Digest, Sha512};
+
+    // Records the absorbed bytes.
+    #[derive(Default)]
+    struct Recorder(Vec<u8>);
+
+    impl Sha512Provider for Recorder {
+        fn update(&mut self, data: &[u8]) {
+            self.0.extend_from_slice(data);
+        }
+
+        fn finalize(self) -> [u8; 64] {
+            unreachable!("only the absorbed bytes are read")
+        }
+    }
+
+    fn absorbed(dom2: Option<(u8, &[u8])>) -> Vec<u8> {
+        let mut recorder = Recorder::default();
+        write_domain(&mut recorder, dom2);
+        recorder.0
+    }
+
+    #[test]
+    fn dom2_exact_bytes() {
+        let prefix = b"SigEd25519 no Ed25519 collisions".to_vec();
+        let long = [0xa5u8; MAX_CONTEXT_LEN];
+
+        // Ed25519ph with the empty context.
+        assert_eq!(absorbed(Some((1, b""))), [&prefix[..], &[1, 0]].concat());
+        for phflag in 0..2 {
+            assert_eq!(
+                absorbed(Some((phflag, b"x"))),
+                [&prefix[..], &[phflag, 1], b"x"].concat()
+            );
+            assert_eq!(
+                absorbed(Some((phflag, &long))),
+                [&prefix[..], &[phflag, 255], &long[..]].concat()
+            );
+        }
+        // Pure Ed25519 absorbs nothing.
+        assert!(absorbed(None).is_empty());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic]
+    fn dom2_rejects_long_context() {
+        absorbed(Some((0, &[0u8; MAX_CONTEXT_LEN + 1])));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic]
+    fn dom2_rejects_unknown_flag() {
+        absorbed(Some((2, b"")));
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    fn sign(keypair: &Keypair, dom2: Option<(u8, &[u8])>, message: &[u8]) -> Signature {
+        keypair
+            .secret
+            .sign_absorbing(&keypair.public, dom2, |hash| hash.update(message))
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    fn verify(
+        keypair: &Keypair,
+        dom2: Option<(u8, &[u8])>,
+        message: &[u8],
+        sig: &Signature,
+    ) -> bool {
+        keypair
+            .public
+            .verify_absorbing(sig, dom2, |hash| hash.update(message))
+            .is_ok()
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn dom2_ed25519ph_vector() {
+        // RFC 8032, section 7.3: Ed25519ph, message "abc".
+        let secret = "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42";
+        let public = "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf";
+        let expected = "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae4131f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406";
+
+        let secret = SecretKey::from_bytes(&hex::decode(secret).unwrap()).unwrap();
+        let keypair = Keypair::from_secret(secret);
+        assert_eq!(format!("{:x}", keypair.public), public);
+
+        let prehash = Sha512::digest(b"abc");
+        let signature = sign(&keypair, Some((1, b"")), &prehash);
+        assert_eq!(hex::encode(signature.as_bytes()), expected);
+        assert!(verify(&keypair, Some((1, b"")), &prehash, &signature));
+    }
+
+    #[cfg(all(
+        not(feature = "verify-only"),
+        any(feature = "rand", feature = "getrandom")
+    ))]
+    #[test]
+    fn dom2_separates_variants() {
+        let keypair = Keypair::generate();
+        let message = b"message";
+        let domains: [Option<(u8, &[u8])>; 5] = [
+            None,
+            Some((0, b"")),
+            Some((1, b"")),
+            Some((0, b"ctx")),
+            Some((1, b"ctx")),
+        ];
+        for signed in domains.iter() {
+            let signature = sign(&keypair, *signed, message);
+            for verified in domains.iter() {
+                assert_eq!(
+                    verify(&keypair, *verified, message, &signature),
+                    signed == verified
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Conversions to and from the types of the `ed25519` crate, the common
+// ground of the Rust Ed25519 implementations: its `Signature`, and the
+// `KeypairBytes` and `PublicKeyBytes` of its PKCS#8 (RFC 8410) support.
+// Code written against those types can take the keys and signatures of
+// this crate as they are.
+
+#[cfg(not(feature = "verify-only"))]
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+#[cfg(not(feature = "verify-only"))]
+use ed25519::pkcs8::KeypairBytes;
+use ed25519::pkcs8::PublicKeyBytes;
+#[cfg(not(feature = "verify-only"))]
+use std::convert::TryFrom;
+
+/// Converts an `ed25519::Signature`, which holds the same 64 bytes.
+impl From<ed25519::Signature> for Signature {
+    fn from(signature: ed25519::Signature) -> Self {
+        Signature::from_array(signature.to_bytes())
+    }
+}
+
+/// Converts into an `ed25519::Signature`, which holds the same 64 bytes.
+impl From<Signature> for ed25519::Signature {
+    fn from(signature: Signature) -> Self {
+        ed25519::Signature::from_bytes(signature.as_bytes())
+    }
+}
+
+/// Converts `PublicKeyBytes`, which holds the same 32 bytes.
+impl From<PublicKeyBytes> for PublicKey {
+    fn from(public: PublicKeyBytes) -> Self {
+        PublicKey::from_array(public.0)
+    }
+}
+
+/// Converts into `PublicKeyBytes`, which holds the same 32 bytes.
+impl From<PublicKey> for PublicKeyBytes {
+    fn from(public: PublicKey) -> Self {
+        PublicKeyBytes(public.to_bytes())
+    }
+}
+
+/// Converts into `KeypairBytes`, with the public key. `KeypairBytes` wipes
+/// its secret key when dropped.
+#[cfg(not(feature = "verify-only"))]
+impl From<&Keypair> for KeypairBytes {
+    fn from(keypair: &Keypair) -> Self {
+        KeypairBytes::from_bytes(&keypair.to_bytes_zeroizing())
+    }
+}
+
+/// Converts `KeypairBytes`, as `Keypair::from_bytes` does if it has a
+/// public key, and deriving the public key from the secret key if not.
+///
+/// Returns `Err(Error::InvalidKeypair)` if the public key isn't the one of
+/// the secret key.
+#[cfg(not(feature = "verify-only"))]
+impl TryFrom<&KeypairBytes> for Keypair {
+    type Error = Error;
+
+    fn try_from(bytes: &KeypairBytes) -> Result<Self, Error> {
+        match &bytes.public_key {
+            Some(public) => {
+                let mut keypair = zeroize::Zeroizing::new([0u8; 64]);
+                keypair[..32].copy_from_slice(&bytes.secret_key);
+                keypair[32..].copy_from_slice(&public.0);
+                Keypair::from_bytes(&keypair[..])
+            }
+            None => Ok(Keypair::from_secret(SecretKey::from_array(
+                bytes.secret_key,
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate base64;
+    extern crate hex;
+
+    use super::*;
+    #[cfg(not(feature = "verify-only"))]
+    use base64::Engine;
+    #[cfg(not(feature = "verify-only"))]
+    use ed25519::pkcs8::{DecodePrivateKey, EncodePrivateKey};
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn ed25519_signature_round_trip() {
+        let keypair = Keypair::generate();
+        let signature = keypair.sign(b"interop");
+
+        let theirs = ed25519::Signature::from(signature);
+        assert_eq!(theirs.to_bytes(), signature.to_bytes());
+        assert_eq!(theirs.r_bytes(), signature.r_bytes());
+        let ours = Signature::from(theirs);
+        assert_eq!(ours, signature);
+        assert!(keypair.public().verify(b"interop", &ours).is_ok());
+
+        let public = PublicKeyBytes::from(*keypair.public());
+        assert!(PublicKey::from(public) == *keypair.public());
+    }
+
+    #[test]
+    fn ed25519_signature_verifies() {
+        // RFC 8032, TEST 1.
+        let public: PublicKey = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+            .parse()
+            .unwrap();
+        let bytes = hex::decode("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap();
+        let theirs = ed25519::Signature::from_slice(&bytes).unwrap();
+        assert!(public.verify(b"", &Signature::from(theirs)).is_ok());
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn keypair_bytes_round_trip() {
+        let keypair = Keypair::generate();
+        let bytes = KeypairBytes::from(&keypair);
+        assert!(bytes.public_key == Some(PublicKeyBytes(keypair.public().to_bytes())));
+        let restored = Keypair::try_from(&bytes).unwrap();
+        assert_eq!(restored.sign(b"x"), keypair.sign(b"x"));
+
+        // Through a PKCS#8 v2 document and back.
+        let der = bytes.to_pkcs8_der().unwrap();
+        let decoded = KeypairBytes::from_pkcs8_der(der.as_bytes()).unwrap();
+        assert!(Keypair::try_from(&decoded).unwrap().public() == keypair.public());
+
+        // A public key that isn't the secret key's.
+        let mut mismatched = KeypairBytes::from(&keypair);
+        mismatched.public_key = Some(PublicKeyBytes(Keypair::generate().public().to_bytes()));
+        assert!(matches!(
+            Keypair::try_from(&mismatched),
+            Err(Error::InvalidKeypair)
+        ));
+    }
+
+    // RFC 8410, section 10.3: a PKCS#8 v1 private key, without the public
+    // key, whose public key is the one of section 10.1.
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn keypair_bytes_rfc8410() {
+        let der = base64::engine::general_purpose::STANDARD
+            .decode("MC4CAQAwBQYDK2VwBCIEINTuctv5E1hK1bbY8fdp+K06/nwoy/HU++CXqI9EdVhC")
+            .unwrap();
+        let bytes = KeypairBytes::from_pkcs8_der(&der).unwrap();
+        assert!(bytes.public_key.is_none());
+        let keypair = Keypair::try_from(&bytes).unwrap();
+        assert_eq!(
+            format!("{:x}", keypair.public()),
+            "19bf44096984cdfe8541bac167dc3b96c85086aa30b6b6cb0c5c38ad703166e1"
+        );
+    }
+}
@@ -6,40 +6,577 @@
 
 use core::fmt::{self, Display};
 
+/// The errors of every fallible operation in the crate.
+///
+/// `SignatureMismatch` and `KeyIdMismatch` are the cryptographic failures:
+/// the input was well formed but the signature doesn't verify under the
+/// key. Every other variant rejects malformed input or misuse, see
+/// `is_mismatch`. More variants may be added in any release.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/error.rs")]
+/// ```
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
     /// The signature doesn't verify.
     SignatureMismatch,
-    /// A weak public key was used.
-    WeakPublicKey,
-    /// The public key is invalid.
+    /// The public key isn't the encoding of a curve point, or its text
+    /// form is malformed.
     InvalidPublicKey,
-    /// The secret key is invalid.
+    /// The public key doesn't have the expected length.
+    InvalidPublicKeyLength { expected: usize, got: usize },
+    /// The secret key's text form is malformed, or the public key stored
+    /// with it isn't its own.
     InvalidSecretKey,
-    /// The signature is invalid.
-    InvalidSignature,
-    /// The noise doesn't have the expected length.
-    InvalidNoise,
-    /// The keypair doesn't have the expected length.
+    /// The secret key doesn't have the expected length.
+    InvalidSecretKeyLength { expected: usize, got: usize },
+    /// The scalar s of the signature isn't reduced modulo L.
+    NonCanonicalScalar,
+    /// The point R of the signature isn't the encoding of a curve point.
+    InvalidSignaturePoint,
+    /// The keypair isn't a 32-byte seed followed by its public key.
     InvalidKeypair,
+    /// The keypair doesn't have the expected length.
+    InvalidKeypairLength { expected: usize, got: usize },
     /// The signature doesn't have the expected length.
-    InvalidSignatureLength,
+    InvalidSignatureLength { expected: usize, got: usize },
+    /// The signature's text form is malformed or doesn't decode to 64
+    /// bytes.
+    InvalidSignatureEncoding,
+    /// The COSE_Key is malformed or not an Ed25519 key.
+    InvalidCoseKey,
+    /// The COSE_Sign1 structure is malformed.
+    InvalidCoseSign1,
+    /// The multi-part message has no room for another part.
+    MultiPartCapacity,
+    /// The input ended before the structure was complete.
+    Truncated { offset: usize },
+    /// The input has bytes left after the structure.
+    TrailingBytes { consumed: usize, total: usize },
+    /// The token is malformed.
+    InvalidToken,
+    /// The token footer doesn't match the expected one.
+    FooterMismatch,
+    /// The did:key identifier is malformed or not an Ed25519 key.
+    InvalidDidKey,
+    /// The libp2p public key is malformed or not an Ed25519 key.
+    InvalidLibp2pKey,
+    /// A declared length exceeds `MAX_MESSAGE_LEN` or the target's `usize`.
+    LengthOverflow,
+    /// The minisign public key is malformed or not an Ed25519 key.
+    InvalidMinisignKey,
+    /// The minisign signature file is malformed.
+    InvalidMinisignSignature,
+    /// The signature was made with another key.
+    KeyIdMismatch,
+    /// The base64 input is malformed at the given offset.
+    InvalidBase64 { offset: usize },
+    /// The output doesn't fit in the given buffer.
+    BufferTooSmall,
+    /// The signify public key is malformed or not an Ed25519 key.
+    InvalidSignifyKey,
+    /// The signify signature is malformed.
+    InvalidSignifySignature,
+    /// The base58 input has a character outside the alphabet.
+    InvalidBase58,
+    /// The checksum of the encoded string (base58check, bech32m, strkey
+    /// or SS58) doesn't match its payload.
+    ChecksumMismatch,
+    /// The bech32m string is malformed.
+    InvalidBech32,
+    /// The bech32m human-readable part isn't the expected one.
+    HrpMismatch,
+    /// The mnemonic doesn't have 12, 15, 18, 21 or 24 words.
+    InvalidMnemonicLength { words: usize },
+    /// The word at `index` of the mnemonic isn't in the BIP39 wordlist.
+    InvalidMnemonicWord { index: usize },
+    /// The mnemonic's checksum doesn't match its entropy.
+    InvalidMnemonicChecksum,
+    /// Argon2 doesn't accept the key derivation parameters.
+    InvalidKdfParams,
+    /// The sealed key is malformed or of an unknown version.
+    InvalidSealedKey,
+    /// The keystore is malformed, or uses an unsupported function.
+    InvalidKeystore,
+    /// The password doesn't open the sealed key or keystore, or it was
+    /// altered.
+    WrongPassword,
+    /// Fewer shares than the threshold were given.
+    NotEnoughShares { threshold: usize, got: usize },
+    /// Two shares have the same index.
+    DuplicateShare { index: usize },
+    /// The share is malformed or corrupted, or the shares come from
+    /// different splits.
+    InvalidShare,
+    /// The public key given to sign with isn't the one of the secret key.
+    PublicKeyMismatch,
+    /// The encoded string has the prefix or version byte of another kind
+    /// of value, or none at all.
+    PrefixMismatch,
+    /// The DNSKEY RDATA is malformed or not of an Ed25519 key.
+    InvalidDnskey,
+    /// The RRSIG RDATA is malformed or not of an Ed25519 signature.
+    InvalidRrsig,
+    /// The public key already signed in the `SignatureSet`.
+    DuplicateSigner,
+    /// The entries of the encoded `SignatureSet` aren't sorted by public
+    /// key.
+    UnsortedSignatureSet,
+    /// No key of the `Keyring` verifies the signature.
+    NoMatchingKey,
+    /// The rotation statement is from another key, hands over to the
+    /// same key or has an empty validity window.
+    InvalidRotationStatement,
+    /// The time is outside the validity window of the statement.
+    OutsideValidityWindow,
+    /// Memory for a `LockedSecretKey` couldn't be allocated or locked.
+    MemoryLockFailed,
+    /// The context is longer than the 255 bytes dom2 can encode.
+    ContextTooLong { len: usize },
+    /// Ed25519ctx was given the empty context.
+    EmptyContext,
+    /// The public key or the R of the signature has another encoding
+    /// than the canonical one of its point.
+    NonCanonicalPoint,
+    /// The public key or the R of the signature is a point of small
+    /// order, the identity included.
+    SmallOrderPoint,
+    /// The messages, signatures and public keys of a batch aren't as many.
+    BatchLengthMismatch {
+        messages: usize,
+        signatures: usize,
+        public_keys: usize,
+    },
+    /// A signature didn't verify right after being made, as a fault
+    /// during signing causes: it wasn't released.
+    SelfCheckFailed,
+    /// A VRF proof isn't 80 bytes long, or its Gamma isn't the canonical
+    /// encoding of a curve point or its s isn't reduced.
+    InvalidVrfProof,
+    /// Reading the message failed, with an error of this kind: the
+    /// signature wasn't checked.
+    #[cfg(feature = "std")]
+    Io { kind: std::io::ErrorKind },
+}
+
+impl Error {
+    /// Whether the input was well formed and the signature still doesn't
+    /// verify: a forgery, a corrupted message or the wrong key, rather
+    /// than a parsing failure.
+    ///
+    /// Returns `true` for `SignatureMismatch` and `KeyIdMismatch`, and
+    /// `false` for every other variant.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Error;
+    ///
+    /// fn main() {
+    ///     assert!(Error::SignatureMismatch.is_mismatch());
+    ///     assert!(!Error::NonCanonicalScalar.is_mismatch());
+    /// }
+    /// ```
+    pub fn is_mismatch(&self) -> bool {
+        matches!(self, Error::SignatureMismatch | Error::KeyIdMismatch)
+    }
 }
 
+/// No variant wraps another error, so `source` is always `None`.
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+/// Wraps `Error` as the source of a `signature::Error`, so that it shows
+/// through the Display and Debug of the `signature::Error` and
+/// `Error::from_signature_error` can recover it.
+#[cfg(feature = "signature")]
+impl From<Error> for ::signature::Error {
+    fn from(error: Error) -> Self {
+        ::signature::Error::from_source(error)
+    }
+}
+
+#[cfg(feature = "signature")]
+impl Error {
+    /// Converts a `signature::Error` back into `Error`.
+    ///
+    /// Returns the `Error` it wraps if it was made by `From<Error>`, and
+    /// `Error::SignatureMismatch` otherwise: another implementation's
+    /// `signature::Error` is opaque, and verification failures are what
+    /// it reports.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    /// extern crate signature;
+    ///
+    /// use ed25519_fun::Error;
+    ///
+    /// fn main() {
+    ///     let error = signature::Error::from(Error::NonCanonicalScalar);
+    ///     assert_eq!(
+    ///         error.to_string(),
+    ///         "signature error: Invalid signature: s isn't reduced modulo the group order"
+    ///     );
+    ///     assert_eq!(Error::from_signature_error(&error), Error::NonCanonicalScalar);
+    /// }
+    /// ```
+    pub fn from_signature_error(error: &::signature::Error) -> Error {
+        std::error::Error::source(error)
+            .and_then(|source| source.downcast_ref::<Error>())
+            .copied()
+            .unwrap_or(Error::SignatureMismatch)
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::SignatureMismatch => write!(f, "Signature doesn't verify"),
-            Error::WeakPublicKey => write!(f, "Weak public key"),
-            Error::InvalidPublicKey => write!(f, "Invalid public key"),
+            Error::InvalidPublicKey => write!(
+                f,
+                "Invalid public key: expected the 32-byte encoding of a curve point"
+            ),
+            Error::InvalidPublicKeyLength { expected, got } => write!(
+                f,
+                "Invalid public key: expected {} bytes, got {}",
+                expected, got
+            ),
             Error::InvalidSecretKey => write!(f, "Invalid secret key"),
-            Error::InvalidSignature => write!(f, "Invalid signature"),
-            Error::InvalidNoise => write!(f, "Invalid noise length"),
-            Error::InvalidKeypair => write!(f, "Invalid keypair length"),
-            Error::InvalidSignatureLength => write!(f, "Invalid keypair length"),
+            Error::InvalidSecretKeyLength { expected, got } => write!(
+                f,
+                "Invalid secret key: expected {} bytes, got {}",
+                expected, got
+            ),
+            Error::NonCanonicalScalar => {
+                write!(
+                    f,
+                    "Invalid signature: s isn't reduced modulo the group order"
+                )
+            }
+            Error::InvalidSignaturePoint => {
+                write!(
+                    f,
+                    "Invalid signature: R isn't the encoding of a curve point"
+                )
+            }
+            Error::InvalidKeypair => write!(
+                f,
+                "Invalid keypair: expected a 32-byte seed followed by its public key"
+            ),
+            Error::InvalidKeypairLength { expected, got } => write!(
+                f,
+                "Invalid keypair: expected {} bytes, got {}",
+                expected, got
+            ),
+            Error::InvalidSignatureLength { expected, got } => write!(
+                f,
+                "Invalid signature: expected {} bytes, got {}",
+                expected, got
+            ),
+            Error::InvalidSignatureEncoding => {
+                write!(f, "Invalid signature: expected the encoding of 64 bytes")
+            }
+            Error::InvalidCoseKey => write!(f, "Invalid COSE_Key"),
+            Error::InvalidCoseSign1 => write!(f, "Invalid COSE_Sign1 structure"),
+            Error::MultiPartCapacity => write!(f, "Too many message parts"),
+            Error::Truncated { offset } => write!(f, "Input truncated at offset {}", offset),
+            Error::TrailingBytes { consumed, total } => write!(
+                f,
+                "Trailing bytes: consumed {} of {} bytes",
+                consumed, total
+            ),
+            Error::InvalidToken => write!(f, "Invalid token"),
+            Error::FooterMismatch => write!(f, "Token footer mismatch"),
+            Error::InvalidDidKey => write!(f, "Invalid did:key"),
+            Error::InvalidLibp2pKey => write!(f, "Invalid libp2p public key"),
+            Error::LengthOverflow => write!(f, "Declared length too large"),
+            Error::InvalidMinisignKey => write!(f, "Invalid minisign public key"),
+            Error::InvalidMinisignSignature => write!(f, "Invalid minisign signature"),
+            Error::KeyIdMismatch => write!(f, "Key identifier mismatch"),
+            Error::InvalidBase64 { offset } => write!(f, "Invalid base64 at offset {}", offset),
+            Error::BufferTooSmall => write!(f, "Output buffer too small"),
+            Error::InvalidSignifyKey => write!(f, "Invalid signify public key"),
+            Error::InvalidSignifySignature => write!(f, "Invalid signify signature"),
+            Error::InvalidBase58 => write!(f, "Invalid base58 character"),
+            Error::ChecksumMismatch => write!(f, "Checksum mismatch"),
+            Error::InvalidBech32 => write!(f, "Invalid bech32m string"),
+            Error::HrpMismatch => write!(f, "Unexpected bech32m human-readable part"),
+            Error::InvalidMnemonicLength { words } => write!(
+                f,
+                "Invalid mnemonic: expected 12, 15, 18, 21 or 24 words, got {}",
+                words
+            ),
+            Error::InvalidMnemonicWord { index } => {
+                write!(f, "Invalid mnemonic: unknown word at index {}", index)
+            }
+            Error::InvalidMnemonicChecksum => write!(f, "Invalid mnemonic: checksum mismatch"),
+            Error::InvalidKdfParams => write!(f, "Invalid key derivation parameters"),
+            Error::InvalidSealedKey => write!(f, "Invalid sealed key"),
+            Error::InvalidKeystore => write!(f, "Invalid keystore"),
+            Error::WrongPassword => write!(f, "Wrong password, or altered key"),
+            Error::NotEnoughShares { threshold, got } => {
+                write!(f, "Not enough shares: expected {}, got {}", threshold, got)
+            }
+            Error::DuplicateShare { index } => write!(f, "Duplicate share index {}", index),
+            Error::InvalidShare => write!(f, "Invalid or corrupted share"),
+            Error::PublicKeyMismatch => write!(f, "Public key doesn't match the secret key"),
+            Error::PrefixMismatch => write!(f, "Unexpected prefix or version byte"),
+            Error::InvalidDnskey => write!(f, "Invalid or non-Ed25519 DNSKEY"),
+            Error::InvalidRrsig => write!(f, "Invalid or non-Ed25519 RRSIG"),
+            Error::DuplicateSigner => write!(f, "Public key already in the signature set"),
+            Error::UnsortedSignatureSet => write!(f, "Signature set not sorted by public key"),
+            Error::NoMatchingKey => write!(f, "No trusted key verifies the signature"),
+            Error::InvalidRotationStatement => write!(f, "Invalid rotation statement"),
+            Error::OutsideValidityWindow => write!(f, "Outside the validity window"),
+            Error::MemoryLockFailed => write!(f, "Couldn't allocate or lock memory for the key"),
+            Error::ContextTooLong { len } => {
+                write!(f, "Context too long: {} bytes, at most 255", len)
+            }
+            Error::EmptyContext => write!(f, "Empty context"),
+            Error::NonCanonicalPoint => write!(f, "Non-canonical point encoding"),
+            Error::SmallOrderPoint => write!(f, "Point of small order"),
+            Error::BatchLengthMismatch {
+                messages,
+                signatures,
+                public_keys,
+            } => write!(
+                f,
+                "Batch length mismatch: {} messages, {} signatures, {} public keys",
+                messages, signatures, public_keys
+            ),
+            Error::SelfCheckFailed => write!(f, "Signature failed its self-check"),
+            Error::InvalidVrfProof => write!(f, "Invalid VRF proof"),
+            #[cfg(feature = "std")]
+            Error::Io { kind } => write!(f, "Couldn't read the message: {}", kind),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_display() {
+        let cases = [
+            (Error::SignatureMismatch, "Signature doesn't verify"),
+            (
+                Error::InvalidPublicKey,
+                "Invalid public key: expected the 32-byte encoding of a curve point",
+            ),
+            (
+                Error::InvalidPublicKeyLength {
+                    expected: 32,
+                    got: 31,
+                },
+                "Invalid public key: expected 32 bytes, got 31",
+            ),
+            (Error::InvalidSecretKey, "Invalid secret key"),
+            (
+                Error::InvalidSecretKeyLength {
+                    expected: 32,
+                    got: 64,
+                },
+                "Invalid secret key: expected 32 bytes, got 64",
+            ),
+            (
+                Error::NonCanonicalScalar,
+                "Invalid signature: s isn't reduced modulo the group order",
+            ),
+            (
+                Error::InvalidSignaturePoint,
+                "Invalid signature: R isn't the encoding of a curve point",
+            ),
+            (
+                Error::InvalidKeypair,
+                "Invalid keypair: expected a 32-byte seed followed by its public key",
+            ),
+            (
+                Error::InvalidKeypairLength {
+                    expected: 64,
+                    got: 32,
+                },
+                "Invalid keypair: expected 64 bytes, got 32",
+            ),
+            (
+                Error::InvalidSignatureLength {
+                    expected: 64,
+                    got: 0,
+                },
+                "Invalid signature: expected 64 bytes, got 0",
+            ),
+            (
+                Error::InvalidSignatureEncoding,
+                "Invalid signature: expected the encoding of 64 bytes",
+            ),
+            (
+                Error::Truncated { offset: 3 },
+                "Input truncated at offset 3",
+            ),
+            (
+                Error::TrailingBytes {
+                    consumed: 1,
+                    total: 2,
+                },
+                "Trailing bytes: consumed 1 of 2 bytes",
+            ),
+            (
+                Error::InvalidBase64 { offset: 7 },
+                "Invalid base64 at offset 7",
+            ),
+            (
+                Error::BatchLengthMismatch {
+                    messages: 2,
+                    signatures: 2,
+                    public_keys: 1,
+                },
+                "Batch length mismatch: 2 messages, 2 signatures, 1 public keys",
+            ),
+            (Error::SelfCheckFailed, "Signature failed its self-check"),
+            (Error::InvalidVrfProof, "Invalid VRF proof"),
+        ];
+        for (error, text) in cases.iter() {
+            assert_eq!(error.to_string(), *text);
+        }
+    }
+
+    // `Error::Io` only exists with `std`.
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_error_display() {
+        let error = Error::Io {
+            kind: std::io::ErrorKind::UnexpectedEof,
+        };
+        assert_eq!(
+            error.to_string(),
+            "Couldn't read the message: unexpected end of file"
+        );
+    }
+
+    #[cfg(feature = "signature")]
+    #[test]
+    fn signature_error_round_trip() {
+        let errors = [
+            Error::SignatureMismatch,
+            Error::InvalidPublicKey,
+            Error::InvalidPublicKeyLength {
+                expected: 32,
+                got: 1,
+            },
+            Error::InvalidSecretKey,
+            Error::InvalidSecretKeyLength {
+                expected: 32,
+                got: 2,
+            },
+            Error::NonCanonicalScalar,
+            Error::InvalidSignaturePoint,
+            Error::InvalidKeypair,
+            Error::InvalidKeypairLength {
+                expected: 64,
+                got: 3,
+            },
+            Error::InvalidSignatureLength {
+                expected: 64,
+                got: 4,
+            },
+            Error::InvalidSignatureEncoding,
+            Error::InvalidCoseKey,
+            Error::InvalidCoseSign1,
+            Error::MultiPartCapacity,
+            Error::Truncated { offset: 1 },
+            Error::TrailingBytes {
+                consumed: 1,
+                total: 2,
+            },
+            Error::InvalidToken,
+            Error::FooterMismatch,
+            Error::InvalidDidKey,
+            Error::InvalidLibp2pKey,
+            Error::LengthOverflow,
+            Error::InvalidMinisignKey,
+            Error::InvalidMinisignSignature,
+            Error::KeyIdMismatch,
+            Error::InvalidBase64 { offset: 2 },
+            Error::BufferTooSmall,
+            Error::InvalidSignifyKey,
+            Error::InvalidSignifySignature,
+            Error::InvalidBase58,
+            Error::ChecksumMismatch,
+            Error::InvalidBech32,
+            Error::HrpMismatch,
+            Error::InvalidMnemonicLength { words: 11 },
+            Error::InvalidMnemonicWord { index: 3 },
+            Error::InvalidMnemonicChecksum,
+            Error::InvalidKdfParams,
+            Error::InvalidSealedKey,
+            Error::InvalidKeystore,
+            Error::WrongPassword,
+            Error::NotEnoughShares {
+                threshold: 3,
+                got: 2,
+            },
+            Error::DuplicateShare { index: 1 },
+            Error::InvalidShare,
+            Error::PublicKeyMismatch,
+            Error::PrefixMismatch,
+            Error::InvalidDnskey,
+            Error::InvalidRrsig,
+            Error::DuplicateSigner,
+            Error::UnsortedSignatureSet,
+            Error::NoMatchingKey,
+            Error::InvalidRotationStatement,
+            Error::OutsideValidityWindow,
+            Error::MemoryLockFailed,
+            Error::ContextTooLong { len: 256 },
+            Error::EmptyContext,
+            Error::NonCanonicalPoint,
+            Error::SmallOrderPoint,
+            Error::BatchLengthMismatch {
+                messages: 1,
+                signatures: 2,
+                public_keys: 3,
+            },
+            Error::SelfCheckFailed,
+            Error::InvalidVrfProof,
+            Error::Io {
+                kind: std::io::ErrorKind::UnexpectedEof,
+            },
+        ];
+        for error in errors.iter() {
+            let wrapped = ::signature::Error::from(*error);
+            assert_eq!(Error::from_signature_error(&wrapped), *error);
+            // Logging the wrapper still tells the cause.
+            assert_eq!(wrapped.to_string(), format!("signature error: {}", error));
+            assert!(format!("{:?}", wrapped).contains(&error.to_string()));
         }
+
+        // Foreign errors carry nothing to recover.
+        assert_eq!(
+            Error::from_signature_error(&::signature::Error::new()),
+            Error::SignatureMismatch
+        );
+        let other: Box<dyn std::error::Error + Send + Sync> = "other".into();
+        assert_eq!(
+            Error::from_signature_error(&::signature::Error::from_source(other)),
+            Error::SignatureMismatch
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn error_is_std_error() {
+        let error: Box<dyn std::error::Error> = Box::new(Error::SignatureMismatch);
+        assert!(error.source().is_none());
+        assert_eq!(error.to_string(), "Signature doesn't verify");
+        assert!(Error::KeyIdMismatch.is_mismatch());
+        assert!(!Error::InvalidSignatureEncoding.is_mismatch());
+        assert!(!Error::InvalidPublicKey.is_mismatch());
     }
 }
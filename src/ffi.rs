@@ -0,0 +1,325 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// C interface: key generation, signing and verification over raw
+// pointers, declared in `include/ed25519_fun.h`.
+//
+// Keys and signatures are fixed-size buffers (32-byte secret keys and
+// public keys, 64-byte signatures) and messages are a pointer and a
+// length. Every function returns `ED25519_FUN_OK` or one of the negative
+// `ED25519_FUN_ERR_*` codes; no panic unwinds into the caller, and no
+// output is written unless the call succeeds.
+//
+// The package builds an rlib only; the shared library comes from
+// `cargo rustc --release --features ffi --crate-type cdylib`.
+
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+use std::os::raw::c_int;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+#[cfg(not(feature = "verify-only"))]
+use std::ptr;
+use std::slice;
+
+/// The call succeeded, and the signature is valid for `ed25519_fun_verify`.
+pub const ED25519_FUN_OK: c_int = 0;
+/// `Error::SignatureMismatch`: the signature doesn't verify.
+pub const ED25519_FUN_ERR_SIGNATURE_MISMATCH: c_int = -1;
+/// `Error::InvalidPublicKey`: the public key isn't a curve point.
+pub const ED25519_FUN_ERR_INVALID_PUBLIC_KEY: c_int = -2;
+/// `Error::NonCanonicalScalar`: s isn't reduced modulo L.
+pub const ED25519_FUN_ERR_NON_CANONICAL_SCALAR: c_int = -3;
+/// `Error::InvalidSignaturePoint`: R isn't a curve point.
+pub const ED25519_FUN_ERR_INVALID_SIGNATURE_POINT: c_int = -4;
+/// `Error::InvalidKeypair`: the public key isn't the secret key's.
+pub const ED25519_FUN_ERR_INVALID_KEYPAIR: c_int = -5;
+/// A required pointer is null.
+pub const ED25519_FUN_ERR_NULL_POINTER: c_int = -6;
+/// The call panicked; nothing was written.
+pub const ED25519_FUN_ERR_PANIC: c_int = -7;
+/// Any other `Error`.
+pub const ED25519_FUN_ERR_OTHER: c_int = -8;
+
+/// Returns the code of `error`.
+fn error_code(error: Error) -> c_int {
+    match error {
+        Error::SignatureMismatch => ED25519_FUN_ERR_SIGNATURE_MISMATCH,
+        Error::InvalidPublicKey => ED25519_FUN_ERR_INVALID_PUBLIC_KEY,
+        Error::NonCanonicalScalar => ED25519_FUN_ERR_NON_CANONICAL_SCALAR,
+        Error::InvalidSignaturePoint => ED25519_FUN_ERR_INVALID_SIGNATURE_POINT,
+        Error::InvalidKeypair => ED25519_FUN_ERR_INVALID_KEYPAIR,
+        _ => ED25519_FUN_ERR_OTHER,
+    }
+}
+
+/// Runs `call`, turning its error into a code and a panic into
+/// `ED25519_FUN_ERR_PANIC`.
+fn guard<F>(call: F) -> c_int
+where
+    F: FnOnce() -> Result<(), c_int>,
+{
+    match catch_unwind(AssertUnwindSafe(call)) {
+        Ok(Ok(())) => ED25519_FUN_OK,
+        Ok(Err(code)) => code,
+        Err(_) => ED25519_FUN_ERR_PANIC,
+    }
+}
+
+/// Borrows `len` bytes at `data`, which may be null if `len` is zero.
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], c_int> {
+    if len == 0 {
+        Ok(&[])
+    } else if data.is_null() {
+        Err(ED25519_FUN_ERR_NULL_POINTER)
+    } else {
+        Ok(slice::from_raw_parts(data, len))
+    }
+}
+
+/// Generates a keypair.
+///
+/// Writes the 32-byte secret key to `out_sk` and the 32-byte public key
+/// to `out_pk`, and returns `ED25519_FUN_OK`.
+///
+/// # Safety
+///
+/// `out_sk` and `out_pk` must be null or valid for writes of 32 bytes.
+#[cfg(not(feature = "verify-only"))]
+#[no_mangle]
+pub unsafe extern "C" fn ed25519_fun_keypair_generate(out_sk: *mut u8, out_pk: *mut u8) -> c_int {
+    guard(|| {
+        if out_sk.is_null() || out_pk.is_null() {
+            return Err(ED25519_FUN_ERR_NULL_POINTER);
+        }
+        let keypair = Keypair::generate();
+        ptr::copy_nonoverlapping(keypair.secret().as_bytes().as_ptr(), out_sk, SecretKeySize);
+        ptr::copy_nonoverlapping(keypair.public().as_bytes().as_ptr(), out_pk, PublicKeySize);
+        Ok(())
+    })
+}
+
+/// Signs the `msg_len` bytes at `msg` with the secret key `sk` and its
+/// public key `pk`.
+///
+/// Writes the 64-byte signature to `sig_out` and returns
+/// `ED25519_FUN_OK`, or returns `ED25519_FUN_ERR_INVALID_KEYPAIR` if `pk`
+/// isn't the public key of `sk`.
+///
+/// # Safety
+///
+/// `sig_out` must be null or valid for writes of 64 bytes, `sk` and `pk`
+/// null or valid for reads of 32 bytes, and `msg` valid for reads of
+/// `msg_len` bytes (it may be null if `msg_len` is zero).
+#[cfg(not(feature = "verify-only"))]
+#[no_mangle]
+pub unsafe extern "C" fn ed25519_fun_sign(
+    sig_out: *mut u8,
+    msg: *const u8,
+    msg_len: usize,
+    sk: *const u8,
+    pk: *const u8,
+) -> c_int {
+    guard(|| {
+        if sig_out.is_null() || sk.is_null() || pk.is_null() {
+            return Err(ED25519_FUN_ERR_NULL_POINTER);
+        }
+        let message = input(msg, msg_len)?;
+        let secret =
+            SecretKey::from_bytes(slice::from_raw_parts(sk, SecretKeySize)).map_err(error_code)?;
+        let public =
+            PublicKey::from_bytes(slice::from_raw_parts(pk, PublicKeySize)).map_err(error_code)?;
+        // Signing with another key's public key leaks the secret key.
+        let signature = secret
+            .sign(&public, message)
+            .map_err(|_| ED25519_FUN_ERR_INVALID_KEYPAIR)?;
+        ptr::copy_nonoverlapping(signature.as_bytes().as_ptr(), sig_out, SignatureSize);
+        Ok(())
+    })
+}
+
+/// Verifies the 64-byte signature `sig` of the `msg_len` bytes at `msg`
+/// with the 32-byte public key `pk`.
+///
+/// Returns `ED25519_FUN_OK` if the signature is valid, and the code of the
+/// `Error` of `PublicKey::verify` otherwise.
+///
+/// # Safety
+///
+/// `pk` must be null or valid for reads of 32 bytes, `sig` null or valid
+/// for reads of 64 bytes, and `msg` valid for reads of `msg_len` bytes (it
+/// may be null if `msg_len` is zero).
+#[no_mangle]
+pub unsafe extern "C" fn ed25519_fun_verify(
+    msg: *const u8,
+    msg_len: usize,
+    pk: *const u8,
+    sig: *const u8,
+) -> c_int {
+    guard(|| {
+        if pk.is_null() || sig.is_null() {
+            return Err(ED25519_FUN_ERR_NULL_POINTER);
+        }
+        let message = input(msg, msg_len)?;
+        let public =
+            PublicKey::from_bytes(slice::from_raw_parts(pk, PublicKeySize)).map_err(error_code)?;
+        let signature =
+            Signature::from_bytes(slice::from_raw_parts(sig, SignatureSize)).map_err(error_code)?;
+        public.verify(message, &signature).map_err(error_code)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use std::ptr;
+
+    // RFC 8032, TEST 2.
+    const PUBLIC_KEY: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const SIGNATURE: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn ffi_round_trip() {
+        let mut sk = [0u8; 32];
+        let mut pk = [0u8; 32];
+        let mut sig = [0u8; 64];
+        let message = b"through the C interface";
+        unsafe {
+            assert_eq!(
+                ed25519_fun_keypair_generate(sk.as_mut_ptr(), pk.as_mut_ptr()),
+                ED25519_FUN_OK
+            );
+            assert_eq!(
+                ed25519_fun_sign(
+                    sig.as_mut_ptr(),
+                    message.as_ptr(),
+                    message.len(),
+                    sk.as_ptr(),
+                    pk.as_ptr()
+                ),
+                ED25519_FUN_OK
+            );
+            assert_eq!(
+                ed25519_fun_verify(message.as_ptr(), message.len(), pk.as_ptr(), sig.as_ptr()),
+                ED25519_FUN_OK
+            );
+            assert_eq!(
+                ed25519_fun_verify(message.as_ptr(), 5, pk.as_ptr(), sig.as_ptr()),
+                ED25519_FUN_ERR_SIGNATURE_MISMATCH
+            );
+        }
+
+        // The same signature as the Rust API.
+        let keypair = Keypair::from_secret(SecretKey::from_array(sk));
+        assert_eq!(keypair.public().to_bytes(), pk);
+        assert_eq!(keypair.sign(message).to_bytes()[..], sig[..]);
+
+        // Another key's public key is refused, and nothing is written.
+        let mut other = [0u8; 64];
+        let mut other_pk = pk;
+        other_pk[0] ^= 1;
+        unsafe {
+            assert_eq!(
+                ed25519_fun_sign(
+                    other.as_mut_ptr(),
+                    message.as_ptr(),
+                    message.len(),
+                    sk.as_ptr(),
+                    other_pk.as_ptr()
+                ),
+                ED25519_FUN_ERR_INVALID_KEYPAIR
+            );
+        }
+        assert_eq!(other[..], [0u8; 64][..]);
+    }
+
+    #[test]
+    fn ffi_verify_codes() {
+        let pk = hex::decode(PUBLIC_KEY).unwrap();
+        let sig = hex::decode(SIGNATURE).unwrap();
+        let verify = |msg: &[u8], pk: &[u8], sig: &[u8]| unsafe {
+            ed25519_fun_verify(msg.as_ptr(), msg.len(), pk.as_ptr(), sig.as_ptr())
+        };
+        assert_eq!(verify(&[0x72], &pk, &sig), ED25519_FUN_OK);
+        assert_eq!(
+            verify(&[0x73], &pk, &sig),
+            ED25519_FUN_ERR_SIGNATURE_MISMATCH
+        );
+
+        let mut off_curve = [0u8; 32];
+        off_curve[0] = 2;
+        assert_eq!(
+            verify(&[0x72], &off_curve, &sig),
+            ED25519_FUN_ERR_INVALID_PUBLIC_KEY
+        );
+        let mut high_s = sig.clone();
+        high_s[63] = 0xff;
+        assert_eq!(
+            verify(&[0x72], &pk, &high_s),
+            ED25519_FUN_ERR_NON_CANONICAL_SCALAR
+        );
+        assert_eq!(
+            error_code(Error::InvalidSignaturePoint),
+            ED25519_FUN_ERR_INVALID_SIGNATURE_POINT
+        );
+        assert_eq!(error_code(Error::InvalidBech32), ED25519_FUN_ERR_OTHER);
+    }
+
+    #[test]
+    fn ffi_null_pointers() {
+        let pk = hex::decode(PUBLIC_KEY).unwrap();
+        let sig = hex::decode(SIGNATURE).unwrap();
+        unsafe {
+            assert_eq!(
+                ed25519_fun_verify(ptr::null(), 1, pk.as_ptr(), sig.as_ptr()),
+                ED25519_FUN_ERR_NULL_POINTER
+            );
+            assert_eq!(
+                ed25519_fun_verify([0x72].as_ptr(), 1, ptr::null(), sig.as_ptr()),
+                ED25519_FUN_ERR_NULL_POINTER
+            );
+            assert_eq!(
+                ed25519_fun_verify([0x72].as_ptr(), 1, pk.as_ptr(), ptr::null()),
+                ED25519_FUN_ERR_NULL_POINTER
+            );
+            // An empty message may be null.
+            assert_eq!(
+                ed25519_fun_verify(ptr::null(), 0, pk.as_ptr(), sig.as_ptr()),
+                ED25519_FUN_ERR_SIGNATURE_MISMATCH
+            );
+        }
+
+        #[cfg(not(feature = "verify-only"))]
+        unsafe {
+            let mut sk = [0u8; 32];
+            let mut sig = [0u8; 64];
+            assert_eq!(
+                ed25519_fun_keypair_generate(sk.as_mut_ptr(), ptr::null_mut()),
+                ED25519_FUN_ERR_NULL_POINTER
+            );
+            assert_eq!(
+                ed25519_fun_sign(sig.as_mut_ptr(), ptr::null(), 0, ptr::null(), pk.as_ptr()),
+                ED25519_FUN_ERR_NULL_POINTER
+            );
+            assert_eq!(sk, [0u8; 32]);
+        }
+    }
+
+    #[test]
+    fn ffi_panics_become_codes() {
+        assert_eq!(
+            guard(|| panic!("across the boundary")),
+            ED25519_FUN_ERR_PANIC
+        );
+        assert_eq!(guard(|| Err(ED25519_FUN_ERR_OTHER)), ED25519_FUN_ERR_OTHER);
+    }
+}
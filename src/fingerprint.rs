@@ -0,0 +1,178 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Key fingerprints: the SHA-256 of the 32-byte public key, its first
+// 8 bytes in hex for logs, and the fingerprint OpenSSH prints.
+//
+// OpenSSH doesn't hash the bare key but its wire blob (RFC 8709): the
+// string "ssh-ed25519" then the string of the 32 bytes, each prefixed by
+// its big-endian u32 length. `fingerprint_openssh` hashes that blob so
+// that it prints what `ssh-keygen -lf` prints for the same key.
+
+use crate::b16;
+#[cfg(feature = "std")]
+use crate::b64;
+#[cfg(feature = "std")]
+use crate::constants::*;
+use crate::public::*;
+
+use core::fmt;
+use sha2::{Digest, Sha256};
+
+/// Base64 with the standard alphabet and no padding, as OpenSSH prints
+/// fingerprints.
+#[cfg(feature = "std")]
+const STANDARD_NO_PAD: b64::Variant = b64::Variant {
+    url_safe: false,
+    padding: false,
+};
+
+/// The key type of Ed25519 keys in OpenSSH.
+#[cfg(feature = "std")]
+const SSH_ED25519: &[u8] = b"ssh-ed25519";
+
+/// The SHA-256 fingerprint of a `PublicKey`.
+///
+/// Displays as lowercase hex, and can key a map.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{FingerPrint, PublicKey};
+/// use std::collections::HashMap;
+///
+/// fn main() {
+///     let public_key: PublicKey = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+///         .parse()
+///         .unwrap();
+///     let mut owners: HashMap<FingerPrint, &str> = HashMap::new();
+///     owners.insert(public_key.fingerprint(), "alice");
+///     assert_eq!(owners[&public_key.fingerprint()], "alice");
+///     assert_eq!(public_key.fingerprint().short(), "21fe31dfa154a261");
+/// }
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FingerPrint([u8; 32]);
+
+impl FingerPrint {
+    /// Borrows the 32 bytes of `FingerPrint`.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Copies the 32 bytes of `FingerPrint`.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+
+    /// Formats the first 8 bytes of `FingerPrint` as lowercase hex, for
+    /// logs.
+    #[cfg(feature = "std")]
+    pub fn short(&self) -> String {
+        b16::encode(&self.0[..8])
+    }
+}
+
+/// Formats `FingerPrint` as lowercase hex.
+impl fmt::Display for FingerPrint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        b16::write(f, &self.0)
+    }
+}
+
+/// Formats `FingerPrint` as `FingerPrint(<hex>)`.
+impl fmt::Debug for FingerPrint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FingerPrint({})", self)
+    }
+}
+
+impl PublicKey {
+    /// Computes the SHA-256 of the 32-byte encoding of `PublicKey`.
+    ///
+    /// `short()` gives the form for logs.
+    pub fn fingerprint(&self) -> FingerPrint {
+        let mut fingerprint = [0u8; 32];
+        fingerprint.copy_from_slice(&Sha256::digest(&self.0));
+        FingerPrint(fingerprint)
+    }
+
+    /// Computes the fingerprint OpenSSH prints for `PublicKey`, as
+    /// `SHA256:` and the unpadded base64 of the SHA-256 of its wire blob.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let public_key: PublicKey = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+    ///         .parse()
+    ///         .unwrap();
+    ///     assert_eq!(
+    ///         public_key.fingerprint_openssh(),
+    ///         "SHA256:bbXpuKG6zhzdmnxq256TlqzFBzRl2f6OOg722cYNbU8"
+    ///     );
+    /// }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn fingerprint_openssh(&self) -> String {
+        let mut hash = Sha256::new();
+        hash.input(&(SSH_ED25519.len() as u32).to_be_bytes());
+        hash.input(SSH_ED25519);
+        hash.input(&(PublicKeySize as u32).to_be_bytes());
+        hash.input(&self.0);
+        format!("SHA256:{}", b64::encode(STANDARD_NO_PAD, &hash.result()))
+    }
+}
+
+// The fingerprint strings need `std`.
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    // RFC 8032, TEST 1 and TEST 2.
+    const PUBLIC_KEY_1: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const PUBLIC_KEY_2: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+
+    // The key as an OpenSSH public key line, for `ssh-keygen -lf`:
+    // ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAINdamAGCsQq31Uv+08lkBzoO4XLz2qYjJa8CGmj3B1Ea
+    // OpenSSH 9.2p1 prints:
+    // 256 SHA256:bbXpuKG6zhzdmnxq256TlqzFBzRl2f6OOg722cYNbU8 rfc8032-test1 (ED25519)
+    #[test]
+    fn fingerprint_openssh_matches_ssh_keygen() {
+        let public: PublicKey = PUBLIC_KEY_1.parse().unwrap();
+        assert_eq!(
+            public.fingerprint_openssh(),
+            "SHA256:bbXpuKG6zhzdmnxq256TlqzFBzRl2f6OOg722cYNbU8"
+        );
+    }
+
+    #[test]
+    fn fingerprint_of_public_key() {
+        let public: PublicKey = PUBLIC_KEY_1.parse().unwrap();
+        let fingerprint = public.fingerprint();
+        assert_eq!(
+            fingerprint.to_string(),
+            "21fe31dfa154a261626bf854046fd2271b7bed4b6abe45aa58877ef47f9721b9"
+        );
+        assert_eq!(fingerprint.short(), "21fe31dfa154a261");
+        assert_eq!(
+            format!("{:?}", fingerprint),
+            format!("FingerPrint({})", fingerprint)
+        );
+        assert_eq!(
+            fingerprint.as_bytes()[..],
+            Sha256::digest(public.as_bytes())[..]
+        );
+
+        let other: PublicKey = PUBLIC_KEY_2.parse().unwrap();
+        assert_eq!(public.fingerprint(), fingerprint);
+        assert_ne!(other.fingerprint(), fingerprint);
+        assert_ne!(other.fingerprint_openssh(), public.fingerprint_openssh());
+    }
+}
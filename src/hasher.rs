@@ -0,0 +1,34 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// The SHA-512 of key derivation, signing and verification, behind a trait
+// so that a hardware engine can stand in for the `sha2` crate. RFC 8032
+// fixes the hash: a provider must compute SHA-512 exactly, and then keys,
+// signatures and verdicts are the same whichever provider computed them.
+
+use sha2::{Digest, Sha512};
+
+/// An implementation of SHA-512. `Default` starts a new hash.
+pub trait Sha512Provider: Default {
+    /// Absorbs `data` into the hash.
+    fn update(&mut self, data: &[u8]);
+
+    /// Finishes the hash.
+    ///
+    /// Returns the 64-byte SHA-512 digest of everything absorbed.
+    fn finalize(self) -> [u8; 64];
+}
+
+/// The software SHA-512 of the `sha2` crate, the one of `sign` and
+/// `verify`.
+impl Sha512Provider for Sha512 {
+    fn update(&mut self, data: &[u8]) {
+        self.input(data);
+    }
+
+    fn finalize(self) -> [u8; 64] {
+        let mut digest = [0u8; 64];
+        digest.copy_from_slice(&self.result());
+        digest
+    }
+}
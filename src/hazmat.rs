@@ -0,0 +1,455 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Low-level exports for what the safe interface doesn't reach.
+//
+// Verification witnesses for external proof systems: the values that
+// satisfy the group equation [s]B = R + [k]A of a verified signature, as
+// canonical byte encodings. Only public data goes in or out. Nothing here
+// is needed to verify a signature. A witness is exported only after
+// `verify` accepted, so acceptance is exactly the one of
+// `PublicKey::verify`.
+//
+// A pluggable SHA-512, for hardware hash engines: `sign_with_hasher` and
+// `verify_with_hasher` are `sign` and `verify` with every hash, the key
+// expansion included, computed by a caller's `Sha512Provider`. A
+// provider that isn't exactly SHA-512 gives keys and signatures no one
+// else accepts.
+
+#![allow(non_snake_case)]
+
+use crate::curve25519::group_element::*;
+use crate::curve25519::scalar_ops::*;
+
+use crate::errors::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+use crate::verify::*;
+
+pub use crate::hasher::Sha512Provider;
+
+use sha2::{Digest, Sha512};
+
+/// The components of the group equation [s]B = R + [k]A of a verified
+/// signature. Points are affine, encoded as x || y with both coordinates
+/// canonical and little-endian; scalars are canonical and little-endian.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/verification_witness.rs")]
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct VerificationWitness {
+    /// The public key point A.
+    pub a: [u8; 64],
+    /// The commitment point R.
+    pub r: [u8; 64],
+    /// The challenge k = SHA512(R || A || M) mod L.
+    pub k: [u8; 32],
+    /// The signature scalar s.
+    pub s: [u8; 32],
+    /// [s]B.
+    pub s_b: [u8; 64],
+    /// [k]A.
+    pub k_a: [u8; 64],
+}
+
+/// Whether `scalar` is reduced modulo L.
+fn is_canonical(scalar: &[u8; 32]) -> bool {
+    let mut wide = [0u8; 64];
+    wide[..32].copy_from_slice(scalar);
+    reduce(&mut wide);
+    wide[..32] == scalar[..]
+}
+
+impl VerificationWitness {
+    /// Checks the witness against itself: every point is on the curve,
+    /// the scalars are canonical, `s_b` is [s]B, `k_a` is [k]A and
+    /// [s]B = R + [k]A.
+    ///
+    /// Returns `true` if all of them hold and `false` otherwise.
+    pub fn check(&self) -> bool {
+        let points = (
+            P3::decode_uncompressed(&self.a),
+            P3::decode_uncompressed(&self.r),
+            P3::decode_uncompressed(&self.s_b),
+            P3::decode_uncompressed(&self.k_a),
+        );
+        let (A, R, sB, kA) = match points {
+            (Some(A), Some(R), Some(sB), Some(kA)) => (A, R, sB, kA),
+            _ => return false,
+        };
+        if !is_canonical(&self.s) || !is_canonical(&self.k) {
+            return false;
+        }
+
+        Precomp::scalar_multiply(&self.s).encode_uncompressed() == self.s_b
+            && P2::double_scalar_multiply_vartime(&self.k, &[0u8; 32], A).encode_uncompressed()
+                == self.k_a
+            && (R + kA.to_Cached()).to_P3().encode_uncompressed() == sB.encode_uncompressed()
+    }
+}
+
+impl PublicKey {
+    /// Verifies a signature with this `PublicKey` and exports the
+    /// components of its group equation.
+    ///
+    /// Returns `Ok(VerificationWitness)` if the signature is valid and the
+    /// error of `verify` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{PublicKey, Signature};
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     let signature = Signature::from_array([
+    ///         0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e,
+    ///         0x82, 0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65,
+    ///         0x22, 0x49, 0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e,
+    ///         0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24,
+    ///         0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+    ///     ]);
+    ///     let witness = public_key.verify_and_export_witness(b"", &signature).unwrap();
+    ///     assert!(witness.check());
+    /// }
+    /// ```
+    pub fn verify_and_export_witness(
+        &self,
+        message: &[u8],
+        sig: &Signature,
+    ) -> Result<VerificationWitness, Error> {
+        self.verify(message, sig)?;
+
+        // Both encodings decode: A was decoded by `verify`, and R is the
+        // encoding of [s]B - [k]A.
+        let signature = sig.as_bytes();
+        let mut R = [0u8; 32];
+        R.copy_from_slice(&signature[..32]);
+        let mut s = [0u8; 32];
+        s.copy_from_slice(&signature[32..]);
+        let A = P3::decode(self.0).ok_or(Error::InvalidPublicKey)?.negate();
+        let R = P3::decode(R).ok_or(Error::InvalidSignaturePoint)?.negate();
+
+        let k = challenge(&signature[..32], &self.0, None, |hash: &mut Sha512| {
+            hash.input(message)
+        });
+
+        Ok(VerificationWitness {
+            a: A.encode_uncompressed(),
+            r: R.encode_uncompressed(),
+            k,
+            s,
+            s_b: Precomp::scalar_multiply(&s).encode_uncompressed(),
+            k_a: P2::double_scalar_multiply_vartime(&k, &[0u8; 32], A).encode_uncompressed(),
+        })
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl SecretKey {
+    /// Signs `message` as `sign` does, with `H` computing every SHA-512:
+    /// the public key check, the key expansion, the nonce and the
+    /// challenge. The signature is the one of `sign` if `H` is SHA-512.
+    ///
+    /// Returns `Ok(Signature)` or `Err(Error::PublicKeyMismatch)` if
+    /// `public` isn't the public key of `SecretKey` under `H`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    /// extern crate sha2;
+    ///
+    /// use ed25519_fun::hazmat::Sha512Provider;
+    /// use ed25519_fun::Keypair;
+    /// use sha2::digest::Input;
+    /// use sha2::{Digest, Sha512};
+    ///
+    /// // Stands for a driver of a SHA-512 peripheral.
+    /// #[derive(Default)]
+    /// struct Engine(Sha512);
+    ///
+    /// impl Sha512Provider for Engine {
+    ///     fn update(&mut self, data: &[u8]) {
+    ///         Input::input(&mut self.0, data);
+    ///     }
+    ///
+    ///     fn finalize(self) -> [u8; 64] {
+    ///         let mut digest = [0u8; 64];
+    ///         digest.copy_from_slice(&self.0.result());
+    ///         digest
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let (secret, public) = (keypair.secret(), keypair.public());
+    ///     let signature = secret.sign_with_hasher::<Engine>(public, b"firmware").unwrap();
+    ///     assert_eq!(signature, keypair.sign(b"firmware"));
+    ///     assert!(public.verify_with_hasher::<Engine>(b"firmware", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn sign_with_hasher<H: Sha512Provider>(
+        &self,
+        public: &PublicKey,
+        message: &[u8],
+    ) -> Result<Signature, Error> {
+        if PublicKey::from_secret_with::<H>(self) != *public {
+            return Err(Error::PublicKeyMismatch);
+        }
+        let signature =
+            self.sign_absorbing_with::<H, _>(public, None, None, |hash| hash.update(message));
+        #[cfg(feature = "observer")]
+        observer::signed(public, |digest| digest.update(message));
+        Ok(signature)
+    }
+}
+
+impl PublicKey {
+    /// Verifies a signature as `verify` does, with `H` computing the
+    /// SHA-512 of the challenge.
+    ///
+    /// Returns `Ok(())` if the signature is valid and the error of
+    /// `verify` otherwise.
+    pub fn verify_with_hasher<H: Sha512Provider>(
+        &self,
+        message: &[u8],
+        sig: &Signature,
+    ) -> Result<(), Error> {
+        self.verify_absorbing_with::<H, _>(sig, None, &VerificationOptions::new(), |hash| {
+            hash.update(message)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+    use super::*;
+    use crate::curve25519::field_element::FieldElement;
+    use sha2::Sha512;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+
+    // (public key, message, signature) of the sign.input corpus.
+    fn corpus() -> Vec<(PublicKey, Vec<u8>, Signature)> {
+        let file = File::open("./tests/sign.input").expect("Where are the test vectors? :(");
+        BufReader::new(file)
+            .lines()
+            .map(|line| {
+                let l = line.unwrap();
+                let slices: Vec<&str> = l.split(':').collect();
+                (
+                    PublicKey::from_bytes(&hex::decode(slices[1]).unwrap()).unwrap(),
+                    hex::decode(slices[2]).unwrap(),
+                    Signature::from_bytes(&hex::decode(slices[3]).unwrap()[..64]).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    // x || y of (X : Y : Z), from the field arithmetic alone.
+    fn affine(X: FieldElement, Y: FieldElement, Z: FieldElement) -> [u8; 64] {
+        let zinv = Z.invert();
+        let mut out = [0u8; 64];
+        out[..32].copy_from_slice(&(X * zinv).encode());
+        out[32..].copy_from_slice(&(Y * zinv).encode());
+        out
+    }
+
+    // n * P by double-and-add over the bits of `n`.
+    fn multiply(n: &[u8; 32], P: P3) -> P3 {
+        let mut acc = P3::zero();
+        for i in (0..256).rev() {
+            acc = acc.double().to_P3();
+            if (n[i / 8] >> (i % 8)) & 1 == 1 {
+                acc = (acc + P.to_Cached()).to_P3();
+            }
+        }
+        acc
+    }
+
+    #[test]
+    fn witness_components_match_independent_computation() {
+        for (public, message, sig) in corpus().into_iter().step_by(97) {
+            let witness = public.verify_and_export_witness(&message, &sig).unwrap();
+            assert!(witness.check());
+
+            let signature = sig.as_bytes();
+            let mut R_bytes = [0u8; 32];
+            R_bytes.copy_from_slice(&signature[..32]);
+            let mut s = [0u8; 32];
+            s.copy_from_slice(&signature[32..]);
+
+            // `P3::decode` returns the negated point.
+            let A = P3::decode(public.to_bytes()).unwrap();
+            let R = P3::decode(R_bytes).unwrap();
+            assert_eq!(witness.a, affine(A.X.negate(), A.Y, A.Z));
+            assert_eq!(witness.r, affine(R.X.negate(), R.Y, R.Z));
+
+            let mut digest = Sha512::new();
+            digest.input(&R_bytes);
+            digest.input(public.as_bytes());
+            digest.input(&message);
+            let mut k = digest.result();
+            reduce(&mut k);
+            assert_eq!(witness.k[..], k[..32]);
+            assert_eq!(witness.s, s);
+
+            let sB = Precomp::scalar_multiply_without_precomputation(&s);
+            assert_eq!(witness.s_b, affine(sB.X, sB.Y, sB.Z));
+            let kA = multiply(&witness.k, A);
+            assert_eq!(witness.k_a, affine(kA.X.negate(), kA.Y, kA.Z));
+        }
+    }
+
+    #[test]
+    fn witness_export_accepts_exactly_what_verify_accepts() {
+        for (public, message, sig) in corpus() {
+            let mut signatures = vec![sig];
+            for &i in [0usize, 31, 32, 63].iter() {
+                let mut bytes = sig.to_bytes();
+                bytes[i] ^= 0x01;
+                signatures.push(Signature::from_bytes(&bytes).unwrap());
+            }
+            for sig in signatures.iter() {
+                let verdict = public.verify(&message, sig);
+                let witness = public.verify_and_export_witness(&message, sig);
+                assert_eq!(verdict.err(), witness.err());
+                assert_eq!(verdict.is_ok(), witness.map(|w| w.check()).unwrap_or(false));
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_witness_fails_check() {
+        let (public, message, sig) = corpus().remove(0);
+        let witness = public.verify_and_export_witness(&message, &sig).unwrap();
+
+        let mut off_curve = witness;
+        off_curve.r[0] ^= 1;
+        assert!(!off_curve.check());
+
+        // A valid point, but not R.
+        let mut other_point = witness;
+        other_point.r = witness.a;
+        assert!(!other_point.check());
+
+        let mut other_k = witness;
+        other_k.k[0] ^= 1;
+        assert!(!other_k.check());
+
+        // s + L: the same point [s]B, but not canonical.
+        let L = [
+            0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9,
+            0xde, 0x14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10,
+        ];
+        let mut non_canonical = witness;
+        let mut carry = 0u16;
+        for (i, l) in L.iter().enumerate() {
+            let sum = witness.s[i] as u16 + *l as u16 + carry;
+            non_canonical.s[i] = sum as u8;
+            carry = sum >> 8;
+        }
+        assert!(!non_canonical.check());
+    }
+
+    // SHA-512 computed in one shot at `finalize`, over everything
+    // buffered: another path through the hash than the streaming state.
+    #[derive(Default)]
+    struct Buffered(Vec<u8>);
+
+    impl Sha512Provider for Buffered {
+        fn update(&mut self, data: &[u8]) {
+            self.0.extend_from_slice(data);
+        }
+
+        fn finalize(self) -> [u8; 64] {
+            let mut digest = [0u8; 64];
+            digest.copy_from_slice(&Sha512::digest(&self.0));
+            digest
+        }
+    }
+
+    // Not SHA-512: a bit of the second byte of the digest is flipped.
+    #[derive(Default)]
+    struct Broken(Sha512);
+
+    impl Sha512Provider for Broken {
+        fn update(&mut self, data: &[u8]) {
+            self.0.input(data);
+        }
+
+        fn finalize(self) -> [u8; 64] {
+            let mut digest = [0u8; 64];
+            digest.copy_from_slice(&self.0.result());
+            digest[1] ^= 1;
+            digest
+        }
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn sign_with_hasher_matches_sign() {
+        let file = File::open("./tests/sign.input").expect("Where are the test vectors? :(");
+        let lines: Vec<String> = BufReader::new(file).lines().map(|l| l.unwrap()).collect();
+        for ((public, message, sig), line) in corpus().into_iter().zip(lines).step_by(17) {
+            let secret = SecretKey::from_bytes(&hex::decode(&line[..64]).unwrap()).unwrap();
+            assert_eq!(
+                secret.sign_with_hasher::<Buffered>(&public, &message),
+                Ok(sig)
+            );
+            assert_eq!(
+                secret.sign_with_hasher::<Sha512>(&public, &message),
+                Ok(sig)
+            );
+            assert_eq!(
+                secret.sign_with_hasher::<Broken>(&public, &message),
+                Err(Error::PublicKeyMismatch)
+            );
+        }
+        let other = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let (public, message, _) = corpus().remove(0);
+        assert_eq!(
+            other.sign_with_hasher::<Buffered>(&public, &message),
+            Err(Error::PublicKeyMismatch)
+        );
+    }
+
+    #[test]
+    fn verify_with_hasher_matches_verify() {
+        for (public, message, sig) in corpus().into_iter().step_by(17) {
+            assert!(public
+                .verify_with_hasher::<Buffered>(&message, &sig)
+                .is_ok());
+            assert!(public.verify_with_hasher::<Sha512>(&message, &sig).is_ok());
+            assert_eq!(
+                public.verify_with_hasher::<Broken>(&message, &sig),
+                Err(Error::SignatureMismatch)
+            );
+
+            let mut bytes = sig.to_bytes();
+            bytes[0] ^= 1;
+            let tampered = Signature::from_bytes(&bytes).unwrap();
+            assert_eq!(
+                public
+                    .verify_with_hasher::<Buffered>(&message, &tampered)
+                    .err(),
+                public.verify(&message, &tampered).err()
+            );
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// HMAC-SHA512 (RFC 2104) and HKDF-SHA512 (RFC 5869), on top of the
+// SHA-512 the signing code already uses. Only bytes go in and out, so
+// the output doesn't depend on the platform. Key pads and pseudorandom
+// keys are wiped after use.
+
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+/// The SHA-512 block size.
+const BLOCK_SIZE: usize = 128;
+
+/// The SHA-512 output size.
+pub(crate) const HASH_SIZE: usize = 64;
+
+/// Computes HMAC-SHA512 of the concatenation of `message` under `key`.
+pub(crate) fn hmac_sha512(key: &[u8], message: &[&[u8]]) -> [u8; HASH_SIZE] {
+    // Keys longer than a block are hashed first; shorter ones are padded
+    // with zeros.
+    let mut pad = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        pad[..HASH_SIZE].copy_from_slice(&Sha512::digest(key));
+    } else {
+        pad[..key.len()].copy_from_slice(key);
+    }
+
+    for byte in pad.iter_mut() {
+        *byte ^= 0x36;
+    }
+    let mut inner = Sha512::new();
+    inner.input(&pad[..]);
+    for part in message {
+        inner.input(part);
+    }
+    let mut inner_hash = [0u8; HASH_SIZE];
+    inner_hash.copy_from_slice(&inner.result());
+
+    // 0x36 ^ 0x5c turns the inner pad into the outer one.
+    for byte in pad.iter_mut() {
+        *byte ^= 0x36 ^ 0x5c;
+    }
+    let mut outer = Sha512::new();
+    outer.input(&pad[..]);
+    outer.input(&inner_hash[..]);
+    let mut mac = [0u8; HASH_SIZE];
+    mac.copy_from_slice(&outer.result());
+
+    pad.zeroize();
+    inner_hash.zeroize();
+    mac
+}
+
+/// HKDF-Extract: the pseudorandom key of `ikm` under `salt`. An empty
+/// salt stands for the 64 zeros of RFC 5869, which HMAC pads it to
+/// anyway.
+pub(crate) fn extract(salt: &[u8], ikm: &[u8]) -> [u8; HASH_SIZE] {
+    hmac_sha512(salt, &[ikm])
+}
+
+/// HKDF-Expand: fills `okm` from the pseudorandom key `prk` and `info`.
+///
+/// `okm` is at most 255 * 64 bytes long.
+pub(crate) fn expand(prk: &[u8; HASH_SIZE], info: &[u8], okm: &mut [u8]) {
+    assert!(okm.len() <= 255 * HASH_SIZE);
+    let mut block = [0u8; HASH_SIZE];
+    for (i, chunk) in okm.chunks_mut(HASH_SIZE).enumerate() {
+        let previous: &[u8] = if i == 0 { &[] } else { &block[..] };
+        let next = hmac_sha512(prk, &[previous, info, &[i as u8 + 1]]);
+        block = next;
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    block.zeroize();
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // RFC 4231, test cases 1, 2 and 6 (a key longer than the block).
+    #[test]
+    fn hmac_sha512_rfc4231() {
+        let cases: [(&[u8], &[u8], &str); 3] = [
+            (&[0x0b; 20], b"Hi There", "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854"),
+            (b"Jefe", b"what do ya want for nothing?", "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"),
+            (&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First", "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598"),
+        ];
+        for (key, message, mac) in cases.iter() {
+            assert_eq!(hex::encode(&hmac_sha512(key, &[message])[..]), *mac);
+            // The message may come in parts.
+            let (a, b) = message.split_at(3);
+            assert_eq!(hex::encode(&hmac_sha512(key, &[a, &[], b])[..]), *mac);
+        }
+    }
+
+    // The inputs of RFC 5869 test case 1, with SHA-512: RFC 5869 has no
+    // SHA-512 vectors, the output is pyca/cryptography's.
+    #[test]
+    fn hkdf_sha512_expands_over_blocks() {
+        let prk = extract(
+            &hex::decode("000102030405060708090a0b0c").unwrap(),
+            &[0x0b; 22],
+        );
+        let mut okm = [0u8; 200];
+        expand(
+            &prk,
+            &hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap(),
+            &mut okm,
+        );
+        assert_eq!(
+            hex::encode(&okm[..]),
+            "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cbcce0dff7098769cf15959867d571c1715450cb530137be3fb62f3cf32b84feba8f1eb1b563e20d9749b8640b8264c4b69b14ad5199115e1d609c83c6940ce5b4214a0c79946983547a35cdcc17e0daf31b647dec0d0e6142b1deaa036b348422068ca66631c0ca5586485a276a4336e1cde0e83159b53f017201c7ccfe4ef0d5b543e6715821462a0e876aec3e7ff562173a205240fb1fa67a81bfa3e267"
+        );
+        // A shorter output is a prefix of a longer one.
+        let mut short = [0u8; 32];
+        expand(
+            &prk,
+            &hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap(),
+            &mut short,
+        );
+        assert_eq!(short[..], okm[..32]);
+    }
+}
@@ -7,14 +7,37 @@
 
 use crate::constants::*;
 use crate::errors::*;
+#[cfg(feature = "observer")]
+use crate::observer;
 use crate::public::*;
 use crate::secret::*;
 use crate::signature::*;
 
+use core::convert::TryFrom;
+use core::fmt;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
 /// A pair of public and secret keys.
+///
+/// The public key is always the one of the secret key: every constructor
+/// but `from_bytes_unchecked` derives or checks it, and the fields can't
+/// be replaced. Signing always hashes the public key of the secret key,
+/// whatever the constructor. Code that
+/// used the former public fields reads them through `secret()` and
+/// `public()`, and takes them apart with `into_parts()`.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/keypair.rs")]
+/// ```
 pub struct Keypair {
-    pub secret: SecretKey,
-    pub public: PublicKey,
+    pub(crate) secret: SecretKey,
+    pub(crate) public: PublicKey,
+    // Set by `from_bytes_unchecked`: `public` may not be the public key
+    // of `secret`, so signing derives it.
+    unchecked: bool,
 }
 
 impl Keypair {
@@ -34,10 +57,95 @@ impl Keypair {
     ///     let keypair = Keypair::generate();
     /// }
     /// ```
+    #[cfg(any(feature = "rand", feature = "getrandom"))]
     pub fn generate() -> Keypair {
         let secret = SecretKey::generate_key();
-        let public = PublicKey::generate(&secret);
-        Keypair { secret, public }
+        let public = PublicKey::from_secret(&secret);
+        Keypair {
+            secret,
+            public,
+            unchecked: false,
+        }
+    }
+
+    /// Generates `Keypair` with its secret key drawn from `rng`, as
+    /// `SecretKey::generate_with` does.
+    ///
+    /// Returns `Keypair` containing the secret and public keys.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    /// extern crate rand;
+    ///
+    /// use ed25519_fun::Keypair;
+    /// use rand::rngs::OsRng;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate_with(&mut OsRng);
+    ///     assert!(keypair.public().verify(b"", &keypair.sign(b"")).is_ok());
+    /// }
+    /// ```
+    pub fn generate_with<R: RngCore + CryptoRng>(rng: &mut R) -> Keypair {
+        Keypair::from_secret(SecretKey::generate_with(rng))
+    }
+
+    /// Generates `n` keypairs, drawing all their secret keys from the
+    /// CSPRNG at once.
+    ///
+    /// The `n * 32` random bytes are cut into consecutive 32-byte
+    /// chunks, one secret key each. CSPRNG output is indistinguishable
+    /// from independent uniform bytes, so the keys are as independent as
+    /// `n` calls to `generate` would make them.
+    ///
+    /// Returns the `n` keypairs.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let devices = Keypair::generate_batch(100);
+    ///     assert_eq!(devices.len(), 100);
+    /// }
+    /// ```
+    #[cfg(all(feature = "std", any(feature = "rand", feature = "getrandom")))]
+    pub fn generate_batch(n: usize) -> Vec<Keypair> {
+        SecretKey::generate_keys(n)
+            .into_iter()
+            .map(Keypair::from_secret)
+            .collect()
+    }
+
+    /// Generates `n` keypairs like `generate_batch`, computing the public
+    /// keys on the rayon thread pool.
+    ///
+    /// Returns the `n` keypairs.
+    #[cfg(feature = "parallel")]
+    pub fn generate_batch_parallel(n: usize) -> Vec<Keypair> {
+        use rayon::prelude::*;
+
+        SecretKey::generate_keys(n)
+            .into_par_iter()
+            .map(Keypair::from_secret)
+            .collect()
+    }
+
+    /// Generates `Keypair` with caller-supplied entropy mixed into its
+    /// secret key, as `SecretKey::generate_with_entropy` does.
+    ///
+    /// Defense in depth, not a substitute for a CSPRNG: the key is as
+    /// hard to guess as the harder of the system randomness and
+    /// `additional`.
+    ///
+    /// Returns the generated `Keypair`.
+    #[cfg(any(feature = "rand", feature = "getrandom"))]
+    pub fn generate_with_entropy(additional: &[u8]) -> Keypair {
+        Keypair::from_secret(SecretKey::generate_with_entropy(additional))
     }
 
     /// Generates `Keypair` by providing a `SecretKey`.
@@ -53,19 +161,147 @@ impl Keypair {
     ///
     /// fn main() {
     ///     let keypair = Keypair::generate();
-    ///     let secret_key: SecretKey = keypair.secret;
-    ///     let keypair_from_secret_key: Keypair = Keypair::generate_public_key(secret_key);
+    ///     let (secret_key, _): (SecretKey, _) = keypair.into_parts();
+    ///     let keypair_from_secret_key: Keypair = Keypair::from_secret(secret_key);
+    /// }
+    /// ```
+    pub fn from_secret(secret: SecretKey) -> Keypair {
+        let public = PublicKey::from_secret(&secret);
+
+        Keypair {
+            secret,
+            public,
+            unchecked: false,
+        }
+    }
+
+    /// Derives `Keypair` from input keying material, as
+    /// `SecretKey::derive_from_ikm` derives its secret key.
+    ///
+    /// The caller is responsible for the entropy of `ikm`.
+    ///
+    /// Returns the `Keypair` of the derived secret key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::derive_from_ikm(b"master secret", b"salt", b"signing");
+    ///     assert_eq!(
+    ///         keypair.public().to_string(),
+    ///         "d8933fbb6b68db4bb332011eebfb35f969cb3d3b3e702c6a16f0b5495930ef88"
+    ///     );
     /// }
     /// ```
+    pub fn derive_from_ikm(ikm: &[u8], salt: &[u8], info: &[u8]) -> Keypair {
+        Keypair::from_secret(SecretKey::derive_from_ikm(ikm, salt, info))
+    }
+
+    /// Generates `Keypair` by providing a `SecretKey`: this is
+    /// `from_secret` under the old name. The public key is derived from
+    /// the secret key, so it always matches; `from_bytes` checks the
+    /// public half of stored keypairs the same way.
+    #[deprecated(since = "0.1.0", note = "renamed to `from_secret`")]
     pub fn generate_public_key(secret: SecretKey) -> Keypair {
-        let public = PublicKey::generate(&secret);
+        Keypair::from_secret(secret)
+    }
+
+    /// Borrows the secret key of this `Keypair`.
+    ///
+    /// Returns `&SecretKey`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let copy = Keypair::from_secret(keypair.secret().clone());
+    ///     assert!(copy.public() == keypair.public());
+    /// }
+    /// ```
+    pub fn secret(&self) -> &SecretKey {
+        &self.secret
+    }
 
-        Keypair { secret, public }
+    /// Borrows the public key of this `Keypair`.
+    ///
+    /// Returns `&PublicKey`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let signature = keypair.sign(b"");
+    ///     assert!(keypair.public().verify(b"", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn public(&self) -> &PublicKey {
+        &self.public
+    }
+
+    /// Takes this `Keypair` apart.
+    ///
+    /// Returns the secret key and the public key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let public_key = *keypair.public();
+    ///     let (secret_key, public) = keypair.into_parts();
+    ///     assert!(public == public_key);
+    ///     assert!(Keypair::from_secret(secret_key).public() == &public_key);
+    /// }
+    /// ```
+    pub fn into_parts(self) -> (SecretKey, PublicKey) {
+        (self.secret, self.public)
+    }
+
+    /// The public key to sign under: the public half, or for a keypair
+    /// from `from_bytes_unchecked` the public key of the seed.
+    pub(crate) fn signing_public(&self) -> PublicKey {
+        if self.unchecked {
+            PublicKey::from_secret(&self.secret)
+        } else {
+            self.public
+        }
     }
 
     /// Converts `Keypair` into a 64-byte array.
     ///
-    /// Returns a 64-byte array `[u8; 64]`.
+    /// `Keypair` doesn't hold its 64 bytes in one place, so unlike the
+    /// other types it can't lend them: this is `to_bytes` under the old
+    /// name.
+    #[deprecated(
+        since = "0.1.0",
+        note = "returns an owned copy; use `to_bytes` or `to_bytes_zeroizing`"
+    )]
+    pub fn as_bytes(&self) -> [u8; 64] {
+        self.to_bytes()
+    }
+
+    /// Converts `Keypair` into a 64-byte array.
+    ///
+    /// Returns a 64-byte array `[u8; 64]`, which holds the secret key and
+    /// is the caller's to wipe; `to_bytes_zeroizing` wipes it on drop.
     ///
     /// # Example
     ///
@@ -76,19 +312,54 @@ impl Keypair {
     ///
     /// fn main() {
     ///     let keypair = Keypair::generate();
-    ///     let bytes: [u8; 64] = keypair.as_bytes();
+    ///     let bytes: [u8; 64] = keypair.to_bytes();
     /// }
     /// ```
-    pub fn as_bytes(&self) -> [u8; 64] {
+    pub fn to_bytes(&self) -> [u8; 64] {
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::Keypair);
         let mut keypair = [0u8; KeypairSize];
         keypair[..SecretKeySize].copy_from_slice(&self.secret.0);
         keypair[SecretKeySize..].copy_from_slice(&self.public.0);
         keypair
     }
 
+    /// Converts `Keypair` into a 64-byte array that is wiped when
+    /// dropped: the secret key followed by the public key.
+    ///
+    /// Returns `Zeroizing<[u8; 64]>`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let bytes = keypair.to_bytes_zeroizing();
+    ///     let keypair_from_bytes = Keypair::from_bytes(&bytes[..]).unwrap();
+    /// }
+    /// ```
+    pub fn to_bytes_zeroizing(&self) -> Zeroizing<[u8; KeypairSize]> {
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::Keypair);
+        let mut keypair = Zeroizing::new([0u8; KeypairSize]);
+        keypair[..SecretKeySize].copy_from_slice(&self.secret.0);
+        keypair[SecretKeySize..].copy_from_slice(&self.public.0);
+        keypair
+    }
+
     /// Constructs `Keypair` from a slice.
     ///
-    /// Returns `Ok(Keypair)` if `bytes` is 64 bytes long and `Err` otherwise.
+    /// The layout is the one of a libsodium secret key: the seed followed
+    /// by its public key.
+    ///
+    /// Returns `Ok(Keypair)` if `bytes` is 64 bytes long and its public
+    /// half is the one of its seed, `Err(Error::InvalidKeypairLength)`,
+    /// with both lengths, if it isn't 64 bytes long and
+    /// `Err(Error::InvalidKeypair)` otherwise.
     ///
     /// # Example
     ///
@@ -99,24 +370,119 @@ impl Keypair {
     ///
     /// fn main() {
     ///     let keypair = Keypair::generate();
-    ///     let bytes: [u8; 64] = keypair.as_bytes();
+    ///     let bytes: [u8; 64] = keypair.to_bytes();
     ///     let keypair_from_bytes: Keypair = Keypair::from_bytes(&bytes).unwrap();
     /// }
     /// ```
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
         if bytes.len() != KeypairSize {
+            return Err(Error::InvalidKeypairLength {
+                expected: KeypairSize,
+                got: bytes.len(),
+            });
+        }
+
+        // The seed is copied straight into the `SecretKey`, so no
+        // temporary copy is left behind to wipe.
+        let mut keypair = Keypair {
+            secret: SecretKey([0u8; SecretKeySize]),
+            public: PublicKey([0u8; PublicKeySize]),
+            unchecked: false,
+        };
+        keypair.secret.0.copy_from_slice(&bytes[..SecretKeySize]);
+        keypair.public = PublicKey::from_secret(&keypair.secret);
+        if keypair.public.0[..] != bytes[SecretKeySize..] {
             return Err(Error::InvalidKeypair);
         }
+        #[cfg(feature = "observer")]
+        observer::imported(observer::KeyKind::Keypair);
 
-        let mut secret_bytes: [u8; 32] = [0u8; SecretKeySize];
-        secret_bytes.copy_from_slice(&bytes[..SecretKeySize]);
-        let mut public_bytes: [u8; 32] = [0u8; PublicKeySize];
-        public_bytes.copy_from_slice(&bytes[SecretKeySize..]);
+        Ok(keypair)
+    }
 
-        Ok(Keypair {
-            secret: SecretKey(secret_bytes),
-            public: PublicKey(public_bytes),
-        })
+    /// Constructs `Keypair` from a slice as `from_bytes` does, but takes
+    /// the public half as it is, without deriving it from the seed.
+    ///
+    /// Only for bytes known to come from `to_bytes`: `public`, `to_bytes`
+    /// and `verify` use the public half, but signing derives the public
+    /// key from the seed every time and hashes that one, since two
+    /// signatures of the same message under different public keys would
+    /// give the secret key away. With a wrong public half, the signatures
+    /// verify under the public key of the seed and not under `public()`.
+    ///
+    /// Returns `Ok(Keypair)` if `bytes` is 64 bytes long and
+    /// `Err(Error::InvalidKeypairLength)` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Error, Keypair};
+    ///
+    /// fn main() {
+    ///     let mut bytes = Keypair::generate().to_bytes();
+    ///     bytes[63] ^= 1;
+    ///     assert_eq!(Keypair::from_bytes(&bytes).err(), Some(Error::InvalidKeypair));
+    ///     let keypair = Keypair::from_bytes_unchecked(&bytes).unwrap();
+    ///     assert_eq!(keypair.public().as_bytes()[..], bytes[32..]);
+    /// }
+    /// ```
+    pub fn from_bytes_unchecked(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != KeypairSize {
+            return Err(Error::InvalidKeypairLength {
+                expected: KeypairSize,
+                got: bytes.len(),
+            });
+        }
+
+        let mut keypair = Keypair {
+            secret: SecretKey([0u8; SecretKeySize]),
+            public: PublicKey([0u8; PublicKeySize]),
+            unchecked: false,
+        };
+        keypair.secret.0.copy_from_slice(&bytes[..SecretKeySize]);
+        keypair.public.0.copy_from_slice(&bytes[SecretKeySize..]);
+        keypair.unchecked = true;
+        #[cfg(feature = "observer")]
+        observer::imported(observer::KeyKind::Keypair);
+
+        Ok(keypair)
+    }
+
+    /// Constructs `Keypair` from a libsodium secret key, as returned by
+    /// `crypto_sign_keypair`: the 32-byte seed followed by the public key.
+    ///
+    /// Returns `Ok(Keypair)` if the embedded public key is the one of the
+    /// seed and `Err(Error::InvalidKeypair)` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let sk: [u8; 64] = keypair.to_libsodium_bytes();
+    ///     let keypair_from_sk = Keypair::from_libsodium_bytes(&sk).unwrap();
+    ///     assert_eq!(keypair_from_sk.public().as_bytes(), keypair.public().as_bytes());
+    /// }
+    /// ```
+    pub fn from_libsodium_bytes(bytes: &[u8; LibsodiumSecretKeySize]) -> Result<Self, Error> {
+        let secret = SecretKey::from_libsodium_seed(bytes).map_err(|_| Error::InvalidKeypair)?;
+        #[cfg(feature = "observer")]
+        observer::imported(observer::KeyKind::Keypair);
+        Ok(Keypair::from_secret(secret))
+    }
+
+    /// Converts `Keypair` into a libsodium secret key: the 32-byte seed
+    /// followed by the public key.
+    ///
+    /// Returns a 64-byte array `[u8; 64]`.
+    pub fn to_libsodium_bytes(&self) -> [u8; LibsodiumSecretKeySize] {
+        self.to_bytes()
     }
 
     /// Signs a message with this `Keypair`.
@@ -137,7 +503,91 @@ impl Keypair {
     /// }
     /// ```
     pub fn sign(&self, message: &[u8]) -> Signature {
-        self.secret.sign(&self.public, message)
+        self.secret.sign_unchecked(&self.signing_public(), message)
+    }
+
+    /// Signs every message with this `Keypair`.
+    ///
+    /// The signatures are those of `sign`, byte for byte, but the secret
+    /// key is expanded and its nonce prefix hashed once for the batch.
+    /// With the `parallel` feature, large batches are signed on the
+    /// rayon thread pool.
+    ///
+    /// Returns the signatures, in the order of `messages`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let messages: [&[u8]; 3] = [b"first", b"second", b"third"];
+    ///     let signatures = keypair.sign_batch(&messages);
+    ///     for (message, signature) in messages.iter().zip(signatures.iter()) {
+    ///         assert_eq!(*signature, keypair.sign(message));
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn sign_batch(&self, messages: &[&[u8]]) -> Vec<Signature> {
+        self.secret
+            .sign_batch_unchecked(&self.signing_public(), messages)
+    }
+
+    /// Signs a message with this `Keypair` and verifies the signature
+    /// before returning it, as `SecretKey::sign_checked` does.
+    ///
+    /// Returns `Ok(Signature)`, or `Err(Error::SelfCheckFailed)` if the
+    /// signature doesn't verify.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let signature = keypair.sign_checked(b"").unwrap();
+    ///     assert!(keypair.public().verify(b"", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn sign_checked(&self, message: &[u8]) -> Result<Signature, Error> {
+        self.secret
+            .sign_checked_trusting_public(&self.signing_public(), message)
+    }
+
+    /// Signs a message with this `Keypair`, mixing 32 bytes drawn from
+    /// `rng` into the nonce, as `SecretKey::sign_hedged` does.
+    ///
+    /// The signature verifies like any other, but signing the same
+    /// message twice gives two different signatures.
+    ///
+    /// Returns `Signature`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    /// extern crate rand;
+    ///
+    /// use ed25519_fun::Keypair;
+    /// use rand::rngs::OsRng;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let signature = keypair.sign_hedged(b"", &mut OsRng);
+    ///     assert_ne!(signature, keypair.sign(b""));
+    ///     assert!(keypair.public().verify(b"", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn sign_hedged<R: RngCore + CryptoRng>(&self, message: &[u8], rng: &mut R) -> Signature {
+        self.secret
+            .sign_hedged_unchecked(&self.signing_public(), message, rng)
     }
 
     /// Verifies a signature with this `Keypair`.
@@ -155,7 +605,7 @@ impl Keypair {
     ///     let message: &[u8] = b"";
     ///     let keypair = Keypair::generate();
     ///     let signature: Signature = keypair.sign(message);
-    ///     let _signok = keypair.verify(message, signature).unwrap();
+    ///     assert!(keypair.verify(message, signature).is_ok());
     /// }
     /// ```
     pub fn verify(&self, message: &[u8], signature: Signature) -> Result<(), Error> {
@@ -163,17 +613,286 @@ impl Keypair {
     }
 }
 
+/// Copies the secret key into a new `Keypair`, with the tradeoff of
+/// cloning a `SecretKey`: the copies are independent and each wipes
+/// itself when dropped, but each is one more copy of the key in memory.
+impl Clone for Keypair {
+    fn clone(&self) -> Self {
+        Keypair {
+            secret: self.secret.clone(),
+            public: self.public,
+            unchecked: self.unchecked,
+        }
+    }
+}
+
+/// Constructs `Keypair` as `from_bytes` does.
+impl TryFrom<&[u8]> for Keypair {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+        Keypair::from_bytes(bytes)
+    }
+}
+
+/// Constructs `Keypair` as `from_bytes` does.
+impl TryFrom<&[u8; KeypairSize]> for Keypair {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8; KeypairSize]) -> Result<Self, Error> {
+        Keypair::from_bytes(bytes)
+    }
+}
+
+/// Wipes the secret key, and the public key with it.
+impl Zeroize for Keypair {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
+        self.public.0.zeroize();
+    }
+}
+
+/// The secret key wipes itself when dropped. `Keypair` has no `Drop` of
+/// its own, so its fields can still be moved out.
+impl ZeroizeOnDrop for Keypair {}
+
+/// Formats `Keypair` with the hex of its public key and its secret key
+/// redacted, as `SecretKey` formats itself.
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair")
+            .field("secret", &self.secret)
+            .field("public", &format_args!("{}", self.public))
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     extern crate hex;
 
     use super::*;
+    use std::mem::ManuallyDrop;
+
+    // No two consecutive bytes of `secret` in `output`, as hex of either
+    // case or as the decimal of an array.
+    fn assert_redacted(output: &str, secret: &[u8]) {
+        for pair in secret.windows(2) {
+            let hex = hex::encode(pair);
+            assert!(
+                !output.to_lowercase().contains(&hex),
+                "{} in {}",
+                hex,
+                output
+            );
+            let decimal = format!("{}, {}", pair[0], pair[1]);
+            assert!(!output.contains(&decimal), "{} in {}", decimal, output);
+        }
+    }
+
+    #[test]
+    fn debug_keypair_redacts_secret_key() {
+        // RFC 8032, TEST 1 and TEST 2.
+        for (secret, public) in [
+            (
+                "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+                "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            ),
+            (
+                "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+                "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            ),
+        ]
+        .iter()
+        {
+            let secret_bytes = hex::decode(secret).unwrap();
+            let keypair = Keypair::from_secret(SecretKey::from_bytes(&secret_bytes).unwrap());
+            let output = format!("{:?}", keypair);
+            assert_eq!(
+                output,
+                format!(
+                    "Keypair {{ secret: SecretKey([REDACTED]), public: {} }}",
+                    public
+                )
+            );
+            assert_redacted(&output, &secret_bytes);
+            assert_redacted(&format!("{:#?}", keypair), &secret_bytes);
+        }
+    }
 
     #[test]
     fn as_from_slices_keypair() {
         let keypair_bytes = hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a").unwrap();
         let keypair = Keypair::from_bytes(&keypair_bytes).unwrap();
-        let bytes = keypair.as_bytes();
+        let bytes = keypair.to_bytes();
         assert!(bytes == keypair_bytes[..]);
     }
+
+    const KEYPAIR: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    // Signatures of one message under two public keys, with one R, would
+    // give the seed away: whatever the public half, an unchecked keypair
+    // signs under the public key of its seed.
+    #[test]
+    fn mismatched_public_half_is_never_signed_under() {
+        let bytes = hex::decode(KEYPAIR).unwrap();
+        let keypair = Keypair::from_bytes(&bytes).unwrap();
+        let message: &[u8] = b"one message";
+        let expected = keypair.sign(message);
+
+        for bit in [0, 1, 100, 254].iter() {
+            let mut corrupted = bytes.clone();
+            corrupted[SecretKeySize + bit / 8] ^= 1 << (bit % 8);
+            let unchecked = Keypair::from_bytes_unchecked(&corrupted).unwrap();
+            assert!(unchecked.public().as_bytes()[..] == corrupted[SecretKeySize..]);
+
+            assert_eq!(unchecked.sign(message), expected);
+            assert_eq!(unchecked.clone().sign(message), expected);
+            assert_eq!(unchecked.sign_checked(message), Ok(expected));
+            #[cfg(feature = "std")]
+            assert_eq!(unchecked.sign_batch(&[message]), vec![expected]);
+        }
+    }
+
+    #[cfg(any(feature = "rand", feature = "getrandom"))]
+    #[test]
+    fn keypair_public_key_is_the_secret_keys() {
+        let bytes = hex::decode(KEYPAIR).unwrap();
+        let keypair = Keypair::from_bytes(&bytes).unwrap();
+        assert!(keypair.secret().as_bytes()[..] == bytes[..SecretKeySize]);
+        assert!(keypair.public().as_bytes()[..] == bytes[SecretKeySize..]);
+
+        // Any other public half, even a valid key of another seed.
+        let other = Keypair::generate();
+        for public in [[0u8; PublicKeySize], other.public.0].iter() {
+            let mut mixed = bytes.clone();
+            mixed[SecretKeySize..].copy_from_slice(public);
+            assert!(matches!(
+                Keypair::from_bytes(&mixed),
+                Err(Error::InvalidKeypair)
+            ));
+        }
+        let mut flipped = bytes.clone();
+        flipped[63] ^= 0x80;
+        assert!(matches!(
+            Keypair::from_bytes(&flipped),
+            Err(Error::InvalidKeypair)
+        ));
+
+        let (secret, public) = keypair.into_parts();
+        assert!(Keypair::from_secret(secret).public == public);
+    }
+
+    #[test]
+    fn try_from_keypair() {
+        let bytes = hex::decode(KEYPAIR).unwrap();
+        let keypair = Keypair::try_from(&bytes[..]).unwrap();
+        assert!(keypair.to_bytes()[..] == bytes[..]);
+        let mut array = [0u8; KeypairSize];
+        array.copy_from_slice(&bytes);
+        assert!(Keypair::try_from(&array).unwrap().to_bytes()[..] == array[..]);
+
+        for len in [0, 32, 63, 65].iter() {
+            assert!(matches!(
+                Keypair::try_from(&vec![0u8; *len][..]),
+                Err(Error::InvalidKeypairLength {
+                    expected: KEYPAIR_LENGTH,
+                    got
+                }) if got == *len
+            ));
+        }
+    }
+
+    #[test]
+    fn zeroize_keypair() {
+        let mut keypair = Keypair::from_bytes(&hex::decode(KEYPAIR).unwrap()).unwrap();
+        keypair.zeroize();
+        assert_eq!(keypair.secret.0, [0u8; SecretKeySize]);
+        assert_eq!(keypair.public.0, [0u8; PublicKeySize]);
+
+        fn zeroize_on_drop<T: ZeroizeOnDrop>() {}
+        zeroize_on_drop::<Keypair>();
+        zeroize_on_drop::<SecretKey>();
+    }
+
+    // `ManuallyDrop` keeps the storage alive after the value is dropped,
+    // so the test can look at what the drop left in it.
+    #[test]
+    fn drop_wipes_crate_owned_copies() {
+        let bytes = hex::decode(KEYPAIR).unwrap();
+
+        let mut keypair = ManuallyDrop::new(Keypair::from_bytes(&bytes).unwrap());
+        assert!(keypair.secret.0[..] == bytes[..SecretKeySize]);
+        unsafe { ManuallyDrop::drop(&mut keypair) };
+        assert_eq!(keypair.secret.0, [0u8; SecretKeySize]);
+
+        let keypair = Keypair::from_bytes(&bytes).unwrap();
+        let mut exported = ManuallyDrop::new(keypair.to_bytes_zeroizing());
+        assert!(exported[..] == keypair.to_bytes()[..]);
+        unsafe { ManuallyDrop::drop(&mut exported) };
+        assert!(exported.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn clone_keypair() {
+        let keypair = Keypair::from_bytes(&hex::decode(KEYPAIR).unwrap()).unwrap();
+        let mut clone = ManuallyDrop::new(keypair.clone());
+        assert_eq!(clone.sign(b"abc"), keypair.sign(b"abc"));
+        assert!(clone.public == keypair.public);
+
+        // The clone owns its own bytes: wiping them leaves the original.
+        unsafe { ManuallyDrop::drop(&mut clone) };
+        assert_eq!(clone.secret.0, [0u8; SecretKeySize]);
+        assert!(keypair.secret.0[..] == hex::decode(KEYPAIR).unwrap()[..SecretKeySize]);
+        assert!(keypair.verify(b"abc", keypair.sign(b"abc")).is_ok());
+    }
+
+    #[test]
+    fn libsodium_bytes() {
+        // crypto_sign_seed_keypair() output from libsodium's
+        // test/default/sign.exp.
+        let mut sk = [0u8; LibsodiumSecretKeySize];
+        sk.copy_from_slice(&hex::decode("421151a459faeade3d247115f94aedae42318124095afabe4d1451a559faedeeb5076a8474a832daee4dd5b4040983b6623b5f344aca57d4d6ee4baf3f259e6e").unwrap());
+        let keypair = Keypair::from_libsodium_bytes(&sk).unwrap();
+        assert_eq!(
+            format!("{:x}", keypair.public),
+            "b5076a8474a832daee4dd5b4040983b6623b5f344aca57d4d6ee4baf3f259e6e"
+        );
+        assert!(keypair.to_libsodium_bytes()[..] == sk[..]);
+
+        // The public half must be the one of the seed.
+        sk[63] ^= 1;
+        assert!(matches!(
+            Keypair::from_libsodium_bytes(&sk),
+            Err(Error::InvalidKeypair)
+        ));
+    }
+
+    #[cfg(all(feature = "std", any(feature = "rand", feature = "getrandom")))]
+    #[test]
+    fn generate_batch() {
+        use std::collections::HashSet;
+
+        assert!(Keypair::generate_batch(0).is_empty());
+        let keypairs = Keypair::generate_batch(64);
+        assert_eq!(keypairs.len(), 64);
+        let secrets: HashSet<[u8; 32]> = keypairs.iter().map(|k| k.secret().0).collect();
+        let publics: HashSet<PublicKey> = keypairs.iter().map(|k| *k.public()).collect();
+        assert_eq!(secrets.len(), 64);
+        assert_eq!(publics.len(), 64);
+        for keypair in keypairs.iter() {
+            assert_eq!(keypair.public(), &keypair.secret().public_key());
+            let signature = keypair.sign(b"device identity");
+            assert!(keypair.verify(b"device identity", signature).is_ok());
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            let keypairs = Keypair::generate_batch_parallel(64);
+            assert_eq!(keypairs.len(), 64);
+            for keypair in keypairs.iter() {
+                assert_eq!(keypair.public(), &keypair.secret().public_key());
+            }
+        }
+    }
 }
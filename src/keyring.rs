@@ -0,0 +1,247 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// A set of trusted public keys, indexed by fingerprint, that answers
+// which of them, if any, made a signature.
+
+use crate::errors::*;
+use crate::fingerprint::*;
+use crate::public::*;
+use crate::signature::*;
+
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+use std::iter::FromIterator;
+
+/// Trusted public keys, indexed by their `FingerPrint`.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{Keypair, Keyring};
+///
+/// fn main() {
+///     let old = Keypair::generate();
+///     let new = Keypair::generate();
+///     let mut keyring: Keyring = vec![*old.public(), *new.public()].into_iter().collect();
+///
+///     let signature = new.sign(b"artifact");
+///     assert_eq!(keyring.verify(b"artifact", &signature), Ok(new.public()));
+///
+///     keyring.remove(old.public());
+///     assert!(keyring.verify(b"artifact", &old.sign(b"artifact")).is_err());
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Keyring {
+    keys: BTreeMap<FingerPrint, PublicKey>,
+}
+
+impl Keyring {
+    /// Creates an empty `Keyring`.
+    pub fn new() -> Self {
+        Keyring {
+            keys: BTreeMap::new(),
+        }
+    }
+
+    /// Trusts `public`.
+    ///
+    /// Returns `true` if it wasn't trusted yet.
+    pub fn insert(&mut self, public: PublicKey) -> bool {
+        self.keys.insert(public.fingerprint(), public).is_none()
+    }
+
+    /// Stops trusting `public`.
+    ///
+    /// Returns `true` if it was trusted.
+    pub fn remove(&mut self, public: &PublicKey) -> bool {
+        self.keys.remove(&public.fingerprint()).is_some()
+    }
+
+    /// Returns `true` if `public` is trusted.
+    pub fn contains(&self, public: &PublicKey) -> bool {
+        self.keys.contains_key(&public.fingerprint())
+    }
+
+    /// Returns the trusted key of fingerprint `fingerprint`, if any.
+    pub fn get(&self, fingerprint: &FingerPrint) -> Option<&PublicKey> {
+        self.keys.get(fingerprint)
+    }
+
+    /// Number of trusted keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if no key is trusted.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Iterates over the trusted keys, in fingerprint order.
+    pub fn iter(&self) -> btree_map::Values<'_, FingerPrint, PublicKey> {
+        self.keys.values()
+    }
+
+    /// Finds the trusted key that made `signature` over `message`,
+    /// trying every key.
+    ///
+    /// Returns `Ok(&PublicKey)` if one of them verifies it and
+    /// `Err(Error::NoMatchingKey)` otherwise.
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<&PublicKey, Error> {
+        self.keys
+            .values()
+            .find(|public| public.verify(message, signature).is_ok())
+            .ok_or(Error::NoMatchingKey)
+    }
+
+    /// Verifies `signature` over `message` against the trusted key of
+    /// fingerprint `hint` only, as sent along with the signature.
+    ///
+    /// Returns `Ok(&PublicKey)` if that key is trusted and verifies it,
+    /// and `Err(Error::NoMatchingKey)` otherwise.
+    pub fn verify_with_hint(
+        &self,
+        message: &[u8],
+        signature: &Signature,
+        hint: &FingerPrint,
+    ) -> Result<&PublicKey, Error> {
+        self.keys
+            .get(hint)
+            .filter(|public| public.verify(message, signature).is_ok())
+            .ok_or(Error::NoMatchingKey)
+    }
+}
+
+impl FromIterator<PublicKey> for Keyring {
+    fn from_iter<I: IntoIterator<Item = PublicKey>>(keys: I) -> Self {
+        let mut keyring = Keyring::new();
+        keyring.extend(keys);
+        keyring
+    }
+}
+
+impl Extend<PublicKey> for Keyring {
+    fn extend<I: IntoIterator<Item = PublicKey>>(&mut self, keys: I) {
+        for public in keys {
+            self.insert(public);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // RFC 8032, tests 1, 2 and 3.
+    const PUBLIC_KEY_1: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const SIGNATURE_1: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+                               5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+    const PUBLIC_KEY_2: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const SIGNATURE_2: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                               085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+    const PUBLIC_KEY_3: &str = "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025";
+
+    fn public(hex: &str) -> PublicKey {
+        PublicKey::from_bytes(&hex::decode(hex).unwrap()).unwrap()
+    }
+
+    fn signature(hex: &str) -> Signature {
+        Signature::from_bytes(&hex::decode(hex).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn keyring_finds_the_signer() {
+        let keyring: Keyring = [PUBLIC_KEY_1, PUBLIC_KEY_2, PUBLIC_KEY_3]
+            .iter()
+            .map(|hex| public(hex))
+            .collect();
+        assert_eq!(keyring.len(), 3);
+        assert_eq!(
+            keyring.verify(b"", &signature(SIGNATURE_1)),
+            Ok(&public(PUBLIC_KEY_1))
+        );
+        assert_eq!(
+            keyring.verify(&[0x72], &signature(SIGNATURE_2)),
+            Ok(&public(PUBLIC_KEY_2))
+        );
+        // A signature over another message.
+        assert_eq!(
+            keyring.verify(&[0x72], &signature(SIGNATURE_1)),
+            Err(Error::NoMatchingKey)
+        );
+
+        let fingerprint = public(PUBLIC_KEY_2).fingerprint();
+        assert_eq!(keyring.get(&fingerprint), Some(&public(PUBLIC_KEY_2)));
+        assert_eq!(
+            keyring.verify_with_hint(&[0x72], &signature(SIGNATURE_2), &fingerprint),
+            Ok(&public(PUBLIC_KEY_2))
+        );
+        // The hint names another trusted key.
+        assert_eq!(
+            keyring.verify_with_hint(
+                &[0x72],
+                &signature(SIGNATURE_2),
+                &public(PUBLIC_KEY_1).fingerprint()
+            ),
+            Err(Error::NoMatchingKey)
+        );
+    }
+
+    #[test]
+    fn keyring_rotation() {
+        let mut keyring = Keyring::new();
+        assert!(keyring.insert(public(PUBLIC_KEY_1)));
+        assert!(!keyring.insert(public(PUBLIC_KEY_1)));
+        assert!(keyring.insert(public(PUBLIC_KEY_2)));
+        assert!(keyring.verify(b"", &signature(SIGNATURE_1)).is_ok());
+
+        // The old key is retired: its signatures are no longer trusted,
+        // the new key's still are.
+        assert!(keyring.remove(&public(PUBLIC_KEY_1)));
+        assert!(!keyring.remove(&public(PUBLIC_KEY_1)));
+        assert!(!keyring.contains(&public(PUBLIC_KEY_1)));
+        assert_eq!(
+            keyring.verify(b"", &signature(SIGNATURE_1)),
+            Err(Error::NoMatchingKey)
+        );
+        assert_eq!(
+            keyring.verify_with_hint(
+                b"",
+                &signature(SIGNATURE_1),
+                &public(PUBLIC_KEY_1).fingerprint()
+            ),
+            Err(Error::NoMatchingKey)
+        );
+        assert!(keyring.verify(&[0x72], &signature(SIGNATURE_2)).is_ok());
+        assert_eq!(
+            keyring.iter().collect::<Vec<_>>(),
+            vec![&public(PUBLIC_KEY_2)]
+        );
+    }
+
+    #[test]
+    fn keyring_rejects_untrusted_signer() {
+        let keyring: Keyring = vec![public(PUBLIC_KEY_2), public(PUBLIC_KEY_3)]
+            .into_iter()
+            .collect();
+        // A valid signature, by a key that isn't in the keyring.
+        assert!(public(PUBLIC_KEY_1)
+            .verify(b"", &signature(SIGNATURE_1))
+            .is_ok());
+        assert_eq!(
+            keyring.verify(b"", &signature(SIGNATURE_1)),
+            Err(Error::NoMatchingKey)
+        );
+        assert_eq!(
+            Keyring::new().verify(b"", &signature(SIGNATURE_1)),
+            Err(Error::NoMatchingKey)
+        );
+    }
+}
@@ -0,0 +1,584 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// EIP-2335 keystores, holding an Ed25519 seed where Ethereum validators
+// hold a BLS secret key. See https://eips.ethereum.org/EIPS/eip-2335.
+//
+// The password is NFKD-normalized and stripped of control codes, then
+// stretched by the KDF (scrypt or PBKDF2-HMAC-SHA256) into 32 bytes: the
+// first 16 are the AES-128-CTR key, the last 16 go into the checksum,
+// SHA-256(key[16..32] || ciphertext). The checksum is verified before
+// anything is decrypted.
+//
+// `pubkey` is the hex of the Ed25519 public key. `decrypt` checks it when
+// it's present; `decrypt_secret_key` doesn't look at it, which opens
+// keystores of other key types as well.
+
+use crate::b16;
+use crate::errors::*;
+use crate::keypair::*;
+use crate::public::*;
+use crate::rng::*;
+use crate::secret::*;
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::convert::TryFrom;
+use subtle::ConstantTimeEq;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::{Zeroize, Zeroizing};
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+const VERSION: u32 = 4;
+const DKLEN: u32 = 32;
+const SALT_SIZE: usize = 32;
+const IV_SIZE: usize = 16;
+
+/// The key derivation function of a keystore and its cost.
+///
+/// The default is scrypt with the parameters of EIP-2335: n = 2^18,
+/// r = 8 and p = 1.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KeystoreKdf {
+    /// scrypt, with `n` a power of two.
+    Scrypt { n: u32, r: u32, p: u32 },
+    /// PBKDF2-HMAC-SHA256 with `c` iterations.
+    Pbkdf2 { c: u32 },
+}
+
+/// scrypt, n = 2^18, r = 8 and p = 1.
+impl Default for KeystoreKdf {
+    fn default() -> Self {
+        KeystoreKdf::Scrypt {
+            n: 1 << 18,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+impl KeystoreKdf {
+    /// Stretches `password` into the 32 bytes of the decryption key and
+    /// the checksum key.
+    ///
+    /// Returns `Err(Error::InvalidKeystore)` if the parameters are out of
+    /// range.
+    fn derive(&self, password: &[u8], salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, Error> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        match *self {
+            KeystoreKdf::Scrypt { n, r, p } => {
+                if !n.is_power_of_two() {
+                    return Err(Error::InvalidKeystore);
+                }
+                let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p, key.len())
+                    .map_err(|_| Error::InvalidKeystore)?;
+                scrypt::scrypt(password, salt, &params, &mut key[..])
+                    .map_err(|_| Error::InvalidKeystore)?;
+            }
+            KeystoreKdf::Pbkdf2 { c } => {
+                if c == 0 {
+                    return Err(Error::InvalidKeystore);
+                }
+                pbkdf2::pbkdf2_hmac::<sha2_10::Sha256>(password, salt, c, &mut key[..]);
+            }
+        }
+        Ok(key)
+    }
+
+    /// Writes the `kdf` module.
+    fn to_module(self, salt: &[u8]) -> Module {
+        let params = match self {
+            KeystoreKdf::Scrypt { n, r, p } => json!({
+                "dklen": DKLEN,
+                "n": n,
+                "p": p,
+                "r": r,
+                "salt": b16::encode(salt),
+            }),
+            KeystoreKdf::Pbkdf2 { c } => json!({
+                "c": c,
+                "dklen": DKLEN,
+                "prf": "hmac-sha256",
+                "salt": b16::encode(salt),
+            }),
+        };
+        let function = match self {
+            KeystoreKdf::Scrypt { .. } => "scrypt",
+            KeystoreKdf::Pbkdf2 { .. } => "pbkdf2",
+        };
+        Module::new(function, params, &[])
+    }
+
+    /// Reads the `kdf` module.
+    ///
+    /// Returns the KDF and its salt.
+    fn from_module(module: &Module) -> Result<(KeystoreKdf, Vec<u8>), Error> {
+        if module.number("dklen")? != DKLEN || !module.message.is_empty() {
+            return Err(Error::InvalidKeystore);
+        }
+        let kdf = match module.function.as_str() {
+            "scrypt" => KeystoreKdf::Scrypt {
+                n: module.number("n")?,
+                r: module.number("r")?,
+                p: module.number("p")?,
+            },
+            "pbkdf2" if module.string("prf")? == "hmac-sha256" => KeystoreKdf::Pbkdf2 {
+                c: module.number("c")?,
+            },
+            _ => return Err(Error::InvalidKeystore),
+        };
+        Ok((kdf, module.bytes("salt")?))
+    }
+}
+
+/// A keystore module: a function, its parameters and its message.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Module {
+    function: String,
+    params: Map<String, Value>,
+    message: String,
+}
+
+impl Module {
+    fn new(function: &str, params: Value, message: &[u8]) -> Module {
+        let params = match params {
+            Value::Object(params) => params,
+            _ => Map::new(),
+        };
+        Module {
+            function: function.to_string(),
+            params,
+            message: b16::encode(message),
+        }
+    }
+
+    fn string(&self, name: &str) -> Result<&str, Error> {
+        self.params
+            .get(name)
+            .and_then(Value::as_str)
+            .ok_or(Error::InvalidKeystore)
+    }
+
+    fn number(&self, name: &str) -> Result<u32, Error> {
+        self.params
+            .get(name)
+            .and_then(Value::as_u64)
+            .and_then(|n| u32::try_from(n).ok())
+            .ok_or(Error::InvalidKeystore)
+    }
+
+    fn bytes(&self, name: &str) -> Result<Vec<u8>, Error> {
+        hex_bytes(self.string(name)?)
+    }
+}
+
+/// Decodes hex of any even length, in either case.
+fn hex_bytes(hex: &str) -> Result<Vec<u8>, Error> {
+    let mut bytes = vec![0u8; hex.len() / 2];
+    if !hex.len().is_multiple_of(2) || b16::decode_to_slice(hex, &mut bytes).is_err() {
+        return Err(Error::InvalidKeystore);
+    }
+    Ok(bytes)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Crypto {
+    kdf: Module,
+    checksum: Module,
+    cipher: Module,
+}
+
+/// An EIP-2335 keystore, holding an Ed25519 seed encrypted under a
+/// password.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::keystore::{Keystore, KeystoreKdf};
+/// use ed25519_fun::Keypair;
+///
+/// fn main() {
+///     let keypair = Keypair::generate();
+///     let kdf = KeystoreKdf::Pbkdf2 { c: 1024 };
+///     let mut keystore = Keystore::encrypt(&keypair, "testpassword", kdf);
+///     keystore.path = "m/44'/501'/0'".to_string();
+///
+///     let json = keystore.to_json();
+///     let decrypted = Keystore::from_json(&json)
+///         .unwrap()
+///         .decrypt("testpassword")
+///         .unwrap();
+///     assert!(decrypted.public() == keypair.public());
+/// }
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Keystore {
+    crypto: Crypto,
+    /// A free-form description.
+    #[serde(default)]
+    pub description: String,
+    /// The hex of the public key, or empty.
+    #[serde(default)]
+    pub pubkey: String,
+    /// The derivation path of the key, or empty.
+    pub path: String,
+    /// The UUID of the keystore.
+    pub uuid: String,
+    version: u32,
+}
+
+/// Prepares `password` as EIP-2335 requires: NFKD normalization, then
+/// without the C0 and C1 control codes and Delete.
+fn process_password(password: &str) -> Vec<u8> {
+    password
+        .nfkd()
+        .filter(|c| !matches!(*c as u32, 0x00..=0x1f | 0x7f..=0x9f))
+        .collect::<String>()
+        .into_bytes()
+}
+
+/// A random (version 4) UUID.
+fn random_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    default_rng().fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    format!(
+        "{}-{}-{}-{}-{}",
+        b16::encode(&bytes[..4]),
+        b16::encode(&bytes[4..6]),
+        b16::encode(&bytes[6..8]),
+        b16::encode(&bytes[8..10]),
+        b16::encode(&bytes[10..])
+    )
+}
+
+/// The checksum of `ciphertext` under the derived key.
+fn checksum(key: &[u8; 32], ciphertext: &[u8]) -> [u8; 32] {
+    let mut hash = Sha256::new();
+    hash.input(&key[16..]);
+    hash.input(ciphertext);
+    let mut checksum = [0u8; 32];
+    checksum.copy_from_slice(&hash.result());
+    checksum
+}
+
+impl Keystore {
+    /// Encrypts the seed of `keypair` under `password` into a new
+    /// keystore, with a fresh random salt, IV and UUID. `pubkey` is set to
+    /// the public key, and `description` and `path` are left empty.
+    ///
+    /// Returns the `Keystore`.
+    pub fn encrypt(keypair: &Keypair, password: &str, kdf: KeystoreKdf) -> Keystore {
+        let mut salt = [0u8; SALT_SIZE];
+        let mut iv = [0u8; IV_SIZE];
+        let mut csprng = default_rng();
+        csprng.fill_bytes(&mut salt);
+        csprng.fill_bytes(&mut iv);
+        Keystore::encrypt_with(keypair, password, kdf, &salt, &iv, random_uuid())
+    }
+
+    fn encrypt_with(
+        keypair: &Keypair,
+        password: &str,
+        kdf: KeystoreKdf,
+        salt: &[u8; SALT_SIZE],
+        iv: &[u8; IV_SIZE],
+        uuid: String,
+    ) -> Keystore {
+        let mut password = process_password(password);
+        let key = kdf.derive(&password, salt).unwrap();
+        password[..].zeroize();
+
+        let mut ciphertext = *keypair.secret().as_bytes();
+        Aes128Ctr::new(key[..16].into(), iv.into()).apply_keystream(&mut ciphertext);
+
+        Keystore {
+            crypto: Crypto {
+                kdf: kdf.to_module(salt),
+                checksum: Module::new("sha256", json!({}), &checksum(&key, &ciphertext)),
+                cipher: Module::new("aes-128-ctr", json!({ "iv": b16::encode(iv) }), &ciphertext),
+            },
+            description: String::new(),
+            pubkey: keypair.public().to_string(),
+            path: String::new(),
+            uuid,
+            version: VERSION,
+        }
+    }
+
+    /// Decrypts the seed of the keystore, without looking at `pubkey`.
+    ///
+    /// Returns `Err(Error::WrongPassword)` if the checksum doesn't match,
+    /// which is checked before decrypting, and
+    /// `Err(Error::InvalidKeystore)` if a module is unsupported or
+    /// malformed.
+    pub fn decrypt_secret_key(&self, password: &str) -> Result<SecretKey, Error> {
+        let (kdf, salt) = KeystoreKdf::from_module(&self.crypto.kdf)?;
+        let checksum_module = &self.crypto.checksum;
+        let cipher = &self.crypto.cipher;
+        if checksum_module.function != "sha256"
+            || !checksum_module.params.is_empty()
+            || cipher.function != "aes-128-ctr"
+        {
+            return Err(Error::InvalidKeystore);
+        }
+        let expected = hex_bytes(&checksum_module.message)?;
+        let ciphertext = hex_bytes(&cipher.message)?;
+        let iv = cipher.bytes("iv")?;
+        if ciphertext.len() != 32 || iv.len() != IV_SIZE {
+            return Err(Error::InvalidKeystore);
+        }
+
+        let mut password = process_password(password);
+        let key = kdf.derive(&password, &salt);
+        password[..].zeroize();
+        let key = key?;
+        if !bool::from(checksum(&key, &ciphertext)[..].ct_eq(&expected[..])) {
+            return Err(Error::WrongPassword);
+        }
+
+        let mut secret = SecretKey([0u8; 32]);
+        secret.0.copy_from_slice(&ciphertext);
+        Aes128Ctr::new(key[..16].into(), iv[..].into()).apply_keystream(&mut secret.0);
+        Ok(secret)
+    }
+
+    /// Decrypts the keystore.
+    ///
+    /// Returns `Ok(Keypair)` of the decrypted seed, the errors of
+    /// `decrypt_secret_key`, and `Err(Error::InvalidKeypair)` if `pubkey`
+    /// isn't empty and isn't the public key of the seed.
+    pub fn decrypt(&self, password: &str) -> Result<Keypair, Error> {
+        let keypair = Keypair::from_secret(self.decrypt_secret_key(password)?);
+        if !self.pubkey.is_empty()
+            && self.pubkey.parse::<PublicKey>().ok() != Some(*keypair.public())
+        {
+            return Err(Error::InvalidKeypair);
+        }
+        Ok(keypair)
+    }
+
+    /// Parses a keystore from its JSON document.
+    ///
+    /// Returns `Err(Error::InvalidKeystore)` if `json` isn't a version 4
+    /// keystore. The modules are only checked by `decrypt`.
+    pub fn from_json(json: &str) -> Result<Keystore, Error> {
+        let keystore: Keystore = serde_json::from_str(json).map_err(|_| Error::InvalidKeystore)?;
+        if keystore.version != VERSION {
+            return Err(Error::InvalidKeystore);
+        }
+        Ok(keystore)
+    }
+
+    /// Writes the JSON document of the keystore.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The test vectors of EIP-2335: a BLS secret key, encrypted under
+    // "𝔱𝔢𝔰𝔱𝔭𝔞𝔰𝔰𝔴𝔬𝔯𝔡🔑", which processes to "testpassword🔑".
+    const PASSWORD: &str = "𝔱𝔢𝔰𝔱𝔭𝔞𝔰𝔰𝔴𝔬𝔯𝔡🔑";
+    const SECRET: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+    const SCRYPT_KEYSTORE: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "scrypt",
+                "params": {
+                    "dklen": 32,
+                    "n": 262144,
+                    "p": 1,
+                    "r": 8,
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "d2217fe5f3e9a1e34581ef8a78f7c9928e436d36dacc5e846690a5581e8ea484"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": {
+                    "iv": "264daa3f303d7259501c93d997d84fe6"
+                },
+                "message": "06ae90d55fe0a6e9c5c3bc5b170827b2e5cce3929ed3f116c2811e6366dfe20f"
+            }
+        },
+        "description": "This is a test keystore that uses scrypt to secure the secret.",
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/3141592653/589793238",
+        "uuid": "1d85ae20-35c5-4611-98e8-aa14a633906f",
+        "version": 4
+    }"#;
+
+    const PBKDF2_KEYSTORE: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "pbkdf2",
+                "params": {
+                    "dklen": 32,
+                    "c": 262144,
+                    "prf": "hmac-sha256",
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": {
+                    "iv": "264daa3f303d7259501c93d997d84fe6"
+                },
+                "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+            }
+        },
+        "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/0/0",
+        "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+        "version": 4
+    }"#;
+
+    #[test]
+    fn keystore_eip2335_vectors() {
+        for json in [SCRYPT_KEYSTORE, PBKDF2_KEYSTORE].iter() {
+            let keystore = Keystore::from_json(json).unwrap();
+            let secret = keystore.decrypt_secret_key(PASSWORD).unwrap();
+            assert_eq!(secret.to_hex(), SECRET);
+            assert_eq!(
+                keystore.decrypt_secret_key("testpassword").err(),
+                Some(Error::WrongPassword)
+            );
+
+            // The BLS public key isn't the Ed25519 one of the seed.
+            assert_eq!(
+                keystore.decrypt(PASSWORD).err(),
+                Some(Error::InvalidKeypair)
+            );
+            let mut keystore = keystore;
+            keystore.pubkey = secret.public_key().to_string();
+            assert!(keystore.decrypt(PASSWORD).unwrap().public() == &secret.public_key());
+            keystore.pubkey.clear();
+            assert!(keystore.decrypt(PASSWORD).is_ok());
+        }
+    }
+
+    #[test]
+    fn keystore_round_trip() {
+        let keypair = Keypair::generate();
+        let kdfs = [
+            KeystoreKdf::Scrypt {
+                n: 1024,
+                r: 8,
+                p: 1,
+            },
+            KeystoreKdf::Pbkdf2 { c: 1024 },
+        ];
+        for kdf in kdfs.iter() {
+            let keystore = Keystore::encrypt(&keypair, "password", *kdf);
+            assert_eq!(keystore.pubkey, keypair.public().to_string());
+            let uuid = keystore.uuid.as_bytes();
+            assert!(uuid.len() == 36 && uuid[14] == b'4' && b"89ab".contains(&uuid[19]));
+
+            let parsed = Keystore::from_json(&keystore.to_json()).unwrap();
+            assert_eq!(parsed.uuid, keystore.uuid);
+            let decrypted = parsed.decrypt("password").unwrap();
+            assert!(decrypted.secret() == keypair.secret());
+            assert_eq!(parsed.decrypt("Password").err(), Some(Error::WrongPassword));
+        }
+    }
+
+    // The password is normalized and stripped of control codes before
+    // stretching, on both sides.
+    #[test]
+    fn keystore_processes_passwords() {
+        assert_eq!(process_password(PASSWORD), "testpassword🔑".as_bytes());
+        assert_eq!(process_password("a\u{0}b\u{7f}c\u{85}d\ne"), b"abcde");
+
+        let keypair = Keypair::generate();
+        let keystore = Keystore::encrypt(&keypair, PASSWORD, KeystoreKdf::Pbkdf2 { c: 16 });
+        assert!(keystore.decrypt("testpassword🔑\n").is_ok());
+    }
+
+    #[test]
+    fn keystore_rejects_malformed() {
+        let keypair = Keypair::generate();
+        let json = Keystore::encrypt(&keypair, "", KeystoreKdf::Pbkdf2 { c: 16 }).to_json();
+        let edited = |from: &str, to: &str| {
+            assert!(json.contains(from));
+            Keystore::from_json(&json.replacen(from, to, 1))
+                .and_then(|keystore| keystore.decrypt(""))
+                .err()
+        };
+
+        assert_eq!(
+            edited("\"version\": 4", "\"version\": 3"),
+            Some(Error::InvalidKeystore)
+        );
+        assert_eq!(
+            edited("\"pbkdf2\"", "\"argon2\""),
+            Some(Error::InvalidKeystore)
+        );
+        assert_eq!(
+            edited("\"hmac-sha256\"", "\"hmac-sha512\""),
+            Some(Error::InvalidKeystore)
+        );
+        assert_eq!(
+            edited("\"dklen\": 32", "\"dklen\": 16"),
+            Some(Error::InvalidKeystore)
+        );
+        assert_eq!(
+            edited("\"c\": 16", "\"c\": 0"),
+            Some(Error::InvalidKeystore)
+        );
+        assert_eq!(
+            edited("\"sha256\"", "\"sha512\""),
+            Some(Error::InvalidKeystore)
+        );
+        assert_eq!(
+            edited("\"aes-128-ctr\"", "\"aes-256-ctr\""),
+            Some(Error::InvalidKeystore)
+        );
+        assert_eq!(
+            edited("\"crypto\"", "\"cryptography\""),
+            Some(Error::InvalidKeystore)
+        );
+        assert!(Keystore::from_json("{}").is_err());
+
+        // The checksum covers the ciphertext.
+        let keystore = Keystore::from_json(&json).unwrap();
+        let message = keystore.crypto.cipher.message.clone();
+        let flipped = format!(
+            "{}{}",
+            if message.starts_with('0') { "1" } else { "0" },
+            &message[1..]
+        );
+        assert_eq!(edited(&message, &flipped), Some(Error::WrongPassword));
+
+        // scrypt's n must be a power of two.
+        let keystore = Keystore::encrypt(&keypair, "", KeystoreKdf::Scrypt { n: 16, r: 8, p: 1 });
+        let json = keystore.to_json().replacen("\"n\": 16", "\"n\": 17", 1);
+        assert_eq!(
+            Keystore::from_json(&json).unwrap().decrypt("").err(),
+            Some(Error::InvalidKeystore)
+        );
+    }
+}
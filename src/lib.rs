@@ -1,21 +1,181 @@
 // Author:
 // - Yuzo <yuzonakai@gmail.com>
 
+// Without `std`, only the curve arithmetic, keys, signing and
+// verification are built, on `core` alone.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "rand")]
 extern crate rand;
+extern crate rand_core;
 extern crate sha2;
 extern crate subtle;
 extern crate zeroize;
 
+// With `verify-only`, the signing half of the curve code is unused.
+#[cfg_attr(feature = "verify-only", allow(dead_code))]
 pub(crate) mod curve25519;
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod b16;
+mod b64;
+#[cfg(any(
+    feature = "base58",
+    feature = "did-key",
+    feature = "libp2p",
+    feature = "near",
+    feature = "ss58",
+    feature = "tezos"
+))]
+mod base58;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "bech32")]
+mod bech32;
+#[cfg(all(feature = "bip39", not(feature = "verify-only")))]
+mod bip39;
+#[cfg(feature = "borsh")]
+mod borsh;
+#[cfg(feature = "codec")]
+mod codec;
+#[cfg(feature = "std")]
+mod combined;
 mod constants;
+mod context;
+#[cfg(feature = "signature-digest")]
+mod digest_signer;
+mod dom2;
+#[cfg(feature = "ed25519")]
+mod ed25519_types;
 mod errors;
+mod fingerprint;
+mod hasher;
+#[cfg(not(feature = "verify-only"))]
+mod hkdf;
+#[cfg(not(feature = "verify-only"))]
 mod keypair;
+#[cfg(feature = "std")]
+mod keyring;
+#[cfg(all(feature = "mlock", not(feature = "verify-only")))]
+mod locked;
+#[cfg(feature = "minicbor")]
+mod minicbor;
+mod multipart;
+#[cfg(feature = "near")]
+mod near;
+mod precomputed;
+mod prehash;
 mod public;
+mod reader;
+#[cfg(feature = "rkyv")]
+mod rkyv;
+// With `verify-only`, only batch verification draws randomness.
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+#[cfg_attr(all(feature = "verify-only", not(feature = "std")), allow(dead_code))]
+mod rng;
+mod rotation;
+#[cfg(not(feature = "verify-only"))]
 mod secret;
 mod signature;
+#[cfg(feature = "std")]
+mod signature_set;
+#[cfg(feature = "ss58")]
+mod ss58;
+#[cfg(feature = "stellar")]
+mod stellar;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "tezos")]
+mod tezos;
+mod utils;
+mod verify;
+mod writer;
+
+#[cfg(feature = "cose")]
+pub mod cose;
+#[cfg(feature = "did-key")]
+pub mod did_key;
+#[cfg(feature = "dkim")]
+pub mod dkim;
+#[cfg(feature = "dnssec")]
+pub mod dnssec;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "hazmat")]
+pub mod hazmat;
+#[cfg(all(feature = "keystore", not(feature = "verify-only")))]
+pub mod keystore;
+#[cfg(feature = "libp2p")]
+pub mod libp2p;
+#[cfg(feature = "minisign")]
+pub mod minisign;
+#[cfg(feature = "nano")]
+pub mod nano;
+#[cfg(feature = "observer")]
+pub mod observer;
+#[cfg(feature = "paseto")]
+pub mod paseto;
+/// `Keypair`, `PublicKey`, `SecretKey`, `Signature` and `Error`.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::prelude::*;
+///
+/// fn main() -> Result<(), Error> {
+///     let keypair = Keypair::from_secret(SecretKey::from_array([7; 32]));
+///     let signature: Signature = keypair.sign(b"prelude");
+///     let public_key: PublicKey = *keypair.public();
+///     public_key.verify(b"prelude", &signature)
+/// }
+/// ```
+pub mod prelude;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+#[cfg(all(feature = "seal", not(feature = "verify-only")))]
+pub mod seal;
+#[cfg(all(feature = "shamir", not(feature = "verify-only")))]
+pub mod shamir;
+#[cfg(feature = "signify")]
+pub mod signify;
+#[cfg(all(feature = "solana", not(feature = "verify-only")))]
+pub mod solana;
+#[cfg(feature = "uniffi")]
+pub mod uniffi;
+#[cfg(feature = "vrf")]
+pub mod vrf;
+#[cfg(feature = "xeddsa")]
+pub mod xeddsa;
+
+#[cfg(feature = "uniffi")]
+::uniffi::setup_scaffolding!();
 
+#[cfg(feature = "std")]
+pub use crate::batch::*;
+pub use crate::constants::{
+    KEYPAIR_LENGTH, MAX_MESSAGE_LEN, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH, SIGNATURE_LENGTH,
+};
+pub use crate::errors::*;
+pub use crate::fingerprint::*;
+#[cfg(not(feature = "verify-only"))]
 pub use crate::keypair::*;
+#[cfg(feature = "std")]
+pub use crate::keyring::*;
+#[cfg(all(feature = "mlock", not(feature = "verify-only")))]
+pub use crate::locked::*;
+pub use crate::multipart::*;
+pub use crate::precomputed::*;
 pub use crate::public::*;
+pub use crate::rotation::*;
+#[cfg(not(feature = "verify-only"))]
 pub use crate::secret::*;
 pub use crate::signature::*;
+#[cfg(feature = "std")]
+pub use crate::signature_set::*;
+#[cfg(feature = "std")]
+pub use crate::stream::*;
+pub use crate::verify::VerificationOptions;
@@ -0,0 +1,185 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// libp2p public keys: the protobuf `PublicKey { Type, Data }` message and
+// the PeerId derived from it. Ed25519 keys are small enough for the
+// identity multihash, so the PeerId embeds the whole protobuf.
+// See https://github.com/libp2p/specs/blob/master/peer-ids/peer-ids.md.
+
+use crate::base58;
+use crate::constants::*;
+use crate::errors::*;
+use crate::public::*;
+use crate::reader::*;
+
+// Protobuf keys (field number << 3 | wire type) of `Type` (varint) and
+// `Data` (length-delimited).
+const FIELD_TYPE: u8 = 1 << 3;
+const FIELD_DATA: u8 = 2 << 3 | 2;
+
+// `KeyType.Ed25519`.
+const KEY_TYPE_ED25519: u8 = 1;
+
+// Length of the encoded protobuf.
+const PROTOBUF_SIZE: usize = 4 + PublicKeySize;
+
+// Identity multihash code.
+const MULTIHASH_IDENTITY: u8 = 0x00;
+
+impl PublicKey {
+    /// Encodes `PublicKey` as a libp2p protobuf `PublicKey` message.
+    ///
+    /// Returns the deterministic encoding `08 01 12 20 || key`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     let protobuf = public_key.to_libp2p_protobuf();
+    ///     let decoded = PublicKey::from_libp2p_protobuf(&protobuf).unwrap();
+    ///     assert_eq!(decoded.as_bytes(), public_key.as_bytes());
+    /// }
+    /// ```
+    pub fn to_libp2p_protobuf(&self) -> [u8; PROTOBUF_SIZE] {
+        let mut protobuf = [0u8; PROTOBUF_SIZE];
+        protobuf[..4].copy_from_slice(&[
+            FIELD_TYPE,
+            KEY_TYPE_ED25519,
+            FIELD_DATA,
+            PublicKeySize as u8,
+        ]);
+        protobuf[4..].copy_from_slice(&self.0);
+        protobuf
+    }
+
+    /// Constructs `PublicKey` from a libp2p protobuf `PublicKey` message.
+    ///
+    /// Only the deterministic encoding required by the libp2p spec is
+    /// accepted.
+    ///
+    /// Returns `Ok(PublicKey)` if `bytes` encodes an Ed25519 key and
+    /// `Err` otherwise.
+    pub fn from_libp2p_protobuf(bytes: &[u8]) -> Result<Self, Error> {
+        parse_exact(bytes, |reader| {
+            let header = reader.take_array::<4>()?;
+            if header
+                != [
+                    FIELD_TYPE,
+                    KEY_TYPE_ED25519,
+                    FIELD_DATA,
+                    PublicKeySize as u8,
+                ]
+            {
+                return Err(Error::InvalidLibp2pKey);
+            }
+            Ok(PublicKey(reader.take_array::<PublicKeySize>()?))
+        })
+    }
+
+    /// Derives the libp2p PeerId of `PublicKey`.
+    ///
+    /// Returns the base58btc PeerId (`12D3KooW...`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     assert!(public_key.to_peer_id_string().starts_with("12D3KooW"));
+    /// }
+    /// ```
+    pub fn to_peer_id_string(&self) -> String {
+        let mut multihash = [0u8; 2 + PROTOBUF_SIZE];
+        multihash[0] = MULTIHASH_IDENTITY;
+        multihash[1] = PROTOBUF_SIZE as u8;
+        multihash[2..].copy_from_slice(&self.to_libp2p_protobuf());
+        base58::encode(&multihash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // Ed25519 key of the libp2p peer-ids spec test vectors. The PeerId
+    // is not taken from go-libp2p: it was computed with a separate Python
+    // implementation of the identity multihash and base58btc encoding.
+    const PROTOBUF: &str =
+        "080112201ed1e8fae2c4a144b8be8fd4b47bf3d3b34b871c3cacf6010f0e42d474fce27e";
+    const PEER_ID: &str = "12D3KooWBtg3aaRMjxwedh83aGiUkwSxDwUZkzuJcfaqUmo7R3pq";
+
+    #[test]
+    fn libp2p_spec_key() {
+        let protobuf = hex::decode(PROTOBUF).unwrap();
+        let public = PublicKey::from_libp2p_protobuf(&protobuf).unwrap();
+        assert_eq!(
+            format!("{:x}", public),
+            "1ed1e8fae2c4a144b8be8fd4b47bf3d3b34b871c3cacf6010f0e42d474fce27e"
+        );
+        assert_eq!(public.to_libp2p_protobuf()[..], protobuf[..]);
+        assert_eq!(public.to_peer_id_string(), PEER_ID);
+    }
+
+    #[test]
+    fn libp2p_protobuf_roundtrip() {
+        for _ in 0..16 {
+            let public = PublicKey(rand::random());
+            let decoded = PublicKey::from_libp2p_protobuf(&public.to_libp2p_protobuf()).unwrap();
+            assert_eq!(decoded.as_bytes(), public.as_bytes());
+        }
+    }
+
+    #[test]
+    fn libp2p_protobuf_rejects_malformed() {
+        let protobuf = hex::decode(PROTOBUF).unwrap();
+
+        // Secp256k1 key type.
+        let mut secp256k1 = protobuf.clone();
+        secp256k1[1] = 2;
+        assert_eq!(
+            PublicKey::from_libp2p_protobuf(&secp256k1).err(),
+            Some(Error::InvalidLibp2pKey)
+        );
+        // Fields out of order.
+        let mut swapped = vec![0x12, 0x20];
+        swapped.extend_from_slice(&protobuf[4..]);
+        swapped.extend_from_slice(&[0x08, 0x01]);
+        assert_eq!(
+            PublicKey::from_libp2p_protobuf(&swapped).err(),
+            Some(Error::InvalidLibp2pKey)
+        );
+        // Wrong data length.
+        assert_eq!(
+            PublicKey::from_libp2p_protobuf(&protobuf[..35]).err(),
+            Some(Error::Truncated { offset: 4 })
+        );
+        let mut long = protobuf.clone();
+        long.push(0);
+        assert_eq!(
+            PublicKey::from_libp2p_protobuf(&long).err(),
+            Some(Error::TrailingBytes {
+                consumed: 36,
+                total: 37
+            })
+        );
+    }
+}
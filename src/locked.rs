@@ -0,0 +1,206 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Secret keys kept for the life of a process, in memory that can't be
+// swapped out: the seed lives in its own mlock'd allocation between
+// guard pages (memsec), is written there directly and never copied to
+// the unlocked heap. Signing still expands the seed on the stack, as
+// `SecretKey` does, and wipes it after.
+//
+// mlock fails where the locked memory limit is too low, as with the
+// RLIMIT_MEMLOCK of 0 of some containers. `LockPolicy` says whether
+// that is an error or the key is kept anyway, unlocked.
+
+use crate::constants::*;
+use crate::errors::*;
+use crate::public::*;
+use crate::rng::*;
+use crate::secret::*;
+use crate::signature::*;
+
+use rand_core::RngCore;
+use std::fmt;
+use std::ptr::{self, NonNull};
+use zeroize::Zeroize;
+
+/// What to do when the memory of a `LockedSecretKey` can't be locked.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LockPolicy {
+    /// Fail with `Error::MemoryLockFailed`.
+    Required,
+    /// Keep the key in unlocked memory: `is_locked` tells.
+    BestEffort,
+}
+
+/// A `SecretKey` in page-locked memory, wiped and unlocked on drop.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{LockPolicy, LockedSecretKey};
+///
+/// fn main() {
+///     let secret = LockedSecretKey::generate(LockPolicy::BestEffort).unwrap();
+///     if !secret.is_locked() {
+///         eprintln!("warning: the signing key may be swapped out");
+///     }
+///     let public = secret.public_key();
+///     let signature = secret.sign(&public, b"message").unwrap();
+///     assert!(public.verify(b"message", &signature).is_ok());
+/// }
+/// ```
+pub struct LockedSecretKey {
+    key: NonNull<SecretKey>,
+    locked: bool,
+}
+
+// The allocation is owned by `LockedSecretKey` alone and only read
+// through `&self`.
+unsafe impl Send for LockedSecretKey {}
+unsafe impl Sync for LockedSecretKey {}
+
+/// Locks the pages of `len` bytes at `ptr`.
+fn lock_pages(ptr: *mut u8, len: usize) -> bool {
+    unsafe { memsec::mlock(ptr, len) }
+}
+
+impl LockedSecretKey {
+    /// Generates a secret key directly in locked memory.
+    ///
+    /// Returns `Err(Error::MemoryLockFailed)` if the memory can't be
+    /// allocated, or can't be locked under `LockPolicy::Required`.
+    pub fn generate(policy: LockPolicy) -> Result<Self, Error> {
+        LockedSecretKey::allocate(policy, lock_pages, |key| {
+            default_rng().fill_bytes(&mut key.0)
+        })
+    }
+
+    /// Moves `secret` into locked memory. `secret` is wiped as it is
+    /// dropped, but earlier copies of it, in the caller's hands, aren't.
+    ///
+    /// Returns `Err(Error::MemoryLockFailed)` if the memory can't be
+    /// allocated, or can't be locked under `LockPolicy::Required`.
+    pub fn from_secret(secret: SecretKey, policy: LockPolicy) -> Result<Self, Error> {
+        LockedSecretKey::allocate(policy, lock_pages, |key| key.0.copy_from_slice(&secret.0))
+    }
+
+    fn allocate<L, F>(policy: LockPolicy, lock: L, fill: F) -> Result<Self, Error>
+    where
+        L: FnOnce(*mut u8, usize) -> bool,
+        F: FnOnce(&mut SecretKey),
+    {
+        let key = unsafe { memsec::malloc::<SecretKey>() }.ok_or(Error::MemoryLockFailed)?;
+        unsafe { ptr::write(key.as_ptr(), SecretKey([0u8; SecretKeySize])) };
+        let mut locked = LockedSecretKey {
+            key,
+            locked: lock(key.as_ptr() as *mut u8, SecretKeySize),
+        };
+        if !locked.locked && policy == LockPolicy::Required {
+            return Err(Error::MemoryLockFailed);
+        }
+        fill(unsafe { locked.key.as_mut() });
+        Ok(locked)
+    }
+
+    /// Returns `true` if the key's memory is locked, `false` if it was
+    /// kept unlocked under `LockPolicy::BestEffort`.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    fn secret(&self) -> &SecretKey {
+        unsafe { self.key.as_ref() }
+    }
+
+    /// Computes the `PublicKey` of the secret key.
+    pub fn public_key(&self) -> PublicKey {
+        self.secret().public_key()
+    }
+
+    /// Signs `message` as `SecretKey::sign` does.
+    ///
+    /// Returns `Err(Error::PublicKeyMismatch)` if `public` isn't the
+    /// public key of this secret key.
+    pub fn sign(&self, public: &PublicKey, message: &[u8]) -> Result<Signature, Error> {
+        self.secret().sign(public, message)
+    }
+}
+
+/// Wipes the key, then unlocks and unmaps its memory.
+impl Drop for LockedSecretKey {
+    fn drop(&mut self) {
+        unsafe {
+            self.key.as_mut().zeroize();
+            memsec::free(self.key);
+        }
+    }
+}
+
+/// Formats `LockedSecretKey` with its key redacted.
+impl fmt::Debug for LockedSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LockedSecretKey([REDACTED], locked: {})", self.locked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // RFC 8032, TEST 2.
+    const SECRET_KEY: &str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+    const PUBLIC_KEY: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const SIGNATURE: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                             085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+    fn secret() -> SecretKey {
+        SecretKey::from_bytes(&hex::decode(SECRET_KEY).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn locked_secret_key_signs_as_secret_key() {
+        let locked = LockedSecretKey::from_secret(secret(), LockPolicy::BestEffort).unwrap();
+        let public = locked.public_key();
+        assert_eq!(hex::encode(public.as_bytes()), PUBLIC_KEY);
+        let signature = locked.sign(&public, &[0x72]).unwrap();
+        assert_eq!(hex::encode(signature.0), SIGNATURE);
+        assert_eq!(signature, secret().sign(&public, &[0x72]).unwrap());
+
+        let other = LockedSecretKey::generate(LockPolicy::BestEffort).unwrap();
+        assert_eq!(
+            other.sign(&public, b"").err(),
+            Some(Error::PublicKeyMismatch)
+        );
+        let public = other.public_key();
+        assert!(public
+            .verify(b"", &other.sign(&public, b"").unwrap())
+            .is_ok());
+        assert_eq!(
+            format!("{:?}", other),
+            format!("LockedSecretKey([REDACTED], locked: {})", other.is_locked())
+        );
+    }
+
+    #[test]
+    fn lock_policy_on_mlock_failure() {
+        let failing = |_: *mut u8, _: usize| false;
+        let fill = |key: &mut SecretKey| key.0.copy_from_slice(&secret().0);
+        assert_eq!(
+            LockedSecretKey::allocate(LockPolicy::Required, failing, fill).err(),
+            Some(Error::MemoryLockFailed)
+        );
+
+        let unlocked = LockedSecretKey::allocate(LockPolicy::BestEffort, failing, fill).unwrap();
+        assert!(!unlocked.is_locked());
+        assert_eq!(hex::encode(unlocked.public_key().as_bytes()), PUBLIC_KEY);
+
+        let locked =
+            LockedSecretKey::allocate(LockPolicy::Required, |_: *mut u8, _: usize| true, fill)
+                .unwrap();
+        assert!(locked.is_locked());
+    }
+}
@@ -0,0 +1,163 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// CBOR through minicbor: `PublicKey` and `Signature` are definite-length
+// byte strings (major type 2) of exactly 32 and 64 bytes. Decoding
+// rejects any other length, an indefinite-length string and any other
+// type, at the offset of the item. Nothing here allocates, and minicbor
+// is built without its `alloc` feature.
+
+use crate::constants::*;
+use crate::public::*;
+use crate::signature::*;
+
+use ::minicbor::decode::{self, Decoder};
+use ::minicbor::encode::{self, Encoder, Write};
+use ::minicbor::{Decode, Encode};
+
+/// Reads a definite-length byte string of exactly `N` bytes.
+fn decode_array<const N: usize>(
+    d: &mut Decoder<'_>,
+    what: &'static str,
+) -> Result<[u8; N], decode::Error> {
+    let position = d.position();
+    let bytes = d.bytes()?;
+    if bytes.len() != N {
+        return Err(decode::Error::message(what).at(position));
+    }
+    let mut out = [0u8; N];
+    out.copy_from_slice(bytes);
+    Ok(out)
+}
+
+/// Writes `PublicKey` as a 32-byte byte string.
+impl<C> Encode<C> for PublicKey {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _: &mut C,
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.bytes(&self.0)?.ok()
+    }
+}
+
+/// Reads `PublicKey` from a 32-byte byte string.
+impl<'b, C> Decode<'b, C> for PublicKey {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        decode_array::<PublicKeySize>(d, "expected a 32-byte public key").map(PublicKey)
+    }
+}
+
+/// Writes `Signature` as a 64-byte byte string.
+impl<C> Encode<C> for Signature {
+    fn encode<W: Write>(
+        &self,
+        e: &mut Encoder<W>,
+        _: &mut C,
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.bytes(&self.0)?.ok()
+    }
+}
+
+/// Reads `Signature` from a 64-byte byte string.
+impl<'b, C> Decode<'b, C> for Signature {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        decode_array::<SignatureSize>(d, "expected a 64-byte signature").map(Signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // RFC 8032, TEST 1.
+    const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+    #[test]
+    fn minicbor_round_trip() {
+        let public: PublicKey = PUBLIC_KEY.parse().unwrap();
+        let signature = Signature::from_hex(SIGNATURE).unwrap();
+
+        // Into fixed buffers, without alloc.
+        let mut out = [0u8; 2 + 32];
+        ::minicbor::encode(public, &mut out[..]).unwrap();
+        assert!(::minicbor::decode::<PublicKey>(&out).unwrap() == public);
+        let mut out = [0u8; 2 + 64];
+        ::minicbor::encode(signature, &mut out[..]).unwrap();
+        assert_eq!(::minicbor::decode::<Signature>(&out).unwrap(), signature);
+
+        let mut short = [0u8; 2 + 31];
+        assert!(::minicbor::encode(public, &mut short[..]).is_err());
+    }
+
+    // The wire format, written by hand: 0x58 is a byte string with a
+    // one-byte length, 0x20 is 32 and 0x40 is 64.
+    #[test]
+    fn minicbor_wire_format() {
+        let public: PublicKey = PUBLIC_KEY.parse().unwrap();
+        let signature = Signature::from_hex(SIGNATURE).unwrap();
+
+        let cbor = hex::decode(format!("5820{}", PUBLIC_KEY)).unwrap();
+        let mut out = [0u8; 34];
+        ::minicbor::encode(public, &mut out[..]).unwrap();
+        assert_eq!(out[..], cbor[..]);
+        assert!(::minicbor::decode::<PublicKey>(&cbor).unwrap() == public);
+
+        let cbor = hex::decode(format!("5840{}", SIGNATURE)).unwrap();
+        let mut out = [0u8; 66];
+        ::minicbor::encode(signature, &mut out[..]).unwrap();
+        assert_eq!(out[..], cbor[..]);
+        assert_eq!(::minicbor::decode::<Signature>(&cbor).unwrap(), signature);
+
+        // In an array: [public key, signature].
+        let cbor = hex::decode(format!("825820{}5840{}", PUBLIC_KEY, SIGNATURE)).unwrap();
+        let (p, s): (PublicKey, Signature) = ::minicbor::decode(&cbor).unwrap();
+        assert!(p == public && s == signature);
+    }
+
+    #[test]
+    fn minicbor_rejects_other_items() {
+        let decode_error = |hex_cbor: String| {
+            let cbor = hex::decode(hex_cbor).unwrap();
+            ::minicbor::decode::<PublicKey>(&cbor).err().unwrap()
+        };
+
+        // 31 and 33 bytes.
+        let error = decode_error(format!("581f{}", &PUBLIC_KEY[2..]));
+        assert!(error.is_message());
+        assert_eq!(
+            error.to_string(),
+            "decode error at position 0: expected a 32-byte public key"
+        );
+        assert!(decode_error(format!("5821{}00", PUBLIC_KEY)).is_message());
+        // A signature is no public key.
+        let cbor = hex::decode(format!("5840{}", SIGNATURE)).unwrap();
+        assert!(::minicbor::decode::<PublicKey>(&cbor)
+            .err()
+            .unwrap()
+            .is_message());
+        // Indefinite length, even if the chunks add up to 32 bytes.
+        assert!(decode_error(format!(
+            "5f5810{}50{}ff",
+            &PUBLIC_KEY[..32],
+            &PUBLIC_KEY[32..]
+        ))
+        .is_type_mismatch());
+        // A text string, an unsigned integer and an array of the bytes.
+        assert!(decode_error("7820".to_string() + &"61".repeat(32)).is_type_mismatch());
+        assert!(decode_error("00".to_string()).is_type_mismatch());
+        assert!(decode_error("9820".to_string() + &"00".repeat(32)).is_type_mismatch());
+        // Truncated.
+        assert!(decode_error(format!("5820{}", &PUBLIC_KEY[..62])).is_end_of_input());
+
+        let cbor = hex::decode(format!("583f{}", &SIGNATURE[2..])).unwrap();
+        let error = ::minicbor::decode::<Signature>(&cbor).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "decode error at position 0: expected a 64-byte signature"
+        );
+    }
+}
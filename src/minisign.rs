@@ -0,0 +1,483 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// minisign public keys and signature files. A signature is Ed25519 over
+// the file (legacy `Ed`) or over its BLAKE2b-512 hash (`ED`), followed by
+// a global signature over the signature and the trusted comment.
+// See https://jedisct1.github.io/minisign/.
+
+use crate::b64;
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::public::*;
+use crate::reader::*;
+use crate::signature::*;
+use crate::writer::*;
+
+use blake2::{Blake2b512, Digest};
+
+const ALGORITHM_LEGACY: [u8; 2] = *b"Ed";
+const ALGORITHM_PREHASHED: [u8; 2] = *b"ED";
+
+const UNTRUSTED_COMMENT: &str = "untrusted comment: ";
+const TRUSTED_COMMENT: &str = "trusted comment: ";
+
+/// The minisign key identifier.
+pub type KeyId = [u8; 8];
+
+/// A minisign public key: the key identifier and the Ed25519 public key.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/minisign.rs")]
+/// ```
+#[derive(Copy, Clone)]
+pub struct MinisignPublicKey {
+    pub key_id: KeyId,
+    pub public: PublicKey,
+}
+
+/// A minisign signature file.
+///
+/// The comments are stored without their `untrusted comment: ` and
+/// `trusted comment: ` prefixes.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/minisign.rs")]
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MinisignSignature {
+    /// Whether the signature is over the BLAKE2b-512 hash of the file.
+    pub prehashed: bool,
+    pub key_id: KeyId,
+    pub signature: Signature,
+    pub untrusted_comment: String,
+    pub trusted_comment: String,
+    /// The signature over `signature` and `trusted_comment`.
+    pub global_signature: Signature,
+}
+
+impl MinisignPublicKey {
+    /// Constructs `MinisignPublicKey` from the base64 line of a minisign
+    /// public key, as given to `minisign -P`.
+    ///
+    /// Returns `Ok(MinisignPublicKey)` if `encoded` is a minisign Ed25519
+    /// public key and `Err` otherwise.
+    pub fn from_base64(encoded: &str) -> Result<Self, Error> {
+        let bytes =
+            b64::decode(b64::STANDARD, encoded.trim()).map_err(|_| Error::InvalidMinisignKey)?;
+
+        parse_exact(&bytes, |reader| {
+            if reader.take_array::<2>()? != ALGORITHM_LEGACY {
+                return Err(Error::InvalidMinisignKey);
+            }
+            Ok(MinisignPublicKey {
+                key_id: reader.take_array::<8>()?,
+                public: PublicKey(reader.take_array::<PublicKeySize>()?),
+            })
+        })
+    }
+
+    /// Constructs `MinisignPublicKey` from the contents of a `minisign.pub`
+    /// file.
+    ///
+    /// Returns `Ok(MinisignPublicKey)` if `file` is an untrusted comment
+    /// line followed by a minisign public key and `Err` otherwise.
+    pub fn decode(file: &str) -> Result<Self, Error> {
+        let mut lines = file.lines();
+        match lines.next() {
+            Some(line) if line.starts_with(UNTRUSTED_COMMENT) => {}
+            _ => return Err(Error::InvalidMinisignKey),
+        }
+        let public_key = Self::from_base64(lines.next().ok_or(Error::InvalidMinisignKey)?)?;
+        if lines.any(|line| !line.trim().is_empty()) {
+            return Err(Error::InvalidMinisignKey);
+        }
+        Ok(public_key)
+    }
+
+    /// Encodes `MinisignPublicKey` as the base64 line of a minisign public
+    /// key.
+    pub fn to_base64(&self) -> String {
+        let mut bytes = [0u8; 2 + 8 + PublicKeySize];
+        bytes[..2].copy_from_slice(&ALGORITHM_LEGACY);
+        bytes[2..10].copy_from_slice(&self.key_id);
+        bytes[10..].copy_from_slice(&self.public.0);
+        b64::encode(b64::STANDARD, &bytes[..])
+    }
+
+    /// Encodes `MinisignPublicKey` as the contents of a `minisign.pub`
+    /// file.
+    pub fn encode(&self) -> String {
+        format!(
+            "{}minisign public key {}\n{}\n",
+            UNTRUSTED_COMMENT,
+            key_id_hex(&self.key_id),
+            self.to_base64()
+        )
+    }
+
+    /// Verifies the minisign signature `signature` of `data`.
+    ///
+    /// Both the legacy and the prehashed modes are accepted, and the global
+    /// signature must authenticate the trusted comment.
+    ///
+    /// Returns `Ok` if both signatures verify and `Err` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::minisign::{MinisignPublicKey, MinisignSignature};
+    ///
+    /// fn main() {
+    ///     let public_key =
+    ///         MinisignPublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")
+    ///             .unwrap();
+    ///     let signature = MinisignSignature::decode(
+    ///         "untrusted comment: signature from minisign secret key\n\
+    ///          RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=\n\
+    ///          trusted comment: timestamp:1556193335\tfile:test\n\
+    ///          y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==\n",
+    ///     )
+    ///     .unwrap();
+    ///     assert!(public_key.verify(b"test", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn verify(&self, data: &[u8], signature: &MinisignSignature) -> Result<(), Error> {
+        if signature.key_id != self.key_id {
+            return Err(Error::KeyIdMismatch);
+        }
+
+        if signature.prehashed {
+            self.public
+                .verify(&Blake2b512::digest(data), &signature.signature)?;
+        } else {
+            self.public.verify(data, &signature.signature)?;
+        }
+        self.public.verify(
+            &global_message(&signature.signature, &signature.trusted_comment)?,
+            &signature.global_signature,
+        )
+    }
+}
+
+impl MinisignSignature {
+    /// Constructs `MinisignSignature` from the contents of a minisign
+    /// signature file.
+    ///
+    /// Returns `Ok(MinisignSignature)` if `file` has the untrusted comment,
+    /// signature, trusted comment and global signature lines and `Err`
+    /// otherwise.
+    pub fn decode(file: &str) -> Result<Self, Error> {
+        let mut lines = file.lines();
+        let mut next_line = || lines.next().ok_or(Error::InvalidMinisignSignature);
+
+        let untrusted_comment = next_line()?
+            .strip_prefix(UNTRUSTED_COMMENT)
+            .ok_or(Error::InvalidMinisignSignature)?
+            .to_string();
+        let bytes = decode_base64(next_line()?)?;
+        let trusted_comment = next_line()?
+            .strip_prefix(TRUSTED_COMMENT)
+            .ok_or(Error::InvalidMinisignSignature)?
+            .to_string();
+        let global_signature = decode_base64(next_line()?)?;
+        if lines.any(|line| !line.trim().is_empty()) {
+            return Err(Error::InvalidMinisignSignature);
+        }
+
+        let (prehashed, key_id, signature) = parse_exact(&bytes, |reader| {
+            let prehashed = match reader.take_array::<2>()? {
+                ALGORITHM_LEGACY => false,
+                ALGORITHM_PREHASHED => true,
+                _ => return Err(Error::InvalidMinisignSignature),
+            };
+            let key_id = reader.take_array::<8>()?;
+            let signature = Signature(reader.take_array::<SignatureSize>()?);
+            Ok((prehashed, key_id, signature))
+        })?;
+        let global_signature = parse_exact(&global_signature, |reader| {
+            Ok(Signature(reader.take_array::<SignatureSize>()?))
+        })?;
+
+        Ok(MinisignSignature {
+            prehashed,
+            key_id,
+            signature,
+            untrusted_comment,
+            trusted_comment,
+            global_signature,
+        })
+    }
+
+    /// Encodes `MinisignSignature` as the contents of a minisign signature
+    /// file.
+    ///
+    /// Returns `Err(Error::InvalidMinisignSignature)` if a comment spans
+    /// several lines.
+    pub fn encode(&self) -> Result<String, Error> {
+        if is_multiline(&self.untrusted_comment) || is_multiline(&self.trusted_comment) {
+            return Err(Error::InvalidMinisignSignature);
+        }
+
+        let mut bytes = [0u8; 2 + 8 + SignatureSize];
+        bytes[..2].copy_from_slice(if self.prehashed {
+            &ALGORITHM_PREHASHED
+        } else {
+            &ALGORITHM_LEGACY
+        });
+        bytes[2..10].copy_from_slice(&self.key_id);
+        bytes[10..].copy_from_slice(&self.signature.0);
+
+        Ok(format!(
+            "{}{}\n{}\n{}{}\n{}\n",
+            UNTRUSTED_COMMENT,
+            self.untrusted_comment,
+            b64::encode(b64::STANDARD, &bytes[..]),
+            TRUSTED_COMMENT,
+            self.trusted_comment,
+            b64::encode(b64::STANDARD, &self.global_signature.0[..])
+        ))
+    }
+}
+
+/// Signs `data` in the prehashed mode, as `minisign -S` does.
+///
+/// Returns the signature, or `Err(Error::InvalidMinisignSignature)` if
+/// `trusted_comment` spans several lines.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::minisign::{sign, MinisignPublicKey, MinisignSignature};
+/// use ed25519_fun::Keypair;
+///
+/// fn main() {
+///     let keypair = Keypair::generate();
+///     let public_key = MinisignPublicKey {
+///         key_id: [1, 2, 3, 4, 5, 6, 7, 8],
+///         public: *keypair.public(),
+///     };
+///     let signature = sign(&keypair, public_key.key_id, b"data", "file:data").unwrap();
+///     let file = signature.encode().unwrap();
+///     let decoded = MinisignSignature::decode(&file).unwrap();
+///     assert!(public_key.verify(b"data", &decoded).is_ok());
+/// }
+/// ```
+#[cfg(not(feature = "verify-only"))]
+pub fn sign(
+    keypair: &Keypair,
+    key_id: KeyId,
+    data: &[u8],
+    trusted_comment: &str,
+) -> Result<MinisignSignature, Error> {
+    if is_multiline(trusted_comment) {
+        return Err(Error::InvalidMinisignSignature);
+    }
+
+    let signature = keypair.sign(&Blake2b512::digest(data));
+    let global_signature = keypair.sign(&global_message(&signature, trusted_comment)?);
+    Ok(MinisignSignature {
+        prehashed: true,
+        key_id,
+        signature,
+        untrusted_comment: format!("signature from minisign secret key {}", key_id_hex(&key_id)),
+        trusted_comment: trusted_comment.to_string(),
+        global_signature,
+    })
+}
+
+/// The message of the global signature: signature || trusted comment.
+pub(crate) fn global_message(
+    signature: &Signature,
+    trusted_comment: &str,
+) -> Result<Vec<u8>, Error> {
+    write_exact(SignatureSize + trusted_comment.len(), |w| {
+        w.write_bytes(&signature.0)?;
+        w.write_bytes(trusted_comment.as_bytes())
+    })
+}
+
+fn decode_base64(line: &str) -> Result<Vec<u8>, Error> {
+    b64::decode(b64::STANDARD, line.trim()).map_err(|_| Error::InvalidMinisignSignature)
+}
+
+fn is_multiline(comment: &str) -> bool {
+    comment.contains('\n') || comment.contains('\r')
+}
+
+/// minisign prints key identifiers as a little-endian 64-bit hex number.
+fn key_id_hex(key_id: &KeyId) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Public key and signatures of the file "test", produced by the
+    // minisign CLI (from the minisign-verify test suite).
+    const PUBLIC_KEY: &str = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+    const LEGACY_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+trusted comment: timestamp:1555779966\tfile:test
+QtKMXWyYcwdpZAlPF7tE2ENJkRd1ujvKjlj1m9RtHTBnZPa5WKU5uWRs5GoP5M/VqE81QFuMKI5k/SfNQUaOAA==
+";
+    const PREHASHED_SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=
+trusted comment: timestamp:1556193335\tfile:test
+y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==
+";
+
+    #[test]
+    fn minisign_cli_signatures() {
+        let public_key = MinisignPublicKey::from_base64(PUBLIC_KEY).unwrap();
+        assert_eq!(public_key.to_base64(), PUBLIC_KEY);
+        assert_eq!(
+            MinisignPublicKey::decode(&public_key.encode())
+                .unwrap()
+                .to_base64(),
+            PUBLIC_KEY
+        );
+
+        for (file, prehashed, trusted_comment) in [
+            (LEGACY_SIGNATURE, false, "timestamp:1555779966\tfile:test"),
+            (PREHASHED_SIGNATURE, true, "timestamp:1556193335\tfile:test"),
+        ]
+        .iter()
+        {
+            let signature = MinisignSignature::decode(file).unwrap();
+            assert_eq!(signature.prehashed, *prehashed);
+            assert_eq!(signature.trusted_comment, *trusted_comment);
+            assert_eq!(signature.encode().unwrap(), *file);
+
+            assert!(public_key.verify(b"test", &signature).is_ok());
+            assert_eq!(
+                public_key.verify(b"Test", &signature),
+                Err(Error::SignatureMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn minisign_rejects_tampering() {
+        let public_key = MinisignPublicKey::from_base64(PUBLIC_KEY).unwrap();
+        let signature = MinisignSignature::decode(PREHASHED_SIGNATURE).unwrap();
+
+        // The trusted comment is covered by the global signature.
+        let mut forged = signature.clone();
+        forged.trusted_comment = "timestamp:1556193335\tfile:other".to_string();
+        assert_eq!(
+            public_key.verify(b"test", &forged),
+            Err(Error::SignatureMismatch)
+        );
+        // The untrusted comment isn't.
+        let mut relabeled = signature.clone();
+        relabeled.untrusted_comment = "anything".to_string();
+        assert!(public_key.verify(b"test", &relabeled).is_ok());
+        // The algorithm is covered by the signature.
+        let mut legacy = signature.clone();
+        legacy.prehashed = false;
+        assert_eq!(
+            public_key.verify(b"test", &legacy),
+            Err(Error::SignatureMismatch)
+        );
+        // Another key identifier.
+        let mut other_key = signature;
+        other_key.key_id[0] ^= 1;
+        assert_eq!(
+            public_key.verify(b"test", &other_key),
+            Err(Error::KeyIdMismatch)
+        );
+    }
+
+    #[test]
+    fn minisign_rejects_malformed() {
+        // Secret key algorithm and truncated keys.
+        assert_eq!(
+            MinisignPublicKey::from_base64(&PUBLIC_KEY.replace("RWQf", "RUQf")).err(),
+            Some(Error::InvalidMinisignKey)
+        );
+        assert_eq!(
+            MinisignPublicKey::from_base64(&b64::encode(b64::STANDARD, &[b'E', b'd', 0, 0])).err(),
+            Some(Error::Truncated { offset: 2 })
+        );
+        assert_eq!(
+            MinisignPublicKey::from_base64("not base64!").err(),
+            Some(Error::InvalidMinisignKey)
+        );
+        assert_eq!(
+            MinisignPublicKey::decode(PUBLIC_KEY).err(),
+            Some(Error::InvalidMinisignKey)
+        );
+
+        // Missing lines and prefixes.
+        let lines: Vec<&str> = PREHASHED_SIGNATURE.lines().collect();
+        for len in 0..lines.len() {
+            assert_eq!(
+                MinisignSignature::decode(&lines[..len].join("\n")).err(),
+                Some(Error::InvalidMinisignSignature)
+            );
+        }
+        assert_eq!(
+            MinisignSignature::decode(
+                &PREHASHED_SIGNATURE.replace("trusted comment: ", "comment: ")
+            )
+            .err(),
+            Some(Error::InvalidMinisignSignature)
+        );
+        assert_eq!(
+            MinisignSignature::decode(&format!("{}extra\n", PREHASHED_SIGNATURE)).err(),
+            Some(Error::InvalidMinisignSignature)
+        );
+        // Unknown algorithm.
+        assert_eq!(
+            MinisignSignature::decode(&PREHASHED_SIGNATURE.replace("\nRUQf", "\nRVQf")).err(),
+            Some(Error::InvalidMinisignSignature)
+        );
+        // Signature line with trailing bytes.
+        let mut long = b64::decode(b64::STANDARD, lines[1]).unwrap();
+        long.push(0);
+        let file = PREHASHED_SIGNATURE.replace(lines[1], &b64::encode(b64::STANDARD, &long));
+        assert_eq!(
+            MinisignSignature::decode(&file).err(),
+            Some(Error::TrailingBytes {
+                consumed: 74,
+                total: 75
+            })
+        );
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn minisign_sign_roundtrip() {
+        let keypair = Keypair::generate();
+        let public_key = MinisignPublicKey {
+            key_id: [0x1f, 0xe8, 0xb4, 0x42, 0x18, 0x0f, 0x62, 0xe7],
+            public: keypair.public,
+        };
+
+        let signature = sign(&keypair, public_key.key_id, b"test", "file:test").unwrap();
+        assert_eq!(
+            signature.untrusted_comment,
+            "signature from minisign secret key E7620F1842B4E81F"
+        );
+        let decoded = MinisignSignature::decode(&signature.encode().unwrap()).unwrap();
+        assert_eq!(decoded, signature);
+        assert!(public_key.verify(b"test", &decoded).is_ok());
+
+        assert_eq!(
+            sign(&keypair, public_key.key_id, b"test", "a\nb").err(),
+            Some(Error::InvalidMinisignSignature)
+        );
+    }
+}
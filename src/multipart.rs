@@ -0,0 +1,317 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Multi-part messages: a fixed number of borrowed fields signed with
+// unambiguous length framing, without building a concatenation buffer.
+
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+use crate::writer::*;
+
+use sha2::digest::Input;
+
+/// A message made of up to `N` borrowed parts.
+///
+/// The parts are signed as the framed byte string
+/// `count || len(part_0) || part_0 || ... || len(part_n) || part_n`, where
+/// `count` and every `len` are 64-bit little-endian integers. This is not
+/// the concatenation of the parts: moving bytes across a part boundary
+/// changes the signed message.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+#[doc = include_str!("../tests/snippets/multipart_message.rs")]
+/// ```
+#[derive(Clone, Copy)]
+pub struct MultiPartMessage<'a, const N: usize> {
+    parts: [&'a [u8]; N],
+    len: usize,
+}
+
+impl<'a, const N: usize> MultiPartMessage<'a, N> {
+    /// Creates an empty `MultiPartMessage`.
+    ///
+    /// # Example
+    ///
+    #[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+    #[cfg_attr(feature = "verify-only", doc = "```ignore")]
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Keypair, MultiPartMessage, Signature};
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let counter = 7u32.to_le_bytes();
+    ///     let mut message: MultiPartMessage<3> = MultiPartMessage::new();
+    ///     message.push(b"device-42").unwrap();
+    ///     message.push(&counter).unwrap();
+    ///     message.push(b"payload").unwrap();
+    ///     let signature: Signature = keypair.secret().sign_multipart(keypair.public(), &message);
+    ///     assert!(keypair.public().verify_multipart(&message, &signature).is_ok());
+    /// }
+    /// ```
+    pub fn new() -> Self {
+        MultiPartMessage {
+            parts: [&[]; N],
+            len: 0,
+        }
+    }
+
+    /// Appends a part.
+    ///
+    /// Returns `Ok(())` if there is room for it and `Err` otherwise. Parts
+    /// longer than `MAX_MESSAGE_LEN` are rejected with
+    /// `Error::LengthOverflow`.
+    pub fn push(&mut self, part: &'a [u8]) -> Result<(), Error> {
+        if self.len == N {
+            return Err(Error::MultiPartCapacity);
+        }
+        if part.len() as u64 > MAX_MESSAGE_LEN {
+            return Err(Error::LengthOverflow);
+        }
+        self.parts[self.len] = part;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Returns the parts pushed so far.
+    pub fn parts(&self) -> &[&'a [u8]] {
+        &self.parts[..self.len]
+    }
+
+    /// Returns the number of parts.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no part has been pushed.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Feeds the framed parts to `hasher`.
+    pub(crate) fn absorb<H: Input>(&self, hasher: &mut H) {
+        absorb_parts(hasher, self.parts());
+    }
+}
+
+/// Feeds `parts` to `hasher` as the framed byte string of
+/// `MultiPartMessage`.
+fn absorb_parts<H: Input>(hasher: &mut H, parts: &[&[u8]]) {
+    let frame = |len: usize| {
+        let mut frame = [0u8; 8];
+        WireWriter::new(&mut frame)
+            .write_u64_le(len as u64)
+            .unwrap();
+        frame
+    };
+    hasher.input(frame(parts.len()));
+    for part in parts {
+        hasher.input(frame(part.len()));
+        hasher.input(part);
+    }
+}
+
+impl<'a, const N: usize> Default for MultiPartMessage<'a, N> {
+    fn default() -> Self {
+        MultiPartMessage::new()
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl SecretKey {
+    /// Signs a `MultiPartMessage` with this `SecretKey`.
+    ///
+    /// Returns `Signature`.
+    pub fn sign_multipart<const N: usize>(
+        &self,
+        public: &PublicKey,
+        message: &MultiPartMessage<N>,
+    ) -> Signature {
+        let signature = self.sign_absorbing(public, None, |hash| message.absorb(hash));
+        #[cfg(feature = "observer")]
+        observer::signed(public, |digest| message.absorb(digest));
+        signature
+    }
+
+    /// Signs `parts` with this `SecretKey`, framed as `MultiPartMessage`
+    /// frames them, for any number of parts.
+    ///
+    /// The signed message is the byte string
+    ///
+    /// ```text
+    /// count || len(parts[0]) || parts[0] || ... || len(parts[n]) || parts[n]
+    /// ```
+    ///
+    /// with `count` and every `len` 64-bit little-endian integers, signed
+    /// with plain Ed25519: any implementation can verify it over those
+    /// bytes. Neither moving bytes across a part boundary nor adding an
+    /// empty part gives the same message.
+    ///
+    /// Returns `Signature`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let (secret, public) = (keypair.secret(), keypair.public());
+    ///     let signature = secret.sign_parts(public, &[b"ab", b"c"]);
+    ///     assert!(public.verify_parts(&[b"ab", b"c"], &signature).is_ok());
+    ///     assert!(public.verify_parts(&[b"a", b"bc"], &signature).is_err());
+    /// }
+    /// ```
+    pub fn sign_parts(&self, public: &PublicKey, parts: &[&[u8]]) -> Signature {
+        let signature = self.sign_absorbing(public, None, |hash| absorb_parts(hash, parts));
+        #[cfg(feature = "observer")]
+        observer::signed(public, |digest| absorb_parts(digest, parts));
+        signature
+    }
+}
+
+impl PublicKey {
+    /// Verifies a signature over a `MultiPartMessage` with this `PublicKey`.
+    ///
+    /// Returns `Ok(())` if the signature is valid and `Err` otherwise.
+    pub fn verify_multipart<const N: usize>(
+        &self,
+        message: &MultiPartMessage<N>,
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        self.verify_absorbing(signature, None, |hash| message.absorb(hash))
+    }
+
+    /// Verifies a signature of `SecretKey::sign_parts` over `parts`.
+    ///
+    /// Returns `Ok(())` if the signature is valid and `Err` otherwise.
+    pub fn verify_parts(&self, parts: &[&[u8]], signature: &Signature) -> Result<(), Error> {
+        self.verify_absorbing(signature, None, |hash| absorb_parts(hash, parts))
+    }
+}
+
+#[cfg(all(test, not(feature = "verify-only")))]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use crate::keypair::*;
+
+    fn keypair() -> Keypair {
+        let secret =
+            hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                .unwrap();
+        Keypair::from_secret(SecretKey::from_bytes(&secret).unwrap())
+    }
+
+    fn sign_parts<const N: usize>(keypair: &Keypair, parts: &[&[u8]]) -> Signature {
+        let mut message: MultiPartMessage<N> = MultiPartMessage::new();
+        for part in parts {
+            message.push(part).unwrap();
+        }
+        keypair.secret.sign_multipart(&keypair.public, &message)
+    }
+
+    #[test]
+    fn multipart_framing_vector() {
+        let keypair = keypair();
+        let counter = [7u8, 0, 0, 0];
+        let parts: [&[u8]; 3] = [b"device-42", &counter, b"payload"];
+        let signature = sign_parts::<3>(&keypair, &parts);
+
+        // Signature computed independently over the framed bytes.
+        let framed = hex::decode("030000000000000009000000000000006465766963652d343204000000000000000700000007000000000000007061796c6f6164").unwrap();
+        assert_eq!(format!("{:x}", signature), "f88ee401fbb3bedc90a0b36d2be54d339e828659e1eaebb66fcd8941f1708d02a8a49ab4db96b2b48b64837ebf97c547e5f55804b49367e3a335e1effce01307");
+        assert!(keypair.public.verify(&framed, &signature).is_ok());
+
+        let mut message: MultiPartMessage<3> = MultiPartMessage::new();
+        for part in &parts {
+            message.push(part).unwrap();
+        }
+        assert!(keypair
+            .public
+            .verify_multipart(&message, &signature)
+            .is_ok());
+    }
+
+    #[test]
+    fn multipart_is_not_concatenation() {
+        let keypair = keypair();
+        let signature = sign_parts::<2>(&keypair, &[b"ab", b"c"]);
+        let concatenated = keypair.sign(b"abc");
+        assert!(signature != concatenated);
+        assert!(keypair.public.verify(b"abc", &signature).is_err());
+    }
+
+    #[test]
+    fn multipart_boundaries_matter() {
+        let keypair = keypair();
+        let base = sign_parts::<2>(&keypair, &[b"ab", b"c"]);
+        // Moving a byte across the boundary.
+        assert!(base != sign_parts::<2>(&keypair, &[b"a", b"bc"]));
+        // Reordering the parts.
+        assert!(base != sign_parts::<2>(&keypair, &[b"c", b"ab"]));
+        // One part versus two.
+        assert!(sign_parts::<2>(&keypair, &[b"ab"]) != sign_parts::<2>(&keypair, &[b"a", b"b"]));
+        // Trailing empty part.
+        assert!(base != sign_parts::<3>(&keypair, &[b"ab", b"c", b""]));
+        // Capacity does not affect the signature.
+        assert!(base == sign_parts::<4>(&keypair, &[b"ab", b"c"]));
+    }
+
+    #[test]
+    fn sign_parts_matches_multipart() {
+        let keypair = keypair();
+        let counter = [7u8, 0, 0, 0];
+        let parts: [&[u8]; 3] = [b"device-42", &counter, b"payload"];
+        let signature = keypair.secret.sign_parts(&keypair.public, &parts);
+        assert_eq!(signature, sign_parts::<3>(&keypair, &parts));
+        assert!(keypair.public.verify_parts(&parts, &signature).is_ok());
+
+        // The classic ambiguity pair.
+        let ab_c = keypair.secret.sign_parts(&keypair.public, &[b"ab", b"c"]);
+        let a_bc = keypair.secret.sign_parts(&keypair.public, &[b"a", b"bc"]);
+        assert!(ab_c != a_bc);
+        assert!(keypair.public.verify_parts(&[b"a", b"bc"], &ab_c).is_err());
+        assert!(keypair.public.verify_parts(&[b"ab", b"c"], &a_bc).is_err());
+        assert!(keypair.public.verify(b"abc", &ab_c).is_err());
+
+        // No parts, and one empty part.
+        let none = keypair.secret.sign_parts(&keypair.public, &[]);
+        assert!(none != keypair.secret.sign_parts(&keypair.public, &[b""]));
+        assert!(keypair.public.verify(&[0u8; 8], &none).is_ok());
+    }
+
+    #[test]
+    fn multipart_capacity() {
+        let mut message: MultiPartMessage<1> = MultiPartMessage::new();
+        assert!(message.is_empty());
+        message.push(b"a").unwrap();
+        assert_eq!(message.push(b"b"), Err(Error::MultiPartCapacity));
+        assert_eq!(message.len(), 1);
+        assert_eq!(message.parts(), &[&b"a"[..]]);
+    }
+
+    // Zeroed allocations are lazy, so the oversized part costs no memory.
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn multipart_rejects_oversized_part() {
+        let big = vec![0u8; MAX_MESSAGE_LEN as usize + 1];
+        let mut message: MultiPartMessage<2> = MultiPartMessage::new();
+        assert_eq!(message.push(&big), Err(Error::LengthOverflow));
+        assert!(message.is_empty());
+        message.push(&big[..MAX_MESSAGE_LEN as usize]).unwrap();
+        assert_eq!(message.len(), 1);
+    }
+}
@@ -0,0 +1,317 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Ed25519-BLAKE2b, the signature scheme of the Nano network: Ed25519
+// with BLAKE2b-512 in place of SHA-512 for the key expansion, the nonce
+// and the challenge. The curve arithmetic is the crate's own; only the
+// hash differs, and that is enough for a seed to give another public key
+// and for no signature to verify under both schemes. The keys have their
+// own types, so that one can't be passed for the other.
+//
+// Nano wallets derive the secret key of account `index` from a 32-byte
+// seed as BLAKE2b-256(seed || index), with the index big-endian.
+
+#![allow(non_snake_case)]
+
+use crate::curve25519::group_element::*;
+use crate::curve25519::scalar_ops::*;
+
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::rng::*;
+use crate::signature::*;
+use crate::verify::*;
+
+#[cfg(not(feature = "verify-only"))]
+use blake2::digest::consts::U32;
+#[cfg(not(feature = "verify-only"))]
+use blake2::Blake2b;
+use blake2::{Blake2b512, Digest};
+#[cfg(not(feature = "verify-only"))]
+use core::fmt;
+#[cfg(not(feature = "verify-only"))]
+use rand_core::RngCore;
+#[cfg(not(feature = "verify-only"))]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// A Nano secret key: the 32-byte seed of Ed25519-BLAKE2b.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::nano::NanoSecretKey;
+///
+/// fn main() {
+///     let secret = NanoSecretKey::generate();
+///     let public = secret.public_key();
+///     let signature = secret.sign(&public, b"block hash").unwrap();
+///     assert!(public.verify(b"block hash", &signature).is_ok());
+/// }
+/// ```
+#[cfg(not(feature = "verify-only"))]
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct NanoSecretKey([u8; SecretKeySize]);
+
+/// A Nano account public key.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NanoPublicKey([u8; PublicKeySize]);
+
+#[cfg(not(feature = "verify-only"))]
+impl NanoSecretKey {
+    /// Generates a secret key from the OS randomness.
+    pub fn generate() -> Self {
+        let mut secret = NanoSecretKey([0u8; SecretKeySize]);
+        default_rng().fill_bytes(&mut secret.0);
+        secret
+    }
+
+    /// Derives the secret key of account `index` of a Nano wallet `seed`.
+    pub fn from_seed(seed: &[u8; 32], index: u32) -> Self {
+        let mut hash = Blake2b::<U32>::new();
+        hash.update(seed);
+        hash.update(index.to_be_bytes());
+        let mut output = hash.finalize();
+        let mut secret = NanoSecretKey([0u8; SecretKeySize]);
+        secret.0.copy_from_slice(&output);
+        output.as_mut_slice().zeroize();
+        secret
+    }
+
+    /// Constructs `NanoSecretKey` from its 32 bytes.
+    ///
+    /// Returns `Ok(NanoSecretKey)` or `Err(Error::InvalidSecretKeyLength)`
+    /// if `bytes` isn't 32 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != SecretKeySize {
+            return Err(Error::InvalidSecretKeyLength {
+                expected: SecretKeySize,
+                got: bytes.len(),
+            });
+        }
+        let mut secret = NanoSecretKey([0u8; SecretKeySize]);
+        secret.0.copy_from_slice(bytes);
+        Ok(secret)
+    }
+
+    /// Returns the 32 bytes of the secret key.
+    pub fn as_bytes(&self) -> &[u8; SecretKeySize] {
+        &self.0
+    }
+
+    /// The clamped scalar of the key, then the nonce prefix.
+    fn expand(&self) -> [u8; 64] {
+        let mut output = Blake2b512::digest(self.0);
+        let mut h = [0u8; 64];
+        h.copy_from_slice(&output);
+        output.as_mut_slice().zeroize();
+        h[0] &= 248;
+        h[31] &= 63;
+        h[31] |= 64;
+        h
+    }
+
+    /// Computes the `NanoPublicKey` of the secret key.
+    pub fn public_key(&self) -> NanoPublicKey {
+        let mut h = self.expand();
+        let public = Precomp::scalar_multiply(&h[0..32]).encode();
+        h.zeroize();
+        NanoPublicKey(public)
+    }
+
+    /// Signs `message` with Ed25519-BLAKE2b. Nano signs the 32-byte hash
+    /// of a block.
+    ///
+    /// Returns `Ok(Signature)`, or `Err(Error::PublicKeyMismatch)` if
+    /// `public` isn't the public key of `NanoSecretKey`.
+    pub fn sign(&self, public: &NanoPublicKey, message: &[u8]) -> Result<Signature, Error> {
+        if self.public_key() != *public {
+            return Err(Error::PublicKeyMismatch);
+        }
+        let mut h = self.expand();
+
+        // r = BLAKE2b-512(prefix || M) and R = [r]B.
+        let mut r = Blake2b512::new()
+            .chain_update(&h[32..64])
+            .chain_update(message)
+            .finalize();
+        reduce(&mut r[..]);
+        let R = Precomp::scalar_multiply(&r[0..32]).encode();
+
+        // k = BLAKE2b-512(R || A || M).
+        let mut k = Blake2b512::new()
+            .chain_update(R)
+            .chain_update(public.0)
+            .chain_update(message)
+            .finalize();
+        reduce(&mut k[..]);
+
+        // s = (r + k * a) mod L.
+        let mut signature = [0u8; 64];
+        signature[0..32].copy_from_slice(&R);
+        multiply_add(&mut signature[32..64], &k[0..32], &h[0..32], &r);
+        h.zeroize();
+        r.as_mut_slice().zeroize();
+
+        Ok(Signature(signature))
+    }
+}
+
+/// Formats `NanoSecretKey` with its bytes redacted.
+#[cfg(not(feature = "verify-only"))]
+impl fmt::Debug for NanoSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("NanoSecretKey([REDACTED])")
+    }
+}
+
+impl NanoPublicKey {
+    /// Constructs `NanoPublicKey` from its 32 bytes.
+    ///
+    /// Returns `Ok(NanoPublicKey)` or `Err(Error::InvalidPublicKeyLength)`
+    /// if `bytes` isn't 32 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != PublicKeySize {
+            return Err(Error::InvalidPublicKeyLength {
+                expected: PublicKeySize,
+                got: bytes.len(),
+            });
+        }
+        let mut public = [0u8; PublicKeySize];
+        public.copy_from_slice(bytes);
+        Ok(NanoPublicKey(public))
+    }
+
+    /// Returns the 32 bytes of the public key.
+    pub fn as_bytes(&self) -> &[u8; PublicKeySize] {
+        &self.0
+    }
+
+    /// Verifies an Ed25519-BLAKE2b signature of `message`.
+    ///
+    /// Returns `Ok(())` if the signature is valid and, as
+    /// `PublicKey::verify`, `Err(Error::InvalidPublicKey)`,
+    /// `Err(Error::NonCanonicalScalar)`, `Err(Error::InvalidSignaturePoint)`
+    /// or `Err(Error::SignatureMismatch)` otherwise.
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result<(), Error> {
+        let A = P3::decode(self.0).ok_or(Error::InvalidPublicKey)?;
+        let signature = signature.as_bytes();
+        if !check_lt_l(&signature[32..64]) {
+            return Err(Error::NonCanonicalScalar);
+        }
+
+        let mut digest = Blake2b512::new()
+            .chain_update(&signature[0..32])
+            .chain_update(self.0)
+            .chain_update(message)
+            .finalize();
+        reduce(&mut digest[..]);
+        let mut k = [0u8; 32];
+        k.copy_from_slice(&digest[0..32]);
+
+        check_equation(&A, signature, &k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use crate::public::*;
+
+    // Account 0 of the all-zero seed, from the Nano documentation.
+    #[cfg(not(feature = "verify-only"))]
+    const SECRET_KEY: &str = "9f0e444c69f77a49bd0be89db92c38fe713e0963165cca12faf5712d7657120f";
+    const PUBLIC_KEY: &str = "c008b814a7d269a1fa3c6528b19201a24d797912db9996ff02a1ff356e45552b";
+
+    // The hash of a state block opening that account with 1 raw, and its
+    // signature, computed with an independent Python implementation of
+    // Ed25519-BLAKE2b.
+    const BLOCK_HASH: &str = "016e59ee1452900277f24e7d5daa2623ea285c852d050a9367894574dc0a40fd";
+    const SIGNATURE: &str = "781c565f98fca7bea027f9910888977f441ebc897a25ab047bcd936e7d28a2d8\
+                             d30a08395a6ac7780011604c3d6c829511e9f6fd06c6dd6c4101038af6213b05";
+
+    fn public() -> NanoPublicKey {
+        NanoPublicKey::from_bytes(&hex::decode(PUBLIC_KEY).unwrap()).unwrap()
+    }
+
+    fn signature() -> Signature {
+        Signature::from_bytes(&hex::decode(SIGNATURE).unwrap()).unwrap()
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn nano_seed_to_account_key() {
+        let secret = NanoSecretKey::from_seed(&[0u8; 32], 0);
+        assert_eq!(hex::encode(secret.as_bytes()), SECRET_KEY);
+        assert_eq!(secret.public_key(), public());
+        assert_ne!(
+            NanoSecretKey::from_seed(&[0u8; 32], 1).as_bytes(),
+            secret.as_bytes()
+        );
+        assert_eq!(format!("{:?}", secret), "NanoSecretKey([REDACTED])");
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn nano_sign_block() {
+        let secret = NanoSecretKey::from_bytes(&hex::decode(SECRET_KEY).unwrap()).unwrap();
+        let block = hex::decode(BLOCK_HASH).unwrap();
+        assert_eq!(secret.sign(&public(), &block), Ok(signature()));
+
+        let other = NanoSecretKey::generate();
+        assert_eq!(other.sign(&public(), &block), Err(Error::PublicKeyMismatch));
+        let public = other.public_key();
+        assert!(public
+            .verify(b"", &other.sign(&public, b"").unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn nano_verify_block() {
+        let block = hex::decode(BLOCK_HASH).unwrap();
+        assert!(public().verify(&block, &signature()).is_ok());
+
+        let mut other = block.clone();
+        other[0] ^= 1;
+        assert_eq!(
+            public().verify(&other, &signature()),
+            Err(Error::SignatureMismatch)
+        );
+        let mut bytes = signature().0;
+        bytes[63] |= 0xf0;
+        assert_eq!(
+            public().verify(&block, &Signature(bytes)),
+            Err(Error::NonCanonicalScalar)
+        );
+    }
+
+    #[test]
+    fn nano_and_ed25519_are_not_confusable() {
+        // The same bytes as an Ed25519 key and signature don't verify.
+        let block = hex::decode(BLOCK_HASH).unwrap();
+        let ed25519 = PublicKey::from_bytes(&hex::decode(PUBLIC_KEY).unwrap()).unwrap();
+        assert!(ed25519.verify(&block, &signature()).is_err());
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn nano_keys_differ_from_ed25519_keys() {
+        use crate::secret::*;
+
+        let seed = hex::decode(SECRET_KEY).unwrap();
+        let ed25519 = SecretKey::from_bytes(&seed).unwrap();
+        let nano = NanoSecretKey::from_bytes(&seed).unwrap();
+        assert_ne!(
+            ed25519.public_key().as_bytes(),
+            nano.public_key().as_bytes()
+        );
+
+        let signature = ed25519.sign_detached(b"m");
+        let public = NanoPublicKey::from_bytes(ed25519.public_key().as_bytes()).unwrap();
+        assert!(public.verify(b"m", &signature).is_err());
+    }
+}
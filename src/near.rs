@@ -0,0 +1,247 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// NEAR key and signature strings: the key type, a colon and the base58
+// of the value, as NEAR writes them in its config files and RPC:
+//
+//   ed25519:<32-byte public key>
+//   ed25519:<64-byte signature>
+//   ed25519:<64-byte seed followed by the public key>
+//
+// NEAR tooling reads a bare base58 string as an ed25519 one, but these
+// decoders require the prefix, so that keys of other types are refused
+// with `Error::PrefixMismatch` rather than read as the wrong bytes.
+
+use crate::base58;
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::public::*;
+use crate::signature::*;
+
+#[cfg(not(feature = "verify-only"))]
+use zeroize::Zeroize;
+
+const PREFIX: &str = "ed25519:";
+
+/// Encodes `bytes` with the `ed25519:` prefix.
+fn encode(bytes: &[u8]) -> String {
+    let mut text = PREFIX.to_string();
+    text.push_str(&base58::encode(bytes));
+    text
+}
+
+/// Decodes an `ed25519:` string of `N` bytes. Texts longer than the
+/// base58 of `N` bytes are rejected before the quadratic decoding.
+///
+/// Returns `Err(Error::PrefixMismatch)` without the prefix,
+/// `Err(Error::InvalidBase58)` on a character outside the alphabet and
+/// `Err(length)` if the value isn't `N` bytes long.
+fn decode<const N: usize>(text: &str, length: Error) -> Result<[u8; N], Error> {
+    let encoded = text.strip_prefix(PREFIX).ok_or(Error::PrefixMismatch)?;
+    // log(256) / log(58) < 1.38, and each leading zero byte is one '1'.
+    if encoded.len() > N * 138 / 100 + 1 {
+        return Err(length);
+    }
+    let decoded = base58::decode(encoded).ok_or(Error::InvalidBase58)?;
+    if decoded.len() != N {
+        return Err(length);
+    }
+    let mut out = [0u8; N];
+    out.copy_from_slice(&decoded);
+    Ok(out)
+}
+
+impl PublicKey {
+    /// Encodes `PublicKey` as a NEAR `ed25519:` string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let text = "ed25519:FVen3X669xLzsi6N2V91DoiyzHzg1uAgqiT8jZ9nS96Z";
+    ///     let public_key = PublicKey::from_near_string(text).unwrap();
+    ///     assert_eq!(public_key.to_near_string(), text);
+    /// }
+    /// ```
+    pub fn to_near_string(&self) -> String {
+        encode(&self.0)
+    }
+
+    /// Decodes a NEAR `ed25519:` public key string.
+    ///
+    /// Returns `Err(Error::PrefixMismatch)` if the string doesn't start
+    /// with `ed25519:`, `Err(Error::InvalidBase58)` on a character outside
+    /// the alphabet and `Err(Error::InvalidPublicKey)` on a wrong length.
+    pub fn from_near_string(text: &str) -> Result<Self, Error> {
+        decode::<PublicKeySize>(text, Error::InvalidPublicKey).map(PublicKey)
+    }
+}
+
+impl Signature {
+    /// Encodes `Signature` as a NEAR `ed25519:` string.
+    pub fn to_near_string(&self) -> String {
+        encode(&self.0)
+    }
+
+    /// Decodes a NEAR `ed25519:` signature string.
+    ///
+    /// Returns `Err(Error::PrefixMismatch)` if the string doesn't start
+    /// with `ed25519:`, `Err(Error::InvalidBase58)` on a character outside
+    /// the alphabet and `Err(Error::InvalidSignatureEncoding)` on a wrong
+    /// length.
+    pub fn from_near_string(text: &str) -> Result<Self, Error> {
+        decode::<SignatureSize>(text, Error::InvalidSignatureEncoding).map(Signature)
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl Keypair {
+    /// Encodes `Keypair` as a NEAR `ed25519:` secret key string: the base58
+    /// of the seed followed by the public key.
+    pub fn to_near_string(&self) -> String {
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::Keypair);
+        let mut bytes = self.to_bytes();
+        let text = encode(&bytes);
+        bytes.zeroize();
+        text
+    }
+
+    /// Decodes a NEAR `ed25519:` secret key string, as stored in the
+    /// `private_key` field of NEAR credential files.
+    ///
+    /// Returns `Err(Error::PrefixMismatch)` if the string doesn't start
+    /// with `ed25519:`, `Err(Error::InvalidBase58)` on a character outside
+    /// the alphabet and `Err(Error::InvalidKeypair)` if the value isn't 64
+    /// bytes or its last 32 aren't the public key of the first 32.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let decoded = Keypair::from_near_string(&keypair.to_near_string()).unwrap();
+    ///     assert_eq!(decoded.public().as_bytes(), keypair.public().as_bytes());
+    /// }
+    /// ```
+    pub fn from_near_string(text: &str) -> Result<Self, Error> {
+        let mut bytes = decode::<KeypairSize>(text, Error::InvalidKeypair)?;
+        let keypair = Keypair::from_libsodium_bytes(&bytes);
+        bytes.zeroize();
+        keypair
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The RFC 8032 test 1 key and its signature of the empty message, in
+    // the format of a near-cli credential file. No near-cli binary is
+    // available to the test suite, so these were not exported by it: they
+    // were encoded with a Python base58.
+    const PUBLIC_KEY: &str = "ed25519:FVen3X669xLzsi6N2V91DoiyzHzg1uAgqiT8jZ9nS96Z";
+    #[cfg(not(feature = "verify-only"))]
+    const PRIVATE_KEY: &str = "ed25519:49W385L4rePHy6PAaQUovbD2aacgN4HsKXSMeUzRg4fmwXszN91JuMFrQRj3vMDpZuRF3ZknQBuRBoWQJEfXstMw";
+    const SIGNATURE: &str = "ed25519:5awYiUvGiDFA33EJjj4TXJG44a5afJc8QjWRpGgQiu6b23jCr7yndW2fmp9ujwqJVe32J456wV3VF78Asb1obnTc";
+
+    #[test]
+    fn near_public_key_and_signature() {
+        let public = PublicKey::from_near_string(PUBLIC_KEY).unwrap();
+        assert_eq!(
+            public.to_string(),
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+        );
+        assert_eq!(public.to_near_string(), PUBLIC_KEY);
+
+        let signature = Signature::from_near_string(SIGNATURE).unwrap();
+        assert_eq!(signature.to_near_string(), SIGNATURE);
+        assert!(public.verify(b"", &signature).is_ok());
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn near_keypair() {
+        let keypair = Keypair::from_near_string(PRIVATE_KEY).unwrap();
+        assert_eq!(keypair.public().to_near_string(), PUBLIC_KEY);
+        assert_eq!(keypair.to_near_string(), PRIVATE_KEY);
+        assert_eq!(keypair.sign(b"").to_near_string(), SIGNATURE);
+
+        let keypair = Keypair::generate();
+        let decoded = Keypair::from_near_string(&keypair.to_near_string()).unwrap();
+        assert_eq!(decoded.to_bytes()[..], keypair.to_bytes()[..]);
+
+        // The public half must be the one of the seed.
+        let mut bytes = keypair.to_bytes();
+        bytes[63] ^= 1;
+        assert_eq!(
+            Keypair::from_near_string(&encode(&bytes)).err(),
+            Some(Error::InvalidKeypair)
+        );
+        // A public key or a signature isn't a keypair.
+        assert_eq!(
+            Keypair::from_near_string(PUBLIC_KEY).err(),
+            Some(Error::InvalidKeypair)
+        );
+        assert_eq!(
+            Keypair::from_near_string(SIGNATURE).err(),
+            Some(Error::InvalidKeypair)
+        );
+        assert_eq!(
+            PublicKey::from_near_string(PRIVATE_KEY).err(),
+            Some(Error::InvalidPublicKey)
+        );
+    }
+
+    #[test]
+    fn near_rejects_malformed() {
+        let bare = &PUBLIC_KEY[PREFIX.len()..];
+        for text in [
+            bare.to_string(),
+            format!("secp256k1:{}", bare),
+            format!("ED25519:{}", bare),
+            format!(" {}", PUBLIC_KEY),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                PublicKey::from_near_string(text).err(),
+                Some(Error::PrefixMismatch),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            PublicKey::from_near_string(&PUBLIC_KEY.replace('V', "0")).err(),
+            Some(Error::InvalidBase58)
+        );
+        assert_eq!(
+            PublicKey::from_near_string(&encode(&[0x5a; 31])).err(),
+            Some(Error::InvalidPublicKey)
+        );
+        assert_eq!(
+            PublicKey::from_near_string(PREFIX).err(),
+            Some(Error::InvalidPublicKey)
+        );
+        assert_eq!(
+            Signature::from_near_string(PUBLIC_KEY).err(),
+            Some(Error::InvalidSignatureEncoding)
+        );
+        assert_eq!(
+            Signature::from_near_string(&format!("{}{}", SIGNATURE, "z".repeat(100))).err(),
+            Some(Error::InvalidSignatureEncoding)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Key lifecycle events for audit logging: one process-global observer is
+// told about every signature, every failed verification and every time
+// secret key bytes enter or leave the crate. It only ever receives
+// public data (fingerprints, digests, lengths, error variants and key
+// kinds), never key bytes nor message contents.
+//
+// Without the `observer` feature none of this is compiled, and neither
+// are the calls into it.
+
+use crate::errors::*;
+use crate::public::*;
+
+use sha2::digest::Input;
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, RwLock};
+
+/// A SHA-256 digest: of a public key for fingerprints, of a message for
+/// message digests.
+pub type Fingerprint = [u8; 32];
+
+/// The secret material that entered or left the crate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KeyKind {
+    /// The 32 bytes of a `SecretKey`.
+    SecretKey,
+    /// The 64 bytes of a `Keypair`: the secret key and the public key.
+    Keypair,
+}
+
+/// Receives key lifecycle events. Every method does nothing by default.
+///
+/// Methods are called on the thread of the operation, after it
+/// completed, and with no lock held: an observer may sign or verify
+/// itself. The events raised while an observer method runs are not
+/// delivered, so such an observer doesn't see its own operations and
+/// can't recurse. A panic in an observer method is caught and discarded;
+/// the operation that raised the event returns as if there were no
+/// observer.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::observer::{set_observer, Fingerprint, KeyEventObserver};
+/// use ed25519_fun::Keypair;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// static SIGNATURES: AtomicUsize = AtomicUsize::new(0);
+///
+/// struct Audit;
+///
+/// impl KeyEventObserver for Audit {
+///     fn on_sign(&self, _key: &Fingerprint, _message_len: usize, _digest: &Fingerprint) {
+///         SIGNATURES.fetch_add(1, Ordering::SeqCst);
+///     }
+/// }
+///
+/// fn main() {
+///     set_observer(Box::new(Audit));
+///     Keypair::generate().sign(b"audited");
+///     assert_eq!(SIGNATURES.load(Ordering::SeqCst), 1);
+/// }
+/// ```
+pub trait KeyEventObserver {
+    /// A message of `message_len` bytes was signed with the key of
+    /// `fingerprint`. `message_digest` is the SHA-256 of the signed bytes:
+    /// the message, or the framed parts of a `MultiPartMessage`.
+    fn on_sign(&self, fingerprint: &Fingerprint, message_len: usize, message_digest: &Fingerprint) {
+        let _ = (fingerprint, message_len, message_digest);
+    }
+
+    /// A signature didn't verify with the key of `fingerprint`.
+    fn on_verify_failure(&self, fingerprint: &Fingerprint, error: Error) {
+        let _ = (fingerprint, error);
+    }
+
+    /// Secret key bytes were imported into a `SecretKey` or `Keypair`.
+    fn on_import(&self, kind: KeyKind) {
+        let _ = kind;
+    }
+
+    /// Secret key bytes were exported from a `SecretKey` or `Keypair`.
+    fn on_export(&self, kind: KeyKind) {
+        let _ = kind;
+    }
+}
+
+type Observer = Arc<dyn KeyEventObserver + Send + Sync>;
+
+static OBSERVER: RwLock<Option<Observer>> = RwLock::new(None);
+
+thread_local! {
+    // Whether an observer method is running on this thread.
+    static NOTIFYING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Installs `observer` for the whole process, replacing the previous
+/// one.
+pub fn set_observer(observer: Box<dyn KeyEventObserver + Send + Sync>) {
+    *OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::from(observer));
+}
+
+/// Removes the observer, restoring the default of no events.
+pub fn clear_observer() {
+    *OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Calls `event` on the observer, if there is one and no observer method
+/// is running on this thread.
+fn notify<F>(event: F)
+where
+    F: FnOnce(&dyn KeyEventObserver),
+{
+    // The lock is released before the call, so the observer may install
+    // another one or raise events of its own.
+    let observer = match OBSERVER.read().unwrap_or_else(|e| e.into_inner()).clone() {
+        Some(observer) => observer,
+        None => return,
+    };
+    NOTIFYING.with(|notifying| {
+        if notifying.replace(true) {
+            return;
+        }
+        let _ = catch_unwind(AssertUnwindSafe(|| event(&*observer)));
+        notifying.set(false);
+    });
+}
+
+fn fingerprint(public: &PublicKey) -> Fingerprint {
+    public.fingerprint().to_bytes()
+}
+
+/// SHA-256 and length of the bytes fed to it.
+#[derive(Default)]
+pub(crate) struct MessageDigest {
+    hash: Sha256,
+    len: usize,
+}
+
+impl MessageDigest {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.len += data.len();
+        Input::input(&mut self.hash, data);
+    }
+}
+
+impl Input for MessageDigest {
+    fn input<B: AsRef<[u8]>>(&mut self, data: B) {
+        self.update(data.as_ref());
+    }
+}
+
+/// Raises `on_sign` for the message fed to the digest by `absorb`.
+#[cfg_attr(feature = "verify-only", allow(dead_code))]
+pub(crate) fn signed<F>(public: &PublicKey, absorb: F)
+where
+    F: FnOnce(&mut MessageDigest),
+{
+    notify(|observer| {
+        let mut message = MessageDigest::default();
+        absorb(&mut message);
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&message.hash.result());
+        observer.on_sign(&fingerprint(public), message.len, &digest)
+    });
+}
+
+/// Raises `on_sign` for each of `messages`, in order.
+#[cfg_attr(feature = "verify-only", allow(dead_code))]
+pub(crate) fn signed_batch(public: &PublicKey, messages: &[&[u8]]) {
+    for message in messages {
+        signed(public, |digest| digest.update(message));
+    }
+}
+
+/// Raises `on_verify_failure` if `verdict` is an error.
+pub(crate) fn verified(public: &PublicKey, verdict: Result<(), Error>) {
+    if let Err(error) = verdict {
+        notify(|observer| observer.on_verify_failure(&fingerprint(public), error));
+    }
+}
+
+/// Raises `on_import`.
+#[cfg_attr(feature = "verify-only", allow(dead_code))]
+pub(crate) fn imported(kind: KeyKind) {
+    notify(|observer| observer.on_import(kind));
+}
+
+/// Raises `on_export`.
+#[cfg_attr(feature = "verify-only", allow(dead_code))]
+pub(crate) fn exported(kind: KeyKind) {
+    notify(|observer| observer.on_export(kind));
+}
+
+#[cfg(test)]
+mod tests {
+    // Every call into this module is compiled out with the feature: each
+    // line that names it must follow a `cfg(feature = "observer")`.
+    #[test]
+    fn observer_calls_are_feature_gated() {
+        let sources = [
+            ("codec.rs", include_str!("codec.rs")),
+            ("keypair.rs", include_str!("keypair.rs")),
+            ("multipart.rs", include_str!("multipart.rs")),
+            ("precomputed.rs", include_str!("precomputed.rs")),
+            ("secret.rs", include_str!("secret.rs")),
+            ("verify.rs", include_str!("verify.rs")),
+        ];
+        let gate = concat!("#[cfg(feature = \"", "observer\")]");
+        for (name, source) in sources.iter() {
+            let lines: Vec<&str> = source.lines().map(|l| l.trim()).collect();
+            let mut calls = 0;
+            for (i, line) in lines.iter().enumerate() {
+                if line.starts_with(concat!("observer", "::")) {
+                    calls += 1;
+                    assert_eq!(lines[i - 1], gate, "{}:{} is not gated", name, i + 1);
+                }
+            }
+            assert!(calls > 0, "{} raises no event", name);
+        }
+    }
+}
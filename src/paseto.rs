@@ -0,0 +1,242 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// PASETO v4.public tokens: Ed25519 over the pre-authentication encoding
+// (PAE) of the header, payload, footer and implicit assertion.
+// See https://github.com/paseto-standard/paseto-spec.
+
+use crate::b64;
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::multipart::*;
+use crate::public::*;
+use crate::signature::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::writer::*;
+
+use subtle::ConstantTimeEq;
+
+const HEADER: &str = "v4.public.";
+
+/// PAE(header, payload, footer, implicit).
+///
+/// The `MultiPartMessage` framing (LE64 count, then LE64 length and
+/// content of every piece) is exactly PAE.
+///
+/// Returns `Err(Error::LengthOverflow)` if a piece is longer than
+/// `MAX_MESSAGE_LEN`.
+fn pae<'a>(
+    payload: &'a [u8],
+    footer: &'a [u8],
+    implicit: &'a [u8],
+) -> Result<MultiPartMessage<'a, 4>, Error> {
+    let mut message = MultiPartMessage::new();
+    for piece in [HEADER.as_bytes(), payload, footer, implicit].iter() {
+        message.push(piece)?;
+    }
+    Ok(message)
+}
+
+/// Signs `payload` as a PASETO v4.public token.
+///
+/// `footer` is appended to the token (base64url encoded) when not empty,
+/// `implicit` is authenticated but not transmitted.
+///
+/// Returns the `v4.public.` token, or `Err(Error::LengthOverflow)` if a
+/// piece is longer than `MAX_MESSAGE_LEN`.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::paseto::{sign_v4_public, verify_v4_public};
+/// use ed25519_fun::Keypair;
+///
+/// fn main() {
+///     let keypair = Keypair::generate();
+///     let token = sign_v4_public(&keypair, b"{\"data\":1}", b"kid", b"").unwrap();
+///     let payload = verify_v4_public(keypair.public(), &token, b"kid", b"").unwrap();
+///     assert_eq!(payload, b"{\"data\":1}");
+/// }
+/// ```
+#[cfg(not(feature = "verify-only"))]
+pub fn sign_v4_public(
+    keypair: &Keypair,
+    payload: &[u8],
+    footer: &[u8],
+    implicit: &[u8],
+) -> Result<String, Error> {
+    let signature = keypair
+        .secret
+        .sign_multipart(&keypair.public, &pae(payload, footer, implicit)?);
+
+    let body = write_exact(payload.len() + SignatureSize, |w| {
+        w.write_bytes(payload)?;
+        w.write_bytes(&signature.0)
+    })?;
+
+    let mut token = String::from(HEADER);
+    token.push_str(&b64::encode(b64::URL_SAFE_NO_PAD, &body));
+    if !footer.is_empty() {
+        token.push('.');
+        token.push_str(&b64::encode(b64::URL_SAFE_NO_PAD, footer));
+    }
+    Ok(token)
+}
+
+/// Verifies a PASETO v4.public token with `public`.
+///
+/// The token footer must equal `footer`.
+///
+/// Returns `Ok` with the payload if the token is valid and `Err`
+/// otherwise.
+pub fn verify_v4_public(
+    public: &PublicKey,
+    token: &str,
+    footer: &[u8],
+    implicit: &[u8],
+) -> Result<Vec<u8>, Error> {
+    if !token.starts_with(HEADER) {
+        return Err(Error::InvalidToken);
+    }
+
+    let mut pieces = token[HEADER.len()..].split('.');
+    let body = pieces.next().ok_or(Error::InvalidToken)?;
+    let token_footer = match pieces.next() {
+        Some(encoded) if !encoded.is_empty() => {
+            b64::decode(b64::URL_SAFE_NO_PAD, encoded).map_err(|_| Error::InvalidToken)?
+        }
+        Some(_) => return Err(Error::InvalidToken),
+        None => Vec::new(),
+    };
+    if pieces.next().is_some() {
+        return Err(Error::InvalidToken);
+    }
+
+    if token_footer.len() != footer.len() || token_footer.ct_eq(footer).unwrap_u8() == 0 {
+        return Err(Error::FooterMismatch);
+    }
+
+    let body = b64::decode(b64::URL_SAFE_NO_PAD, body).map_err(|_| Error::InvalidToken)?;
+    if body.len() < SignatureSize {
+        return Err(Error::InvalidToken);
+    }
+
+    let (payload, signature) = body.split_at(body.len() - SignatureSize);
+    let signature = Signature::from_bytes(signature)?;
+    public.verify_multipart(&pae(payload, footer, implicit)?, &signature)?;
+
+    Ok(payload.to_vec())
+}
+
+#[cfg(all(test, not(feature = "verify-only")))]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use crate::secret::*;
+
+    // Official PASETO v4 test vectors 4-S-1, 4-S-2 and 4-S-3.
+    const SEED: &str = "b4cbfb43df4ce210727d953e4a713307fa19bb7d9f85041438d9e11b942a3774";
+    const PUBLIC: &str = "1eb9dbbbbc047c03fd70604e0071f0987e16b28b757225c11f00415d0e20b1a2";
+    const PAYLOAD: &[u8] =
+        b"{\"data\":\"this is a signed message\",\"exp\":\"2022-01-01T00:00:00+00:00\"}";
+    const FOOTER: &[u8] = b"{\"kid\":\"zVhMiPBP9fRf2snEcT7gFTioeA9COcNy9DfgL1W60haN\"}";
+    const IMPLICIT: &[u8] = b"{\"test-vector\":\"4-S-3\"}";
+    const TOKEN_1: &str = "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9bg_XBBzds8lTZShVlwwKSgeKpLT3yukTw6JUz3W4h_ExsQV-P0V54zemZDcAxFaSeef1QlXEFtkqxT1ciiQEDA";
+    const TOKEN_2: &str = "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9v3Jt8mx_TdM2ceTGoqwrh4yDFn0XsHvvV_D0DtwQxVrJEBMl0F2caAdgnpKlt4p7xBnx1HcO-SPo8FPp214HDw.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9";
+    const TOKEN_3: &str = "v4.public.eyJkYXRhIjoidGhpcyBpcyBhIHNpZ25lZCBtZXNzYWdlIiwiZXhwIjoiMjAyMi0wMS0wMVQwMDowMDowMCswMDowMCJ9NPWciuD3d0o5eXJXG5pJy-DiVEoyPYWs1YSTwWHNJq6DZD3je5gf-0M4JR9ipdUSJbIovzmBECeaWmaqcaP0DQ.eyJraWQiOiJ6VmhNaVBCUDlmUmYyc25FY1Q3Z0ZUaW9lQTlDT2NOeTlEZmdMMVc2MGhhTiJ9";
+
+    fn keypair() -> Keypair {
+        let secret = SecretKey::from_bytes(&hex::decode(SEED).unwrap()).unwrap();
+        Keypair::from_secret(secret)
+    }
+
+    #[test]
+    fn paseto_v4_public_vectors() {
+        let keypair = keypair();
+        assert_eq!(format!("{:x}", keypair.public), PUBLIC);
+
+        let vectors = [
+            (TOKEN_1, &b""[..], &b""[..]),
+            (TOKEN_2, FOOTER, &b""[..]),
+            (TOKEN_3, FOOTER, IMPLICIT),
+        ];
+        for (token, footer, implicit) in vectors.iter() {
+            assert_eq!(
+                sign_v4_public(&keypair, PAYLOAD, footer, implicit).unwrap(),
+                *token
+            );
+            let payload = verify_v4_public(&keypair.public, token, footer, implicit).unwrap();
+            assert_eq!(payload, PAYLOAD);
+        }
+    }
+
+    #[test]
+    fn paseto_v4_public_rejects_mismatches() {
+        let public = keypair().public;
+
+        assert_eq!(
+            verify_v4_public(&public, TOKEN_2, b"other", b""),
+            Err(Error::FooterMismatch)
+        );
+        assert_eq!(
+            verify_v4_public(&public, TOKEN_2, b"", b""),
+            Err(Error::FooterMismatch)
+        );
+        assert_eq!(
+            verify_v4_public(&public, TOKEN_3, FOOTER, b""),
+            Err(Error::SignatureMismatch)
+        );
+        assert_eq!(
+            verify_v4_public(&public, &TOKEN_1.replace("v4.", "v3."), b"", b""),
+            Err(Error::InvalidToken)
+        );
+        assert_eq!(
+            verify_v4_public(&public, &format!("{}.", TOKEN_1), b"", b""),
+            Err(Error::InvalidToken)
+        );
+    }
+
+    #[test]
+    fn paseto_v4_public_rejects_truncation() {
+        let public = keypair().public;
+        for len in 0..TOKEN_1.len() {
+            assert!(verify_v4_public(&public, &TOKEN_1[..len], b"", b"").is_err());
+        }
+        // Shorter than a signature.
+        assert_eq!(
+            verify_v4_public(&public, "v4.public.AAAA", b"", b""),
+            Err(Error::InvalidToken)
+        );
+    }
+
+    // Zeroed allocations are lazy, so the oversized piece costs no memory
+    // as long as it isn't read.
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn paseto_rejects_oversized_pieces() {
+        let keypair = keypair();
+        let big = vec![0u8; MAX_MESSAGE_LEN as usize + 1];
+
+        assert_eq!(pae(&big, b"", b"").err(), Some(Error::LengthOverflow));
+        assert_eq!(pae(b"", b"", &big).err(), Some(Error::LengthOverflow));
+        assert!(pae(b"", b"", &big[..MAX_MESSAGE_LEN as usize]).is_ok());
+
+        assert_eq!(
+            sign_v4_public(&keypair, &big, b"", b"").err(),
+            Some(Error::LengthOverflow)
+        );
+        assert_eq!(
+            sign_v4_public(&keypair, b"", &big, b"").err(),
+            Some(Error::LengthOverflow)
+        );
+        assert_eq!(
+            verify_v4_public(&keypair.public, TOKEN_1, b"", &big).err(),
+            Some(Error::LengthOverflow)
+        );
+    }
+}
@@ -0,0 +1,442 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Public key with the curve point decoded once, for verifying many
+// signatures from the same signer. Like `verify.rs`, this module only
+// sees public data.
+//
+// With the `verify-memo` feature, a `PrecomputedPublicKey` can also
+// remember the verdicts of recent (message, signature) pairs, so exact
+// retransmits skip the curve arithmetic.
+
+#![allow(non_snake_case)]
+
+use crate::curve25519::group_element::*;
+
+use crate::errors::*;
+#[cfg(feature = "observer")]
+use crate::observer;
+use crate::public::*;
+use crate::signature::*;
+use crate::verify::*;
+
+use sha2::{Digest, Sha512};
+#[cfg(feature = "verify-memo")]
+use std::sync::Mutex;
+
+/// An Ed25519 public key with its curve point decoded.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/precomputed_public_key.rs")]
+/// ```
+pub struct PrecomputedPublicKey {
+    public: PublicKey,
+    A: P3,
+    #[cfg(feature = "verify-memo")]
+    memo: Option<Memo>,
+}
+
+impl PrecomputedPublicKey {
+    /// Decodes `public` once for repeated verifications.
+    ///
+    /// Returns `Ok(PrecomputedPublicKey)` if `public` is a valid point
+    /// encoding and `Err` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{PrecomputedPublicKey, PublicKey, Signature};
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     let signature = Signature::from_array([
+    ///         0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e,
+    ///         0x82, 0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65,
+    ///         0x22, 0x49, 0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e,
+    ///         0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24,
+    ///         0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+    ///     ]);
+    ///     let precomputed = PrecomputedPublicKey::new(&public_key).unwrap();
+    ///     assert!(precomputed.verify(b"", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn new(public: &PublicKey) -> Result<Self, Error> {
+        let A = P3::decode(public.0).ok_or(Error::InvalidPublicKey)?;
+        Ok(PrecomputedPublicKey {
+            public: *public,
+            A,
+            #[cfg(feature = "verify-memo")]
+            memo: None,
+        })
+    }
+
+    /// Returns the `PublicKey`.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public
+    }
+
+    /// Verifies a signature with this `PrecomputedPublicKey`.
+    ///
+    /// Returns `Ok(())` if the signature is valid and `Err` otherwise.
+    pub fn verify(&self, message: &[u8], sig: &Signature) -> Result<(), Error> {
+        let verdict = self.verify_memoized(message, sig);
+        #[cfg(feature = "observer")]
+        observer::verified(&self.public, verdict);
+        verdict
+    }
+
+    /// `verify` without the observer, through the memo if there is one.
+    fn verify_memoized(&self, message: &[u8], sig: &Signature) -> Result<(), Error> {
+        #[cfg(feature = "verify-memo")]
+        {
+            if let Some(memo) = &self.memo {
+                let key = Memo::key(message, sig);
+                if let Some(verdict) = memo.lookup(&key) {
+                    return verdict;
+                }
+                let verdict = self.verify_uncached(message, sig);
+                memo.insert(key, verdict);
+                return verdict;
+            }
+        }
+
+        self.verify_uncached(message, sig)
+    }
+
+    fn verify_uncached(&self, message: &[u8], sig: &Signature) -> Result<(), Error> {
+        verify_decoded(
+            &self.public.0,
+            &self.A,
+            sig,
+            None,
+            &VerificationOptions::new(),
+            |hash: &mut Sha512| hash.input(message),
+        )
+    }
+}
+
+#[cfg(feature = "verify-memo")]
+impl PrecomputedPublicKey {
+    /// Like `new`, with a memo of the last `capacity` verdicts.
+    ///
+    /// A verification whose (message, signature) pair is in the memo
+    /// returns the remembered verdict, `Ok` or the same `Err` variant,
+    /// without touching the curve. Only verdicts computed by this object
+    /// are ever inserted. A `capacity` of 0 disables the memo.
+    ///
+    /// Returns `Ok(PrecomputedPublicKey)` if `public` is a valid point
+    /// encoding and `Err` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{PrecomputedPublicKey, PublicKey, Signature};
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     let signature = Signature::from_array([
+    ///         0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e,
+    ///         0x82, 0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65,
+    ///         0x22, 0x49, 0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e,
+    ///         0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24,
+    ///         0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+    ///     ]);
+    ///     let precomputed = PrecomputedPublicKey::with_memo(&public_key, 64).unwrap();
+    ///     assert!(precomputed.verify(b"", &signature).is_ok());
+    ///     // Retransmit: answered from the memo.
+    ///     assert!(precomputed.verify(b"", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn with_memo(public: &PublicKey, capacity: usize) -> Result<Self, Error> {
+        let mut precomputed = PrecomputedPublicKey::new(public)?;
+        if capacity > 0 {
+            precomputed.memo = Some(Memo::new(capacity));
+        }
+        Ok(precomputed)
+    }
+
+    /// Forgets every remembered verdict.
+    pub fn clear_memo(&self) {
+        if let Some(memo) = &self.memo {
+            memo.clear();
+        }
+    }
+
+    /// Returns the number of remembered verdicts.
+    pub fn memo_len(&self) -> usize {
+        self.memo.as_ref().map_or(0, |memo| memo.len())
+    }
+}
+
+/// Bounded verdict memo, keyed by SHA-512(R || s || message).
+///
+/// Entries live in a fixed-size ring behind a single `Mutex`: once full,
+/// the oldest verdict is evicted first. The lock is only held for the
+/// linear scan of the ring and the insertion, never during curve
+/// arithmetic, so concurrent verifiers contend on short critical
+/// sections only; the memo is meant to be small (a retransmit window).
+#[cfg(feature = "verify-memo")]
+struct Memo {
+    capacity: usize,
+    ring: Mutex<MemoRing>,
+}
+
+#[cfg(feature = "verify-memo")]
+struct MemoRing {
+    entries: Vec<([u8; 64], Result<(), Error>)>,
+    next: usize,
+}
+
+#[cfg(feature = "verify-memo")]
+impl Memo {
+    fn new(capacity: usize) -> Memo {
+        Memo {
+            capacity,
+            ring: Mutex::new(MemoRing {
+                entries: Vec::with_capacity(capacity),
+                next: 0,
+            }),
+        }
+    }
+
+    fn key(message: &[u8], sig: &Signature) -> [u8; 64] {
+        let mut hash = Sha512::default();
+        hash.input(sig.as_bytes());
+        hash.input(message);
+        let mut key = [0u8; 64];
+        key.copy_from_slice(&hash.result());
+        key
+    }
+
+    // A panic while holding the lock can't leave the ring inconsistent,
+    // so a poisoned lock is still usable.
+    fn ring(&self) -> std::sync::MutexGuard<'_, MemoRing> {
+        self.ring
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lookup(&self, key: &[u8; 64]) -> Option<Result<(), Error>> {
+        self.ring()
+            .entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, verdict)| *verdict)
+    }
+
+    fn insert(&self, key: [u8; 64], verdict: Result<(), Error>) {
+        let mut ring = self.ring();
+        // Another thread may have inserted the same pair meanwhile.
+        if ring.entries.iter().any(|(k, _)| *k == key) {
+            return;
+        }
+        if ring.entries.len() < self.capacity {
+            ring.entries.push((key, verdict));
+        } else {
+            let next = ring.next;
+            ring.entries[next] = (key, verdict);
+            ring.next = (next + 1) % self.capacity;
+        }
+    }
+
+    fn clear(&self) {
+        let mut ring = self.ring();
+        ring.entries.clear();
+        ring.next = 0;
+    }
+
+    fn len(&self) -> usize {
+        self.ring().entries.len()
+    }
+}
+
+#[cfg(all(test, not(feature = "verify-only")))]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use crate::keypair::*;
+    use crate::secret::*;
+
+    fn keypair() -> Keypair {
+        let secret =
+            hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                .unwrap();
+        Keypair::from_secret(SecretKey::from_bytes(&secret).unwrap())
+    }
+
+    #[test]
+    fn precomputed_matches_public_key() {
+        let keypair = keypair();
+        let precomputed = PrecomputedPublicKey::new(&keypair.public).unwrap();
+        let signature = keypair.sign(b"message");
+        let mut s_too_big = signature.to_bytes();
+        s_too_big[32..].copy_from_slice(&[0xff; 32]);
+        let s_too_big = Signature::from_bytes(&s_too_big).unwrap();
+
+        for (message, sig) in [
+            (&b"message"[..], &signature),
+            (&b"other"[..], &signature),
+            (&b"message"[..], &s_too_big),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                precomputed.verify(message, sig),
+                keypair.public.verify(message, sig)
+            );
+        }
+        assert_eq!(
+            precomputed.public_key().as_bytes(),
+            keypair.public.as_bytes()
+        );
+    }
+
+    #[test]
+    fn precomputed_rejects_invalid_point() {
+        // y = 2 is not on the curve.
+        let mut bytes = [0u8; 32];
+        bytes[0] = 2;
+        let public = PublicKey::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            PrecomputedPublicKey::new(&public).err(),
+            Some(Error::InvalidPublicKey)
+        );
+    }
+
+    #[cfg(feature = "verify-memo")]
+    mod memo {
+        use super::*;
+
+        use std::sync::Arc;
+        use std::thread;
+
+        #[test]
+        fn memo_hit_and_miss() {
+            let keypair = keypair();
+            let precomputed = PrecomputedPublicKey::with_memo(&keypair.public, 4).unwrap();
+            let signature = keypair.sign(b"message");
+            let memo = precomputed.memo.as_ref().unwrap();
+            let key = Memo::key(b"message", &signature);
+
+            assert_eq!(memo.lookup(&key), None);
+            assert!(precomputed.verify(b"message", &signature).is_ok());
+            assert_eq!(memo.lookup(&key), Some(Ok(())));
+            assert!(precomputed.verify(b"message", &signature).is_ok());
+            assert_eq!(precomputed.memo_len(), 1);
+
+            precomputed.clear_memo();
+            assert_eq!(precomputed.memo_len(), 0);
+            assert_eq!(memo.lookup(&key), None);
+        }
+
+        #[test]
+        fn memo_keeps_error_variant() {
+            let keypair = keypair();
+            let precomputed = PrecomputedPublicKey::with_memo(&keypair.public, 4).unwrap();
+            let signature = keypair.sign(b"message");
+            let mut s_too_big = signature.to_bytes();
+            s_too_big[32..].copy_from_slice(&[0xff; 32]);
+            let s_too_big = Signature::from_bytes(&s_too_big).unwrap();
+
+            let cases = [
+                (&b"other"[..], signature, Err(Error::SignatureMismatch)),
+                (&b"message"[..], s_too_big, Err(Error::NonCanonicalScalar)),
+            ];
+            for (message, sig, verdict) in cases.iter() {
+                // Miss, then hit.
+                assert_eq!(precomputed.verify(message, sig), *verdict);
+                assert_eq!(
+                    precomputed
+                        .memo
+                        .as_ref()
+                        .unwrap()
+                        .lookup(&Memo::key(message, sig)),
+                    Some(*verdict)
+                );
+                assert_eq!(precomputed.verify(message, sig), *verdict);
+            }
+        }
+
+        #[test]
+        fn memo_evicts_oldest() {
+            let keypair = keypair();
+            let precomputed = PrecomputedPublicKey::with_memo(&keypair.public, 2).unwrap();
+            let memo = precomputed.memo.as_ref().unwrap();
+            let messages: [&[u8]; 3] = [b"a", b"b", b"c"];
+            let signatures: Vec<Signature> = messages.iter().map(|m| keypair.sign(m)).collect();
+
+            for (message, signature) in messages.iter().zip(signatures.iter()) {
+                assert!(precomputed.verify(message, signature).is_ok());
+            }
+            assert_eq!(precomputed.memo_len(), 2);
+            assert_eq!(memo.lookup(&Memo::key(b"a", &signatures[0])), None);
+            assert!(memo.lookup(&Memo::key(b"b", &signatures[1])).is_some());
+            assert!(memo.lookup(&Memo::key(b"c", &signatures[2])).is_some());
+        }
+
+        #[test]
+        fn memo_disabled_with_zero_capacity() {
+            let keypair = keypair();
+            let precomputed = PrecomputedPublicKey::with_memo(&keypair.public, 0).unwrap();
+            assert!(precomputed.memo.is_none());
+            assert!(precomputed
+                .verify(b"message", &keypair.sign(b"message"))
+                .is_ok());
+            assert_eq!(precomputed.memo_len(), 0);
+        }
+
+        #[test]
+        fn memo_concurrent_consistency() {
+            fn assert_send_sync<T: Send + Sync>() {}
+            assert_send_sync::<PrecomputedPublicKey>();
+
+            let keypair = keypair();
+            let precomputed =
+                Arc::new(PrecomputedPublicKey::with_memo(&keypair.public, 3).unwrap());
+            let signature = keypair.sign(b"message");
+            type Case = (&'static [u8], Signature, Result<(), Error>);
+            let cases: Arc<Vec<Case>> = Arc::new(vec![
+                (b"message", signature, Ok(())),
+                (b"other", signature, Err(Error::SignatureMismatch)),
+                (b"a", keypair.sign(b"a"), Ok(())),
+                (b"b", keypair.sign(b"b"), Ok(())),
+                (b"b", keypair.sign(b"a"), Err(Error::SignatureMismatch)),
+            ]);
+
+            let handles: Vec<_> = (0..8)
+                .map(|t| {
+                    let precomputed = Arc::clone(&precomputed);
+                    let cases = Arc::clone(&cases);
+                    thread::spawn(move || {
+                        for i in 0..200 {
+                            let (message, sig, verdict) = &cases[(i + t) % cases.len()];
+                            assert_eq!(precomputed.verify(message, sig), *verdict);
+                            if i % 50 == 0 {
+                                precomputed.clear_memo();
+                            }
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            assert!(precomputed.memo_len() <= 3);
+        }
+    }
+}
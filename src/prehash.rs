@@ -0,0 +1,342 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Ed25519ph (RFC 8032, section 5.1): Ed25519 over the 64-byte SHA-512
+// of the message, in the domain dom2(1, context). The caller hashes the
+// message, in as many pieces as it likes, and signs or verifies the
+// finished hash.
+
+use crate::dom2::*;
+use crate::errors::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+use sha2::{Digest, Sha512};
+
+/// The dom2 of Ed25519ph with `context`, absent meaning empty.
+///
+/// Returns `Err(Error::ContextTooLong)` if `context` is longer than 255
+/// bytes.
+pub(crate) fn ph_domain(context: Option<&[u8]>) -> Result<Option<(u8, &[u8])>, Error> {
+    let context = context.unwrap_or(b"");
+    if context.len() > MAX_CONTEXT_LEN {
+        return Err(Error::ContextTooLong { len: context.len() });
+    }
+    Ok(Some((1, context)))
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl SecretKey {
+    /// Signs the finished SHA-512 `digest` of a message with Ed25519ph,
+    /// in the domain of `context` (`None` is the empty context).
+    ///
+    /// Ed25519ph signatures aren't Ed25519 signatures: neither verifies
+    /// as the other.
+    ///
+    /// Returns `Ok(Signature)`, `Err(Error::PublicKeyMismatch)` if
+    /// `public` isn't the public key of `SecretKey` and
+    /// `Err(Error::ContextTooLong)` if `context` is longer than 255
+    /// bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    /// extern crate sha2;
+    ///
+    /// use ed25519_fun::Keypair;
+    /// use sha2::{Digest, Sha512};
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let mut digest = Sha512::new();
+    ///     digest.input(b"a long message, ");
+    ///     digest.input(b"hashed in pieces");
+    ///     let signature = keypair
+    ///         .secret()
+    ///         .sign_prehashed(keypair.public(), digest.clone(), Some(b"protocol v1"))
+    ///         .unwrap();
+    ///     assert!(keypair
+    ///         .public()
+    ///         .verify_prehashed(digest, Some(b"protocol v1"), &signature)
+    ///         .is_ok());
+    /// }
+    /// ```
+    pub fn sign_prehashed(
+        &self,
+        public: &PublicKey,
+        digest: Sha512,
+        context: Option<&[u8]>,
+    ) -> Result<Signature, Error> {
+        let domain = ph_domain(context)?;
+        if PublicKey::from_secret(self) != *public {
+            return Err(Error::PublicKeyMismatch);
+        }
+        Ok(self.sign_prehash_unchecked(public, &digest.result(), domain))
+    }
+
+    /// Signs the 64-byte prehash `prehash` in the Ed25519ph domain
+    /// `domain`, as returned by `ph_domain`, with `public` taken as the
+    /// public key of this `SecretKey`.
+    pub(crate) fn sign_prehash_unchecked(
+        &self,
+        public: &PublicKey,
+        prehash: &[u8],
+        domain: Option<(u8, &[u8])>,
+    ) -> Signature {
+        let signature = self.sign_absorbing(public, domain, |hash| hash.input(prehash));
+        #[cfg(feature = "observer")]
+        observer::signed(public, |digest| digest.update(prehash));
+        signature
+    }
+}
+
+#[cfg(all(feature = "digest", not(feature = "verify-only")))]
+impl SecretKey {
+    /// Signs the finished 64-byte `digest` of a message in place of its
+    /// SHA-512, in the Ed25519ph domain of `context` (`None` is the empty
+    /// context).
+    ///
+    /// With any hash but SHA-512 this is not RFC 8032 Ed25519ph, only its
+    /// construction with another PH: other implementations won't verify
+    /// it. The nonce is still the SHA-512 of the key prefix and the
+    /// digest, so its security doesn't depend on `D`. For `Sha512` of
+    /// RustCrypto `sha2`, the signature is the RFC 8032 one.
+    ///
+    /// Returns `Ok(Signature)`, `Err(Error::PublicKeyMismatch)` if
+    /// `public` isn't the public key of `SecretKey` and
+    /// `Err(Error::ContextTooLong)` if `context` is longer than 255
+    /// bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate blake2;
+    /// extern crate ed25519_fun;
+    ///
+    /// use blake2::{Blake2b512, Digest};
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let digest = Blake2b512::new().chain_update(b"large artifact");
+    ///     let signature = keypair
+    ///         .secret()
+    ///         .sign_digest(keypair.public(), digest.clone(), None)
+    ///         .unwrap();
+    ///     assert!(keypair
+    ///         .public()
+    ///         .verify_digest(digest, None, &signature)
+    ///         .is_ok());
+    /// }
+    /// ```
+    pub fn sign_digest<D>(
+        &self,
+        public: &PublicKey,
+        digest: D,
+        context: Option<&[u8]>,
+    ) -> Result<Signature, Error>
+    where
+        D: ::digest::Digest<OutputSize = ::digest::consts::U64>,
+    {
+        let domain = ph_domain(context)?;
+        if PublicKey::from_secret(self) != *public {
+            return Err(Error::PublicKeyMismatch);
+        }
+        Ok(self.sign_prehash_unchecked(public, &digest.finalize(), domain))
+    }
+}
+
+impl PublicKey {
+    /// Verifies a signature of `sign_digest` over the finished 64-byte
+    /// `digest`, in the Ed25519ph domain of `context` (`None` is the
+    /// empty context). The digest must come from the same hash as the
+    /// signer's.
+    ///
+    /// Returns `Ok(())` if the signature is valid,
+    /// `Err(Error::ContextTooLong)` if `context` is longer than 255 bytes
+    /// and `Err` otherwise.
+    #[cfg(feature = "digest")]
+    pub fn verify_digest<D>(
+        &self,
+        digest: D,
+        context: Option<&[u8]>,
+        signature: &Signature,
+    ) -> Result<(), Error>
+    where
+        D: ::digest::Digest<OutputSize = ::digest::consts::U64>,
+    {
+        let domain = ph_domain(context)?;
+        self.verify_prehash(&digest.finalize(), domain, signature)
+    }
+
+    /// Verifies an Ed25519ph signature of the finished SHA-512 `digest`
+    /// of a message, in the domain of `context` (`None` is the empty
+    /// context).
+    ///
+    /// Returns `Ok(())` if the signature is valid,
+    /// `Err(Error::ContextTooLong)` if `context` is longer than 255 bytes
+    /// and `Err` otherwise.
+    pub fn verify_prehashed(
+        &self,
+        digest: Sha512,
+        context: Option<&[u8]>,
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        let domain = ph_domain(context)?;
+        self.verify_prehash(&digest.result(), domain, signature)
+    }
+
+    /// Verifies an Ed25519ph signature of the 64-byte prehash `prehash`
+    /// in the domain `domain`, as returned by `ph_domain`.
+    pub(crate) fn verify_prehash(
+        &self,
+        prehash: &[u8],
+        domain: Option<(u8, &[u8])>,
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        self.verify_absorbing(signature, domain, |hash| hash.input(prehash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // RFC 8032, section 7.3: Ed25519ph, message "abc".
+    #[cfg(not(feature = "verify-only"))]
+    const SECRET_KEY: &str = "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42";
+    const PUBLIC_KEY: &str = "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf";
+    const SIGNATURE: &str = "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+                             31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406";
+
+    fn abc() -> Sha512 {
+        let mut digest = Sha512::new();
+        digest.input(b"abc");
+        digest
+    }
+
+    fn public() -> PublicKey {
+        PublicKey::from_bytes(&hex::decode(PUBLIC_KEY).unwrap()).unwrap()
+    }
+
+    fn signature() -> Signature {
+        Signature::from_bytes(&hex::decode(SIGNATURE).unwrap()).unwrap()
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn sign_prehashed_rfc8032_vector() {
+        let secret = SecretKey::from_bytes(&hex::decode(SECRET_KEY).unwrap()).unwrap();
+        for context in [None, Some(&b""[..])].iter() {
+            let signature = secret.sign_prehashed(&public(), abc(), *context).unwrap();
+            assert_eq!(hex::encode(signature.0), SIGNATURE);
+        }
+
+        // Plain Ed25519 over the same bytes is another signature.
+        let plain = secret.sign(&public(), b"abc").unwrap();
+        assert!(plain != signature());
+        assert!(public().verify(b"abc", &plain).is_ok());
+        assert!(public().verify_prehashed(abc(), None, &plain).is_err());
+
+        let other = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        assert_eq!(
+            other.sign_prehashed(&public(), abc(), None),
+            Err(Error::PublicKeyMismatch)
+        );
+    }
+
+    #[test]
+    fn verify_prehashed_rfc8032_vector() {
+        assert!(public().verify_prehashed(abc(), None, &signature()).is_ok());
+        assert!(public()
+            .verify_prehashed(abc(), Some(b""), &signature())
+            .is_ok());
+        // Another context, or the message as a plain Ed25519 one.
+        assert!(public()
+            .verify_prehashed(abc(), Some(b"x"), &signature())
+            .is_err());
+        assert!(public().verify(b"abc", &signature()).is_err());
+        assert!(public().verify(&abc().result(), &signature()).is_err());
+    }
+
+    #[cfg(all(feature = "digest", not(feature = "verify-only")))]
+    #[test]
+    fn sign_digest_other_hashes() {
+        extern crate blake2;
+        extern crate sha3;
+
+        use ::digest::Digest;
+        use blake2::Blake2b512;
+        use sha3::Sha3_512;
+
+        let secret = SecretKey::from_bytes(&hex::decode(SECRET_KEY).unwrap()).unwrap();
+        let blake2b = || Blake2b512::new().chain_update(b"abc");
+        let sha3 = || Sha3_512::new().chain_update(b"abc");
+
+        let signature = secret.sign_digest(&public(), blake2b(), None).unwrap();
+        assert!(public().verify_digest(blake2b(), None, &signature).is_ok());
+        // The digest replaces PH(M), SHA-512 or not.
+        assert!(public()
+            .verify_prehash(&blake2b().finalize(), ph_domain(None).unwrap(), &signature)
+            .is_ok());
+        // Another hash, context or message.
+        assert!(public().verify_digest(sha3(), None, &signature).is_err());
+        assert!(public()
+            .verify_digest(blake2b(), Some(b"x"), &signature)
+            .is_err());
+        let other = Blake2b512::new().chain_update(b"abd");
+        assert!(public().verify_digest(other, None, &signature).is_err());
+
+        let signature = secret.sign_digest(&public(), sha3(), Some(b"x")).unwrap();
+        assert!(public()
+            .verify_digest(sha3(), Some(b"x"), &signature)
+            .is_ok());
+        assert!(public()
+            .verify_digest(blake2b(), Some(b"x"), &signature)
+            .is_err());
+
+        let too_long = [0u8; 256];
+        assert_eq!(
+            secret.sign_digest(&public(), sha3(), Some(&too_long)),
+            Err(Error::ContextTooLong { len: 256 })
+        );
+        let other = SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        assert_eq!(
+            other.sign_digest(&public(), sha3(), None),
+            Err(Error::PublicKeyMismatch)
+        );
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn prehashed_context_lengths() {
+        let secret = SecretKey::from_bytes(&hex::decode(SECRET_KEY).unwrap()).unwrap();
+        let longest = [0xa5u8; 255];
+        let signature = secret
+            .sign_prehashed(&public(), abc(), Some(&longest))
+            .unwrap();
+        assert!(public()
+            .verify_prehashed(abc(), Some(&longest), &signature)
+            .is_ok());
+        assert!(public()
+            .verify_prehashed(abc(), Some(&longest[1..]), &signature)
+            .is_err());
+
+        let too_long = [0xa5u8; 256];
+        assert_eq!(
+            secret.sign_prehashed(&public(), abc(), Some(&too_long)),
+            Err(Error::ContextTooLong { len: 256 })
+        );
+        assert_eq!(
+            public().verify_prehashed(abc(), Some(&too_long), &signature),
+            Err(Error::ContextTooLong { len: 256 })
+        );
+    }
+}
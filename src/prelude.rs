@@ -0,0 +1,13 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// The types almost every user of the crate names, for a single glob
+// import.
+
+pub use crate::errors::Error;
+#[cfg(not(feature = "verify-only"))]
+pub use crate::keypair::Keypair;
+pub use crate::public::PublicKey;
+#[cfg(not(feature = "verify-only"))]
+pub use crate::secret::SecretKey;
+pub use crate::signature::Signature;
@@ -0,0 +1,156 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// proptest strategies for crates that property-test their own encodings
+// and protocols on top of these types.
+//
+// Which artifacts verify:
+//
+//   SecretKey, Keypair   valid keys, from arbitrary 32-byte seeds
+//   PublicKey            valid keys, derived from arbitrary seeds
+//   signed_messages()    a public key, a message and its signature:
+//                        always verifies
+//   Signature            64 arbitrary bytes: it parses, but almost never
+//                        verifies, and s is usually not reduced
+//
+// With `verify-only` there are no secret keys to derive from, so only
+// `Signature` is arbitrary.
+
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+use ::proptest::arbitrary::{any, Arbitrary};
+#[cfg(not(feature = "verify-only"))]
+use ::proptest::collection::vec;
+use ::proptest::strategy::{BoxedStrategy, Strategy};
+
+/// The longest message of `signed_messages`.
+#[cfg(not(feature = "verify-only"))]
+const MAX_MESSAGE_SIZE: usize = 256;
+
+#[cfg(not(feature = "verify-only"))]
+/// Valid secret keys, from arbitrary seeds.
+impl Arbitrary for SecretKey {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<SecretKey>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<[u8; 32]>().prop_map(SecretKey).boxed()
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+/// Keypairs of arbitrary secret keys.
+impl Arbitrary for Keypair {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Keypair>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<SecretKey>().prop_map(Keypair::from_secret).boxed()
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+/// Public keys of arbitrary secret keys, so always curve points.
+impl Arbitrary for PublicKey {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<PublicKey>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<SecretKey>()
+            .prop_map(|secret| PublicKey::from_secret(&secret))
+            .boxed()
+    }
+}
+
+/// Signatures of 64 arbitrary bytes: well formed, but not made by any
+/// key. Use `signed_messages` for signatures that verify.
+impl Arbitrary for Signature {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Signature>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<[u8; 64]>().prop_map(Signature).boxed()
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+/// Generates a public key, a message of up to 256 bytes and the
+/// signature of the message under the key.
+///
+/// Every triple verifies.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+/// extern crate proptest;
+///
+/// use ed25519_fun::proptest::signed_messages;
+/// use proptest::prop_assert;
+/// use proptest::test_runner::TestRunner;
+///
+/// fn main() {
+///     let mut runner = TestRunner::default();
+///     runner
+///         .run(&signed_messages(), |(public_key, message, signature)| {
+///             prop_assert!(public_key.verify(&message, &signature).is_ok());
+///             Ok(())
+///         })
+///         .unwrap();
+/// }
+/// ```
+pub fn signed_messages() -> impl Strategy<Value = (PublicKey, Vec<u8>, Signature)> {
+    (any::<Keypair>(), vec(any::<u8>(), 0..=MAX_MESSAGE_SIZE)).prop_map(|(keypair, message)| {
+        let signature = keypair.sign(&message);
+        (*keypair.public(), message, signature)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::proptest::prelude::*;
+
+    proptest! {
+        #[cfg(not(feature = "verify-only"))]
+        #[test]
+        fn signed_messages_verify((public, message, signature) in signed_messages()) {
+            prop_assert!(public.verify(&message, &signature).is_ok());
+            // Through bytes, as a downstream codec would carry them.
+            let public = PublicKey::from_bytes(public.as_bytes()).unwrap();
+            let signature = Signature::from_bytes(signature.as_bytes()).unwrap();
+            prop_assert!(public.verify(&message, &signature).is_ok());
+            let mut altered = message.clone();
+            altered.push(0);
+            prop_assert!(public.verify(&altered, &signature).is_err());
+        }
+
+        #[cfg(not(feature = "verify-only"))]
+        #[test]
+        fn keypairs_are_consistent(keypair in any::<Keypair>()) {
+            prop_assert_eq!(PublicKey::from_secret(keypair.secret()), *keypair.public());
+        }
+
+        #[cfg(not(feature = "verify-only"))]
+        #[test]
+        fn arbitrary_signatures_dont_verify(
+            public in any::<PublicKey>(),
+            message in vec(any::<u8>(), 0..64),
+            signature in any::<Signature>(),
+        ) {
+            prop_assert!(public.verify(&message, &signature).is_err());
+        }
+
+        #[test]
+        fn arbitrary_signatures_round_trip(signature in any::<Signature>()) {
+            let decoded = Signature::from_bytes(signature.as_bytes()).unwrap();
+            prop_assert_eq!(decoded.as_bytes(), signature.as_bytes());
+        }
+    }
+}
@@ -3,110 +3,97 @@
 
 // Ed25519 interface.
 
-#![allow(non_snake_case)]
-
-use crate::curve25519::group_element::*;
-
+use crate::b16;
 use crate::constants::*;
-use crate::curve25519::scalar_ops::*;
 use crate::errors::*;
-use crate::secret::*;
-use crate::signature::*;
 
-use sha2::{Digest, Sha512};
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+use subtle::{Choice, ConstantTimeEq};
 
 /// The Ed25519 public key.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/public_key.rs")]
+/// ```
 #[derive(Copy, Clone)]
+#[repr(transparent)]
 pub struct PublicKey(pub(crate) [u8; PublicKeySize]);
 
-const L: [u8; 32] = [
-    0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-    0x14, 0xde, 0xf9, 0xde, 0xa2, 0xf7, 0x9c, 0xd6, 0x58, 0x12, 0x63, 0x1a, 0x5c, 0xf5, 0xd3, 0xed,
-];
-
-/// Check if the signature s is within the group order L.
-fn check_lt_l(s: &[u8]) -> bool {
-    let mut c: u8 = 0;
-    let mut n: u8 = 1;
-
-    let mut i = 31;
-    loop {
-        c |= ((((s[i] as i32) - (L[i] as i32)) >> 8) as u8) & n;
-        n &= ((((s[i] ^ L[i]) as i32) - 1) >> 8) as u8;
-        if i == 0 {
-            break;
-        } else {
-            i -= 1;
-        }
-    }
-    c == 0
-}
-
 impl PublicKey {
-    /// Generates `PublicKey` by providing a `SecretKey`.
-    ///
-    /// Returns the `PublicKey` counterpart.
-    pub(crate) fn generate(pr: &SecretKey) -> PublicKey {
-        // Hash the 32-byte private key using SHA-512, storing the digest in
-        // a 64-octet large buffer h. Only the lower 32 bytes are
-        // used for generating the public key.
-        let h = {
-            let mut hash = Sha512::default();
-            hash.input(pr.0);
-            let mut output = hash.result();
-            // Lowest 3 bits of the first octet are cleared
-            output[0] &= 248;
-            // Highest bit of the last octet is cleared
-            output[31] &= 63;
-            // Second highest bit of the last octet is set
-            output[31] |= 64;
-            output
-        };
-
-        // Scalar multiplication: h * B.
-        let point = Precomp::scalar_multiply(&h[0..32]);
-        // Encode P2 point y coordinate.
-        let public: [u8; 32] = point.encode();
-
-        PublicKey(public)
+    /// Borrows the 32-byte encoding of `PublicKey`.
+    ///
+    /// Returns a reference to the 32-byte array `[u8; 32]`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     let bytes: &[u8; 32] = public_key.as_bytes();
+    /// }
+    /// ```
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
     }
 
     /// Converts `PublicKey` into a 32-byte array.
     ///
-    /// Returns a 32-byte array `[u8; 32]`.
+    /// Returns a copy of the 32-byte array `[u8; 32]`.
     ///
     /// # Example
     ///
     /// ```rust
     /// extern crate ed25519_fun;
     ///
-    /// use ed25519_fun::{Keypair, PublicKey};
+    /// use ed25519_fun::PublicKey;
     ///
     /// fn main() {
-    ///     let keypair = Keypair::generate();
-    ///     let public_key = keypair.public;
-    ///     let bytes: [u8; 32] = public_key.as_bytes();
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     let bytes: [u8; 32] = public_key.to_bytes();
     /// }
     /// ```
-    pub fn as_bytes(&self) -> [u8; 32] {
+    pub fn to_bytes(&self) -> [u8; 32] {
         self.0
     }
 
     /// Constructs `PublicKey` from a slice.
     ///
-    /// Returns `Ok(PublicKey)` if `bytes` is 32 bytes long and `Err` otherwise.
+    /// Returns `Ok(PublicKey)` if `bytes` is 32 bytes long and
+    /// `Err(Error::InvalidPublicKeyLength)`, with both lengths, otherwise.
     ///
     /// # Example
     ///
     /// ```rust
     /// extern crate ed25519_fun;
     ///
-    /// use ed25519_fun::{Keypair, PublicKey};
+    /// use ed25519_fun::PublicKey;
     ///
     /// fn main() {
-    ///     let keypair = Keypair::generate();
-    ///     let public_key = keypair.public;
-    ///     let bytes: [u8; 32] = public_key.as_bytes();
+    ///     let public_key = PublicKey::from_bytes(&[
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ])
+    ///     .unwrap();
+    ///     let bytes: [u8; 32] = public_key.to_bytes();
     ///     let public_key_from_bytes: PublicKey = PublicKey::from_bytes(&bytes).unwrap();
     /// }
     /// ```
@@ -114,7 +101,10 @@ impl PublicKey {
         let mut public = [0u8; PublicKeySize];
 
         if bytes.len() != PublicKeySize {
-            return Err(Error::InvalidPublicKey);
+            return Err(Error::InvalidPublicKeyLength {
+                expected: PublicKeySize,
+                got: bytes.len(),
+            });
         }
 
         public.copy_from_slice(bytes);
@@ -122,70 +112,173 @@ impl PublicKey {
         Ok(PublicKey(public))
     }
 
-    /// Verifies a signature with this `PublicKey`.
+    /// Constructs `PublicKey` from an array. Like `from_bytes`, it doesn't
+    /// check that the bytes encode a curve point: `verify` does.
     ///
-    /// Returns `Ok(())` if the signature is valid and `Err` otherwise.
+    /// Returns the `PublicKey`.
     ///
     /// # Example
     ///
     /// ```rust
     /// extern crate ed25519_fun;
     ///
-    /// use ed25519_fun::{Keypair, Signature};
+    /// use ed25519_fun::PublicKey;
     ///
     /// fn main() {
-    ///     let message: &[u8] = b"";
-    ///     let keypair = Keypair::generate();
-    ///     let secret_key = keypair.secret;
-    ///     let public_key = keypair.public;
-    ///     let signature: Signature = secret_key.sign(&public_key, message);
-    ///     let _signok = public_key.verify(message, &signature);
+    ///     let public_key = PublicKey::from_array([0x5a; 32]);
+    ///     let bytes: [u8; 32] = public_key.into();
+    ///     assert!(PublicKey::from(bytes) == public_key);
     /// }
     /// ```
-    pub fn verify(&self, message: &[u8], sig: &Signature) -> Result<(), Error> {
-        let signature = sig.as_bytes();
-        let s = &signature[32..64];
+    pub fn from_array(bytes: [u8; PublicKeySize]) -> Self {
+        PublicKey(bytes)
+    }
+}
 
-        if check_lt_l(s) {
-            return Err(Error::InvalidSignature);
-        }
+/// Constructs `PublicKey` as `from_array` does.
+impl From<[u8; PublicKeySize]> for PublicKey {
+    fn from(bytes: [u8; PublicKeySize]) -> Self {
+        PublicKey::from_array(bytes)
+    }
+}
 
-        // Try to decode the public key into a P3 point.
-        // Verification fails if decoding fails.
-        let A = match P3::decode(self.0) {
-            Some(point) => point,
-            None => {
-                return Err(Error::InvalidSignature);
-            }
-        };
-
-        // Compute SHA512(R || A || PH(M)), and interpret the
-        // 64-octet digest as a little-endian integer k.
-        let mut k = {
-            let mut hash = Sha512::default();
-            hash.input(&signature[0..32]);
-            hash.input(&self.0);
-            hash.input(&message);
-            hash.result()
-        };
-        reduce(&mut k);
-
-        // Check the group equation [s]B = R + [k]A'.
-        // Perform [s]B + [k]A'.
-        let eq = P2::double_scalar_multiply_vartime(&k[..], s, A);
-        // Check [s]B + [k]A' == R?
-        if eq
-            .encode()
-            .as_ref()
-            .iter()
-            .zip(signature.iter())
-            .fold(0, |acc, (x, y)| acc | (x ^ y))
-            == 0
-        {
-            Ok(())
-        } else {
-            return Err(Error::SignatureMismatch);
-        }
+/// Returns the 32-byte encoding.
+impl From<PublicKey> for [u8; PublicKeySize] {
+    fn from(public: PublicKey) -> Self {
+        public.0
+    }
+}
+
+/// Borrows the 32-byte encoding.
+impl AsRef<[u8]> for PublicKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Constructs `PublicKey` as `from_bytes` does.
+impl TryFrom<&[u8]> for PublicKey {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+        PublicKey::from_bytes(bytes)
+    }
+}
+
+/// Constructs `PublicKey` as `from_bytes` does.
+impl TryFrom<&[u8; PublicKeySize]> for PublicKey {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8; PublicKeySize]) -> Result<Self, Error> {
+        PublicKey::from_bytes(bytes)
+    }
+}
+
+impl ConstantTimeEq for PublicKey {
+    /// Determines if two keys are equal in constant time.
+    fn ct_eq(&self, other: &PublicKey) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl Eq for PublicKey {}
+
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &PublicKey) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1u8
+    }
+}
+
+/// Hashes the 32-byte encoding, so keys equal under `PartialEq` hash the
+/// same.
+impl Hash for PublicKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+/// Orders keys lexicographically by their 32-byte encoding, for sorted
+/// collections. The order has no cryptographic meaning, and unlike `==`
+/// it is not constant time.
+impl Ord for PublicKey {
+    fn cmp(&self, other: &PublicKey) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl PartialOrd for PublicKey {
+    fn partial_cmp(&self, other: &PublicKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Formats `PublicKey` as lowercase hex.
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        b16::write(f, &self.0)
+    }
+}
+
+/// Formats `PublicKey` as `PublicKey(<hex>)`.
+impl fmt::Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PublicKey({})", self)
+    }
+}
+
+/// Formats `PublicKey` as lowercase hex. `#` prefixes `0x`, and width,
+/// fill and the `0` flag pad as for integers.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::PublicKey;
+///
+/// fn main() {
+///     let public_key = PublicKey::from_array([0xab; 32]);
+///     assert_eq!(format!("{:x}", public_key), "ab".repeat(32));
+///     assert_eq!(format!("{:#X}", public_key), format!("0x{}", "AB".repeat(32)));
+/// }
+/// ```
+impl fmt::LowerHex for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        b16::write_integral(f, &self.0, false)
+    }
+}
+
+/// Formats `PublicKey` as uppercase hex, as `LowerHex` does otherwise.
+impl fmt::UpperHex for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        b16::write_integral(f, &self.0, true)
+    }
+}
+
+/// Parses `PublicKey` from hex, in either case.
+///
+/// Returns `Err(Error::InvalidPublicKey)` if `s` isn't the hex of 32 bytes.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::PublicKey;
+///
+/// fn main() {
+///     let hex = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+///     let public_key: PublicKey = hex.parse().unwrap();
+///     assert_eq!(public_key.to_string(), hex);
+/// }
+/// ```
+impl FromStr for PublicKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut public = [0u8; PublicKeySize];
+        b16::decode_to_slice(s, &mut public).map_err(|_| Error::InvalidPublicKey)?;
+        Ok(PublicKey(public))
     }
 }
 
@@ -202,6 +295,147 @@ mod tests {
                 .unwrap();
         let public = PublicKey::from_bytes(&public_bytes).unwrap();
         let bytes = public.as_bytes();
-        assert!(bytes == public_bytes[..]);
+        assert!(bytes[..] == public_bytes[..]);
+        assert!(public.to_bytes() == *bytes);
+    }
+
+    #[test]
+    fn as_ref_try_from_public_key() {
+        use sha2::{Digest, Sha512};
+
+        let bytes = hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+            .unwrap();
+        let public = PublicKey::try_from(&bytes[..]).unwrap();
+        let mut array = [0u8; PublicKeySize];
+        array.copy_from_slice(&bytes);
+        assert!(PublicKey::try_from(&array).unwrap() == public);
+        assert_eq!(public.as_ref(), &bytes[..]);
+
+        // Straight into generic code taking `AsRef<[u8]>`.
+        assert_eq!(Sha512::digest(public.as_ref()), Sha512::digest(&bytes));
+        let mut hash = Sha512::new();
+        hash.input(public);
+        assert_eq!(hash.result(), Sha512::digest(&bytes));
+
+        for len in [0, 31, 33, 64].iter() {
+            assert!(matches!(
+                PublicKey::try_from(&vec![0u8; *len][..]),
+                Err(Error::InvalidPublicKeyLength {
+                    expected: PUBLIC_KEY_LENGTH,
+                    got
+                }) if got == *len
+            ));
+        }
+    }
+
+    #[test]
+    fn from_array_public_key() {
+        let bytes = [0xd7u8; PublicKeySize];
+        let public = PublicKey::from_array(bytes);
+        assert!(public == PublicKey::from_bytes(&bytes).unwrap());
+        assert!(PublicKey::from(bytes) == public);
+        assert_eq!(<[u8; PublicKeySize]>::from(public), bytes);
+    }
+
+    #[test]
+    fn eq_and_hash_public_key() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashMap;
+
+        fn hash(public: &PublicKey) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            public.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let hex = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+        let public: PublicKey = hex.parse().unwrap();
+        let same: PublicKey = hex.to_uppercase().parse().unwrap();
+        assert!(public == same);
+        assert_eq!(public.ct_eq(&same).unwrap_u8(), 1);
+        assert_eq!(hash(&public), hash(&same));
+
+        // Every single-bit flip is a different key.
+        for bit in 0..8 * PublicKeySize {
+            let mut flipped = public;
+            flipped.0[bit / 8] ^= 1 << (bit % 8);
+            assert!(public != flipped);
+            assert_eq!(public.ct_eq(&flipped).unwrap_u8(), 0);
+        }
+
+        let mut names = HashMap::new();
+        names.insert(public, "TEST 1");
+        assert_eq!(names.get(&same), Some(&"TEST 1"));
+        let mut other = public;
+        other.0[31] ^= 0x80;
+        assert_eq!(names.get(&other), None);
+    }
+
+    #[test]
+    fn ord_public_key() {
+        use rand::seq::SliceRandom;
+        use rand::{thread_rng, RngCore};
+
+        let mut rng = thread_rng();
+        let mut keys: Vec<PublicKey> = (0..64)
+            .map(|i| {
+                let mut bytes = [0u8; PublicKeySize];
+                rng.fill_bytes(&mut bytes);
+                // Long shared prefixes, and some equal keys.
+                bytes[..30].copy_from_slice(&[0x5a; 30]);
+                bytes[30] = i % 4;
+                if i % 8 == 0 {
+                    bytes[31] = 0;
+                }
+                PublicKey(bytes)
+            })
+            .collect();
+        keys.shuffle(&mut rng);
+        keys.sort();
+        for pair in keys.windows(2) {
+            assert!(pair[0].0 <= pair[1].0);
+        }
+
+        for a in keys.iter() {
+            for b in keys.iter() {
+                assert_eq!(a.cmp(b), a.0.cmp(&b.0));
+                assert_eq!(a.cmp(b) == Ordering::Equal, a == b);
+                assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+            }
+        }
+    }
+
+    #[test]
+    fn hex_string_public_key() {
+        // RFC 8032, TEST 1 and TEST 2.
+        for hex in [
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+        ]
+        .iter()
+        {
+            let public: PublicKey = hex.parse().unwrap();
+            assert!(public.as_bytes()[..] == hex::decode(hex).unwrap()[..]);
+            assert_eq!(public.to_string(), *hex);
+            assert_eq!(format!("{:?}", public), format!("PublicKey({})", hex));
+            let upper: PublicKey = hex.to_uppercase().parse().unwrap();
+            assert!(upper.as_bytes() == public.as_bytes());
+        }
+
+        for bad in [
+            "",
+            "d75a",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a00",
+            "x75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        ]
+        .iter()
+        {
+            assert!(matches!(
+                bad.parse::<PublicKey>(),
+                Err(Error::InvalidPublicKey)
+            ));
+        }
     }
 }
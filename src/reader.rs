@@ -0,0 +1,204 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Exact-consumption reader shared by every parser of a container format.
+// A parser either consumes its whole input or fails: trailing bytes are
+// never silently ignored, so two different blobs can't decode to the
+// same object.
+
+use crate::errors::*;
+use crate::utils::*;
+
+/// Cursor over a byte slice that errors on over-read and, through
+/// `finish`, on under-consumption.
+pub(crate) struct ExactReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ExactReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> ExactReader<'a> {
+        ExactReader { data, pos: 0 }
+    }
+
+    /// Number of bytes left.
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    /// Returns the next byte without consuming it.
+    #[cfg_attr(not(feature = "cose"), allow(dead_code))]
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    /// Consumes `len` bytes.
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.remaining() < len {
+            return Err(Error::Truncated { offset: self.pos });
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Validates a length or item count declared on the wire, before
+    /// any conversion to `usize`. Every item is at least one byte long,
+    /// so a declared count can't exceed the bytes left either.
+    ///
+    /// Returns `Err(Error::LengthOverflow)` if `len` exceeds
+    /// `MAX_MESSAGE_LEN` and `Err(Error::Truncated)` if it exceeds the
+    /// bytes left.
+    pub(crate) fn declared_len(&self, len: u64) -> Result<usize, Error> {
+        let len = checked_len(len)?;
+        if len > self.remaining() {
+            return Err(Error::Truncated { offset: self.pos });
+        }
+        Ok(len)
+    }
+
+    /// Consumes `len` bytes, `len` being declared on the wire.
+    #[cfg_attr(not(feature = "cose"), allow(dead_code))]
+    pub(crate) fn take_declared(&mut self, len: u64) -> Result<&'a [u8], Error> {
+        let len = self.declared_len(len)?;
+        self.take(len)
+    }
+
+    /// Consumes a fixed-size array.
+    pub(crate) fn take_array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    /// Consumes one byte.
+    #[cfg_attr(not(feature = "cose"), allow(dead_code))]
+    pub(crate) fn read_u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Consumes a 16-bit little-endian integer.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn read_u16_le(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.take_array()?))
+    }
+
+    /// Consumes a 32-bit little-endian integer.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn read_u32_le(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.take_array()?))
+    }
+
+    /// Consumes a 64-bit little-endian integer.
+    pub(crate) fn read_u64_le(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.take_array()?))
+    }
+
+    /// Consumes a 64-bit little-endian length and that many bytes, as
+    /// written by `WireWriter::write_prefixed`.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn take_prefixed(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.read_u64_le()?;
+        self.take_declared(len)
+    }
+
+    /// Checks that the whole input was consumed.
+    pub(crate) fn finish(self) -> Result<(), Error> {
+        if self.pos != self.data.len() {
+            return Err(Error::TrailingBytes {
+                consumed: self.pos,
+                total: self.data.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Runs `parse` over `bytes` and requires it to consume all of them.
+pub(crate) fn parse_exact<'a, T, F>(bytes: &'a [u8], parse: F) -> Result<T, Error>
+where
+    F: FnOnce(&mut ExactReader<'a>) -> Result<T, Error>,
+{
+    let mut reader = ExactReader::new(bytes);
+    let value = parse(&mut reader)?;
+    reader.finish()?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    #[test]
+    fn exact_reader_bounds() {
+        let data = [1u8, 2, 3, 4];
+        let mut reader = ExactReader::new(&data);
+        assert_eq!(reader.read_u8(), Ok(1));
+        assert_eq!(reader.take_array::<2>(), Ok([2, 3]));
+        assert_eq!(reader.take(2), Err(Error::Truncated { offset: 3 }));
+        assert_eq!(
+            reader.finish(),
+            Err(Error::TrailingBytes {
+                consumed: 3,
+                total: 4
+            })
+        );
+    }
+
+    #[test]
+    fn exact_reader_declared_lengths() {
+        let data = [0u8; 4];
+        let mut reader = ExactReader::new(&data);
+        reader.read_u8().unwrap();
+        for len in [u64::MAX, 1 << 32, (1 << 32) - 1].iter() {
+            assert_eq!(reader.take_declared(*len), Err(Error::LengthOverflow));
+        }
+        assert_eq!(reader.take_declared(4), Err(Error::Truncated { offset: 1 }));
+        assert_eq!(reader.declared_len(3), Ok(3));
+        assert_eq!(reader.take_declared(3), Ok(&data[1..]));
+        assert!(reader.finish().is_ok());
+    }
+
+    #[test]
+    fn exact_reader_little_endian() {
+        let data = hex::decode(
+            "00ff0201ffff040302010000000000000000ffffffffffffffff0300000000000000616263",
+        )
+        .unwrap();
+        let mut reader = ExactReader::new(&data);
+        assert_eq!(reader.read_u8(), Ok(0));
+        assert_eq!(reader.read_u8(), Ok(u8::MAX));
+        assert_eq!(reader.read_u16_le(), Ok(0x0102));
+        assert_eq!(reader.read_u16_le(), Ok(u16::MAX));
+        assert_eq!(reader.read_u32_le(), Ok(0x0102_0304));
+        assert_eq!(reader.read_u64_le(), Ok(0));
+        assert_eq!(reader.read_u64_le(), Ok(u64::MAX));
+        assert_eq!(reader.take_prefixed(), Ok(&b"abc"[..]));
+        assert!(reader.finish().is_ok());
+
+        // A declared length past the input, or past `MAX_MESSAGE_LEN`.
+        let mut reader = ExactReader::new(&data[18..]);
+        assert_eq!(reader.take_prefixed(), Err(Error::LengthOverflow));
+        let mut reader = ExactReader::new(&data[26..36]);
+        assert_eq!(reader.take_prefixed(), Err(Error::Truncated { offset: 8 }));
+        assert_eq!(
+            ExactReader::new(&data[..7]).read_u64_le(),
+            Err(Error::Truncated { offset: 0 })
+        );
+    }
+
+    #[test]
+    fn parse_exact_requires_full_consumption() {
+        let data = [1u8, 2, 3];
+        assert_eq!(parse_exact(&data, |r| r.take(3).map(|b| b.len())), Ok(3));
+        assert_eq!(
+            parse_exact(&data, |r| r.take(2).map(|b| b.len())),
+            Err(Error::TrailingBytes {
+                consumed: 2,
+                total: 3
+            })
+        );
+    }
+}
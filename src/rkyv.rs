@@ -0,0 +1,174 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// rkyv archives: `PublicKey` and `Signature` are archived as themselves.
+// Both are `repr(transparent)` byte arrays, with no pointers, no padding,
+// an alignment of 1 and no byte order to swap, so the bytes in an archive
+// are the encoding and an archived key or signature is used in place,
+// `verify` included.
+//
+// Any 32 or 64 bytes are a valid value of either type, so `CheckBytes`
+// accepts them all: as with `from_bytes`, whether they are a curve point
+// and a canonical signature is up to `verify`.
+
+use crate::public::*;
+use crate::signature::*;
+
+use ::rkyv::{Archive, Deserialize, Fallible, Serialize};
+use core::convert::Infallible;
+
+/// Archives `PublicKey` as its 32 bytes.
+impl Archive for PublicKey {
+    type Archived = PublicKey;
+    type Resolver = ();
+
+    #[inline]
+    unsafe fn resolve(&self, _: usize, _: (), out: *mut PublicKey) {
+        out.write(*self);
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for PublicKey {
+    #[inline]
+    fn serialize(&self, _: &mut S) -> Result<(), S::Error> {
+        Ok(())
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<PublicKey, D> for PublicKey {
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<PublicKey, D::Error> {
+        Ok(*self)
+    }
+}
+
+/// Accepts any 32 bytes, as `PublicKey::from_bytes` does.
+impl<C: ?Sized> ::rkyv::bytecheck::CheckBytes<C> for PublicKey {
+    type Error = Infallible;
+
+    #[inline]
+    unsafe fn check_bytes<'a>(
+        value: *const PublicKey,
+        _: &mut C,
+    ) -> Result<&'a PublicKey, Infallible> {
+        Ok(&*value)
+    }
+}
+
+/// Archives `Signature` as its 64 bytes.
+impl Archive for Signature {
+    type Archived = Signature;
+    type Resolver = ();
+
+    #[inline]
+    unsafe fn resolve(&self, _: usize, _: (), out: *mut Signature) {
+        out.write(*self);
+    }
+}
+
+impl<S: Fallible + ?Sized> Serialize<S> for Signature {
+    #[inline]
+    fn serialize(&self, _: &mut S) -> Result<(), S::Error> {
+        Ok(())
+    }
+}
+
+impl<D: Fallible + ?Sized> Deserialize<Signature, D> for Signature {
+    #[inline]
+    fn deserialize(&self, _: &mut D) -> Result<Signature, D::Error> {
+        Ok(*self)
+    }
+}
+
+/// Accepts any 64 bytes, as `Signature::from_bytes` does.
+impl<C: ?Sized> ::rkyv::bytecheck::CheckBytes<C> for Signature {
+    type Error = Infallible;
+
+    #[inline]
+    unsafe fn check_bytes<'a>(
+        value: *const Signature,
+        _: &mut C,
+    ) -> Result<&'a Signature, Infallible> {
+        Ok(&*value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::*;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+
+    extern crate hex;
+
+    #[derive(Archive, Deserialize, Serialize)]
+    #[archive(check_bytes)]
+    struct Record {
+        public: PublicKey,
+        signature: Signature,
+        message: Vec<u8>,
+    }
+
+    // The records of the sign.input corpus.
+    fn records() -> Vec<Record> {
+        let file = File::open("./tests/sign.input").expect("Where are the test vectors? :(");
+        BufReader::new(file)
+            .lines()
+            .step_by(8)
+            .map(|line| {
+                let l = line.unwrap();
+                let slices: Vec<&str> = l.split(':').collect();
+                Record {
+                    public: PublicKey::from_bytes(&hex::decode(slices[1]).unwrap()).unwrap(),
+                    signature: Signature::from_bytes(&hex::decode(slices[3]).unwrap()[..64])
+                        .unwrap(),
+                    message: hex::decode(slices[2]).unwrap(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rkyv_archive_verifies_in_place() {
+        let records = records();
+        let bytes = ::rkyv::to_bytes::<_, 4096>(&records).unwrap();
+
+        let archived = ::rkyv::check_archived_root::<Vec<Record>>(&bytes[..]).unwrap();
+        assert_eq!(archived.len(), records.len());
+        for (archived, record) in archived.iter().zip(records.iter()) {
+            // Keys and signatures are read where they lie in the archive.
+            let public: &PublicKey = &archived.public;
+            assert!(public
+                .verify(&archived.message, &archived.signature)
+                .is_ok());
+            assert!(*public == record.public && archived.signature == record.signature);
+            assert_eq!(
+                public.verify(b"tampered", &archived.signature),
+                Err(Error::SignatureMismatch)
+            );
+        }
+
+        let restored: Vec<Record> = archived.deserialize(&mut ::rkyv::Infallible).unwrap();
+        assert!(restored
+            .iter()
+            .zip(records.iter())
+            .all(|(a, b)| a.public == b.public
+                && a.signature == b.signature
+                && a.message == b.message));
+    }
+
+    #[test]
+    fn rkyv_archived_layout_is_the_bytes() {
+        let public: PublicKey = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+            .parse()
+            .unwrap();
+        let bytes = ::rkyv::to_bytes::<_, 64>(&public).unwrap();
+        assert_eq!(&bytes[..], public.as_bytes());
+        let archived = ::rkyv::check_archived_root::<PublicKey>(&bytes[..]).unwrap();
+        assert!(*archived == public);
+
+        // Untrusted input of the wrong length doesn't check.
+        assert!(::rkyv::check_archived_root::<Signature>(&bytes[..]).is_err());
+    }
+}
@@ -0,0 +1,20 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// The CSPRNG of the functions that draw their own randomness, such as
+// `Keypair::generate`: `thread_rng` with the default `rand` feature, the
+// operating system's generator through `getrandom` without it. Without
+// either feature, keys are only generated from the caller's generator,
+// with `generate_with`.
+
+/// Returns the CSPRNG of `Keypair::generate`.
+#[cfg(feature = "rand")]
+pub(crate) fn default_rng() -> rand::rngs::ThreadRng {
+    rand::thread_rng()
+}
+
+/// Returns the CSPRNG of `Keypair::generate`.
+#[cfg(not(feature = "rand"))]
+pub(crate) fn default_rng() -> rand_core::OsRng {
+    rand_core::OsRng
+}
@@ -0,0 +1,310 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Key rotation statements: the old key and the new key both sign the
+// same fixed layout, so the old key vouches for its successor and the
+// new key proves it was there to accept it.
+//
+// The signed message is the context string then the encoded statement
+// without its signatures:
+//
+//   "ed25519-fun rotation v1" || old || new || not_before || not_after
+//
+// with both bounds 64-bit little-endian integers. The context string
+// keeps these signatures from being valid for anything else the keys
+// sign.
+
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::public::*;
+use crate::reader::*;
+use crate::signature::*;
+use crate::writer::*;
+
+#[cfg(not(feature = "verify-only"))]
+use core::ops::RangeInclusive;
+
+/// Domain separation of rotation signatures.
+const CONTEXT: &[u8] = b"ed25519-fun rotation v1";
+
+/// Size of the signed part: both keys and both bounds.
+const BODY_SIZE: usize = 2 * PublicKeySize + 16;
+
+/// Size of the message both keys sign.
+const MESSAGE_SIZE: usize = CONTEXT.len() + BODY_SIZE;
+
+/// Size of an encoded `RotationStatement`.
+pub const ROTATION_STATEMENT_LENGTH: usize = BODY_SIZE + 2 * SignatureSize;
+
+/// A statement that `old` hands over to `new` from `not_before` to
+/// `not_after`, both inclusive, signed by both keys.
+///
+/// Encoded as `old || new || not_before || not_after || old_signature ||
+/// new_signature`.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{Keypair, RotationStatement};
+///
+/// fn main() {
+///     let old = Keypair::generate();
+///     let new = Keypair::generate();
+///     let statement = RotationStatement::create(&old, &new, 1_700_000_000..=1_800_000_000).unwrap();
+///
+///     let received = RotationStatement::from_bytes(&statement.to_bytes()).unwrap();
+///     assert_eq!(received.verify(old.public(), 1_750_000_000), Ok(new.public()));
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RotationStatement {
+    old: PublicKey,
+    new: PublicKey,
+    not_before: u64,
+    not_after: u64,
+    old_signature: Signature,
+    new_signature: Signature,
+}
+
+impl RotationStatement {
+    /// The message both keys sign.
+    fn message(&self) -> [u8; MESSAGE_SIZE] {
+        let mut message = [0u8; MESSAGE_SIZE];
+        let mut writer = WireWriter::new(&mut message);
+        writer
+            .write_bytes(CONTEXT)
+            .and_then(|()| self.write_body(&mut writer))
+            .and_then(|()| writer.finish())
+            .expect("the buffer has the exact size of the message");
+        message
+    }
+
+    fn write_body(&self, writer: &mut WireWriter) -> Result<(), Error> {
+        writer.write_bytes(self.old.as_bytes())?;
+        writer.write_bytes(self.new.as_bytes())?;
+        writer.write_u64_le(self.not_before)?;
+        writer.write_u64_le(self.not_after)
+    }
+
+    /// Signs the rotation from `old` to `new`, valid for `window`, with
+    /// both keys.
+    ///
+    /// Returns `Err(Error::InvalidRotationStatement)` if the two keys are
+    /// the same or `window` is empty.
+    #[cfg(not(feature = "verify-only"))]
+    pub fn create(
+        old: &Keypair,
+        new: &Keypair,
+        window: RangeInclusive<u64>,
+    ) -> Result<Self, Error> {
+        let (not_before, not_after) = window.into_inner();
+        if old.public() == new.public() || not_before > not_after {
+            return Err(Error::InvalidRotationStatement);
+        }
+        let mut statement = RotationStatement {
+            old: *old.public(),
+            new: *new.public(),
+            not_before,
+            not_after,
+            old_signature: Signature([0u8; SignatureSize]),
+            new_signature: Signature([0u8; SignatureSize]),
+        };
+        let message = statement.message();
+        statement.old_signature = old.sign(&message);
+        statement.new_signature = new.sign(&message);
+        Ok(statement)
+    }
+
+    /// Checks that `trusted_old` hands over to the new key at time `now`.
+    ///
+    /// Returns the new key if the statement is from `trusted_old`, both
+    /// signatures are valid and `not_before <= now <= not_after`. Returns
+    /// `Err(Error::InvalidRotationStatement)` if the old key isn't
+    /// `trusted_old`, `Err(Error::OutsideValidityWindow)` if `now` is out
+    /// of the window and `Err` if a signature doesn't verify.
+    pub fn verify(&self, trusted_old: &PublicKey, now: u64) -> Result<&PublicKey, Error> {
+        if self.old != *trusted_old || self.old == self.new {
+            return Err(Error::InvalidRotationStatement);
+        }
+        let message = self.message();
+        self.old.verify(&message, &self.old_signature)?;
+        self.new.verify(&message, &self.new_signature)?;
+        if now < self.not_before || now > self.not_after {
+            return Err(Error::OutsideValidityWindow);
+        }
+        Ok(&self.new)
+    }
+
+    /// The key being retired.
+    pub fn old(&self) -> &PublicKey {
+        &self.old
+    }
+
+    /// The key taking over.
+    pub fn new_key(&self) -> &PublicKey {
+        &self.new
+    }
+
+    /// The first second of validity.
+    pub fn not_before(&self) -> u64 {
+        self.not_before
+    }
+
+    /// The last second of validity.
+    pub fn not_after(&self) -> u64 {
+        self.not_after
+    }
+
+    /// Encodes `RotationStatement` as its `ROTATION_STATEMENT_LENGTH`
+    /// bytes.
+    pub fn to_bytes(&self) -> [u8; ROTATION_STATEMENT_LENGTH] {
+        let mut out = [0u8; ROTATION_STATEMENT_LENGTH];
+        let mut writer = WireWriter::new(&mut out);
+        self.write_body(&mut writer)
+            .and_then(|()| writer.write_bytes(&self.old_signature.0))
+            .and_then(|()| writer.write_bytes(&self.new_signature.0))
+            .and_then(|()| writer.finish())
+            .expect("the buffer has the exact size of a statement");
+        out
+    }
+
+    /// Decodes a `RotationStatement` encoded by `to_bytes`.
+    ///
+    /// Returns `Ok(RotationStatement)` if `bytes` is exactly a statement
+    /// and `Err` otherwise. The signatures are only checked by `verify`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        parse_exact(bytes, |reader| {
+            Ok(RotationStatement {
+                old: PublicKey::from_bytes(reader.take(PublicKeySize)?)?,
+                new: PublicKey::from_bytes(reader.take(PublicKeySize)?)?,
+                not_before: reader.read_u64_le()?,
+                not_after: reader.read_u64_le()?,
+                old_signature: Signature(reader.take_array()?),
+                new_signature: Signature(reader.take_array()?),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // The RFC 8032 test 1 key hands over to the test 2 key from
+    // 1700000000 to 1800000000. Signed with the `cryptography` package
+    // of Python, over the layout above.
+    #[cfg(not(feature = "verify-only"))]
+    const OLD_SEED: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    #[cfg(not(feature = "verify-only"))]
+    const NEW_SEED: &str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+    const OLD: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const NEW: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const STATEMENT: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a\
+                             3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c\
+                             00f153650000000000d2496b00000000\
+                             8b8ceed325c0bea404e09b667e4162576f5e7b2758f19a7c502afa2c6bc86836\
+                             00b5837d564087d00d04c5491a48a06bccf900ff78b342de8a4acbfd54015b03\
+                             97369ce0b9ddef65280d0f891fb2afc9f606c813ecc8a7b767ae142d6c75fe3a\
+                             9e3c2f4eaabf905e248dc41290b605bd7c4203768f7d7e91db32d1b183032006";
+
+    fn public(hex: &str) -> PublicKey {
+        PublicKey::from_bytes(&hex::decode(hex).unwrap()).unwrap()
+    }
+
+    fn statement() -> RotationStatement {
+        RotationStatement::from_bytes(&hex::decode(STATEMENT).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn rotation_statement_verifies() {
+        let statement = statement();
+        assert_eq!(statement.old(), &public(OLD));
+        assert_eq!(statement.new_key(), &public(NEW));
+        assert_eq!(statement.not_before(), 1_700_000_000);
+        assert_eq!(statement.not_after(), 1_800_000_000);
+        assert_eq!(hex::encode(statement.to_bytes()), STATEMENT);
+        for now in [1_700_000_000, 1_750_000_000, 1_800_000_000].iter() {
+            assert_eq!(statement.verify(&public(OLD), *now), Ok(&public(NEW)));
+        }
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn rotation_statement_creates() {
+        use crate::secret::*;
+
+        let keypair = |seed: &str| {
+            Keypair::from_secret(SecretKey::from_bytes(&hex::decode(seed).unwrap()).unwrap())
+        };
+        let (old, new) = (keypair(OLD_SEED), keypair(NEW_SEED));
+        let statement = RotationStatement::create(&old, &new, 1_700_000_000..=1_800_000_000);
+        assert_eq!(statement, Ok(self::statement()));
+
+        assert_eq!(
+            RotationStatement::create(&old, &old, 0..=1),
+            Err(Error::InvalidRotationStatement)
+        );
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 2..=1;
+        assert_eq!(
+            RotationStatement::create(&old, &new, empty),
+            Err(Error::InvalidRotationStatement)
+        );
+    }
+
+    #[test]
+    fn rotation_statement_rejects() {
+        let statement = statement();
+        // Expired, and not yet valid.
+        assert_eq!(
+            statement.verify(&public(OLD), 1_800_000_001),
+            Err(Error::OutsideValidityWindow)
+        );
+        assert_eq!(
+            statement.verify(&public(OLD), 1_699_999_999),
+            Err(Error::OutsideValidityWindow)
+        );
+
+        // The keys swapped, as if the new key handed back to the old one.
+        let mut bytes = hex::decode(STATEMENT).unwrap();
+        bytes[..2 * PublicKeySize].rotate_left(PublicKeySize);
+        let swapped = RotationStatement::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            swapped.verify(&public(OLD), 1_750_000_000),
+            Err(Error::InvalidRotationStatement)
+        );
+        assert!(swapped.verify(&public(NEW), 1_750_000_000).is_err());
+        // From a key that isn't trusted.
+        assert_eq!(
+            statement.verify(&public(NEW), 1_750_000_000),
+            Err(Error::InvalidRotationStatement)
+        );
+
+        // The counter-signature of the new key stripped: zeroed, or cut
+        // off.
+        let mut bytes = hex::decode(STATEMENT).unwrap();
+        for b in bytes[BODY_SIZE + SignatureSize..].iter_mut() {
+            *b = 0;
+        }
+        let stripped = RotationStatement::from_bytes(&bytes).unwrap();
+        assert!(stripped.verify(&public(OLD), 1_750_000_000).is_err());
+        assert!(RotationStatement::from_bytes(&bytes[..BODY_SIZE + SignatureSize]).is_err());
+
+        // An extended window.
+        let mut bytes = hex::decode(STATEMENT).unwrap();
+        bytes[2 * PublicKeySize + 15] = 0xff;
+        let extended = RotationStatement::from_bytes(&bytes).unwrap();
+        assert!(extended.verify(&public(OLD), 1_750_000_000).is_err());
+
+        let bytes = hex::decode(STATEMENT).unwrap();
+        assert!(RotationStatement::from_bytes(&[&bytes[..], &[0]].concat()).is_err());
+        assert!(RotationStatement::from_bytes(&[]).is_err());
+    }
+}
@@ -5,41 +5,144 @@
 
 #![allow(non_snake_case)]
 
-use rand::prelude::ThreadRng;
-use rand::thread_rng;
-use rand::RngCore;
+use rand_core::{CryptoRng, RngCore};
 
 use crate::curve25519::group_element::*;
 use crate::curve25519::scalar_ops::*;
 
+use crate::b16;
 use crate::constants::*;
+use crate::dom2::*;
 use crate::errors::*;
+use crate::hasher::*;
+use crate::hkdf;
+#[cfg(feature = "observer")]
+use crate::observer;
 use crate::public::*;
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+use crate::rng::*;
 use crate::signature::*;
+use crate::verify::{check_equation, check_lt_l};
 
+use core::convert::{Infallible, TryFrom};
+use core::fmt;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "secrecy")]
+use secrecy::{CloneableSecret, DebugSecret, ExposeSecret, SecretVec};
 use sha2::{Digest, Sha512};
-use zeroize::Zeroize;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// The Ed25519 secret key.
-#[derive(Zeroize)]
-#[zeroize(drop)]
+///
+/// There is deliberately no `AsRef<[u8]>`, nor `LowerHex` and `UpperHex`:
+/// the key bytes only leave through the explicit `as_bytes` and `to_hex`,
+/// never by accident through generic code or a format string.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/secret_key.rs")]
+/// ```
+#[derive(Zeroize, ZeroizeOnDrop)]
 pub struct SecretKey(pub(crate) [u8; SecretKeySize]);
 
+// Smaller batches are signed on the calling thread.
+#[cfg(feature = "parallel")]
+const PARALLEL_THRESHOLD: usize = 64;
+
 impl SecretKey {
     /// Generates the secret key: 32 octets of cryptographically
     /// secure random data.
     ///
     /// Returns `SecretKey`.
+    #[cfg(any(feature = "rand", feature = "getrandom"))]
     pub(crate) fn generate_key() -> SecretKey {
-        let mut sk = [0u8; 32];
-        let mut csprng: ThreadRng = thread_rng();
-        csprng.fill_bytes(&mut sk);
-        SecretKey(sk)
+        SecretKey::generate_with(&mut default_rng())
+    }
+
+    /// Generates the secret key from 32 bytes of `rng`, such as a vetted
+    /// DRBG, or a seeded one for reproducible tests.
+    ///
+    /// Returns `SecretKey`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    /// extern crate rand;
+    ///
+    /// use ed25519_fun::SecretKey;
+    /// use rand::rngs::OsRng;
+    ///
+    /// fn main() {
+    ///     let secret = SecretKey::generate_with(&mut OsRng);
+    ///     assert!(secret != SecretKey::generate_with(&mut OsRng));
+    /// }
+    /// ```
+    pub fn generate_with<R: RngCore + CryptoRng>(rng: &mut R) -> SecretKey {
+        let mut sk = SecretKey([0u8; SecretKeySize]);
+        rng.fill_bytes(&mut sk.0);
+        sk
+    }
+
+    /// Generates `n` secret keys from one draw of `n * 32` bytes of the
+    /// CSPRNG, cut into consecutive 32-byte chunks.
+    ///
+    /// The CSPRNG output is indistinguishable from independent uniform
+    /// bytes, so disjoint chunks are independent keys, exactly as if each
+    /// came from its own call. The buffer is wiped once the keys are
+    /// copied out.
+    ///
+    /// Returns the `n` secret keys.
+    #[cfg(all(feature = "std", any(feature = "rand", feature = "getrandom")))]
+    pub(crate) fn generate_keys(n: usize) -> Vec<SecretKey> {
+        let len = n
+            .checked_mul(SecretKeySize)
+            .expect("too many keys for one buffer");
+        let mut buffer = vec![0u8; len];
+        default_rng().fill_bytes(&mut buffer);
+        let keys = buffer
+            .chunks_exact(SecretKeySize)
+            .map(|chunk| {
+                let mut sk = SecretKey([0u8; SecretKeySize]);
+                sk.0.copy_from_slice(chunk);
+                sk
+            })
+            .collect();
+        buffer.as_mut_slice().zeroize();
+        keys
+    }
+
+    /// Borrows the 32 bytes of `SecretKey`, without copying them out of
+    /// the wiped-on-drop key.
+    ///
+    /// Returns a reference to the 32-byte array `[u8; 32]`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Keypair};
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let secret_key = keypair.secret();
+    ///     let bytes: &[u8; 32] = secret_key.as_bytes();
+    /// }
+    /// ```
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::SecretKey);
+        &self.0
     }
 
     /// Converts `SecretKey` into a 32-byte array.
     ///
-    /// Returns a 32-byte array `[u8; 32]`.
+    /// Returns a copy of the 32-byte array `[u8; 32]`, which is the
+    /// caller's to wipe.
     ///
     /// # Example
     ///
@@ -50,17 +153,20 @@ impl SecretKey {
     ///
     /// fn main() {
     ///     let keypair = Keypair::generate();
-    ///     let secret_key = keypair.secret;
-    ///     let bytes: [u8; 32] = secret_key.as_bytes();
+    ///     let secret_key = keypair.secret();
+    ///     let bytes: [u8; 32] = secret_key.to_bytes();
     /// }
     /// ```
-    pub fn as_bytes(&self) -> [u8; 32] {
+    pub fn to_bytes(&self) -> [u8; 32] {
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::SecretKey);
         self.0
     }
 
     /// Constructs `SecretKey` from a slice.
     ///
-    /// Returns `Ok(SecretKey)` if `bytes` is 32 bytes long and `Err` otherwise.
+    /// Returns `Ok(SecretKey)` if `bytes` is 32 bytes long and
+    /// `Err(Error::InvalidSecretKeyLength)`, with both lengths, otherwise.
     ///
     /// # Example
     ///
@@ -71,21 +177,248 @@ impl SecretKey {
     ///
     /// fn main() {
     ///     let keypair = Keypair::generate();
-    ///     let secret_key = keypair.secret;
-    ///     let bytes: [u8; 32] = secret_key.as_bytes();
+    ///     let secret_key = keypair.secret();
+    ///     let bytes: [u8; 32] = secret_key.to_bytes();
     ///     let secret_key_from_bytes: SecretKey = SecretKey::from_bytes(&bytes).unwrap();
     /// }
     /// ```
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        let mut secret = [0u8; SecretKeySize];
         if bytes.len() != SecretKeySize {
+            return Err(Error::InvalidSecretKeyLength {
+                expected: SecretKeySize,
+                got: bytes.len(),
+            });
+        }
+        let mut secret = SecretKey([0u8; SecretKeySize]);
+        secret.0.copy_from_slice(bytes);
+        #[cfg(feature = "observer")]
+        observer::imported(observer::KeyKind::SecretKey);
+        Ok(secret)
+    }
+
+    /// Constructs `SecretKey` from an array. Every 32 bytes are a secret
+    /// key, so there is nothing to check. The array is copied, and
+    /// wiping the caller's copy is up to the caller.
+    ///
+    /// There is no conversion back into an array: the bytes only leave
+    /// through `as_bytes` and `to_hex`.
+    ///
+    /// Returns the `SecretKey`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Keypair, SecretKey};
+    ///
+    /// fn main() {
+    ///     let secret_key = SecretKey::from_array([
+    ///         0x9d, 0x61, 0xb1, 0x9d, 0xef, 0xfd, 0x5a, 0x60, 0xba, 0x84, 0x4a, 0xf4, 0x92, 0xec,
+    ///         0x2c, 0xc4, 0x44, 0x49, 0xc5, 0x69, 0x7b, 0x32, 0x69, 0x19, 0x70, 0x3b, 0xac, 0x03,
+    ///         0x1c, 0xae, 0x7f, 0x60,
+    ///     ]);
+    ///     let keypair = Keypair::from_secret(secret_key);
+    ///     assert!(keypair.public().to_string().starts_with("d75a9801"));
+    /// }
+    /// ```
+    pub fn from_array(bytes: [u8; SecretKeySize]) -> Self {
+        #[cfg(feature = "observer")]
+        observer::imported(observer::KeyKind::SecretKey);
+        SecretKey(bytes)
+    }
+
+    /// Constructs `SecretKey` from hex, in either case. There is no
+    /// `FromStr` (nor `Display`) on purpose: secret keys only go through
+    /// strings when asked to.
+    ///
+    /// Returns `Ok(SecretKey)` if `s` is the hex of 32 bytes and
+    /// `Err(Error::InvalidSecretKey)` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::SecretKey;
+    ///
+    /// fn main() {
+    ///     let hex = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+    ///     let secret_key = SecretKey::from_hex(hex).unwrap();
+    ///     assert_eq!(secret_key.to_hex(), hex);
+    /// }
+    /// ```
+    pub fn from_hex(s: &str) -> Result<Self, Error> {
+        let mut secret = SecretKey([0u8; SecretKeySize]);
+        b16::decode_to_slice(s, &mut secret.0).map_err(|_| Error::InvalidSecretKey)?;
+        #[cfg(feature = "observer")]
+        observer::imported(observer::KeyKind::SecretKey);
+        Ok(secret)
+    }
+
+    /// Converts `SecretKey` into lowercase hex.
+    ///
+    /// Returns the 64-character hex string; the caller is responsible for
+    /// wiping it.
+    #[cfg(feature = "std")]
+    pub fn to_hex(&self) -> String {
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::SecretKey);
+        b16::encode(&self.0)
+    }
+
+    /// Constructs `SecretKey` from a libsodium secret key, as returned by
+    /// `crypto_sign_keypair`: the 32-byte seed followed by the public key.
+    ///
+    /// Returns `Ok(SecretKey)` if the embedded public key is the one of the
+    /// seed and `Err(Error::InvalidSecretKey)` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Keypair, SecretKey};
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let sk: [u8; 64] = keypair.to_libsodium_bytes();
+    ///     let secret_key = SecretKey::from_libsodium_sk(&sk).unwrap();
+    ///     assert_eq!(secret_key.as_bytes(), keypair.secret().as_bytes());
+    /// }
+    /// ```
+    pub fn from_libsodium_sk(bytes: &[u8; LibsodiumSecretKeySize]) -> Result<Self, Error> {
+        let secret = SecretKey::from_libsodium_seed(bytes)?;
+        #[cfg(feature = "observer")]
+        observer::imported(observer::KeyKind::SecretKey);
+        Ok(secret)
+    }
+
+    /// Derives `SecretKey` from input keying material with HKDF-SHA512
+    /// (RFC 5869): extract with `salt`, then expand with `info` to the 32
+    /// bytes of the key. Different `info` strings give independent keys
+    /// from the same `ikm`, one per purpose.
+    ///
+    /// The derivation is deterministic and the same on every platform. It
+    /// doesn't add entropy: the caller is responsible for `ikm` being a
+    /// high-entropy secret, at least 32 bytes from a CSPRNG, and not a
+    /// password.
+    ///
+    /// Returns the derived `SecretKey`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::SecretKey;
+    ///
+    /// fn main() {
+    ///     let master = [0x42u8; 32];
+    ///     let signing = SecretKey::derive_from_ikm(&master, b"app v1", b"signing");
+    ///     let backup = SecretKey::derive_from_ikm(&master, b"app v1", b"backup");
+    ///     assert!(signing != backup);
+    ///     assert!(signing == SecretKey::derive_from_ikm(&master, b"app v1", b"signing"));
+    /// }
+    /// ```
+    pub fn derive_from_ikm(ikm: &[u8], salt: &[u8], info: &[u8]) -> SecretKey {
+        let mut prk = hkdf::extract(salt, ikm);
+        let mut secret = SecretKey([0u8; SecretKeySize]);
+        hkdf::expand(&prk, info, &mut secret.0);
+        prk.zeroize();
+        secret
+    }
+
+    /// Generates `SecretKey` from 32 bytes of the OS-seeded CSPRNG mixed
+    /// with caller-supplied entropy: the key is the first 32 bytes of
+    /// SHA-512(random || additional).
+    ///
+    /// This is defense in depth, not a substitute for a CSPRNG: the key
+    /// is as hard to guess as the harder of the two inputs, so a weak or
+    /// public `additional` costs nothing and a good one covers a broken
+    /// system generator. `additional` may be empty or of any length.
+    ///
+    /// Returns the generated `SecretKey`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::SecretKey;
+    ///
+    /// fn main() {
+    ///     let operator_entropy = b"dice rolls: 3 6 1 4 4 2 5 1 6 3";
+    ///     let secret = SecretKey::generate_with_entropy(operator_entropy);
+    ///     assert!(secret != SecretKey::generate_with_entropy(operator_entropy));
+    /// }
+    /// ```
+    #[cfg(any(feature = "rand", feature = "getrandom"))]
+    pub fn generate_with_entropy(additional: &[u8]) -> SecretKey {
+        SecretKey::generate_with_entropy_from(&mut default_rng(), additional)
+    }
+
+    /// Generates `SecretKey` as `generate_with_entropy` does, drawing the
+    /// random half from `rng`.
+    #[cfg_attr(not(any(feature = "rand", feature = "getrandom")), allow(dead_code))]
+    pub(crate) fn generate_with_entropy_from<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        additional: &[u8],
+    ) -> SecretKey {
+        let mut random = [0u8; SecretKeySize];
+        rng.fill_bytes(&mut random);
+        let mut hash = Sha512::new();
+        hash.input(&random);
+        hash.input(additional);
+        random.zeroize();
+        let mut digest = hash.result();
+        let mut sk = SecretKey([0u8; SecretKeySize]);
+        sk.0.copy_from_slice(&digest[..SecretKeySize]);
+        digest.as_mut_slice().zeroize();
+        sk
+    }
+
+    /// Takes the seed of a libsodium secret key and checks the public key
+    /// that follows it.
+    ///
+    /// Returns `Ok(SecretKey)` if the public key is the one of the seed
+    /// and `Err(Error::InvalidSecretKey)` otherwise.
+    pub(crate) fn from_libsodium_seed(bytes: &[u8; LibsodiumSecretKeySize]) -> Result<Self, Error> {
+        let mut secret = SecretKey([0u8; SecretKeySize]);
+        secret.0.copy_from_slice(&bytes[..SecretKeySize]);
+        if PublicKey::from_secret(&secret).0[..] != bytes[SecretKeySize..] {
             return Err(Error::InvalidSecretKey);
         }
-        secret.copy_from_slice(bytes);
-        Ok(SecretKey(secret))
+        Ok(secret)
     }
 
-    /// Signs a message with this `SecretKey`.
+    /// Derives the `PublicKey` of this `SecretKey`, as
+    /// `PublicKey::from_secret` does.
+    ///
+    /// Returns the `PublicKey` counterpart.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::SecretKey;
+    ///
+    /// fn main() {
+    ///     let secret_key = SecretKey::from_array([7; 32]);
+    ///     let public_key = secret_key.public_key();
+    ///     let signature = secret_key.sign(&public_key, b"").unwrap();
+    ///     assert!(public_key.verify(b"", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from_secret(self)
+    }
+
+    /// Signs a message with this `SecretKey`, deriving its public key.
+    ///
+    /// Costs one more scalar multiplication than `Keypair::sign`, which
+    /// keeps the public key; sign with a `Keypair` for many messages.
     ///
     /// Returns `Signature`.
     ///
@@ -94,67 +427,605 @@ impl SecretKey {
     /// ```rust
     /// extern crate ed25519_fun;
     ///
-    /// use ed25519_fun::{Keypair, Signature};
+    /// use ed25519_fun::{SecretKey, Signature};
+    ///
+    /// fn main() {
+    ///     let secret_key = SecretKey::from_array([7; 32]);
+    ///     let signature: Signature = secret_key.sign_detached(b"");
+    ///     assert!(secret_key.public_key().verify(b"", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn sign_detached(&self, message: &[u8]) -> Signature {
+        self.sign_unchecked(&PublicKey::from_secret(self), message)
+    }
+
+    /// Signs a message with this `SecretKey` and its `PublicKey`.
+    ///
+    /// The public key is hashed into the signature, and signing the same
+    /// message under two different public keys gives away the secret
+    /// key, so `public` is checked against the one derived from
+    /// `SecretKey`. `sign_detached` derives it instead.
+    ///
+    /// Returns `Ok(Signature)`, or `Err(Error::PublicKeyMismatch)` if
+    /// `public` isn't the public key of `SecretKey`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Error, Keypair, Signature};
     ///
     /// fn main() {
     ///     let message: &[u8] = b"";
     ///     let keypair = Keypair::generate();
-    ///     let secret_key = keypair.secret;
-    ///     let public_key = keypair.public;
-    ///     let signature: Signature = secret_key.sign(&public_key, message);
+    ///     let secret_key = keypair.secret();
+    ///     let public_key = keypair.public();
+    ///     let signature: Signature = secret_key.sign(public_key, message).unwrap();
+    ///
+    ///     let other = Keypair::generate();
+    ///     assert_eq!(
+    ///         secret_key.sign(other.public(), message),
+    ///         Err(Error::PublicKeyMismatch)
+    ///     );
     /// }
     /// ```
-    pub fn sign(&self, public: &PublicKey, message: &[u8]) -> Signature {
-        // Hash the secret key using SHA-512.
-        let h = {
-            let mut hash = Sha512::new();
-            hash.input(self.0);
-            let mut output = hash.result();
-            output[0] &= 248;
-            output[31] &= 63;
-            output[31] |= 64;
-            output
-        };
+    pub fn sign(&self, public: &PublicKey, message: &[u8]) -> Result<Signature, Error> {
+        if PublicKey::from_secret(self) != *public {
+            return Err(Error::PublicKeyMismatch);
+        }
+        Ok(self.sign_unchecked(public, message))
+    }
 
-        // Compute SHA-512(prefix || PH(M)), where M is the
-        // message to be signed and prefix is the second half of h.
-        // Interpret the 64-octet digest as a little-endian integer r.
-        let mut r = {
-            let mut hash = Sha512::default();
-            hash.input(&h[32..64]);
+    /// Signs a message with this `SecretKey` and its `PublicKey`, and
+    /// verifies the signature before returning it.
+    ///
+    /// A fault during signing, such as a glitched scalar multiplication,
+    /// can make a signature that gives the secret key away. The check
+    /// catches those in R and s at the cost of one verification. It
+    /// reuses the challenge k of signing instead of hashing the message
+    /// again, so a fault in that hash goes through: pair it with
+    /// `sign_hedged` if that matters.
+    ///
+    /// Returns `Ok(Signature)`, `Err(Error::PublicKeyMismatch)` if
+    /// `public` isn't the public key of `SecretKey`, or
+    /// `Err(Error::SelfCheckFailed)` if the signature doesn't verify.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let (secret, public) = (keypair.secret(), keypair.public());
+    ///     let signature = secret.sign_checked(public, b"").unwrap();
+    ///     assert_eq!(signature, keypair.sign(b""));
+    /// }
+    /// ```
+    pub fn sign_checked(&self, public: &PublicKey, message: &[u8]) -> Result<Signature, Error> {
+        if PublicKey::from_secret(self) != *public {
+            return Err(Error::PublicKeyMismatch);
+        }
+        self.sign_checked_trusting_public(public, message)
+    }
+
+    /// `sign_checked` with `public` trusted to be the public key of this
+    /// `SecretKey`: the signature is verified under `public`, but `public`
+    /// isn't compared with the one of the secret key.
+    ///
+    /// Returns `Ok(Signature)`, or `Err(Error::SelfCheckFailed)`.
+    pub(crate) fn sign_checked_trusting_public(
+        &self,
+        public: &PublicKey,
+        message: &[u8],
+    ) -> Result<Signature, Error> {
+        let signed = self.try_sign_with_challenge::<Sha512, _, _>(public, None, None, |hash| {
             hash.input(message);
-            hash.result()
+            Ok::<(), Infallible>(())
+        });
+        let (signature, k) = match signed {
+            Ok(signed) => signed,
+            Err(never) => match never {},
         };
+        #[cfg(test)]
+        let signature = {
+            let mut signature = signature;
+            tests::tamper(&mut signature);
+            signature
+        };
+
+        let A = P3::decode(public.0).ok_or(Error::SelfCheckFailed)?;
+        if !check_lt_l(&signature.0[32..64]) {
+            return Err(Error::SelfCheckFailed);
+        }
+        check_equation(&A, &signature.0, &k).map_err(|_| Error::SelfCheckFailed)?;
+        #[cfg(feature = "observer")]
+        observer::signed(public, |digest| digest.update(message));
+        Ok(signature)
+    }
+
+    /// Signs a message with this `SecretKey` and its `PublicKey`, mixing
+    /// 32 bytes drawn from `rng` into the nonce: r = SHA-512(prefix || Z
+    /// || M), as in draft-irtf-cfrg-det-sigs-with-noise.
+    ///
+    /// A fault during deterministic signing can give away the secret key
+    /// from two signatures of the same message, one of them faulty: they
+    /// share the nonce. The fresh Z gives each signature its own. The
+    /// signatures verify with `PublicKey::verify` like any other, but
+    /// they are no longer reproducible: signing the same message twice
+    /// gives two different signatures, and neither is the one of `sign`.
+    /// The nonce stays secret even if `rng` is broken, since the prefix
+    /// still goes into it.
+    ///
+    /// Returns `Ok(Signature)`, or `Err(Error::PublicKeyMismatch)` if
+    /// `public` isn't the public key of `SecretKey`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    /// extern crate rand;
+    ///
+    /// use ed25519_fun::Keypair;
+    /// use rand::rngs::OsRng;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let (secret, public) = (keypair.secret(), keypair.public());
+    ///     let first = secret.sign_hedged(public, b"", &mut OsRng).unwrap();
+    ///     let second = secret.sign_hedged(public, b"", &mut OsRng).unwrap();
+    ///     assert_ne!(first, second);
+    ///     assert!(public.verify(b"", &first).is_ok());
+    ///     assert!(public.verify(b"", &second).is_ok());
+    /// }
+    /// ```
+    pub fn sign_hedged<R: RngCore + CryptoRng>(
+        &self,
+        public: &PublicKey,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Signature, Error> {
+        if PublicKey::from_secret(self) != *public {
+            return Err(Error::PublicKeyMismatch);
+        }
+        Ok(self.sign_hedged_unchecked(public, message, rng))
+    }
+
+    /// `sign_hedged` with `public` taken as the public key of this
+    /// `SecretKey`.
+    ///
+    /// Returns `Signature`.
+    pub(crate) fn sign_hedged_unchecked<R: RngCore + CryptoRng>(
+        &self,
+        public: &PublicKey,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Signature {
+        let mut noise = [0u8; 32];
+        rng.fill_bytes(&mut noise);
+        let signature = self.sign_absorbing_with::<Sha512, _>(public, None, Some(&noise), |hash| {
+            hash.input(message)
+        });
+        noise.zeroize();
+        #[cfg(feature = "observer")]
+        observer::signed(public, |digest| digest.update(message));
+        signature
+    }
+
+    /// Signs a message with `public` taken as the public key of this
+    /// `SecretKey`, for callers that already hold the pair.
+    ///
+    /// Returns `Signature`.
+    pub(crate) fn sign_unchecked(&self, public: &PublicKey, message: &[u8]) -> Signature {
+        let signature = self.sign_absorbing(public, None, |hash| hash.input(message));
+        #[cfg(feature = "observer")]
+        observer::signed(public, |digest| digest.update(message));
+        signature
+    }
+
+    /// Signs every message with `public` taken as the public key of this
+    /// `SecretKey`, as `sign_unchecked` does one by one. The key is
+    /// expanded and the nonce prefix hashed once for the batch, and large
+    /// batches are signed on the rayon thread pool with the `parallel`
+    /// feature.
+    ///
+    /// Returns the signatures, in the order of `messages`.
+    #[cfg(feature = "std")]
+    pub(crate) fn sign_batch_unchecked(
+        &self,
+        public: &PublicKey,
+        messages: &[&[u8]],
+    ) -> Vec<Signature> {
+        let mut h = self.expand_with::<Sha512>();
+        let mut prefix = Sha512::new();
+        prefix.input(&h[32..64]);
 
-        // Compute the point [r]B.  For efficiency, do this by first
-        // reducing r modulo L, the group order of B.
-        reduce(&mut r[..]);
-        let R: P3 = Precomp::scalar_multiply(&r[0..32]);
-
-        // Compute SHA512(enc(R) || A || PH(M)), and interpret the
-        // 64-octet digest as a little-endian integer k.
-        let mut k = {
-            let mut hash = Sha512::default();
-            hash.input(&R.encode());
-            hash.input(public.0);
-            hash.input(&message);
-            hash.result()
+        let signatures = {
+            let sign = |message: &&[u8]| {
+                // SHA-512(prefix || M), from the hash of the prefix.
+                let mut hash = prefix.clone();
+                hash.input(message);
+                let mut r = [0u8; 64];
+                r.copy_from_slice(&hash.result());
+                let signed =
+                    sign_with_nonce::<Sha512, _, Infallible>(&h, r, public, None, |hash| {
+                        hash.input(message);
+                        Ok(())
+                    });
+                match signed {
+                    Ok((signature, _)) => signature,
+                    Err(never) => match never {},
+                }
+            };
+            #[cfg(feature = "parallel")]
+            let signatures: Vec<Signature> = if messages.len() >= PARALLEL_THRESHOLD {
+                messages.par_iter().map(sign).collect()
+            } else {
+                messages.iter().map(sign).collect()
+            };
+            #[cfg(not(feature = "parallel"))]
+            let signatures: Vec<Signature> = messages.iter().map(sign).collect();
+            signatures
         };
-        reduce(&mut k[..]);
+        h.zeroize();
 
-        // The signature.
-        let mut signature = [0u8; 64];
-        // Populate the second half of the signature with the
-        // result of (r + k * s) mod L.
-        multiply_add(&mut signature[32..64], &k[0..32], &h[0..32], &r);
+        #[cfg(feature = "observer")]
+        observer::signed_batch(public, messages);
+        signatures
+    }
 
-        // Populate the first half of the signature with the
-        // encoding of R.
-        for (result_byte, source_byte) in &mut signature[0..32].iter_mut().zip(R.encode().iter()) {
-            *result_byte = *source_byte;
+    /// Signs the message fed to the hash by `absorb`, which is called
+    /// once for the nonce and once for the challenge, in the domain
+    /// `dom2` (`None` for Ed25519).
+    ///
+    /// Returns `Signature`.
+    pub(crate) fn sign_absorbing<F>(
+        &self,
+        public: &PublicKey,
+        dom2: Option<(u8, &[u8])>,
+        absorb: F,
+    ) -> Signature
+    where
+        F: Fn(&mut Sha512),
+    {
+        self.sign_absorbing_with::<Sha512, F>(public, dom2, None, absorb)
+    }
+
+    /// Signs as `sign_absorbing`, with the SHA-512 of `H` and the 32
+    /// bytes of `noise`, if any, hashed into the nonce after the prefix.
+    ///
+    /// Returns `Signature`.
+    pub(crate) fn sign_absorbing_with<H, F>(
+        &self,
+        public: &PublicKey,
+        dom2: Option<(u8, &[u8])>,
+        noise: Option<&[u8; 32]>,
+        absorb: F,
+    ) -> Signature
+    where
+        H: Sha512Provider,
+        F: Fn(&mut H),
+    {
+        let signature = self.try_sign_absorbing_with::<H, _, _>(public, dom2, noise, |hash| {
+            absorb(hash);
+            Ok::<(), Infallible>(())
+        });
+        match signature {
+            Ok(signature) => signature,
+            Err(never) => match never {},
         }
+    }
+
+    /// Signs as `sign_absorbing`, with an `absorb` that may fail, as one
+    /// reading the message from a source does. Nothing is signed if
+    /// either call fails.
+    ///
+    /// Returns `Ok(Signature)`, or the first error of `absorb`.
+    #[cfg(feature = "std")]
+    pub(crate) fn try_sign_absorbing<F, E>(
+        &self,
+        public: &PublicKey,
+        dom2: Option<(u8, &[u8])>,
+        absorb: F,
+    ) -> Result<Signature, E>
+    where
+        F: FnMut(&mut Sha512) -> Result<(), E>,
+    {
+        self.try_sign_absorbing_with::<Sha512, F, E>(public, dom2, None, absorb)
+    }
+
+    /// Signs as `try_sign_absorbing`, with the SHA-512 of `H` and the
+    /// `noise` of `sign_absorbing_with`.
+    ///
+    /// Returns `Ok(Signature)`, or the first error of `absorb`.
+    pub(crate) fn try_sign_absorbing_with<H, F, E>(
+        &self,
+        public: &PublicKey,
+        dom2: Option<(u8, &[u8])>,
+        noise: Option<&[u8; 32]>,
+        absorb: F,
+    ) -> Result<Signature, E>
+    where
+        H: Sha512Provider,
+        F: FnMut(&mut H) -> Result<(), E>,
+    {
+        self.try_sign_with_challenge::<H, F, E>(public, dom2, noise, absorb)
+            .map(|(signature, _)| signature)
+    }
+
+    /// Signs as `try_sign_absorbing_with`.
+    ///
+    /// Returns `Ok((Signature, k))`, with k the reduced challenge of the
+    /// signature, or the first error of `absorb`.
+    fn try_sign_with_challenge<H, F, E>(
+        &self,
+        public: &PublicKey,
+        dom2: Option<(u8, &[u8])>,
+        noise: Option<&[u8; 32]>,
+        mut absorb: F,
+    ) -> Result<(Signature, [u8; 32]), E>
+    where
+        H: Sha512Provider,
+        F: FnMut(&mut H) -> Result<(), E>,
+    {
+        // Hash the secret key using SHA-512.
+        let mut h = self.expand_with::<H>();
+
+        // Compute SHA-512(dom2 || prefix || Z || PH(M)), where M is the
+        // message to be signed, prefix is the second half of h and Z the
+        // noise of hedged signing, empty otherwise.
+        // Interpret the 64-octet digest as a little-endian integer r.
+        let r = {
+            let mut hash = H::default();
+            write_domain(&mut hash, dom2);
+            hash.update(&h[32..64]);
+            if let Some(noise) = noise {
+                hash.update(noise);
+            }
+            if let Err(error) = absorb(&mut hash) {
+                h.zeroize();
+                return Err(error);
+            }
+            hash.finalize()
+        };
+
+        let signed = sign_with_nonce::<H, F, E>(&h, r, public, dom2, absorb);
+        h.zeroize();
+        signed
+    }
+
+    /// Hashes the secret key with the SHA-512 of `H` and clamps the
+    /// first half of the digest.
+    ///
+    /// Returns the scalar, then the nonce prefix; the caller wipes them.
+    pub(crate) fn expand_with<H: Sha512Provider>(&self) -> [u8; 64] {
+        let mut hash = H::default();
+        hash.update(&self.0);
+        let mut output = hash.finalize();
+        // Lowest 3 bits of the first octet are cleared
+        output[0] &= 248;
+        // Highest bit of the last octet is cleared
+        output[31] &= 63;
+        // Second highest bit of the last octet is set
+        output[31] |= 64;
+        output
+    }
+}
+
+/// Finishes a signature from the expanded secret key `h` and the nonce
+/// digest `r`, which is wiped: R = [r]B, k = SHA512(dom2 || enc(R) || A
+/// || PH(M)) with PH(M) fed by `absorb`, and s = (r + k * a) mod L.
+///
+/// Returns `Ok((Signature, k))`, with k reduced, or the error of
+/// `absorb`.
+pub(crate) fn sign_with_nonce<H, F, E>(
+    h: &[u8; 64],
+    mut r: [u8; 64],
+    public: &PublicKey,
+    dom2: Option<(u8, &[u8])>,
+    mut absorb: F,
+) -> Result<(Signature, [u8; 32]), E>
+where
+    H: Sha512Provider,
+    F: FnMut(&mut H) -> Result<(), E>,
+{
+    // Compute the point [r]B.  For efficiency, do this by first
+    // reducing r modulo L, the group order of B.
+    reduce(&mut r[..]);
+    let R: P3 = Precomp::scalar_multiply(&r[0..32]);
+
+    // Compute SHA512(dom2 || enc(R) || A || PH(M)), and interpret
+    // the 64-octet digest as a little-endian integer k.
+    let mut k = {
+        let mut hash = H::default();
+        write_domain(&mut hash, dom2);
+        hash.update(&R.encode());
+        hash.update(&public.0);
+        if let Err(error) = absorb(&mut hash) {
+            r.zeroize();
+            return Err(error);
+        }
+        hash.finalize()
+    };
+    reduce(&mut k[..]);
+
+    // The signature.
+    let mut signature = [0u8; 64];
+    // Populate the second half of the signature with the
+    // result of (r + k * s) mod L.
+    multiply_add(&mut signature[32..64], &k[0..32], &h[0..32], &r);
+    // The nonce; the caller wipes the expanded secret key.
+    r.zeroize();
+
+    // Populate the first half of the signature with the
+    // encoding of R.
+    for (result_byte, source_byte) in &mut signature[0..32].iter_mut().zip(R.encode().iter()) {
+        *result_byte = *source_byte;
+    }
+
+    let mut challenge = [0u8; 32];
+    challenge.copy_from_slice(&k[..32]);
+    Ok((Signature(signature), challenge))
+}
+
+impl ConstantTimeEq for SecretKey {
+    /// Determines if two keys are equal in constant time.
+    fn ct_eq(&self, other: &SecretKey) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl Eq for SecretKey {}
+
+/// Compares in constant time, through `ct_eq`: the time doesn't tell
+/// where two keys first differ.
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &SecretKey) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1u8
+    }
+}
+
+/// Copies the key bytes into a new `SecretKey`. Each copy wipes itself
+/// when dropped, but every copy is one more place the key can leak from
+/// (a core dump, a swapped page, a stray `mem::forget`): clone only for
+/// as long as a copy is needed.
+impl Clone for SecretKey {
+    fn clone(&self) -> Self {
+        SecretKey(self.0)
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl SecretKey {
+    /// Constructs `SecretKey` from secret bytes, copying them from the
+    /// `SecretVec` straight into the key.
+    ///
+    /// Returns `Ok(SecretKey)` if `bytes` is 32 bytes long and
+    /// `Err(Error::InvalidSecretKeyLength)` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    /// extern crate secrecy;
+    ///
+    /// use ed25519_fun::{Keypair, SecretKey};
+    /// use secrecy::{ExposeSecret, Secret, SecretVec};
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let public_key = *keypair.public();
+    ///     let stored = SecretVec::new(keypair.secret().as_bytes().to_vec());
+    ///
+    ///     // The seed only ever lives in wiped-on-drop types.
+    ///     let secret = Secret::new(SecretKey::from_secret_bytes(stored).unwrap());
+    ///     let signature = secret.expose_secret().sign(&public_key, b"message").unwrap();
+    ///     assert!(public_key.verify(b"message", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn from_secret_bytes(bytes: SecretVec<u8>) -> Result<Self, Error> {
+        SecretKey::from_bytes(bytes.expose_secret())
+    }
+
+    /// Borrows the 32-byte seed, as `ExposeSecret::expose_secret` does
+    /// for a `Secret`: the name marks every place the seed is read.
+    ///
+    /// Returns a reference to the 32-byte array `[u8; 32]`.
+    pub fn expose_seed(&self) -> &[u8; 32] {
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::SecretKey);
+        &self.0
+    }
+}
+
+/// `Secret<SecretKey>` can be cloned, like `SecretKey` itself.
+#[cfg(feature = "secrecy")]
+impl CloneableSecret for SecretKey {}
+
+/// `Secret<SecretKey>` formats as `Secret([REDACTED <type name>])`.
+#[cfg(feature = "secrecy")]
+impl DebugSecret for SecretKey {}
+
+/// Constructs `SecretKey` as `from_bytes` does.
+impl TryFrom<&[u8]> for SecretKey {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+        SecretKey::from_bytes(bytes)
+    }
+}
+
+/// Constructs `SecretKey` as `from_bytes` does.
+impl TryFrom<&[u8; SecretKeySize]> for SecretKey {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8; SecretKeySize]) -> Result<Self, Error> {
+        SecretKey::from_bytes(bytes)
+    }
+}
+
+/// Constructs `SecretKey` as `from_array` does.
+impl From<[u8; SecretKeySize]> for SecretKey {
+    fn from(bytes: [u8; SecretKeySize]) -> Self {
+        SecretKey::from_array(bytes)
+    }
+}
+
+/// Formats `SecretKey` as `SecretKey([REDACTED])`: the key bytes are
+/// neither read nor copied, so nothing outlives the zeroizing drop.
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey([REDACTED])")
+    }
+}
+
+/// Derives the `PublicKey` of a `SecretKey` as `PublicKey::from_secret`
+/// does.
+impl From<&SecretKey> for PublicKey {
+    fn from(secret: &SecretKey) -> Self {
+        PublicKey::from_secret(secret)
+    }
+}
+
+impl PublicKey {
+    /// Derives the `PublicKey` of a `SecretKey`, without building a
+    /// `Keypair`.
+    ///
+    /// Returns the `PublicKey` counterpart.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Keypair, PublicKey};
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     assert!(PublicKey::from_secret(keypair.secret()) == *keypair.public());
+    /// }
+    /// ```
+    pub fn from_secret(pr: &SecretKey) -> PublicKey {
+        PublicKey::from_secret_with::<Sha512>(pr)
+    }
+
+    /// Derives the `PublicKey` of a `SecretKey` with the SHA-512 of `H`.
+    pub(crate) fn from_secret_with<H: Sha512Provider>(pr: &SecretKey) -> PublicKey {
+        // Hash the 32-byte private key using SHA-512, storing the digest in
+        // a 64-octet large buffer h. Only the lower 32 bytes are
+        // used for generating the public key.
+        let mut h = pr.expand_with::<H>();
 
-        Signature(signature)
+        // Scalar multiplication: h * B.
+        let point = Precomp::scalar_multiply(&h[0..32]);
+        // Encode P2 point y coordinate.
+        let public: [u8; 32] = point.encode();
+        h.zeroize();
+
+        PublicKey(public)
     }
 }
 
@@ -164,6 +1035,120 @@ mod tests {
 
     use super::*;
 
+    thread_local! {
+        static TAMPER: core::cell::Cell<Option<fn(&mut Signature)>> =
+            const { core::cell::Cell::new(None) };
+    }
+
+    // Lets a test corrupt the signatures of `sign_checked` between signing
+    // and the check, as a fault would.
+    pub(super) fn tamper(signature: &mut Signature) {
+        if let Some(tamper) = TAMPER.with(|hook| hook.get()) {
+            tamper(signature);
+        }
+    }
+
+    // Fills with 0, 1, 2, ... in place of the OS randomness.
+    struct CountingRng(u8);
+
+    impl RngCore for CountingRng {
+        fn next_u32(&mut self) -> u32 {
+            let mut bytes = [0u8; 4];
+            self.fill_bytes(&mut bytes);
+            u32::from_le_bytes(bytes)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let mut bytes = [0u8; 8];
+            self.fill_bytes(&mut bytes);
+            u64::from_le_bytes(bytes)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for byte in dest.iter_mut() {
+                *byte = self.0;
+                self.0 = self.0.wrapping_add(1);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for CountingRng {}
+
+    #[test]
+    fn generate_with_entropy_mixing() {
+        // The first 32 bytes of SHA-512(00 01 .. 1f || additional),
+        // computed with Python's hashlib.
+        for (additional, expected) in [
+            (
+                &b""[..],
+                "3d94eea49c580aef816935762be049559d6d1440dede12e6a125f1841fff8e6f",
+            ),
+            (
+                &b"operator entropy"[..],
+                "9c3d77b9fa3457c4d1d4529f4435137b0fedfe63809a9cb0ac0f52b9e300d892",
+            ),
+            (
+                &[0u8; 1000][..],
+                "fa6cbf0f1c04c2c8b047cbc291ba4b93ee173b145bc991d6f5f55762aa4fea6a",
+            ),
+        ]
+        .iter()
+        {
+            let secret = SecretKey::generate_with_entropy_from(&mut CountingRng(0), additional);
+            assert_eq!(hex::encode(secret.0), *expected);
+        }
+
+        // The system randomness still counts.
+        let secret = SecretKey::generate_with_entropy_from(&mut CountingRng(1), b"");
+        assert_ne!(
+            hex::encode(secret.0),
+            "3d94eea49c580aef816935762be049559d6d1440dede12e6a125f1841fff8e6f"
+        );
+        #[cfg(any(feature = "rand", feature = "getrandom"))]
+        {
+            assert!(
+                SecretKey::generate_with_entropy(b"x") != SecretKey::generate_with_entropy(b"x")
+            );
+            let keypair = crate::keypair::Keypair::generate_with_entropy(b"");
+            assert_eq!(keypair.public(), &keypair.secret().public_key());
+        }
+    }
+
+    #[test]
+    fn generate_with_seeded_chacha20() {
+        extern crate rand_chacha;
+
+        use crate::keypair::Keypair;
+        use rand::SeedableRng;
+        use rand_chacha::ChaCha20Rng;
+
+        // The secret key is the first ChaCha20 block of the zero key and
+        // nonce (RFC 7539, section A.1, test vector 1). The public key
+        // was derived from it with the `cryptography` package of Python.
+        let secret = SecretKey::generate_with(&mut ChaCha20Rng::from_seed([0u8; 32]));
+        assert_eq!(
+            hex::encode(secret.0),
+            "76b8e0ada0f13d90405d6ae55386bd28bdd219b8a08ded1aa836efcc8b770dc7"
+        );
+        let keypair = Keypair::generate_with(&mut ChaCha20Rng::from_seed([0u8; 32]));
+        assert!(keypair.secret() == &secret);
+        assert_eq!(
+            hex::encode(keypair.public().as_bytes()),
+            "20fdbac9b10b7587bba7b5bc163bce69e796d71e4ed44c10fcb4488689f7a144"
+        );
+        assert_eq!(keypair.public(), &PublicKey::from_secret(&secret));
+
+        // The next draw of the same generator is another key.
+        let mut rng = ChaCha20Rng::from_seed([0u8; 32]);
+        let first = Keypair::generate_with(&mut rng);
+        assert!(Keypair::generate_with(&mut rng).public() != first.public());
+    }
+
     #[test]
     fn as_from_slices_secret_key() {
         let secret_bytes =
@@ -171,6 +1156,314 @@ mod tests {
                 .unwrap();
         let secret = SecretKey::from_bytes(&secret_bytes).unwrap();
         let bytes = secret.as_bytes();
-        assert!(bytes == secret_bytes[..]);
+        assert!(bytes[..] == secret_bytes[..]);
+        assert!(secret.to_bytes() == *bytes);
+    }
+
+    #[test]
+    fn try_from_secret_key() {
+        let bytes = hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+            .unwrap();
+        let secret = SecretKey::try_from(&bytes[..]).unwrap();
+        assert!(secret.as_bytes()[..] == bytes[..]);
+        let mut array = [0u8; SecretKeySize];
+        array.copy_from_slice(&bytes);
+        assert!(SecretKey::try_from(&array).unwrap().as_bytes() == &array);
+
+        for len in [0, 31, 33, 64].iter() {
+            assert!(matches!(
+                SecretKey::try_from(&vec![0u8; *len][..]),
+                Err(Error::InvalidSecretKeyLength {
+                    expected: SECRET_KEY_LENGTH,
+                    got
+                }) if got == *len
+            ));
+        }
+    }
+
+    #[test]
+    fn from_array_secret_key() {
+        let bytes = [0x9du8; SecretKeySize];
+        assert!(SecretKey::from_array(bytes).as_bytes() == &bytes);
+        assert!(SecretKey::from(bytes).as_bytes() == &bytes);
+    }
+
+    #[cfg(feature = "secrecy")]
+    #[test]
+    fn secrecy_secret_key() {
+        use secrecy::Secret;
+
+        let seed = hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+            .unwrap();
+        let secret =
+            Secret::new(SecretKey::from_secret_bytes(SecretVec::new(seed.clone())).unwrap());
+        assert!(secret.expose_secret().expose_seed()[..] == seed[..]);
+
+        let debug = format!("{:?}", secret.clone());
+        assert!(debug.starts_with("Secret([REDACTED "), "{}", debug);
+        assert!(!debug.contains("9d61") && !debug.contains("157"));
+
+        // RFC 8032, TEST 1, signed through the wrapper.
+        let public = PublicKey::from_bytes(
+            &hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            format!("{:x}", secret.expose_secret().sign(&public, b"").unwrap()),
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+        );
+
+        assert!(matches!(
+            SecretKey::from_secret_bytes(SecretVec::new(vec![0u8; 31])),
+            Err(Error::InvalidSecretKeyLength {
+                expected: SECRET_KEY_LENGTH,
+                got: 31
+            })
+        ));
+    }
+
+    #[test]
+    fn sign_checks_public_key() {
+        // RFC 8032, TEST 1 and the public key of TEST 2.
+        let secret =
+            SecretKey::from_hex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                .unwrap();
+        let public: PublicKey = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+            .parse()
+            .unwrap();
+        let other: PublicKey = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c"
+            .parse()
+            .unwrap();
+        let signature = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+        assert_eq!(
+            hex::encode(secret.sign(&public, b"").unwrap().to_bytes()),
+            signature
+        );
+        assert_eq!(hex::encode(secret.sign_detached(b"").to_bytes()), signature);
+        assert_eq!(secret.sign(&other, b""), Err(Error::PublicKeyMismatch));
+        // Any other key, however close, is refused.
+        let mut near = public;
+        near.0[31] ^= 1;
+        assert_eq!(secret.sign(&near, b""), Err(Error::PublicKeyMismatch));
+    }
+
+    #[test]
+    fn eq_secret_key() {
+        let seed = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+        let secret = SecretKey::from_hex(seed).unwrap();
+        let same = SecretKey::from_hex(&seed.to_uppercase()).unwrap();
+        assert!(secret == same);
+        assert_eq!(secret.ct_eq(&same).unwrap_u8(), 1);
+
+        for bit in 0..8 * SecretKeySize {
+            let mut flipped = secret.clone();
+            flipped.0[bit / 8] ^= 1 << (bit % 8);
+            assert!(secret != flipped);
+            assert_eq!(secret.ct_eq(&flipped).unwrap_u8(), 0);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hex_string_secret_key() {
+        // RFC 8032, TEST 1 and TEST 2.
+        for hex in [
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+        ]
+        .iter()
+        {
+            let secret = SecretKey::from_hex(hex).unwrap();
+            assert!(secret.as_bytes()[..] == hex::decode(hex).unwrap()[..]);
+            assert_eq!(secret.to_hex(), *hex);
+            let upper = SecretKey::from_hex(&hex.to_uppercase()).unwrap();
+            assert!(upper.as_bytes() == secret.as_bytes());
+        }
+
+        for bad in [
+            "",
+            "9d61b",
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f6z",
+        ]
+        .iter()
+        {
+            assert!(matches!(
+                SecretKey::from_hex(bad),
+                Err(Error::InvalidSecretKey)
+            ));
+        }
+    }
+
+    #[test]
+    fn debug_secret_key_is_redacted() {
+        let secret_bytes =
+            hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                .unwrap();
+        let secret = SecretKey::from_bytes(&secret_bytes).unwrap();
+        let output = format!("{:?}", secret);
+        assert_eq!(output, "SecretKey([REDACTED])");
+        assert_eq!(format!("{:#?}", secret), output);
+
+        // Types holding a secret key can derive Debug.
+        #[derive(Debug)]
+        struct Holder {
+            #[allow(dead_code)]
+            key: SecretKey,
+        }
+        let holder = format!("{:?}", Holder { key: secret });
+        assert_eq!(holder, "Holder { key: SecretKey([REDACTED]) }");
+    }
+
+    #[test]
+    fn public_key_from_secret() {
+        use std::fs::File;
+        use std::io::{BufRead, BufReader};
+
+        let file = File::open("./tests/sign.input").expect("Where are the test vectors? :(");
+        for line in BufReader::new(file).lines() {
+            let l = line.unwrap();
+            let slices: Vec<&str> = l.split(':').collect();
+            let secret = SecretKey::from_bytes(&hex::decode(slices[0]).unwrap()[..32]).unwrap();
+            let public = PublicKey::from_bytes(&hex::decode(slices[1]).unwrap()).unwrap();
+
+            assert!(PublicKey::from_secret(&secret) == public);
+            assert!(PublicKey::from(&secret) == public);
+            assert!(secret.public_key() == public);
+        }
+    }
+
+    // HKDF-SHA512 outputs and their public keys, from pyca/cryptography.
+    // Derived keys must never change between versions.
+    #[test]
+    fn derive_from_ikm_vectors() {
+        let vectors = [
+            (
+                "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b",
+                "000102030405060708090a0b0c",
+                "f0f1f2f3f4f5f6f7f8f9",
+                "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c14815793",
+                "f2c7e937ce21076af24f0542dfed0a7b78e3d3101b2b3abad8e7f498662479ea",
+            ),
+            (
+                "6d617374657220736563726574",
+                "",
+                "",
+                "1253518663d9496b7cd953579d04b8ac98fee4e8be59c06befdf225ab50d1509",
+                "cc861bb0abeb63cbe0485c699715fe9d9e52c43f80f7cb9a91510be03c338264",
+            ),
+            (
+                "6d617374657220736563726574",
+                "73616c74",
+                "7369676e696e67",
+                "5e7131b6e5877a0eb5587abd86c7af45305662eac2a54e91e40d380ba95e5a63",
+                "d8933fbb6b68db4bb332011eebfb35f969cb3d3b3e702c6a16f0b5495930ef88",
+            ),
+            (
+                "6d617374657220736563726574",
+                "73616c74",
+                "6261636b7570",
+                "52caec916b3adf998c9b49c943de8cbd598a58fcfefc770cfb61efa752dabd71",
+                "1ecf12e1685b00c6c5aad3d25401bad2c8243db8b72b1e1ad60cb1ee91a9a323",
+            ),
+        ];
+        for (ikm, salt, info, secret, public) in vectors.iter() {
+            let derived = SecretKey::derive_from_ikm(
+                &hex::decode(ikm).unwrap(),
+                &hex::decode(salt).unwrap(),
+                &hex::decode(info).unwrap(),
+            );
+            assert_eq!(hex::encode(derived.as_bytes()), *secret);
+            assert_eq!(derived.public_key().to_string(), *public);
+        }
+    }
+
+    #[test]
+    fn from_libsodium_sk() {
+        // crypto_sign_seed_keypair() output from libsodium's
+        // test/default/sign.exp.
+        let mut sk = [0u8; LibsodiumSecretKeySize];
+        sk.copy_from_slice(&hex::decode("421151a459faeade3d247115f94aedae42318124095afabe4d1451a559faedeeb5076a8474a832daee4dd5b4040983b6623b5f344aca57d4d6ee4baf3f259e6e").unwrap());
+        let secret = SecretKey::from_libsodium_sk(&sk).unwrap();
+        assert!(secret.as_bytes()[..] == sk[..SecretKeySize]);
+
+        sk[SecretKeySize] ^= 1;
+        assert!(matches!(
+            SecretKey::from_libsodium_sk(&sk),
+            Err(Error::InvalidSecretKey)
+        ));
+    }
+
+    #[test]
+    fn hedged_signing_is_pinned() {
+        // RFC 8032, TEST 1, signing "hedged" with Z = 00 01 .. 1f and then
+        // Z = 20 21 .. 3f. Computed with a Python transcription of the
+        // reference code of RFC 8032, hashing prefix || Z || M.
+        let secret =
+            SecretKey::from_hex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                .unwrap();
+        let public = secret.public_key();
+        let mut rng = CountingRng(0);
+        for expected in [
+            "d6149de3d86e401f270ae07849d212ce3916ac01bf153e12344060c357f6e3e15e99b6cbf3687558d00b91f538a7e2d9153b1646e708a89152900a1e4c97f305",
+            "3159324fa1f9bb6010a5b3b1d344909509ba585cb29eefb36ff543124c0a772790e366b529a62c4c557990fc2770ec858193ab0c5fda913b5a332effe336d70b",
+        ]
+        .iter()
+        {
+            let signature = secret.sign_hedged(&public, b"hedged", &mut rng).unwrap();
+            assert_eq!(hex::encode(signature.to_bytes()), *expected);
+            assert_eq!(public.verify(b"hedged", &signature), Ok(()));
+            assert_ne!(signature, secret.sign_detached(b"hedged"));
+        }
+
+        let other = SecretKey::from_array([7; 32]).public_key();
+        assert_eq!(
+            secret.sign_hedged(&other, b"hedged", &mut rng),
+            Err(Error::PublicKeyMismatch)
+        );
+    }
+
+    #[test]
+    fn sign_checked_catches_faults() {
+        let secret = SecretKey::from_array([7; 32]);
+        let public = secret.public_key();
+        for message in [&b""[..], b"checked"].iter() {
+            assert_eq!(
+                secret.sign_checked(&public, message),
+                Ok(secret.sign_detached(message))
+            );
+        }
+        assert_eq!(
+            secret.sign_checked(&SecretKey::from_array([8; 32]).public_key(), b""),
+            Err(Error::PublicKeyMismatch)
+        );
+
+        let faults: [fn(&mut Signature); 3] = [
+            // A bit of R.
+            |signature| signature.0[3] ^= 0x10,
+            // A bit of s.
+            |signature| signature.0[40] ^= 0x01,
+            // s + L, which is no longer reduced.
+            |signature| {
+                let L = [
+                    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde,
+                    0xf9, 0xde, 0x14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10,
+                ];
+                let mut carry = 0u16;
+                for (i, l) in L.iter().enumerate() {
+                    let sum = signature.0[32 + i] as u16 + *l as u16 + carry;
+                    signature.0[32 + i] = sum as u8;
+                    carry = sum >> 8;
+                }
+            },
+        ];
+        for fault in faults.iter() {
+            TAMPER.with(|hook| hook.set(Some(*fault)));
+            let checked = secret.sign_checked(&public, b"checked");
+            TAMPER.with(|hook| hook.set(None));
+            assert_eq!(checked, Err(Error::SelfCheckFailed));
+        }
+        assert!(secret.sign_checked(&public, b"checked").is_ok());
     }
 }
@@ -0,0 +1,470 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Shamir secret sharing of the 32-byte seed, for disaster recovery: each
+// byte of the seed is the constant term of its own random polynomial of
+// degree k - 1 over GF(256), and share x holds the 32 values at x. Any k
+// shares interpolate the polynomials back at 0; fewer reveal nothing
+// about the seed.
+//
+// GF(256) is the AES field, x^8 + x^4 + x^3 + x + 1. Multiplication runs
+// in constant time since share values are secret; only the public share
+// indices are ever inverted.
+//
+// A share is 70 bytes:
+//
+//   index       1   x, from 1 to n
+//   threshold   1   k
+//   public key 32   the public key of the shared seed
+//   value      32   the 32 polynomial values at x
+//   checksum    4   the first 4 bytes of the SHA-256 of the above
+//
+// The checksum catches corrupted shares before combining, and the public
+// key catches the rest after: the recovered seed must derive it.
+
+use crate::constants::*;
+use crate::errors::*;
+use crate::public::*;
+use crate::reader::*;
+use crate::secret::*;
+
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use zeroize::Zeroize;
+
+const CHECKSUM_SIZE: usize = 4;
+
+/// The length of an encoded `Share`.
+pub const SHARE_LENGTH: usize = 2 + PublicKeySize + SecretKeySize + CHECKSUM_SIZE;
+
+/// Multiplies in GF(256), in constant time.
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & 0u8.wrapping_sub(b & 1);
+        let carry = 0u8.wrapping_sub(a >> 7);
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    product
+}
+
+/// Inverts a nonzero element of GF(256), as a^254.
+fn gf_inv(a: u8) -> u8 {
+    // 254 = 2 + 4 + ... + 128.
+    let mut square = a;
+    let mut inverse = 1u8;
+    for _ in 0..7 {
+        square = gf_mul(square, square);
+        inverse = gf_mul(inverse, square);
+    }
+    inverse
+}
+
+/// One share of a `SecretKey`, from `SecretKey::split`.
+///
+/// The share value is wiped on drop, and Debug leaves it out.
+#[derive(Clone)]
+pub struct Share {
+    index: u8,
+    threshold: u8,
+    public_key: PublicKey,
+    value: [u8; SecretKeySize],
+}
+
+impl Share {
+    /// The index of `Share`, from 1 to the number of shares.
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    /// The number of shares needed to recover the key.
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// The public key of the shared key.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Encodes `Share` in `SHARE_LENGTH` bytes, with a checksum.
+    pub fn to_bytes(&self) -> [u8; SHARE_LENGTH] {
+        let mut bytes = [0u8; SHARE_LENGTH];
+        bytes[0] = self.index;
+        bytes[1] = self.threshold;
+        bytes[2..2 + PublicKeySize].copy_from_slice(self.public_key.as_bytes());
+        bytes[2 + PublicKeySize..SHARE_LENGTH - CHECKSUM_SIZE].copy_from_slice(&self.value);
+        let checksum = Sha256::digest(&bytes[..SHARE_LENGTH - CHECKSUM_SIZE]);
+        bytes[SHARE_LENGTH - CHECKSUM_SIZE..].copy_from_slice(&checksum[..CHECKSUM_SIZE]);
+        bytes
+    }
+
+    /// Decodes a `Share` encoded by `to_bytes`.
+    ///
+    /// Returns `Err(Error::InvalidShare)` if the checksum doesn't match or
+    /// the index, threshold or public key is invalid, and
+    /// `Err(Error::Truncated)` or `Err(Error::TrailingBytes)` if `bytes`
+    /// is shorter or longer than a share.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Share, Error> {
+        let (index, threshold, public_key, value, checksum) = parse_exact(bytes, |reader| {
+            Ok((
+                reader.read_u8()?,
+                reader.read_u8()?,
+                reader.take_array::<PublicKeySize>()?,
+                reader.take_array::<SecretKeySize>()?,
+                reader.take_array::<CHECKSUM_SIZE>()?,
+            ))
+        })?;
+        let share = Share {
+            index,
+            threshold,
+            public_key: PublicKey::from_bytes(&public_key).map_err(|_| Error::InvalidShare)?,
+            value,
+        };
+        if Sha256::digest(&bytes[..SHARE_LENGTH - CHECKSUM_SIZE])[..CHECKSUM_SIZE] != checksum
+            || index == 0
+            || threshold == 0
+        {
+            return Err(Error::InvalidShare);
+        }
+        Ok(share)
+    }
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// Formats `Share` without its value.
+impl fmt::Debug for Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Share")
+            .field("index", &self.index)
+            .field("threshold", &self.threshold)
+            .field("public_key", &format_args!("{}", self.public_key))
+            .finish()
+    }
+}
+
+impl SecretKey {
+    /// Splits `SecretKey` into `n` shares, any `k` of which recover it
+    /// with `SecretKey::combine`.
+    ///
+    /// Returns the shares, with indices 1 to `n`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0 or greater than `n`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    /// extern crate rand;
+    ///
+    /// use ed25519_fun::shamir::Share;
+    /// use ed25519_fun::{Error, Keypair, SecretKey};
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let keypair = Keypair::generate();
+    ///     let shares = keypair.secret().split(5, 3, &mut rand::thread_rng());
+    ///
+    ///     // Shares travel as bytes.
+    ///     let stored: Vec<_> = shares.iter().map(Share::to_bytes).collect();
+    ///     let kept = [
+    ///         Share::from_bytes(&stored[4])?,
+    ///         Share::from_bytes(&stored[0])?,
+    ///         Share::from_bytes(&stored[2])?,
+    ///     ];
+    ///     assert!(SecretKey::combine(&kept)? == *keypair.secret());
+    ///     assert_eq!(
+    ///         SecretKey::combine(&kept[..2]).err(),
+    ///         Some(Error::NotEnoughShares { threshold: 3, got: 2 })
+    ///     );
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn split<R: RngCore + CryptoRng>(&self, n: u8, k: u8, rng: &mut R) -> Vec<Share> {
+        assert!(k >= 1 && k <= n, "the threshold must be from 1 to n");
+        let public_key = PublicKey::from_secret(self);
+
+        // The coefficients of degree 1 to k - 1 of the 32 polynomials.
+        let mut coefficients = vec![[0u8; SecretKeySize]; k as usize - 1];
+        for coefficient in coefficients.iter_mut() {
+            rng.fill_bytes(coefficient);
+        }
+
+        let shares = (1..=n)
+            .map(|x| {
+                // Horner's rule, from the highest degree down to the seed.
+                let mut value = [0u8; SecretKeySize];
+                for coefficient in coefficients.iter().rev() {
+                    for (y, c) in value.iter_mut().zip(coefficient.iter()) {
+                        *y = gf_mul(*y, x) ^ c;
+                    }
+                }
+                for (y, s) in value.iter_mut().zip(self.0.iter()) {
+                    *y = gf_mul(*y, x) ^ s;
+                }
+                Share {
+                    index: x,
+                    threshold: k,
+                    public_key,
+                    value,
+                }
+            })
+            .collect();
+        for coefficient in coefficients.iter_mut() {
+            coefficient.zeroize();
+        }
+        shares
+    }
+
+    /// Recovers a `SecretKey` from the shares of `split`, in any order.
+    ///
+    /// Returns `Err(Error::NotEnoughShares)` if fewer shares than the
+    /// threshold are given, `Err(Error::DuplicateShare)` if two shares
+    /// have the same index, and `Err(Error::InvalidShare)` if the shares
+    /// come from different splits or the recovered key isn't the one
+    /// they were split from, which a corrupted share causes.
+    pub fn combine(shares: &[Share]) -> Result<SecretKey, Error> {
+        let first = shares.first().ok_or(Error::NotEnoughShares {
+            threshold: 1,
+            got: 0,
+        })?;
+        for (i, share) in shares.iter().enumerate() {
+            if share.threshold != first.threshold || share.public_key != first.public_key {
+                return Err(Error::InvalidShare);
+            }
+            if shares[..i].iter().any(|other| other.index == share.index) {
+                return Err(Error::DuplicateShare {
+                    index: share.index as usize,
+                });
+            }
+        }
+        if shares.len() < first.threshold as usize {
+            return Err(Error::NotEnoughShares {
+                threshold: first.threshold as usize,
+                got: shares.len(),
+            });
+        }
+
+        // Lagrange interpolation at 0, over every share given, so that a
+        // corrupted one can't go unnoticed.
+        let mut secret = SecretKey([0u8; SecretKeySize]);
+        for share in shares {
+            let mut numerator = 1u8;
+            let mut denominator = 1u8;
+            for other in shares.iter().filter(|other| other.index != share.index) {
+                numerator = gf_mul(numerator, other.index);
+                denominator = gf_mul(denominator, other.index ^ share.index);
+            }
+            let basis = gf_mul(numerator, gf_inv(denominator));
+            for (s, y) in secret.0.iter_mut().zip(share.value.iter()) {
+                *s ^= gf_mul(basis, *y);
+            }
+        }
+        if PublicKey::from_secret(&secret) != first.public_key {
+            return Err(Error::InvalidShare);
+        }
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use crate::keypair::*;
+
+    // RFC 8032, TEST 2.
+    const SECRET_KEY: &str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+
+    fn secret_key() -> SecretKey {
+        SecretKey::from_bytes(&hex::decode(SECRET_KEY).unwrap()).unwrap()
+    }
+
+    // FIPS 197, section 4.2: {57} * {83} = {c1} and {57} * {13} = {fe}.
+    #[test]
+    fn gf256_arithmetic() {
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+            assert_eq!(gf_mul(a, 1), a);
+            assert_eq!(gf_mul(a, 0), 0);
+        }
+    }
+
+    #[test]
+    fn split_and_combine() {
+        let secret = secret_key();
+        let message = b"disaster recovery";
+        let signature = Keypair::from_secret(secret_key()).sign(message);
+        let mut rng = rand::thread_rng();
+
+        for &(k, n) in [
+            (1, 1),
+            (1, 3),
+            (2, 2),
+            (2, 3),
+            (3, 5),
+            (5, 5),
+            (4, 10),
+            (2, 255),
+        ]
+        .iter()
+        {
+            let shares = secret.split(n, k, &mut rng);
+            assert_eq!(shares.len(), n as usize);
+            for (i, share) in shares.iter().enumerate() {
+                assert_eq!(share.index() as usize, i + 1);
+                assert_eq!(share.threshold(), k);
+                assert!(*share.public_key() == PublicKey::from_secret(&secret));
+            }
+
+            // Every window of k shares, forwards and backwards, and all n.
+            let mut subsets: Vec<Vec<Share>> = (0..=(n - k) as usize)
+                .map(|start| shares[start..start + k as usize].to_vec())
+                .collect();
+            subsets.push(shares.iter().rev().take(k as usize).cloned().collect());
+            subsets.push(shares.clone());
+            for subset in subsets.iter() {
+                let recovered = SecretKey::combine(subset).unwrap();
+                assert!(recovered == secret);
+                assert_eq!(
+                    Keypair::from_secret(recovered).sign(message).as_bytes()[..],
+                    signature.as_bytes()[..]
+                );
+            }
+
+            // Through bytes.
+            let decoded: Vec<Share> = shares
+                .iter()
+                .map(|share| Share::from_bytes(&share.to_bytes()).unwrap())
+                .collect();
+            assert!(SecretKey::combine(&decoded[..k as usize]).unwrap() == secret);
+        }
+    }
+
+    #[test]
+    fn fresh_randomness_per_split() {
+        let secret = secret_key();
+        let mut rng = rand::thread_rng();
+        let a = secret.split(3, 2, &mut rng);
+        let b = secret.split(3, 2, &mut rng);
+        assert_ne!(a[0].value, b[0].value);
+        // With k = 1 the polynomial is the constant seed.
+        let shares = secret.split(2, 1, &mut rng);
+        assert_eq!(shares[1].value, secret.0);
+    }
+
+    #[test]
+    fn combine_rejects_bad_share_sets() {
+        let secret = secret_key();
+        let mut rng = rand::thread_rng();
+        let shares = secret.split(5, 3, &mut rng);
+
+        assert_eq!(
+            SecretKey::combine(&[]).err(),
+            Some(Error::NotEnoughShares {
+                threshold: 1,
+                got: 0
+            })
+        );
+        assert_eq!(
+            SecretKey::combine(&shares[..2]).err(),
+            Some(Error::NotEnoughShares {
+                threshold: 3,
+                got: 2
+            })
+        );
+        let duplicated = [shares[0].clone(), shares[3].clone(), shares[0].clone()];
+        assert_eq!(
+            SecretKey::combine(&duplicated).err(),
+            Some(Error::DuplicateShare { index: 1 })
+        );
+
+        // A share of another split of the same key.
+        let other = secret.split(5, 3, &mut rng);
+        let mixed = [shares[0].clone(), shares[1].clone(), other[2].clone()];
+        assert_eq!(SecretKey::combine(&mixed).err(), Some(Error::InvalidShare));
+
+        // A share of another key.
+        let foreign = SecretKey::from_array([7; 32]).split(5, 3, &mut rng);
+        let mixed = [shares[0].clone(), shares[1].clone(), foreign[2].clone()];
+        assert_eq!(SecretKey::combine(&mixed).err(), Some(Error::InvalidShare));
+
+        // A share with another threshold.
+        let other = secret.split(5, 2, &mut rng);
+        let mixed = [shares[0].clone(), shares[1].clone(), other[2].clone()];
+        assert_eq!(SecretKey::combine(&mixed).err(), Some(Error::InvalidShare));
+    }
+
+    #[test]
+    fn corrupted_shares_are_detected() {
+        let secret = secret_key();
+        let shares = secret.split(4, 3, &mut rand::thread_rng());
+        let bytes = shares[0].to_bytes();
+
+        // Any flipped bit fails the checksum or the public key.
+        for i in 0..SHARE_LENGTH {
+            let mut corrupted = bytes;
+            corrupted[i] ^= 0x10;
+            assert_eq!(
+                Share::from_bytes(&corrupted).err(),
+                Some(Error::InvalidShare)
+            );
+        }
+        assert_eq!(
+            Share::from_bytes(&bytes[..SHARE_LENGTH - 1]).err(),
+            Some(Error::Truncated {
+                offset: SHARE_LENGTH - CHECKSUM_SIZE
+            })
+        );
+        let mut long = bytes.to_vec();
+        long.push(0);
+        assert_eq!(
+            Share::from_bytes(&long).err(),
+            Some(Error::TrailingBytes {
+                consumed: SHARE_LENGTH,
+                total: SHARE_LENGTH + 1
+            })
+        );
+
+        // A corrupted value that got past the checksum.
+        let mut corrupted = shares[..3].to_vec();
+        corrupted[1].value[17] ^= 1;
+        assert_eq!(
+            SecretKey::combine(&corrupted).err(),
+            Some(Error::InvalidShare)
+        );
+        // An extra share is used too.
+        let mut corrupted = shares.clone();
+        corrupted[3].value[0] ^= 1;
+        assert_eq!(
+            SecretKey::combine(&corrupted).err(),
+            Some(Error::InvalidShare)
+        );
+        assert!(SecretKey::combine(&corrupted[..3]).unwrap() == secret);
+    }
+
+    #[test]
+    fn share_debug_hides_value() {
+        let shares = secret_key().split(2, 2, &mut rand::thread_rng());
+        let debug = format!("{:?}", shares[0]);
+        assert!(debug.starts_with("Share { index: 1, threshold: 2, public_key: "));
+        assert!(!debug.contains(&hex::encode(&shares[0].value[..])));
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_rejects_threshold_above_n() {
+        secret_key().split(2, 3, &mut rand::thread_rng());
+    }
+}
@@ -5,52 +5,104 @@
 
 #![allow(non_snake_case)]
 
+use crate::b16;
+use crate::b64;
 use crate::constants::*;
 use crate::errors::*;
+use crate::verify::check_lt_l;
+
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+use subtle::{Choice, ConstantTimeEq};
 
 /// The Ed25519 signature.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/signature.rs")]
+/// ```
+#[derive(Copy, Clone, Debug)]
+#[repr(transparent)]
 pub struct Signature(pub(crate) [u8; SignatureSize]);
 
 impl Signature {
+    /// Borrows the 64-byte encoding of `Signature`.
+    ///
+    /// Returns a reference to the 64-byte array `[u8; 64]`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Signature;
+    ///
+    /// fn main() {
+    ///     let signature = Signature::from_array([
+    ///         0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e,
+    ///         0x82, 0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65,
+    ///         0x22, 0x49, 0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e,
+    ///         0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24,
+    ///         0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+    ///     ]);
+    ///     let bytes: &[u8; 64] = signature.as_bytes();
+    /// }
+    /// ```
+    pub fn as_bytes(&self) -> &[u8; 64] {
+        &self.0
+    }
+
     /// Converts `Signature` into a 64-byte array.
     ///
-    /// Returns a 64-byte array `[u8; 64]`.
+    /// Returns a copy of the 64-byte array `[u8; 64]`.
     ///
     /// # Example
     ///
     /// ```rust
     /// extern crate ed25519_fun;
     ///
-    /// use ed25519_fun::{Keypair, Signature};
+    /// use ed25519_fun::Signature;
     ///
     /// fn main() {
-    ///     let message: &[u8] = b"";
-    ///     let keypair = Keypair::generate();
-    ///     let signature: Signature = keypair.sign(message);
-    ///     let bytes: [u8; 64] = signature.as_bytes();
+    ///     let signature = Signature::from_array([
+    ///         0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e,
+    ///         0x82, 0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65,
+    ///         0x22, 0x49, 0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e,
+    ///         0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24,
+    ///         0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+    ///     ]);
+    ///     let bytes: [u8; 64] = signature.to_bytes();
     /// }
     /// ```
-    pub fn as_bytes(&self) -> [u8; 64] {
+    pub fn to_bytes(&self) -> [u8; 64] {
         self.0
     }
 
     /// Constructs `Signature` from a slice.
     ///
-    /// Returns `Ok(Signature)` if `bytes` is 64 bytes long and `Err` otherwise.
+    /// Returns `Ok(Signature)` if `bytes` is 64 bytes long and
+    /// `Err(Error::InvalidSignatureLength)`, with both lengths, otherwise.
     ///
     /// # Example
     ///
     /// ```rust
     /// extern crate ed25519_fun;
     ///
-    /// use ed25519_fun::{Keypair, Signature};
+    /// use ed25519_fun::Signature;
     ///
     /// fn main() {
-    ///     let message: &[u8] = b"";
-    ///     let keypair = Keypair::generate();
-    ///     let signature: Signature = keypair.sign(message);
-    ///     let bytes: [u8; 64] = signature.as_bytes();
+    ///     let signature = Signature::from_bytes(&[
+    ///         0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e,
+    ///         0x82, 0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65,
+    ///         0x22, 0x49, 0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e,
+    ///         0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24,
+    ///         0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+    ///     ])
+    ///     .unwrap();
+    ///     let bytes: [u8; 64] = signature.to_bytes();
     ///     let signature_from_bytes = Signature::from_bytes(&bytes);
     /// }
     /// ```
@@ -58,12 +110,271 @@ impl Signature {
         let mut signature = [0u8; SignatureSize];
 
         if bytes.len() != SignatureSize {
-            return Err(Error::InvalidSignatureLength);
+            return Err(Error::InvalidSignatureLength {
+                expected: SignatureSize,
+                got: bytes.len(),
+            });
         }
 
         signature.copy_from_slice(bytes);
         Ok(Signature(signature))
     }
+
+    /// Constructs `Signature` from an array. Like `from_bytes`, it doesn't
+    /// check that R is a curve point nor that s is reduced: `verify` does.
+    ///
+    /// Returns the `Signature`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Signature;
+    ///
+    /// fn main() {
+    ///     let signature = Signature::from_array([0x5a; 64]);
+    ///     let bytes: [u8; 64] = signature.into();
+    ///     assert_eq!(Signature::from(bytes), signature);
+    /// }
+    /// ```
+    pub fn from_array(bytes: [u8; SignatureSize]) -> Self {
+        Signature(bytes)
+    }
+
+    /// Constructs `Signature` from its point R and its scalar s.
+    ///
+    /// Returns `Ok(Signature)` if `s` is reduced modulo L and
+    /// `Err(Error::NonCanonicalScalar)` otherwise. As with `from_bytes`,
+    /// R is left to `verify`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Error, Signature};
+    ///
+    /// fn main() {
+    ///     let signature = Signature::from_components([0x5a; 32], [0x01; 32]).unwrap();
+    ///     assert_eq!(signature.r_bytes(), &[0x5a; 32]);
+    ///     assert_eq!(signature.to_components(), ([0x5a; 32], [0x01; 32]));
+    ///     assert_eq!(
+    ///         Signature::from_components([0x5a; 32], [0xff; 32]),
+    ///         Err(Error::NonCanonicalScalar)
+    ///     );
+    /// }
+    /// ```
+    pub fn from_components(r: [u8; 32], s: [u8; 32]) -> Result<Self, Error> {
+        if !check_lt_l(&s) {
+            return Err(Error::NonCanonicalScalar);
+        }
+        let mut signature = Signature([0u8; SignatureSize]);
+        signature.0[..32].copy_from_slice(&r);
+        signature.0[32..].copy_from_slice(&s);
+        Ok(signature)
+    }
+
+    /// Borrows the encoding of the point R: the first 32 bytes.
+    ///
+    /// Returns a reference to the 32-byte array `[u8; 32]`.
+    pub fn r_bytes(&self) -> &[u8; 32] {
+        <&[u8; 32]>::try_from(&self.0[..32]).unwrap()
+    }
+
+    /// Borrows the encoding of the scalar s: the last 32 bytes.
+    ///
+    /// Returns a reference to the 32-byte array `[u8; 32]`.
+    pub fn s_bytes(&self) -> &[u8; 32] {
+        <&[u8; 32]>::try_from(&self.0[32..]).unwrap()
+    }
+
+    /// Splits `Signature` into its point R and its scalar s.
+    ///
+    /// Returns copies of R and s, in that order.
+    pub fn to_components(&self) -> ([u8; 32], [u8; 32]) {
+        (*self.r_bytes(), *self.s_bytes())
+    }
+
+    /// Converts `Signature` into unpadded base64url (RFC 4648, section 5).
+    ///
+    /// Returns the 86-character string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Signature;
+    ///
+    /// fn main() {
+    ///     let signature = Signature::from_array([0xfb; 64]);
+    ///     let encoded = signature.to_base64();
+    ///     assert!(encoded.starts_with("-_v7"));
+    ///     assert_eq!(Signature::from_base64(&encoded), Ok(signature));
+    /// }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn to_base64(&self) -> String {
+        b64::encode(b64::URL_SAFE_NO_PAD, &self.0)
+    }
+
+    /// Constructs `Signature` from unpadded base64url or from padded
+    /// standard base64, the encodings other tools most often ship. `s` is
+    /// read as standard base64 if it is padded or holds a `+` or `/`, and
+    /// as base64url otherwise.
+    ///
+    /// Returns `Ok(Signature)` if `s` is the encoding of 64 bytes,
+    /// `Err(Error::InvalidSignatureEncoding)` if it decodes to any other
+    /// length and `Err(Error::InvalidBase64)` if it is malformed.
+    pub fn from_base64(s: &str) -> Result<Self, Error> {
+        let variant = if s.ends_with('=') || s.contains(['+', '/']) {
+            b64::STANDARD
+        } else {
+            b64::URL_SAFE_NO_PAD
+        };
+        let mut signature = [0u8; SignatureSize];
+        match b64::decode_to_slice(variant, s.as_bytes(), &mut signature) {
+            Ok(len) if len == SignatureSize => Ok(Signature(signature)),
+            Ok(_) | Err(Error::BufferTooSmall) => Err(Error::InvalidSignatureEncoding),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Converts `Signature` into lowercase hex.
+    ///
+    /// Returns the 128-character hex string.
+    #[cfg(feature = "std")]
+    pub fn to_hex(&self) -> String {
+        b16::encode(&self.0)
+    }
+
+    /// Constructs `Signature` from hex, in either case.
+    ///
+    /// Returns `Ok(Signature)` if `s` is the hex of 64 bytes and
+    /// `Err(Error::InvalidSignatureEncoding)` otherwise.
+    pub fn from_hex(s: &str) -> Result<Self, Error> {
+        let mut signature = [0u8; SignatureSize];
+        b16::decode_to_slice(s, &mut signature).map_err(|_| Error::InvalidSignatureEncoding)?;
+        Ok(Signature(signature))
+    }
+}
+
+/// Borrows the 64-byte encoding.
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Constructs `Signature` as `from_bytes` does.
+impl TryFrom<&[u8]> for Signature {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+        Signature::from_bytes(bytes)
+    }
+}
+
+/// Constructs `Signature` as `from_bytes` does.
+impl TryFrom<&[u8; SignatureSize]> for Signature {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8; SignatureSize]) -> Result<Self, Error> {
+        Signature::from_bytes(bytes)
+    }
+}
+
+/// Constructs `Signature` as `from_array` does.
+impl From<[u8; SignatureSize]> for Signature {
+    fn from(bytes: [u8; SignatureSize]) -> Self {
+        Signature::from_array(bytes)
+    }
+}
+
+/// Returns the 64-byte encoding.
+impl From<Signature> for [u8; SignatureSize] {
+    fn from(signature: Signature) -> Self {
+        signature.0
+    }
+}
+
+impl ConstantTimeEq for Signature {
+    /// Determines if two signatures are equal in constant time.
+    fn ct_eq(&self, other: &Signature) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl Eq for Signature {}
+
+/// Compares in constant time, through `ct_eq`.
+impl PartialEq for Signature {
+    fn eq(&self, other: &Signature) -> bool {
+        self.ct_eq(other).unwrap_u8() == 1u8
+    }
+}
+
+/// Orders signatures lexicographically by their 64-byte encoding, for
+/// canonical sorting. The order has no cryptographic meaning.
+impl Ord for Signature {
+    fn cmp(&self, other: &Signature) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl PartialOrd for Signature {
+    fn partial_cmp(&self, other: &Signature) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Formats `Signature` as unpadded base64url, as `to_base64` does.
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = [0u8; Base64SignatureSize];
+        let len = b64::encode_to_slice(b64::URL_SAFE_NO_PAD, &self.0, &mut out)
+            .map_err(|_| fmt::Error)?;
+        f.write_str(core::str::from_utf8(&out[..len]).map_err(|_| fmt::Error)?)
+    }
+}
+
+/// Formats `Signature` as lowercase hex. `#` prefixes `0x`, and width,
+/// fill and the `0` flag pad as for integers.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::Signature;
+///
+/// fn main() {
+///     let signature = Signature::from_array([0xab; 64]);
+///     assert_eq!(format!("{:x}", signature), "ab".repeat(64));
+///     assert_eq!(format!("{:#X}", signature), format!("0x{}", "AB".repeat(64)));
+/// }
+/// ```
+impl fmt::LowerHex for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        b16::write_integral(f, &self.0, false)
+    }
+}
+
+/// Formats `Signature` as uppercase hex, as `LowerHex` does otherwise.
+impl fmt::UpperHex for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        b16::write_integral(f, &self.0, true)
+    }
+}
+
+/// Parses `Signature` from base64, as `from_base64` does.
+impl FromStr for Signature {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Signature::from_base64(s)
+    }
 }
 
 #[cfg(test)]
@@ -77,6 +388,229 @@ mod tests {
         let sig_bytes = hex::decode("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap();
         let sig = Signature::from_bytes(&sig_bytes).unwrap();
         let bytes = sig.as_bytes();
-        assert!(bytes == sig_bytes[..]);
+        assert!(bytes[..] == sig_bytes[..]);
+        assert!(sig.to_bytes() == *bytes);
+    }
+
+    #[test]
+    fn as_ref_try_from_signature() {
+        let bytes = hex::decode("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap();
+        let sig = Signature::try_from(&bytes[..]).unwrap();
+        let mut array = [0u8; SignatureSize];
+        array.copy_from_slice(&bytes);
+        assert_eq!(Signature::try_from(&array), Ok(sig));
+        assert_eq!(sig.as_ref(), &bytes[..]);
+
+        for len in [0, 32, 63, 65].iter() {
+            assert_eq!(
+                Signature::try_from(&vec![0u8; *len][..]),
+                Err(Error::InvalidSignatureLength {
+                    expected: SIGNATURE_LENGTH,
+                    got: *len
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn from_array_signature() {
+        let bytes = [0xe5u8; SignatureSize];
+        let sig = Signature::from_array(bytes);
+        assert_eq!(Signature::from_bytes(&bytes), Ok(sig));
+        assert_eq!(Signature::from(bytes), sig);
+        assert_eq!(<[u8; SignatureSize]>::from(sig), bytes);
+    }
+
+    #[test]
+    fn components_signature() {
+        // RFC 8032, TEST 1.
+        let bytes = hex::decode("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap();
+        let sig = Signature::from_bytes(&bytes).unwrap();
+        assert!(sig.r_bytes()[..] == bytes[..32]);
+        assert!(sig.s_bytes()[..] == bytes[32..]);
+        let (r, s) = sig.to_components();
+        assert_eq!(Signature::from_components(r, s), Ok(sig));
+
+        // L, the smallest non-canonical s, and L - 1, the largest
+        // canonical one.
+        let mut l = [0u8; 32];
+        l.copy_from_slice(
+            &hex::decode("edd3f55c1a631258d69cf7a2def9de1400000000000000000000000000000010")
+                .unwrap(),
+        );
+        assert_eq!(
+            Signature::from_components(r, l),
+            Err(Error::NonCanonicalScalar)
+        );
+        assert_eq!(
+            Signature::from_components(r, [0xff; 32]),
+            Err(Error::NonCanonicalScalar)
+        );
+        l[0] -= 1;
+        assert_eq!(Signature::from_components(r, l).unwrap().s_bytes(), &l);
+    }
+
+    #[test]
+    fn eq_signature() {
+        let sig = Signature::from_hex("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap();
+        let same = Signature::from_bytes(sig.as_bytes()).unwrap();
+        assert_eq!(sig, same);
+        assert_eq!(sig.ct_eq(&same).unwrap_u8(), 1);
+
+        // Every single-bit flip is a different signature.
+        for bit in 0..8 * SignatureSize {
+            let mut flipped = sig;
+            flipped.0[bit / 8] ^= 1 << (bit % 8);
+            assert_ne!(sig, flipped);
+            assert_eq!(sig.ct_eq(&flipped).unwrap_u8(), 0);
+        }
+    }
+
+    #[test]
+    fn ord_signature() {
+        use rand::seq::SliceRandom;
+        use rand::{thread_rng, RngCore};
+
+        let mut rng = thread_rng();
+        let mut signatures: Vec<Signature> = (0..64)
+            .map(|i| {
+                let mut bytes = [0u8; SignatureSize];
+                rng.fill_bytes(&mut bytes);
+                // R shared across signatures, s differing late or not at all.
+                bytes[..62].copy_from_slice(&[0xa5; 62]);
+                bytes[62] = i % 4;
+                if i % 8 == 0 {
+                    bytes[63] = 0;
+                }
+                Signature(bytes)
+            })
+            .collect();
+        signatures.shuffle(&mut rng);
+        signatures.sort();
+        for pair in signatures.windows(2) {
+            assert!(pair[0].0[..] <= pair[1].0[..]);
+        }
+
+        for a in signatures.iter() {
+            for b in signatures.iter() {
+                assert_eq!(a.cmp(b), a.0.cmp(&b.0));
+                assert_eq!(a.cmp(b) == Ordering::Equal, a == b);
+                assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hex_string_signature() {
+        // RFC 8032, TEST 1 and TEST 2.
+        for hex in [
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+        ]
+        .iter()
+        {
+            let sig = Signature::from_hex(hex).unwrap();
+            assert!(sig.as_bytes()[..] == hex::decode(hex).unwrap()[..]);
+            assert_eq!(sig.to_hex(), *hex);
+            assert_eq!(Signature::from_hex(&hex.to_uppercase()), Ok(sig));
+        }
+
+        assert_eq!(
+            Signature::from_hex("e556"),
+            Err(Error::InvalidSignatureEncoding)
+        );
+        assert_eq!(
+            Signature::from_hex("e5564"),
+            Err(Error::InvalidSignatureEncoding)
+        );
+        assert_eq!(
+            Signature::from_hex("g5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"),
+            Err(Error::InvalidSignatureEncoding)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn base64_string_signature() {
+        // RFC 8032, TEST 1 and TEST 2, with the output of GNU coreutils 9.1
+        // `base64 -w0` and `basenc --base64url -w0` (padding trimmed).
+        let vectors = [
+            (
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b",
+                "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc+bRr0lv18FlbviRlUUFDjnoQCw==",
+                "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw",
+            ),
+            (
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+                "kqAJqfDUyrhyDoILX2QlQKKye1QWUD+Ps3YiI+vbadoIWsHkPhWZbkWPNhPQ8R2MOHsurrQwKu6wDSkWErsMAA==",
+                "kqAJqfDUyrhyDoILX2QlQKKye1QWUD-Ps3YiI-vbadoIWsHkPhWZbkWPNhPQ8R2MOHsurrQwKu6wDSkWErsMAA",
+            ),
+        ];
+        for (hex, standard, url_safe) in vectors.iter() {
+            let sig = Signature::from_hex(hex).unwrap();
+            assert_eq!(sig.to_base64(), *url_safe);
+            assert_eq!(sig.to_string(), *url_safe);
+            assert_eq!(Signature::from_base64(url_safe), Ok(sig));
+            assert_eq!(Signature::from_base64(standard), Ok(sig));
+            assert_eq!(url_safe.parse(), Ok(sig));
+            assert_eq!(standard.parse(), Ok(sig));
+        }
+
+        // Round trips of every byte value in every position.
+        for start in 0..=(256 - SignatureSize) {
+            let bytes: Vec<u8> = (start..start + SignatureSize).map(|b| b as u8).collect();
+            let sig = Signature::from_bytes(&bytes).unwrap();
+            assert_eq!(sig.to_string().len(), Base64SignatureSize);
+            assert_eq!(sig.to_string().parse(), Ok(sig));
+        }
+    }
+
+    #[test]
+    fn base64_string_signature_rejects() {
+        let url_safe = "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw";
+        let standard = "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc+bRr0lv18FlbviRlUUFDjnoQCw==";
+
+        // Well-formed base64 of the wrong length: 63, 65 and 0 bytes.
+        for bad in [
+            &url_safe[..84],
+            "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCwAA",
+            "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc+bRr0lv18FlbviRlUUFDjnoQCwA=",
+            "",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                Signature::from_base64(bad),
+                Err(Error::InvalidSignatureEncoding),
+                "{}",
+                bad
+            );
+        }
+
+        // Malformed: a foreign character, the two alphabets mixed (read as
+        // standard base64, which must be padded), and padding on the
+        // url-safe alphabet. Hex reads as base64url of 96 bytes.
+        let mixed = url_safe.replacen('5', "+", 1);
+        let padded_url_safe = format!("{}==", url_safe);
+        let foreign = standard.replacen('A', "!", 1);
+        let hex = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+        assert_eq!(
+            Signature::from_base64(&foreign),
+            Err(Error::InvalidBase64 { offset: 4 })
+        );
+        assert_eq!(
+            Signature::from_base64(&mixed),
+            Err(Error::InvalidBase64 { offset: 86 })
+        );
+        assert_eq!(
+            Signature::from_base64(&padded_url_safe),
+            Err(Error::InvalidBase64 { offset: 60 })
+        );
+        assert_eq!(
+            hex.parse::<Signature>(),
+            Err(Error::InvalidSignatureEncoding)
+        );
     }
 }
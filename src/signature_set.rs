@@ -0,0 +1,412 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Signatures of several independent keys over the same message, in one
+// canonical container: entries are kept sorted by public key, a key
+// signs at most once, and the encoding of a set is unique.
+
+#[cfg(any(feature = "rand", feature = "getrandom"))]
+use crate::batch::*;
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(all(feature = "observer", any(feature = "rand", feature = "getrandom")))]
+use crate::observer;
+use crate::public::*;
+use crate::reader::*;
+use crate::signature::*;
+use crate::writer::*;
+
+/// Size of one encoded entry: the public key, then the signature.
+const ENTRY_SIZE: usize = PublicKeySize + SignatureSize;
+
+/// `(PublicKey, Signature)` pairs over a single message, sorted by public
+/// key.
+///
+/// Encoded as `count || public_0 || signature_0 || ... || public_n ||
+/// signature_n`, where `count` is a 64-bit little-endian integer.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{Keypair, SignatureSet};
+///
+/// fn main() {
+///     let alice = Keypair::generate();
+///     let bob = Keypair::generate();
+///     let mut set = SignatureSet::new();
+///     set.add(*alice.public(), alice.sign(b"document")).unwrap();
+///     set.add(*bob.public(), bob.sign(b"document")).unwrap();
+///     assert!(set.verify_all(b"document").iter().all(|r| r.is_ok()));
+///
+///     let decoded = SignatureSet::from_bytes(&set.to_bytes()).unwrap();
+///     assert_eq!(decoded, set);
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SignatureSet {
+    entries: Vec<(PublicKey, Signature)>,
+}
+
+impl SignatureSet {
+    /// Creates an empty `SignatureSet`.
+    pub fn new() -> Self {
+        SignatureSet {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds the signature of `public`, keeping the entries sorted.
+    ///
+    /// Returns `Ok(())` if `public` isn't in the set yet and
+    /// `Err(Error::DuplicateSigner)`, leaving the set unchanged,
+    /// otherwise. The signature isn't checked: see `verify_all`.
+    pub fn add(&mut self, public: PublicKey, signature: Signature) -> Result<(), Error> {
+        match self.entries.binary_search_by(|(key, _)| key.cmp(&public)) {
+            Ok(_) => Err(Error::DuplicateSigner),
+            Err(index) => {
+                self.entries.insert(index, (public, signature));
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the signature of `public`, if it is in the set.
+    pub fn get(&self, public: &PublicKey) -> Option<&Signature> {
+        self.entries
+            .binary_search_by(|(key, _)| key.cmp(public))
+            .ok()
+            .map(|index| &self.entries[index].1)
+    }
+
+    /// Number of signers.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the set has no signers.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries, sorted by public key.
+    pub fn entries(&self) -> &[(PublicKey, Signature)] {
+        &self.entries
+    }
+
+    /// Verifies every signature over `message`: as one batch, with the
+    /// bisection of `verify_batch_identify`, when `rand` or `getrandom`
+    /// provides the coefficients, and one by one with `verify` otherwise.
+    ///
+    /// The batch equation is cofactored, as in `verify_batch`, so an
+    /// entry with a small-order component may verify here and not with
+    /// `verify`. Signatures made by signing get the same verdict either
+    /// way.
+    ///
+    /// Returns one result per entry, in the order of `entries`: `Ok(())`
+    /// if that signature is valid and `Err` otherwise.
+    pub fn verify_all(&self, message: &[u8]) -> Vec<Result<(), Error>> {
+        #[cfg(any(feature = "rand", feature = "getrandom"))]
+        {
+            let messages = vec![message; self.entries.len()];
+            let (public_keys, signatures): (Vec<PublicKey>, Vec<Signature>) =
+                self.entries.iter().copied().unzip();
+            let verdicts = identify_each(&messages, &signatures, &public_keys);
+            // The decoding failures were reported as the batch was
+            // prepared; the failures of the equation are reported here.
+            #[cfg(feature = "observer")]
+            public_keys
+                .iter()
+                .zip(&verdicts)
+                .filter(|(_, verdict)| **verdict == Err(Error::SignatureMismatch))
+                .for_each(|(public, verdict)| observer::verified(public, *verdict));
+            verdicts
+        }
+        #[cfg(not(any(feature = "rand", feature = "getrandom")))]
+        self.entries
+            .iter()
+            .map(|(public, signature)| public.verify(message, signature))
+            .collect()
+    }
+
+    /// Encodes `SignatureSet` as its entry count and entries.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        write_exact(8 + self.entries.len() * ENTRY_SIZE, |writer| {
+            self.write_entries(writer)
+        })
+        .expect("the buffer has the exact size of the set")
+    }
+
+    /// Decodes a `SignatureSet` encoded by `to_bytes`.
+    ///
+    /// Returns `Ok(SignatureSet)` if `bytes` is exactly a count and that
+    /// many entries, `Err(Error::DuplicateSigner)` if a public key
+    /// appears twice, `Err(Error::UnsortedSignatureSet)` if the entries
+    /// aren't sorted by public key and `Err` otherwise.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        parse_exact(bytes, SignatureSet::read_entries)
+    }
+
+    /// Returns the length of the encoding of `to_bytes`: 8 bytes, then
+    /// 96 per entry.
+    #[cfg(feature = "codec")]
+    pub fn encoded_len(&self) -> usize {
+        8 + self.entries.len() * ENTRY_SIZE
+    }
+
+    /// Writes `SignatureSet`, encoded as by `to_bytes`, to the start of
+    /// `out`.
+    ///
+    /// Returns the number of bytes written, or
+    /// `Err(Error::BufferTooSmall)`, with nothing written, if `out` is
+    /// shorter than `encoded_len`.
+    #[cfg(feature = "codec")]
+    pub fn encode_into(&self, out: &mut [u8]) -> Result<usize, Error> {
+        let len = self.encoded_len();
+        if out.len() < len {
+            return Err(Error::BufferTooSmall);
+        }
+        let mut writer = WireWriter::new(&mut out[..len]);
+        self.write_entries(&mut writer)?;
+        writer.finish()
+    }
+
+    /// Reads a `SignatureSet` encoded by `to_bytes` from the start of
+    /// `input`, leaving what follows it unread.
+    ///
+    /// Returns the set and the number of bytes consumed, or the error of
+    /// `from_bytes` for a set that is invalid or cut short.
+    #[cfg(feature = "codec")]
+    pub fn decode_from(input: &[u8]) -> Result<(Self, usize), Error> {
+        let mut reader = ExactReader::new(input);
+        let set = SignatureSet::read_entries(&mut reader)?;
+        Ok((set, input.len() - reader.remaining()))
+    }
+
+    /// Writes the entry count, then the entries.
+    fn write_entries(&self, writer: &mut WireWriter) -> Result<(), Error> {
+        writer.write_u64_le(self.entries.len() as u64)?;
+        for (public, signature) in self.entries.iter() {
+            writer.write_bytes(public.as_bytes())?;
+            writer.write_bytes(&signature.0)?;
+        }
+        Ok(())
+    }
+
+    /// Reads an entry count and that many entries, checking that they
+    /// are sorted and distinct.
+    fn read_entries(reader: &mut ExactReader) -> Result<Self, Error> {
+        let count = reader.read_u64_le()?;
+        let count = reader.declared_len(count)?;
+        if count > reader.remaining() / ENTRY_SIZE {
+            return Err(Error::Truncated { offset: 8 });
+        }
+        let mut entries: Vec<(PublicKey, Signature)> = Vec::with_capacity(count);
+        for _ in 0..count {
+            let public = PublicKey::from_bytes(reader.take(PublicKeySize)?)?;
+            let signature = Signature(reader.take_array()?);
+            if let Some((last, _)) = entries.last() {
+                if *last == public {
+                    return Err(Error::DuplicateSigner);
+                }
+                if *last > public {
+                    return Err(Error::UnsortedSignatureSet);
+                }
+            }
+            entries.push((public, signature));
+        }
+        Ok(SignatureSet { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    #[cfg(not(feature = "verify-only"))]
+    use crate::keypair::*;
+    #[cfg(not(feature = "verify-only"))]
+    use crate::secret::*;
+
+    // RFC 8032, tests 1 and 2, over their own messages: the public key of
+    // test 2 sorts first.
+    const PUBLIC_KEY_1: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const SIGNATURE_1: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+                               5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+    const PUBLIC_KEY_2: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const SIGNATURE_2: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+                               085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+    fn entry(public: &str, signature: &str) -> (PublicKey, Signature) {
+        (
+            PublicKey::from_bytes(&hex::decode(public).unwrap()).unwrap(),
+            Signature::from_bytes(&hex::decode(signature).unwrap()).unwrap(),
+        )
+    }
+
+    fn rfc8032_set() -> SignatureSet {
+        let mut set = SignatureSet::new();
+        let (public, signature) = entry(PUBLIC_KEY_1, SIGNATURE_1);
+        set.add(public, signature).unwrap();
+        let (public, signature) = entry(PUBLIC_KEY_2, SIGNATURE_2);
+        set.add(public, signature).unwrap();
+        set
+    }
+
+    #[test]
+    fn signature_set_sorted_and_rejects_duplicates() {
+        let mut set = rfc8032_set();
+        assert_eq!(set.len(), 2);
+        assert_eq!(hex::encode(set.entries()[0].0.as_bytes()), PUBLIC_KEY_2);
+        assert_eq!(hex::encode(set.entries()[1].0.as_bytes()), PUBLIC_KEY_1);
+
+        let (public, signature) = entry(PUBLIC_KEY_1, SIGNATURE_1);
+        assert_eq!(set.get(&public), Some(&signature));
+        // A second signature by the same key, even a different one.
+        let (_, other) = entry(PUBLIC_KEY_2, SIGNATURE_2);
+        assert_eq!(set.add(public, other), Err(Error::DuplicateSigner));
+        assert_eq!(set, rfc8032_set());
+    }
+
+    #[test]
+    fn signature_set_verify_all_reports_each_entry() {
+        // Each RFC 8032 signature is over its own message, so over
+        // either message one entry verifies and the other doesn't.
+        let set = rfc8032_set();
+        assert_eq!(
+            set.verify_all(&[0x72]),
+            vec![Ok(()), Err(Error::SignatureMismatch)]
+        );
+        assert_eq!(
+            set.verify_all(b""),
+            vec![Err(Error::SignatureMismatch), Ok(())]
+        );
+        assert!(SignatureSet::new().verify_all(b"").is_empty());
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn signature_set_tampered_entry_fails() {
+        let keypairs: Vec<Keypair> = (1..5)
+            .map(|i| Keypair::from_secret(SecretKey::from_array([i; 32])))
+            .collect();
+        let mut set = SignatureSet::new();
+        for keypair in keypairs.iter() {
+            set.add(*keypair.public(), keypair.sign(b"document"))
+                .unwrap();
+        }
+        assert!(set.verify_all(b"document").iter().all(|r| r.is_ok()));
+
+        let mut bytes = set.to_bytes();
+        // The signature of the third entry.
+        bytes[8 + 2 * ENTRY_SIZE + PublicKeySize] ^= 1;
+        let tampered = SignatureSet::from_bytes(&bytes).unwrap();
+        let results = tampered.verify_all(b"document");
+        assert!(results[2].is_err());
+        let each: Vec<_> = tampered
+            .entries()
+            .iter()
+            .map(|(public, signature)| public.verify(b"document", signature))
+            .collect();
+        assert_eq!(results, each);
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 3);
+    }
+
+    #[test]
+    fn signature_set_round_trip() {
+        let set = rfc8032_set();
+        let bytes = set.to_bytes();
+        assert_eq!(bytes.len(), 8 + 2 * ENTRY_SIZE);
+        assert_eq!(
+            hex::encode(&bytes),
+            format!(
+                "0200000000000000{}{}{}{}",
+                PUBLIC_KEY_2, SIGNATURE_2, PUBLIC_KEY_1, SIGNATURE_1
+            )
+        );
+        assert_eq!(SignatureSet::from_bytes(&bytes), Ok(set));
+
+        let empty = SignatureSet::new();
+        assert_eq!(hex::encode(empty.to_bytes()), "0000000000000000");
+        assert_eq!(SignatureSet::from_bytes(&empty.to_bytes()), Ok(empty));
+    }
+
+    // The codec and the byte methods agree on every prefix of a stream
+    // holding a set and more bytes, including the errors.
+    #[cfg(feature = "codec")]
+    #[test]
+    fn signature_set_codec_matches_byte_methods() {
+        let set = rfc8032_set();
+        let bytes = set.to_bytes();
+        let stream = [&bytes[..], &[0xff; 5][..]].concat();
+
+        for len in 0..stream.len() {
+            let input = &stream[..len];
+            match SignatureSet::decode_from(input) {
+                Ok((decoded, read)) => {
+                    assert_eq!(read, bytes.len());
+                    assert_eq!(read, decoded.encoded_len());
+                    assert_eq!(SignatureSet::from_bytes(&input[..read]), Ok(decoded));
+                }
+                Err(error) => {
+                    assert!(len < bytes.len());
+                    assert_eq!(SignatureSet::from_bytes(input), Err(error));
+                }
+            }
+        }
+        assert!(matches!(
+            SignatureSet::from_bytes(&stream),
+            Err(Error::TrailingBytes { .. })
+        ));
+
+        let mut out = vec![0u8; bytes.len() + 5];
+        assert_eq!(set.encode_into(&mut out), Ok(bytes.len()));
+        assert_eq!(out[..bytes.len()], bytes[..]);
+        assert_eq!(
+            set.encode_into(&mut out[..bytes.len() - 1]),
+            Err(Error::BufferTooSmall)
+        );
+        // Nothing was written on error.
+        let mut untouched = vec![0u8; bytes.len() - 1];
+        assert!(set.encode_into(&mut untouched).is_err());
+        assert!(untouched.iter().all(|&b| b == 0));
+
+        let empty = SignatureSet::new();
+        assert_eq!(empty.encode_into(&mut out), Ok(8));
+        assert_eq!(SignatureSet::decode_from(&out[..8]), Ok((empty, 8)));
+    }
+
+    #[test]
+    fn signature_set_rejects_non_canonical_bytes() {
+        let bytes = rfc8032_set().to_bytes();
+        let first = &bytes[8..8 + ENTRY_SIZE];
+        let second = &bytes[8 + ENTRY_SIZE..];
+
+        let swapped = [&bytes[..8], second, first].concat();
+        assert_eq!(
+            SignatureSet::from_bytes(&swapped),
+            Err(Error::UnsortedSignatureSet)
+        );
+        let repeated = [&bytes[..8], first, first].concat();
+        assert_eq!(
+            SignatureSet::from_bytes(&repeated),
+            Err(Error::DuplicateSigner)
+        );
+
+        // A count that doesn't match the entries.
+        let mut short = bytes.clone();
+        short[0] = 3;
+        assert!(SignatureSet::from_bytes(&short).is_err());
+        let mut long = bytes.clone();
+        long[0] = 1;
+        assert!(SignatureSet::from_bytes(&long).is_err());
+        let mut huge = bytes.clone();
+        huge[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(SignatureSet::from_bytes(&huge).is_err());
+        assert!(SignatureSet::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(SignatureSet::from_bytes(&[]).is_err());
+    }
+}
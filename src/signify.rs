@@ -0,0 +1,300 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// OpenBSD signify public keys and signatures: an untrusted comment line
+// followed by the base64 of the `Ed` algorithm marker, the 8-byte key
+// number and the key or signature.
+// See https://man.openbsd.org/signify.
+
+use crate::b64;
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::public::*;
+use crate::reader::*;
+use crate::signature::*;
+use crate::writer::*;
+
+const ALGORITHM: [u8; 2] = *b"Ed";
+
+const UNTRUSTED_COMMENT: &str = "untrusted comment: ";
+
+/// The signify key number.
+pub type KeyNum = [u8; 8];
+
+/// A signify public key.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/signify.rs")]
+/// ```
+#[derive(Copy, Clone)]
+pub struct SignifyPublicKey {
+    pub key_num: KeyNum,
+    pub public: PublicKey,
+}
+
+/// A signify signature. The comment is stored without its
+/// `untrusted comment: ` prefix.
+///
+/// # Example
+///
+/// ```rust
+#[doc = include_str!("../tests/snippets/signify.rs")]
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignifySignature {
+    pub key_num: KeyNum,
+    pub signature: Signature,
+    pub comment: String,
+}
+
+/// Splits a signify file into its comment and decoded base64 lines.
+fn decode_file(file: &str, malformed: Error) -> Result<(String, Vec<u8>), Error> {
+    let mut lines = file.lines();
+    let comment = lines
+        .next()
+        .and_then(|line| line.strip_prefix(UNTRUSTED_COMMENT))
+        .ok_or(malformed)?;
+    let bytes =
+        b64::decode(b64::STANDARD, lines.next().ok_or(malformed)?).map_err(|_| malformed)?;
+    if lines.next().is_some() {
+        return Err(malformed);
+    }
+    Ok((comment.to_string(), bytes))
+}
+
+pub(crate) fn encode_file(
+    comment: &str,
+    key_num: &KeyNum,
+    data: &[u8],
+    malformed: Error,
+) -> Result<String, Error> {
+    if comment.contains('\n') || comment.contains('\r') {
+        return Err(malformed);
+    }
+    let bytes = write_exact(ALGORITHM.len() + key_num.len() + data.len(), |w| {
+        w.write_bytes(&ALGORITHM)?;
+        w.write_bytes(key_num)?;
+        w.write_bytes(data)
+    })?;
+    Ok(format!(
+        "{}{}\n{}\n",
+        UNTRUSTED_COMMENT,
+        comment,
+        b64::encode(b64::STANDARD, &bytes)
+    ))
+}
+
+impl SignifyPublicKey {
+    /// Constructs `SignifyPublicKey` from the contents of a signify public
+    /// key file.
+    ///
+    /// Returns `Ok(SignifyPublicKey)` if `file` is a signify Ed25519 public
+    /// key and `Err` otherwise.
+    pub fn decode(file: &str) -> Result<Self, Error> {
+        let (_, bytes) = decode_file(file, Error::InvalidSignifyKey)?;
+        parse_exact(&bytes, |reader| {
+            if reader.take_array::<2>()? != ALGORITHM {
+                return Err(Error::InvalidSignifyKey);
+            }
+            Ok(SignifyPublicKey {
+                key_num: reader.take_array::<8>()?,
+                public: PublicKey(reader.take_array::<PublicKeySize>()?),
+            })
+        })
+    }
+
+    /// Encodes `SignifyPublicKey` as the contents of a signify public key
+    /// file, with the comment `comment`.
+    ///
+    /// Returns `Err(Error::InvalidSignifyKey)` if `comment` spans several
+    /// lines.
+    pub fn encode(&self, comment: &str) -> Result<String, Error> {
+        encode_file(
+            comment,
+            &self.key_num,
+            &self.public.0,
+            Error::InvalidSignifyKey,
+        )
+    }
+}
+
+impl SignifySignature {
+    /// Constructs `SignifySignature` from the contents of a signify
+    /// signature file.
+    ///
+    /// Returns `Ok(SignifySignature)` if `file` is a signify Ed25519
+    /// signature and `Err` otherwise.
+    pub fn decode(file: &str) -> Result<Self, Error> {
+        let (comment, bytes) = decode_file(file, Error::InvalidSignifySignature)?;
+        parse_exact(&bytes, |reader| {
+            if reader.take_array::<2>()? != ALGORITHM {
+                return Err(Error::InvalidSignifySignature);
+            }
+            Ok(SignifySignature {
+                key_num: reader.take_array::<8>()?,
+                signature: Signature(reader.take_array::<SignatureSize>()?),
+                comment,
+            })
+        })
+    }
+
+    /// Encodes `SignifySignature` as the contents of a signify signature
+    /// file.
+    ///
+    /// Returns `Err(Error::InvalidSignifySignature)` if the comment spans
+    /// several lines.
+    pub fn encode(&self) -> Result<String, Error> {
+        encode_file(
+            &self.comment,
+            &self.key_num,
+            &self.signature.0,
+            Error::InvalidSignifySignature,
+        )
+    }
+}
+
+/// Signs `message` as `signify -S` does.
+///
+/// Returns the signature, with the comment `verify with <key_name>`.
+#[cfg(not(feature = "verify-only"))]
+pub fn sign_signify(
+    keypair: &Keypair,
+    key_num: KeyNum,
+    message: &[u8],
+    key_name: &str,
+) -> SignifySignature {
+    SignifySignature {
+        key_num,
+        signature: keypair.sign(message),
+        comment: format!("verify with {}", key_name),
+    }
+}
+
+/// Verifies the signify signature file `signature` of `message` with the
+/// public key file `public_key`.
+///
+/// Returns `Ok` if the signature verifies, `Err(Error::KeyIdMismatch)` if
+/// it was made with another key and `Err` otherwise.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::signify::verify_signify;
+///
+/// fn main() {
+///     let public_key = "untrusted comment: signify public key\n\
+///                       RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3\n";
+///     let signature = "untrusted comment: verify with key.pub\n\
+///                      RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=\n";
+///     assert!(verify_signify(public_key, signature, b"test").is_ok());
+/// }
+/// ```
+pub fn verify_signify(public_key: &str, signature: &str, message: &[u8]) -> Result<(), Error> {
+    let public_key = SignifyPublicKey::decode(public_key)?;
+    let signature = SignifySignature::decode(signature)?;
+    if signature.key_num != public_key.key_num {
+        return Err(Error::KeyIdMismatch);
+    }
+    public_key.public.verify(message, &signature.signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // signify and legacy minisign share the key and signature encodings.
+    // No signify CLI is available to the test suite, so this is a legacy
+    // ("Ed") signature of the file "test" produced by the minisign CLI
+    // (from the minisign-verify test suite), which signify verifies as is.
+    const PUBLIC_KEY: &str = "untrusted comment: minisign public key E7620F1842B4E81F
+RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3
+";
+    const SIGNATURE: &str = "untrusted comment: signature from minisign secret key
+RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=
+";
+
+    #[test]
+    fn signify_verifies_cli_signature() {
+        assert_eq!(verify_signify(PUBLIC_KEY, SIGNATURE, b"test"), Ok(()));
+        assert_eq!(
+            verify_signify(PUBLIC_KEY, SIGNATURE, b"Test"),
+            Err(Error::SignatureMismatch)
+        );
+
+        let public_key = SignifyPublicKey::decode(PUBLIC_KEY).unwrap();
+        assert_eq!(
+            public_key
+                .encode("minisign public key E7620F1842B4E81F")
+                .unwrap(),
+            PUBLIC_KEY
+        );
+        let signature = SignifySignature::decode(SIGNATURE).unwrap();
+        assert_eq!(signature.comment, "signature from minisign secret key");
+        assert_eq!(signature.encode().unwrap(), SIGNATURE);
+    }
+
+    #[test]
+    fn signify_key_mismatch_is_distinct() {
+        let mut signature = SignifySignature::decode(SIGNATURE).unwrap();
+        signature.key_num[7] ^= 1;
+        assert_eq!(
+            verify_signify(PUBLIC_KEY, &signature.encode().unwrap(), b"test"),
+            Err(Error::KeyIdMismatch)
+        );
+    }
+
+    #[test]
+    fn signify_rejects_malformed() {
+        // Missing comment, extra lines and a secret key marker.
+        assert_eq!(
+            SignifyPublicKey::decode(PUBLIC_KEY.lines().nth(1).unwrap()).err(),
+            Some(Error::InvalidSignifyKey)
+        );
+        assert_eq!(
+            SignifySignature::decode(&format!("{}\n{}", SIGNATURE, SIGNATURE)).err(),
+            Some(Error::InvalidSignifySignature)
+        );
+        assert_eq!(
+            SignifySignature::decode(&SIGNATURE.replace("\nRWQf", "\nRUQf")).err(),
+            Some(Error::InvalidSignifySignature)
+        );
+        // A key where a signature is expected, and the reverse.
+        assert_eq!(
+            SignifySignature::decode(PUBLIC_KEY).err(),
+            Some(Error::Truncated { offset: 10 })
+        );
+        assert_eq!(
+            SignifyPublicKey::decode(SIGNATURE).err(),
+            Some(Error::TrailingBytes {
+                consumed: 42,
+                total: 74
+            })
+        );
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn signify_sign_roundtrip() {
+        let keypair = Keypair::generate();
+        let public_key = SignifyPublicKey {
+            key_num: [1, 2, 3, 4, 5, 6, 7, 8],
+            public: keypair.public,
+        };
+        let signature = sign_signify(&keypair, public_key.key_num, b"release", "key.pub");
+        assert_eq!(signature.comment, "verify with key.pub");
+        assert_eq!(
+            verify_signify(
+                &public_key.encode("signify public key").unwrap(),
+                &signature.encode().unwrap(),
+                b"release"
+            ),
+            Ok(())
+        );
+    }
+}
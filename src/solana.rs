@@ -0,0 +1,148 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Solana keypair files: a JSON array of the 64 bytes of the secret key
+// followed by the public key, as written by `solana-keygen`.
+
+use crate::constants::*;
+use crate::errors::*;
+use crate::keypair::*;
+
+use zeroize::Zeroize;
+
+/// Parses a JSON array of exactly `KeypairSize` integers in 0..=255.
+fn parse_array(json: &str, bytes: &mut [u8; KeypairSize]) -> Result<(), Error> {
+    let inner = json
+        .trim()
+        .strip_prefix('[')
+        .and_then(|json| json.strip_suffix(']'))
+        .ok_or(Error::InvalidKeypair)?;
+
+    let mut count = 0;
+    for element in inner.split(',') {
+        let element = element.trim();
+        // JSON integers: digits only, no leading zeros.
+        if element.is_empty()
+            || !element.bytes().all(|c| c.is_ascii_digit())
+            || (element.len() > 1 && element.starts_with('0'))
+        {
+            return Err(Error::InvalidKeypair);
+        }
+        let byte = element.parse::<u8>().map_err(|_| Error::InvalidKeypair)?;
+        *bytes.get_mut(count).ok_or(Error::InvalidKeypair)? = byte;
+        count += 1;
+    }
+    if count != KeypairSize {
+        return Err(Error::InvalidKeypair);
+    }
+    Ok(())
+}
+
+impl Keypair {
+    /// Constructs `Keypair` from the contents of a Solana keypair file.
+    ///
+    /// Returns `Ok(Keypair)` if `json` is an array of 64 bytes whose last
+    /// 32 are the public key of the first 32, and
+    /// `Err(Error::InvalidKeypair)` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let json = keypair.to_solana_json();
+    ///     let decoded = Keypair::from_solana_json(&json).unwrap();
+    ///     assert_eq!(decoded.public().as_bytes(), keypair.public().as_bytes());
+    /// }
+    /// ```
+    pub fn from_solana_json(json: &str) -> Result<Self, Error> {
+        let mut bytes = [0u8; KeypairSize];
+        let parsed =
+            parse_array(json, &mut bytes).and_then(|_| Keypair::from_libsodium_bytes(&bytes));
+        bytes.zeroize();
+        parsed
+    }
+
+    /// Encodes `Keypair` as the contents of a Solana keypair file.
+    ///
+    /// Returns the JSON array of the secret and public keys.
+    pub fn to_solana_json(&self) -> String {
+        let mut bytes = self.to_bytes();
+        let mut json = String::with_capacity(4 * KeypairSize + 2);
+        json.push('[');
+        for (i, byte) in bytes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(&byte.to_string());
+        }
+        json.push(']');
+        bytes.zeroize();
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // The RFC 8032 test 1 key, laid out as `solana-keygen new` writes its
+    // files (no spaces, no trailing newline). No solana-keygen binary is
+    // available to the test suite, so the file was not exported by it.
+    const FIXTURE: &str = "[157,97,177,157,239,253,90,96,186,132,74,244,146,236,44,196,68,73,197,105,123,50,105,25,112,59,172,3,28,174,127,96,215,90,152,1,130,177,10,183,213,75,254,211,201,100,7,58,14,225,114,243,218,166,35,37,175,2,26,104,247,7,81,26]";
+    const PUBLIC: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+    #[test]
+    fn solana_json_fixture() {
+        let keypair = Keypair::from_solana_json(FIXTURE).unwrap();
+        assert_eq!(format!("{:x}", keypair.public), PUBLIC);
+        assert_eq!(keypair.to_solana_json(), FIXTURE);
+
+        // Whitespace, as pretty-printers produce.
+        let pretty = FIXTURE.replace(',', ",\n  ").replace('[', "[\n  ");
+        assert!(Keypair::from_solana_json(&pretty).is_ok());
+    }
+
+    #[test]
+    fn solana_json_roundtrip() {
+        let keypair = Keypair::generate();
+        let decoded = Keypair::from_solana_json(&keypair.to_solana_json()).unwrap();
+        assert_eq!(decoded.to_bytes()[..], keypair.to_bytes()[..]);
+    }
+
+    #[test]
+    fn solana_json_rejects_malformed() {
+        let elements: Vec<&str> = FIXTURE[1..FIXTURE.len() - 1].split(',').collect();
+        let cases = [
+            // Lengths.
+            format!("[{}]", elements[..63].join(",")),
+            format!("[{},0]", elements.join(",")),
+            "[]".to_string(),
+            // Out of range and non-integers.
+            FIXTURE.replacen("157", "256", 1),
+            FIXTURE.replacen("157", "-1", 1),
+            FIXTURE.replacen("157", "157.0", 1),
+            FIXTURE.replacen("157", "0157", 1),
+            FIXTURE.replacen("157", "\"157\"", 1),
+            FIXTURE.replacen(",", ",,", 1),
+            // Not an array.
+            FIXTURE[1..].to_string(),
+            format!("{{\"key\":{}}}", FIXTURE),
+            // The public key doesn't match the seed.
+            FIXTURE.replacen("26]", "27]", 1),
+        ];
+        for json in cases.iter() {
+            assert!(
+                matches!(Keypair::from_solana_json(json), Err(Error::InvalidKeypair)),
+                "{}",
+                json
+            );
+        }
+    }
+}
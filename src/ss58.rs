@@ -0,0 +1,216 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// SS58 addresses of Substrate chains: the base58 of a network prefix,
+// the 32-byte key and the first 2 bytes of the BLAKE2b-512 of "SS58PRE"
+// followed by both.
+//
+// Prefixes below 64 take one byte. Prefixes from 64 to 16383 take two,
+// with the first byte in 64..128:
+//
+//   0b01hhhhhh llllllhh   h: bits 2..8 then 0..2, l: bits 8..14
+//
+// Decoding refuses the two-byte form of a prefix below 64, so that each
+// address has one encoding.
+
+use crate::base58;
+use crate::constants::*;
+use crate::errors::*;
+use crate::public::*;
+
+use blake2::{Blake2b512, Digest};
+
+/// The highest prefix of the two-byte form.
+const MAX_PREFIX: u16 = 0x3fff;
+
+const CHECKSUM_SIZE: usize = 2;
+
+/// The base58 of the longest address: 256^36 < 58^50.
+const MAX_TEXT_LENGTH: usize = 50;
+
+/// The checksum of `payload`.
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_SIZE] {
+    let mut hasher = Blake2b512::new();
+    hasher.update(b"SS58PRE");
+    hasher.update(payload);
+    let hash = hasher.finalize();
+    [hash[0], hash[1]]
+}
+
+impl PublicKey {
+    /// Encodes `PublicKey` as the SS58 address of network `prefix`: 0 for
+    /// Polkadot, 2 for Kusama and 42 for generic Substrate chains.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is above 16383, the highest SS58 prefix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let address = "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu";
+    ///     let (prefix, public_key) = PublicKey::from_ss58(address).unwrap();
+    ///     assert_eq!(prefix, 42);
+    ///     assert_eq!(public_key.to_ss58(42), address);
+    /// }
+    /// ```
+    pub fn to_ss58(&self, prefix: u16) -> String {
+        assert!(prefix <= MAX_PREFIX, "SS58 prefix above 16383");
+        let mut payload = Vec::with_capacity(2 + PublicKeySize + CHECKSUM_SIZE);
+        if prefix < 64 {
+            payload.push(prefix as u8);
+        } else {
+            payload.push(0x40 | ((prefix & 0xfc) >> 2) as u8);
+            payload.push(((prefix >> 8) | ((prefix & 0x03) << 6)) as u8);
+        }
+        payload.extend_from_slice(&self.0);
+        let check = checksum(&payload);
+        payload.extend_from_slice(&check);
+        base58::encode(&payload)
+    }
+
+    /// Decodes an SS58 address.
+    ///
+    /// Returns `Ok((prefix, PublicKey))` on a valid address,
+    /// `Err(Error::InvalidBase58)` on a character outside the alphabet,
+    /// `Err(Error::ChecksumMismatch)` if the checksum is wrong and
+    /// `Err(Error::InvalidPublicKey)` if the address isn't of a 32-byte
+    /// key or its prefix isn't in the shortest form.
+    pub fn from_ss58(text: &str) -> Result<(u16, PublicKey), Error> {
+        if text.len() > MAX_TEXT_LENGTH {
+            return Err(Error::InvalidPublicKey);
+        }
+        let decoded = base58::decode(text).ok_or(Error::InvalidBase58)?;
+        let (prefix, prefix_len) = match decoded.first() {
+            Some(&first) if first < 64 => (first as u16, 1),
+            Some(&first) if first < 128 && decoded.len() > 1 => {
+                let second = decoded[1] as u16;
+                let lower = (((first as u16) << 2) | (second >> 6)) & 0xff;
+                let upper = second & 0x3f;
+                (lower | (upper << 8), 2)
+            }
+            _ => return Err(Error::InvalidPublicKey),
+        };
+        if prefix_len == 2 && prefix < 64 {
+            return Err(Error::InvalidPublicKey);
+        }
+        if decoded.len() != prefix_len + PublicKeySize + CHECKSUM_SIZE {
+            return Err(Error::InvalidPublicKey);
+        }
+        let (payload, check) = decoded.split_at(prefix_len + PublicKeySize);
+        if checksum(payload) != check {
+            return Err(Error::ChecksumMismatch);
+        }
+        let public = PublicKey::from_bytes(&payload[prefix_len..])?;
+        Ok((prefix, public))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // The ed25519 //Alice development key and its addresses as `subkey
+    // inspect --scheme ed25519 //Alice` prints them. No subkey binary is
+    // available to the test suite: these were checked against a Python
+    // SS58 encoder, which also produced the other prefixes.
+    const PUBLIC_KEY: &str = "88dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee";
+    const SUBSTRATE: &str = "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu";
+    const POLKADOT: &str = "146SvjUZXoMaemdeiecyxgALeYMm8ZWh1yrGo8RtpoPfe7WL";
+
+    fn public() -> PublicKey {
+        PublicKey::from_bytes(&hex::decode(PUBLIC_KEY).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn ss58_vectors() {
+        let cases: [(u16, &str); 6] = [
+            (0, POLKADOT),
+            (2, "FfmSiZNJP72xtSaXiP2iUhBwWeMEvmjPrxY2ViVkWaeChDC"),
+            (42, SUBSTRATE),
+            (64, "cEYfegp3eYHB5JvpA6PHRKxm2ow1u9w2QBBXEmFa9Sq5wPCfh"),
+            (255, "yGFpxQAY6YkHrWU2mkYymBThT9K4N4E2uzuUDNP6dPKeVcgU8"),
+            (16383, "yNYRWgfeVm5PeMAWzp6QFyETDrt4tWmhhtANV8ScbZC7DLpkv"),
+        ];
+        for &(prefix, address) in cases.iter() {
+            assert_eq!(public().to_ss58(prefix), address);
+            let (decoded_prefix, decoded) = PublicKey::from_ss58(address).unwrap();
+            assert_eq!(decoded_prefix, prefix);
+            assert_eq!(decoded, public());
+        }
+
+        // The sr25519 //Alice key, whose addresses are the most quoted.
+        let (prefix, alice) =
+            PublicKey::from_ss58("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5").unwrap();
+        assert_eq!(prefix, 0);
+        assert_eq!(
+            alice.to_string(),
+            "d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d"
+        );
+        assert_eq!(
+            alice.to_ss58(42),
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+        );
+    }
+
+    #[test]
+    fn ss58_rejects_invalid_addresses() {
+        let mut altered = SUBSTRATE.to_string();
+        altered.replace_range(10..11, "X");
+        assert_eq!(
+            PublicKey::from_ss58(&altered).err(),
+            Some(Error::ChecksumMismatch)
+        );
+        assert_eq!(
+            PublicKey::from_ss58(&SUBSTRATE.replace('9', "0")).err(),
+            Some(Error::InvalidBase58)
+        );
+
+        // The two-byte form of prefix 42.
+        let mut payload = vec![0x40 | (42 >> 2), (42 & 0x03) << 6];
+        payload.extend_from_slice(public().as_bytes());
+        let check = checksum(&payload);
+        payload.extend_from_slice(&check);
+        assert_eq!(
+            PublicKey::from_ss58(&base58::encode(&payload)).err(),
+            Some(Error::InvalidPublicKey)
+        );
+
+        // A 33-byte account, a first byte of 128 and above, and lengths.
+        let mut payload = vec![42];
+        payload.extend_from_slice(&[0x5a; 33]);
+        let check = checksum(&payload);
+        payload.extend_from_slice(&check);
+        let mut reserved = payload.clone();
+        reserved[0] = 128;
+        for text in [
+            base58::encode(&payload),
+            base58::encode(&reserved),
+            String::new(),
+            SUBSTRATE[..SUBSTRATE.len() - 4].to_string(),
+            "z".repeat(51),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                PublicKey::from_ss58(text).err(),
+                Some(Error::InvalidPublicKey),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "SS58 prefix above 16383")]
+    fn ss58_prefix_out_of_range() {
+        public().to_ss58(16384);
+    }
+}
@@ -0,0 +1,305 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Stellar strkeys (SEP-23): a version byte, the 32-byte key and the
+// CRC16-XModem of both, little-endian, in unpadded uppercase base32
+// (RFC 4648). 35 bytes are exactly 56 characters, so there are no spare
+// bits to check, and any other length, padding or lowercase is refused.
+//
+//   G   6 << 3    account ID (public key)
+//   S   18 << 3   seed (secret key)
+//
+// Seeds are secret, so the base32 codec and the checksum don't branch on
+// the data: characters are mapped with masks, as in b64.rs.
+
+use crate::errors::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+
+use zeroize::Zeroize;
+
+const ACCOUNT_ID: u8 = 6 << 3;
+#[cfg(not(feature = "verify-only"))]
+const SEED: u8 = 18 << 3;
+
+/// The version byte, the key and the checksum.
+const RAW_SIZE: usize = 1 + 32 + 2;
+
+/// The length of a strkey.
+const STRKEY_LENGTH: usize = RAW_SIZE * 8 / 5;
+
+/// 0xff if `a < b` and 0 otherwise, for `a` and `b` below 2^8.
+fn lt(a: u8, b: u8) -> u8 {
+    ((a as u16).wrapping_sub(b as u16) >> 8) as u8
+}
+
+/// CRC16-XModem: polynomial 0x1021, initial value 0.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = (crc << 1) ^ (0x1021 & 0u16.wrapping_sub(crc >> 15));
+        }
+    }
+    crc
+}
+
+/// Encodes `version` and `key` as a strkey.
+fn encode(version: u8, key: &[u8; 32]) -> String {
+    let mut raw = [0u8; RAW_SIZE];
+    raw[0] = version;
+    raw[1..33].copy_from_slice(key);
+    let crc = crc16(&raw[..33]);
+    raw[33..].copy_from_slice(&crc.to_le_bytes());
+
+    let mut text = String::with_capacity(STRKEY_LENGTH);
+    for chunk in raw.chunks(5) {
+        let mut bits = 0u64;
+        for &byte in chunk {
+            bits = (bits << 8) | byte as u64;
+        }
+        for i in 0..8 {
+            let value = ((bits >> (35 - 5 * i)) & 0x1f) as u8;
+            // 'A' + value below 26, '2' + value - 26 from there.
+            let digit = !lt(value, 26);
+            text.push((value + b'A' - (digit & (b'A' + 26 - b'2'))) as char);
+        }
+        bits.zeroize();
+    }
+    raw.zeroize();
+    text
+}
+
+/// Decodes a strkey of `version`.
+///
+/// Returns `Err(length)` if `text` isn't 56 characters of the uppercase
+/// base32 alphabet, `Err(Error::ChecksumMismatch)` on a wrong checksum
+/// and `Err(Error::PrefixMismatch)` on another version byte.
+fn decode(text: &str, version: u8, length: Error) -> Result<[u8; 32], Error> {
+    if text.len() != STRKEY_LENGTH {
+        return Err(length);
+    }
+    let mut raw = [0u8; RAW_SIZE];
+    let mut invalid = 0u8;
+    for (chunk, out) in text.as_bytes().chunks(8).zip(raw.chunks_mut(5)) {
+        let mut bits = 0u64;
+        for &c in chunk {
+            let letter = !lt(c, b'A') & lt(c, b'Z' + 1);
+            let digit = !lt(c, b'2') & lt(c, b'7' + 1);
+            invalid |= !(letter | digit);
+            let value = (letter & c.wrapping_sub(b'A')) | (digit & c.wrapping_sub(b'2' - 26));
+            bits = (bits << 5) | value as u64;
+        }
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = (bits >> (32 - 8 * i)) as u8;
+        }
+        bits.zeroize();
+    }
+
+    let result = if invalid != 0 {
+        Err(length)
+    } else if crc16(&raw[..33]).to_le_bytes() != raw[33..] {
+        Err(Error::ChecksumMismatch)
+    } else if raw[0] != version {
+        Err(Error::PrefixMismatch)
+    } else {
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&raw[1..33]);
+        Ok(key)
+    };
+    raw.zeroize();
+    result
+}
+
+impl PublicKey {
+    /// Encodes `PublicKey` as a Stellar account ID, the `G` strkey.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let account = "GDLVVGABQKYQVN6VJP7NHSLEA45A5YLS6PNKMIZFV4BBU2HXA5IRVHUR";
+    ///     let public_key = PublicKey::from_strkey(account).unwrap();
+    ///     assert_eq!(public_key.to_strkey(), account);
+    /// }
+    /// ```
+    pub fn to_strkey(&self) -> String {
+        encode(ACCOUNT_ID, &self.0)
+    }
+
+    /// Decodes a Stellar account ID, the `G` strkey.
+    ///
+    /// Returns `Err(Error::PrefixMismatch)` if the strkey is of another
+    /// kind, `Err(Error::ChecksumMismatch)` if its checksum is wrong and
+    /// `Err(Error::InvalidPublicKey)` if it isn't 56 characters of the
+    /// uppercase base32 alphabet, without padding.
+    pub fn from_strkey(text: &str) -> Result<Self, Error> {
+        decode(text, ACCOUNT_ID, Error::InvalidPublicKey).map(PublicKey)
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl SecretKey {
+    /// Encodes the seed of `SecretKey` as a Stellar secret seed, the `S`
+    /// strkey.
+    pub fn to_strkey_seed(&self) -> String {
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::SecretKey);
+        encode(SEED, &self.0)
+    }
+
+    /// Decodes a Stellar secret seed, the `S` strkey.
+    ///
+    /// Returns `Err(Error::PrefixMismatch)` if the strkey is of another
+    /// kind, `Err(Error::ChecksumMismatch)` if its checksum is wrong and
+    /// `Err(Error::InvalidSecretKey)` if it isn't 56 characters of the
+    /// uppercase base32 alphabet, without padding.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::SecretKey;
+    ///
+    /// fn main() {
+    ///     let seed = "SCOWDMM5576VUYF2QRFPJEXMFTCEISOFNF5TE2IZOA52YAY4VZ7WBQNO";
+    ///     let secret_key = SecretKey::from_strkey_seed(seed).unwrap();
+    ///     assert_eq!(
+    ///         secret_key.public_key().to_strkey(),
+    ///         "GDLVVGABQKYQVN6VJP7NHSLEA45A5YLS6PNKMIZFV4BBU2HXA5IRVHUR"
+    ///     );
+    /// }
+    /// ```
+    pub fn from_strkey_seed(text: &str) -> Result<Self, Error> {
+        let secret = decode(text, SEED, Error::InvalidSecretKey).map(SecretKey)?;
+        #[cfg(feature = "observer")]
+        observer::imported(observer::KeyKind::SecretKey);
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The public key and seed of RFC 8032 TEST 1, encoded with a Python
+    // strkey reference.
+    const ACCOUNT: &str = "GDLVVGABQKYQVN6VJP7NHSLEA45A5YLS6PNKMIZFV4BBU2HXA5IRVHUR";
+    const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    #[cfg(not(feature = "verify-only"))]
+    const SEED: &str = "SCOWDMM5576VUYF2QRFPJEXMFTCEISOFNF5TE2IZOA52YAY4VZ7WBQNO";
+    #[cfg(not(feature = "verify-only"))]
+    const SECRET_KEY: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+
+    #[test]
+    fn stellar_checksum() {
+        // The CRC16-XModem check value.
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+    }
+
+    #[test]
+    fn stellar_account_id() {
+        let public = PublicKey::from_strkey(ACCOUNT).unwrap();
+        assert_eq!(public.to_string(), PUBLIC_KEY);
+        assert_eq!(public.to_strkey(), ACCOUNT);
+
+        // Another account ID, checked against the same reference.
+        let public =
+            PublicKey::from_strkey("GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ")
+                .unwrap();
+        assert_eq!(
+            public.to_string(),
+            "3f0c34bf93ad0d9971d04ccc90f705511c838aad9734a4a2fb0d7a03fc7fe89a"
+        );
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn stellar_seed() {
+        let secret = SecretKey::from_strkey_seed(SEED).unwrap();
+        assert_eq!(secret.to_hex(), SECRET_KEY);
+        assert_eq!(secret.to_strkey_seed(), SEED);
+        assert_eq!(secret.public_key().to_strkey(), ACCOUNT);
+
+        let secret =
+            SecretKey::from_strkey_seed("SCZANGBA5YHTNYVVV4C3U252E2B6P6F5T3U6MM63WBSBZATAQI3EBTQ4")
+                .unwrap();
+        assert_eq!(
+            secret.public_key().to_strkey(),
+            "GC2BKLYOOYPDEFJKLKY6FNNRQMGFLVHJKQRGNSSRRGSMPGF32LHCQVGF"
+        );
+    }
+
+    #[test]
+    fn stellar_rejects_invalid_strkeys() {
+        // A seed isn't an account ID, and the reverse.
+        #[cfg(not(feature = "verify-only"))]
+        {
+            assert_eq!(
+                PublicKey::from_strkey(SEED).err(),
+                Some(Error::PrefixMismatch)
+            );
+            assert_eq!(
+                SecretKey::from_strkey_seed(ACCOUNT).err(),
+                Some(Error::PrefixMismatch)
+            );
+        }
+        // Nor is a pre-authorized transaction (T) of the same bytes.
+        let key = PublicKey::from_strkey(ACCOUNT).unwrap();
+        assert_eq!(
+            PublicKey::from_strkey(&encode(19 << 3, &key.0)).err(),
+            Some(Error::PrefixMismatch)
+        );
+
+        let mut altered = ACCOUNT.to_string();
+        altered.replace_range(55..56, "S");
+        assert_eq!(
+            PublicKey::from_strkey(&altered).err(),
+            Some(Error::ChecksumMismatch)
+        );
+        let mut altered = ACCOUNT.to_string();
+        altered.replace_range(20..21, "B");
+        assert_eq!(
+            PublicKey::from_strkey(&altered).err(),
+            Some(Error::ChecksumMismatch)
+        );
+
+        // Padding, lowercase, characters outside the alphabet and wrong
+        // lengths.
+        let invalid = [
+            format!("{}=", ACCOUNT),
+            format!("{}====", ACCOUNT),
+            format!("{}=", &ACCOUNT[..55]),
+            ACCOUNT.to_lowercase(),
+            format!("{}r", &ACCOUNT[..55]),
+            format!("{}0", &ACCOUNT[..55]),
+            format!("{}1", &ACCOUNT[..55]),
+            format!("{}8", &ACCOUNT[..55]),
+            format!("{}A", ACCOUNT),
+            ACCOUNT[..55].to_string(),
+            String::new(),
+        ];
+        for text in invalid.iter() {
+            assert_eq!(
+                PublicKey::from_strkey(text).err(),
+                Some(Error::InvalidPublicKey),
+                "{}",
+                text
+            );
+        }
+        #[cfg(not(feature = "verify-only"))]
+        assert_eq!(
+            SecretKey::from_strkey_seed(&SEED.to_lowercase()).err(),
+            Some(Error::InvalidSecretKey)
+        );
+    }
+}
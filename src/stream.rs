@@ -0,0 +1,639 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Signing messages too large to hold in memory. Ed25519 hashes the
+// message twice, once for the nonce and once for the challenge, and the
+// nonce must be known before the challenge is started: one pass over
+// the message is not enough. `StreamSigner` takes one pass by signing
+// with Ed25519ph, over the SHA-512 of the message. `sign_rewindable`
+// keeps pure Ed25519 by reading a seekable source twice.
+//
+// Verifying takes one pass either way: the challenge hash of Ed25519,
+// SHA512(R || A || M), starts with the signature and the public key, so
+// `StreamVerifier` keeps that hash state and feeds it the chunks.
+//
+// `sign_reader` and `verify_reader` run them over an `io::Read`, through
+// an 8 KiB buffer on the stack: hashing a file in the page cache is no
+// faster with larger buffers, and 12% slower with 1 KiB.
+
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::prehash::*;
+use crate::public::*;
+use crate::signature::*;
+use crate::verify::*;
+
+use sha2::{Digest, Sha512};
+use std::io::{self, ErrorKind, Read};
+#[cfg(not(feature = "verify-only"))]
+use std::io::{Seek, SeekFrom};
+
+/// Size of the buffer messages are read through.
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Reads `reader` to its end, passing each chunk read to `consume`.
+/// Interrupted reads are retried.
+fn read_chunks<R, F>(reader: &mut R, mut consume: F) -> io::Result<()>
+where
+    R: Read + ?Sized,
+    F: FnMut(&[u8]),
+{
+    let mut buffer = [0u8; BUFFER_SIZE];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => consume(&buffer[..n]),
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Signs a message fed in chunks, with Ed25519ph and the empty context.
+///
+/// The signature is the one `SecretKey::sign_prehashed` makes over the
+/// SHA-512 of the whole message: it verifies with
+/// `PublicKey::verify_prehashed`, never with `PublicKey::verify`.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+/// extern crate sha2;
+///
+/// use ed25519_fun::{Keypair, StreamSigner};
+/// use sha2::{Digest, Sha512};
+///
+/// fn main() {
+///     let keypair = Keypair::generate();
+///     let mut signer = StreamSigner::new(&keypair);
+///     for chunk in [&b"a large "[..], b"artifact"].iter() {
+///         signer.update(chunk);
+///     }
+///     let signature = signer.finalize();
+///
+///     let mut digest = Sha512::new();
+///     digest.input(b"a large artifact");
+///     assert!(keypair.public().verify_prehashed(digest, None, &signature).is_ok());
+/// }
+/// ```
+#[cfg(not(feature = "verify-only"))]
+pub struct StreamSigner<'a> {
+    keypair: &'a Keypair,
+    hash: Sha512,
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl<'a> StreamSigner<'a> {
+    /// Starts signing an empty message with `keypair`.
+    pub fn new(keypair: &'a Keypair) -> Self {
+        StreamSigner {
+            keypair,
+            hash: Sha512::new(),
+        }
+    }
+
+    /// Appends `chunk` to the message.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hash.input(chunk);
+    }
+
+    /// Signs the message fed so far.
+    ///
+    /// Returns `Signature`.
+    pub fn finalize(self) -> Signature {
+        let domain = ph_domain(None).expect("the empty context is short enough");
+        self.keypair.secret.sign_prehash_unchecked(
+            &self.keypair.signing_public(),
+            &self.hash.result(),
+            domain,
+        )
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl Keypair {
+    /// Signs with pure Ed25519 the bytes of `source` from its current
+    /// position to its end, reading them twice: the same signature as
+    /// `sign` over those bytes, in constant memory.
+    ///
+    /// Signing two different messages with the same nonce gives away the
+    /// secret key, so both passes are hashed and compared, and nothing is
+    /// signed if the source changed in between.
+    ///
+    /// Returns `Ok(Signature)`, the error of `source`, or an error of
+    /// kind `InvalidData` if the two passes didn't read the same bytes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    /// use std::io::Cursor;
+    ///
+    /// fn main() {
+    ///     let keypair = Keypair::generate();
+    ///     let mut source = Cursor::new(b"a large artifact".to_vec());
+    ///     let signature = keypair.sign_rewindable(&mut source).unwrap();
+    ///     assert!(keypair.public().verify(b"a large artifact", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn sign_rewindable<R: Read + Seek>(&self, source: &mut R) -> io::Result<Signature> {
+        let start = source.stream_position()?;
+        let mut first_pass = None;
+        #[cfg(feature = "observer")]
+        let mut observed = observer::MessageDigest::default();
+
+        let signature = self
+            .secret
+            .try_sign_absorbing(&self.signing_public(), None, |hash| {
+                source.seek(SeekFrom::Start(start))?;
+                let mut check = Sha512::new();
+                read_chunks(source, |chunk| {
+                    hash.input(chunk);
+                    check.input(chunk);
+                    #[cfg(feature = "observer")]
+                    if first_pass.is_some() {
+                        observed.update(chunk);
+                    }
+                })?;
+                let check = check.result();
+                match first_pass {
+                    None => first_pass = Some(check),
+                    Some(first) if first == check => {}
+                    Some(_) => {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            "the source changed between the two passes",
+                        ))
+                    }
+                }
+                Ok(())
+            })?;
+        #[cfg(feature = "observer")]
+        observer::signed(&self.public, |digest| *digest = observed);
+        Ok(signature)
+    }
+
+    /// Signs the bytes of `reader` up to its end, as `StreamSigner` does:
+    /// with Ed25519ph and the empty context, in constant memory.
+    ///
+    /// Returns `Ok(Signature)`, or the first error of `reader`, with
+    /// nothing signed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::Keypair;
+    /// use std::fs::File;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let keypair = Keypair::generate();
+    ///     let signature = keypair.sign_reader(&mut File::open("Cargo.toml")?)?;
+    ///     assert!(keypair
+    ///         .public()
+    ///         .verify_reader(&mut File::open("Cargo.toml")?, &signature)
+    ///         .is_ok());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn sign_reader<R: Read + ?Sized>(&self, reader: &mut R) -> io::Result<Signature> {
+        let mut signer = StreamSigner::new(self);
+        read_chunks(reader, |chunk| signer.update(chunk))?;
+        Ok(signer.finalize())
+    }
+}
+
+impl PublicKey {
+    /// Verifies an Ed25519ph signature of `sign_reader` or `StreamSigner`
+    /// over the bytes of `reader` up to its end, in constant memory.
+    ///
+    /// Returns `Ok(())` if the signature is valid, `Err(Error::Io)` with
+    /// the kind of the first error of `reader`, and the errors of
+    /// `StreamVerifier` otherwise.
+    pub fn verify_reader<R: Read + ?Sized>(
+        &self,
+        reader: &mut R,
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        let mut verifier = StreamVerifier::new(self, signature);
+        read_chunks(reader, |chunk| verifier.update(chunk))
+            .map_err(|error| Error::Io { kind: error.kind() })?;
+        verifier.finalize()
+    }
+}
+
+/// Verifies a signature of a message fed in chunks, in constant memory.
+///
+/// `new` verifies the Ed25519ph signatures of `StreamSigner`, `pure` the
+/// Ed25519 signatures of `sign` and `sign_rewindable`. Either way the
+/// verdict, error variant included, is the one of the one-shot
+/// `PublicKey::verify_prehashed` or `PublicKey::verify` over the whole
+/// message.
+///
+/// # Example
+///
+#[cfg_attr(not(feature = "verify-only"), doc = "```rust")]
+#[cfg_attr(feature = "verify-only", doc = "```ignore")]
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::{Keypair, StreamSigner, StreamVerifier};
+///
+/// fn main() {
+///     let keypair = Keypair::generate();
+///     let mut signer = StreamSigner::new(&keypair);
+///     signer.update(b"a large artifact");
+///     let signature = signer.finalize();
+///
+///     let mut verifier = StreamVerifier::new(keypair.public(), &signature);
+///     for chunk in [&b"a large "[..], b"artifact"].iter() {
+///         verifier.update(chunk);
+///     }
+///     assert!(verifier.finalize().is_ok());
+///
+///     let signature = keypair.sign(b"a small message");
+///     let mut verifier = StreamVerifier::pure(keypair.public(), &signature);
+///     verifier.update(b"a small ");
+///     verifier.update(b"message");
+///     assert!(verifier.finalize().is_ok());
+/// }
+/// ```
+pub struct StreamVerifier<'a> {
+    public: &'a PublicKey,
+    signature: &'a Signature,
+    hash: Sha512,
+    prehashed: bool,
+}
+
+impl<'a> StreamVerifier<'a> {
+    /// Starts verifying an Ed25519ph `signature`, with the empty context,
+    /// of a message to be fed with `update`.
+    pub fn new(public: &'a PublicKey, signature: &'a Signature) -> Self {
+        StreamVerifier {
+            public,
+            signature,
+            hash: Sha512::new(),
+            prehashed: true,
+        }
+    }
+
+    /// Starts verifying a pure Ed25519 `signature` of a message to be fed
+    /// with `update`.
+    pub fn pure(public: &'a PublicKey, signature: &'a Signature) -> Self {
+        StreamVerifier {
+            public,
+            signature,
+            hash: challenge_hash(&signature.as_bytes()[0..32], &public.0, None),
+            prehashed: false,
+        }
+    }
+
+    /// Appends `chunk` to the message.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.hash.input(chunk);
+    }
+
+    /// Verifies the signature of the message fed so far.
+    ///
+    /// Returns `Ok(())` if the signature is valid and, as the one-shot
+    /// verification, `Err` otherwise.
+    pub fn finalize(self) -> Result<(), Error> {
+        if self.prehashed {
+            let domain = ph_domain(None)?;
+            self.public
+                .verify_prehash(&self.hash.result(), domain, self.signature)
+        } else {
+            self.public.verify_challenge_hash(self.signature, self.hash)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    #[cfg(not(feature = "verify-only"))]
+    use crate::secret::*;
+    #[cfg(not(feature = "verify-only"))]
+    use ::proptest::prelude::*;
+    #[cfg(not(feature = "verify-only"))]
+    use std::io::Cursor;
+
+    // RFC 8032, section 7.3: Ed25519ph, message "abc".
+    #[cfg(not(feature = "verify-only"))]
+    const SECRET_KEY: &str = "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42";
+    const PUBLIC_KEY: &str = "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf";
+    const SIGNATURE: &str = "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae41\
+                             31f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406";
+
+    #[cfg(not(feature = "verify-only"))]
+    fn keypair() -> Keypair {
+        Keypair::from_secret(SecretKey::from_bytes(&hex::decode(SECRET_KEY).unwrap()).unwrap())
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    fn message() -> Vec<u8> {
+        (0..3 * BUFFER_SIZE + 7).map(|i| (i * 31) as u8).collect()
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn stream_signer_rfc8032_ed25519ph() {
+        let keypair = keypair();
+        let mut signer = StreamSigner::new(&keypair);
+        signer.update(b"ab");
+        signer.update(b"");
+        signer.update(b"c");
+        assert_eq!(hex::encode(signer.finalize().0), SIGNATURE);
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn stream_signer_chunkings() {
+        let keypair = keypair();
+        let message = message();
+        let mut digest = Sha512::new();
+        digest.input(&message);
+        let expected = keypair
+            .secret()
+            .sign_prehashed(keypair.public(), digest.clone(), None)
+            .unwrap();
+
+        for size in [1, 7, 64, 1000, BUFFER_SIZE, message.len()].iter() {
+            let mut signer = StreamSigner::new(&keypair);
+            for chunk in message.chunks(*size) {
+                signer.update(chunk);
+            }
+            assert_eq!(signer.finalize(), expected);
+        }
+        assert!(keypair
+            .public()
+            .verify_prehashed(digest, None, &expected)
+            .is_ok());
+        assert!(keypair.public().verify(&message, &expected).is_err());
+
+        // The empty message.
+        let mut digest = Sha512::new();
+        digest.input(b"");
+        assert!(keypair
+            .public()
+            .verify_prehashed(digest, None, &StreamSigner::new(&keypair).finalize())
+            .is_ok());
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn sign_rewindable_is_pure_ed25519() {
+        let keypair = keypair();
+        let message = message();
+        let mut source = Cursor::new(message.clone());
+        assert_eq!(
+            keypair.sign_rewindable(&mut source).unwrap(),
+            keypair.sign(&message)
+        );
+
+        // From the current position.
+        source.set_position(100);
+        assert_eq!(
+            keypair.sign_rewindable(&mut source).unwrap(),
+            keypair.sign(&message[100..])
+        );
+        let mut empty = Cursor::new(Vec::new());
+        assert_eq!(
+            keypair.sign_rewindable(&mut empty).unwrap(),
+            keypair.sign(b"")
+        );
+    }
+
+    // Serves `first` up to its end, then `second`.
+    #[cfg(not(feature = "verify-only"))]
+    struct Changing {
+        first: Cursor<Vec<u8>>,
+        second: Cursor<Vec<u8>>,
+        switched: bool,
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    impl Read for Changing {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if !self.switched {
+                let n = self.first.read(buffer)?;
+                self.switched = n == 0;
+                return Ok(n);
+            }
+            self.second.read(buffer)
+        }
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    impl Seek for Changing {
+        fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+            self.second.seek(position)?;
+            self.first.seek(position)
+        }
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn sign_rewindable_rejects_changing_sources() {
+        let mut source = Changing {
+            first: Cursor::new(b"pay 10".to_vec()),
+            second: Cursor::new(b"pay 99".to_vec()),
+            switched: false,
+        };
+        let error = keypair().sign_rewindable(&mut source).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    // RFC 8032, TEST 3.
+    const PUBLIC_KEY_3: &str = "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025";
+    const SIGNATURE_3: &str = "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac\
+                               18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a";
+
+    fn verify_chunks(verifier: StreamVerifier, chunks: &[&[u8]]) -> Result<(), Error> {
+        let mut verifier = verifier;
+        for chunk in chunks.iter() {
+            verifier.update(chunk);
+        }
+        verifier.finalize()
+    }
+
+    #[test]
+    fn stream_verifier_rfc8032_vectors() {
+        let public = PublicKey::from_bytes(&hex::decode(PUBLIC_KEY).unwrap()).unwrap();
+        let signature = Signature::from_bytes(&hex::decode(SIGNATURE).unwrap()).unwrap();
+        let verifier = || StreamVerifier::new(&public, &signature);
+        assert!(verify_chunks(verifier(), &[b"a", b"", b"bc"]).is_ok());
+        assert_eq!(
+            verify_chunks(verifier(), &[b"ab"]),
+            Err(Error::SignatureMismatch)
+        );
+        assert!(verify_chunks(StreamVerifier::pure(&public, &signature), &[b"abc"]).is_err());
+
+        let public = PublicKey::from_bytes(&hex::decode(PUBLIC_KEY_3).unwrap()).unwrap();
+        let signature = Signature::from_bytes(&hex::decode(SIGNATURE_3).unwrap()).unwrap();
+        let verifier = || StreamVerifier::pure(&public, &signature);
+        assert!(verify_chunks(verifier(), &[&[0xaf], &[0x82]]).is_ok());
+        assert!(verify_chunks(verifier(), &[&[0xaf, 0x82]]).is_ok());
+        assert_eq!(
+            verify_chunks(verifier(), &[&[0xaf, 0x83]]),
+            Err(Error::SignatureMismatch)
+        );
+        assert!(verify_chunks(StreamVerifier::new(&public, &signature), &[&[0xaf, 0x82]]).is_err());
+
+        // The verdicts of malformed keys and signatures.
+        let mut bytes = signature.0;
+        bytes[63] |= 0xf0;
+        let non_canonical = Signature(bytes);
+        assert_eq!(
+            verify_chunks(
+                StreamVerifier::pure(&public, &non_canonical),
+                &[&[0xaf, 0x82]]
+            ),
+            Err(Error::NonCanonicalScalar)
+        );
+        let invalid = (0..=255u8)
+            .map(|y| PublicKey([y; 32]))
+            .find(|public| public.verify(b"", &signature) == Err(Error::InvalidPublicKey))
+            .unwrap();
+        assert_eq!(
+            verify_chunks(StreamVerifier::pure(&invalid, &signature), &[]),
+            Err(Error::InvalidPublicKey)
+        );
+    }
+
+    // Splits `message` at the `splits`, taken modulo its length.
+    #[cfg(not(feature = "verify-only"))]
+    fn split<'a>(message: &'a [u8], splits: &[usize]) -> Vec<&'a [u8]> {
+        let mut points: Vec<usize> = splits.iter().map(|i| i % (message.len() + 1)).collect();
+        points.push(0);
+        points.push(message.len());
+        points.sort_unstable();
+        points.windows(2).map(|w| &message[w[0]..w[1]]).collect()
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    proptest! {
+        #[test]
+        fn stream_verifier_matches_one_shot(
+            seed in any::<[u8; 32]>(),
+            message in proptest::collection::vec(any::<u8>(), 0..600),
+            splits in proptest::collection::vec(any::<usize>(), 0..8),
+            tamper in proptest::option::of((any::<usize>(), 0..8u8)),
+        ) {
+            let keypair = Keypair::from_secret(SecretKey::from_array(seed));
+            let public = keypair.public();
+            let mut digest = Sha512::new();
+            digest.input(&message);
+            let prehashed = keypair.secret().sign_prehashed(public, digest, None).unwrap();
+            let pure = keypair.sign(&message);
+
+            // Verify a tampered message, or the same one.
+            let mut received = message.clone();
+            if let Some((index, bit)) = tamper {
+                if received.is_empty() {
+                    received.push(1 << bit);
+                } else {
+                    let index = index % received.len();
+                    received[index] ^= 1 << bit;
+                }
+            }
+            let chunks = split(&received, &splits);
+
+            let mut digest = Sha512::new();
+            digest.input(&received);
+            prop_assert_eq!(
+                verify_chunks(StreamVerifier::new(public, &prehashed), &chunks),
+                public.verify_prehashed(digest, None, &prehashed)
+            );
+            prop_assert_eq!(
+                verify_chunks(StreamVerifier::pure(public, &pure), &chunks),
+                public.verify(&received, &pure)
+            );
+            prop_assert_eq!(
+                verify_chunks(StreamVerifier::pure(public, &pure), &chunks).is_ok(),
+                tamper.is_none()
+            );
+        }
+    }
+
+    // Fails after `left` bytes.
+    struct Failing {
+        left: usize,
+    }
+
+    impl Read for Failing {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            if self.left == 0 {
+                return Err(io::Error::new(ErrorKind::ConnectionReset, "reset"));
+            }
+            let n = buffer.len().min(self.left);
+            self.left -= n;
+            Ok(n)
+        }
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn reader_helpers_on_files() {
+        use std::io::Write;
+
+        let keypair = keypair();
+        let message = message();
+        for size in [0, 1, BUFFER_SIZE - 1, BUFFER_SIZE, message.len()].iter() {
+            let mut file = tempfile::tempfile().unwrap();
+            file.write_all(&message[..*size]).unwrap();
+
+            file.seek(SeekFrom::Start(0)).unwrap();
+            let signature = keypair.sign_reader(&mut file).unwrap();
+            let mut signer = StreamSigner::new(&keypair);
+            signer.update(&message[..*size]);
+            assert_eq!(signature, signer.finalize());
+
+            file.seek(SeekFrom::Start(0)).unwrap();
+            assert!(keypair
+                .public()
+                .verify_reader(&mut file, &signature)
+                .is_ok());
+            // Read to the end: the rest is the empty message.
+            assert_eq!(
+                keypair
+                    .public()
+                    .verify_reader(&mut file, &signature)
+                    .is_ok(),
+                *size == 0
+            );
+        }
+    }
+
+    #[test]
+    fn reader_errors_are_io_errors() {
+        let public = PublicKey::from_bytes(&hex::decode(PUBLIC_KEY).unwrap()).unwrap();
+        let signature = Signature::from_bytes(&hex::decode(SIGNATURE).unwrap()).unwrap();
+        let mut failing = Failing {
+            left: 3 * BUFFER_SIZE,
+        };
+        assert_eq!(
+            public.verify_reader(&mut failing, &signature),
+            Err(Error::Io {
+                kind: ErrorKind::ConnectionReset
+            })
+        );
+        assert!(public.verify_reader(&mut &b"abc"[..], &signature).is_ok());
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn sign_reader_propagates_io_errors() {
+        let mut failing = Failing { left: 10 };
+        let error = keypair().sign_reader(&mut failing).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ConnectionReset);
+    }
+}
@@ -0,0 +1,267 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Tezos encodings: base58check (double SHA-256 checksum) of a magic prefix
+// followed by the value. The prefixes are chosen so that the strings
+// start with a fixed tag:
+//
+//   edpk    0d 0f 25 d9      32-byte public key
+//   edsk    0d 0f 3a 07      32-byte seed (the 54-character form)
+//   edsig   09 f5 cd 86 12   64-byte signature
+//
+// Decoding checks the checksum first, then the prefix, then the length:
+// a string with a good checksum but another prefix is a value of another
+// kind, and fails with `Error::PrefixMismatch` rather than
+// `Error::ChecksumMismatch`.
+
+use crate::base58;
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(all(feature = "observer", not(feature = "verify-only")))]
+use crate::observer;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+const EDPK: &[u8] = &[0x0d, 0x0f, 0x25, 0xd9];
+#[cfg(not(feature = "verify-only"))]
+const EDSK: &[u8] = &[0x0d, 0x0f, 0x3a, 0x07];
+const EDSIG: &[u8] = &[0x09, 0xf5, 0xcd, 0x86, 0x12];
+
+/// Longer than any Tezos key or signature, whose prefix is then still
+/// told apart from a wrong length.
+const MAX_TEXT_LENGTH: usize = 128;
+
+/// Encodes `prefix` followed by `payload` as base58check.
+fn encode(prefix: &[u8], payload: &[u8]) -> String {
+    let mut bytes = prefix.to_vec();
+    bytes.extend_from_slice(payload);
+    base58::encode_check(&bytes)
+}
+
+/// Decodes the base58check of `prefix` followed by `N` bytes. Texts
+/// longer than `MAX_TEXT_LENGTH` are rejected before the quadratic
+/// decoding.
+///
+/// Returns `Err(Error::InvalidBase58)` on a character outside the
+/// alphabet, `Err(Error::ChecksumMismatch)` on a wrong checksum,
+/// `Err(Error::PrefixMismatch)` on another prefix and `Err(length)` if
+/// the value isn't `N` bytes long.
+fn decode<const N: usize>(text: &str, prefix: &[u8], length: Error) -> Result<[u8; N], Error> {
+    if text.len() > MAX_TEXT_LENGTH {
+        return Err(length);
+    }
+    let decoded = base58::decode(text).ok_or(Error::InvalidBase58)?;
+    if decoded.len() < 4 {
+        return Err(length);
+    }
+    let (payload, check) = decoded.split_at(decoded.len() - 4);
+    if base58::checksum(payload) != check {
+        return Err(Error::ChecksumMismatch);
+    }
+    if !payload.starts_with(prefix) {
+        return Err(Error::PrefixMismatch);
+    }
+    if payload.len() != prefix.len() + N {
+        return Err(length);
+    }
+    let mut out = [0u8; N];
+    out.copy_from_slice(&payload[prefix.len()..]);
+    Ok(out)
+}
+
+impl PublicKey {
+    /// Encodes `PublicKey` as a Tezos `edpk` string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::PublicKey;
+    ///
+    /// fn main() {
+    ///     let public_key =
+    ///         PublicKey::from_tezos("edpkuBknW28nW72KG6RoHtYW7p12T6GKc7nAbwYX5m8Wd9sDVC9yav").unwrap();
+    ///     assert_eq!(public_key.to_tezos(), "edpkuBknW28nW72KG6RoHtYW7p12T6GKc7nAbwYX5m8Wd9sDVC9yav");
+    /// }
+    /// ```
+    pub fn to_tezos(&self) -> String {
+        encode(EDPK, &self.0)
+    }
+
+    /// Decodes a Tezos `edpk` string.
+    ///
+    /// Returns `Err(Error::PrefixMismatch)` if the string isn't an `edpk`,
+    /// `Err(Error::ChecksumMismatch)` if its checksum is wrong,
+    /// `Err(Error::InvalidBase58)` on a character outside the alphabet
+    /// and `Err(Error::InvalidPublicKey)` on a wrong length.
+    pub fn from_tezos(text: &str) -> Result<Self, Error> {
+        decode::<PublicKeySize>(text, EDPK, Error::InvalidPublicKey).map(PublicKey)
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl SecretKey {
+    /// Encodes the seed of `SecretKey` as a Tezos `edsk` string, in its
+    /// 54-character form.
+    pub fn to_tezos_edsk(&self) -> String {
+        #[cfg(feature = "observer")]
+        observer::exported(observer::KeyKind::SecretKey);
+        encode(EDSK, &self.0)
+    }
+
+    /// Decodes a Tezos `edsk` string of a 32-byte seed.
+    ///
+    /// Returns `Err(Error::PrefixMismatch)` if the string isn't an `edsk`,
+    /// `Err(Error::ChecksumMismatch)` if its checksum is wrong,
+    /// `Err(Error::InvalidBase58)` on a character outside the alphabet
+    /// and `Err(Error::InvalidSecretKey)` on a wrong length. The 98-character
+    /// `edsk` of a seed and public key has another prefix.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Keypair, SecretKey};
+    ///
+    /// fn main() {
+    ///     let secret_key =
+    ///         SecretKey::from_tezos_edsk("edsk3gUfUPyBSfrS9CCgmCiQsTCHGkviBDusMxDJstFtojtc1zcpsh")
+    ///             .unwrap();
+    ///     let keypair = Keypair::from_secret(secret_key);
+    ///     assert_eq!(
+    ///         keypair.public().to_tezos(),
+    ///         "edpkuBknW28nW72KG6RoHtYW7p12T6GKc7nAbwYX5m8Wd9sDVC9yav"
+    ///     );
+    /// }
+    /// ```
+    pub fn from_tezos_edsk(text: &str) -> Result<Self, Error> {
+        let secret = decode::<SecretKeySize>(text, EDSK, Error::InvalidSecretKey).map(SecretKey)?;
+        #[cfg(feature = "observer")]
+        observer::imported(observer::KeyKind::SecretKey);
+        Ok(secret)
+    }
+}
+
+impl Signature {
+    /// Encodes `Signature` as a Tezos `edsig` string.
+    pub fn to_tezos_edsig(&self) -> String {
+        encode(EDSIG, &self.0)
+    }
+
+    /// Decodes a Tezos `edsig` string.
+    ///
+    /// Returns `Err(Error::PrefixMismatch)` if the string isn't an
+    /// `edsig`, `Err(Error::ChecksumMismatch)` if its checksum is wrong,
+    /// `Err(Error::InvalidBase58)` on a character outside the alphabet
+    /// and `Err(Error::InvalidSignatureEncoding)` on a wrong length.
+    pub fn from_tezos_edsig(text: &str) -> Result<Self, Error> {
+        decode::<SignatureSize>(text, EDSIG, Error::InvalidSignatureEncoding).map(Signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // The bootstrap1 account of the Tezos sandbox: its address,
+    // tz1KqTpEZ7Yob7QbPE4Hy4Wo8fHG8LhKxZSx, is the BLAKE2b-160 of this
+    // public key, and the public key is the one of this seed.
+    const EDSK_STR: &str = "edsk3gUfUPyBSfrS9CCgmCiQsTCHGkviBDusMxDJstFtojtc1zcpsh";
+    const EDPK_STR: &str = "edpkuBknW28nW72KG6RoHtYW7p12T6GKc7nAbwYX5m8Wd9sDVC9yav";
+    #[cfg(not(feature = "verify-only"))]
+    const SEED: &str = "8500c86780141917fcd8ac6a54a43a9eeda1aba9d263ce5dec5a1d0e5df1e598";
+    const PUBLIC_KEY: &str = "4798d2cc98473d7e250c898885718afd2e4efbcb1a1595ab9730761ed830de0f";
+    // The signature of the empty message under that key, signed and
+    // encoded with pyca/cryptography and a Python base58check.
+    const EDSIG_STR: &str = "edsigu31DZm3pwByn1dSLzDCYEXBgjQYVFuLj7T38W6ibqQHsX2k8rp3iNhnT3JAn8hHHQaXrEA46k5RBGQpAjGgLnjM49T7K89";
+
+    #[test]
+    fn tezos_public_key() {
+        let public = PublicKey::from_tezos(EDPK_STR).unwrap();
+        assert_eq!(public.to_string(), PUBLIC_KEY);
+        assert_eq!(public.to_tezos(), EDPK_STR);
+    }
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn tezos_secret_key_and_signature() {
+        let secret = SecretKey::from_tezos_edsk(EDSK_STR).unwrap();
+        assert_eq!(secret.to_hex(), SEED);
+        assert_eq!(secret.to_tezos_edsk(), EDSK_STR);
+        assert_eq!(secret.public_key().to_tezos(), EDPK_STR);
+        assert_eq!(secret.sign_detached(b"").to_tezos_edsig(), EDSIG_STR);
+    }
+
+    #[test]
+    fn tezos_signature() {
+        let signature = Signature::from_tezos_edsig(EDSIG_STR).unwrap();
+        assert_eq!(signature.to_tezos_edsig(), EDSIG_STR);
+        let public = PublicKey::from_tezos(EDPK_STR).unwrap();
+        assert!(public.verify(b"", &signature).is_ok());
+    }
+
+    #[test]
+    fn tezos_errors_are_distinct() {
+        // Each kind refuses the others by their prefix.
+        assert_eq!(
+            PublicKey::from_tezos(EDSIG_STR).err(),
+            Some(Error::PrefixMismatch)
+        );
+        assert_eq!(
+            PublicKey::from_tezos(EDSK_STR).err(),
+            Some(Error::PrefixMismatch)
+        );
+        assert_eq!(
+            Signature::from_tezos_edsig(EDPK_STR).err(),
+            Some(Error::PrefixMismatch)
+        );
+        #[cfg(not(feature = "verify-only"))]
+        assert_eq!(
+            SecretKey::from_tezos_edsk(EDPK_STR).err(),
+            Some(Error::PrefixMismatch)
+        );
+        // So does a tz1 address.
+        assert_eq!(
+            PublicKey::from_tezos("tz1KqTpEZ7Yob7QbPE4Hy4Wo8fHG8LhKxZSx").err(),
+            Some(Error::PrefixMismatch)
+        );
+
+        // A changed character breaks the checksum.
+        let mut altered = EDPK_STR.to_string();
+        altered.replace_range(10..11, "X");
+        assert_eq!(
+            PublicKey::from_tezos(&altered).err(),
+            Some(Error::ChecksumMismatch)
+        );
+        assert_eq!(
+            PublicKey::from_tezos(&EDPK_STR.replace('W', "0")).err(),
+            Some(Error::InvalidBase58)
+        );
+
+        // The right prefix and checksum over a short value.
+        let short = encode(EDPK, &hex::decode(PUBLIC_KEY).unwrap()[..31]);
+        assert_eq!(
+            PublicKey::from_tezos(&short).err(),
+            Some(Error::InvalidPublicKey)
+        );
+        let long = encode(EDSIG, &[0x5a; 65]);
+        assert_eq!(
+            Signature::from_tezos_edsig(&long).err(),
+            Some(Error::InvalidSignatureEncoding)
+        );
+        assert_eq!(
+            PublicKey::from_tezos("").err(),
+            Some(Error::InvalidPublicKey)
+        );
+        assert_eq!(
+            Signature::from_tezos_edsig(&format!("{}{}", EDSIG_STR, EDSIG_STR)).err(),
+            Some(Error::InvalidSignatureEncoding)
+        );
+    }
+}
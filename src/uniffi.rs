@@ -0,0 +1,239 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// UniFFI bindings, for the Kotlin and Swift apps: key generation, signing
+// and verification over byte vectors, through the proc-macro interface
+// (the scaffolding is set up in lib.rs). Bindings are generated from the
+// built library with `uniffi-bindgen generate --library`.
+//
+// Foreign code can't hold a wiped-on-drop `SecretKey`, so the seed
+// crosses the boundary as plain bytes; zeroizing the foreign copies is up
+// to the caller.
+
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::keypair::*;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::*;
+use crate::signature::*;
+
+use std::fmt;
+
+/// The secret key (the 32-byte seed) and the 32-byte public key of a
+/// keypair.
+#[derive(uniffi::Record)]
+pub struct KeypairBytes {
+    pub secret_key: Vec<u8>,
+    pub public_key: Vec<u8>,
+}
+
+/// `Error` as the bindings see it: one variant per kind of input the
+/// bindings take, the lengths of a length mismatch, and the message of
+/// any other `Error`.
+#[derive(Debug, PartialEq, uniffi::Error)]
+pub enum Ed25519Error {
+    /// A key or signature doesn't have the expected length.
+    InvalidLength { expected: u64, got: u64 },
+    /// The seed is malformed.
+    InvalidSecretKey,
+    /// The public key is malformed.
+    InvalidPublicKey,
+    /// The signature is malformed.
+    InvalidSignature,
+    /// The signature doesn't verify.
+    SignatureMismatch,
+    /// Any other `Error`, by its message.
+    Other { message: String },
+}
+
+impl From<Error> for Ed25519Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::InvalidPublicKeyLength { expected, got }
+            | Error::InvalidSecretKeyLength { expected, got }
+            | Error::InvalidKeypairLength { expected, got }
+            | Error::InvalidSignatureLength { expected, got } => Ed25519Error::InvalidLength {
+                expected: expected as u64,
+                got: got as u64,
+            },
+            Error::InvalidSecretKey => Ed25519Error::InvalidSecretKey,
+            Error::InvalidPublicKey => Ed25519Error::InvalidPublicKey,
+            Error::NonCanonicalScalar
+            | Error::InvalidSignaturePoint
+            | Error::InvalidSignatureEncoding => Ed25519Error::InvalidSignature,
+            Error::SignatureMismatch => Ed25519Error::SignatureMismatch,
+            error => Ed25519Error::Other {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Ed25519Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ed25519Error::InvalidLength { expected, got } => {
+                write!(
+                    f,
+                    "Invalid length: expected {} bytes, got {}",
+                    expected, got
+                )
+            }
+            Ed25519Error::InvalidSecretKey => write!(f, "Invalid secret key"),
+            Ed25519Error::InvalidPublicKey => write!(f, "Invalid public key"),
+            Ed25519Error::InvalidSignature => write!(f, "Invalid signature"),
+            Ed25519Error::SignatureMismatch => write!(f, "Signature doesn't verify"),
+            Ed25519Error::Other { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Ed25519Error {}
+
+#[cfg(not(feature = "verify-only"))]
+/// Generates a keypair.
+///
+/// Returns its seed and public key.
+#[uniffi::export]
+pub fn generate_keypair() -> KeypairBytes {
+    let keypair = Keypair::generate();
+    KeypairBytes {
+        secret_key: keypair.secret().as_bytes().to_vec(),
+        public_key: keypair.public().as_bytes().to_vec(),
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+/// Signs `msg` with the 32-byte `seed`.
+///
+/// Returns the 64-byte signature, or `Ed25519Error::InvalidLength` if the
+/// seed isn't 32 bytes long.
+#[uniffi::export]
+pub fn sign(mut seed: Vec<u8>, msg: Vec<u8>) -> Result<Vec<u8>, Ed25519Error> {
+    let secret = SecretKey::from_bytes(&seed);
+    zeroize::Zeroize::zeroize(&mut seed[..]);
+    let keypair = Keypair::from_secret(secret?);
+    Ok(keypair.sign(&msg).as_bytes().to_vec())
+}
+
+/// Verifies the signature `sig` of `msg` with the public key `pk`.
+///
+/// Returns `true` if the signature is valid, and `false` otherwise,
+/// malformed keys and signatures included.
+#[uniffi::export]
+pub fn verify(pk: Vec<u8>, msg: Vec<u8>, sig: Vec<u8>) -> bool {
+    let verdict = PublicKey::from_bytes(&pk)
+        .and_then(|public| public.verify(&msg, &Signature::from_bytes(&sig)?));
+    verdict.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use crate::UniFfiTag;
+    use ::uniffi::{Lower, RustBuffer, RustCallStatus, RustCallStatusCode};
+
+    // RFC 8032, TEST 2.
+    #[cfg(not(feature = "verify-only"))]
+    const SECRET_KEY: &str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+    const PUBLIC_KEY: &str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const SIGNATURE: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+    fn lower(bytes: &str) -> RustBuffer {
+        <Vec<u8> as Lower<UniFfiTag>>::lower(hex::decode(bytes).unwrap())
+    }
+
+    // Through the exported scaffolding, as the generated Kotlin and Swift
+    // call it.
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn uniffi_scaffolding_sign() {
+        let mut status = RustCallStatus::default();
+        let keypair = uniffi_ed25519_fun_fn_func_generate_keypair(&mut status);
+        assert!(status.code == RustCallStatusCode::Success);
+        let keypair = <KeypairBytes as ::uniffi::Lift<UniFfiTag>>::try_lift(keypair).unwrap();
+        assert_eq!(keypair.secret_key.len(), 32);
+        let public = PublicKey::from_secret(&SecretKey::from_bytes(&keypair.secret_key).unwrap());
+        assert_eq!(public.as_bytes()[..], keypair.public_key[..]);
+
+        let signature =
+            uniffi_ed25519_fun_fn_func_sign(lower(SECRET_KEY), lower("72"), &mut status);
+        assert!(status.code == RustCallStatusCode::Success);
+        let signature = <Vec<u8> as ::uniffi::Lift<UniFfiTag>>::try_lift(signature).unwrap();
+        assert_eq!(hex::encode(signature), SIGNATURE);
+
+        // A short seed raises, with the lengths.
+        uniffi_ed25519_fun_fn_func_sign(lower(&SECRET_KEY[2..]), lower("72"), &mut status);
+        assert!(status.code == RustCallStatusCode::Error);
+        let error = <Ed25519Error as ::uniffi::Lift<UniFfiTag>>::try_lift_from_rust_buffer(
+            std::mem::ManuallyDrop::into_inner(status.error_buf),
+        );
+        assert_eq!(
+            error.unwrap(),
+            Ed25519Error::InvalidLength {
+                expected: 32,
+                got: 31
+            }
+        );
+    }
+
+    #[test]
+    fn uniffi_scaffolding_verify() {
+        let mut status = RustCallStatus::default();
+        let verdict = uniffi_ed25519_fun_fn_func_verify(
+            lower(PUBLIC_KEY),
+            lower("72"),
+            lower(SIGNATURE),
+            &mut status,
+        );
+        assert!(status.code == RustCallStatusCode::Success);
+        assert_eq!(verdict, 1);
+        let verdict = uniffi_ed25519_fun_fn_func_verify(
+            lower(PUBLIC_KEY),
+            lower("73"),
+            lower(SIGNATURE),
+            &mut status,
+        );
+        assert_eq!(verdict, 0);
+
+        // Malformed inputs don't verify, and don't raise either.
+        let verdict = uniffi_ed25519_fun_fn_func_verify(
+            lower(&PUBLIC_KEY[2..]),
+            lower("72"),
+            lower(SIGNATURE),
+            &mut status,
+        );
+        assert!(status.code == RustCallStatusCode::Success);
+        assert_eq!(verdict, 0);
+    }
+
+    #[test]
+    fn uniffi_error_mapping() {
+        assert_eq!(
+            Ed25519Error::from(Error::InvalidSignatureLength {
+                expected: 64,
+                got: 63
+            }),
+            Ed25519Error::InvalidLength {
+                expected: 64,
+                got: 63
+            }
+        );
+        assert_eq!(
+            Ed25519Error::from(Error::NonCanonicalScalar),
+            Ed25519Error::InvalidSignature
+        );
+        assert_eq!(
+            Ed25519Error::from(Error::InvalidBech32).to_string(),
+            "Invalid bech32m string"
+        );
+        assert!(!verify(
+            hex::decode(PUBLIC_KEY).unwrap(),
+            b"r".to_vec(),
+            vec![0u8; 63]
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Helpers shared by the framing formats.
+
+use crate::constants::*;
+use crate::errors::*;
+
+/// Converts a length declared on the wire to `usize`.
+///
+/// Returns `Ok(usize)` if `len` is at most `MAX_MESSAGE_LEN` and fits in
+/// `usize` on this target, and `Err(Error::LengthOverflow)` otherwise.
+pub(crate) fn checked_len(len: u64) -> Result<usize, Error> {
+    checked_len_for(len, usize::MAX as u64).map(|len| len as usize)
+}
+
+/// `checked_len` for a target whose `usize::MAX` is `usize_max`, so the
+/// 32-bit decision can be tested on any host.
+fn checked_len_for(len: u64, usize_max: u64) -> Result<u64, Error> {
+    if len > MAX_MESSAGE_LEN || len > usize_max {
+        return Err(Error::LengthOverflow);
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_len_on_32_bit_targets() {
+        let usize_max = u32::MAX as u64;
+        for len in [u64::MAX, 1 << 32, (1 << 32) - 1, MAX_MESSAGE_LEN + 1].iter() {
+            assert_eq!(checked_len_for(*len, usize_max), Err(Error::LengthOverflow));
+        }
+        assert_eq!(
+            checked_len_for(MAX_MESSAGE_LEN, usize_max),
+            Ok(MAX_MESSAGE_LEN)
+        );
+        assert_eq!(checked_len_for(0, usize_max), Ok(0));
+    }
+
+    #[test]
+    fn checked_len_on_host() {
+        for len in [u64::MAX, 1 << 32, (1 << 32) - 1, MAX_MESSAGE_LEN + 1].iter() {
+            assert_eq!(checked_len(*len), Err(Error::LengthOverflow));
+        }
+        assert_eq!(checked_len(MAX_MESSAGE_LEN), Ok(MAX_MESSAGE_LEN as usize));
+    }
+}
@@ -0,0 +1,879 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Ed25519 signature verification.
+//
+// This module only sees public data: it must never import the secret
+// key types, so verification can't depend on secret material by
+// construction. The same holds for `precomputed.rs` and `batch.rs`. The
+// `verify_module_has_no_secret_access` test below is a tripwire for this
+// rule.
+
+#![allow(non_snake_case)]
+
+use crate::curve25519::group_element::*;
+use crate::curve25519::scalar_ops::*;
+
+use crate::dom2::*;
+use crate::errors::*;
+use crate::hasher::*;
+#[cfg(feature = "observer")]
+use crate::observer;
+use crate::public::*;
+use crate::signature::*;
+
+use sha2::{Digest, Sha512};
+
+// The group order, little-endian like the scalars it bounds.
+const L: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// Checks if the signature s is below the group order L.
+///
+/// Returns `true` if it is, `false` otherwise.
+pub(crate) fn check_lt_l(s: &[u8]) -> bool {
+    let mut c: u8 = 0;
+    let mut n: u8 = 1;
+
+    let mut i = 31;
+    loop {
+        c |= ((((s[i] as i32) - (L[i] as i32)) >> 8) as u8) & n;
+        n &= ((((s[i] ^ L[i]) as i32) - 1) >> 8) as u8;
+        if i == 0 {
+            break;
+        } else {
+            i -= 1;
+        }
+    }
+    c == 1
+}
+
+/// The checks a signature verification makes, beyond s being reduced
+/// and the group equation holding.
+///
+/// Implementations of Ed25519 differ on the encodings they accept for
+/// the public key and R, on points of small order and on the equation
+/// they check, and a protocol where several of them must agree on every
+/// signature has to pick one policy. `new` gives the checks of
+/// `PublicKey::verify`, and the presets those of the other methods:
+///
+/// | preset    | method              | canonical A, R | A, R not small | cofactored |
+/// |-----------|---------------------|----------------|----------------|------------|
+/// | `new`     | `verify`            | no             | no             | no         |
+/// | `strict`  | `verify_strict`     | yes            | yes            | no         |
+/// | `rfc8032` | `verify_cofactored` | yes            | no             | yes        |
+/// | `zip215`  | `verify_zip215`     | no             | no             | yes        |
+///
+/// Without the cofactor, R is compared to [s]B - [k]A by its encoding,
+/// so a non-canonical R never verifies: `reject_noncanonical_r` only
+/// turns the `Error::SignatureMismatch` into `Error::NonCanonicalPoint`.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::VerificationOptions;
+///
+/// fn main() {
+///     let options = VerificationOptions::new()
+///         .reject_noncanonical_a(true)
+///         .reject_noncanonical_r(true)
+///         .cofactored(true);
+///     assert_eq!(options, VerificationOptions::rfc8032());
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VerificationOptions {
+    reject_noncanonical_a: bool,
+    reject_noncanonical_r: bool,
+    reject_small_order_a: bool,
+    reject_small_order_r: bool,
+    cofactored: bool,
+}
+
+impl VerificationOptions {
+    /// Creates the options of `PublicKey::verify`: any encoding of a
+    /// point of any order, and the cofactorless equation.
+    pub const fn new() -> Self {
+        VerificationOptions {
+            reject_noncanonical_a: false,
+            reject_noncanonical_r: false,
+            reject_small_order_a: false,
+            reject_small_order_r: false,
+            cofactored: false,
+        }
+    }
+
+    /// Creates the options of `PublicKey::verify_strict`: canonical
+    /// encodings of points of large order, and the cofactorless
+    /// equation.
+    pub const fn strict() -> Self {
+        VerificationOptions {
+            reject_noncanonical_a: true,
+            reject_noncanonical_r: true,
+            reject_small_order_a: true,
+            reject_small_order_r: true,
+            cofactored: false,
+        }
+    }
+
+    /// Creates the options of `PublicKey::verify_cofactored`: canonical
+    /// encodings of points of any order, and the cofactored equation.
+    pub const fn rfc8032() -> Self {
+        VerificationOptions {
+            reject_noncanonical_a: true,
+            reject_noncanonical_r: true,
+            reject_small_order_a: false,
+            reject_small_order_r: false,
+            cofactored: true,
+        }
+    }
+
+    /// Creates the options of `PublicKey::verify_zip215`: any encoding
+    /// of a point of any order, and the cofactored equation.
+    pub const fn zip215() -> Self {
+        VerificationOptions {
+            reject_noncanonical_a: false,
+            reject_noncanonical_r: false,
+            reject_small_order_a: false,
+            reject_small_order_r: false,
+            cofactored: true,
+        }
+    }
+
+    /// Sets whether a public key that isn't the canonical encoding of its
+    /// point fails with `Error::NonCanonicalPoint`.
+    pub const fn reject_noncanonical_a(mut self, reject: bool) -> Self {
+        self.reject_noncanonical_a = reject;
+        self
+    }
+
+    /// Sets whether an R that isn't the canonical encoding of its point
+    /// fails with `Error::NonCanonicalPoint`.
+    pub const fn reject_noncanonical_r(mut self, reject: bool) -> Self {
+        self.reject_noncanonical_r = reject;
+        self
+    }
+
+    /// Sets whether a public key of small order fails with
+    /// `Error::SmallOrderPoint`.
+    pub const fn reject_small_order_a(mut self, reject: bool) -> Self {
+        self.reject_small_order_a = reject;
+        self
+    }
+
+    /// Sets whether an R of small order fails with
+    /// `Error::SmallOrderPoint`.
+    pub const fn reject_small_order_r(mut self, reject: bool) -> Self {
+        self.reject_small_order_r = reject;
+        self
+    }
+
+    /// Sets whether to check the cofactored equation
+    /// [8][s]B = [8]R + [8][k]A instead of [s]B = R + [k]A.
+    pub const fn cofactored(mut self, cofactored: bool) -> Self {
+        self.cofactored = cofactored;
+        self
+    }
+}
+
+impl Default for VerificationOptions {
+    fn default() -> Self {
+        VerificationOptions::new()
+    }
+}
+
+impl PublicKey {
+    /// Verifies a signature with this `PublicKey`.
+    ///
+    /// Returns `Ok(())` if the signature is valid,
+    /// `Err(Error::InvalidPublicKey)` if this `PublicKey` isn't a curve
+    /// point, `Err(Error::NonCanonicalScalar)` if s isn't reduced,
+    /// `Err(Error::InvalidSignaturePoint)` if R isn't a curve point and
+    /// `Err(Error::SignatureMismatch)` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{PublicKey, Signature};
+    ///
+    /// fn main() {
+    ///     let public_key = PublicKey::from_array([
+    ///         0xd7, 0x5a, 0x98, 0x01, 0x82, 0xb1, 0x0a, 0xb7, 0xd5, 0x4b, 0xfe, 0xd3, 0xc9, 0x64,
+    ///         0x07, 0x3a, 0x0e, 0xe1, 0x72, 0xf3, 0xda, 0xa6, 0x23, 0x25, 0xaf, 0x02, 0x1a, 0x68,
+    ///         0xf7, 0x07, 0x51, 0x1a,
+    ///     ]);
+    ///     let signature = Signature::from_array([
+    ///         0xe5, 0x56, 0x43, 0x00, 0xc3, 0x60, 0xac, 0x72, 0x90, 0x86, 0xe2, 0xcc, 0x80, 0x6e,
+    ///         0x82, 0x8a, 0x84, 0x87, 0x7f, 0x1e, 0xb8, 0xe5, 0xd9, 0x74, 0xd8, 0x73, 0xe0, 0x65,
+    ///         0x22, 0x49, 0x01, 0x55, 0x5f, 0xb8, 0x82, 0x15, 0x90, 0xa3, 0x3b, 0xac, 0xc6, 0x1e,
+    ///         0x39, 0x70, 0x1c, 0xf9, 0xb4, 0x6b, 0xd2, 0x5b, 0xf5, 0xf0, 0x59, 0x5b, 0xbe, 0x24,
+    ///         0x65, 0x51, 0x41, 0x43, 0x8e, 0x7a, 0x10, 0x0b,
+    ///     ]);
+    ///     assert!(public_key.verify(b"", &signature).is_ok());
+    /// }
+    /// ```
+    pub fn verify(&self, message: &[u8], sig: &Signature) -> Result<(), Error> {
+        self.verify_with_options(message, sig, &VerificationOptions::new())
+    }
+
+    /// Verifies a signature with the checks `options` selects.
+    ///
+    /// Returns `Ok(())` if the signature is valid,
+    /// `Err(Error::InvalidPublicKey)` or `Err(Error::InvalidSignaturePoint)`
+    /// if the public key or R isn't a curve point,
+    /// `Err(Error::NonCanonicalPoint)` or `Err(Error::SmallOrderPoint)`
+    /// if one of them is rejected by `options`,
+    /// `Err(Error::NonCanonicalScalar)` if s isn't reduced and
+    /// `Err(Error::SignatureMismatch)` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Error, PublicKey, Signature, VerificationOptions};
+    ///
+    /// fn main() {
+    ///     // The identity as public key, and as R with s = 0.
+    ///     let mut identity = [0u8; 32];
+    ///     identity[0] = 1;
+    ///     let public_key = PublicKey::from_array(identity);
+    ///     let mut signature = [0u8; 64];
+    ///     signature[..32].copy_from_slice(&identity);
+    ///     let signature = Signature::from_array(signature);
+    ///
+    ///     let options = VerificationOptions::new().reject_small_order_a(true);
+    ///     assert_eq!(
+    ///         public_key.verify_with_options(b"anything", &signature, &options),
+    ///         Err(Error::SmallOrderPoint)
+    ///     );
+    ///     let options = options.reject_small_order_a(false).cofactored(true);
+    ///     assert!(public_key
+    ///         .verify_with_options(b"anything", &signature, &options)
+    ///         .is_ok());
+    /// }
+    /// ```
+    pub fn verify_with_options(
+        &self,
+        message: &[u8],
+        sig: &Signature,
+        options: &VerificationOptions,
+    ) -> Result<(), Error> {
+        self.verify_absorbing_with::<Sha512, _>(sig, None, options, |hash| hash.input(message))
+    }
+
+    /// Verifies a signature as `verify` does, and also rejects the inputs
+    /// that let a signature verify under several keys or messages: a
+    /// public key or R that isn't the canonical encoding of its point, or
+    /// that is a point of small order. The identity is one, so it isn't a
+    /// valid public key either.
+    ///
+    /// Every signature made by signing with an honestly generated key
+    /// passes both. `verify` stays as permissive as it was.
+    ///
+    /// Returns `Ok(())` if the signature is valid,
+    /// `Err(Error::NonCanonicalPoint)` or `Err(Error::SmallOrderPoint)`
+    /// for a public key or R rejected as above, and the errors of
+    /// `verify` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{Error, PublicKey, Signature};
+    ///
+    /// fn main() {
+    ///     // The identity as public key, and as R with s = 0: a signature
+    ///     // of every message under the cofactorless equation.
+    ///     let mut identity = [0u8; 32];
+    ///     identity[0] = 1;
+    ///     let public_key = PublicKey::from_array(identity);
+    ///     let mut signature = [0u8; 64];
+    ///     signature[..32].copy_from_slice(&identity);
+    ///     let signature = Signature::from_array(signature);
+    ///
+    ///     assert!(public_key.verify(b"anything", &signature).is_ok());
+    ///     assert_eq!(
+    ///         public_key.verify_strict(b"anything", &signature),
+    ///         Err(Error::SmallOrderPoint)
+    ///     );
+    /// }
+    /// ```
+    pub fn verify_strict(&self, message: &[u8], sig: &Signature) -> Result<(), Error> {
+        self.verify_with_options(message, sig, &VerificationOptions::strict())
+    }
+
+    /// Verifies a signature with the cofactored equation
+    /// [8][s]B = [8]R + [8][k]A, and the decoding of RFC 8032: the public
+    /// key and R must be canonical encodings of curve points, of any
+    /// order, and s must be reduced.
+    ///
+    /// RFC 8032 (section 5.1.7) specifies this equation, and permits the
+    /// cofactorless [s]B = R + [k]A of `verify` as a sufficient check.
+    /// The two only disagree when R or [k]A has a small-order component,
+    /// which signing never produces: `verify` then rejects what
+    /// `verify_cofactored` accepts, and which one an implementation picks
+    /// decides whether it agrees with the others on such signatures.
+    ///
+    /// Returns `Ok(())` if the signature is valid,
+    /// `Err(Error::NonCanonicalPoint)` for a non-canonical public key or
+    /// R, and the errors of `verify` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{PublicKey, Signature};
+    ///
+    /// fn main() {
+    ///     // A point of order 8 as public key, the identity as R, and
+    ///     // s = 0. The cofactorless equation needs [k]A to be the
+    ///     // identity, which only happens when 8 divides k.
+    ///     let public_key = PublicKey::from_array([
+    ///         0xc7, 0x17, 0x6a, 0x70, 0x3d, 0x4d, 0xd8, 0x4f, 0xba, 0x3c, 0x0b, 0x76, 0x0d, 0x10,
+    ///         0x67, 0x0f, 0x2a, 0x20, 0x53, 0xfa, 0x2c, 0x39, 0xcc, 0xc6, 0x4e, 0xc7, 0xfd, 0x77,
+    ///         0x92, 0xac, 0x03, 0x7a,
+    ///     ]);
+    ///     let mut signature = [0u8; 64];
+    ///     signature[0] = 1;
+    ///     let signature = Signature::from_array(signature);
+    ///
+    ///     assert!(public_key.verify_cofactored(b"message", &signature).is_ok());
+    ///     assert!(public_key.verify(b"message", &signature).is_err());
+    /// }
+    /// ```
+    pub fn verify_cofactored(&self, message: &[u8], sig: &Signature) -> Result<(), Error> {
+        self.verify_with_options(message, sig, &VerificationOptions::rfc8032())
+    }
+
+    /// Verifies a signature with the rules of ZIP-215, those of Zcash
+    /// consensus since Canopy: the public key and R may be any encoding
+    /// of a curve point, canonical or not and of any order, s must be
+    /// reduced, k hashes the encodings as given, and the equation is
+    /// the cofactored [8][s]B = [8]R + [8][k]A.
+    ///
+    /// `verify` checks [s]B = R + [k]A without the cofactor and compares
+    /// R by its encoding, so it rejects a non-canonical R and a signature
+    /// that only holds up to a small-order component. `verify_strict`
+    /// rejects non-canonical and small-order points altogether. The two
+    /// agree with `verify_zip215` on every signature made by signing.
+    /// Batch verification uses the same decoding and cofactored equation,
+    /// so a batch passes exactly when `verify_zip215` accepts each entry,
+    /// up to the usual negligible chance of the random coefficients.
+    ///
+    /// Returns `Ok(())` if the signature is valid,
+    /// `Err(Error::InvalidPublicKey)` or `Err(Error::InvalidSignaturePoint)`
+    /// if the public key or R isn't a curve point,
+    /// `Err(Error::NonCanonicalScalar)` if s isn't reduced and
+    /// `Err(Error::SignatureMismatch)` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// extern crate ed25519_fun;
+    ///
+    /// use ed25519_fun::{PublicKey, Signature};
+    ///
+    /// fn main() {
+    ///     // A point of order 8 as public key, y = p + 1 (the identity,
+    ///     // not reduced) as R, and s = 0.
+    ///     let public_key = PublicKey::from_array([
+    ///         0xc7, 0x17, 0x6a, 0x70, 0x3d, 0x4d, 0xd8, 0x4f, 0xba, 0x3c, 0x0b, 0x76, 0x0d, 0x10,
+    ///         0x67, 0x0f, 0x2a, 0x20, 0x53, 0xfa, 0x2c, 0x39, 0xcc, 0xc6, 0x4e, 0xc7, 0xfd, 0x77,
+    ///         0x92, 0xac, 0x03, 0x7a,
+    ///     ]);
+    ///     let mut signature = [0u8; 64];
+    ///     signature[..32].copy_from_slice(&[0xff; 32]);
+    ///     signature[0] = 0xee;
+    ///     signature[31] = 0x7f;
+    ///     let signature = Signature::from_array(signature);
+    ///
+    ///     assert!(public_key.verify_zip215(b"Zcash", &signature).is_ok());
+    ///     assert!(public_key.verify(b"Zcash", &signature).is_err());
+    /// }
+    /// ```
+    pub fn verify_zip215(&self, message: &[u8], sig: &Signature) -> Result<(), Error> {
+        self.verify_with_options(message, sig, &VerificationOptions::zip215())
+    }
+
+    /// Verifies a signature over the message fed to the hash by `absorb`,
+    /// in the domain `dom2` (`None` for Ed25519).
+    ///
+    /// Returns `Ok(())` if the signature is valid and `Err` otherwise.
+    pub(crate) fn verify_absorbing<F>(
+        &self,
+        sig: &Signature,
+        dom2: Option<(u8, &[u8])>,
+        absorb: F,
+    ) -> Result<(), Error>
+    where
+        F: Fn(&mut Sha512),
+    {
+        self.verify_absorbing_with::<Sha512, F>(sig, dom2, &VerificationOptions::new(), absorb)
+    }
+
+    /// Verifies as `verify_absorbing`, with the SHA-512 of `H` and the
+    /// checks `options` selects.
+    ///
+    /// Returns `Ok(())` if the signature is valid and `Err` otherwise.
+    pub(crate) fn verify_absorbing_with<H, F>(
+        &self,
+        sig: &Signature,
+        dom2: Option<(u8, &[u8])>,
+        options: &VerificationOptions,
+        absorb: F,
+    ) -> Result<(), Error>
+    where
+        H: Sha512Provider,
+        F: Fn(&mut H),
+    {
+        // Try to decode the public key into a P3 point.
+        // Verification fails if decoding fails.
+        let verdict = decode_point(
+            self.0,
+            Error::InvalidPublicKey,
+            options.reject_noncanonical_a,
+            options.reject_small_order_a,
+        )
+        .and_then(|A| verify_decoded(&self.0, &A, sig, dom2, options, absorb));
+        #[cfg(feature = "observer")]
+        observer::verified(self, verdict);
+        verdict
+    }
+
+    /// Verifies a signature of the message fed to `hash`, a challenge
+    /// hash started by `challenge_hash` with this key and the R of `sig`,
+    /// with the checks and verdicts of `verify_absorbing`.
+    ///
+    /// Returns `Ok(())` if the signature is valid and `Err` otherwise.
+    #[cfg(feature = "std")]
+    pub(crate) fn verify_challenge_hash(&self, sig: &Signature, hash: Sha512) -> Result<(), Error> {
+        let signature = sig.as_bytes();
+        let verdict = match P3::decode(self.0) {
+            Some(_) if !check_lt_l(&signature[32..64]) => Err(Error::NonCanonicalScalar),
+            Some(A) => check_equation(&A, signature, &finish_challenge(hash)),
+            None => Err(Error::InvalidPublicKey),
+        };
+        #[cfg(feature = "observer")]
+        observer::verified(self, verdict);
+        verdict
+    }
+}
+
+/// Verifies a signature with an already decoded public key: `public` is
+/// the encoding and `A` the point returned by `P3::decode` (negated),
+/// already checked against `options`.
+///
+/// Returns `Ok(())` if the signature is valid and `Err` otherwise.
+pub(crate) fn verify_decoded<H, F>(
+    public: &[u8; 32],
+    A: &P3,
+    sig: &Signature,
+    dom2: Option<(u8, &[u8])>,
+    options: &VerificationOptions,
+    absorb: F,
+) -> Result<(), Error>
+where
+    H: Sha512Provider,
+    F: Fn(&mut H),
+{
+    let signature = sig.as_bytes();
+    let s = &signature[32..64];
+
+    if !check_lt_l(s) {
+        return Err(Error::NonCanonicalScalar);
+    }
+
+    // Without the cofactor and the checks on R, R is only decoded when
+    // the equation fails, by `check_equation`.
+    let R = if options.cofactored || options.reject_noncanonical_r || options.reject_small_order_r {
+        let mut R = [0u8; 32];
+        R.copy_from_slice(&signature[..32]);
+        Some(decode_point(
+            R,
+            Error::InvalidSignaturePoint,
+            options.reject_noncanonical_r,
+            options.reject_small_order_r,
+        )?)
+    } else {
+        None
+    };
+
+    let k = challenge(&signature[0..32], public, dom2, absorb);
+    match R {
+        Some(R) if options.cofactored => check_cofactored_equation(A, &R, s, &k),
+        _ => check_equation(A, signature, &k),
+    }
+}
+
+/// Decodes a point, rejecting non-canonical encodings and points of
+/// small order when asked to.
+///
+/// Returns the point as `P3::decode` does (negated), `Err(invalid)` if
+/// `enc` isn't the encoding of a curve point,
+/// `Err(Error::NonCanonicalPoint)` if it isn't the canonical one and
+/// `Err(Error::SmallOrderPoint)` if the point has small order.
+pub(crate) fn decode_point(
+    enc: [u8; 32],
+    invalid: Error,
+    reject_noncanonical: bool,
+    reject_small_order: bool,
+) -> Result<P3, Error> {
+    let P = P3::decode(enc).ok_or(invalid)?;
+    // Re-encoding gives the canonical encoding: y reduced, and no sign
+    // bit when x = 0.
+    if reject_noncanonical && P.negate().encode() != enc {
+        return Err(Error::NonCanonicalPoint);
+    }
+    if reject_small_order && P.is_small_order() {
+        return Err(Error::SmallOrderPoint);
+    }
+    Ok(P)
+}
+
+/// Checks the group equation of a signature whose s is already known to
+/// be reduced, with `A` as returned by `P3::decode` (negated) and `k` the
+/// reduced challenge.
+///
+/// Returns `Ok(())` if the signature is valid and `Err` otherwise.
+pub(crate) fn check_equation(A: &P3, signature: &[u8; 64], k: &[u8; 32]) -> Result<(), Error> {
+    let s = &signature[32..64];
+
+    // Check the group equation [s]B = R + [k]A'.
+    // Perform [s]B + [k]A'.
+    let eq = P2::double_scalar_multiply_vartime(k, s, *A);
+    // Check [s]B + [k]A' == R?
+    if eq
+        .encode()
+        .as_ref()
+        .iter()
+        .zip(signature.iter())
+        .fold(0, |acc, (x, y)| acc | (x ^ y))
+        == 0
+    {
+        Ok(())
+    } else {
+        // Only failures pay for decoding R, to tell a malformed R from a
+        // well-formed signature that doesn't verify.
+        let mut R = [0u8; 32];
+        R.copy_from_slice(&signature[..32]);
+        match P3::decode(R) {
+            Some(_) => Err(Error::SignatureMismatch),
+            None => Err(Error::InvalidSignaturePoint),
+        }
+    }
+}
+
+/// Checks the cofactored group equation [8][s]B = [8]R + [8][k]A, with
+/// `A` and `R` as returned by `P3::decode` (negated), `s` reduced and `k`
+/// the reduced challenge.
+///
+/// Returns `Ok(())` if it holds and `Err(Error::SignatureMismatch)`
+/// otherwise.
+pub(crate) fn check_cofactored_equation(
+    A: &P3,
+    R: &P3,
+    s: &[u8],
+    k: &[u8; 32],
+) -> Result<(), Error> {
+    // [s]B - [k]A - R.
+    let difference = P2::double_scalar_multiply_vartime(k, s, *A).to_P3() + R.to_Cached();
+    if difference.to_P3().multiply_by_cofactor().is_identity() {
+        Ok(())
+    } else {
+        Err(Error::SignatureMismatch)
+    }
+}
+
+/// Computes SHA512(dom2 || R || A || PH(M)), where `absorb` feeds PH(M)
+/// to the hash, and interprets the 64-octet digest as a little-endian
+/// integer.
+///
+/// Returns the challenge k, reduced modulo L.
+pub(crate) fn challenge<H, F>(
+    R: &[u8],
+    public: &[u8; 32],
+    dom2: Option<(u8, &[u8])>,
+    absorb: F,
+) -> [u8; 32]
+where
+    H: Sha512Provider,
+    F: Fn(&mut H),
+{
+    let mut hash = challenge_hash(R, public, dom2);
+    absorb(&mut hash);
+    finish_challenge(hash)
+}
+
+/// Starts the challenge hash: SHA512(dom2 || R || A), for the caller to
+/// feed PH(M) to.
+pub(crate) fn challenge_hash<H: Sha512Provider>(
+    R: &[u8],
+    public: &[u8; 32],
+    dom2: Option<(u8, &[u8])>,
+) -> H {
+    let mut hash = H::default();
+    write_domain(&mut hash, dom2);
+    hash.update(R);
+    hash.update(public);
+    hash
+}
+
+/// Finishes a challenge hash started by `challenge_hash`.
+///
+/// Returns the challenge k, reduced modulo L.
+pub(crate) fn finish_challenge<H: Sha512Provider>(hash: H) -> [u8; 32] {
+    let mut digest = hash.finalize();
+    reduce(&mut digest);
+
+    let mut k = [0u8; 32];
+    k.copy_from_slice(&digest[..32]);
+    k
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    #[test]
+    fn verify_failures_map_to_variants() {
+        // RFC 8032, TEST 1.
+        let public = PublicKey::from_bytes(
+            &hex::decode("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .unwrap(),
+        )
+        .unwrap();
+        let signature = hex::decode("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap();
+        let sig = Signature::from_bytes(&signature).unwrap();
+        assert_eq!(public.verify(b"", &sig), Ok(()));
+        assert_eq!(public.verify(b"x", &sig), Err(Error::SignatureMismatch));
+
+        // y = 2 has no x on the curve.
+        let mut off_curve = [0u8; 32];
+        off_curve[0] = 2;
+        assert_eq!(
+            PublicKey(off_curve).verify(b"", &sig),
+            Err(Error::InvalidPublicKey)
+        );
+        let mut bad_r = sig.to_bytes();
+        bad_r[..32].copy_from_slice(&off_curve);
+        assert_eq!(
+            public.verify(b"", &Signature(bad_r)),
+            Err(Error::InvalidSignaturePoint)
+        );
+
+        // s + L, and the bounds around L.
+        let mut s_plus_l = sig.to_bytes();
+        let mut carry = 0u16;
+        for (i, l) in L.iter().enumerate() {
+            let sum = signature[32 + i] as u16 + *l as u16 + carry;
+            s_plus_l[32 + i] = sum as u8;
+            carry = sum >> 8;
+        }
+        assert_eq!(
+            public.verify(b"", &Signature(s_plus_l)),
+            Err(Error::NonCanonicalScalar)
+        );
+        let mut l_minus_one = L;
+        l_minus_one[0] -= 1;
+        assert!(check_lt_l(&l_minus_one));
+        assert!(!check_lt_l(&L));
+        assert!(!check_lt_l(&[0xff; 32]));
+        assert!(check_lt_l(&[0; 32]));
+    }
+
+    // The eight points of order dividing 8, canonically encoded.
+    const EIGHT_TORSION: [&str; 8] = [
+        "0100000000000000000000000000000000000000000000000000000000000000",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a",
+        "0000000000000000000000000000000000000000000000000000000000000080",
+        "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+        "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+        "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc85",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac03fa",
+    ];
+
+    fn point(hex_encoding: &str) -> [u8; 32] {
+        let mut enc = [0u8; 32];
+        enc.copy_from_slice(&hex::decode(hex_encoding).unwrap());
+        enc
+    }
+
+    #[test]
+    fn strict_decoding_rejects_torsion_and_non_canonical_points() {
+        for torsion in EIGHT_TORSION.iter() {
+            let enc = point(torsion);
+            assert!(P3::decode(enc).is_some());
+            assert_eq!(
+                decode_point(enc, Error::InvalidPublicKey, true, true).err(),
+                Some(Error::SmallOrderPoint)
+            );
+        }
+
+        let basepoint = point("5866666666666666666666666666666666666666666666666666666666666666");
+        assert!(decode_point(basepoint, Error::InvalidPublicKey, true, true).is_ok());
+        // y = p + 1, the identity again.
+        let one_plus_p = point("eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f");
+        assert!(P3::decode(one_plus_p).is_some());
+        assert_eq!(
+            decode_point(one_plus_p, Error::InvalidPublicKey, true, true).err(),
+            Some(Error::NonCanonicalPoint)
+        );
+        // The identity with the sign bit of x set.
+        let mut negative_zero = point(EIGHT_TORSION[0]);
+        negative_zero[31] |= 0x80;
+        assert_eq!(
+            decode_point(negative_zero, Error::InvalidSignaturePoint, true, true).err(),
+            Some(Error::NonCanonicalPoint)
+        );
+        let mut off_curve = [0u8; 32];
+        off_curve[0] = 2;
+        assert_eq!(
+            decode_point(off_curve, Error::InvalidSignaturePoint, true, true).err(),
+            Some(Error::InvalidSignaturePoint)
+        );
+    }
+
+    // Signs with the key a = 42 and the nonce r = 23, after adding the
+    // small-order point `torsion_a` to A and `torsion_r` to R.
+    fn sign_with_torsion(
+        message: &[u8],
+        torsion_a: &str,
+        torsion_r: &str,
+    ) -> (PublicKey, Signature, [u8; 32]) {
+        let plus_torsion = |scalar: &[u8; 32], torsion: &str| {
+            let T = P3::decode(point(torsion)).unwrap().negate();
+            (Precomp::scalar_multiply(scalar) + T.to_Cached())
+                .to_P3()
+                .encode()
+        };
+        let mut a = [0u8; 32];
+        a[0] = 42;
+        let mut r = [0u8; 32];
+        r[0] = 23;
+        let A = plus_torsion(&a, torsion_a);
+        let R = plus_torsion(&r, torsion_r);
+
+        let k = challenge(&R, &A, None, |hash: &mut Sha512| hash.input(message));
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(&R);
+        multiply_add(&mut signature[32..], &k, &a, &r);
+        (PublicKey(A), Signature(signature), k)
+    }
+
+    #[test]
+    fn cofactored_and_cofactorless_equations_disagree_on_torsion() {
+        let identity = EIGHT_TORSION[0];
+        let mut disagreements = 0;
+        let (public, sig, _) = sign_with_torsion(b"message", identity, identity);
+        assert_eq!(public.verify(b"message", &sig), Ok(()));
+        assert_eq!(public.verify_cofactored(b"message", &sig), Ok(()));
+
+        for torsion in EIGHT_TORSION[1..].iter() {
+            // [s]B - [k]A = [r]B, which is R without its torsion.
+            let (public, sig, _) = sign_with_torsion(b"message", identity, torsion);
+            assert_eq!(
+                public.verify(b"message", &sig),
+                Err(Error::SignatureMismatch)
+            );
+            assert_eq!(public.verify_cofactored(b"message", &sig), Ok(()));
+            assert_eq!(public.verify_zip215(b"message", &sig), Ok(()));
+
+            // [s]B - [k]A = R - [k]T, so the cofactorless equation holds
+            // when [k]T is the identity and fails otherwise.
+            for message in [&b"message"[..], b"torsion", b"Zcash"].iter() {
+                let (public, sig, k) = sign_with_torsion(message, torsion, identity);
+                let T = P3::decode(point(torsion)).unwrap();
+                let kT = P2::double_scalar_multiply_vartime(&k, &[0u8; 32], T).to_P3();
+                let expected = if kT.is_identity() {
+                    Ok(())
+                } else {
+                    disagreements += 1;
+                    Err(Error::SignatureMismatch)
+                };
+                assert_eq!(public.verify(message, &sig), expected);
+                assert_eq!(public.verify_cofactored(message, &sig), Ok(()));
+            }
+        }
+        assert!(disagreements > 0);
+
+        // The cofactored equation still binds the signature to the key
+        // and the message.
+        let (public, sig, _) = sign_with_torsion(b"message", EIGHT_TORSION[1], EIGHT_TORSION[2]);
+        assert_eq!(
+            public.verify_cofactored(b"massage", &sig),
+            Err(Error::SignatureMismatch)
+        );
+        let (other, _, _) = sign_with_torsion(b"message", identity, identity);
+        assert_eq!(
+            other.verify_cofactored(b"message", &sig),
+            Err(Error::SignatureMismatch)
+        );
+    }
+
+    #[test]
+    fn cofactored_verification_rejects_non_canonical_points() {
+        let (public, sig, _) = sign_with_torsion(b"", EIGHT_TORSION[0], EIGHT_TORSION[0]);
+        let one_plus_p = point("eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f");
+        assert_eq!(
+            PublicKey(one_plus_p).verify_cofactored(b"", &sig),
+            Err(Error::NonCanonicalPoint)
+        );
+        let mut bad_r = sig.to_bytes();
+        bad_r[..32].copy_from_slice(&one_plus_p);
+        assert_eq!(
+            public.verify_cofactored(b"", &Signature(bad_r)),
+            Err(Error::NonCanonicalPoint)
+        );
+        // ZIP-215 takes both.
+        let mut zero_s = [0u8; 64];
+        zero_s[..32].copy_from_slice(&one_plus_p);
+        assert_eq!(
+            PublicKey(one_plus_p).verify_zip215(b"", &Signature(zero_s)),
+            Ok(())
+        );
+    }
+
+    // Tripwire: the verification path must not be able to name secret
+    // key material. Comments and the test modules are not scanned, and
+    // the needles are split so this test doesn't match itself.
+    #[test]
+    fn verify_module_has_no_secret_access() {
+        let sources = [
+            include_str!("verify.rs"),
+            include_str!("precomputed.rs"),
+            include_str!("batch.rs"),
+        ];
+        let forbidden = [
+            concat!("Secret", "Key"),
+            concat!("Expanded", "Secret"),
+            concat!("crate::", "secret"),
+            concat!("crate::", "keypair"),
+            concat!("Key", "pair"),
+        ];
+        for source in sources.iter() {
+            let code = source.split(concat!("mod ", "tests")).next().unwrap();
+            for line in code.lines().filter(|l| !l.trim_start().starts_with("//")) {
+                for needle in forbidden.iter() {
+                    assert!(
+                        !line.contains(needle),
+                        "verification must not reference {}: {}",
+                        needle,
+                        line
+                    );
+                }
+            }
+        }
+    }
+}
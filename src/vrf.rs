@@ -0,0 +1,433 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// ECVRF-EDWARDS25519-SHA512-TAI, the verifiable random function of
+// RFC 9381 over edwards25519, with try-and-increment hash-to-curve.
+//
+// The keys are those of Ed25519: the secret key is a 32-byte seed
+// expanded with SHA-512 and clamped, and the public key is [x]B. A proof
+// of alpha is (Gamma, c, s), with Gamma = [x]H for H the hash of alpha to
+// the curve, and its output beta is the hash of [8]Gamma. Only the holder
+// of the secret key can compute beta, and anyone with the public key can
+// check it. The keys have their own types, so that a VRF key can't be
+// used to sign.
+
+#![allow(non_snake_case)]
+
+use crate::curve25519::group_element::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::curve25519::scalar_ops::*;
+
+use crate::constants::*;
+use crate::errors::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::rng::*;
+use crate::verify::*;
+
+#[cfg(not(feature = "verify-only"))]
+use core::fmt;
+#[cfg(not(feature = "verify-only"))]
+use rand_core::RngCore;
+use sha2::{Digest, Sha512};
+#[cfg(not(feature = "verify-only"))]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+// The suite string of ECVRF-EDWARDS25519-SHA512-TAI.
+const SUITE: u8 = 0x03;
+
+/// The length of a proof: Gamma, then c on 16 bytes and s on 32.
+pub const VRF_PROOF_LENGTH: usize = 80;
+
+/// A VRF secret key: a 32-byte seed, as an Ed25519 secret key.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::vrf::VrfSecretKey;
+///
+/// fn main() {
+///     let secret = VrfSecretKey::generate();
+///     let public = secret.public_key();
+///     let proof = secret.prove(b"round 7");
+///     assert_eq!(public.verify(b"round 7", &proof), Ok(proof.to_hash()));
+///     assert!(public.verify(b"round 8", &proof).is_err());
+/// }
+/// ```
+#[cfg(not(feature = "verify-only"))]
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct VrfSecretKey([u8; SecretKeySize]);
+
+/// A VRF public key.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VrfPublicKey([u8; PublicKeySize]);
+
+/// A VRF proof: Gamma, c and s. `from_bytes` only takes well-formed
+/// ones, so `to_hash` can't fail.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VrfProof([u8; VRF_PROOF_LENGTH]);
+
+#[cfg(not(feature = "verify-only"))]
+impl VrfSecretKey {
+    /// Generates a secret key from the OS randomness.
+    pub fn generate() -> Self {
+        let mut secret = VrfSecretKey([0u8; SecretKeySize]);
+        default_rng().fill_bytes(&mut secret.0);
+        secret
+    }
+
+    /// Constructs `VrfSecretKey` from its 32 bytes.
+    ///
+    /// Returns `Ok(VrfSecretKey)` or `Err(Error::InvalidSecretKeyLength)`
+    /// if `bytes` isn't 32 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != SecretKeySize {
+            return Err(Error::InvalidSecretKeyLength {
+                expected: SecretKeySize,
+                got: bytes.len(),
+            });
+        }
+        let mut secret = VrfSecretKey([0u8; SecretKeySize]);
+        secret.0.copy_from_slice(bytes);
+        Ok(secret)
+    }
+
+    /// Returns the 32 bytes of the secret key.
+    pub fn as_bytes(&self) -> &[u8; SecretKeySize] {
+        &self.0
+    }
+
+    /// The clamped scalar x of the key, then the nonce prefix.
+    fn expand(&self) -> [u8; 64] {
+        let mut h = [0u8; 64];
+        h.copy_from_slice(&Sha512::digest(&self.0));
+        h[0] &= 248;
+        h[31] &= 63;
+        h[31] |= 64;
+        h
+    }
+
+    /// Computes the `VrfPublicKey` of the secret key.
+    pub fn public_key(&self) -> VrfPublicKey {
+        let mut h = self.expand();
+        let public = Precomp::scalar_multiply(&h[0..32]).encode();
+        h.zeroize();
+        VrfPublicKey(public)
+    }
+
+    /// Proves the VRF output of `alpha`.
+    ///
+    /// Returns `VrfProof`, whose `to_hash` is the output beta.
+    pub fn prove(&self, alpha: &[u8]) -> VrfProof {
+        let mut h = self.expand();
+        let Y = Precomp::scalar_multiply(&h[0..32]);
+        let H = encode_to_curve(&Y.encode(), alpha);
+        let Gamma = H.scalar_multiply(&h[0..32]);
+
+        // k = SHA-512(prefix || enc(H)) mod L, as the nonce of Ed25519.
+        let mut k = Sha512::new().chain(&h[32..64]).chain(&H.encode()).result();
+        reduce(&mut k[..]);
+        let kB = Precomp::scalar_multiply(&k[0..32]);
+        let kH = H.scalar_multiply(&k[0..32]);
+        let c = challenge(&Y, &H, &Gamma, &kB, &kH);
+
+        // s = (k + c * x) mod L.
+        let mut proof = [0u8; VRF_PROOF_LENGTH];
+        proof[0..32].copy_from_slice(&Gamma.encode());
+        proof[32..48].copy_from_slice(&c[0..16]);
+        multiply_add(&mut proof[48..80], &c, &h[0..32], &k[0..32]);
+        h.zeroize();
+        k.as_mut_slice().zeroize();
+
+        VrfProof(proof)
+    }
+}
+
+/// Formats `VrfSecretKey` with its bytes redacted.
+#[cfg(not(feature = "verify-only"))]
+impl fmt::Debug for VrfSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VrfSecretKey([REDACTED])")
+    }
+}
+
+impl VrfPublicKey {
+    /// Constructs `VrfPublicKey` from its 32 bytes.
+    ///
+    /// Returns `Ok(VrfPublicKey)` or `Err(Error::InvalidPublicKeyLength)`
+    /// if `bytes` isn't 32 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != PublicKeySize {
+            return Err(Error::InvalidPublicKeyLength {
+                expected: PublicKeySize,
+                got: bytes.len(),
+            });
+        }
+        let mut public = [0u8; PublicKeySize];
+        public.copy_from_slice(bytes);
+        Ok(VrfPublicKey(public))
+    }
+
+    /// Returns the 32 bytes of the public key.
+    pub fn as_bytes(&self) -> &[u8; PublicKeySize] {
+        &self.0
+    }
+
+    /// Verifies a proof of `alpha`, validating the public key as RFC 9381
+    /// recommends.
+    ///
+    /// Returns `Ok(beta)`, the VRF output, if the proof is valid,
+    /// `Err(Error::InvalidPublicKey)` or `Err(Error::NonCanonicalPoint)`
+    /// if the public key isn't the canonical encoding of a curve point,
+    /// `Err(Error::SmallOrderPoint)` if it has small order and
+    /// `Err(Error::SignatureMismatch)` otherwise.
+    pub fn verify(&self, alpha: &[u8], proof: &VrfProof) -> Result<[u8; 64], Error> {
+        // Decoded points are negated: -Y here.
+        let Y = decode_point(self.0, Error::InvalidPublicKey, true, true)?;
+        let Gamma = proof.Gamma();
+        let H = encode_to_curve(&self.0, alpha);
+        let mut c = [0u8; 32];
+        c[0..16].copy_from_slice(&proof.0[32..48]);
+        let s = &proof.0[48..80];
+
+        // U = [s]B - [c]Y and V = [s]H - [c]Gamma.
+        let U = P2::double_scalar_multiply_vartime(&c, s, Y).to_P3();
+        let V = (H.scalar_multiply(s) - Gamma.scalar_multiply(&c).to_Cached()).to_P3();
+        if challenge(&Y.negate(), &H, &Gamma, &U, &V) != c {
+            return Err(Error::SignatureMismatch);
+        }
+        Ok(proof.to_hash())
+    }
+}
+
+impl VrfProof {
+    /// Constructs `VrfProof` from its 80 bytes.
+    ///
+    /// Returns `Ok(VrfProof)`, or `Err(Error::InvalidVrfProof)` if
+    /// `bytes` isn't 80 bytes long, Gamma isn't the canonical encoding of
+    /// a curve point or s isn't reduced.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != VRF_PROOF_LENGTH {
+            return Err(Error::InvalidVrfProof);
+        }
+        let mut Gamma = [0u8; 32];
+        Gamma.copy_from_slice(&bytes[0..32]);
+        decode_point(Gamma, Error::InvalidVrfProof, true, false)
+            .map_err(|_| Error::InvalidVrfProof)?;
+        if !check_lt_l(&bytes[48..80]) {
+            return Err(Error::InvalidVrfProof);
+        }
+        let mut proof = [0u8; VRF_PROOF_LENGTH];
+        proof.copy_from_slice(bytes);
+        Ok(VrfProof(proof))
+    }
+
+    /// Returns the 80 bytes of the proof.
+    pub fn to_bytes(&self) -> [u8; VRF_PROOF_LENGTH] {
+        self.0
+    }
+
+    /// Returns the 80 bytes of the proof.
+    pub fn as_bytes(&self) -> &[u8; VRF_PROOF_LENGTH] {
+        &self.0
+    }
+
+    /// Computes the VRF output beta of the proof: SHA-512(suite || 0x03
+    /// || enc([8]Gamma) || 0x00). Only trust it once `verify` accepted
+    /// the proof, or for a proof of your own.
+    ///
+    /// Returns the 64 bytes of beta.
+    pub fn to_hash(&self) -> [u8; 64] {
+        let mut beta = [0u8; 64];
+        beta.copy_from_slice(
+            &Sha512::new()
+                .chain([SUITE, 0x03])
+                .chain(self.Gamma().multiply_by_cofactor().encode())
+                .chain([0x00])
+                .result(),
+        );
+        beta
+    }
+
+    /// Gamma, checked by `from_bytes`.
+    fn Gamma(&self) -> P3 {
+        let mut Gamma = [0u8; 32];
+        Gamma.copy_from_slice(&self.0[0..32]);
+        match P3::decode(Gamma) {
+            Some(Gamma) => Gamma.negate(),
+            None => unreachable!("Gamma is checked by VrfProof::from_bytes"),
+        }
+    }
+}
+
+/// Hashes `alpha` to the curve by try and increment: the first of
+/// SHA-512(suite || 0x01 || public || alpha || ctr || 0x00), for ctr from
+/// 0, whose first 32 bytes are the canonical encoding of a point, times
+/// the cofactor.
+///
+/// Returns the point H.
+fn encode_to_curve(public: &[u8; 32], alpha: &[u8]) -> P3 {
+    // Each try succeeds with probability about 1/2.
+    for ctr in 0..=255u8 {
+        let digest = Sha512::new()
+            .chain([SUITE, 0x01])
+            .chain(public)
+            .chain(alpha)
+            .chain([ctr, 0x00])
+            .result();
+        let mut candidate = [0u8; 32];
+        candidate.copy_from_slice(&digest[0..32]);
+        if let Ok(H) = decode_point(candidate, Error::InvalidPublicKey, true, false) {
+            return H.negate().multiply_by_cofactor();
+        }
+    }
+    unreachable!("256 hashes in a row aren't curve points")
+}
+
+/// Computes the challenge c of the five points: the first 16 bytes of
+/// SHA-512(suite || 0x02 || enc(P1) || ... || enc(P5) || 0x00).
+///
+/// Returns c, zero-extended to a 32-byte scalar.
+fn challenge(P1: &P3, P2: &P3, P3: &P3, P4: &P3, P5: &P3) -> [u8; 32] {
+    let digest = Sha512::new()
+        .chain([SUITE, 0x02])
+        .chain(P1.encode())
+        .chain(P2.encode())
+        .chain(P3.encode())
+        .chain(P4.encode())
+        .chain(P5.encode())
+        .chain([0x00])
+        .result();
+    let mut c = [0u8; 32];
+    c[0..16].copy_from_slice(&digest[0..16]);
+    c
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // The examples of ECVRF-EDWARDS25519-SHA512-TAI in RFC 9381,
+    // appendix B.3: secret key, public key, alpha, proof and beta.
+    const VECTORS: [(&str, &str, &str, &str, &str); 3] = [
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "",
+            "8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f\
+             26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab12\
+             68a1b0db10836d9826a528ca76567805",
+            "90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff\
+             66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae",
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb",
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c",
+            "72",
+            "f3141cd382dc42909d19ec5110469e4feae18300e94f304590abdced48aed593\
+             3bf0864a62558b3ed7f2fea45c92a465301b3bbf5e3e54ddf2d935be3b67926d\
+             a3ef39226bbc355bdc9850112c8f4b02",
+            "eb4440665d3891d668e7e0fcaf587f1b4bd7fbfe99d0eb2211ccec90496310eb\
+             5e33821bc613efb94db5e5b54c70a848a0bef4553a41befc57663b56373a5031",
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7",
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025",
+            "af82",
+            "9bc0f79119cc5604bf02d23b4caede71393cedfbb191434dd016d30177ccbf80\
+             96bb474e53895c362d8628ee9f9ea3c0e52c7a5c691b6c18c9979866568add7a\
+             2d41b00b05081ed0f58ee5e31b3a970e",
+            "645427e5d00c62a23fb703732fa5d892940935942101e456ecca7bb217c61c45\
+             2118fec1219202a0edcf038bb6373241578be7217ba85a2687f7a0310b2df19f",
+        ),
+    ];
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn rfc_9381_vectors_prove() {
+        for (secret, public, alpha, proof, beta) in VECTORS.iter() {
+            let secret = VrfSecretKey::from_bytes(&hex::decode(secret).unwrap()).unwrap();
+            let alpha = hex::decode(alpha).unwrap();
+            assert_eq!(hex::encode(secret.public_key().as_bytes()), *public);
+            let pi = secret.prove(&alpha);
+            assert_eq!(hex::encode(pi.as_bytes()), *proof);
+            assert_eq!(hex::encode(pi.to_hash()), *beta);
+        }
+    }
+
+    #[test]
+    fn rfc_9381_vectors_verify() {
+        for (_, public, alpha, proof, beta) in VECTORS.iter() {
+            let public = VrfPublicKey::from_bytes(&hex::decode(public).unwrap()).unwrap();
+            let alpha = hex::decode(alpha).unwrap();
+            let proof = VrfProof::from_bytes(&hex::decode(proof).unwrap()).unwrap();
+            let output = public.verify(&alpha, &proof).unwrap();
+            assert_eq!(hex::encode(output), *beta);
+            assert_eq!(
+                public.verify(b"another alpha", &proof),
+                Err(Error::SignatureMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_proofs_are_rejected() {
+        let (_, public, alpha, proof, _) = VECTORS[1];
+        let public = VrfPublicKey::from_bytes(&hex::decode(public).unwrap()).unwrap();
+        let alpha = hex::decode(alpha).unwrap();
+        let bytes = hex::decode(proof).unwrap();
+
+        // A bit of Gamma, of c and of s.
+        for &index in [0, 32, 47, 48, 79].iter() {
+            let mut tampered = bytes.clone();
+            tampered[index] ^= 0x01;
+            match VrfProof::from_bytes(&tampered) {
+                Ok(proof) => {
+                    assert_eq!(public.verify(&alpha, &proof), Err(Error::SignatureMismatch))
+                }
+                Err(error) => assert_eq!(error, Error::InvalidVrfProof),
+            }
+        }
+
+        // s + L is the same scalar, but not reduced.
+        let mut s_plus_l = bytes.clone();
+        let L = [
+            0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9,
+            0xde, 0x14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10,
+        ];
+        let mut carry = 0u16;
+        for (i, l) in L.iter().enumerate() {
+            let sum = bytes[48 + i] as u16 + *l as u16 + carry;
+            s_plus_l[48 + i] = sum as u8;
+            carry = sum >> 8;
+        }
+        assert_eq!(VrfProof::from_bytes(&s_plus_l), Err(Error::InvalidVrfProof));
+        // Gamma off the curve, and a proof of the wrong length.
+        let mut off_curve = bytes.clone();
+        off_curve[0..32].copy_from_slice(&[0u8; 32]);
+        off_curve[0] = 2;
+        assert_eq!(
+            VrfProof::from_bytes(&off_curve),
+            Err(Error::InvalidVrfProof)
+        );
+        assert_eq!(
+            VrfProof::from_bytes(&bytes[..79]),
+            Err(Error::InvalidVrfProof)
+        );
+
+        // The proof under another key, and the identity as key.
+        let proof = VrfProof::from_bytes(&bytes).unwrap();
+        let (_, other, _, _, _) = VECTORS[0];
+        let other = VrfPublicKey::from_bytes(&hex::decode(other).unwrap()).unwrap();
+        assert_eq!(other.verify(&alpha, &proof), Err(Error::SignatureMismatch));
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        assert_eq!(
+            VrfPublicKey(identity).verify(&alpha, &proof),
+            Err(Error::SmallOrderPoint)
+        );
+    }
+}
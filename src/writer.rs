@@ -0,0 +1,256 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Writer shared by every format that builds signed or transmitted bytes,
+// the counterpart of `ExactReader`. Integers are only ever written through
+// an explicit little-endian conversion, so the bytes, and the signatures
+// over them, are the same on every target. The writer fills a caller
+// buffer and never allocates.
+
+use crate::constants::*;
+use crate::errors::*;
+
+/// Cursor over a caller buffer that errors on overflow and, through
+/// `finish`, on a buffer left partly unwritten.
+pub(crate) struct WireWriter<'a> {
+    out: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> WireWriter<'a> {
+    pub(crate) fn new(out: &'a mut [u8]) -> WireWriter<'a> {
+        WireWriter { out, pos: 0 }
+    }
+
+    /// Number of bytes written so far.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn written(&self) -> usize {
+        self.pos
+    }
+
+    /// Writes `bytes` as they are.
+    ///
+    /// Returns `Err(Error::BufferTooSmall)`, with nothing written, if
+    /// they don't fit.
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        if self.out.len() - self.pos < bytes.len() {
+            return Err(Error::BufferTooSmall);
+        }
+        self.out[self.pos..self.pos + bytes.len()].copy_from_slice(bytes);
+        self.pos += bytes.len();
+        Ok(())
+    }
+
+    /// Writes one byte.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn write_u8(&mut self, value: u8) -> Result<(), Error> {
+        self.write_bytes(&[value])
+    }
+
+    /// Writes a 16-bit little-endian integer.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn write_u16_le(&mut self, value: u16) -> Result<(), Error> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    /// Writes a 32-bit little-endian integer.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn write_u32_le(&mut self, value: u32) -> Result<(), Error> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    /// Writes a 64-bit little-endian integer.
+    pub(crate) fn write_u64_le(&mut self, value: u64) -> Result<(), Error> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    /// Writes the 64-bit little-endian length of `bytes`, then `bytes`.
+    ///
+    /// Returns `Err(Error::LengthOverflow)` if `bytes` is longer than
+    /// `MAX_MESSAGE_LEN` and `Err(Error::BufferTooSmall)`, with nothing
+    /// written, if the two don't fit.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub(crate) fn write_prefixed(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        if bytes.len() as u64 > MAX_MESSAGE_LEN {
+            return Err(Error::LengthOverflow);
+        }
+        if self.out.len() - self.pos < 8 + bytes.len() {
+            return Err(Error::BufferTooSmall);
+        }
+        self.write_u64_le(bytes.len() as u64)?;
+        self.write_bytes(bytes)
+    }
+
+    /// Checks that the whole buffer was written.
+    ///
+    /// Returns the number of bytes written, or `Err(Error::TrailingBytes)`
+    /// if the buffer is longer.
+    pub(crate) fn finish(self) -> Result<usize, Error> {
+        if self.pos != self.out.len() {
+            return Err(Error::TrailingBytes {
+                consumed: self.pos,
+                total: self.out.len(),
+            });
+        }
+        Ok(self.pos)
+    }
+}
+
+/// Writes the bytes built by `write` into a new buffer of exactly `len`
+/// bytes.
+#[cfg(feature = "std")]
+pub(crate) fn write_exact<F>(len: usize, write: F) -> Result<Vec<u8>, Error>
+where
+    F: FnOnce(&mut WireWriter) -> Result<(), Error>,
+{
+    let mut out = vec![0u8; len];
+    let mut writer = WireWriter::new(&mut out);
+    write(&mut writer)?;
+    writer.finish()?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+    use crate::multipart::*;
+    use sha2::digest::Input;
+
+    // Records the absorbed bytes.
+    #[derive(Default)]
+    struct Recorder(Vec<u8>);
+
+    impl Input for Recorder {
+        fn input<B: AsRef<[u8]>>(&mut self, data: B) {
+            self.0.extend_from_slice(data.as_ref());
+        }
+    }
+
+    #[test]
+    fn wire_writer_boundary_values() {
+        let mut out = [0xaau8; 30];
+        let mut writer = WireWriter::new(&mut out);
+        writer.write_u8(0).unwrap();
+        writer.write_u8(u8::MAX).unwrap();
+        writer.write_u16_le(0x0102).unwrap();
+        writer.write_u16_le(u16::MAX).unwrap();
+        writer.write_u32_le(0x0102_0304).unwrap();
+        writer.write_u64_le(0).unwrap();
+        writer.write_u64_le(u64::MAX).unwrap();
+        assert_eq!(writer.written(), 26);
+        assert_eq!(
+            writer.finish(),
+            Err(Error::TrailingBytes {
+                consumed: 26,
+                total: 30
+            })
+        );
+        assert_eq!(
+            hex::encode(&out[..26]),
+            "00ff0201ffff040302010000000000000000ffffffffffffffff"
+        );
+    }
+
+    #[test]
+    fn wire_writer_overflow_writes_nothing() {
+        let mut out = [0u8; 12];
+        let mut writer = WireWriter::new(&mut out);
+        writer.write_u32_le(u32::MAX).unwrap();
+        assert_eq!(writer.write_prefixed(b"x"), Err(Error::BufferTooSmall));
+        assert_eq!(writer.written(), 4);
+        writer.write_prefixed(b"").unwrap();
+        assert_eq!(writer.write_u8(1), Err(Error::BufferTooSmall));
+        assert_eq!(writer.finish(), Ok(12));
+        assert_eq!(out, [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn wire_writer_matches_reader_framing() {
+        let out = write_exact(8 + 3 + 8, |w| {
+            w.write_prefixed(b"abc")?;
+            w.write_u64_le(u64::MAX)
+        })
+        .unwrap();
+        assert_eq!(hex::encode(&out), "0300000000000000616263ffffffffffffffff");
+        assert_eq!(
+            write_exact(4, |w| w.write_u16_le(0)),
+            Err(Error::TrailingBytes {
+                consumed: 2,
+                total: 4
+            })
+        );
+    }
+
+    // The canonical bytes of every structured format for fixed inputs.
+    // A change here is a change of wire format: it breaks every
+    // signature made before it.
+    #[test]
+    fn structured_formats_frozen_bytes() {
+        // PAE examples of the PASETO specification (docs/01-Protocol-
+        // Versions/Common.md): PAE([]), PAE([""]) and PAE(["test"]).
+        let pae = |pieces: &[&[u8]]| {
+            let mut message: MultiPartMessage<1> = MultiPartMessage::new();
+            for piece in pieces {
+                message.push(piece).unwrap();
+            }
+            let mut recorder = Recorder::default();
+            message.absorb(&mut recorder);
+            hex::encode(recorder.0)
+        };
+        assert_eq!(pae(&[]), "0000000000000000");
+        assert_eq!(pae(&[b""]), "01000000000000000000000000000000");
+        assert_eq!(pae(&[b"test"]), "0100000000000000040000000000000074657374");
+
+        #[cfg(feature = "signify")]
+        assert_eq!(
+            crate::signify::encode_file(
+                "c",
+                &[1, 2, 3, 4, 5, 6, 7, 8],
+                &[0xaa, 0xbb],
+                Error::InvalidSignifySignature
+            ),
+            Ok("untrusted comment: c\nRWQBAgMEBQYHCKq7\n".to_string())
+        );
+
+        #[cfg(feature = "minisign")]
+        assert_eq!(
+            crate::minisign::global_message(&crate::signature::Signature([0x11; 64]), "t"),
+            Ok([&[0x11u8; 64][..], b"t"].concat())
+        );
+    }
+
+    // Nothing in the formats' sources serializes with the native byte
+    // order. The needle is split so this file doesn't match itself.
+    #[test]
+    fn formats_never_use_native_endianness() {
+        let sources = [
+            ("borsh.rs", include_str!("borsh.rs")),
+            ("codec.rs", include_str!("codec.rs")),
+            ("cose.rs", include_str!("cose.rs")),
+            ("did_key.rs", include_str!("did_key.rs")),
+            ("dnssec.rs", include_str!("dnssec.rs")),
+            ("dom2.rs", include_str!("dom2.rs")),
+            ("libp2p.rs", include_str!("libp2p.rs")),
+            ("minicbor.rs", include_str!("minicbor.rs")),
+            ("minisign.rs", include_str!("minisign.rs")),
+            ("multipart.rs", include_str!("multipart.rs")),
+            ("paseto.rs", include_str!("paseto.rs")),
+            ("reader.rs", include_str!("reader.rs")),
+            ("rotation.rs", include_str!("rotation.rs")),
+            ("seal.rs", include_str!("seal.rs")),
+            ("shamir.rs", include_str!("shamir.rs")),
+            ("signature_set.rs", include_str!("signature_set.rs")),
+            ("signify.rs", include_str!("signify.rs")),
+            ("solana.rs", include_str!("solana.rs")),
+            ("writer.rs", include_str!("writer.rs")),
+        ];
+        for (name, source) in sources.iter() {
+            for needle in [concat!("to_", "ne_bytes"), concat!("from_", "ne_bytes")].iter() {
+                assert!(!source.contains(needle), "{} uses {}", name, needle);
+            }
+        }
+    }
+}
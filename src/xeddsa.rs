@@ -0,0 +1,425 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// XEdDSA, Signal's signatures with X25519 keys: the Montgomery secret
+// scalar k is turned into the Edwards key pair (A, a) whose A has its
+// sign bit clear, negating k when [k]B has it set, so that A follows
+// from the Montgomery u alone. The signatures are Ed25519 signatures
+// under A, with a nonce hashed from a, the message and 64 random bytes.
+//
+// libsignal signs under [k]B itself and carries its sign bit in the top
+// bit of s, which a reduced s never sets. `xeddsa_verify` takes both
+// forms, as libsignal does, so keys and signatures go both ways; only
+// the form of the specification verifies through `PublicKey::verify`.
+
+#![allow(non_snake_case)]
+
+use crate::curve25519::constants::FieldOne;
+use crate::curve25519::field_element::FieldElement;
+#[cfg(not(feature = "verify-only"))]
+use crate::curve25519::group_element::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::curve25519::scalar_ops::*;
+
+use crate::constants::*;
+use crate::errors::*;
+use crate::public::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::rng::*;
+#[cfg(not(feature = "verify-only"))]
+use crate::secret::sign_with_nonce;
+use crate::signature::*;
+
+#[cfg(not(feature = "verify-only"))]
+use core::convert::Infallible;
+#[cfg(not(feature = "verify-only"))]
+use core::fmt;
+#[cfg(not(feature = "verify-only"))]
+use rand_core::RngCore;
+#[cfg(not(feature = "verify-only"))]
+use sha2::{Digest, Sha512};
+#[cfg(not(feature = "verify-only"))]
+use subtle::{Choice, ConditionallySelectable};
+#[cfg(not(feature = "verify-only"))]
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+// L - 1, that is -1 mod L.
+#[cfg(not(feature = "verify-only"))]
+const MINUS_ONE: [u8; 32] = [
+    0xec, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// An X25519 secret key.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate ed25519_fun;
+///
+/// use ed25519_fun::xeddsa::{xeddsa_sign, xeddsa_verify, XSecretKey};
+///
+/// fn main() {
+///     let secret = XSecretKey::generate();
+///     let public = secret.public_key();
+///     // 64 fresh random bytes for each signature.
+///     let nonce = [7u8; 64];
+///     let signature = xeddsa_sign(&secret, b"prekey", &nonce);
+///     assert!(xeddsa_verify(&public, b"prekey", &signature).is_ok());
+///     let ed25519 = public.to_ed25519().unwrap();
+///     assert!(ed25519.verify(b"prekey", &signature).is_ok());
+/// }
+/// ```
+#[cfg(not(feature = "verify-only"))]
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct XSecretKey([u8; SecretKeySize]);
+
+/// An X25519 public key: the u-coordinate of a Montgomery point.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct XPublicKey([u8; PublicKeySize]);
+
+#[cfg(not(feature = "verify-only"))]
+impl XSecretKey {
+    /// Generates a secret key from the OS randomness.
+    pub fn generate() -> Self {
+        let mut secret = XSecretKey([0u8; SecretKeySize]);
+        default_rng().fill_bytes(&mut secret.0);
+        secret
+    }
+
+    /// Constructs `XSecretKey` from its 32 bytes, clamped or not.
+    ///
+    /// Returns `Ok(XSecretKey)` or `Err(Error::InvalidSecretKeyLength)`
+    /// if `bytes` isn't 32 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != SecretKeySize {
+            return Err(Error::InvalidSecretKeyLength {
+                expected: SecretKeySize,
+                got: bytes.len(),
+            });
+        }
+        let mut secret = XSecretKey([0u8; SecretKeySize]);
+        secret.0.copy_from_slice(bytes);
+        Ok(secret)
+    }
+
+    /// Returns the 32 bytes of the secret key.
+    pub fn as_bytes(&self) -> &[u8; SecretKeySize] {
+        &self.0
+    }
+
+    /// The clamped scalar k, in 64 bytes for `reduce`.
+    fn clamped(&self) -> [u8; 64] {
+        let mut k = [0u8; 64];
+        k[0..32].copy_from_slice(&self.0);
+        k[0] &= 248;
+        k[31] &= 63;
+        k[31] |= 64;
+        k
+    }
+
+    /// Computes the `XPublicKey` of the secret key, as X25519 does:
+    /// u = (1 + y) / (1 - y) for the y of [k]B.
+    pub fn public_key(&self) -> XPublicKey {
+        let mut k = self.clamped();
+        let E = Precomp::scalar_multiply(&k[0..32]);
+        k.zeroize();
+        let u = (E.Z + E.Y) * (E.Z - E.Y).invert();
+        XPublicKey(u.encode())
+    }
+
+    /// Computes the Edwards key pair of the secret key: A = [k]B with its
+    /// sign bit cleared, and a = k, or -k mod L if [k]B had it set, so
+    /// that A = [a]B.
+    ///
+    /// Returns `(PublicKey, a)`, with a reduced.
+    pub fn calculate_key_pair(&self) -> (PublicKey, Zeroizing<[u8; 32]>) {
+        let mut k = self.clamped();
+        let mut A = Precomp::scalar_multiply(&k[0..32]).encode();
+        let negative = Choice::from(A[31] >> 7);
+        A[31] &= 127;
+
+        reduce(&mut k[..]);
+        let mut minus_k = [0u8; 32];
+        multiply_add(&mut minus_k, &MINUS_ONE, &k[0..32], &[0u8; 32]);
+        let mut a = Zeroizing::new([0u8; 32]);
+        for (i, byte) in a.iter_mut().enumerate() {
+            *byte = u8::conditional_select(&k[i], &minus_k[i], negative);
+        }
+        k.zeroize();
+        minus_k.zeroize();
+
+        (PublicKey(A), a)
+    }
+}
+
+/// Formats `XSecretKey` with its bytes redacted.
+#[cfg(not(feature = "verify-only"))]
+impl fmt::Debug for XSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("XSecretKey([REDACTED])")
+    }
+}
+
+impl XPublicKey {
+    /// Constructs `XPublicKey` from its 32 bytes.
+    ///
+    /// Returns `Ok(XPublicKey)` or `Err(Error::InvalidPublicKeyLength)`
+    /// if `bytes` isn't 32 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != PublicKeySize {
+            return Err(Error::InvalidPublicKeyLength {
+                expected: PublicKeySize,
+                got: bytes.len(),
+            });
+        }
+        let mut public = [0u8; PublicKeySize];
+        public.copy_from_slice(bytes);
+        Ok(XPublicKey(public))
+    }
+
+    /// Returns the 32 bytes of the public key.
+    pub fn as_bytes(&self) -> &[u8; PublicKeySize] {
+        &self.0
+    }
+
+    /// Converts the key to the Edwards public key of its signatures:
+    /// y = (u - 1) / (u + 1), with the sign bit clear. Like
+    /// `PublicKey::from_bytes`, it doesn't check that y is on the curve:
+    /// `verify` does.
+    ///
+    /// Returns `Ok(PublicKey)`, or `Err(Error::InvalidPublicKey)` if u
+    /// isn't below p.
+    pub fn to_ed25519(&self) -> Result<PublicKey, Error> {
+        let u = FieldElement::decode(self.0);
+        if u.encode() != self.0 {
+            return Err(Error::InvalidPublicKey);
+        }
+        // u = -1 inverts 0 to 0, and gives y = 0 as in the specification.
+        let y = (u - FieldOne) * (u + FieldOne).invert();
+        Ok(PublicKey(y.encode()))
+    }
+}
+
+/// Signs `message` with XEdDSA. `nonce` must be 64 fresh random bytes: r
+/// = SHA-512(0xfe || 0xff * 31 || a || M || Z), Z the nonce.
+///
+/// Returns the `Signature`, an Ed25519 signature under the `PublicKey`
+/// of `calculate_key_pair`.
+#[cfg(not(feature = "verify-only"))]
+pub fn xeddsa_sign(secret: &XSecretKey, message: &[u8], nonce: &[u8; 64]) -> Signature {
+    let (public, a) = secret.calculate_key_pair();
+
+    // The prefix is 2^256 - 2, never the canonical encoding of a point,
+    // so r is hashed apart from the challenge SHA-512(R || A || M).
+    let mut prefix = [0xffu8; 32];
+    prefix[0] = 0xfe;
+    let mut output = Sha512::new()
+        .chain(&prefix[..])
+        .chain(&a[..])
+        .chain(message)
+        .chain(&nonce[..])
+        .result();
+    let mut r = [0u8; 64];
+    r.copy_from_slice(&output);
+    output.as_mut_slice().zeroize();
+
+    let mut h = [0u8; 64];
+    h[0..32].copy_from_slice(&a[..]);
+    let signed = sign_with_nonce::<Sha512, _, Infallible>(&h, r, &public, None, |hash| {
+        hash.input(message);
+        Ok(())
+    });
+    h.zeroize();
+    match signed {
+        Ok((signature, _)) => signature,
+        Err(never) => match never {},
+    }
+}
+
+/// Verifies an XEdDSA signature, of the specification or of libsignal:
+/// `PublicKey::verify` under the `to_ed25519` of `public`, with its sign
+/// bit taken from the top bit of s, which is then cleared.
+///
+/// A signature of `xeddsa_sign`, or of libsignal whose [k]B has the sign
+/// bit clear, has that bit clear: the key is the one of `to_ed25519`.
+/// Either way, s must be reduced once the bit is cleared.
+///
+/// Returns `Ok(())` if the signature is valid, and the error of
+/// `to_ed25519` or of `PublicKey::verify` otherwise.
+pub fn xeddsa_verify(
+    public: &XPublicKey,
+    message: &[u8],
+    signature: &Signature,
+) -> Result<(), Error> {
+    let mut A = public.to_ed25519()?;
+    let mut signature = *signature;
+    if signature.0[63] & 0x80 != 0 {
+        signature.0[63] &= 0x7f;
+        A.0[31] |= 0x80;
+    }
+    A.verify(message, &signature)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate hex;
+
+    use super::*;
+
+    // The `test_signature` vector of the curve module of libsignal: the
+    // identity key of Alice, whose [k]B has its sign bit set, the
+    // ephemeral key it signs and libsignal's signature of it.
+    const ALICE_SECRET: &str = "c097248412e58bf05df48796820513279417\
+                                8e367637f5818f81e0e6ce73e865";
+    const ALICE_PUBLIC: &str = "ab7e717d4a163b7d9a1d8071dfe9dcf8cdcd\
+                                1cea3339b6356be84d887e322c64";
+    const ALICE_EPHEMERAL: &str = "05edce9d9c415ca78cb7252e72c2c4a554d3eb\
+                                   29485a0e1d503118d1a82d99fb4a";
+    const ALICE_SIGNATURE: &str = "5de88ca9a89b4a115da79109c67c9c7464a3e4\
+                                   180274f1cb8c63c2984e286dfbede82deb9dcd\
+                                   9fae0bfbb821569b3d9001bd8130cd11d486ce\
+                                   f047bd60b86e88";
+
+    // Secret key, X25519 public key, Edwards public key, message, nonce
+    // and signature. The specification publishes no vectors: the keys
+    // are libsignal's and the key 0x02 * 32, and the signatures come
+    // from a Python transcription of the specification's pseudocode,
+    // with Python's integers for the field and the group.
+    const VECTORS: [(&str, &str, &str, &str, &str, &str); 3] = [
+        (
+            ALICE_SECRET,
+            ALICE_PUBLIC,
+            "6e76ed464e9784130e359de1d7cd59c71d12abb745b0c436f7a2a0c93bc56244",
+            ALICE_EPHEMERAL,
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+             202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f",
+            "1580b2de018ab1cfafc94dd56d9df41f100bcd2648fb5a6afa255b82a9a668d6\
+             fc7c6c8f0ddd27e8aabb0145c98ab4ee018d8cb8088433e4c023d6935a9f710c",
+        ),
+        (
+            ALICE_SECRET,
+            ALICE_PUBLIC,
+            "6e76ed464e9784130e359de1d7cd59c71d12abb745b0c436f7a2a0c93bc56244",
+            "",
+            "0000000000000000000000000000000000000000000000000000000000000000\
+             0000000000000000000000000000000000000000000000000000000000000000",
+            "2247c6acd71420bea2e15117ca8442b45697fa6e8bd64b1e36ec5e4abf9ee756\
+             b00bcde515cf87a6ddc26402c72562c0d16a0a76ae385853be734d9d2e4e2905",
+        ),
+        (
+            "0202020202020202020202020202020202020202020202020202020202020202",
+            "ce8d3ad1ccb633ec7b70c17814a5c76ecd029685050d344745ba05870e587d59",
+            "63726524db47758a263a39f2441b8498d9c80dc4888a5d8a18dfebe07413cd1a",
+            "584564445341",
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+             202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f",
+            "e7a0f97e5843d68e6a9b0efe048c05589ba4267513621a0d31f7779363847c37\
+             be7439a2690263bbd721825b5ba0a32ab2e3183223baac92c9f3cc6682da6303",
+        ),
+    ];
+
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    fn xeddsa_vectors_sign() {
+        for (secret, x25519, ed25519, message, nonce, signature) in VECTORS.iter() {
+            let secret = XSecretKey::from_bytes(&hex::decode(secret).unwrap()).unwrap();
+            let message = hex::decode(message).unwrap();
+            let mut z = [0u8; 64];
+            z.copy_from_slice(&hex::decode(nonce).unwrap());
+
+            assert_eq!(hex::encode(secret.public_key().as_bytes()), *x25519);
+            let (public, a) = secret.calculate_key_pair();
+            assert_eq!(hex::encode(public.as_bytes()), *ed25519);
+            let mut check = [0u8; 64];
+            check[..32].copy_from_slice(&a[..]);
+            reduce(&mut check);
+            assert_eq!(
+                Precomp::scalar_multiply(&a[..]).encode(),
+                *public.as_bytes()
+            );
+            assert_eq!(check[..32], a[..]);
+
+            let signed = xeddsa_sign(&secret, &message, &z);
+            assert_eq!(hex::encode(signed.to_bytes()), *signature);
+        }
+    }
+
+    #[test]
+    fn xeddsa_vectors_verify() {
+        for (_, x25519, ed25519, message, _, signature) in VECTORS.iter() {
+            let public = XPublicKey::from_bytes(&hex::decode(x25519).unwrap()).unwrap();
+            let message = hex::decode(message).unwrap();
+            let signature = Signature::from_bytes(&hex::decode(signature).unwrap()).unwrap();
+
+            let converted = public.to_ed25519().unwrap();
+            assert_eq!(hex::encode(converted.as_bytes()), *ed25519);
+            assert_eq!(xeddsa_verify(&public, &message, &signature), Ok(()));
+            assert_eq!(converted.verify(&message, &signature), Ok(()));
+            assert_eq!(
+                xeddsa_verify(&public, b"another message", &signature),
+                Err(Error::SignatureMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn libsignal_signature_verifies() {
+        let public = XPublicKey::from_bytes(&hex::decode(ALICE_PUBLIC).unwrap()).unwrap();
+        let message = hex::decode(ALICE_EPHEMERAL).unwrap();
+        let bytes = hex::decode(ALICE_SIGNATURE).unwrap();
+        let libsignal = Signature::from_bytes(&bytes).unwrap();
+
+        // The top bit of s is set: the key is -A of `to_ed25519`.
+        assert_eq!(xeddsa_verify(&public, &message, &libsignal), Ok(()));
+        assert_eq!(
+            xeddsa_verify(&public, b"another message", &libsignal),
+            Err(Error::SignatureMismatch)
+        );
+        assert_eq!(
+            public.to_ed25519().unwrap().verify(&message, &libsignal),
+            Err(Error::NonCanonicalScalar)
+        );
+
+        // Without the bit, s is checked under the other key.
+        let mut cleared = bytes.clone();
+        cleared[63] &= 0x7f;
+        let cleared = Signature::from_bytes(&cleared).unwrap();
+        assert_eq!(
+            xeddsa_verify(&public, &message, &cleared),
+            Err(Error::SignatureMismatch)
+        );
+    }
+
+    #[test]
+    fn invalid_keys_and_signatures_are_rejected() {
+        let public = XPublicKey::from_bytes(&hex::decode(ALICE_PUBLIC).unwrap()).unwrap();
+        let message = hex::decode(ALICE_EPHEMERAL).unwrap();
+
+        // A signature of the specification with the bit set.
+        let (_, _, _, _, _, signature) = VECTORS[0];
+        let mut flipped = hex::decode(signature).unwrap();
+        flipped[63] |= 0x80;
+        let flipped = Signature::from_bytes(&flipped).unwrap();
+        assert_eq!(
+            xeddsa_verify(&public, &message, &flipped),
+            Err(Error::SignatureMismatch)
+        );
+
+        // u = p and u with the top bit set.
+        let mut p = [0xffu8; 32];
+        p[0] = 0xed;
+        p[31] = 0x7f;
+        assert_eq!(XPublicKey(p).to_ed25519(), Err(Error::InvalidPublicKey));
+        let mut high = *public.as_bytes();
+        high[31] |= 0x80;
+        assert_eq!(XPublicKey(high).to_ed25519(), Err(Error::InvalidPublicKey));
+        assert_eq!(
+            XPublicKey::from_bytes(&[9u8; 31]),
+            Err(Error::InvalidPublicKeyLength {
+                expected: 32,
+                got: 31
+            })
+        );
+    }
+}
@@ -0,0 +1,189 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// The example CLI, driven through `std::process::Command` on temporary
+// files. `cargo test` builds the examples next to the test binaries.
+
+#![cfg(not(feature = "verify-only"))]
+
+extern crate ed25519_fun;
+extern crate hex;
+
+use ed25519_fun::{PublicKey, SecretKey};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// target/<profile>/examples/cli, from target/<profile>/deps/cli-<hash>.
+fn cli() -> Command {
+    let mut path = env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.push("examples");
+    path.push(format!("cli{}", env::consts::EXE_SUFFIX));
+    assert!(path.exists(), "{} not built", path.display());
+    Command::new(path)
+}
+
+/// A fresh directory for one test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("ed25519-fun-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs `command` with the `--name path` options, and returns the exit
+/// status.
+fn run(command: &str, options: &[(&str, &PathBuf)]) -> i32 {
+    let mut cli = cli();
+    cli.arg(command);
+    for (name, path) in options {
+        cli.arg(name).arg(path);
+    }
+    cli.output().unwrap().status.code().unwrap()
+}
+
+#[test]
+fn cli_keygen_sign_verify() {
+    let dir = temp_dir("roundtrip");
+    let key = dir.join("key.hex");
+    let public = dir.join("pk.hex");
+    let input = dir.join("file");
+    let sig = dir.join("file.sig");
+    // A 1 MiB input.
+    fs::write(&input, vec![0x5a; 1 << 20]).unwrap();
+
+    let output = cli()
+        .args(&["keygen", "--out"])
+        .arg(&key)
+        .arg("--pub")
+        .arg(&public)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let secret = SecretKey::from_hex(fs::read_to_string(&key).unwrap().trim()).unwrap();
+    let public_hex = fs::read_to_string(&public).unwrap();
+    assert_eq!(public_hex.trim(), secret.public_key().to_string());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), public_hex);
+
+    assert_eq!(
+        run(
+            "sign",
+            &[("--key", &key), ("--in", &input), ("--out", &sig)]
+        ),
+        0
+    );
+    assert_eq!(fs::read(&sig).unwrap().len(), 64);
+    assert_eq!(
+        run(
+            "verify",
+            &[("--pub", &public), ("--in", &input), ("--sig", &sig)]
+        ),
+        0
+    );
+
+    // A changed message is a bad signature.
+    fs::write(&input, b"other").unwrap();
+    assert_eq!(
+        run(
+            "verify",
+            &[("--pub", &public), ("--in", &input), ("--sig", &sig)]
+        ),
+        1
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_rfc8032_vector() {
+    let dir = temp_dir("rfc8032");
+    let key = dir.join("key.hex");
+    let public = dir.join("pk.hex");
+    let input = dir.join("file");
+    let sig = dir.join("file.sig");
+    // RFC 8032, test 2.
+    fs::write(
+        &key,
+        "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb\n",
+    )
+    .unwrap();
+    fs::write(&input, [0x72]).unwrap();
+
+    assert_eq!(
+        run(
+            "sign",
+            &[("--key", &key), ("--in", &input), ("--out", &sig)]
+        ),
+        0
+    );
+    assert_eq!(
+        hex::encode(fs::read(&sig).unwrap()),
+        "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da\
+         085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+    );
+    let expected = PublicKey::from_bytes(
+        &hex::decode("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c").unwrap(),
+    )
+    .unwrap();
+    fs::write(&public, expected.to_string()).unwrap();
+    assert_eq!(
+        run(
+            "verify",
+            &[("--pub", &public), ("--in", &input), ("--sig", &sig)]
+        ),
+        0
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_bad_input_exit_status() {
+    let dir = temp_dir("bad-input");
+    let key = dir.join("key.hex");
+    let public = dir.join("pk.hex");
+    let input = dir.join("file");
+    let sig = dir.join("file.sig");
+    let missing = dir.join("missing");
+    fs::write(&key, "not hex").unwrap();
+    fs::write(&public, "00").unwrap();
+    fs::write(&input, b"message").unwrap();
+    fs::write(&sig, [0u8; 63]).unwrap();
+
+    // Usage.
+    assert_eq!(cli().output().unwrap().status.code(), Some(2));
+    assert_eq!(
+        cli().arg("frobnicate").output().unwrap().status.code(),
+        Some(2)
+    );
+    assert_eq!(run("sign", &[("--key", &key)]), 2);
+    // Malformed keys and signatures, and missing files.
+    assert_eq!(
+        run(
+            "sign",
+            &[("--key", &key), ("--in", &input), ("--out", &sig)]
+        ),
+        2
+    );
+    assert_eq!(
+        run(
+            "verify",
+            &[("--pub", &public), ("--in", &input), ("--sig", &sig)]
+        ),
+        2
+    );
+    assert_eq!(
+        run(
+            "verify",
+            &[("--pub", &missing), ("--in", &input), ("--sig", &sig)]
+        ),
+        2
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,102 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// The primary documentation example of every public type. The doc comments
+// include the files of tests/snippets verbatim, and this file runs the
+// same files, so the two can't diverge.
+
+#[cfg(not(feature = "verify-only"))]
+mod keypair {
+    include!("snippets/keypair.rs");
+
+    #[test]
+    fn keypair_snippet() -> Result<(), Error> {
+        main()
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+mod secret_key {
+    include!("snippets/secret_key.rs");
+
+    #[test]
+    fn secret_key_snippet() -> Result<(), Error> {
+        main()
+    }
+}
+
+mod public_key {
+    include!("snippets/public_key.rs");
+
+    #[test]
+    fn public_key_snippet() -> Result<(), Error> {
+        main()
+    }
+}
+
+mod signature {
+    include!("snippets/signature.rs");
+
+    #[test]
+    fn signature_snippet() -> Result<(), Error> {
+        main()
+    }
+}
+
+mod error {
+    include!("snippets/error.rs");
+
+    #[test]
+    fn error_snippet() -> Result<(), Error> {
+        main()
+    }
+}
+
+mod precomputed_public_key {
+    include!("snippets/precomputed_public_key.rs");
+
+    #[test]
+    fn precomputed_public_key_snippet() -> Result<(), Error> {
+        main()
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+mod multipart_message {
+    include!("snippets/multipart_message.rs");
+
+    #[test]
+    fn multipart_message_snippet() -> Result<(), Error> {
+        main()
+    }
+}
+
+#[cfg(feature = "hazmat")]
+mod verification_witness {
+    include!("snippets/verification_witness.rs");
+
+    #[test]
+    fn verification_witness_snippet() -> Result<(), Error> {
+        main()
+    }
+}
+
+#[cfg(feature = "minisign")]
+mod minisign {
+    include!("snippets/minisign.rs");
+
+    #[test]
+    fn minisign_snippet() -> Result<(), Error> {
+        main()
+    }
+}
+
+#[cfg(feature = "signify")]
+mod signify {
+    include!("snippets/signify.rs");
+
+    #[test]
+    fn signify_snippet() -> Result<(), Error> {
+        main()
+    }
+}
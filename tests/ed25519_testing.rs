@@ -10,12 +10,15 @@ extern crate sha2;
 
 #[cfg(test)]
 mod test_vectors {
-    use ed25519_fun::{Keypair, PublicKey, SecretKey, Signature};
+    #[cfg(not(feature = "verify-only"))]
+    use ed25519_fun::{Error, Keypair, SecretKey};
+    use ed25519_fun::{PublicKey, Signature, VerificationOptions};
     use std::fs::File;
     use std::io::BufRead;
     use std::io::BufReader;
 
     //  sign.input test vectors: http://ed25519.cr.yp.to/python/sign.input
+    #[cfg(not(feature = "verify-only"))]
     #[test]
     pub fn ed25519_cr_yp_to_regression_test() {
         let file = File::open("./tests/sign.input");
@@ -42,9 +45,21 @@ mod test_vectors {
             let pk1 = PublicKey::from_bytes(&public_bytes[..32]).unwrap();
             let sign1 = Signature::from_bytes(&signature_bytes[..64]).unwrap();
 
-            let keypair = Keypair::generate_public_key(secret);
+            // The paths that check or derive the public key agree.
+            assert!(
+                secret.sign_detached(&message_bytes).as_bytes() == sign1.as_bytes(),
+                "Detached signatures do not match: {}",
+                lineno
+            );
+            assert!(
+                secret.sign(&pk1, &message_bytes).unwrap().as_bytes() == sign1.as_bytes(),
+                "Checked signatures do not match: {}",
+                lineno
+            );
 
-            let pk2 = keypair.public;
+            let keypair = Keypair::from_secret(secret);
+
+            let pk2 = keypair.public();
             let sign2 = keypair.sign(&message_bytes);
 
             assert!(
@@ -62,7 +77,99 @@ mod test_vectors {
                 "Verification failed: {}",
                 lineno
             );
+            assert!(
+                pk1.verify_strict(&message_bytes, &sign1).is_ok(),
+                "Strict verification failed: {}",
+                lineno
+            );
+        }
+    }
+
+    // Batches of sign.input messages signed by the key of their first
+    // line, against `sign` one message at a time and the corpus.
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    pub fn sign_batch_matches_sign() {
+        let file = File::open("./tests/sign.input").expect("Where are the test vectors? :(");
+        let vectors: Vec<(Keypair, Vec<u8>, Vec<u8>)> = BufReader::new(file)
+            .lines()
+            .map(|line| {
+                let l = line.unwrap();
+                let slices: Vec<&str> = l.split(":").collect();
+                let secret = SecretKey::from_bytes(&hex::decode(&slices[0]).unwrap()[..32]);
+                (
+                    Keypair::from_secret(secret.unwrap()),
+                    hex::decode(&slices[2]).unwrap(),
+                    hex::decode(&slices[3]).unwrap()[..64].to_vec(),
+                )
+            })
+            .collect();
+
+        for &size in [1, 2, 63, 64, 100].iter() {
+            for chunk in vectors.chunks(size).take(4) {
+                let keypair = &chunk[0].0;
+                let messages: Vec<&[u8]> = chunk.iter().map(|(_, m, _)| &m[..]).collect();
+                let signatures = keypair.sign_batch(&messages);
+                assert_eq!(signatures.len(), size);
+                assert_eq!(signatures[0].as_bytes()[..], chunk[0].2[..]);
+                for (message, signature) in messages.iter().zip(signatures.iter()) {
+                    assert_eq!(*signature, keypair.sign(message));
+                }
+            }
+        }
+        for (keypair, message, signature) in vectors.iter() {
+            assert_eq!(
+                keypair.sign_batch(&[message])[0].as_bytes()[..],
+                signature[..]
+            );
         }
+        assert!(vectors[0].0.sign_batch(&[]).is_empty());
+    }
+
+    // The first sign.input line, whose secret key field is the seed
+    // followed by the public key.
+    #[cfg(not(feature = "verify-only"))]
+    #[test]
+    pub fn corrupted_keypair_test() {
+        let file = File::open("./tests/sign.input").unwrap();
+        let line = BufReader::new(file).lines().next().unwrap().unwrap();
+        let slices: Vec<&str> = line.split(":").collect();
+        let keypair_bytes: Vec<u8> = hex::decode(&slices[0]).unwrap();
+
+        let keypair = Keypair::from_bytes(&keypair_bytes).unwrap();
+        assert!(keypair.to_bytes()[..] == keypair_bytes[..]);
+
+        // Any flipped bit of the public half is caught.
+        for bit in 8 * 32..8 * 64 {
+            let mut corrupted = keypair_bytes.clone();
+            corrupted[bit / 8] ^= 1 << (bit % 8);
+            assert_eq!(
+                Keypair::from_bytes(&corrupted).err(),
+                Some(Error::InvalidKeypair)
+            );
+            // The unchecked constructor takes it, but signs under the
+            // public key of the seed: not under the corrupted one.
+            let unchecked = Keypair::from_bytes_unchecked(&corrupted).unwrap();
+            assert!(unchecked.to_bytes()[..] == corrupted[..]);
+            let signature = unchecked.sign(b"corrupted");
+            assert!(unchecked.verify(b"corrupted", signature).is_err());
+            assert!(keypair.verify(b"corrupted", signature).is_ok());
+        }
+
+        // So is a flipped bit of the seed.
+        let mut corrupted = keypair_bytes.clone();
+        corrupted[0] ^= 1;
+        assert_eq!(
+            Keypair::from_bytes(&corrupted).err(),
+            Some(Error::InvalidKeypair)
+        );
+        assert!(matches!(
+            Keypair::from_bytes_unchecked(&keypair_bytes[..63]),
+            Err(Error::InvalidKeypairLength {
+                expected: 64,
+                got: 63
+            })
+        ));
     }
 
     // verify.input test vectors: Taming the many EdDSAs
@@ -99,4 +206,279 @@ mod test_vectors {
         }
         println!("{:?}", results);
     }
+
+    // (message, public key, signature) of verify.input.
+    fn taming_vectors() -> Vec<(Vec<u8>, PublicKey, Signature)> {
+        let file =
+            File::open("./tests/verify.input").expect("Where are the second test vectors? :(");
+        BufReader::new(file)
+            .lines()
+            .map(|line| {
+                let l = line.unwrap();
+                let slices: Vec<&str> = l.split(":").collect();
+                (
+                    hex::decode(&slices[0]).unwrap(),
+                    PublicKey::from_bytes(&hex::decode(&slices[1]).unwrap()).unwrap(),
+                    Signature::from_bytes(&hex::decode(&slices[2]).unwrap()).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    // The twelve cases of "Taming the many EdDSAs": small-order A or R
+    // (0 to 2), mixed-order A and R (3), equations only the cofactored
+    // check accepts (4, 5), s not reduced (6, 7), non-canonical R (8, 9)
+    // and non-canonical A (10, 11).
+    #[test]
+    pub fn eddsa_test_vectors_strict() {
+        use ed25519_fun::Error::*;
+
+        let vectors = taming_vectors();
+        let verify: Vec<_> = vectors
+            .iter()
+            .map(|(message, pk, sig)| pk.verify(message, sig))
+            .collect();
+        let strict: Vec<_> = vectors
+            .iter()
+            .map(|(message, pk, sig)| pk.verify_strict(message, sig))
+            .collect();
+        assert_eq!(
+            verify,
+            vec![
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Err(SignatureMismatch),
+                Err(SignatureMismatch),
+                Err(NonCanonicalScalar),
+                Err(NonCanonicalScalar),
+                Err(SignatureMismatch),
+                Err(SignatureMismatch),
+                Err(SignatureMismatch),
+                Ok(()),
+            ]
+        );
+        assert_eq!(
+            strict,
+            vec![
+                Err(SmallOrderPoint),
+                Err(SmallOrderPoint),
+                Err(SmallOrderPoint),
+                Ok(()),
+                Err(SignatureMismatch),
+                Err(SignatureMismatch),
+                Err(NonCanonicalScalar),
+                Err(NonCanonicalScalar),
+                Err(NonCanonicalPoint),
+                Err(NonCanonicalPoint),
+                Err(NonCanonicalPoint),
+                Err(NonCanonicalPoint),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn eddsa_test_vectors_cofactored() {
+        use ed25519_fun::Error::*;
+
+        let cofactored: Vec<_> = taming_vectors()
+            .iter()
+            .map(|(message, pk, sig)| pk.verify_cofactored(message, sig))
+            .collect();
+        assert_eq!(
+            cofactored,
+            vec![
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Err(NonCanonicalScalar),
+                Err(NonCanonicalScalar),
+                Err(NonCanonicalPoint),
+                Err(NonCanonicalPoint),
+                Err(NonCanonicalPoint),
+                Err(NonCanonicalPoint),
+            ]
+        );
+    }
+
+    // The verdicts of verify.input that `options` changes from those of
+    // `verify`, by index.
+    fn changed_verdicts(
+        options: VerificationOptions,
+    ) -> Vec<(usize, Result<(), ed25519_fun::Error>)> {
+        taming_vectors()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (message, pk, sig))| {
+                let verdict = pk.verify_with_options(message, sig, &options);
+                if verdict == pk.verify(message, sig) {
+                    None
+                } else {
+                    Some((i, verdict))
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    pub fn verification_options_each_flag() {
+        use ed25519_fun::Error::*;
+
+        let none = VerificationOptions::new();
+        assert_eq!(none, VerificationOptions::default());
+        assert_eq!(changed_verdicts(none), vec![]);
+        // 8 to 11 encode the point of order 2, whose x is 0, with the
+        // sign bit of x set: as R in 8 and 9, as A in 10 and 11.
+        assert_eq!(
+            changed_verdicts(none.reject_noncanonical_a(true)),
+            vec![(10, Err(NonCanonicalPoint)), (11, Err(NonCanonicalPoint))]
+        );
+        assert_eq!(
+            changed_verdicts(none.reject_noncanonical_r(true)),
+            vec![(8, Err(NonCanonicalPoint)), (9, Err(NonCanonicalPoint))]
+        );
+        // A has small order in 0, 1, 10 and 11, R in 0, 2, 8 and 9.
+        assert_eq!(
+            changed_verdicts(none.reject_small_order_a(true)),
+            vec![
+                (0, Err(SmallOrderPoint)),
+                (1, Err(SmallOrderPoint)),
+                (10, Err(SmallOrderPoint)),
+                (11, Err(SmallOrderPoint)),
+            ]
+        );
+        assert_eq!(
+            changed_verdicts(none.reject_small_order_r(true)),
+            vec![
+                (0, Err(SmallOrderPoint)),
+                (2, Err(SmallOrderPoint)),
+                (8, Err(SmallOrderPoint)),
+                (9, Err(SmallOrderPoint)),
+            ]
+        );
+        // 4 and 5 only hold up to a small-order component, and so do 9
+        // and 10 once their non-canonical point is decoded.
+        assert_eq!(
+            changed_verdicts(none.cofactored(true)),
+            vec![(4, Ok(())), (5, Ok(())), (9, Ok(())), (10, Ok(()))]
+        );
+    }
+
+    #[test]
+    pub fn verification_presets_match_methods() {
+        let presets = [
+            VerificationOptions::strict(),
+            VerificationOptions::rfc8032(),
+            VerificationOptions::zip215(),
+        ];
+        for (message, pk, sig) in taming_vectors().iter() {
+            let methods = [
+                pk.verify_strict(message, sig),
+                pk.verify_cofactored(message, sig),
+                pk.verify_zip215(message, sig),
+            ];
+            for (options, verdict) in presets.iter().zip(methods.iter()) {
+                assert_eq!(pk.verify_with_options(message, sig, options), *verdict);
+            }
+        }
+        assert_eq!(
+            VerificationOptions::strict(),
+            VerificationOptions::new()
+                .reject_noncanonical_a(true)
+                .reject_noncanonical_r(true)
+                .reject_small_order_a(true)
+                .reject_small_order_r(true)
+        );
+        assert_eq!(
+            VerificationOptions::zip215(),
+            VerificationOptions::new().cofactored(true)
+        );
+    }
+
+    // Only 8 fails: its k was computed over the reduced encoding of R,
+    // while ZIP-215 hashes the encoding as given.
+    #[test]
+    pub fn eddsa_test_vectors_zip215() {
+        use ed25519_fun::Error::*;
+
+        let zip215: Vec<_> = taming_vectors()
+            .iter()
+            .map(|(message, pk, sig)| pk.verify_zip215(message, sig))
+            .collect();
+        assert_eq!(
+            zip215,
+            vec![
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Err(NonCanonicalScalar),
+                Err(NonCanonicalScalar),
+                Err(SignatureMismatch),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+            ]
+        );
+    }
+
+    // The 196 cases of ZIP-215: every pair of the 14 encodings of
+    // small-order points (the 8 canonical ones and 6 that aren't) as A
+    // and R, with s = 0 and the message "Zcash". All of them are valid.
+    fn zip215_cases() -> Vec<(PublicKey, Signature)> {
+        let small_order = [
+            "0100000000000000000000000000000000000000000000000000000000000000",
+            "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a",
+            "0000000000000000000000000000000000000000000000000000000000000080",
+            "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc05",
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "26e8958fc2b227b045c3f489f2ef98f0d5dfac05d3c63339b13802886d53fc85",
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac03fa",
+            "0100000000000000000000000000000000000000000000000000000000000080",
+            "ecffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "edffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff7f",
+            "eeffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+        ];
+        let mut cases = Vec::new();
+        for a in small_order.iter() {
+            for r in small_order.iter() {
+                let mut signature = [0u8; 64];
+                signature[..32].copy_from_slice(&hex::decode(r).unwrap());
+                cases.push((
+                    PublicKey::from_bytes(&hex::decode(a).unwrap()).unwrap(),
+                    Signature::from_array(signature),
+                ));
+            }
+        }
+        cases
+    }
+
+    #[test]
+    pub fn zip215_small_order_vectors() {
+        let cases = zip215_cases();
+        assert_eq!(cases.len(), 196);
+        for (pk, sig) in cases.iter() {
+            assert_eq!(pk.verify_zip215(b"Zcash", sig), Ok(()));
+            assert!(pk.verify_strict(b"Zcash", sig).is_err());
+        }
+
+        // Batch verification follows the same rules.
+        let messages = vec![&b"Zcash"[..]; cases.len()];
+        let signatures: Vec<_> = cases.iter().map(|(_, sig)| *sig).collect();
+        let public_keys: Vec<_> = cases.iter().map(|(pk, _)| *pk).collect();
+        assert!(
+            ed25519_fun::verify_batch_deterministic(&messages, &signatures, &public_keys).is_ok()
+        );
+        #[cfg(any(feature = "rand", feature = "getrandom"))]
+        assert!(ed25519_fun::verify_batch(&messages, &signatures, &public_keys).is_ok());
+    }
 }
@@ -0,0 +1,99 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Adversarial length fields through the framing parsers.
+
+#![cfg(feature = "cose")]
+
+extern crate ed25519_fun;
+extern crate hex;
+
+use ed25519_fun::{Error, PublicKey, MAX_MESSAGE_LEN};
+
+// COSE_Key of the RFC 8032 test-1 key, up to the head of `x`.
+const COSE_KEY_PREFIX: &str = "a401010327200621";
+const X: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+
+/// Shortest CBOR byte string head declaring `len`.
+fn bytes_head(len: u64) -> Vec<u8> {
+    match len {
+        0..=23 => vec![0x40 | len as u8],
+        24..=0xff => vec![0x58, len as u8],
+        0x100..=0xffff => [&[0x59][..], &(len as u16).to_be_bytes()].concat(),
+        0x1_0000..=0xffff_ffff => [&[0x5a][..], &(len as u32).to_be_bytes()].concat(),
+        _ => [&[0x5b][..], &len.to_be_bytes()].concat(),
+    }
+}
+
+/// COSE_Key whose `x` byte string declares `len`.
+fn cose_key_declaring(len: u64) -> Vec<u8> {
+    let mut key = hex::decode(COSE_KEY_PREFIX).unwrap();
+    key.extend_from_slice(&bytes_head(len));
+    key.extend_from_slice(&hex::decode(X).unwrap());
+    key
+}
+
+#[test]
+fn cose_key_declared_lengths() {
+    for len in [u64::MAX, 1 << 32, (1 << 32) - 1, MAX_MESSAGE_LEN + 1].iter() {
+        assert_eq!(
+            PublicKey::from_cose_key(&cose_key_declaring(*len)).err(),
+            Some(Error::LengthOverflow),
+            "declared length {}",
+            len
+        );
+    }
+
+    // Within the limit but longer than the input.
+    assert_eq!(
+        PublicKey::from_cose_key(&cose_key_declaring(MAX_MESSAGE_LEN)).err(),
+        Some(Error::Truncated { offset: 13 })
+    );
+    assert_eq!(
+        PublicKey::from_cose_key(&cose_key_declaring(33)).err(),
+        Some(Error::Truncated { offset: 10 })
+    );
+
+    // Exactly the bytes left.
+    let key = PublicKey::from_cose_key(&cose_key_declaring(32)).unwrap();
+    assert_eq!(format!("{:x}", key), X);
+
+    // The same length with a longer head than needed is malformed.
+    let mut key = hex::decode(COSE_KEY_PREFIX).unwrap();
+    key.extend_from_slice(&[0x5b, 0, 0, 0, 0, 0, 0, 0, 0x20]);
+    key.extend_from_slice(&hex::decode(X).unwrap());
+    assert_eq!(
+        PublicKey::from_cose_key(&key).err(),
+        Some(Error::InvalidCoseKey)
+    );
+}
+
+#[test]
+fn cose_key_declared_counts() {
+    // Map head claiming u64::MAX and 2^32 entries.
+    for len in [u64::MAX, 1 << 32].iter() {
+        let mut key = vec![0xbb];
+        key.extend_from_slice(&len.to_be_bytes());
+        key.extend_from_slice(&hex::decode("010103272006215820").unwrap());
+        key.extend_from_slice(&hex::decode(X).unwrap());
+        assert_eq!(
+            PublicKey::from_cose_key(&key).err(),
+            Some(Error::LengthOverflow)
+        );
+    }
+}
+
+#[test]
+fn cose_sign1_declared_lengths() {
+    // COSE_Sign1 whose payload declares a huge length.
+    for len in [u64::MAX, 1 << 32, (1 << 32) - 1].iter() {
+        let mut sign1 = hex::decode("d28443a10127a0").unwrap();
+        sign1.extend_from_slice(&bytes_head(*len));
+        sign1.extend_from_slice(b"payload");
+        let public = PublicKey::from_bytes(&hex::decode(X).unwrap()).unwrap();
+        assert_eq!(
+            ed25519_fun::cose::verify_cose_sign1(&public, &sign1).err(),
+            Some(Error::LengthOverflow)
+        );
+    }
+}
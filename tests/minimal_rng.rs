@@ -0,0 +1,36 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Key generation from the crate's own CSPRNG. With
+// `--no-default-features --features getrandom`, `rand` isn't built and
+// the keys come from the OS generator through `getrandom`; with the
+// default features, from `thread_rng`.
+
+#![cfg(all(
+    not(feature = "verify-only"),
+    any(feature = "rand", feature = "getrandom")
+))]
+
+extern crate ed25519_fun;
+
+use ed25519_fun::{Keypair, SecretKey};
+
+#[test]
+fn generate_sign_verify() {
+    let keypair = Keypair::generate();
+    let signature = keypair.sign(b"minimal build");
+    assert!(keypair
+        .public()
+        .verify(b"minimal build", &signature)
+        .is_ok());
+    assert!(keypair
+        .public()
+        .verify(b"another message", &signature)
+        .is_err());
+
+    // Fresh keys every time.
+    assert!(Keypair::generate().public() != keypair.public());
+    assert!(SecretKey::generate_with_entropy(b"") != SecretKey::generate_with_entropy(b""));
+    let batch = Keypair::generate_batch(2);
+    assert!(batch[0].public() != batch[1].public());
+}
@@ -0,0 +1,78 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Multi-part signing must not allocate. Building without std is
+// checked once the crate supports no_std (synth-3587).
+
+#![cfg(not(feature = "verify-only"))]
+
+extern crate ed25519_fun;
+
+use ed25519_fun::{Keypair, MultiPartMessage};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+struct CountingAllocator;
+
+// Allocations are only counted on a thread that turned counting on, so
+// the test harness threads can't skew the result.
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = COUNTING.try_with(|counting| {
+            if counting.get() {
+                let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            }
+        });
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations made by `f` on this thread.
+fn allocations_in<F: FnOnce()>(f: F) -> usize {
+    ALLOCATIONS.with(|count| count.set(0));
+    COUNTING.with(|counting| counting.set(true));
+    f();
+    COUNTING.with(|counting| counting.set(false));
+    ALLOCATIONS.with(|count| count.get())
+}
+
+#[test]
+fn multipart_sign_and_verify_do_not_allocate() {
+    let keypair = Keypair::generate();
+    let counter = 7u64.to_le_bytes();
+
+    let mut verified = None;
+
+    let allocations = allocations_in(|| {
+        let mut message: MultiPartMessage<3> = MultiPartMessage::new();
+        message.push(b"device-42").unwrap();
+        message.push(&counter).unwrap();
+        message.push(b"payload").unwrap();
+        let signature = keypair.secret().sign_multipart(keypair.public(), &message);
+        verified = Some(keypair.public().verify_multipart(&message, &signature));
+    });
+
+    assert!(verified.unwrap().is_ok());
+    assert_eq!(allocations, 0);
+}
+
+#[test]
+fn counting_allocator_sees_allocations() {
+    let allocations = allocations_in(|| {
+        let _ = std::hint::black_box(vec![0u8; 16]);
+    });
+    assert!(allocations > 0);
+}
@@ -0,0 +1,335 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Key lifecycle events. The observer is process-global, so the tests
+// take turns through `LOCK`, and each recording observer only keeps the
+// events raised on the thread that installed it.
+
+#![cfg(feature = "observer")]
+
+extern crate ed25519_fun;
+extern crate hex;
+extern crate sha2;
+
+use ed25519_fun::observer::{clear_observer, set_observer, Fingerprint, KeyEventObserver, KeyKind};
+use ed25519_fun::{Error, PrecomputedPublicKey, PublicKey, Signature};
+#[cfg(not(feature = "verify-only"))]
+use ed25519_fun::{Keypair, MultiPartMessage, SecretKey};
+
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, ThreadId};
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+// RFC 8032, TEST 1.
+#[cfg(not(feature = "verify-only"))]
+const SECRET_KEY: &str = "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+const PUBLIC_KEY: &str = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+const SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+
+// Everything an observer can be given. None of these types can carry key
+// bytes or message contents.
+#[derive(Debug, PartialEq)]
+enum Event {
+    Sign {
+        fingerprint: Fingerprint,
+        message_len: usize,
+        message_digest: Fingerprint,
+    },
+    VerifyFailure {
+        fingerprint: Fingerprint,
+        error: Error,
+    },
+    Import(KeyKind),
+    Export(KeyKind),
+}
+
+struct Recorder {
+    thread: ThreadId,
+    events: Arc<Mutex<Vec<Event>>>,
+}
+
+impl Recorder {
+    fn record(&self, event: Event) {
+        if thread::current().id() == self.thread {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+}
+
+impl KeyEventObserver for Recorder {
+    fn on_sign(&self, fingerprint: &Fingerprint, message_len: usize, message_digest: &Fingerprint) {
+        self.record(Event::Sign {
+            fingerprint: *fingerprint,
+            message_len,
+            message_digest: *message_digest,
+        });
+    }
+
+    fn on_verify_failure(&self, fingerprint: &Fingerprint, error: Error) {
+        self.record(Event::VerifyFailure {
+            fingerprint: *fingerprint,
+            error,
+        });
+    }
+
+    fn on_import(&self, kind: KeyKind) {
+        self.record(Event::Import(kind));
+    }
+
+    fn on_export(&self, kind: KeyKind) {
+        self.record(Event::Export(kind));
+    }
+}
+
+// Installs a recording observer until the guard is dropped.
+fn record() -> (MutexGuard<'static, ()>, Arc<Mutex<Vec<Event>>>) {
+    let guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let events = Arc::new(Mutex::new(Vec::new()));
+    set_observer(Box::new(Recorder {
+        thread: thread::current().id(),
+        events: events.clone(),
+    }));
+    (guard, events)
+}
+
+fn sha256(bytes: &[u8]) -> Fingerprint {
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&Sha256::digest(bytes));
+    digest
+}
+
+fn take(events: &Arc<Mutex<Vec<Event>>>) -> Vec<Event> {
+    events.lock().unwrap().drain(..).collect()
+}
+
+#[test]
+fn verify_failures_are_observed() {
+    let (_guard, events) = record();
+    let public: PublicKey = PUBLIC_KEY.parse().unwrap();
+    let signature = Signature::from_hex(SIGNATURE).unwrap();
+    let fingerprint = sha256(&hex::decode(PUBLIC_KEY).unwrap());
+
+    public.verify(b"", &signature).unwrap();
+    assert!(take(&events).is_empty());
+
+    assert!(public.verify(b"tampered", &signature).is_err());
+    let precomputed = PrecomputedPublicKey::new(&public).unwrap();
+    precomputed.verify(b"", &signature).unwrap();
+    assert!(precomputed.verify(b"tampered", &signature).is_err());
+    assert_eq!(
+        take(&events),
+        [
+            Event::VerifyFailure {
+                fingerprint,
+                error: Error::SignatureMismatch
+            },
+            Event::VerifyFailure {
+                fingerprint,
+                error: Error::SignatureMismatch
+            },
+        ]
+    );
+    clear_observer();
+}
+
+#[cfg(not(feature = "verify-only"))]
+#[test]
+fn scripted_operations_raise_exactly_their_events() {
+    let (_guard, events) = record();
+    let fingerprint = sha256(&hex::decode(PUBLIC_KEY).unwrap());
+
+    let secret = SecretKey::from_hex(SECRET_KEY).unwrap();
+    let keypair = Keypair::from_secret(secret);
+    assert_eq!(take(&events), [Event::Import(KeyKind::SecretKey)]);
+
+    let signature = keypair.sign(b"abc");
+    keypair.public().verify(b"abc", &signature).unwrap();
+    assert!(keypair.verify(b"abd", signature).is_err());
+    assert_eq!(
+        take(&events),
+        [
+            Event::Sign {
+                fingerprint,
+                message_len: 3,
+                message_digest: sha256(b"abc"),
+            },
+            Event::VerifyFailure {
+                fingerprint,
+                error: Error::SignatureMismatch
+            },
+        ]
+    );
+
+    // The signed bytes of a multi-part message are its framing.
+    let mut message: MultiPartMessage<2> = MultiPartMessage::new();
+    message.push(b"ab").unwrap();
+    message.push(b"c").unwrap();
+    keypair.secret().sign_multipart(keypair.public(), &message);
+    let framed = hex::decode("0200000000000000020000000000000061620100000000000000").unwrap();
+    let framed = [&framed[..], b"c"].concat();
+    assert_eq!(
+        take(&events),
+        [Event::Sign {
+            fingerprint,
+            message_len: 27,
+            message_digest: sha256(&framed),
+        }]
+    );
+
+    let bytes = keypair.to_bytes();
+    Keypair::from_bytes(&bytes).unwrap();
+    let libsodium = keypair.to_libsodium_bytes();
+    Keypair::from_libsodium_bytes(&libsodium).unwrap();
+    SecretKey::from_libsodium_sk(&libsodium).unwrap();
+    keypair.secret().as_bytes();
+    keypair.secret().to_hex();
+    SecretKey::from_bytes(&bytes[..32]).unwrap();
+    assert_eq!(
+        take(&events),
+        [
+            Event::Export(KeyKind::Keypair),
+            Event::Import(KeyKind::Keypair),
+            Event::Export(KeyKind::Keypair),
+            Event::Import(KeyKind::Keypair),
+            Event::Import(KeyKind::SecretKey),
+            Event::Export(KeyKind::SecretKey),
+            Event::Export(KeyKind::SecretKey),
+            Event::Import(KeyKind::SecretKey),
+        ]
+    );
+
+    // Neither generation, failed imports nor public data raise events.
+    Keypair::generate();
+    assert!(SecretKey::from_bytes(&bytes).is_err());
+    assert!(Keypair::from_bytes(&bytes[..32]).is_err());
+    keypair.public().as_bytes();
+    assert!(take(&events).is_empty());
+    clear_observer();
+}
+
+#[cfg(not(feature = "verify-only"))]
+#[test]
+fn observer_never_sees_secret_bytes() {
+    let (_guard, events) = record();
+    let secret = hex::decode(SECRET_KEY).unwrap();
+    let message = [0x5au8; 64];
+
+    let keypair = Keypair::from_secret(SecretKey::from_bytes(&secret).unwrap());
+    let signature = keypair.sign(&message);
+    assert!(keypair.public().verify(&message[1..], &signature).is_err());
+    Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+
+    let recorded = take(&events);
+    assert_eq!(recorded.len(), 5);
+    for event in recorded {
+        let bytes: Vec<&[u8]> = match &event {
+            Event::Sign {
+                fingerprint,
+                message_digest,
+                ..
+            } => vec![fingerprint, message_digest],
+            Event::VerifyFailure { fingerprint, .. } => vec![fingerprint],
+            Event::Import(_) | Event::Export(_) => vec![],
+        };
+        for b in bytes {
+            assert!(b != &secret[..] && b != &message[..32] && b != &signature.as_bytes()[32..]);
+        }
+    }
+    clear_observer();
+}
+
+// Signs and fails a verification from inside `on_sign`.
+#[cfg(not(feature = "verify-only"))]
+struct Reentrant {
+    inner: Arc<Mutex<Option<Signature>>>,
+    events: Arc<Mutex<Vec<Event>>>,
+}
+
+#[cfg(not(feature = "verify-only"))]
+impl KeyEventObserver for Reentrant {
+    fn on_sign(&self, fingerprint: &Fingerprint, message_len: usize, message_digest: &Fingerprint) {
+        let keypair = Keypair::generate();
+        let signature = keypair.sign(b"from the observer");
+        assert!(keypair.public().verify(b"other", &signature).is_err());
+        *self.inner.lock().unwrap() = Some(signature);
+        self.events.lock().unwrap().push(Event::Sign {
+            fingerprint: *fingerprint,
+            message_len,
+            message_digest: *message_digest,
+        });
+    }
+
+    fn on_verify_failure(&self, fingerprint: &Fingerprint, error: Error) {
+        self.events.lock().unwrap().push(Event::VerifyFailure {
+            fingerprint: *fingerprint,
+            error,
+        });
+    }
+}
+
+#[cfg(not(feature = "verify-only"))]
+#[test]
+fn observer_may_sign_without_deadlock_or_recursion() {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let inner = Arc::new(Mutex::new(None));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    set_observer(Box::new(Reentrant {
+        inner: inner.clone(),
+        events: events.clone(),
+    }));
+
+    let keypair = Keypair::generate();
+    let signature = keypair.sign(b"outer");
+    assert!(keypair.public().verify(b"outer", &signature).is_ok());
+
+    // The observer ran and signed, and its own events weren't delivered.
+    assert!(inner.lock().unwrap().is_some());
+    let recorded = events.lock().unwrap();
+    assert_eq!(recorded.len(), 1);
+    assert!(matches!(recorded[0], Event::Sign { message_len: 5, .. }));
+    drop(recorded);
+    clear_observer();
+}
+
+struct Panicking {
+    events: Arc<Mutex<Vec<Event>>>,
+}
+
+impl KeyEventObserver for Panicking {
+    fn on_verify_failure(&self, fingerprint: &Fingerprint, error: Error) {
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events.push(Event::VerifyFailure {
+            fingerprint: *fingerprint,
+            error,
+        });
+        if events.len() == 1 {
+            drop(events);
+            panic!("observer failure");
+        }
+    }
+}
+
+#[test]
+fn observer_panics_are_contained() {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let events = Arc::new(Mutex::new(Vec::new()));
+    set_observer(Box::new(Panicking {
+        events: events.clone(),
+    }));
+
+    let public: PublicKey = PUBLIC_KEY.parse().unwrap();
+    let signature = Signature::from_hex(SIGNATURE).unwrap();
+    // The first event panics in the observer, the verdicts are unchanged
+    // and the next event is still delivered.
+    for _ in 0..2 {
+        assert_eq!(
+            public.verify(b"tampered", &signature),
+            Err(Error::SignatureMismatch)
+        );
+        assert!(public.verify(b"", &signature).is_ok());
+    }
+    assert_eq!(events.lock().unwrap().len(), 2);
+    clear_observer();
+}
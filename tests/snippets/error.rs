@@ -0,0 +1,32 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::{Error, PublicKey, Signature};
+
+fn verify(public_key: &str, message: &[u8], signature: &str) -> Result<(), Error> {
+    let public_key: PublicKey = public_key.parse()?;
+    let signature: Signature = signature.parse()?;
+    public_key.verify(message, &signature)
+}
+
+fn main() -> Result<(), Error> {
+    // RFC 8032, TEST 1.
+    let public_key = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    let signature = "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw";
+    verify(public_key, b"", signature)?;
+
+    // Each failure has its own variant, and a message through Display.
+    let tampered = verify(public_key, b"tampered", signature);
+    assert_eq!(tampered, Err(Error::SignatureMismatch));
+    if let Err(error) = tampered {
+        assert_eq!(error.to_string(), "Signature doesn't verify");
+    }
+    assert_eq!(
+        verify(public_key, b"", &signature[2..]),
+        Err(Error::InvalidSignatureEncoding)
+    );
+    assert_eq!(
+        verify(&public_key[2..], b"", signature),
+        Err(Error::InvalidPublicKey)
+    );
+    Ok(())
+}
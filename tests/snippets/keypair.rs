@@ -0,0 +1,21 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::{Error, Keypair, Signature};
+
+fn main() -> Result<(), Error> {
+    let keypair = Keypair::generate();
+    let message: &[u8] = b"ed25519-fun";
+    let signature: Signature = keypair.sign(message);
+
+    keypair.verify(message, signature)?;
+    assert_eq!(
+        keypair.public().verify(b"another message", &signature),
+        Err(Error::SignatureMismatch)
+    );
+
+    // The secret key followed by the public key.
+    let bytes: [u8; 64] = keypair.to_bytes();
+    let restored = Keypair::from_bytes(&bytes)?;
+    assert_eq!(restored.sign(message), signature);
+    Ok(())
+}
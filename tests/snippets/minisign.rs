@@ -0,0 +1,20 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::minisign::{MinisignPublicKey, MinisignSignature};
+use ed25519_fun::Error;
+
+fn main() -> Result<(), Error> {
+    // A signature of the file "test" made by the minisign CLI.
+    let public_key =
+        MinisignPublicKey::from_base64("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3")?;
+    let signature = MinisignSignature::decode(
+        "untrusted comment: signature from minisign secret key\n\
+         RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=\n\
+         trusted comment: timestamp:1556193335\tfile:test\n\
+         y/rUw2y8/hOUYjZU71eHp/Wo1KZ40fGy2VJEDl34XMJM+TX48Ss/17u3IvIfbVR1FkZZSNCisQbuQY+bHwhEBg==\n",
+    )?;
+
+    public_key.verify(b"test", &signature)?;
+    assert_eq!(signature.trusted_comment, "timestamp:1556193335\tfile:test");
+    Ok(())
+}
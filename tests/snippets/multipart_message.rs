@@ -0,0 +1,23 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::{Error, Keypair, MultiPartMessage, Signature};
+
+fn main() -> Result<(), Error> {
+    let keypair = Keypair::generate();
+    let counter = 7u32.to_le_bytes();
+    let mut message: MultiPartMessage<3> = MultiPartMessage::new();
+    message.push(b"device-42")?;
+    message.push(&counter)?;
+    message.push(b"payload")?;
+
+    let signature: Signature = keypair.secret().sign_multipart(keypair.public(), &message);
+    keypair.public().verify_multipart(&message, &signature)?;
+
+    // The same bytes split differently are another message.
+    let mut moved: MultiPartMessage<3> = MultiPartMessage::new();
+    moved.push(b"device-4")?;
+    moved.push(b"2\x07\x00\x00\x00")?;
+    moved.push(b"payload")?;
+    assert!(keypair.public().verify_multipart(&moved, &signature).is_err());
+    Ok(())
+}
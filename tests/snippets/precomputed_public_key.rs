@@ -0,0 +1,22 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::{Error, PrecomputedPublicKey, PublicKey, Signature};
+
+fn main() -> Result<(), Error> {
+    // RFC 8032, TEST 1.
+    let public_key: PublicKey =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a".parse()?;
+    let signature: Signature = "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw"
+        .parse()?;
+
+    // The point is decoded once, here, for all the verifications below.
+    let precomputed = PrecomputedPublicKey::new(&public_key)?;
+    for _ in 0..3 {
+        precomputed.verify(b"", &signature)?;
+    }
+    assert_eq!(
+        precomputed.verify(b"tampered", &signature),
+        Err(Error::SignatureMismatch)
+    );
+    Ok(())
+}
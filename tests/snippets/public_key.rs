@@ -0,0 +1,20 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::{Error, PublicKey, Signature};
+
+fn main() -> Result<(), Error> {
+    // RFC 8032, TEST 1.
+    let public_key: PublicKey =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a".parse()?;
+    let signature: Signature = "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw"
+        .parse()?;
+    public_key.verify(b"", &signature)?;
+
+    let bytes: &[u8; 32] = public_key.as_bytes();
+    assert_eq!(PublicKey::from_bytes(bytes)?.as_bytes(), bytes);
+    assert_eq!(
+        public_key.to_string(),
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+    );
+    Ok(())
+}
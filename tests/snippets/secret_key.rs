@@ -0,0 +1,21 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::{Error, Keypair, SecretKey};
+
+fn main() -> Result<(), Error> {
+    // RFC 8032, TEST 1. Secret keys have no Display: hex is explicit.
+    let secret_key =
+        SecretKey::from_hex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")?;
+    assert_eq!(
+        SecretKey::from_bytes(secret_key.as_bytes())?.to_hex(),
+        secret_key.to_hex()
+    );
+
+    let keypair = Keypair::from_secret(secret_key);
+    assert_eq!(
+        keypair.public().to_string(),
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+    );
+    assert_eq!(keypair.sign(b"").to_string(), "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw");
+    Ok(())
+}
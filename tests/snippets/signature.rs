@@ -0,0 +1,30 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::{Error, Signature};
+
+fn main() -> Result<(), Error> {
+    // RFC 8032, TEST 1: R followed by s. Strings are unpadded base64url;
+    // padded standard base64 is accepted too, and hex is explicit.
+    let base64 = "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw";
+    let signature: Signature = base64.parse()?;
+    assert_eq!(signature.to_string(), base64);
+    assert_eq!(
+        Signature::from_base64("5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc+bRr0lv18FlbviRlUUFDjnoQCw==")?,
+        signature
+    );
+    assert_eq!(
+        Signature::from_hex("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b")?,
+        signature
+    );
+
+    let bytes: &[u8; 64] = signature.as_bytes();
+    assert_eq!(Signature::from_bytes(bytes)?, signature);
+    assert_eq!(
+        Signature::from_bytes(&bytes[..63]),
+        Err(Error::InvalidSignatureLength {
+            expected: 64,
+            got: 63
+        })
+    );
+    Ok(())
+}
@@ -0,0 +1,19 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::signify::{SignifyPublicKey, SignifySignature};
+use ed25519_fun::Error;
+
+fn main() -> Result<(), Error> {
+    let public_key = SignifyPublicKey::decode(
+        "untrusted comment: signify public key\n\
+         RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3\n",
+    )?;
+    let signature = SignifySignature::decode(
+        "untrusted comment: verify with key.pub\n\
+         RWQf6LRCGA9i59SLOFxz6NxvASXDJeRtuZykwQepbDEGt87ig1BNpWaVWuNrm73YiIiJbq71Wi+dP9eKL8OC351vwIasSSbXxwA=\n",
+    )?;
+
+    assert_eq!(signature.key_num, public_key.key_num);
+    public_key.public.verify(b"test", &signature.signature)?;
+    Ok(())
+}
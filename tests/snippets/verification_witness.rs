@@ -0,0 +1,21 @@
+extern crate ed25519_fun;
+
+use ed25519_fun::{Error, PublicKey, Signature};
+
+fn main() -> Result<(), Error> {
+    // RFC 8032, TEST 1.
+    let public_key: PublicKey =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a".parse()?;
+    let signature: Signature = "5VZDAMNgrHKQhuLMgG6CioSHfx645dl02HPgZSJJAVVfuIIVkKM7rMYeOXAc-bRr0lv18FlbviRlUUFDjnoQCw"
+        .parse()?;
+
+    let witness = public_key.verify_and_export_witness(b"", &signature)?;
+    assert!(witness.check());
+
+    // Nothing is exported for a signature that doesn't verify.
+    assert_eq!(
+        public_key.verify_and_export_witness(b"tampered", &signature),
+        Err(Error::SignatureMismatch)
+    );
+    Ok(())
+}
@@ -0,0 +1,37 @@
+// Author:
+// - Yuzo <yuzonakai@gmail.com>
+
+// Verification with public data only. This test also runs under the
+// `verify-only` feature, where the secret key types don't exist.
+
+extern crate ed25519_fun;
+extern crate hex;
+
+use ed25519_fun::{PublicKey, Signature};
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+
+#[test]
+fn verify_sign_input_corpus_with_public_data_only() {
+    let file = File::open("./tests/sign.input").expect("Where are the test vectors? :(");
+    let buffer = BufReader::new(file);
+
+    for (lineno, line) in buffer.lines().enumerate() {
+        let l = line.unwrap();
+        let slices: Vec<&str> = l.split(':').collect();
+
+        let public_bytes: Vec<u8> = hex::decode(slices[1]).unwrap();
+        let message_bytes: Vec<u8> = hex::decode(slices[2]).unwrap();
+        let signature_bytes: Vec<u8> = hex::decode(slices[3]).unwrap();
+
+        let public = PublicKey::from_bytes(&public_bytes).unwrap();
+        let signature = Signature::from_bytes(&signature_bytes[..64]).unwrap();
+
+        assert!(
+            public.verify(&message_bytes, &signature).is_ok(),
+            "Verification failed: {}",
+            lineno
+        );
+    }
+}